@@ -0,0 +1,216 @@
+//! One-shot answers from the command line
+//!
+//! `opensnitch-tui answer --pid 1234 --action deny --duration 1h` sends a
+//! request over the control socket, so a launcher such as rofi or dmenu can
+//! answer without switching to the TUI. The running instance answers the
+//! pending prompt of that process, or the one on screen. Without a prompt
+//! to answer it adds a rule for the process to the active node instead.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::unix::{OwnedReadHalf, OwnedWriteHalf};
+use tokio::net::UnixStream;
+use tokio::sync::{mpsc, oneshot, Mutex};
+
+use super::state::{AppMessage, AppState, UiUpdateSignal};
+use crate::grpc::notifications::NotificationAction;
+use crate::models::{Connection, Operator, PromptOutcome, Rule, RuleAction, RuleDuration};
+use crate::utils::host::is_local_node;
+
+/// How long the TUI gets to answer the prompt it shows
+const UI_ANSWER_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// How long the command line waits for the running instance
+const REPLY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Which process to answer for and how
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AnswerRequest {
+    pub pid: Option<u32>,
+    /// Executable path, matched when no pid is given
+    pub process: Option<String>,
+    pub action: RuleAction,
+    pub duration: RuleDuration,
+}
+
+impl AnswerRequest {
+    pub fn matches(&self, connection: &Connection) -> bool {
+        match (self.pid, &self.process) {
+            (Some(pid), _) => connection.process_id == pid,
+            (None, Some(path)) => &connection.process_path == path,
+            (None, None) => false,
+        }
+    }
+}
+
+/// A line a client sends on the control socket. Clients that send nothing
+/// are mirrors.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ControlRequest {
+    Answer(AnswerRequest),
+}
+
+/// Reply to a request, sent between the mirror snapshots
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ControlReply {
+    pub ok: bool,
+    pub message: String,
+}
+
+impl ControlReply {
+    fn from_result(result: Result<String, String>) -> Self {
+        match result {
+            Ok(message) => Self { ok: true, message },
+            Err(message) => Self { ok: false, message },
+        }
+    }
+}
+
+/// The prompt on screen, handed to the TUI which owns it
+#[derive(Debug)]
+pub struct RemoteAnswer {
+    pub request: AnswerRequest,
+    /// Whether the prompt on screen was the one asked for
+    pub done: oneshot::Sender<bool>,
+}
+
+/// Strict parsing for the command line, a typo must not allow a connection
+pub fn parse_action(value: &str) -> Result<RuleAction, String> {
+    match value.to_lowercase().as_str() {
+        "allow" => Ok(RuleAction::Allow),
+        "deny" => Ok(RuleAction::Deny),
+        "reject" => Ok(RuleAction::Reject),
+        _ => Err(format!("unknown action {:?}, expected allow, deny or reject", value)),
+    }
+}
+
+pub fn parse_duration(value: &str) -> Result<RuleDuration, String> {
+    let duration = RuleDuration::from(value);
+    if duration.to_string() != value.to_lowercase() {
+        return Err(format!(
+            "unknown duration {:?}, expected once, until restart, always, 5m, 15m, 30m, 1h, 12h or 24h",
+            value
+        ));
+    }
+    Ok(duration)
+}
+
+/// Handle a request from the control socket
+pub async fn handle(state: &AppState, state_tx: &mpsc::Sender<AppMessage>, request: ControlRequest) -> ControlReply {
+    let ControlRequest::Answer(request) = request;
+    ControlReply::from_result(answer(state, state_tx, request).await)
+}
+
+async fn answer(state: &AppState, state_tx: &mpsc::Sender<AppMessage>, request: AnswerRequest) -> Result<String, String> {
+    if request.pid.is_none() && request.process.is_none() {
+        return Err("no pid or process to answer for".to_string());
+    }
+
+    // Queued behind the prompt on screen
+    let mut prompts = state.pending_prompts.write().await;
+    let queued = prompts.iter().position(|pending| request.matches(&pending.connection));
+    if let Some(pending) = queued.and_then(|index| prompts.remove(index)) {
+        drop(prompts);
+        let name = pending.connection.process_name().to_string();
+        let rule = Rule::for_connection(&pending.connection, request.action, request.duration.clone());
+        let _ = pending.response_tx.send(rule);
+        state.set_prompt_outcome(pending.id, PromptOutcome::Answered).await;
+        return Ok(format!("{} {} {}", request.action, name, request.duration));
+    }
+    drop(prompts);
+
+    // On screen, only the TUI can answer it
+    let (done, answered) = oneshot::channel();
+    state.remote_answers.write().await.push_back(RemoteAnswer { request: request.clone(), done });
+    state.notify_ui(UiUpdateSignal::RemoteAnswer);
+    if let Ok(Ok(true)) = tokio::time::timeout(UI_ANSWER_TIMEOUT, answered).await {
+        return Ok(format!("answered the prompt on screen: {} {}", request.action, request.duration));
+    }
+
+    add_rule(state, state_tx, &request).await
+}
+
+/// No prompt to answer, a rule for the process takes its place
+async fn add_rule(state: &AppState, state_tx: &mpsc::Sender<AppMessage>, request: &AnswerRequest) -> Result<String, String> {
+    if request.duration == RuleDuration::Once {
+        return Err("no pending prompt for this process, and a rule can't last once".to_string());
+    }
+    let Some(node_addr) = state.nodes.read().await.active_addr().map(str::to_string) else {
+        return Err("no node to add the rule to".to_string());
+    };
+    let path = match (&request.process, request.pid) {
+        (Some(path), _) => path.clone(),
+        // Only this host's processes can be looked up
+        (None, Some(pid)) if is_local_node(&node_addr) => std::fs::read_link(format!("/proc/{}/exe", pid))
+            .map(|exe| exe.to_string_lossy().to_string())
+            .map_err(|e| format!("no pending prompt for pid {}, and its executable is unknown: {}", pid, e))?,
+        (None, Some(pid)) => {
+            return Err(format!("no pending prompt for pid {}, and {} runs on another host", pid, node_addr))
+        }
+        (None, None) => unreachable!("checked by answer"),
+    };
+
+    let process_name = path.rsplit('/').next().unwrap_or(&path);
+    let name = format!("{}-{}", request.action, process_name);
+    let rule = Rule::new(&name, request.action, request.duration.clone(), Operator::simple("process.path", &path))
+        .with_precedence(request.action == RuleAction::Allow);
+    let _ = state_tx
+        .send(AppMessage::RuleAdded { node_addr: node_addr.clone(), rule: rule.clone() })
+        .await;
+    let _ = state_tx
+        .send(AppMessage::SendNotification { node_addr: node_addr.clone(), action: NotificationAction::ChangeRule(rule) })
+        .await;
+    Ok(format!("no pending prompt, added rule {} on {}", name, node_addr))
+}
+
+/// Send a request to the running instance and wait for its reply
+pub async fn send(path: &str, request: ControlRequest) -> Result<ControlReply> {
+    let stream = UnixStream::connect(path).await?;
+    let (reader, mut writer) = stream.into_split();
+    let mut line = serde_json::to_vec(&request)?;
+    line.push(b'\n');
+    writer.write_all(&line).await?;
+
+    // Mirror snapshots keep coming, the reply is the line that parses as one
+    let mut lines = BufReader::new(reader).lines();
+    let reply = tokio::time::timeout(REPLY_TIMEOUT, async {
+        while let Some(line) = lines.next_line().await? {
+            if let Ok(reply) = serde_json::from_str::<ControlReply>(&line) {
+                return Ok(Some(reply));
+            }
+        }
+        Ok::<_, std::io::Error>(None)
+    })
+    .await;
+    match reply {
+        Ok(Ok(Some(reply))) => Ok(reply),
+        Ok(Ok(None)) => bail!("The running instance closed the connection without replying"),
+        Ok(Err(e)) => Err(e.into()),
+        Err(_) => bail!("No reply after {}s", REPLY_TIMEOUT.as_secs()),
+    }
+}
+
+/// Serve requests from one client, replying through the shared writer
+pub(crate) async fn serve_requests(
+    reader: OwnedReadHalf,
+    writer: Arc<Mutex<OwnedWriteHalf>>,
+    state: Arc<AppState>,
+    state_tx: mpsc::Sender<AppMessage>,
+) -> Result<()> {
+    let mut lines = BufReader::new(reader).lines();
+    while let Some(line) = lines.next_line().await? {
+        let reply = match serde_json::from_str::<ControlRequest>(&line) {
+            Ok(request) => handle(&state, &state_tx, request).await,
+            Err(e) => ControlReply { ok: false, message: format!("invalid request: {}", e) },
+        };
+        let mut line = serde_json::to_vec(&reply)?;
+        line.push(b'\n');
+        writer.lock().await.write_all(&line).await?;
+    }
+    Ok(())
+}
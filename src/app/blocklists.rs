@@ -0,0 +1,168 @@
+//! Well-known blocklists for `lists.domains` rules
+//!
+//! A lists rule names a directory, the daemon loads every file in it and
+//! denies the domains listed. The lists here come in hosts format
+//! (`0.0.0.0 ads.example`), which the daemon reads as is. Each one is
+//! downloaded to a file of its own in the directory, and a single deny rule
+//! reads them all.
+
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+
+use super::scheduler::refresh_blocklist;
+use crate::config::{ScheduledTask, TaskKind};
+use crate::models::{Operator, OperatorType, Rule, RuleAction, RuleDuration};
+
+/// Where the official GUI suggests keeping domain lists
+pub const DEFAULT_LISTS_DIR: &str = "/etc/opensnitchd/blocklists/domains";
+
+/// Name of the deny rule reading the lists
+pub const RULE_NAME: &str = "deny-blocklists";
+
+/// A blocklist that can be downloaded
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KnownList {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub url: &'static str,
+    /// File the list is kept in, within the lists directory
+    pub file: &'static str,
+}
+
+pub const KNOWN_LISTS: [KnownList; 4] = [
+    KnownList {
+        name: "StevenBlack",
+        description: "Ads and malware, unified hosts",
+        url: "https://raw.githubusercontent.com/StevenBlack/hosts/master/hosts",
+        file: "stevenblack-hosts.txt",
+    },
+    KnownList {
+        name: "AdAway",
+        description: "Mobile ads and trackers",
+        url: "https://adaway.org/hosts.txt",
+        file: "adaway-hosts.txt",
+    },
+    KnownList {
+        name: "Peter Lowe",
+        description: "Ad and tracking servers",
+        url: "https://pgl.yoyo.org/adservers/serverlist.php?hostformat=hosts&showintro=0&mimetype=plaintext",
+        file: "yoyo-hosts.txt",
+    },
+    KnownList {
+        name: "URLhaus",
+        description: "Malware distribution sites",
+        url: "https://urlhaus.abuse.ch/downloads/hostfile/",
+        file: "urlhaus-hosts.txt",
+    },
+];
+
+impl KnownList {
+    pub fn path(&self, dir: &str) -> PathBuf {
+        Path::new(dir).join(self.file)
+    }
+
+    /// The download as a blocklist task, fetched with curl like the update check
+    pub fn task(&self, dir: &str) -> ScheduledTask {
+        ScheduledTask {
+            name: self.name.to_string(),
+            task: TaskKind::Blocklist,
+            command: format!("curl -fsSL '{}'", self.url),
+            path: self.path(dir).to_string_lossy().into_owned(),
+            ..Default::default()
+        }
+    }
+}
+
+/// A list downloaded before
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ListFile {
+    pub entries: usize,
+    pub modified: Option<DateTime<Utc>>,
+}
+
+impl ListFile {
+    /// Entries of the list at `path`, None when it isn't there
+    pub fn read(path: &Path) -> Option<Self> {
+        let content = std::fs::read(path).ok()?;
+        let entries = String::from_utf8_lossy(&content)
+            .lines()
+            .filter(|line| !line.trim().is_empty() && !line.starts_with('#'))
+            .count();
+        let modified = std::fs::metadata(path).and_then(|m| m.modified()).ok().map(DateTime::<Utc>::from);
+        Some(Self { entries, modified })
+    }
+}
+
+/// Directories read by the `lists.domains` rules, in rule order
+pub fn list_dirs(rules: &[Rule]) -> Vec<String> {
+    fn collect(operator: &Operator, dirs: &mut Vec<String>) {
+        if operator.op_type == OperatorType::Lists && operator.operand == "lists.domains" && !dirs.contains(&operator.data) {
+            dirs.push(operator.data.clone());
+        }
+        for entry in &operator.list {
+            collect(entry, dirs);
+        }
+    }
+    let mut dirs = Vec::new();
+    for rule in rules {
+        collect(&rule.operator, &mut dirs);
+    }
+    dirs
+}
+
+/// Deny rule reading the lists in `dir`; a rule of the same name keeps its
+/// other settings, None when it already reads the directory
+pub fn blocklist_rule(dir: &str, existing: Option<&Rule>) -> Option<Rule> {
+    let operator = Operator::new(OperatorType::Lists, "lists.domains", dir);
+    match existing {
+        Some(rule)
+            if rule.enabled
+                && rule.operator.op_type == operator.op_type
+                && rule.operator.operand == operator.operand
+                && rule.operator.data == operator.data =>
+        {
+            None
+        }
+        Some(rule) => {
+            let mut rule = rule.clone();
+            rule.operator = operator;
+            rule.enabled = true;
+            rule.updated = Some(Utc::now());
+            Some(rule)
+        }
+        None => Some(
+            Rule::new(RULE_NAME, RuleAction::Deny, RuleDuration::Always, operator)
+                .with_description("Domains of the downloaded blocklists"),
+        ),
+    }
+}
+
+/// Outcome of downloading lists
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BlocklistReport {
+    /// List name and what was written, or why it failed
+    pub lists: Vec<(String, Result<String, String>)>,
+    /// The deny rule sent, None when it was already in place or nothing was written
+    pub rule: Option<String>,
+}
+
+impl BlocklistReport {
+    /// Whether any list made it to disk
+    pub fn written(&self) -> bool {
+        self.lists.iter().any(|(_, result)| result.is_ok())
+    }
+}
+
+/// Download the lists one after the other, a failed one keeps its old file
+pub async fn download(tasks: Vec<ScheduledTask>) -> BlocklistReport {
+    let mut report = BlocklistReport::default();
+    for task in tasks {
+        let result = refresh_blocklist(&task).await;
+        if let Err(e) = &result {
+            tracing::warn!("Blocklist {} not updated: {}", task.name, e);
+        }
+        report.lists.push((task.name, result));
+    }
+    report
+}
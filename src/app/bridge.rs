@@ -0,0 +1,343 @@
+//! Read-only event stream for web dashboards
+//!
+//! Connections and alerts are pushed as JSON text messages to WebSocket
+//! clients on a loopback address, for small web pages or home automation
+//! tools to show what the firewall does. Events are sanitized first: no
+//! arguments, environment, working directory or checksums of processes
+//! leave the TUI. Nothing sent by clients is acted upon.
+//!
+//! Any page a browser opens may connect to a local port, so requests from
+//! a browser are refused unless the page is itself local or its origin is
+//! configured. Any local user may connect too, so clients must also send
+//! the configured token, as a `token` query parameter since browsers can't
+//! add headers to WebSocket requests, or as a bearer token.
+
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{bail, Context, Result};
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use sha1::{Digest, Sha1};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::tcp::OwnedReadHalf;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::mpsc;
+
+use crate::app::state::AppState;
+use crate::models::{Alert, AlertPriority, AlertType, AlertWhat, Event};
+
+/// Events waiting for slow clients, older ones are skipped beyond
+pub const STREAM_CAPACITY: usize = 1024;
+
+/// How long a client may take to send its handshake
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Largest handshake request and client message accepted
+const MAX_REQUEST: usize = 8192;
+
+/// GUID the accept key of RFC 6455 is derived with
+const WEBSOCKET_GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+
+/// A message sent to the clients, tagged with its `type`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum StreamEvent {
+    Connection(ConnectionEvent),
+    Alert(AlertEvent),
+    /// Events the client was too slow for
+    Lagged { skipped: u64 },
+}
+
+/// What a dashboard gets to know about a connection
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConnectionEvent {
+    pub time: String,
+    /// allow, deny or reject, None before a rule matched
+    pub action: Option<String>,
+    pub rule: Option<String>,
+    pub protocol: String,
+    pub dst_host: String,
+    pub dst_ip: String,
+    pub dst_port: u32,
+    pub process_path: String,
+    pub user_id: u32,
+    /// Identical events coalesced into this one
+    pub count: u64,
+}
+
+/// What a dashboard gets to know about an alert
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AlertEvent {
+    pub id: u64,
+    pub time: String,
+    pub node: String,
+    pub alert_type: AlertType,
+    pub priority: AlertPriority,
+    pub what: AlertWhat,
+    pub text: String,
+}
+
+impl StreamEvent {
+    pub fn connection(event: &Event) -> Self {
+        let connection = &event.connection;
+        Self::Connection(ConnectionEvent {
+            time: event.time.clone(),
+            action: event
+                .rule
+                .as_ref()
+                .map(|rule| rule.action.to_string())
+                .or_else(|| connection.action.clone()),
+            rule: event.rule.as_ref().map(|rule| rule.name.clone()).or_else(|| connection.rule_name.clone()),
+            protocol: connection.protocol.clone(),
+            dst_host: connection.dst_host.clone(),
+            dst_ip: connection.dst_ip.clone(),
+            dst_port: connection.dst_port,
+            process_path: connection.process_path.clone(),
+            user_id: connection.user_id,
+            count: event.count,
+        })
+    }
+
+    pub fn alert(alert: &Alert) -> Self {
+        Self::Alert(AlertEvent {
+            id: alert.id,
+            time: alert.timestamp.to_rfc3339(),
+            node: alert.node.clone(),
+            alert_type: alert.alert_type,
+            priority: alert.priority,
+            what: alert.what,
+            text: alert.text(),
+        })
+    }
+}
+
+/// Listen on a loopback address, others are refused
+pub async fn bind(address: &str) -> Result<TcpListener> {
+    let addr: SocketAddr = address.parse().with_context(|| format!("invalid event stream address: {}", address))?;
+    if !addr.ip().is_loopback() {
+        bail!("event stream address must be a loopback address: {}", address);
+    }
+    Ok(TcpListener::bind(addr).await?)
+}
+
+/// Accept dashboards until the task is aborted
+pub async fn serve(listener: TcpListener, state: Arc<AppState>, origins: Vec<String>, token: String) -> Result<()> {
+    if token.is_empty() {
+        bail!("event_stream_token is empty");
+    }
+    let access = Arc::new((origins, token));
+    loop {
+        let (stream, peer) = listener.accept().await?;
+        let state = state.clone();
+        let access = access.clone();
+        tokio::spawn(async move {
+            let (origins, token) = &*access;
+            if let Err(e) = serve_client(stream, &state, origins, token).await {
+                tracing::debug!("Event stream client {} left: {}", peer, e);
+            }
+        });
+    }
+}
+
+async fn serve_client(mut stream: TcpStream, state: &AppState, origins: &[String], token: &str) -> Result<()> {
+    let request = tokio::time::timeout(HANDSHAKE_TIMEOUT, read_request(&mut stream))
+        .await
+        .context("handshake timed out")??;
+    let key = match handshake_key(&request, origins, token) {
+        Ok(key) => key,
+        Err((status, reason)) => {
+            let response = format!("HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}", status, reason.len(), reason);
+            stream.write_all(response.as_bytes()).await?;
+            bail!("{}", reason);
+        }
+    };
+    // Subscribe before answering, so nothing is missed in between
+    let mut events = state.stream_tx.subscribe();
+    let response = format!(
+        "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Accept: {}\r\n\r\n",
+        accept_key(&key)
+    );
+    stream.write_all(response.as_bytes()).await?;
+
+    let (reader, mut writer) = stream.into_split();
+    let (control_tx, mut control_rx) = mpsc::channel(8);
+    let reader = tokio::spawn(read_frames(reader, control_tx));
+    let result = async {
+        loop {
+            let message = tokio::select! {
+                event = events.recv() => match event {
+                    Ok(event) => event,
+                    Err(RecvError::Lagged(skipped)) => StreamEvent::Lagged { skipped },
+                    Err(RecvError::Closed) => return Ok(()),
+                },
+                frame = control_rx.recv() => match frame {
+                    Some(Frame::Ping(payload)) => {
+                        writer.write_all(&frame_bytes(OPCODE_PONG, &payload)).await?;
+                        continue;
+                    }
+                    // Echo the close, then hang up
+                    Some(Frame::Close) => {
+                        writer.write_all(&frame_bytes(OPCODE_CLOSE, &[])).await?;
+                        return Ok(());
+                    }
+                    None => return Ok(()),
+                },
+            };
+            writer.write_all(&frame_bytes(OPCODE_TEXT, serde_json::to_string(&message)?.as_bytes())).await?;
+        }
+    }
+    .await;
+    reader.abort();
+    result
+}
+
+/// The handshake request, up to the empty line
+async fn read_request(stream: &mut TcpStream) -> Result<String> {
+    let mut request = Vec::new();
+    let mut buf = [0u8; 1024];
+    while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+        let n = stream.read(&mut buf).await?;
+        if n == 0 {
+            bail!("closed during the handshake");
+        }
+        request.extend_from_slice(&buf[..n]);
+        if request.len() > MAX_REQUEST {
+            bail!("handshake request too large");
+        }
+    }
+    Ok(String::from_utf8_lossy(&request).into_owned())
+}
+
+/// The client's key when the request is an acceptable upgrade, else the
+/// status and reason to answer with
+fn handshake_key(request: &str, origins: &[String], token: &str) -> Result<String, (&'static str, String)> {
+    let mut lines = request.lines();
+    let Some(target) = lines.next().and_then(|line| line.strip_prefix("GET ")) else {
+        return Err(("405 Method Not Allowed", "only GET is supported".to_string()));
+    };
+    let headers: Vec<(String, &str)> = lines
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, value)| (name.trim().to_ascii_lowercase(), value.trim()))
+        .collect();
+    let header = |name: &str| headers.iter().find(|(n, _)| n == name).map(|(_, value)| *value);
+
+    if !header("upgrade").is_some_and(|upgrade| upgrade.eq_ignore_ascii_case("websocket")) {
+        return Err(("426 Upgrade Required", "WebSocket clients only".to_string()));
+    }
+    if header("sec-websocket-version") != Some("13") {
+        return Err(("426 Upgrade Required", "WebSocket version 13 only".to_string()));
+    }
+    if let Some(origin) = header("origin") {
+        if !is_local_origin(origin) && !origins.iter().any(|allowed| allowed == origin) {
+            return Err(("403 Forbidden", format!("origin not allowed: {}", origin)));
+        }
+    }
+    let query_token = target
+        .split(' ')
+        .next()
+        .and_then(|path| path.split_once('?'))
+        .and_then(|(_, query)| query.split('&').find_map(|pair| pair.strip_prefix("token=")));
+    let bearer = header("authorization").and_then(|value| value.strip_prefix("Bearer "));
+    if !query_token.or(bearer).is_some_and(|sent| same_secret(sent, token)) {
+        return Err(("401 Unauthorized", "missing or wrong token".to_string()));
+    }
+    header("sec-websocket-key")
+        .filter(|key| !key.is_empty())
+        .map(str::to_string)
+        .ok_or(("400 Bad Request", "missing Sec-WebSocket-Key".to_string()))
+}
+
+/// Whether a browser origin is a page served from this host
+fn is_local_origin(origin: &str) -> bool {
+    let Some((_, authority)) = origin.split_once("://") else {
+        return false;
+    };
+    let host = match authority.strip_prefix('[') {
+        Some(rest) => rest.split(']').next().unwrap_or_default(),
+        None => authority.split(':').next().unwrap_or_default(),
+    };
+    host == "localhost" || host.parse::<IpAddr>().is_ok_and(|ip| ip.is_loopback())
+}
+
+/// Compare without returning at the first differing byte
+fn same_secret(sent: &str, token: &str) -> bool {
+    sent.len() == token.len() && sent.bytes().zip(token.bytes()).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
+/// Sec-WebSocket-Accept for a client's key
+pub fn accept_key(key: &str) -> String {
+    let digest = Sha1::digest(format!("{}{}", key, WEBSOCKET_GUID).as_bytes());
+    base64::engine::general_purpose::STANDARD.encode(digest)
+}
+
+const OPCODE_TEXT: u8 = 0x1;
+const OPCODE_CLOSE: u8 = 0x8;
+const OPCODE_PING: u8 = 0x9;
+const OPCODE_PONG: u8 = 0xA;
+
+/// Client messages the writer has to answer
+enum Frame {
+    Ping(Vec<u8>),
+    Close,
+}
+
+/// Read client frames until the connection ends, passing on pings and
+/// the close. Other messages are read and dropped.
+async fn read_frames(mut reader: OwnedReadHalf, control_tx: mpsc::Sender<Frame>) -> Result<()> {
+    loop {
+        let mut head = [0u8; 2];
+        if reader.read_exact(&mut head).await.is_err() {
+            break;
+        }
+        let opcode = head[0] & 0x0F;
+        let masked = head[1] & 0x80 != 0;
+        let len = match head[1] & 0x7F {
+            126 => reader.read_u16().await? as u64,
+            127 => reader.read_u64().await?,
+            len => len as u64,
+        };
+        if len > MAX_REQUEST as u64 {
+            break;
+        }
+        let mut mask = [0u8; 4];
+        if masked {
+            reader.read_exact(&mut mask).await?;
+        }
+        let mut payload = vec![0u8; len as usize];
+        reader.read_exact(&mut payload).await?;
+        if masked {
+            payload.iter_mut().enumerate().for_each(|(i, byte)| *byte ^= mask[i % 4]);
+        }
+        let frame = match opcode {
+            OPCODE_PING => Frame::Ping(payload),
+            OPCODE_CLOSE => break,
+            _ => continue,
+        };
+        if control_tx.send(frame).await.is_err() {
+            break;
+        }
+    }
+    let _ = control_tx.send(Frame::Close).await;
+    Ok(())
+}
+
+/// An unmasked, unfragmented frame, as servers send them
+fn frame_bytes(opcode: u8, payload: &[u8]) -> Vec<u8> {
+    let mut frame = vec![0x80 | opcode];
+    match payload.len() {
+        len if len < 126 => frame.push(len as u8),
+        len if len <= u16::MAX as usize => {
+            frame.push(126);
+            frame.extend_from_slice(&(len as u16).to_be_bytes());
+        }
+        len => {
+            frame.push(127);
+            frame.extend_from_slice(&(len as u64).to_be_bytes());
+        }
+    }
+    frame.extend_from_slice(payload);
+    frame
+}
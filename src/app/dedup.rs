@@ -0,0 +1,104 @@
+//! De-duplication of bursts of identical events
+//!
+//! Some applications open the same connection hundreds of times a second.
+//! Within a configured window, identical events are merged into the first
+//! one, which carries how many arrived. Only that one is shown and
+//! persisted, with its count. Source ports differ between the attempts and
+//! are ignored.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use crate::models::Event;
+
+/// What makes two events identical
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct EventKey {
+    node_addr: String,
+    rule: Option<String>,
+    protocol: String,
+    src_ip: String,
+    dst_ip: String,
+    dst_host: String,
+    dst_port: u32,
+    user_id: u32,
+    process_id: u32,
+    process_path: String,
+    process_args: Vec<String>,
+}
+
+impl EventKey {
+    fn of(node_addr: &str, event: &Event) -> Self {
+        let conn = &event.connection;
+        Self {
+            node_addr: node_addr.to_string(),
+            rule: event.rule.as_ref().map(|rule| rule.name.clone()).or_else(|| conn.rule_name.clone()),
+            protocol: conn.protocol.clone(),
+            src_ip: conn.src_ip.clone(),
+            dst_ip: conn.dst_ip.clone(),
+            dst_host: conn.dst_host.clone(),
+            dst_port: conn.dst_port,
+            user_id: conn.user_id,
+            process_id: conn.process_id,
+            process_path: conn.process_path.clone(),
+            process_args: conn.process_args.clone(),
+        }
+    }
+}
+
+/// Holds events for the window, merging the identical ones
+#[derive(Debug, Default)]
+pub struct EventCoalescer {
+    /// Zero keeps every event apart
+    window: Duration,
+    /// Events held back, with when their window started
+    pending: HashMap<EventKey, (Instant, Event)>,
+}
+
+impl EventCoalescer {
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window,
+            pending: HashMap::new(),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        !self.window.is_zero()
+    }
+
+    /// Add an event arriving at `now`, returns it right away when disabled
+    pub fn push(&mut self, node_addr: &str, event: Event, now: Instant) -> Option<Event> {
+        if !self.is_enabled() {
+            return Some(event);
+        }
+        let key = EventKey::of(node_addr, &event);
+        match self.pending.get_mut(&key) {
+            Some((_, first)) => first.count += event.count.max(1),
+            None => {
+                self.pending.insert(key, (now, event));
+            }
+        }
+        None
+    }
+
+    /// Events whose window has ended by `now`, oldest first
+    pub fn flush(&mut self, now: Instant) -> Vec<Event> {
+        let window = self.window;
+        let mut due: Vec<(Instant, Event)> = self
+            .pending
+            .extract_if(|_, (start, _)| now.saturating_duration_since(*start) >= window)
+            .map(|(_, pending)| pending)
+            .collect();
+        due.sort_by_key(|(start, _)| *start);
+        due.into_iter().map(|(_, event)| event).collect()
+    }
+
+    /// Every event held back, oldest first, when there is no waiting for
+    /// the window
+    pub fn drain(&mut self) -> Vec<Event> {
+        let mut pending: Vec<(Instant, Event)> = self.pending.drain().map(|(_, pending)| pending).collect();
+        pending.sort_by_key(|(start, _)| *start);
+        pending.into_iter().map(|(_, event)| event).collect()
+    }
+}
@@ -0,0 +1,118 @@
+//! Desktop notifications for prompts and alerts arriving unseen
+//!
+//! A prompt waits in the terminal, easy to miss while working in another
+//! window. When enabled, prompts and high-priority alerts also run a
+//! notification command such as notify-send, given the summary and body as
+//! its last two arguments. Terminals reporting focus get no notifications
+//! while focused; with the others every prompt and alert is notified.
+
+use std::process::Stdio;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use tokio::process::Command;
+
+use crate::config::Settings;
+use crate::models::{Alert, AlertPriority, Connection};
+use crate::utils::Formats;
+
+/// How long the notification command may take
+const NOTIFY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Summary and body of the notification for a prompt
+pub fn prompt_message(connection: &Connection, formats: &Formats) -> (String, String) {
+    let host = if connection.dst_host.is_empty() { &connection.dst_ip } else { &connection.dst_host };
+    let summary = format!("{} wants to connect", connection.process_name());
+    let body = format!(
+        "to {}:{} ({})\n{}",
+        formats.host(host),
+        connection.dst_port,
+        connection.protocol,
+        formats.path(&connection.process_path)
+    );
+    (summary, body)
+}
+
+/// Summary and body of the notification for an alert
+pub fn alert_message(alert: &Alert, formats: &Formats) -> (String, String) {
+    let summary = format!("{} {} alert", alert.alert_type, alert.what);
+    let body = format!("{}\non {}", formats.text(&alert.text()), formats.host(&alert.node));
+    (summary, body)
+}
+
+/// Run the notification command, the summary and body appended
+pub async fn send(command: &str, summary: &str, body: &str) -> Result<(), String> {
+    let run = Command::new("sh")
+        .arg("-c")
+        .arg(format!("{} \"$@\"", command))
+        .arg("sh")
+        .arg(summary)
+        .arg(body)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .output();
+    let output = tokio::time::timeout(NOTIFY_TIMEOUT, run)
+        .await
+        .map_err(|_| format!("no exit after {}s", NOTIFY_TIMEOUT.as_secs()))?
+        .map_err(|e| e.to_string())?;
+    if output.status.success() {
+        return Ok(());
+    }
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    Err(match stderr.lines().next() {
+        Some(line) => format!("{}: {}", output.status, line),
+        None => output.status.to_string(),
+    })
+}
+
+pub struct DesktopNotifier {
+    command: String,
+    /// None until the terminal reports focus
+    focused: Option<bool>,
+    /// Last alert notified, as alerts are signaled again when acknowledged
+    last_alert: Option<(u64, DateTime<Utc>)>,
+}
+
+impl DesktopNotifier {
+    /// None unless enabled with a command in the settings
+    pub fn from_settings(settings: &Settings) -> Option<Self> {
+        let command = settings.desktop_notify_command.trim();
+        (settings.desktop_notifications && !command.is_empty()).then(|| Self {
+            command: command.to_string(),
+            focused: None,
+            last_alert: None,
+        })
+    }
+
+    pub fn set_focused(&mut self, focused: bool) {
+        self.focused = Some(focused);
+    }
+
+    fn notify(&self, (summary, body): (String, String)) {
+        if self.focused == Some(true) {
+            return;
+        }
+        let command = self.command.clone();
+        tokio::spawn(async move {
+            if let Err(e) = send(&command, &summary, &body).await {
+                tracing::warn!("Desktop notification failed: {}", e);
+            }
+        });
+    }
+
+    pub fn prompt(&self, connection: &Connection, formats: &Formats) {
+        self.notify(prompt_message(connection, formats));
+    }
+
+    /// Notify a new, open high-priority alert once
+    pub fn alert(&mut self, alert: &Alert, formats: &Formats) {
+        let key = (alert.id, alert.timestamp);
+        if alert.priority != AlertPriority::High || alert.acknowledged || alert.restored || self.last_alert == Some(key) {
+            return;
+        }
+        self.last_alert = Some(key);
+        self.notify(alert_message(alert, formats));
+    }
+}
@@ -1,14 +1,17 @@
 //! Input event handling
 
-use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyModifiers};
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
 use std::time::Duration;
 
 /// Application input events
 #[derive(Debug, Clone)]
 pub enum AppEvent {
     Key(KeyEvent),
+    Mouse(MouseEvent),
     Tick,
     Resize(u16, u16),
+    /// The terminal gained or lost focus, if it reports that
+    Focus(bool),
 }
 
 /// Event handler for terminal input
@@ -26,7 +29,10 @@ impl EventHandler {
         if event::poll(self.tick_rate).ok()? {
             match event::read().ok()? {
                 Event::Key(key) => Some(AppEvent::Key(key)),
+                Event::Mouse(mouse) => Some(AppEvent::Mouse(mouse)),
                 Event::Resize(w, h) => Some(AppEvent::Resize(w, h)),
+                Event::FocusGained => Some(AppEvent::Focus(true)),
+                Event::FocusLost => Some(AppEvent::Focus(false)),
                 _ => None,
             }
         } else {
@@ -77,6 +83,26 @@ pub fn navigation_delta(event: &KeyEvent) -> Option<i32> {
     }
 }
 
+/// Lines moved per notch of the scroll wheel
+const SCROLL_LINES: i32 = 3;
+
+/// Check for scroll wheel turns (returns delta)
+pub fn scroll_delta(event: &MouseEvent) -> Option<i32> {
+    match event.kind {
+        MouseEventKind::ScrollUp => Some(-SCROLL_LINES),
+        MouseEventKind::ScrollDown => Some(SCROLL_LINES),
+        _ => None,
+    }
+}
+
+/// Check for a left click (returns column and row)
+pub fn click_position(event: &MouseEvent) -> Option<(u16, u16)> {
+    match event.kind {
+        MouseEventKind::Down(MouseButton::Left) => Some((event.column, event.row)),
+        _ => None,
+    }
+}
+
 /// Check for tab navigation (returns delta)
 pub fn tab_delta(event: &KeyEvent) -> Option<i32> {
     match (event.code, event.modifiers) {
@@ -88,7 +114,7 @@ pub fn tab_delta(event: &KeyEvent) -> Option<i32> {
     }
 }
 
-/// Check for tab number keys (1-6)
+/// Check for tab number keys (1-8)
 pub fn tab_number(event: &KeyEvent) -> Option<usize> {
     match event.code {
         KeyCode::Char('1') => Some(0),
@@ -97,6 +123,8 @@ pub fn tab_number(event: &KeyEvent) -> Option<usize> {
         KeyCode::Char('4') => Some(3),
         KeyCode::Char('5') => Some(4),
         KeyCode::Char('6') => Some(5),
+        KeyCode::Char('7') => Some(6),
+        KeyCode::Char('8') => Some(7),
         _ => None,
     }
 }
@@ -0,0 +1,52 @@
+//! Reports and statistics written to the exports directory
+//!
+//! Exported from the UI on demand or by the scheduler. Files are named
+//! after what they hold, the node and the time, so they never overwrite
+//! each other.
+
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+
+use crate::models::report::{rules_report, ReportFormat};
+use crate::models::{Rule, Statistics, StatsFormat};
+
+/// Write a rules report into `dir`, returning its path
+pub fn export_rules_report(dir: &Path, node_addr: &str, rules: &[Rule], format: ReportFormat) -> Result<PathBuf, std::io::Error> {
+    write_export(dir, "rules", node_addr, format.extension(), |now| rules_report(node_addr, rules, format, now))
+}
+
+/// Write a node's statistics into `dir`, returning its path
+pub fn export_stats(
+    dir: &Path,
+    node_addr: &str,
+    node_name: &str,
+    stats: &Statistics,
+    format: StatsFormat,
+) -> Result<PathBuf, std::io::Error> {
+    write_export(dir, "stats", node_addr, format.extension(), |now| stats.export(node_addr, node_name, now, format))
+}
+
+fn write_export(
+    dir: &Path,
+    kind: &str,
+    node_addr: &str,
+    extension: &str,
+    content: impl FnOnce(DateTime<Utc>) -> String,
+) -> Result<PathBuf, std::io::Error> {
+    let now = Utc::now();
+    let node: String = node_addr
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { '-' })
+        .collect();
+    std::fs::create_dir_all(dir)?;
+    let path = dir.join(format!(
+        "{}-{}-{}.{}",
+        kind,
+        node.trim_matches('-'),
+        now.format("%Y%m%d-%H%M%S"),
+        extension
+    ));
+    std::fs::write(&path, content(now))?;
+    Ok(path)
+}
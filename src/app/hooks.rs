@@ -0,0 +1,291 @@
+//! Local actions run when events matched by a rule arrive
+//!
+//! A hook names a rule and runs a command or posts to a webhook for the
+//! events referencing it, e.g. to log every telemetry attempt elsewhere.
+//! The daemon knows nothing about it. Runs are rate limited per hook;
+//! events arriving in between are counted and reported with the next run.
+
+use std::collections::VecDeque;
+use std::process::Stdio;
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Utc};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::process::Command;
+
+use crate::config::RuleHook;
+use crate::models::Event;
+
+/// How long a command or webhook may take
+const RUN_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Runs kept in the execution log
+pub const MAX_HOOK_RUNS: usize = 100;
+
+/// Where a hook sends an event
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Sink {
+    /// Shell command, the event as JSON on stdin
+    Command(String),
+    /// http:// URL the event is POSTed to
+    Webhook(String),
+}
+
+impl Sink {
+    /// Sinks configured on a hook
+    pub fn of(hook: &RuleHook) -> Vec<Self> {
+        let mut sinks = Vec::new();
+        if !hook.command.is_empty() {
+            sinks.push(Self::Command(hook.command.clone()));
+        }
+        if !hook.webhook.is_empty() {
+            sinks.push(Self::Webhook(hook.webhook.clone()));
+        }
+        sinks
+    }
+
+    pub fn label(&self) -> String {
+        match self {
+            Self::Command(command) => format!("run {}", command),
+            Self::Webhook(url) => format!("post {}", url),
+        }
+    }
+
+    /// Send a payload, returns what came back or why it failed
+    pub async fn deliver(&self, payload: &str) -> Result<String, String> {
+        let delivery = async {
+            match self {
+                Self::Command(command) => run_command(command, payload).await,
+                Self::Webhook(url) => post_webhook(url, payload).await,
+            }
+        };
+        tokio::time::timeout(RUN_TIMEOUT, delivery)
+            .await
+            .unwrap_or_else(|_| Err(format!("no result after {}s", RUN_TIMEOUT.as_secs())))
+    }
+}
+
+async fn run_command(command: &str, payload: &str) -> Result<String, String> {
+    let mut child = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| e.to_string())?;
+    if let Some(mut stdin) = child.stdin.take() {
+        // A command not reading its input is fine
+        let _ = stdin.write_all(payload.as_bytes()).await;
+    }
+    let output = child.wait_with_output().await.map_err(|e| e.to_string())?;
+    if output.status.success() {
+        return Ok(output.status.to_string());
+    }
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    match stderr.lines().next() {
+        Some(line) => Err(format!("{}: {}", output.status, line)),
+        None => Err(output.status.to_string()),
+    }
+}
+
+/// Minimal HTTP/1.1 POST; https endpoints need a command such as curl
+async fn post_webhook(url: &str, payload: &str) -> Result<String, String> {
+    let (code, _) = http_request("POST", url, payload).await?;
+    if (200..300).contains(&code) {
+        Ok(format!("HTTP {}", code))
+    } else {
+        Err(format!("HTTP {}", code))
+    }
+}
+
+/// Send a request to an http:// URL, returns the status code and body
+pub(crate) async fn http_request(method: &str, url: &str, body: &str) -> Result<(u16, Vec<u8>), String> {
+    let rest = url
+        .strip_prefix("http://")
+        .ok_or_else(|| "only http:// URLs are supported, use a command for others".to_string())?;
+    let (authority, path) = match rest.find('/') {
+        Some(i) => (&rest[..i], &rest[i..]),
+        None => (rest, "/"),
+    };
+    let address = if authority.rsplit_once(':').is_some_and(|(_, port)| port.parse::<u16>().is_ok()) {
+        authority.to_string()
+    } else {
+        format!("{}:80", authority)
+    };
+
+    let mut stream = TcpStream::connect(&address).await.map_err(|e| e.to_string())?;
+    let request = format!(
+        "{} {} HTTP/1.1\r\nHost: {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        method,
+        path,
+        authority,
+        body.len(),
+        body
+    );
+    stream.write_all(request.as_bytes()).await.map_err(|e| e.to_string())?;
+
+    let mut response = Vec::new();
+    stream.read_to_end(&mut response).await.map_err(|e| e.to_string())?;
+    let Some(split) = response.windows(4).position(|w| w == b"\r\n\r\n") else {
+        return Err("no HTTP response".to_string());
+    };
+    let head = String::from_utf8_lossy(&response[..split]).to_lowercase();
+    let code = head
+        .lines()
+        .next()
+        .and_then(|status| status.split_whitespace().nth(1))
+        .and_then(|code| code.parse::<u16>().ok())
+        .ok_or_else(|| "no HTTP response".to_string())?;
+    let body = response.split_off(split + 4);
+    if head.lines().any(|line| line.starts_with("transfer-encoding:") && line.contains("chunked")) {
+        return Ok((code, dechunk(&body)));
+    }
+    Ok((code, body))
+}
+
+/// Body of a chunked response
+fn dechunk(mut body: &[u8]) -> Vec<u8> {
+    let mut out = Vec::new();
+    while let Some(eol) = body.windows(2).position(|w| w == b"\r\n") {
+        let size = String::from_utf8_lossy(&body[..eol]);
+        let size = usize::from_str_radix(size.split(';').next().unwrap_or_default().trim(), 16).unwrap_or(0);
+        let start = eol + 2;
+        if size == 0 || start + size > body.len() {
+            break;
+        }
+        out.extend_from_slice(&body[start..start + size]);
+        body = &body[(start + size + 2).min(body.len())..];
+    }
+    out
+}
+
+/// A hook that is due to run for an event
+#[derive(Debug, Clone)]
+pub struct Trigger {
+    pub rule: String,
+    pub sinks: Vec<Sink>,
+    /// The event as JSON
+    pub payload: String,
+    /// Events left out since the previous run
+    pub suppressed: u64,
+}
+
+impl Trigger {
+    /// Deliver to every sink, one log entry each
+    pub async fn run(self) -> Vec<HookRun> {
+        let mut runs = Vec::with_capacity(self.sinks.len());
+        for sink in &self.sinks {
+            let result = sink.deliver(&self.payload).await;
+            match &result {
+                Ok(_) => tracing::debug!("Hook for {}: {} done", self.rule, sink.label()),
+                Err(e) => tracing::warn!("Hook for {}: {} failed: {}", self.rule, sink.label(), e),
+            }
+            runs.push(HookRun {
+                time: Utc::now(),
+                rule: self.rule.clone(),
+                sink: sink.label(),
+                suppressed: self.suppressed,
+                result,
+            });
+        }
+        runs
+    }
+}
+
+/// One delivery in the execution log
+#[derive(Debug, Clone)]
+pub struct HookRun {
+    pub time: DateTime<Utc>,
+    pub rule: String,
+    pub sink: String,
+    /// Events left out by rate limiting before this run
+    pub suppressed: u64,
+    pub result: Result<String, String>,
+}
+
+/// Configured hooks with their rate limiting state
+#[derive(Debug, Default)]
+pub struct RuleHooks {
+    hooks: Vec<RuleHook>,
+    /// Per hook, when it last ran and the events left out since
+    last_run: Vec<Option<Instant>>,
+    suppressed: Vec<u64>,
+}
+
+impl RuleHooks {
+    pub fn new(hooks: Vec<RuleHook>) -> Self {
+        let hooks: Vec<_> = hooks
+            .into_iter()
+            .filter(|hook| !hook.rule.is_empty() && !Sink::of(hook).is_empty())
+            .collect();
+        Self {
+            last_run: vec![None; hooks.len()],
+            suppressed: vec![0; hooks.len()],
+            hooks,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.hooks.is_empty()
+    }
+
+    /// Hooks to run for an event, counting the ones held back by rate limiting
+    pub fn due(&mut self, node_addr: &str, event: &Event, now: Instant) -> Vec<Trigger> {
+        let Some(rule) = event
+            .rule
+            .as_ref()
+            .map(|rule| rule.name.as_str())
+            .or(event.connection.rule_name.as_deref())
+        else {
+            return Vec::new();
+        };
+
+        let mut due = Vec::new();
+        for (i, hook) in self.hooks.iter().enumerate().filter(|(_, hook)| hook.rule == rule) {
+            let interval = Duration::from_secs(hook.min_interval_secs);
+            if self.last_run[i].is_some_and(|last| now.duration_since(last) < interval) {
+                self.suppressed[i] += 1;
+                continue;
+            }
+            self.last_run[i] = Some(now);
+            let suppressed = std::mem::take(&mut self.suppressed[i]);
+            due.push(Trigger {
+                rule: rule.to_string(),
+                sinks: Sink::of(hook),
+                payload: payload(rule, node_addr, event, suppressed),
+                suppressed,
+            });
+        }
+        due
+    }
+}
+
+fn payload(rule: &str, node_addr: &str, event: &Event, suppressed: u64) -> String {
+    let conn = &event.connection;
+    serde_json::json!({
+        "rule": rule,
+        "node": node_addr,
+        "time": event.time,
+        "action": event.rule.as_ref().map(|rule| rule.action.to_string()),
+        "process": conn.process_path,
+        "pid": conn.process_id,
+        "uid": conn.user_id,
+        "protocol": conn.protocol,
+        "dst_host": conn.dst_host,
+        "dst_ip": conn.dst_ip,
+        "dst_port": conn.dst_port,
+        "suppressed": suppressed,
+    })
+    .to_string()
+}
+
+/// Add runs to the execution log, newest first
+pub fn record(log: &mut VecDeque<HookRun>, runs: Vec<HookRun>) {
+    for run in runs {
+        log.push_front(run);
+    }
+    log.truncate(MAX_HOOK_RUNS);
+}
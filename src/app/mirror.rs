@@ -0,0 +1,155 @@
+//! Read-only mirrors of the live view over a local control socket
+//!
+//! The primary instance streams newline-delimited JSON snapshots of its
+//! nodes, connections and alerts to every attached client. A mirror applies
+//! them to its own state and never talks to a daemon, so it can't answer
+//! prompts or change rules. Clients may also send requests, see `answer`.
+
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::unix::OwnedWriteHalf;
+use tokio::net::{UnixListener, UnixStream};
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::{mpsc, Mutex};
+
+use crate::app::answer::serve_requests;
+use crate::app::state::{AppMessage, AppState, UiUpdateSignal};
+use crate::app::xref::XrefIndex;
+use crate::config::paths;
+use crate::models::{Alert, DnsEntry, DnsLog, Event, Node};
+
+/// Minimum time between two snapshots sent to a mirror
+const SNAPSHOT_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Everything a mirror needs to render the live view
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MirrorSnapshot {
+    pub nodes: Vec<Node>,
+    pub active_node: Option<String>,
+    /// Newest first, as kept in the app state
+    pub connections: Vec<Event>,
+    pub alerts: Vec<Alert>,
+    #[serde(default)]
+    pub dns: Vec<DnsEntry>,
+}
+
+impl MirrorSnapshot {
+    pub async fn capture(state: &AppState) -> Self {
+        let nodes = state.nodes.read().await;
+        Self {
+            nodes: nodes.nodes.values().cloned().collect(),
+            active_node: nodes.active_node.clone(),
+            connections: state.connections.read().await.iter().cloned().collect(),
+            alerts: state.alerts.read().await.iter().cloned().collect(),
+            dns: state.dns.lock().unwrap().entries(),
+        }
+    }
+
+    /// Replace the mirrored parts of the state, keeping the locally chosen
+    /// active node while the primary still knows it
+    pub async fn apply(self, state: &AppState) {
+        let mut nodes = state.nodes.write().await;
+        let local_active = nodes.active_node.take();
+        nodes.nodes = self.nodes.into_iter().map(|n| (n.addr.clone(), n)).collect();
+        nodes.active_node = local_active
+            .filter(|addr| nodes.nodes.contains_key(addr))
+            .or(self.active_node);
+        drop(nodes);
+
+        // Oldest first, as they arrived
+        let mut xref = XrefIndex::default();
+        self.connections.iter().rev().for_each(|event| xref.add_event(event));
+        self.alerts.iter().rev().for_each(|alert| xref.add_alert(alert));
+        *state.xref.lock().unwrap() = xref;
+        let mut dns = DnsLog::default();
+        dns.load(self.dns);
+        *state.dns.lock().unwrap() = dns;
+
+        *state.connections.write().await = self.connections.into();
+        *state.alerts.write().await = self.alerts.into();
+
+        state.notify_ui(UiUpdateSignal::NodeChanged);
+        state.notify_ui(UiUpdateSignal::StatsUpdated);
+        state.notify_ui(UiUpdateSignal::ConnectionsUpdated);
+        state.notify_ui(UiUpdateSignal::AlertsUpdated);
+    }
+}
+
+/// Accept mirrors on the control socket until the task is aborted
+pub async fn serve(path: String, state: Arc<AppState>, state_tx: mpsc::Sender<AppMessage>) -> Result<()> {
+    let listener = bind_private(Path::new(&path))?;
+    tracing::info!("Control socket listening on {}", path);
+
+    loop {
+        let (stream, _) = listener.accept().await?;
+        let (state, state_tx) = (state.clone(), state_tx.clone());
+        tokio::spawn(async move {
+            let (reader, writer) = stream.into_split();
+            let writer = Arc::new(Mutex::new(writer));
+            let requests = tokio::spawn(serve_requests(reader, writer.clone(), state.clone(), state_tx));
+            if let Err(e) = stream_snapshots(writer, state).await {
+                tracing::debug!("Mirror detached: {}", e);
+            }
+            requests.abort();
+        });
+    }
+}
+
+/// Bind a socket only the current user can connect to. Snapshots carry
+/// connection details: the socket is created without access for others,
+/// inside the private runtime directory unless configured elsewhere.
+fn bind_private(path: &Path) -> std::io::Result<UnixListener> {
+    let runtime_dir = paths::runtime_dir();
+    if path.parent() == Some(runtime_dir.as_path()) {
+        paths::create_private_dir(&runtime_dir)?;
+    }
+    // Left behind by a previous run
+    let _ = std::fs::remove_file(path);
+
+    paths::bind_private_socket(path)
+}
+
+/// Send a snapshot now and after every batch of UI updates
+async fn stream_snapshots(stream: Arc<Mutex<OwnedWriteHalf>>, state: Arc<AppState>) -> Result<()> {
+    let mut updates = state.ui_update_tx.subscribe();
+    loop {
+        let mut line = serde_json::to_vec(&MirrorSnapshot::capture(&state).await)?;
+        line.push(b'\n');
+        stream.lock().await.write_all(&line).await?;
+
+        tokio::time::sleep(SNAPSHOT_INTERVAL).await;
+        match updates.recv().await {
+            Ok(_) | Err(RecvError::Lagged(_)) => {}
+            Err(RecvError::Closed) => return Ok(()),
+        }
+        // Fold everything that arrived meanwhile into one snapshot
+        while updates.try_recv().is_ok() {}
+    }
+}
+
+/// Apply snapshots from the primary instance until it goes away
+pub async fn attach(path: &str, state: Arc<AppState>) -> Result<()> {
+    let stream = UnixStream::connect(path).await?;
+    let mut lines = BufReader::new(stream).lines();
+
+    while let Some(line) = lines.next_line().await? {
+        match serde_json::from_str::<MirrorSnapshot>(&line) {
+            Ok(snapshot) => snapshot.apply(&state).await,
+            Err(e) => tracing::warn!("Invalid mirror snapshot: {}", e),
+        }
+    }
+
+    // Primary exited, nothing shown is live any more
+    let mut nodes = state.nodes.write().await;
+    for node in nodes.nodes.values_mut() {
+        node.disconnect();
+    }
+    drop(nodes);
+    state.notify_ui(UiUpdateSignal::NodeChanged);
+    Ok(())
+}
@@ -1,5 +1,23 @@
 pub mod actions;
+pub mod answer;
+pub mod blocklists;
+pub mod bridge;
+pub mod dedup;
+pub mod desktop;
 pub mod events;
+pub mod export;
+pub mod hooks;
+pub mod mirror;
+pub mod persistence;
+pub mod proxy;
+pub mod replay;
+pub mod sampling;
+pub mod scheduler;
+pub mod snapshots;
+pub mod status;
 pub mod state;
+pub mod suggestions;
+pub mod updates;
+pub mod xref;
 
 pub use state::{AppMessage, AppState};
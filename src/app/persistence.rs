@@ -0,0 +1,149 @@
+//! Writes held back while the database is unavailable
+//!
+//! When the database file can't be opened or written, e.g. on a full disk,
+//! events and alerts stay in memory as usual and their writes are buffered.
+//! The database is tried again periodically and the buffer written out once
+//! it works. Rules aren't buffered: daemons send all of them again when they
+//! reconnect.
+
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+
+use super::state::{AppState, UiUpdateSignal};
+use crate::db::Database;
+use crate::models::{Alert, DnsEntry, Event, Rule, RuleHits};
+
+/// Most writes buffered, older ones are dropped beyond
+pub const MAX_BUFFERED_WRITES: usize = 10_000;
+
+/// How often the database is tried again while unavailable
+const RETRY_INTERVAL: Duration = Duration::from_secs(15);
+
+/// A write to replay once the database works again
+#[derive(Debug, Clone)]
+pub enum PendingWrite {
+    Connection(Event),
+    Alert(Alert),
+    Dns(DnsEntry),
+    RuleHits { node: String, rule: Rule, hits: RuleHits },
+}
+
+impl PendingWrite {
+    fn apply(&self, db: &Database) -> Result<()> {
+        match self {
+            Self::Connection(event) => db.insert_connection(event),
+            Self::Alert(alert) => db.insert_alert(alert),
+            Self::Dns(entry) => db.upsert_dns(entry),
+            Self::RuleHits { node, rule, hits } => db.store_rule_hits(node, rule, hits),
+        }
+    }
+}
+
+/// Since when and why the database is unavailable
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DbOutage {
+    pub since: DateTime<Utc>,
+    /// Last error the database gave
+    pub error: String,
+    /// Writes waiting for the database
+    pub buffered: usize,
+    /// Writes lost to the buffer limit
+    pub dropped: u64,
+}
+
+/// Writes go straight to the database until one fails, then into the buffer
+#[derive(Debug, Default)]
+pub struct WriteBuffer {
+    outage: Option<DbOutage>,
+    pending: VecDeque<PendingWrite>,
+}
+
+impl WriteBuffer {
+    /// Consider the database unavailable from now on
+    pub fn fail(&mut self, error: String) {
+        match &mut self.outage {
+            Some(outage) => outage.error = error,
+            None => {
+                tracing::error!("Database unavailable, keeping writes in memory: {}", error);
+                self.outage = Some(DbOutage {
+                    since: Utc::now(),
+                    error,
+                    buffered: 0,
+                    dropped: 0,
+                });
+            }
+        }
+    }
+
+    /// Write to the database, or buffer the write while it is unavailable
+    pub fn write(&mut self, db: &Database, write: PendingWrite) {
+        if self.outage.is_none() {
+            match write.apply(db) {
+                Ok(()) => return,
+                Err(e) => self.fail(e.to_string()),
+            }
+        }
+        self.pending.push_back(write);
+        if self.pending.len() > MAX_BUFFERED_WRITES {
+            self.pending.pop_front();
+            if let Some(outage) = &mut self.outage {
+                outage.dropped += 1;
+            }
+        }
+        self.update_count();
+    }
+
+    /// Write out the buffer in order, stopping at the first failure.
+    /// Returns whether the database is available again.
+    pub fn retry(&mut self, db: &Database) -> bool {
+        if self.outage.is_none() {
+            return true;
+        }
+        if let Err(e) = db.reopen() {
+            self.fail(e.to_string());
+            return false;
+        }
+        while let Some(write) = self.pending.front() {
+            if let Err(e) = write.apply(db) {
+                self.fail(e.to_string());
+                self.update_count();
+                return false;
+            }
+            self.pending.pop_front();
+        }
+        if let Some(outage) = self.outage.take() {
+            tracing::info!(
+                "Database available again, {} buffered writes stored, {} dropped",
+                outage.buffered,
+                outage.dropped
+            );
+        }
+        true
+    }
+
+    pub fn outage(&self) -> Option<&DbOutage> {
+        self.outage.as_ref()
+    }
+
+    fn update_count(&mut self) {
+        if let Some(outage) = &mut self.outage {
+            outage.buffered = self.pending.len();
+        }
+    }
+}
+
+/// Try the database again while it is unavailable
+pub async fn run(state: Arc<AppState>) {
+    let mut interval = tokio::time::interval(RETRY_INTERVAL);
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+    loop {
+        interval.tick().await;
+        if state.db_outage().is_some() && state.retry_db() {
+            state.notify_ui(UiUpdateSignal::Redraw);
+        }
+    }
+}
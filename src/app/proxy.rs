@@ -0,0 +1,112 @@
+//! Attribution of connections made through local proxies
+
+use std::collections::{HashMap, VecDeque};
+use std::net::IpAddr;
+
+use crate::config::KnownProxy;
+use crate::models::Event;
+
+/// How long after a client connects to a proxy its outgoing connection may follow
+const CORRELATION_WINDOW_NANOS: i64 = 2_000_000_000;
+
+/// Identifies one event: time, process id and source port
+type EventId = (i64, u32, u32);
+
+fn event_id(event: &Event) -> EventId {
+    let conn = &event.connection;
+    (event.unix_nano, conn.process_id, conn.src_port)
+}
+
+fn is_loopback(ip: &str) -> bool {
+    ip.parse::<IpAddr>().is_ok_and(|ip| ip.is_loopback())
+}
+
+/// Proxy hops found between a set of events
+#[derive(Debug, Default)]
+pub struct ProxyChains {
+    /// Originating process of connections made by a proxy
+    origins: HashMap<EventId, String>,
+    /// Proxy that local client connections were handed to
+    proxies: HashMap<EventId, String>,
+}
+
+impl ProxyChains {
+    /// Process that really asked for a connection the proxy made
+    pub fn origin(&self, event: &Event) -> Option<&str> {
+        self.origins.get(&event_id(event)).map(String::as_str)
+    }
+
+    /// Proxy process behind a connection to a local proxy port
+    pub fn proxy(&self, event: &Event) -> Option<&str> {
+        self.proxies.get(&event_id(event)).map(String::as_str)
+    }
+}
+
+/// Pairs connections to local proxy ports with the proxy's next outgoing one
+#[derive(Debug, Clone)]
+pub struct ProxyCorrelator {
+    proxies: Vec<KnownProxy>,
+}
+
+impl Default for ProxyCorrelator {
+    fn default() -> Self {
+        Self::new(KnownProxy::defaults())
+    }
+}
+
+impl ProxyCorrelator {
+    pub fn new(proxies: Vec<KnownProxy>) -> Self {
+        Self { proxies }
+    }
+
+    /// Proxy listening on the destination of a local connection
+    fn proxy_for(&self, event: &Event) -> Option<&KnownProxy> {
+        let conn = &event.connection;
+        if !is_loopback(&conn.dst_ip) {
+            return None;
+        }
+        self.proxies
+            .iter()
+            .find(|p| p.port == conn.dst_port && p.process != conn.process_name())
+    }
+
+    /// Match events in any order; each client connection pairs with at most
+    /// one outgoing proxy connection, oldest first
+    pub fn correlate<'a>(&self, events: impl IntoIterator<Item = &'a Event>) -> ProxyChains {
+        let mut events: Vec<&Event> = events.into_iter().collect();
+        events.sort_by_key(|e| e.unix_nano);
+
+        let mut chains = ProxyChains::default();
+        // Unmatched client connections per proxy process: (time, client name)
+        let mut waiting: HashMap<&str, VecDeque<(i64, String)>> = HashMap::new();
+
+        for event in events {
+            let conn = &event.connection;
+            if let Some(proxy) = self.proxy_for(event) {
+                chains.proxies.insert(event_id(event), proxy.process.clone());
+                waiting
+                    .entry(proxy.process.as_str())
+                    .or_default()
+                    .push_back((event.unix_nano, conn.process_name().to_string()));
+                continue;
+            }
+
+            if is_loopback(&conn.dst_ip) {
+                continue;
+            }
+            let Some(queue) = waiting.get_mut(conn.process_name()) else {
+                continue;
+            };
+            while queue
+                .front()
+                .is_some_and(|(t, _)| event.unix_nano - t > CORRELATION_WINDOW_NANOS)
+            {
+                queue.pop_front();
+            }
+            if let Some((_, client)) = queue.pop_front() {
+                chains.origins.insert(event_id(event), client);
+            }
+        }
+        chains
+    }
+}
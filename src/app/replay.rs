@@ -0,0 +1,106 @@
+//! Replaying a capture of daemon traffic without a daemon
+//!
+//! A capture is a JSONL file, one call a daemon makes per line: subscribing,
+//! pinging with statistics and events, asking about a connection, posting an
+//! alert or going away. Each line is turned into the messages the gRPC
+//! server would send the state manager for that call, so the TUI shows it
+//! as it would live. Demos and bug reports work without a running daemon.
+//!
+//! ```text
+//! {"type": "subscribe", "node": "unix:/demo", "config": {...}}
+//! {"type": "ask", "delay_ms": 1500, "node": "unix:/demo", "connection": {...}}
+//! ```
+
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use tokio::sync::{mpsc, oneshot};
+
+use crate::app::state::{AppMessage, AppState};
+use crate::config::InterceptionMode;
+use crate::models::node::{AuthStatus, ClientConfig};
+use crate::models::{Alert, Connection, Statistics};
+
+/// A daemon call, tagged with its `type`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ReplayCall {
+    Subscribe { node: String, config: ClientConfig },
+    Ping { node: String, stats: Statistics },
+    Ask { node: String, connection: Connection },
+    Alert { node: String, alert: Alert },
+    Disconnect { node: String },
+}
+
+/// A line of a capture
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplayStep {
+    /// Wait after the previous line
+    #[serde(default)]
+    pub delay_ms: u64,
+    #[serde(flatten)]
+    pub call: ReplayCall,
+}
+
+/// Read a capture, blank lines skipped
+pub fn load(path: &Path) -> Result<Vec<ReplayStep>> {
+    let text = std::fs::read_to_string(path).with_context(|| format!("Cannot read {}", path.display()))?;
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| {
+            serde_json::from_str(line).with_context(|| format!("{} line {}", path.display(), i + 1))
+        })
+        .collect()
+}
+
+impl ReplayCall {
+    /// Messages the gRPC server sends for the call, prompts only when asking
+    pub fn messages(self, interception: InterceptionMode) -> Vec<AppMessage> {
+        match self {
+            Self::Subscribe { node, config } => vec![AppMessage::NodeConnected {
+                addr: node,
+                config,
+                auth: AuthStatus::default(),
+                listener: None,
+            }],
+            Self::Ping { node, stats } => vec![AppMessage::StatsUpdate { node_addr: node, stats }],
+            Self::Ask { node, connection } => {
+                let mut messages = vec![AppMessage::NewConnection {
+                    node_addr: node.clone(),
+                    connection: connection.clone(),
+                }];
+                if interception == InterceptionMode::Ask {
+                    // Nobody waits for the answer
+                    let (response_tx, _) = oneshot::channel();
+                    messages.push(AppMessage::ConnectionPrompt { node_addr: node, connection, response_tx });
+                }
+                messages
+            }
+            Self::Alert { node, mut alert } => {
+                alert.node = node;
+                vec![AppMessage::AlertReceived { alert }]
+            }
+            Self::Disconnect { node } => vec![AppMessage::NodeDisconnected { addr: node }],
+        }
+    }
+}
+
+/// Feed the steps to the state manager, each after its delay
+pub async fn run(steps: Vec<ReplayStep>, state: Arc<AppState>, state_tx: mpsc::Sender<AppMessage>) {
+    let count = steps.len();
+    for step in steps {
+        if step.delay_ms > 0 {
+            tokio::time::sleep(Duration::from_millis(step.delay_ms)).await;
+        }
+        for message in step.call.messages(state.interception()) {
+            if state_tx.send(message).await.is_err() {
+                return;
+            }
+        }
+    }
+    tracing::info!("Replay finished after {} steps", count);
+}
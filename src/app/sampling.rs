@@ -0,0 +1,93 @@
+//! Event sampling under extreme load
+//!
+//! Above a configured event rate only one in N events is shown and
+//! persisted, so a busy gateway can't drown the UI and the database. The
+//! counters keep seeing every event.
+
+use std::time::{Duration, Instant};
+
+/// Length of the window the event rate is measured over
+const RATE_WINDOW: Duration = Duration::from_secs(1);
+
+/// Sampling state, as shown in the UI
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SamplingStatus {
+    /// One in this many events is kept
+    pub one_in: u64,
+    /// Events seen since sampling started, kept or not
+    pub seen: u64,
+}
+
+/// Decides which events are kept once the rate exceeds a threshold
+#[derive(Debug)]
+pub struct EventSampler {
+    /// Events per second that switch sampling on (0 = disabled)
+    threshold: u64,
+    one_in: u64,
+    window_start: Option<Instant>,
+    window_count: u64,
+    sampling: bool,
+    /// Events seen since sampling started
+    seen: u64,
+}
+
+impl EventSampler {
+    pub fn new(threshold: u64, one_in: u64) -> Self {
+        Self {
+            threshold,
+            one_in: one_in.max(1),
+            window_start: None,
+            window_count: 0,
+            sampling: false,
+            seen: 0,
+        }
+    }
+
+    /// Count an event arriving at `now` and decide whether to keep it
+    pub fn admit(&mut self, now: Instant) -> bool {
+        if self.threshold == 0 {
+            return true;
+        }
+
+        let start = *self.window_start.get_or_insert(now);
+        let elapsed = now.saturating_duration_since(start);
+        if elapsed >= RATE_WINDOW {
+            // The last window decides, leaving sampling once the rate drops
+            let rate = self.window_count as f64 / elapsed.as_secs_f64();
+            if self.sampling && rate <= self.threshold as f64 {
+                tracing::info!("Event rate back to {:.0}/s, sampling off", rate);
+                self.sampling = false;
+            }
+            self.window_start = Some(now);
+            self.window_count = 0;
+        }
+        self.window_count += 1;
+
+        // React within the window instead of waiting for it to end
+        if !self.sampling && self.window_count > self.threshold {
+            tracing::warn!("Over {} events/s, keeping 1 in {}", self.threshold, self.one_in);
+            self.sampling = true;
+            self.seen = 0;
+        }
+        if !self.sampling {
+            return true;
+        }
+
+        self.seen += 1;
+        (self.seen - 1).is_multiple_of(self.one_in)
+    }
+
+    /// Current sampling state, None at full fidelity
+    pub fn status(&self) -> Option<SamplingStatus> {
+        self.sampling.then_some(SamplingStatus {
+            one_in: self.one_in,
+            seen: self.seen,
+        })
+    }
+}
+
+impl Default for EventSampler {
+    fn default() -> Self {
+        Self::new(0, 1)
+    }
+}
@@ -0,0 +1,272 @@
+//! Maintenance and reports run on a schedule
+//!
+//! Tasks are defined in the config file: purging old events from the
+//! database and compacting it, writing rules reports and refreshing the
+//! blocklists read by `lists` rules, so a long-running console looks after
+//! itself. When each task last ran is kept in the state directory, a
+//! restart neither runs a task early nor forgets one that was missed while
+//! the TUI was down.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, Local, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::process::Command;
+
+use super::export::export_rules_report;
+use super::hooks::http_request;
+use super::state::AppState;
+use crate::config::{ScheduledTask, TaskKind};
+use crate::db::Database;
+use crate::models::report::ReportFormat;
+use crate::utils::Formats;
+
+/// How often the schedule is checked
+const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// How long a blocklist download or command may take
+const FETCH_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Outcome of the last run of a task
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LastRun {
+    pub time: DateTime<Utc>,
+    pub result: Result<String, String>,
+}
+
+/// A task as shown in the settings
+#[derive(Debug, Clone)]
+pub struct TaskStatus {
+    pub name: String,
+    pub task: TaskKind,
+    /// e.g. "every 24h at 03:00"
+    pub schedule: String,
+    pub last_run: Option<LastRun>,
+    pub next_run: DateTime<Utc>,
+}
+
+/// When a task runs next, given its last run
+///
+/// Tasks with an hour run in the first check within that hour once
+/// `every_hours` have passed, so a nightly task stays at night. A task
+/// missed while nothing was running is caught up right away.
+pub fn next_run(task: &ScheduledTask, last_run: Option<DateTime<Utc>>, now: DateTime<Utc>) -> DateTime<Utc> {
+    let every = chrono::Duration::hours(task.every_hours.max(1) as i64);
+    let Some(hour) = task.at_hour.filter(|hour| *hour < 24) else {
+        return last_run.map(|last| last + every).unwrap_or(now);
+    };
+
+    // The slot may start up to an hour early, runs start somewhere within it
+    let earliest = match last_run {
+        Some(last) => last + every - chrono::Duration::hours(1),
+        None => now - chrono::Duration::hours(1),
+    }
+    .with_timezone(&Local);
+    let mut day = earliest.date_naive();
+    loop {
+        let slot = day
+            .and_hms_opt(hour, 0, 0)
+            .and_then(|start| Local.from_local_datetime(&start).earliest());
+        if let Some(slot) = slot.filter(|slot| *slot >= earliest) {
+            return slot.with_timezone(&Utc);
+        }
+        day = day.succ_opt().unwrap_or(day);
+    }
+}
+
+fn describe(task: &ScheduledTask) -> String {
+    match task.at_hour {
+        Some(hour) => format!("every {}h at {:02}:00", task.every_hours.max(1), hour),
+        None => format!("every {}h", task.every_hours.max(1)),
+    }
+}
+
+/// Configured tasks and when they last ran
+#[derive(Debug, Default)]
+pub struct Scheduler {
+    tasks: Vec<ScheduledTask>,
+    last_runs: HashMap<String, LastRun>,
+    /// Where the last runs are kept, None to keep them in memory only
+    path: Option<PathBuf>,
+}
+
+impl Scheduler {
+    /// Tasks without a name can't be told apart and are left out
+    pub fn new(tasks: Vec<ScheduledTask>) -> Self {
+        Self {
+            tasks: tasks.into_iter().filter(|task| !task.name.is_empty()).collect(),
+            last_runs: HashMap::new(),
+            path: None,
+        }
+    }
+
+    /// Keep the last runs in a file, loading the ones recorded there
+    pub fn with_state_file(mut self, path: PathBuf) -> Self {
+        self.last_runs = std::fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+        self.path = Some(path);
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tasks.is_empty()
+    }
+
+    /// Tasks whose next run has come
+    pub fn due(&self, now: DateTime<Utc>) -> Vec<ScheduledTask> {
+        self.tasks
+            .iter()
+            .filter(|task| next_run(task, self.last_runs.get(&task.name).map(|run| run.time), now) <= now)
+            .cloned()
+            .collect()
+    }
+
+    /// Remember a run, returns an error if it can't be kept
+    pub fn record(&mut self, name: &str, run: LastRun) -> anyhow::Result<()> {
+        self.last_runs.insert(name.to_string(), run);
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, serde_json::to_string_pretty(&self.last_runs)?)?;
+        Ok(())
+    }
+
+    pub fn status(&self, now: DateTime<Utc>) -> Vec<TaskStatus> {
+        self.tasks
+            .iter()
+            .map(|task| {
+                let last_run = self.last_runs.get(&task.name).cloned();
+                TaskStatus {
+                    name: task.name.clone(),
+                    task: task.task,
+                    schedule: describe(task),
+                    next_run: next_run(task, last_run.as_ref().map(|run| run.time), now),
+                    last_run,
+                }
+            })
+            .collect()
+    }
+}
+
+/// Run tasks as they come due, until the program ends
+pub async fn run(state: Arc<AppState>, mut scheduler: Scheduler, exports: PathBuf) {
+    let mut ticks = tokio::time::interval(CHECK_INTERVAL);
+    loop {
+        ticks.tick().await;
+        for task in scheduler.due(Utc::now()) {
+            let result = run_task(&task, &state, &exports).await;
+            match &result {
+                Ok(message) => tracing::info!("Scheduled task {}: {}", task.name, message),
+                Err(e) => tracing::warn!("Scheduled task {} failed: {}", task.name, e),
+            }
+            let run = LastRun { time: Utc::now(), result };
+            if let Err(e) = scheduler.record(&task.name, run) {
+                tracing::warn!("Cannot keep the last run of {}: {}", task.name, e);
+            }
+        }
+        *state.schedule.write().await = scheduler.status(Utc::now());
+    }
+}
+
+/// Run one task, returns what it did or why it failed
+pub async fn run_task(task: &ScheduledTask, state: &AppState, exports: &Path) -> Result<String, String> {
+    match task.task {
+        TaskKind::Purge => purge(&state.db, task.keep_days),
+        TaskKind::Vacuum => vacuum(&state.db),
+        TaskKind::Report => {
+            let nodes = state.nodes.read().await;
+            let mut written = 0;
+            for node in nodes.connected_nodes() {
+                export_rules_report(exports, &node.addr, &node.rules, ReportFormat::Markdown)
+                    .map_err(|e| e.to_string())?;
+                written += 1;
+            }
+            Ok(format!("{} reports written to {}", written, exports.display()))
+        }
+        TaskKind::Blocklist => refresh_blocklist(task).await,
+    }
+}
+
+/// Download a blocklist and replace the file at the task's path with it
+pub async fn refresh_blocklist(task: &ScheduledTask) -> Result<String, String> {
+    if task.path.is_empty() {
+        return Err("no path to write the list to".to_string());
+    }
+    let list = tokio::time::timeout(FETCH_TIMEOUT, fetch_list(task))
+        .await
+        .unwrap_or_else(|_| Err(format!("no list after {}s", FETCH_TIMEOUT.as_secs())))?;
+    let entries = String::from_utf8_lossy(&list)
+        .lines()
+        .filter(|line| !line.trim().is_empty() && !line.starts_with('#'))
+        .count();
+    if entries == 0 {
+        return Err("the list is empty, the previous one is kept".to_string());
+    }
+    // Replace in one step, the daemon may be reading the old list
+    let path = Path::new(&task.path);
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+    let partial = path.with_extension("partial");
+    std::fs::write(&partial, &list).map_err(|e| e.to_string())?;
+    std::fs::rename(&partial, path).map_err(|e| e.to_string())?;
+    Ok(format!("{} entries written to {}", entries, task.path))
+}
+
+/// Delete events and alerts older than `keep_days`
+pub fn purge(db: &Database, keep_days: u64) -> Result<String, String> {
+    let before = (Utc::now() - chrono::Duration::days(keep_days as i64)).to_rfc3339();
+    let connections = db.purge_connections_before(&before).map_err(|e| e.to_string())?;
+    let alerts = db.purge_alerts_before(&before).map_err(|e| e.to_string())?;
+    Ok(format!("{} events and {} alerts purged", connections, alerts))
+}
+
+/// Compact the database, returns how much smaller it got
+pub fn vacuum(db: &Database) -> Result<String, String> {
+    let before = db.size().map_err(|e| e.to_string())?;
+    db.vacuum().map_err(|e| e.to_string())?;
+    let after = db.size().map_err(|e| e.to_string())?;
+    let formats = Formats::default();
+    Ok(format!(
+        "{} freed, {} now",
+        formats.bytes(before.bytes.saturating_sub(after.bytes)),
+        formats.bytes(after.bytes)
+    ))
+}
+
+async fn fetch_list(task: &ScheduledTask) -> Result<Vec<u8>, String> {
+    if !task.command.is_empty() {
+        let output = Command::new("sh")
+            .arg("-c")
+            .arg(&task.command)
+            .stdin(Stdio::null())
+            .kill_on_drop(true)
+            .output()
+            .await
+            .map_err(|e| e.to_string())?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(match stderr.lines().next() {
+                Some(line) => format!("{}: {}", output.status, line),
+                None => output.status.to_string(),
+            });
+        }
+        return Ok(output.stdout);
+    }
+    if task.url.is_empty() {
+        return Err("neither a url nor a command to get the list from".to_string());
+    }
+    match http_request("GET", &task.url, "").await? {
+        (code, body) if (200..300).contains(&code) => Ok(body),
+        (code, _) => Err(format!("HTTP {}", code)),
+    }
+}
@@ -0,0 +1,153 @@
+//! Rule snapshots
+//!
+//! A temporary rule set, such as a quarantine, takes over a node for a
+//! while. The node's rules are saved in the database under a label first,
+//! so they outlive a restart, and are put back as they were once it's
+//! lifted. Rules added in between are left alone.
+
+use tokio::sync::mpsc;
+
+use super::state::{AppMessage, AppState};
+use crate::grpc::notifications::NotificationAction;
+use crate::models::{quarantine, Rule, RuleSnapshot};
+
+/// Label of the snapshot taken when quarantining a node
+pub const QUARANTINE: &str = "quarantine";
+
+/// Save the node's rules under `label`, then disable them and add `rules`.
+/// Returns how many rules were disabled.
+pub async fn replace_rules(
+    state: &AppState,
+    state_tx: &mpsc::Sender<AppMessage>,
+    node_addr: &str,
+    label: &str,
+    rules: Vec<Rule>,
+) -> Result<usize, String> {
+    let current = node_rules(state, node_addr).await?;
+    if state.db.select_rule_snapshot(node_addr, label).map_err(|e| e.to_string())?.is_some() {
+        return Err(format!("the rules of {} are saved for {} already", node_addr, label));
+    }
+    state.db.insert_rule_snapshot(node_addr, label, &current).map_err(|e| e.to_string())?;
+
+    let disabled: Vec<Rule> = current
+        .iter()
+        .filter(|r| r.enabled && !rules.iter().any(|rule| rule.name == r.name))
+        .map(|r| Rule { enabled: false, ..r.clone() })
+        .collect();
+    for rule in &disabled {
+        let _ = state_tx
+            .send(AppMessage::RuleToggled { node_addr: node_addr.to_string(), name: rule.name.clone(), enabled: false })
+            .await;
+    }
+    let count = disabled.len();
+    if count > 0 {
+        send(state_tx, node_addr, NotificationAction::DisableRules(disabled)).await;
+    }
+
+    for rule in rules {
+        let exists = current.iter().any(|r| r.name == rule.name);
+        put_rule(state_tx, node_addr, rule, exists).await;
+    }
+    Ok(count)
+}
+
+/// Put back the rules saved under `label`, deleting the rules `temporary`
+/// names that weren't there before
+pub async fn restore_rules(
+    state: &AppState,
+    state_tx: &mpsc::Sender<AppMessage>,
+    node_addr: &str,
+    label: &str,
+    temporary: impl Fn(&str) -> bool,
+) -> Result<RuleSnapshot, String> {
+    let current = node_rules(state, node_addr).await?;
+    let snapshot = state
+        .db
+        .select_rule_snapshot(node_addr, label)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("no rules of {} are saved for {}", node_addr, label))?;
+
+    for rule in current.iter().filter(|r| temporary(&r.name) && !snapshot.rules.iter().any(|s| s.name == r.name)) {
+        let _ = state_tx
+            .send(AppMessage::RuleDeleted { node_addr: node_addr.to_string(), name: rule.name.clone() })
+            .await;
+        send(state_tx, node_addr, NotificationAction::DeleteRule(rule.name.clone())).await;
+    }
+    for rule in &snapshot.rules {
+        match current.iter().find(|r| r.name == rule.name) {
+            Some(now) if same_rule(now, rule) => {}
+            now => put_rule(state_tx, node_addr, rule.clone(), now.is_some()).await,
+        }
+    }
+
+    state.db.delete_rule_snapshot(node_addr, label).map_err(|e| e.to_string())?;
+    Ok(snapshot)
+}
+
+/// Quarantine a node, see `models::quarantine`
+pub async fn quarantine(
+    state: &AppState,
+    state_tx: &mpsc::Sender<AppMessage>,
+    node_addr: &str,
+    allowlist: &[String],
+) -> Result<String, String> {
+    let rules = quarantine::rules(allowlist)?;
+    // DNS, DHCP and the deny rule come on top of the hosts
+    let hosts = rules.len() - 3;
+    let disabled = replace_rules(state, state_tx, node_addr, QUARANTINE, rules).await?;
+    let plural = |n: usize| if n == 1 { "" } else { "s" };
+    Ok(format!(
+        "{} rule{} disabled, only DNS, DHCP and {} management host{} are allowed until the quarantine is lifted.",
+        disabled,
+        plural(disabled),
+        hosts,
+        plural(hosts)
+    ))
+}
+
+/// Whether the node is quarantined: its rules are saved for it until lifted,
+/// whatever became of the quarantine rules meanwhile
+pub fn quarantined(state: &AppState, node_addr: &str) -> bool {
+    matches!(state.db.select_rule_snapshot(node_addr, QUARANTINE), Ok(Some(_)))
+}
+
+/// Lift the quarantine of a node, returning the snapshot its rules came from
+pub async fn lift_quarantine(
+    state: &AppState,
+    state_tx: &mpsc::Sender<AppMessage>,
+    node_addr: &str,
+) -> Result<RuleSnapshot, String> {
+    restore_rules(state, state_tx, node_addr, QUARANTINE, quarantine::is_quarantine_rule).await
+}
+
+async fn node_rules(state: &AppState, node_addr: &str) -> Result<Vec<Rule>, String> {
+    state
+        .nodes
+        .read()
+        .await
+        .get_node(node_addr)
+        .map(|node| node.rules.clone())
+        .ok_or_else(|| format!("node {} is gone", node_addr))
+}
+
+/// Rules aren't comparable as such, their saved form is
+fn same_rule(a: &Rule, b: &Rule) -> bool {
+    serde_json::to_value(a).ok() == serde_json::to_value(b).ok()
+}
+
+async fn put_rule(state_tx: &mpsc::Sender<AppMessage>, node_addr: &str, rule: Rule, exists: bool) {
+    let node_addr = node_addr.to_string();
+    let local = if exists {
+        AppMessage::RuleModified { node_addr: node_addr.clone(), rule: rule.clone() }
+    } else {
+        AppMessage::RuleAdded { node_addr: node_addr.clone(), rule: rule.clone() }
+    };
+    let _ = state_tx.send(local).await;
+    send(state_tx, &node_addr, NotificationAction::ChangeRule(rule)).await;
+}
+
+async fn send(state_tx: &mpsc::Sender<AppMessage>, node_addr: &str, action: NotificationAction) {
+    let _ = state_tx
+        .send(AppMessage::SendNotification { node_addr: node_addr.to_string(), action })
+        .await;
+}
@@ -1,17 +1,55 @@
 //! Application state management
 
-use std::collections::{HashMap, VecDeque};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Arc;
+use std::time::Duration;
 
 use tokio::sync::{broadcast, mpsc, oneshot, RwLock};
 
+use crate::app::answer::RemoteAnswer;
+use crate::app::dedup::EventCoalescer;
+use crate::app::bridge::{StreamEvent, STREAM_CAPACITY};
+use crate::app::hooks::{self, HookRun, RuleHooks};
+use crate::app::persistence::{DbOutage, PendingWrite, WriteBuffer};
+use crate::app::sampling::{EventSampler, SamplingStatus};
+use crate::app::scheduler::TaskStatus;
+use crate::app::updates::UpdateInfo;
+use crate::app::suggestions::{DenialTracker, RuleSuggestion};
+use crate::app::xref::XrefIndex;
+use crate::config::{InterceptionMode, PromptPolicy};
 use crate::db::Database;
-use crate::grpc::notifications::{NotificationAction, NotificationIdGenerator};
+use crate::grpc::notifications::{self, Delivery, NotificationAction, NotificationIdGenerator, RejectedChange, SentNotification};
 use crate::grpc::proto;
 use crate::models::{
-    Alert, Connection, Event, Node, NodeManager, Rule, Statistics, SysFirewall,
-    node::ClientConfig,
+    Alert, AlertData, AlertPriority, AlertWhat, Automations, AutomationRun, Connection, DnsEntry, DnsLog, Event, MissedPrompt, Node, NodeManager, PromptOutcome, Rule, RuleChange, RuleHitLog, Statistics, TrafficHistory,
+    SysFirewall,
+    automation::{self, alert_binary, deny_rule},
+    dns::MAX_DNS_ENTRIES,
+    node::{ask_deadline, AuthStatus, ClientConfig, DEFAULT_ASK_TIMEOUT},
 };
+use crate::utils::host::is_local_node;
+use crate::utils::process::ProcCache;
+use crate::utils::{NetworkState, RoutingTable};
+
+/// How long asks wait for a prompt unless configured otherwise
+pub const DEFAULT_PROMPT_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Missed prompts kept on screen; older ones stay in the database
+const MAX_MISSED_PROMPTS: i64 = 200;
+
+/// How long details read from /proc are used for a process
+const PROC_CACHE_TTL: Duration = Duration::from_secs(60);
+
+/// How long a routing table read is used for interface lookups
+const ROUTES_MAX_AGE: Duration = Duration::from_secs(30);
+
+/// How often coalesced events are checked for the end of their window
+const COALESCE_TICK: Duration = Duration::from_millis(25);
+
+/// How often rules are checked for an expiry the daemon has reached
+const EXPIRY_TICK: Duration = Duration::from_secs(1);
 
 /// Messages for state updates
 #[derive(Debug)]
@@ -20,6 +58,12 @@ pub enum AppMessage {
     NodeConnected {
         addr: String,
         config: ClientConfig,
+        auth: AuthStatus,
+        /// Label of the listener the node came in on
+        listener: Option<String>,
+    },
+    NodeAuthRejected {
+        addr: String,
     },
     NodeDisconnected {
         addr: String,
@@ -42,7 +86,7 @@ pub enum AppMessage {
     // Connection events
     ConnectionEvent {
         node_addr: String,
-        event: Event,
+        event: Box<Event>,
     },
     NewConnection {
         node_addr: String,
@@ -53,6 +97,11 @@ pub enum AppMessage {
         connection: Connection,
         response_tx: oneshot::Sender<Rule>,
     },
+    /// An ask was answered after `latency`
+    AskAnswered {
+        node_addr: String,
+        latency: Duration,
+    },
 
     // Rule events
     RuleAdded {
@@ -92,6 +141,11 @@ pub enum AppMessage {
     PromptResponse {
         rule: Rule,
     },
+    /// Cut every new connection of a node off, or restore its configuration
+    Panic {
+        node_addr: String,
+        engage: bool,
+    },
 }
 
 /// UI update signals
@@ -104,6 +158,12 @@ pub enum UiUpdateSignal {
     FirewallUpdated,
     AlertsUpdated,
     PromptReceived,
+    /// An answer from the command line for the prompt on screen
+    RemoteAnswer,
+    SuggestionReceived,
+    /// A daemon rejected a rule change, which was undone locally
+    ChangeRejected,
+    NetworkChanged,
     Redraw,
 }
 
@@ -111,19 +171,107 @@ pub enum UiUpdateSignal {
 pub struct PendingPrompt {
     pub connection: Connection,
     pub node_addr: String,
+    /// Row written ahead in the database, None if that failed
+    pub id: Option<i64>,
     pub response_tx: oneshot::Sender<Rule>,
 }
 
+impl PendingPrompt {
+    /// Same program, user and destination on the same node, so one answer fits both
+    pub fn is_identical(&self, node_addr: &str, connection: &Connection) -> bool {
+        let own = &self.connection;
+        self.node_addr == node_addr
+            && own.process_path == connection.process_path
+            && own.user_id == connection.user_id
+            && own.protocol == connection.protocol
+            && own.dst_host == connection.dst_host
+            && own.dst_ip == connection.dst_ip
+            && own.dst_port == connection.dst_port
+    }
+}
+
+/// An answer given for the next prompts identical to the one answered
+#[derive(Debug, Clone)]
+pub struct PromptBatch {
+    pub node_addr: String,
+    pub connection: Connection,
+    pub rule: Rule,
+    /// Prompts it still answers
+    pub remaining: usize,
+}
+
 /// Central application state
 pub struct AppState {
     pub nodes: RwLock<NodeManager>,
     pub connections: RwLock<VecDeque<Event>>,
     pub alerts: RwLock<VecDeque<Alert>>,
     pub pending_prompts: RwLock<VecDeque<PendingPrompt>>,
+    /// Answers from the command line waiting for the TUI
+    pub remote_answers: RwLock<VecDeque<RemoteAnswer>>,
+    pub suggestions: RwLock<VecDeque<RuleSuggestion>>,
+    /// Prompts that timed out or were lost, most recent first
+    pub missed_prompts: RwLock<Vec<MissedPrompt>>,
+    /// Network the host is on and the prompt policy it selects
+    pub network: RwLock<NetworkState>,
+    pub prompt_policy: RwLock<PromptPolicy>,
+    /// How long an ask waits for the prompt before the default answers
+    prompt_timeout: std::sync::Mutex<Duration>,
+    /// Whether asks are prompted for or answered straight away
+    interception: std::sync::Mutex<InterceptionMode>,
+    /// Maintenance mode: asks are answered with the default and events
+    /// are dropped instead of shown and persisted
+    maintenance: AtomicBool,
+    /// Events dropped since maintenance mode was turned on
+    maintenance_skipped: AtomicU64,
+    /// Nodes cut off with the panic button and their configuration before
+    panicked: std::sync::Mutex<HashMap<String, String>>,
+    /// Nodes an earlier run left in panic, not offered to lift yet
+    restored_panics: std::sync::Mutex<HashSet<String>>,
+    /// Thins out events shown and persisted under extreme load
+    sampler: std::sync::Mutex<EventSampler>,
+    /// Merges bursts of identical events before they are shown and persisted
+    coalescer: std::sync::Mutex<EventCoalescer>,
+    /// Commands and webhooks run for events of some rules
+    hooks: std::sync::Mutex<RuleHooks>,
+    /// Hook runs, most recent first
+    pub hook_log: Arc<RwLock<VecDeque<HookRun>>>,
+    /// Actions taken when alerts arrive
+    automations: std::sync::Mutex<Automations>,
+    /// Automation runs, most recent first
+    pub automation_log: RwLock<VecDeque<AutomationRun>>,
+    /// Scheduled tasks with their last and next runs
+    pub schedule: RwLock<Vec<TaskStatus>>,
+    /// Routes for the interface of events, None unless the daemon runs here
+    routes: Option<std::sync::Mutex<(RoutingTable, std::time::Instant)>>,
+    /// Processes read from the proc filesystem to fill in what local daemons
+    /// leave out, None when the daemon doesn't share it
+    procs: Option<std::sync::Mutex<ProcCache>>,
+    /// Addresses daemons can connect to, for the Nodes tab
+    pub listeners: RwLock<Vec<String>>,
     pub notification_channels: RwLock<HashMap<String, mpsc::Sender<proto::Notification>>>,
+    /// Notifications sent to daemons with their replies, most recent first
+    pub sent_notifications: RwLock<VecDeque<SentNotification>>,
+    /// Rules as they were before local changes not sent yet, by node and name
+    staged_rules: std::sync::Mutex<HashMap<(String, String), Option<Rule>>>,
+    /// Rule changes daemons rejected, waiting to be shown
+    pub rejected: RwLock<VecDeque<RejectedChange>>,
     pub notification_id_gen: NotificationIdGenerator,
     pub db: Database,
+    /// Writes buffered while the database is unavailable
+    writes: std::sync::Mutex<WriteBuffer>,
+    /// Related rules, events and alerts, to jump between tabs
+    pub xref: std::sync::Mutex<XrefIndex>,
+    /// Hostnames and the addresses they resolved to, per node
+    pub dns: std::sync::Mutex<DnsLog>,
+    /// Connections and drops per second of each node, from its statistics
+    pub traffic: std::sync::Mutex<TrafficHistory>,
+    /// How often each rule matched events, per node
+    pub rule_hits: std::sync::Mutex<RuleHitLog>,
     pub ui_update_tx: broadcast::Sender<UiUpdateSignal>,
+    /// Sanitized events for the dashboards on the event stream
+    pub stream_tx: broadcast::Sender<StreamEvent>,
+    /// Releases newer than the running one, None until one is found
+    pub update: std::sync::Mutex<Option<UpdateInfo>>,
 
     // Configuration
     pub max_connections: usize,
@@ -137,30 +285,242 @@ impl AppState {
             connections: RwLock::new(VecDeque::with_capacity(1000)),
             alerts: RwLock::new(VecDeque::with_capacity(500)),
             pending_prompts: RwLock::new(VecDeque::new()),
+            remote_answers: RwLock::new(VecDeque::new()),
+            suggestions: RwLock::new(VecDeque::new()),
+            missed_prompts: RwLock::new(Vec::new()),
+            network: RwLock::new(NetworkState::default()),
+            prompt_policy: RwLock::new(PromptPolicy::default()),
+            prompt_timeout: std::sync::Mutex::new(DEFAULT_PROMPT_TIMEOUT),
+            interception: std::sync::Mutex::new(InterceptionMode::default()),
+            maintenance: AtomicBool::new(false),
+            maintenance_skipped: AtomicU64::new(0),
+            panicked: std::sync::Mutex::new(HashMap::new()),
+            restored_panics: std::sync::Mutex::new(HashSet::new()),
+            sampler: std::sync::Mutex::new(EventSampler::default()),
+            coalescer: std::sync::Mutex::new(EventCoalescer::default()),
+            hooks: std::sync::Mutex::new(RuleHooks::default()),
+            hook_log: Arc::new(RwLock::new(VecDeque::new())),
+            automations: std::sync::Mutex::new(Automations::default()),
+            automation_log: RwLock::new(VecDeque::new()),
+            schedule: RwLock::new(Vec::new()),
+            routes: None,
+            procs: None,
+            listeners: RwLock::new(Vec::new()),
             notification_channels: RwLock::new(HashMap::new()),
+            sent_notifications: RwLock::new(VecDeque::new()),
+            staged_rules: std::sync::Mutex::new(HashMap::new()),
+            rejected: RwLock::new(VecDeque::new()),
             notification_id_gen: NotificationIdGenerator::new(),
             db,
+            writes: std::sync::Mutex::new(WriteBuffer::default()),
+            xref: std::sync::Mutex::new(XrefIndex::default()),
+            dns: std::sync::Mutex::new(DnsLog::default()),
+            traffic: std::sync::Mutex::new(TrafficHistory::default()),
+            rule_hits: std::sync::Mutex::new(RuleHitLog::default()),
             ui_update_tx,
+            stream_tx: broadcast::channel(STREAM_CAPACITY).0,
+            update: std::sync::Mutex::new(None),
             max_connections: 1000,
             max_alerts: 500,
         }
     }
 
+    /// Sample events once they arrive faster than the sampler's threshold
+    pub fn with_sampler(mut self, sampler: EventSampler) -> Self {
+        self.sampler = std::sync::Mutex::new(sampler);
+        self
+    }
+
+    /// How many connections and alerts are kept in memory
+    pub fn with_limits(mut self, max_connections: usize, max_alerts: usize) -> Self {
+        self.max_connections = max_connections;
+        self.max_alerts = max_alerts;
+        self
+    }
+
+    /// Answer unanswered prompts this way until a network profile applies
+    pub fn with_prompt_policy(mut self, policy: PromptPolicy) -> Self {
+        self.prompt_policy = RwLock::new(policy);
+        self
+    }
+
+    /// How long asks wait for a prompt to be answered
+    pub fn with_prompt_timeout(mut self, timeout: Duration) -> Self {
+        self.prompt_timeout = std::sync::Mutex::new(timeout);
+        self
+    }
+
+    /// Start out prompting for asks, or answering them all the same way
+    pub fn with_interception(mut self, mode: InterceptionMode) -> Self {
+        self.interception = std::sync::Mutex::new(mode);
+        self
+    }
+
+    /// Merge identical events arriving within `window` into one with a count
+    pub fn with_dedup(mut self, window: Duration) -> Self {
+        self.coalescer = std::sync::Mutex::new(EventCoalescer::new(window));
+        self
+    }
+
+    /// Run commands and webhooks for events of the hooked rules
+    pub fn with_hooks(mut self, hooks: RuleHooks) -> Self {
+        self.hooks = std::sync::Mutex::new(hooks);
+        self
+    }
+
+    /// Act on alerts with these automations
+    pub fn with_automations(mut self, automations: Automations) -> Self {
+        self.automations = std::sync::Mutex::new(automations);
+        self
+    }
+
+    pub fn automations(&self) -> Automations {
+        self.automations.lock().unwrap().clone()
+    }
+
+    pub fn set_automations(&self, automations: Automations) {
+        *self.automations.lock().unwrap() = automations;
+    }
+
+    /// Look up the interface of events in this host's routing table,
+    /// which only tells something about a daemon on the same host
+    pub fn with_interface_lookup(mut self, enabled: bool) -> Self {
+        self.routes = enabled.then(|| std::sync::Mutex::new((RoutingTable::read(), std::time::Instant::now())));
+        self
+    }
+
+    /// Fill process details missing from local nodes' events from this /proc
+    pub fn with_proc_fallback(mut self, root: Option<PathBuf>) -> Self {
+        self.procs = root.map(|root| std::sync::Mutex::new(ProcCache::new(&root, PROC_CACHE_TTL)));
+        self
+    }
+
+    /// Sampling state, None while every event is kept
+    pub fn sampling_status(&self) -> Option<SamplingStatus> {
+        self.sampler.lock().unwrap().status()
+    }
+
     pub fn notify_ui(&self, signal: UiUpdateSignal) {
         let _ = self.ui_update_tx.send(signal);
     }
 
+    pub fn in_maintenance(&self) -> bool {
+        self.maintenance.load(Ordering::Relaxed)
+    }
+
+    /// Turn maintenance mode on or off, restarting the skipped count
+    pub fn set_maintenance(&self, enabled: bool) {
+        if self.maintenance.swap(enabled, Ordering::Relaxed) != enabled {
+            tracing::info!("Maintenance mode {}", if enabled { "on" } else { "off" });
+            self.maintenance_skipped.store(0, Ordering::Relaxed);
+        }
+        self.notify_ui(UiUpdateSignal::Redraw);
+    }
+
+    /// Nodes cut off with the panic button
+    pub fn panicked_nodes(&self) -> Vec<String> {
+        let mut nodes: Vec<_> = self.panicked.lock().unwrap().keys().cloned().collect();
+        nodes.sort();
+        nodes
+    }
+
+    pub fn in_panic(&self, node_addr: &str) -> bool {
+        self.panicked.lock().unwrap().contains_key(node_addr)
+    }
+
+    /// Have a node deny every new connection until resumed, then send back
+    /// the configuration it had before
+    pub async fn set_panic(&self, node_addr: &str, engage: bool) {
+        let mut nodes = self.nodes.write().await;
+        let Some(node) = nodes.get_node_mut(node_addr) else {
+            return;
+        };
+        let config = if engage {
+            if self.in_panic(node_addr) {
+                return;
+            }
+            let Some(lockdown) = node.lockdown_config() else {
+                tracing::warn!("Daemon config of {} can't be parsed", node_addr);
+                return;
+            };
+            let previous = std::mem::replace(&mut node.config, lockdown.clone());
+            // Kept on disk too, a restart must not lose the way back
+            if let Err(e) = self.db.insert_panic(node_addr, &previous) {
+                tracing::error!("Failed to store the configuration before the panic: {}", e);
+            }
+            self.panicked.lock().unwrap().insert(node_addr.to_string(), previous);
+            lockdown
+        } else {
+            let Some(previous) = self.panicked.lock().unwrap().remove(node_addr) else {
+                return;
+            };
+            if let Err(e) = self.db.delete_panic(node_addr) {
+                tracing::error!("Failed to drop the configuration before the panic: {}", e);
+            }
+            self.restored_panics.lock().unwrap().remove(node_addr);
+            node.config = previous.clone();
+            previous
+        };
+        drop(nodes);
+        tracing::warn!("Panic mode {} on {}", if engage { "engaged" } else { "lifted" }, node_addr);
+        self.send_notification(node_addr, NotificationAction::ChangeConfig(config)).await;
+        self.notify_ui(UiUpdateSignal::NodeChanged);
+    }
+
+    pub fn prompt_timeout(&self) -> Duration {
+        *self.prompt_timeout.lock().unwrap()
+    }
+
+    /// Change how unanswered prompts are handled while running; a network
+    /// profile in effect keeps its action and duration
+    pub async fn set_prompt_defaults(&self, policy: PromptPolicy, timeout: Duration) {
+        *self.prompt_timeout.lock().unwrap() = timeout;
+        let mut current = self.prompt_policy.write().await;
+        if current.profile.is_none() {
+            *current = policy;
+        }
+        tracing::info!("Prompts default to {} {} after {}s", current.default_action, current.default_duration, timeout.as_secs());
+        drop(current);
+        self.notify_ui(UiUpdateSignal::Redraw);
+    }
+
+    pub fn interception(&self) -> InterceptionMode {
+        *self.interception.lock().unwrap()
+    }
+
+    pub fn set_interception(&self, mode: InterceptionMode) {
+        *self.interception.lock().unwrap() = mode;
+        tracing::info!("Interception mode {}", mode.label().to_lowercase());
+        self.notify_ui(UiUpdateSignal::Redraw);
+    }
+
+    /// Number of events dropped during the current maintenance window
+    pub fn maintenance_skipped(&self) -> u64 {
+        self.maintenance_skipped.load(Ordering::Relaxed)
+    }
+
+    /// Drop `count` events if in maintenance mode, returns whether they were dropped
+    pub fn skip_events(&self, count: usize) -> bool {
+        if !self.in_maintenance() {
+            return false;
+        }
+        self.maintenance_skipped.fetch_add(count as u64, Ordering::Relaxed);
+        true
+    }
+
     pub async fn add_connection(&self, event: Event) {
         let mut connections = self.connections.write().await;
         connections.push_front(event.clone());
         while connections.len() > self.max_connections {
             connections.pop_back();
         }
+        self.xref.lock().unwrap().add_event(&event);
+        if self.stream_tx.receiver_count() > 0 {
+            let _ = self.stream_tx.send(StreamEvent::connection(&event));
+        }
 
         // Persist to database
-        if let Err(e) = self.db.insert_connection(&event) {
-            tracing::error!("Failed to persist connection: {}", e);
-        }
+        self.writes.lock().unwrap().write(&self.db, PendingWrite::Connection(event));
     }
 
     pub async fn add_alert(&self, alert: Alert) {
@@ -169,34 +529,596 @@ impl AppState {
         while alerts.len() > self.max_alerts {
             alerts.pop_back();
         }
+        self.xref.lock().unwrap().add_alert(&alert);
+        if self.stream_tx.receiver_count() > 0 {
+            let _ = self.stream_tx.send(StreamEvent::alert(&alert));
+        }
 
         // Persist to database
-        if let Err(e) = self.db.insert_alert(&alert) {
-            tracing::error!("Failed to persist alert: {}", e);
+        self.writes.lock().unwrap().write(&self.db, PendingWrite::Alert(alert));
+    }
+
+    /// Run the automations matching an alert, logging what each did.
+    /// Returns true if rules were added
+    pub async fn run_automations(&self, alert: &mut Alert) -> bool {
+        let matching: Vec<_> = self.automations.lock().unwrap().matching(alert).cloned().collect();
+        let mut added = false;
+        for automation in matching {
+            let mut actions = Vec::new();
+            if automation.deny_binary {
+                match alert_binary(alert).map(deny_rule) {
+                    Some(rule) => {
+                        let mut nodes = self.nodes.write().await;
+                        match nodes.get_node_mut(&alert.node) {
+                            Some(node) if node.rules.iter().any(|r| r.name == rule.name) => {
+                                actions.push(format!("rule {} already there", rule.name));
+                            }
+                            Some(node) => {
+                                node.rules.push(rule.clone());
+                                drop(nodes);
+                                self.stage_rule(&alert.node, &rule.name, None);
+                                if let Err(e) = self.db.insert_rule(&alert.node, &rule) {
+                                    tracing::error!("Failed to persist rule: {}", e);
+                                }
+                                actions.push(format!("added rule {}", rule.name));
+                                self.send_notification(&alert.node, NotificationAction::ChangeRule(rule)).await;
+                                added = true;
+                            }
+                            None => actions.push("node unknown, nothing denied".to_string()),
+                        }
+                    }
+                    None => actions.push("no binary to deny".to_string()),
+                }
+            }
+            if automation.acknowledge {
+                alert.acknowledged = true;
+                actions.push("acknowledged".to_string());
+            }
+            tracing::info!("Automation {} on {}: {}", automation.name, alert.node, actions.join(", "));
+            let run = AutomationRun {
+                time: chrono::Utc::now(),
+                automation: automation.name,
+                node: alert.node.clone(),
+                alert: alert.text(),
+                actions,
+            };
+            automation::record(&mut *self.automation_log.write().await, run);
+        }
+        added
+    }
+
+    /// Keep the hostname an event's destination resolved from
+    pub fn record_dns(&self, node_addr: &str, event: &Event) {
+        let Some(entry) = DnsEntry::of(node_addr, event, chrono::Utc::now()) else {
+            return;
+        };
+        if let Some(entry) = self.dns.lock().unwrap().record(entry) {
+            self.writes.lock().unwrap().write(&self.db, PendingWrite::Dns(entry));
+        }
+    }
+
+    /// Count the event towards the rule it matched
+    pub fn record_rule_hit(&self, node_addr: &str, event: &Event) {
+        let Some(rule) = &event.rule else {
+            return;
+        };
+        let at = chrono::DateTime::parse_from_rfc3339(&event.time)
+            .map(|t| t.with_timezone(&chrono::Utc))
+            .unwrap_or_else(|_| chrono::Utc::now());
+        let stored = self.rule_hits.lock().unwrap().record(node_addr, &rule.name, event.count.max(1), at);
+        if let Some(hits) = stored {
+            let write = PendingWrite::RuleHits { node: node_addr.to_string(), rule: rule.clone(), hits };
+            self.writes.lock().unwrap().write(&self.db, write);
+        }
+    }
+
+    /// Buffer writes from now on, e.g. when the database couldn't be opened
+    pub fn db_failed(&self, error: String) {
+        self.writes.lock().unwrap().fail(error);
+        self.notify_ui(UiUpdateSignal::Redraw);
+    }
+
+    /// Why writes are buffered, None while the database works
+    pub fn db_outage(&self) -> Option<DbOutage> {
+        self.writes.lock().unwrap().outage().cloned()
+    }
+
+    /// Try the database again and write out the buffer, returns whether it works
+    pub fn retry_db(&self) -> bool {
+        self.writes.lock().unwrap().retry(&self.db)
+    }
+
+    /// Mark the alert with this id and timestamp as acknowledged
+    pub async fn acknowledge_alert(&self, id: u64, timestamp: chrono::DateTime<chrono::Utc>) {
+        let mut alerts = self.alerts.write().await;
+        if let Some(alert) = alerts.iter_mut().find(|a| a.id == id && a.timestamp == timestamp) {
+            alert.acknowledged = true;
+            if let Err(e) = self.db.ack_alert(&alert.node, &alert.timestamp) {
+                tracing::error!("Failed to acknowledge alert: {}", e);
+            }
+        }
+        drop(alerts);
+        self.notify_ui(UiUpdateSignal::AlertsUpdated);
+    }
+
+    /// Mark every alert as acknowledged
+    pub async fn acknowledge_all_alerts(&self) {
+        let mut alerts = self.alerts.write().await;
+        for alert in alerts.iter_mut() {
+            alert.acknowledged = true;
+        }
+        drop(alerts);
+        if let Err(e) = self.db.ack_all_alerts() {
+            tracing::error!("Failed to acknowledge alerts: {}", e);
+        }
+        self.notify_ui(UiUpdateSignal::AlertsUpdated);
+    }
+
+    /// Number of high-priority alerts not yet acknowledged, for drawing:
+    /// none are counted while the alerts are being written
+    pub fn unacknowledged_high_alerts(&self) -> usize {
+        self.alerts
+            .try_read()
+            .map(|alerts| {
+                alerts
+                    .iter()
+                    .filter(|a| a.priority == AlertPriority::High && !a.acknowledged)
+                    .count()
+            })
+            .unwrap_or(0)
+    }
+
+    /// Take in an event from a node: it always counts towards suggestions,
+    /// but may be sampled out of the list and the database
+    async fn ingest(&self, denials: &mut DenialTracker, node_addr: &str, mut event: Event) {
+        event.node = node_addr.to_string();
+        self.add_interface(node_addr, &mut event).await;
+        if let Some(procs) = self.procs.as_ref().filter(|_| is_local_node(node_addr)) {
+            procs.lock().unwrap().enrich(&mut event.connection, std::time::Instant::now());
+        }
+        self.record_dns(node_addr, &event);
+        self.record_rule_hit(node_addr, &event);
+        self.track_denial(denials, node_addr, &event).await;
+        self.run_hooks(node_addr, &event);
+        let event = self.coalescer.lock().unwrap().push(node_addr, event, std::time::Instant::now());
+        if let Some(event) = event {
+            if self.admit_sampled() {
+                self.add_connection(event).await;
+            }
         }
     }
 
+    /// Forget rules whose time is up, on one node or all of them. Returns
+    /// how many were dropped.
+    ///
+    /// Newer daemons report when they delete a temporary rule. For the
+    /// others the expiry is worked out from the duration, and connected
+    /// nodes are asked to delete the rule in case it is still there.
+    pub async fn drop_expired_rules(&self, node_addr: Option<&str>, now: chrono::DateTime<chrono::Utc>) -> usize {
+        let mut expired = Vec::new();
+        let mut nodes = self.nodes.write().await;
+        for node in nodes.nodes.values_mut().filter(|node| node_addr.is_none_or(|addr| node.addr == addr)) {
+            if !node.rules.iter().any(|rule| rule.is_expired(now)) {
+                continue;
+            }
+            node.rules.retain(|rule| {
+                if rule.is_expired(now) {
+                    expired.push((node.addr.clone(), rule.name.clone(), rule.expiry_estimated()));
+                }
+                !rule.is_expired(now)
+            });
+        }
+        drop(nodes);
+
+        for (addr, name, estimated) in &expired {
+            tracing::info!("Rule {} on {} expired", name, addr);
+            if let Err(e) = self.db.delete_rule(addr, name) {
+                tracing::error!("Failed to delete expired rule: {}", e);
+            }
+            if *estimated && self.notification_channels.read().await.contains_key(addr) {
+                self.send_notification(addr, NotificationAction::DeleteRule(name.clone())).await;
+            }
+        }
+        expired.len()
+    }
+
+    /// Store the coalesced events whose window has ended, returns whether there were any
+    pub async fn flush_coalesced(&self) -> bool {
+        let events = self.coalescer.lock().unwrap().flush(std::time::Instant::now());
+        self.store_coalesced(events).await
+    }
+
+    /// Store every event still held back, before shutting down
+    pub async fn drain_coalesced(&self) {
+        let events = self.coalescer.lock().unwrap().drain();
+        self.store_coalesced(events).await;
+    }
+
+    async fn store_coalesced(&self, events: Vec<Event>) -> bool {
+        let flushed = !events.is_empty();
+        for event in events {
+            if self.admit_sampled() {
+                self.add_connection(event).await;
+            }
+        }
+        flushed
+    }
+
+    /// Whether the next event is kept by the sampler
+    fn admit_sampled(&self) -> bool {
+        self.sampler.lock().unwrap().admit(std::time::Instant::now())
+    }
+
+    /// Count a connection event towards rule suggestions
+    async fn track_denial(&self, denials: &mut DenialTracker, node_addr: &str, event: &Event) {
+        if let Some(suggestion) = denials.record(node_addr, event) {
+            tracing::info!("Suggesting rule: {}", suggestion.message());
+            self.suggestions.write().await.push_back(suggestion);
+            self.notify_ui(UiUpdateSignal::SuggestionReceived);
+        }
+    }
+
+    /// Fill in and count the interface an event's source address is on,
+    /// and fill in the network of the address
+    async fn add_interface(&self, node_addr: &str, event: &mut Event) {
+        let connection = &mut event.connection;
+        if let Some(routes) = &self.routes {
+            let mut routes = routes.lock().unwrap();
+            if routes.1.elapsed() >= ROUTES_MAX_AGE {
+                *routes = (RoutingTable::read(), std::time::Instant::now());
+            }
+            if connection.interface.is_empty() {
+                connection.interface = routes.0.interface(&connection.src_ip).unwrap_or_default().to_string();
+            }
+            if connection.src_network.is_empty() {
+                connection.src_network = routes.0.network(&connection.src_ip).unwrap_or_default();
+            }
+        }
+        if connection.interface.is_empty() {
+            return;
+        }
+        if let Some(node) = self.nodes.write().await.get_node_mut(node_addr) {
+            *node.by_interface.entry(connection.interface.clone()).or_default() += 1;
+        }
+    }
+
+    /// Start the hooks due for an event, their runs land in the log
+    fn run_hooks(&self, node_addr: &str, event: &Event) {
+        let mut hooks = self.hooks.lock().unwrap();
+        if hooks.is_empty() {
+            return;
+        }
+        for trigger in hooks.due(node_addr, event, std::time::Instant::now()) {
+            let log = self.hook_log.clone();
+            tokio::spawn(async move {
+                let runs = trigger.run().await;
+                hooks::record(&mut *log.write().await, runs);
+            });
+        }
+    }
+
+    /// Switch to the prompt policy of a newly detected network
+    pub async fn set_network(&self, network: NetworkState, policy: PromptPolicy) {
+        tracing::info!(
+            "Network changed to {} (profile: {})",
+            network.label(),
+            policy.profile.as_deref().unwrap_or("default")
+        );
+        *self.network.write().await = network;
+        *self.prompt_policy.write().await = policy;
+        self.notify_ui(UiUpdateSignal::NetworkChanged);
+    }
+
+    /// Bring back the nodes archived by previous runs
+    pub async fn restore_archived_nodes(&self) {
+        let archived = match self.db.select_archived_nodes() {
+            Ok(archived) => archived,
+            Err(e) => {
+                tracing::error!("Failed to load archived nodes: {}", e);
+                return;
+            }
+        };
+        let mut nodes = self.nodes.write().await;
+        for node in archived {
+            nodes.restore_archived(node);
+        }
+    }
+
+    /// Bring back the nodes previous runs left in panic, with the
+    /// configuration to restore when it is lifted
+    pub fn restore_panics(&self) {
+        match self.db.select_panics() {
+            Ok(panics) => {
+                if !panics.is_empty() {
+                    tracing::warn!("Panic mode is still on for {} nodes", panics.len());
+                }
+                self.restored_panics.lock().unwrap().extend(panics.keys().cloned());
+                self.panicked.lock().unwrap().extend(panics);
+            }
+            Err(e) => tracing::error!("Failed to load nodes in panic: {}", e),
+        }
+    }
+
+    /// A connected node an earlier run left in panic, with its name, to
+    /// offer lifting the panic once
+    pub async fn take_restored_panic(&self) -> Option<(String, String)> {
+        let nodes = self.nodes.read().await;
+        let mut restored = self.restored_panics.lock().unwrap();
+        let node = restored
+            .iter()
+            .filter_map(|addr| nodes.get_node(addr))
+            .find(|node| !node.archived)?;
+        restored.remove(&node.addr);
+        Some((node.addr.clone(), node.display_name().to_string()))
+    }
+
+    /// Fill the lists with the most recent events and alerts of previous
+    /// runs, so the history shows before anything new comes in
+    pub async fn restore_history(&self) {
+        match self.db.select_connections(self.max_connections as i64) {
+            Ok(events) => {
+                let mut connections = self.connections.write().await;
+                let mut xref = self.xref.lock().unwrap();
+                // Newest first, as they are listed
+                for mut event in events.into_iter().rev() {
+                    event.restored = true;
+                    xref.add_event(&event);
+                    connections.push_front(event);
+                }
+            }
+            Err(e) => tracing::error!("Failed to load stored connections: {}", e),
+        }
+        match self.db.select_alerts(self.max_alerts as i64) {
+            Ok(stored) => {
+                let mut alerts = self.alerts.write().await;
+                let mut xref = self.xref.lock().unwrap();
+                for mut alert in stored.into_iter().rev() {
+                    alert.restored = true;
+                    xref.add_alert(&alert);
+                    alerts.push_front(alert);
+                }
+            }
+            Err(e) => tracing::error!("Failed to load stored alerts: {}", e),
+        }
+    }
+
+    /// Bring back the DNS mappings seen by previous runs
+    pub fn restore_dns(&self) {
+        match self.db.select_dns(MAX_DNS_ENTRIES as i64) {
+            Ok(entries) => self.dns.lock().unwrap().load(entries),
+            Err(e) => tracing::error!("Failed to load DNS mappings: {}", e),
+        }
+    }
+
+    /// Bring back how often rules matched in previous runs
+    pub fn restore_rule_hits(&self) {
+        match self.db.select_rule_hits() {
+            Ok(hits) => self.rule_hits.lock().unwrap().load(hits),
+            Err(e) => tracing::error!("Failed to load rule hits: {}", e),
+        }
+    }
+
+    /// Mark prompts left pending by a crash as lost and load the missed ones
+    pub async fn recover_missed_prompts(&self) {
+        match self.db.mark_pending_prompts_lost() {
+            Ok(0) => {}
+            Ok(lost) => tracing::warn!("{} prompts were still pending when the last run ended", lost),
+            Err(e) => tracing::error!("Failed to recover pending prompts: {}", e),
+        }
+        self.reload_missed_prompts().await;
+    }
+
+    async fn reload_missed_prompts(&self) {
+        match self.db.select_missed_prompts(MAX_MISSED_PROMPTS) {
+            Ok(missed) => *self.missed_prompts.write().await = missed,
+            Err(e) => tracing::error!("Failed to load missed prompts: {}", e),
+        }
+    }
+
+    /// Record what became of a prompt written ahead
+    pub async fn set_prompt_outcome(&self, id: Option<i64>, outcome: PromptOutcome) {
+        let Some(id) = id else {
+            return;
+        };
+        if let Err(e) = self.db.set_prompt_outcome(id, outcome) {
+            tracing::error!("Failed to update prompt {}: {}", id, e);
+            return;
+        }
+        match outcome {
+            PromptOutcome::TimedOut => self.reload_missed_prompts().await,
+            PromptOutcome::Resolved => self.missed_prompts.write().await.retain(|p| p.id != id),
+            _ => {}
+        }
+    }
+
+    /// Next queued prompt to show, dropping those the daemon stopped waiting for
+    pub async fn next_prompt(&self) -> Option<PendingPrompt> {
+        loop {
+            let pending = self.pending_prompts.write().await.pop_front()?;
+            if !pending.response_tx.is_closed() {
+                return Some(pending);
+            }
+            self.set_prompt_outcome(pending.id, PromptOutcome::TimedOut).await;
+        }
+    }
+
+    /// Answer the queued prompts the batch covers, returns how many
+    pub async fn answer_batch(&self, batch: &mut PromptBatch) -> usize {
+        let mut prompts = self.pending_prompts.write().await;
+        let mut answered = Vec::new();
+        let mut index = 0;
+        while batch.remaining > answered.len() && index < prompts.len() {
+            if prompts[index].is_identical(&batch.node_addr, &batch.connection) {
+                answered.extend(prompts.remove(index));
+            } else {
+                index += 1;
+            }
+        }
+        drop(prompts);
+
+        batch.remaining -= answered.len();
+        let count = answered.len();
+        for pending in answered {
+            let _ = pending.response_tx.send(batch.rule.clone());
+            self.set_prompt_outcome(pending.id, PromptOutcome::Answered).await;
+        }
+        count
+    }
+
+    /// Forget an archived node and its snapshot
+    pub async fn forget_archived_node(&self, addr: &str) {
+        if !self.nodes.write().await.forget_archived(addr) {
+            return;
+        }
+        if let Err(e) = self.db.delete_archived_node(addr) {
+            tracing::error!("Failed to delete archived node: {}", e);
+        }
+        self.notify_ui(UiUpdateSignal::NodeChanged);
+    }
+
     pub async fn get_active_node(&self) -> Option<Node> {
         let nodes = self.nodes.read().await;
         nodes.active_node().cloned()
     }
 
+    /// Remember how a rule was before a local change, until the change is
+    /// sent. Further changes keep the state from before the first.
+    fn stage_rule(&self, node_addr: &str, name: &str, previous: Option<Rule>) {
+        self.staged_rules
+            .lock()
+            .unwrap()
+            .entry((node_addr.to_string(), name.to_string()))
+            .or_insert(previous);
+    }
+
+    /// Rules of a node changed locally and sent, but not confirmed by the daemon yet
+    pub async fn unconfirmed_rules(&self, node_addr: &str) -> HashSet<String> {
+        self.sent_notifications
+            .read()
+            .await
+            .iter()
+            .filter(|sent| sent.node_addr == node_addr && sent.delivery == Delivery::Pending)
+            .flat_map(|sent| sent.undo.iter().map(|(name, _)| name.clone()))
+            .collect()
+    }
+
+    /// Put back the rules of a change the daemon rejected, locally and in the database
+    async fn roll_back(&self, rejected: &RejectedChange) {
+        tracing::warn!("Rolling back rejected change: {}", rejected.message());
+        let addr = &rejected.node_addr;
+        let mut nodes = self.nodes.write().await;
+        let Some(node) = nodes.get_node_mut(addr) else {
+            return;
+        };
+        for (name, previous) in &rejected.undo {
+            match previous {
+                Some(rule) => match node.rules.iter_mut().find(|r| r.name == *name) {
+                    Some(existing) => *existing = rule.clone(),
+                    None => node.rules.push(rule.clone()),
+                },
+                None => node.rules.retain(|r| r.name != *name),
+            }
+        }
+        drop(nodes);
+
+        for (name, previous) in &rejected.undo {
+            let restored = self.db.delete_rule(addr, name).and_then(|_| match previous {
+                Some(rule) => self.db.insert_rule(addr, rule),
+                None => Ok(()),
+            });
+            if let Err(e) = restored {
+                tracing::error!("Failed to restore rule {}: {}", name, e);
+            }
+        }
+    }
+
+    /// Put back the rule the latest change on a node replaced, and send it
+    /// to the node. Returns what was undone.
+    pub async fn undo_rule_change(&self, node_addr: &str) -> Result<String, String> {
+        let version = self
+            .db
+            .last_rule_version(node_addr)
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| "no change left to undo".to_string())?;
+        // Marked first, a change failing to undo isn't tried again and again
+        self.db.mark_rule_version_undone(version.id).map_err(|e| e.to_string())?;
+
+        let name = &version.name;
+        let mut nodes = self.nodes.write().await;
+        let node = nodes
+            .get_node_mut(node_addr)
+            .ok_or_else(|| format!("node {} is gone", node_addr))?;
+        let current = node.rules.iter().find(|r| r.name == *name).cloned();
+        match &version.previous {
+            Some(rule) => match node.rules.iter_mut().find(|r| r.name == *name) {
+                Some(existing) => *existing = rule.clone(),
+                None => node.rules.push(rule.clone()),
+            },
+            None => node.rules.retain(|r| r.name != *name),
+        }
+        drop(nodes);
+
+        let restored = self.db.delete_rule(node_addr, name).and_then(|_| match &version.previous {
+            Some(rule) => self.db.insert_rule(node_addr, rule),
+            None => Ok(()),
+        });
+        if let Err(e) = restored {
+            tracing::error!("Failed to restore rule {}: {}", name, e);
+        }
+
+        self.stage_rule(node_addr, name, current);
+        let action = match &version.previous {
+            Some(rule) => NotificationAction::ChangeRule(rule.clone()),
+            None => NotificationAction::DeleteRule(name.clone()),
+        };
+        self.send(node_addr, action, true).await;
+        self.notify_ui(UiUpdateSignal::RulesUpdated);
+        Ok(format!("{} is back as it was before being {}", name, version.change))
+    }
+
     pub async fn send_notification(&self, node_addr: &str, action: NotificationAction) {
+        self.send(node_addr, action, false).await
+    }
+
+    /// Send an action, keeping the rules it changes as they were before;
+    /// `reverting` for the undoing of an earlier change
+    async fn send(&self, node_addr: &str, action: NotificationAction, reverting: bool) {
+        let id = self.notification_id_gen.next();
+        let mut sent = SentNotification::new(id, node_addr, &action);
+        let names = action.rule_names();
+        // The action goes out below, what it does to rules is worked out first
+        let (modified, added) = if reverting {
+            (RuleChange::Reverted, RuleChange::Reverted)
+        } else {
+            (action.rule_change(true), action.rule_change(false))
+        };
         let channels = self.notification_channels.read().await;
         if let Some(tx) = channels.get(node_addr) {
-            let notification = crate::grpc::notifications::create_notification(
-                self.notification_id_gen.next(),
-                node_addr,
-                "opensnitch-tui",
-                action,
-                None,
-            );
+            let notification = notifications::create_notification(id, node_addr, "opensnitch-tui", action, None);
             if let Err(e) = tx.send(notification).await {
                 tracing::error!("Failed to send notification to {}: {}", node_addr, e);
+                sent.delivery = Delivery::NotSent;
             }
         } else {
             tracing::warn!("No notification channel for node {}", node_addr);
+            sent.delivery = Delivery::NotSent;
+        }
+        drop(channels);
+        // Changes that didn't go out stay local, as for a node edited offline
+        for name in names {
+            let previous = self.staged_rules.lock().unwrap().remove(&(node_addr.to_string(), name.clone()));
+            let Some(previous) = previous else {
+                continue;
+            };
+            let change = if previous.is_some() { modified } else { added };
+            if let Err(e) = self.db.insert_rule_version(node_addr, &name, change, previous.as_ref()) {
+                tracing::error!("Failed to keep the previous version of rule {}: {}", name, e);
+            }
+            if sent.delivery == Delivery::Pending {
+                sent.undo.push((name, previous));
+            }
         }
+        notifications::record(&mut *self.sent_notifications.write().await, sent);
     }
 }
 
@@ -205,25 +1127,72 @@ pub async fn run_state_manager(
     state: Arc<AppState>,
     mut rx: mpsc::Receiver<AppMessage>,
     ui_update_tx: broadcast::Sender<UiUpdateSignal>,
+    mut denials: DenialTracker,
 ) {
     tracing::info!("State manager started");
 
-    while let Some(msg) = rx.recv().await {
+    let mut flush_ticks = tokio::time::interval(COALESCE_TICK);
+    flush_ticks.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    let mut expiry_ticks = tokio::time::interval(EXPIRY_TICK);
+    expiry_ticks.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    loop {
+        let msg = tokio::select! {
+            msg = rx.recv() => msg,
+            _ = flush_ticks.tick() => {
+                if state.flush_coalesced().await {
+                    let _ = ui_update_tx.send(UiUpdateSignal::ConnectionsUpdated);
+                }
+                continue;
+            }
+            _ = expiry_ticks.tick() => {
+                if state.drop_expired_rules(None, chrono::Utc::now()).await > 0 {
+                    let _ = ui_update_tx.send(UiUpdateSignal::RulesUpdated);
+                }
+                continue;
+            }
+        };
+        let Some(msg) = msg else {
+            state.drain_coalesced().await;
+            break;
+        };
         match msg {
-            AppMessage::NodeConnected { addr, config } => {
+            AppMessage::NodeConnected { addr, config, auth, listener } => {
                 tracing::info!("Node connected: {} ({})", config.name, addr);
                 let mut nodes = state.nodes.write().await;
-                nodes.add_node(&addr, config);
+                let node = nodes.add_node(&addr, config);
+                node.auth = auth;
+                node.listener = listener;
                 drop(nodes);
+
+                // Live again, the archived snapshot is stale
+                if let Err(e) = state.db.delete_archived_node(&addr) {
+                    tracing::error!("Failed to drop archived node: {}", e);
+                }
                 let _ = ui_update_tx.send(UiUpdateSignal::NodeChanged);
             }
 
+            AppMessage::NodeAuthRejected { addr } => {
+                let requests = state.nodes.write().await.record_rejection(&addr);
+                // Logged and shown on the 1st, 2nd, 4th, 8th... request, a flood doesn't flood the log
+                if requests.is_power_of_two() {
+                    tracing::warn!("Rejected {} unauthenticated requests so far, the last from {}", requests, addr);
+                    let _ = ui_update_tx.send(UiUpdateSignal::NodeChanged);
+                }
+            }
+
             AppMessage::NodeDisconnected { addr } => {
                 tracing::info!("Node disconnected: {}", addr);
                 let mut nodes = state.nodes.write().await;
                 nodes.remove_node(&addr);
+                let archived = nodes.get_node(&addr).cloned();
                 drop(nodes);
 
+                if let Some(node) = archived {
+                    if let Err(e) = state.db.archive_node(&node) {
+                        tracing::error!("Failed to archive node: {}", e);
+                    }
+                }
+
                 // Remove notification channel
                 let mut channels = state.notification_channels.write().await;
                 channels.remove(&addr);
@@ -233,12 +1202,15 @@ pub async fn run_state_manager(
             }
 
             AppMessage::StatsUpdate { node_addr, stats } => {
-                // Add events to connections list
-                let has_events = !stats.events.is_empty();
-                for event in &stats.events {
-                    state.add_connection(event.clone()).await;
+                // Add events to connections list, unless in maintenance
+                let has_events = !stats.events.is_empty() && !state.skip_events(stats.events.len());
+                if has_events {
+                    for event in &stats.events {
+                        state.ingest(&mut denials, &node_addr, event.clone()).await;
+                    }
                 }
 
+                state.traffic.lock().unwrap().record(&node_addr, &stats, chrono::Utc::now());
                 let mut nodes = state.nodes.write().await;
                 if let Some(node) = nodes.get_node_mut(&node_addr) {
                     node.update_stats(stats);
@@ -261,6 +1233,17 @@ pub async fn run_state_manager(
                     "Notification reply from {}: id={} code={} data={}",
                     node_addr, id, code, data
                 );
+                let mut sent = state.sent_notifications.write().await;
+                notifications::record_reply(&mut sent, &node_addr, id, Delivery::from_reply(code, data));
+                let rejected = notifications::take_rejected(&mut sent, &node_addr, id);
+                drop(sent);
+
+                if let Some(rejected) = rejected {
+                    state.roll_back(&rejected).await;
+                    state.rejected.write().await.push_back(rejected);
+                    let _ = ui_update_tx.send(UiUpdateSignal::RulesUpdated);
+                    let _ = ui_update_tx.send(UiUpdateSignal::ChangeRejected);
+                }
             }
 
             AppMessage::ConnectionPrompt { node_addr, connection, response_tx } => {
@@ -269,32 +1252,66 @@ pub async fn run_state_manager(
                     connection.process_name(),
                     connection.destination()
                 );
+                let id = state
+                    .db
+                    .insert_prompt(&node_addr, &connection)
+                    .map_err(|e| tracing::error!("Failed to write prompt ahead: {}", e))
+                    .ok();
                 let mut prompts = state.pending_prompts.write().await;
                 prompts.push_back(PendingPrompt {
                     connection,
                     node_addr,
+                    id,
                     response_tx,
                 });
                 drop(prompts);
                 let _ = ui_update_tx.send(UiUpdateSignal::PromptReceived);
             }
 
-            AppMessage::ConnectionEvent { node_addr: _, event } => {
-                state.add_connection(event).await;
+            AppMessage::AskAnswered { node_addr, latency } => {
+                let mut nodes = state.nodes.write().await;
+                if let Some(node) = nodes.get_node_mut(&node_addr) {
+                    if node.ask_latency.record(latency, ask_deadline(DEFAULT_ASK_TIMEOUT)) {
+                        tracing::warn!(
+                            "Answered an ask from {} after {:?}, the daemon may have applied its default",
+                            node_addr, latency
+                        );
+                    }
+                }
+            }
+
+            AppMessage::ConnectionEvent { .. } | AppMessage::NewConnection { .. } if state.skip_events(1) => {}
+
+            AppMessage::ConnectionEvent { node_addr, event } => {
+                state.ingest(&mut denials, &node_addr, *event).await;
                 let _ = ui_update_tx.send(UiUpdateSignal::ConnectionsUpdated);
             }
 
-            AppMessage::NewConnection { node_addr: _, connection } => {
+            AppMessage::NewConnection { node_addr, connection } => {
                 // Convert connection to event for monitoring
-                let event = Event::new(connection, None);
-                state.add_connection(event).await;
-                let _ = ui_update_tx.send(UiUpdateSignal::ConnectionsUpdated);
+                let mut event = Event::new(connection, None);
+                event.node = node_addr.clone();
+                state.record_dns(&node_addr, &event);
+                if state.admit_sampled() {
+                    state.add_connection(event).await;
+                    let _ = ui_update_tx.send(UiUpdateSignal::ConnectionsUpdated);
+                }
             }
 
             AppMessage::RuleAdded { node_addr, rule } => {
                 let mut nodes = state.nodes.write().await;
                 if let Some(node) = nodes.get_node_mut(&node_addr) {
-                    node.rules.push(rule.clone());
+                    // A rule saved under a taken name replaces it, as on the daemon
+                    match node.rules.iter_mut().find(|r| r.name == rule.name) {
+                        Some(existing) => {
+                            state.stage_rule(&node_addr, &rule.name, Some(existing.clone()));
+                            *existing = rule.clone();
+                        }
+                        None => {
+                            state.stage_rule(&node_addr, &rule.name, None);
+                            node.rules.push(rule.clone());
+                        }
+                    }
                 }
                 drop(nodes);
 
@@ -309,6 +1326,7 @@ pub async fn run_state_manager(
                 let mut nodes = state.nodes.write().await;
                 if let Some(node) = nodes.get_node_mut(&node_addr) {
                     if let Some(existing) = node.rules.iter_mut().find(|r| r.name == rule.name) {
+                        state.stage_rule(&node_addr, &rule.name, Some(existing.clone()));
                         *existing = rule.clone();
                     }
                 }
@@ -324,6 +1342,9 @@ pub async fn run_state_manager(
             AppMessage::RuleDeleted { node_addr, name } => {
                 let mut nodes = state.nodes.write().await;
                 if let Some(node) = nodes.get_node_mut(&node_addr) {
+                    if let Some(existing) = node.rules.iter().find(|r| r.name == name) {
+                        state.stage_rule(&node_addr, &name, Some(existing.clone()));
+                    }
                     node.rules.retain(|r| r.name != name);
                 }
                 drop(nodes);
@@ -339,6 +1360,7 @@ pub async fn run_state_manager(
                 let mut nodes = state.nodes.write().await;
                 if let Some(node) = nodes.get_node_mut(&node_addr) {
                     if let Some(rule) = node.rules.iter_mut().find(|r| r.name == name) {
+                        state.stage_rule(&node_addr, &name, Some(rule.clone()));
                         rule.enabled = enabled;
                     }
                 }
@@ -355,7 +1377,25 @@ pub async fn run_state_manager(
                 let _ = ui_update_tx.send(UiUpdateSignal::FirewallUpdated);
             }
 
-            AppMessage::AlertReceived { alert } => {
+            AppMessage::AlertReceived { mut alert } => {
+                if state.run_automations(&mut alert).await {
+                    let _ = ui_update_tx.send(UiUpdateSignal::RulesUpdated);
+                }
+                // Newer daemons report the rules they delete on expiry
+                if let (AlertWhat::Rule, Some(AlertData::Rule(rule))) = (alert.what, &alert.data) {
+                    let now = chrono::Utc::now();
+                    if rule.is_expired(now) {
+                        let mut nodes = state.nodes.write().await;
+                        if let Some(node) = nodes.get_node_mut(&alert.node) {
+                            node.rules.retain(|r| r.name != rule.name);
+                        }
+                        drop(nodes);
+                        if let Err(e) = state.db.delete_rule(&alert.node, &rule.name) {
+                            tracing::error!("Failed to delete expired rule: {}", e);
+                        }
+                        let _ = ui_update_tx.send(UiUpdateSignal::RulesUpdated);
+                    }
+                }
                 state.add_alert(alert).await;
                 let _ = ui_update_tx.send(UiUpdateSignal::AlertsUpdated);
             }
@@ -364,6 +1404,10 @@ pub async fn run_state_manager(
                 state.send_notification(&node_addr, action).await;
             }
 
+            AppMessage::Panic { node_addr, engage } => {
+                state.set_panic(&node_addr, engage).await;
+            }
+
             AppMessage::PromptResponse { rule } => {
                 // This is handled by the prompt dialog
                 tracing::debug!("Prompt response: {} - {}", rule.action, rule.name);
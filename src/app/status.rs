@@ -0,0 +1,112 @@
+//! Short status for status bars such as tmux or i3status
+//!
+//! The running collector writes a summary to a file every few seconds, and
+//! `opensnitch-tui status` prints it. Status bars usually run as the user
+//! rather than root, so a world-readable file suits them better than the
+//! control socket. A summary that stopped being updated reads as not running.
+
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::state::AppState;
+use crate::models::RuleAction;
+
+/// How often the status file is rewritten
+const STATUS_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Age after which a summary no longer describes a running collector
+const STALE_AFTER: Duration = Duration::from_secs(30);
+
+/// What a status bar shows
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StatusSummary {
+    pub updated: DateTime<Utc>,
+    pub connected_nodes: usize,
+    pub pending_prompts: u64,
+    /// Denied events in the last hour, among the ones kept in memory
+    pub denied_last_hour: u64,
+}
+
+impl StatusSummary {
+    pub async fn capture(state: &AppState) -> Self {
+        let now = Utc::now();
+        let hour_ago = now - chrono::Duration::hours(1);
+        let denied_last_hour = state
+            .connections
+            .read()
+            .await
+            .iter()
+            .filter(|event| event.rule.as_ref().is_some_and(|rule| rule.action != RuleAction::Allow))
+            .filter(|event| {
+                DateTime::parse_from_rfc3339(&event.time).is_ok_and(|time| time.with_timezone(&Utc) >= hour_ago)
+            })
+            .map(|event| event.count.max(1))
+            .sum();
+        let pending_prompts = state
+            .db
+            .pending_prompt_count()
+            .map_err(|e| tracing::warn!("Cannot count pending prompts: {}", e))
+            .unwrap_or(0);
+        Self {
+            updated: now,
+            connected_nodes: state.nodes.read().await.connected_nodes().count(),
+            pending_prompts: pending_prompts.max(0) as u64,
+            denied_last_hour,
+        }
+    }
+
+    /// Whether the collector stopped updating the summary
+    pub fn is_stale(&self, now: DateTime<Utc>) -> bool {
+        now.signed_duration_since(self.updated).to_std().is_ok_and(|age| age > STALE_AFTER)
+    }
+
+    /// One line for a status bar
+    pub fn oneline(&self) -> String {
+        let mut line = format!(
+            "OpenSnitch {} node{}",
+            self.connected_nodes,
+            if self.connected_nodes == 1 { "" } else { "s" }
+        );
+        if self.pending_prompts > 0 {
+            line.push_str(&format!(" | {} prompt{}", self.pending_prompts, if self.pending_prompts == 1 { "" } else { "s" }));
+        }
+        line.push_str(&format!(" | {} denied/h", self.denied_last_hour));
+        line
+    }
+
+    /// Load a summary written by the collector
+    pub fn load(path: &Path) -> Option<Self> {
+        std::fs::read_to_string(path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+    }
+
+    /// Replace the summary in one step, status bars may be reading it
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let partial = path.with_extension("partial");
+        std::fs::write(&partial, serde_json::to_string(self)?)?;
+        std::fs::set_permissions(&partial, std::fs::Permissions::from_mode(0o644))?;
+        std::fs::rename(&partial, path)?;
+        Ok(())
+    }
+}
+
+/// Keep the status file up to date until the task is aborted
+pub async fn run(state: Arc<AppState>, path: std::path::PathBuf) {
+    let mut ticks = tokio::time::interval(STATUS_INTERVAL);
+    loop {
+        ticks.tick().await;
+        if let Err(e) = StatusSummary::capture(&state).await.save(&path) {
+            tracing::warn!("Cannot write the status to {}: {}", path.display(), e);
+        }
+    }
+}
@@ -0,0 +1,140 @@
+//! Rule suggestions from repeated denials
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::time::Duration;
+
+use crate::models::{Event, Operator, Rule, RuleAction, RuleDuration};
+
+/// Number of tracked keys above which stale entries are dropped
+const MAX_TRACKED: usize = 1024;
+
+/// Node, process path and destination a denial is counted under
+type DenialKey = (String, String, String);
+
+/// Suggestion to make a repeatedly denied connection permanent
+#[derive(Debug, Clone)]
+pub struct RuleSuggestion {
+    pub node_addr: String,
+    pub process_path: String,
+    /// Destination host, or IP if the host is unknown
+    pub destination: String,
+    /// Denials seen within the window
+    pub count: usize,
+}
+
+impl RuleSuggestion {
+    pub fn process_name(&self) -> &str {
+        self.process_path
+            .rsplit('/')
+            .next()
+            .unwrap_or(&self.process_path)
+    }
+
+    pub fn message(&self) -> String {
+        format!(
+            "{} has been denied {} times for {}",
+            self.process_name(),
+            self.count,
+            self.destination
+        )
+    }
+
+    /// Permanent rule matching this process and destination
+    pub fn rule(&self, action: RuleAction) -> Rule {
+        let dest_operand = if self.destination.parse::<std::net::IpAddr>().is_ok() {
+            "dest.ip"
+        } else {
+            "dest.host"
+        };
+        let name = format!("{}-{}-{}", action, self.process_name(), self.destination);
+        let rule = Rule::new(
+            &name,
+            action,
+            RuleDuration::Always,
+            Operator::list(vec![
+                Operator::simple("process.path", &self.process_path),
+                Operator::simple(dest_operand, &self.destination),
+            ]),
+        );
+        // An allow rule has to win over whatever rule kept denying it
+        rule.with_precedence(action == RuleAction::Allow)
+    }
+}
+
+/// Counts denied events per process and destination within a sliding window
+pub struct DenialTracker {
+    /// Denials needed to trigger a suggestion (0 = disabled)
+    threshold: usize,
+    window_nanos: i64,
+    /// Event timestamps (unix nanos) per key within the window
+    denials: HashMap<DenialKey, VecDeque<i64>>,
+    /// Keys already suggested this session
+    suggested: HashSet<DenialKey>,
+}
+
+impl DenialTracker {
+    pub fn new(threshold: usize, window: Duration) -> Self {
+        Self {
+            threshold,
+            window_nanos: window.as_nanos().min(i64::MAX as u128) as i64,
+            denials: HashMap::new(),
+            suggested: HashSet::new(),
+        }
+    }
+
+    /// Record an event, returns a suggestion once a key crosses the threshold
+    pub fn record(&mut self, node_addr: &str, event: &Event) -> Option<RuleSuggestion> {
+        if self.threshold == 0 {
+            return None;
+        }
+        let denied = event
+            .rule
+            .as_ref()
+            .is_some_and(|r| r.action != RuleAction::Allow);
+        if !denied {
+            return None;
+        }
+
+        let conn = &event.connection;
+        let destination = if conn.dst_host.is_empty() {
+            conn.dst_ip.clone()
+        } else {
+            conn.dst_host.clone()
+        };
+        let key = (node_addr.to_string(), conn.process_path.clone(), destination);
+        if self.suggested.contains(&key) {
+            return None;
+        }
+
+        let now = event.unix_nano;
+        let times = self.denials.entry(key.clone()).or_default();
+        // Stats updates can repeat events already counted
+        if times.contains(&now) {
+            return None;
+        }
+        times.push_back(now);
+        while times.front().is_some_and(|t| now - t > self.window_nanos) {
+            times.pop_front();
+        }
+
+        let count = times.len();
+        if count < self.threshold {
+            if self.denials.len() > MAX_TRACKED {
+                let window = self.window_nanos;
+                self.denials
+                    .retain(|_, t| t.back().is_some_and(|last| now - last <= window));
+            }
+            return None;
+        }
+
+        self.denials.remove(&key);
+        self.suggested.insert(key.clone());
+        let (node_addr, process_path, destination) = key;
+        Some(RuleSuggestion {
+            node_addr,
+            process_path,
+            destination,
+            count,
+        })
+    }
+}
@@ -0,0 +1,130 @@
+//! Opt-in check for new releases of the TUI
+//!
+//! Nothing goes over the network unless `update_check` is set. Then the
+//! release list is fetched once at startup and once a day, and releases
+//! newer than the running one are kept for the status bar and the
+//! changelog. The releases API only speaks https, so the list is fetched
+//! with a command, curl by default.
+
+use std::process::Stdio;
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use tokio::process::Command;
+
+use super::state::{AppState, UiUpdateSignal};
+
+/// Version of the running TUI
+pub const CURRENT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// How often releases are checked for
+const CHECK_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// How long the command may take
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// A release as the GitHub API lists it
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct Release {
+    pub tag_name: String,
+    #[serde(default)]
+    pub name: Option<String>,
+    /// Release notes, markdown
+    #[serde(default)]
+    pub body: Option<String>,
+    #[serde(default)]
+    pub html_url: String,
+    #[serde(default)]
+    pub published_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub draft: bool,
+    #[serde(default)]
+    pub prerelease: bool,
+}
+
+/// Releases newer than the running one
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UpdateInfo {
+    /// Newest first
+    pub releases: Vec<Release>,
+    pub checked: DateTime<Utc>,
+}
+
+impl UpdateInfo {
+    pub fn latest(&self) -> &Release {
+        &self.releases[0]
+    }
+}
+
+/// Major, minor and patch of a tag like `v1.2.3` or `1.2.3-rc1`
+pub fn parse_version(tag: &str) -> Option<(u64, u64, u64)> {
+    let version = tag.trim().trim_start_matches('v');
+    let version = version.split(['-', '+']).next()?;
+    let mut parts = version.split('.').map(|part| part.parse::<u64>().ok());
+    let major = parts.next()??;
+    let minor = parts.next().unwrap_or(Some(0))?;
+    let patch = parts.next().unwrap_or(Some(0))?;
+    Some((major, minor, patch))
+}
+
+/// Stable releases newer than `current` in the API's release list, None
+/// when there is none
+pub fn newer_releases(json: &str, current: &str, now: DateTime<Utc>) -> Result<Option<UpdateInfo>, String> {
+    let current = parse_version(current).ok_or_else(|| format!("invalid version: {}", current))?;
+    let releases: Vec<Release> = serde_json::from_str(json).map_err(|e| format!("invalid release list: {}", e))?;
+    let mut newer: Vec<(_, Release)> = releases
+        .into_iter()
+        .filter(|release| !release.draft && !release.prerelease)
+        .filter_map(|release| parse_version(&release.tag_name).map(|version| (version, release)))
+        .filter(|(version, _)| *version > current)
+        .collect();
+    newer.sort_by(|(a, _), (b, _)| b.cmp(a));
+    Ok((!newer.is_empty()).then(|| UpdateInfo {
+        releases: newer.into_iter().map(|(_, release)| release).collect(),
+        checked: now,
+    }))
+}
+
+/// Run the command and look for newer releases in what it prints
+pub async fn check(command: &str) -> Result<Option<UpdateInfo>, String> {
+    let output = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .stdin(Stdio::null())
+        .kill_on_drop(true)
+        .output();
+    let output = tokio::time::timeout(COMMAND_TIMEOUT, output)
+        .await
+        .map_err(|_| "timed out".to_string())?
+        .map_err(|e| e.to_string())?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(match stderr.lines().next() {
+            Some(line) => format!("{}: {}", output.status, line),
+            None => output.status.to_string(),
+        });
+    }
+    newer_releases(&String::from_utf8_lossy(&output.stdout), CURRENT_VERSION, Utc::now())
+}
+
+/// Check for releases at startup and daily, until the task is aborted
+pub async fn run(state: Arc<AppState>, command: String) {
+    let mut interval = tokio::time::interval(CHECK_INTERVAL);
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+    loop {
+        interval.tick().await;
+        match check(&command).await {
+            Ok(update) => {
+                if let Some(update) = &update {
+                    tracing::info!("Release {} is available", update.latest().tag_name);
+                }
+                *state.update.lock().unwrap() = update;
+                state.notify_ui(UiUpdateSignal::Redraw);
+            }
+            // The last known releases stay, e.g. while offline
+            Err(e) => tracing::warn!("Update check failed: {}", e),
+        }
+    }
+}
@@ -0,0 +1,111 @@
+//! Cross-references between rules, events and alerts
+//!
+//! Kept up to date as events and alerts arrive, so a tab can jump from an
+//! item to the related ones on another tab, e.g. from a rule to the
+//! connections it matched lately, without going through everything.
+
+use std::collections::{HashMap, VecDeque};
+
+use chrono::{DateTime, Utc};
+
+use crate::models::{Alert, AlertData, Connection, Event};
+
+/// Connections kept per rule and alerts per firewall rule
+const MAX_REFS: usize = 100;
+
+/// Connections whose last rule is kept, all are forgotten beyond
+const MAX_CONNECTIONS: usize = 10_000;
+
+/// A connection across its events: the program and where it connects to
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ConnectionRef {
+    pub process_path: String,
+    pub protocol: String,
+    pub dst_host: String,
+    pub dst_ip: String,
+    pub dst_port: u32,
+}
+
+impl ConnectionRef {
+    pub fn of(connection: &Connection) -> Self {
+        Self {
+            process_path: connection.process_path.clone(),
+            protocol: connection.protocol.clone(),
+            dst_host: connection.dst_host.clone(),
+            dst_ip: connection.dst_ip.clone(),
+            dst_port: connection.dst_port,
+        }
+    }
+
+    pub fn matches(&self, connection: &Connection) -> bool {
+        self.process_path == connection.process_path
+            && self.protocol == connection.protocol
+            && self.dst_host == connection.dst_host
+            && self.dst_ip == connection.dst_ip
+            && self.dst_port == connection.dst_port
+    }
+}
+
+/// An alert, by the id and time the daemon gave it
+pub type AlertRef = (u64, DateTime<Utc>);
+
+#[derive(Debug, Default)]
+pub struct XrefIndex {
+    /// Connections each rule matched, most recent last
+    rule_events: HashMap<String, VecDeque<ConnectionRef>>,
+    /// Rule that last matched each connection
+    connection_rules: HashMap<ConnectionRef, String>,
+    /// Alerts about each system firewall rule by uuid, most recent last
+    firewall_alerts: HashMap<String, VecDeque<AlertRef>>,
+}
+
+impl XrefIndex {
+    pub fn add_event(&mut self, event: &Event) {
+        let Some(rule) = event
+            .rule
+            .as_ref()
+            .map(|rule| rule.name.clone())
+            .or_else(|| event.connection.rule_name.clone())
+            .filter(|name| !name.is_empty())
+        else {
+            return;
+        };
+        let connection = ConnectionRef::of(&event.connection);
+        push_recent(self.rule_events.entry(rule.clone()).or_default(), connection.clone());
+
+        if self.connection_rules.len() >= MAX_CONNECTIONS && !self.connection_rules.contains_key(&connection) {
+            self.connection_rules.clear();
+        }
+        self.connection_rules.insert(connection, rule);
+    }
+
+    pub fn add_alert(&mut self, alert: &Alert) {
+        if let Some(AlertData::FirewallRule(rule)) = &alert.data {
+            push_recent(self.firewall_alerts.entry(rule.uuid.clone()).or_default(), (alert.id, alert.timestamp));
+        }
+    }
+
+    /// Connections the rule matched lately, most recent first
+    pub fn rule_events(&self, rule: &str) -> Vec<ConnectionRef> {
+        self.rule_events.get(rule).map(|refs| refs.iter().rev().cloned().collect()).unwrap_or_default()
+    }
+
+    /// Rule that last matched the connection
+    pub fn connection_rule(&self, connection: &Connection) -> Option<&str> {
+        self.connection_rules.get(&ConnectionRef::of(connection)).map(String::as_str)
+    }
+
+    /// Alerts about a system firewall rule, most recent first
+    pub fn firewall_alerts(&self, uuid: &str) -> Vec<AlertRef> {
+        self.firewall_alerts.get(uuid).map(|refs| refs.iter().rev().copied().collect()).unwrap_or_default()
+    }
+}
+
+/// Move `item` to the recent end, dropping the oldest beyond the limit
+fn push_recent<T: PartialEq>(refs: &mut VecDeque<T>, item: T) {
+    refs.retain(|r| r != &item);
+    refs.push_back(item);
+    if refs.len() > MAX_REFS {
+        refs.pop_front();
+    }
+}
@@ -1,4 +1,10 @@
 pub mod keybinds;
+pub mod paths;
+pub mod session;
 pub mod settings;
 
-pub use settings::Settings;
+pub use paths::DataDirs;
+pub use session::SessionState;
+pub use settings::{
+    AuthType, HostMode, InterceptionMode, KnownProxy, ListenAddress, PromptPolicy, PromptPosition, RuleHook, ScheduledTask, Settings, StatsLimits, TaskKind, TerminalMode,
+};
@@ -0,0 +1,159 @@
+//! Where data and state files are kept
+//!
+//! Follows the XDG base directories: the database and exports go to
+//! `$XDG_DATA_HOME/opensnitch-tui`, session state to
+//! `$XDG_STATE_HOME/opensnitch-tui`. A data dir set with `--data-dir` or
+//! the `data_dir` setting holds everything instead. Sockets go to a
+//! private runtime directory, `$XDG_RUNTIME_DIR/opensnitch-tui`.
+
+use std::os::unix::fs::{DirBuilderExt, MetadataExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+
+use super::Settings;
+
+const APP_DIR: &str = "opensnitch-tui";
+const DATABASE_FILE: &str = "opensnitch.db";
+
+/// Data and state directories
+#[derive(Debug, Clone)]
+pub struct DataDirs {
+    data: PathBuf,
+    state: PathBuf,
+    /// Database from before the data dir, kept in use while it exists
+    legacy_database: Option<PathBuf>,
+}
+
+impl DataDirs {
+    /// Everything under `dir`
+    pub fn at(dir: &Path) -> Self {
+        Self {
+            data: dir.to_path_buf(),
+            state: dir.join("state"),
+            legacy_database: None,
+        }
+    }
+
+    /// XDG directories of the current user
+    pub fn xdg() -> Self {
+        Self {
+            data: xdg_dir("XDG_DATA_HOME", ".local/share"),
+            state: xdg_dir("XDG_STATE_HOME", ".local/state"),
+            legacy_database: Some(Settings::config_dir().join(DATABASE_FILE)),
+        }
+    }
+
+    /// Event and rule database
+    pub fn database(&self) -> PathBuf {
+        let path = self.data.join(DATABASE_FILE);
+        match &self.legacy_database {
+            Some(legacy) if !path.exists() && legacy.exists() => legacy.clone(),
+            _ => path,
+        }
+    }
+
+    /// UI state restored on the next start
+    pub fn session(&self) -> PathBuf {
+        self.state.join("session.json")
+    }
+
+    /// Summary of the running collector for status bars
+    pub fn status(&self) -> PathBuf {
+        self.state.join("status.json")
+    }
+
+    /// When the scheduled tasks last ran
+    pub fn schedule(&self) -> PathBuf {
+        self.state.join("schedule.json")
+    }
+
+    /// Socket a daemon connects to while the TUI runs without root
+    pub fn daemon_socket(&self) -> PathBuf {
+        self.state.join("daemon.sock")
+    }
+
+    /// Rules grouped into named policies
+    pub fn policies(&self) -> PathBuf {
+        self.data.join("policies.json")
+    }
+
+    /// Actions taken when alerts arrive
+    pub fn automations(&self) -> PathBuf {
+        self.data.join("automations.json")
+    }
+
+    /// Reports and other files exported on request
+    pub fn exports(&self) -> PathBuf {
+        self.data.join("exports")
+    }
+}
+
+/// Directory of the TUI's sockets: `$XDG_RUNTIME_DIR/opensnitch-tui`, or
+/// `/tmp/opensnitch-tui-<uid>` for sessions without a runtime directory
+pub fn runtime_dir() -> PathBuf {
+    runtime_dir_in(std::env::var_os("XDG_RUNTIME_DIR").map(PathBuf::from))
+}
+
+/// [`runtime_dir`] for a session with this runtime directory, if any
+pub fn runtime_dir_in(xdg_runtime_dir: Option<PathBuf>) -> PathBuf {
+    match xdg_runtime_dir.filter(|path| path.is_absolute()) {
+        Some(dir) => dir.join(APP_DIR),
+        None => std::env::temp_dir().join(format!("{}-{}", APP_DIR, unsafe { libc::geteuid() })),
+    }
+}
+
+/// Create `dir` only accessible to the current user, or check an existing
+/// one is: a real directory of ours that nobody else can enter
+pub fn create_private_dir(dir: &Path) -> std::io::Result<()> {
+    match std::fs::DirBuilder::new().mode(0o700).create(dir) {
+        Ok(()) => return Ok(()),
+        Err(e) if e.kind() != std::io::ErrorKind::AlreadyExists => return Err(e),
+        Err(_) => {}
+    }
+    let meta = std::fs::symlink_metadata(dir)?;
+    let private = meta.is_dir() && meta.uid() == unsafe { libc::geteuid() } && meta.permissions().mode() & 0o077 == 0;
+    if !private {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::PermissionDenied,
+            format!("{} isn't a private directory of this user", dir.display()),
+        ));
+    }
+    Ok(())
+}
+
+/// Bind a Unix socket at `path` only the current user can connect to.
+///
+/// The socket is bound in a private directory next to `path` and closed
+/// to others before it is moved into place, so it never is open to them,
+/// not even briefly. The umask, shared by all threads, is left alone.
+pub fn bind_private_socket(path: &Path) -> std::io::Result<tokio::net::UnixListener> {
+    let name = path.file_name().map(|name| name.to_string_lossy()).unwrap_or_default();
+    let staging = path.with_file_name(format!(".{}.{}", name, std::process::id()));
+    create_private_dir(&staging)?;
+    let staged = staging.join("socket");
+    let _ = std::fs::remove_file(&staged);
+
+    let bound = std::os::unix::net::UnixListener::bind(&staged).and_then(|listener| {
+        std::fs::set_permissions(&staged, std::fs::Permissions::from_mode(0o600))?;
+        std::fs::rename(&staged, path)?;
+        listener.set_nonblocking(true)?;
+        tokio::net::UnixListener::from_std(listener)
+    });
+    let _ = std::fs::remove_file(&staged);
+    let _ = std::fs::remove_dir(&staging);
+    bound
+}
+
+/// `$var/opensnitch-tui`, or `~/<fallback>/opensnitch-tui` when unset
+///
+/// Relative paths are invalid per the XDG spec and ignored.
+fn xdg_dir(var: &str, fallback: &str) -> PathBuf {
+    std::env::var_os(var)
+        .map(PathBuf::from)
+        .filter(|path| path.is_absolute())
+        .unwrap_or_else(|| {
+            dirs::home_dir()
+                .unwrap_or_else(|| PathBuf::from("."))
+                .join(fallback)
+        })
+        .join(APP_DIR)
+}
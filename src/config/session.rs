@@ -0,0 +1,41 @@
+//! Session state persisted between runs
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// UI state restored on the next start
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SessionState {
+    /// Past search queries per tab, oldest first
+    pub search_history: HashMap<String, Vec<String>>,
+    /// Theme picked in the UI, over the configured one
+    pub theme: Option<String>,
+}
+
+impl SessionState {
+    /// Load session state, falling back to empty state if missing or unreadable
+    pub fn load(path: &Path) -> Self {
+        std::fs::read_to_string(path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    /// Remember a theme picked in the UI. Picking the configured theme
+    /// forgets it, so later changes to the configuration apply again.
+    pub fn pick_theme(&mut self, picked: &str, configured: &str) {
+        self.theme = Some(picked.to_string()).filter(|name| name != configured);
+    }
+
+    /// Save session state to disk
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
@@ -3,28 +3,50 @@
 use anyhow::Result;
 use directories::ProjectDirs;
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
+use super::{paths, DataDirs};
 use crate::models::{RuleAction, RuleDuration};
+use crate::utils::host::is_local_node;
+use crate::utils::{DateStyle, Formats, NetworkState};
+
+/// Control socket default of earlier versions, anyone could take it over
+const TMP_CONTROL_SOCKET: &str = "/tmp/opensnitch-tui.sock";
+
+/// Last gRPC fallback of earlier versions, open to anyone too
+const TMP_FALLBACK_SOCKET: &str = "unix:///tmp/osui-tui.sock";
 
 /// Application settings
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Settings {
     /// gRPC socket address
     pub socket_address: String,
 
-    /// Database file path
+    /// Database file path (empty = in the data directory)
     pub database_path: String,
 
+    /// Directory for the database, session state and exports (empty = XDG directories)
+    pub data_dir: String,
+
     /// Default action when prompt times out
     pub default_action: RuleAction,
 
     /// Default rule duration
     pub default_duration: RuleDuration,
 
+    /// How long a sandboxed process stays cut off from the network
+    pub sandbox_duration: RuleDuration,
+
     /// Prompt timeout in seconds
     pub prompt_timeout: u64,
 
+    /// Where prompts open: centered over the view, or docked at the bottom
+    pub prompt_position: PromptPosition,
+
+    /// Connections are asked about ("ask"), or answered "allow-all" or "deny-all" without prompting
+    pub interception_mode: InterceptionMode,
+
     /// Maximum connections to keep in memory
     pub max_connections: usize,
 
@@ -37,30 +59,521 @@ pub struct Settings {
     /// Theme name
     pub theme: String,
 
+    /// Locale for counts and dates, e.g. "de_DE" (empty = from LC_ALL, LC_NUMERIC or LANG)
+    pub locale: String,
+
+    /// Group thousands in counts the way the locale does
+    pub group_numbers: bool,
+
+    /// Dates as "iso" or in the locale's "locale" order
+    pub date_format: DateStyle,
+
+    /// Terminal capabilities: detected from TERM and the locale, or forced
+    pub terminal_mode: TerminalMode,
+
     /// Show notifications
     pub show_notifications: bool,
+
+    /// Open a popup when a high-priority alert arrives
+    pub popup_high_alerts: bool,
+
+    /// Desktop notification for prompts and high-priority alerts while the terminal isn't focused
+    pub desktop_notifications: bool,
+
+    /// Command run for desktop notifications, given the summary and body
+    pub desktop_notify_command: String,
+
+    /// Denials of the same process and destination before a rule is suggested (0 = disabled)
+    pub suggestion_threshold: usize,
+
+    /// Window in seconds the denials have to fall within
+    pub suggestion_window_secs: u64,
+
+    /// Minutes without input before the UI locks (0 = disabled)
+    pub idle_lock_minutes: u64,
+
+    /// Passphrase required to unlock the UI (empty = Enter unlocks)
+    pub idle_lock_passphrase: String,
+
+    /// Maximum entries shown in each Statistics breakdown panel
+    pub stats_limits: StatsLimits,
+
+    /// Seconds between Statistics panel refreshes from node stats
+    pub stats_refresh_secs: u64,
+
+    /// gRPC authentication type, named after opensnitchd's Server.Authentication.Type
+    pub auth_type: AuthType,
+
+    /// Shared secret requests must send when auth_type is "token". opensnitchd
+    /// doesn't send one, only a proxy in front of it can add it
+    pub auth_token: String,
+
+    /// Control socket read-only mirrors attach to (empty = disabled)
+    pub control_socket: String,
+
+    /// Summary for status bars, read by `opensnitch-tui status` (empty = in the state directory)
+    pub status_file: String,
+
+    /// Loopback address web dashboards get events from over a WebSocket (empty = disabled)
+    pub event_stream: String,
+
+    /// Browser origins besides local pages allowed on the event stream
+    pub event_stream_origins: Vec<String>,
+
+    /// Secret dashboards must send as a `token` query parameter or a bearer
+    /// token, the event stream stays off without one
+    pub event_stream_token: String,
+
+    /// Look for new releases in the background (off = no network access)
+    pub update_check: bool,
+
+    /// Command printing the release list as JSON
+    pub update_command: String,
+
+    /// Local proxies whose outgoing connections are attributed to their clients
+    pub proxies: Vec<KnownProxy>,
+
+    /// Per-network answers for connections nobody is asked about, first match wins
+    pub network_profiles: Vec<NetworkProfile>,
+
+    /// Seconds between checks of the current network (0 = disabled)
+    pub network_check_secs: u64,
+
+    /// Events per second above which only some events are kept (0 = disabled)
+    pub sampling_threshold: u64,
+
+    /// While sampling, one in this many events is shown and persisted
+    pub sampling_rate: u64,
+
+    /// Milliseconds identical events are merged within, shown and persisted once with a count (0 = disabled)
+    pub dedup_window_ms: u64,
+
+    /// Addresses tried in order when the gRPC address is taken (empty = no fallback)
+    pub server_fallback: Vec<String>,
+
+    /// Next to the daemon or a remote console: detected from the host, or forced
+    pub host_mode: HostMode,
+
+    /// Address the gRPC server listens on as a remote console. Beyond
+    /// loopback it needs `auth_token` or TLS, anyone reaching it could
+    /// drive the firewall otherwise.
+    pub console_address: String,
+
+    /// More addresses to accept daemons on at the same time, `unix:///path` or `host:port`,
+    /// or objects with an address, a label and the default answer for their nodes
+    pub listen_addresses: Vec<ListenAddress>,
+
+    /// PEM certificate and key for TCP listeners (empty = no TLS)
+    pub tls_cert: String,
+    pub tls_key: String,
+
+    /// PEM CA daemons' client certificates must be signed by (empty = not required)
+    pub tls_client_ca: String,
+
+    /// Local actions run when events of a rule arrive
+    pub rule_hooks: Vec<RuleHook>,
+
+    /// Hosts a quarantined node may still reach besides DNS and DHCP: addresses, networks or host names
+    pub quarantine_allowlist: Vec<String>,
+
+    /// Maintenance and reports run in the background
+    pub scheduled_tasks: Vec<ScheduledTask>,
+}
+
+/// Whether the TUI manages the daemon on its own host
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HostMode {
+    /// Console inside containers or without an installed daemon, local otherwise
+    #[default]
+    Auto,
+    /// Configure and restart the daemon running here
+    Local,
+    /// Only listen for remote daemons, never touch the host
+    Console,
+}
+
+/// What the terminal is assumed to render
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TerminalMode {
+    /// Detect from TERM, COLORTERM and the locale
+    #[default]
+    Auto,
+    /// Unicode symbols and the full palette
+    Full,
+    /// ASCII symbols and the 8 basic colors, for serial and plain consoles
+    Basic,
+}
+
+/// Where connection prompts open
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PromptPosition {
+    #[default]
+    Center,
+    /// Full width at the bottom, the view above stays readable
+    Bottom,
+}
+
+/// How connections the daemon asks about are answered
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum InterceptionMode {
+    /// Prompt, answering with the default action when nobody does in time
+    #[default]
+    Ask,
+    /// Allow everything without prompting, for monitoring
+    AllowAll,
+    /// Deny everything without prompting
+    DenyAll,
+}
+
+impl InterceptionMode {
+    /// The next mode, for cycling from the status bar
+    pub fn next(self) -> Self {
+        match self {
+            Self::Ask => Self::AllowAll,
+            Self::AllowAll => Self::DenyAll,
+            Self::DenyAll => Self::Ask,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Ask => "ASK",
+            Self::AllowAll => "ALLOW ALL",
+            Self::DenyAll => "DENY ALL",
+        }
+    }
+}
+
+/// gRPC authentication type
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AuthType {
+    /// No authentication
+    #[default]
+    Simple,
+    /// Shared-secret token in the `authorization` request metadata
+    Token,
+}
+
+/// A further address daemons connect to, e.g. from a network namespace
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(from = "ListenEntry")]
+pub struct ListenAddress {
+    /// `unix:///path` or `host:port`
+    pub address: String,
+    /// Shown with the nodes arriving on the address (empty = none)
+    pub label: String,
+    /// Answer for asks of its nodes nobody answered, instead of the network's
+    pub default_action: Option<RuleAction>,
+    pub default_duration: Option<RuleDuration>,
+}
+
+/// A bare address or a full listener in the config file
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ListenEntry {
+    Address(String),
+    Listener {
+        address: String,
+        #[serde(default)]
+        label: String,
+        #[serde(default)]
+        default_action: Option<RuleAction>,
+        #[serde(default)]
+        default_duration: Option<RuleDuration>,
+    },
+}
+
+impl From<ListenEntry> for ListenAddress {
+    fn from(entry: ListenEntry) -> Self {
+        match entry {
+            ListenEntry::Address(address) => Self::new(&address),
+            ListenEntry::Listener { address, label, default_action, default_duration } => Self {
+                address,
+                label,
+                default_action,
+                default_duration,
+            },
+        }
+    }
+}
+
+impl ListenAddress {
+    pub fn new(address: &str) -> Self {
+        Self {
+            address: address.to_string(),
+            label: String::new(),
+            default_action: None,
+            default_duration: None,
+        }
+    }
+
+    /// The label, None when there is none
+    pub fn tag(&self) -> Option<&str> {
+        (!self.label.is_empty()).then_some(self.label.as_str())
+    }
+
+    /// Prompt policy of the listener, None to follow the network
+    pub fn prompt_policy(&self) -> Option<PromptPolicy> {
+        let default_action = self.default_action?;
+        Some(PromptPolicy {
+            profile: Some(self.tag().unwrap_or(&self.address).to_string()),
+            default_action,
+            default_duration: self.default_duration.clone().unwrap_or(RuleDuration::Once),
+        })
+    }
+}
+
+/// Proxy process listening on a local port
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KnownProxy {
+    /// Process name, as in the basename of its path
+    pub process: String,
+    pub port: u32,
+}
+
+impl KnownProxy {
+    pub fn new(process: &str, port: u32) -> Self {
+        Self {
+            process: process.to_string(),
+            port,
+        }
+    }
+
+    /// Common local proxies on their default ports
+    pub fn defaults() -> Vec<Self> {
+        vec![
+            Self::new("tor", 9050),
+            Self::new("tor", 9150),
+            Self::new("privoxy", 8118),
+            Self::new("squid", 3128),
+            Self::new("polipo", 8123),
+            Self::new("redsocks", 12345),
+            Self::new("sockd", 1080),
+            Self::new("ssh", 1080),
+        ]
+    }
+}
+
+/// Command or webhook run when events matched by a rule arrive
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RuleHook {
+    /// Name of the rule the events reference
+    pub rule: String,
+    /// Shell command, given the event as JSON on stdin (empty = none)
+    pub command: String,
+    /// http:// URL the event is POSTed to as JSON (empty = none)
+    pub webhook: String,
+    /// Seconds between runs, events in between are only counted (0 = every event)
+    pub min_interval_secs: u64,
+}
+
+impl Default for RuleHook {
+    fn default() -> Self {
+        Self {
+            rule: String::new(),
+            command: String::new(),
+            webhook: String::new(),
+            min_interval_secs: 60,
+        }
+    }
+}
+
+/// What a scheduled task does
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TaskKind {
+    /// Delete events and alerts older than keep_days from the database
+    #[default]
+    Purge,
+    /// Write a rules report of every node to the exports directory
+    Report,
+    /// Download a blocklist to the file a lists rule reads
+    Blocklist,
+    /// Give the space of deleted rows back and refresh the query statistics
+    Vacuum,
+}
+
+/// Task run on a schedule, e.g. a nightly purge or a weekly report
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScheduledTask {
+    /// Name the last run is shown under
+    pub name: String,
+    pub task: TaskKind,
+    /// Hours between runs
+    pub every_hours: u64,
+    /// Local hour the runs start in (none = every_hours after the last run)
+    pub at_hour: Option<u32>,
+    /// purge: days of events and alerts kept
+    pub keep_days: u64,
+    /// blocklist: http:// URL the list is downloaded from
+    pub url: String,
+    /// blocklist: shell command printing the list instead, e.g. curl for https
+    pub command: String,
+    /// blocklist: file written, in the directory of a lists rule
+    pub path: String,
+}
+
+impl Default for ScheduledTask {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            task: TaskKind::Purge,
+            every_hours: 24,
+            at_hour: None,
+            keep_days: 30,
+            url: String::new(),
+            command: String::new(),
+            path: String::new(),
+        }
+    }
+}
+
+/// Prompt policy applied while on a matching network
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NetworkProfile {
+    pub name: String,
+    /// Wi-Fi network name (empty = any)
+    pub ssid: String,
+    /// Default gateway address (empty = any)
+    pub gateway: String,
+    /// Interface of the default route (empty = any)
+    pub interface: String,
+    pub default_action: RuleAction,
+    pub default_duration: RuleDuration,
+}
+
+impl Default for NetworkProfile {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            ssid: String::new(),
+            gateway: String::new(),
+            interface: String::new(),
+            default_action: RuleAction::Deny,
+            default_duration: RuleDuration::Once,
+        }
+    }
+}
+
+impl NetworkProfile {
+    pub fn matches(&self, network: &NetworkState) -> bool {
+        let field = |want: &str, have: &str| want.is_empty() || want == have;
+        field(&self.ssid, &network.ssid)
+            && field(&self.gateway, &network.gateway)
+            && field(&self.interface, &network.interface)
+    }
+}
+
+/// How unanswered connections are handled on the current network
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PromptPolicy {
+    /// Matching network profile, None when the global defaults apply
+    pub profile: Option<String>,
+    pub default_action: RuleAction,
+    pub default_duration: RuleDuration,
+}
+
+impl Default for PromptPolicy {
+    fn default() -> Self {
+        Self {
+            profile: None,
+            default_action: RuleAction::Allow,
+            default_duration: RuleDuration::Once,
+        }
+    }
+}
+
+/// Per-panel entry limits for the Statistics tab (0 = unlimited)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StatsLimits {
+    pub by_protocol: usize,
+    pub by_host: usize,
+    pub by_port: usize,
+    pub by_user: usize,
+    pub by_executable: usize,
+    pub by_interface: usize,
 }
 
 impl Default for Settings {
     fn default() -> Self {
         Self {
             socket_address: "unix:///tmp/osui.sock".to_string(),
-            database_path: Self::default_db_path()
-                .to_string_lossy()
-                .to_string(),
+            database_path: String::new(),
+            data_dir: String::new(),
             default_action: RuleAction::Allow, // User preference: permissive
             default_duration: RuleDuration::Once,
+            sandbox_duration: RuleDuration::ThirtyMinutes,
             prompt_timeout: 15,
+            prompt_position: PromptPosition::Center,
+            interception_mode: InterceptionMode::Ask,
             max_connections: 1000,
             max_alerts: 500,
             log_level: "info".to_string(),
             theme: "default".to_string(),
+            locale: String::new(),
+            group_numbers: true,
+            date_format: DateStyle::Iso,
+            terminal_mode: TerminalMode::Auto,
             show_notifications: true,
+            popup_high_alerts: false,
+            desktop_notifications: false,
+            desktop_notify_command: "notify-send --app-name=opensnitch-tui".to_string(),
+            suggestion_threshold: 10,
+            suggestion_window_secs: 60,
+            idle_lock_minutes: 0,
+            idle_lock_passphrase: String::new(),
+            stats_limits: StatsLimits::default(),
+            stats_refresh_secs: 1,
+            auth_type: AuthType::Simple,
+            auth_token: String::new(),
+            control_socket: paths::runtime_dir().join("control.sock").to_string_lossy().into_owned(),
+            status_file: String::new(),
+            event_stream: String::new(),
+            event_stream_origins: Vec::new(),
+            event_stream_token: String::new(),
+            update_check: false,
+            update_command: "curl -fsSL -H 'Accept: application/vnd.github+json' \
+                https://api.github.com/repos/icryo/opensnitch-tui/releases"
+                .to_string(),
+            proxies: KnownProxy::defaults(),
+            network_profiles: Vec::new(),
+            network_check_secs: 10,
+            sampling_threshold: 500,
+            sampling_rate: 10,
+            dedup_window_ms: 100,
+            server_fallback: vec![
+                "127.0.0.1:50052".to_string(),
+                "127.0.0.1:50053".to_string(),
+                format!("unix://{}", paths::runtime_dir().join("grpc.sock").display()),
+            ],
+            host_mode: HostMode::Auto,
+            console_address: "127.0.0.1:50051".to_string(),
+            listen_addresses: Vec::new(),
+            tls_cert: String::new(),
+            tls_key: String::new(),
+            tls_client_ca: String::new(),
+            rule_hooks: Vec::new(),
+            quarantine_allowlist: Vec::new(),
+            scheduled_tasks: Vec::new(),
         }
     }
 }
 
 impl Settings {
+    /// Number and date formatting for the UI
+    pub fn formats(&self) -> Formats {
+        Formats::detect(&self.locale, self.group_numbers, self.date_format)
+    }
+
     /// Load settings from file or create default
     pub fn load(path: Option<&str>) -> Result<Self> {
         let config_path = path
@@ -69,13 +582,29 @@ impl Settings {
 
         if config_path.exists() {
             let content = std::fs::read_to_string(&config_path)?;
-            let settings: Self = serde_json::from_str(&content)?;
+            let mut settings: Self = serde_json::from_str(&content)?;
+            settings.leave_tmp();
             Ok(settings)
         } else {
             Ok(Self::default())
         }
     }
 
+    /// Move sockets saved with the former defaults in the shared /tmp to the
+    /// private runtime directory
+    fn leave_tmp(&mut self) {
+        let defaults = Self::default();
+        if self.control_socket == TMP_CONTROL_SOCKET {
+            self.control_socket = defaults.control_socket;
+        }
+        let private = defaults.server_fallback.iter().find(|address| address.starts_with("unix://"));
+        if let Some(private) = private {
+            for address in self.server_fallback.iter_mut().filter(|address| *address == TMP_FALLBACK_SOCKET) {
+                *address = private.clone();
+            }
+        }
+    }
+
     /// Save settings to file
     pub fn save(&self, path: Option<&str>) -> Result<()> {
         let config_path = path
@@ -92,6 +621,44 @@ impl Settings {
         Ok(())
     }
 
+    /// Token required from daemons, or None when authentication is disabled
+    pub fn grpc_auth_token(&self) -> Result<Option<String>> {
+        match self.auth_type {
+            AuthType::Simple => Ok(None),
+            AuthType::Token if self.auth_token.is_empty() => {
+                anyhow::bail!("auth_type is \"token\" but auth_token is empty")
+            }
+            AuthType::Token => Ok(Some(self.auth_token.clone())),
+        }
+    }
+
+    /// Refuse a console listening beyond loopback to anyone who connects
+    pub fn check_console_address(&self) -> Result<()> {
+        if is_local_node(&self.console_address) || self.grpc_auth_token()?.is_some() || !self.tls_cert.is_empty() {
+            return Ok(());
+        }
+        anyhow::bail!(
+            "console_address {} is reachable from the network, set auth_token or tls_cert and tls_key, or listen on 127.0.0.1",
+            self.console_address
+        )
+    }
+
+    /// Policy of the first profile matching the network, else the global defaults
+    pub fn prompt_policy(&self, network: &NetworkState) -> PromptPolicy {
+        match self.network_profiles.iter().find(|p| p.matches(network)) {
+            Some(profile) => PromptPolicy {
+                profile: Some(profile.name.clone()),
+                default_action: profile.default_action,
+                default_duration: profile.default_duration.clone(),
+            },
+            None => PromptPolicy {
+                profile: None,
+                default_action: self.default_action,
+                default_duration: self.default_duration.clone(),
+            },
+        }
+    }
+
     /// Get default config directory
     pub fn config_dir() -> PathBuf {
         ProjectDirs::from("com", "opensnitch", "opensnitch-tui")
@@ -109,8 +676,30 @@ impl Settings {
         Self::config_dir().join("config.json")
     }
 
-    /// Get default database path
-    pub fn default_db_path() -> PathBuf {
-        Self::config_dir().join("opensnitch.db")
+    /// Directories for data and state files
+    pub fn data_dirs(&self) -> DataDirs {
+        if self.data_dir.is_empty() {
+            DataDirs::xdg()
+        } else {
+            DataDirs::at(Path::new(&self.data_dir))
+        }
+    }
+
+    /// Database to open, the configured file or the one in the data directory
+    pub fn database_file(&self) -> String {
+        if self.database_path.is_empty() {
+            self.data_dirs().database().to_string_lossy().to_string()
+        } else {
+            self.database_path.clone()
+        }
+    }
+
+    /// Status summary file, the configured one or the one in the state directory
+    pub fn status_file(&self) -> PathBuf {
+        if self.status_file.is_empty() {
+            self.data_dirs().status()
+        } else {
+            PathBuf::from(&self.status_file)
+        }
     }
 }
@@ -0,0 +1,214 @@
+//! Import from the official OpenSnitch GUI database
+
+use std::collections::HashSet;
+use std::path::PathBuf;
+
+use anyhow::Result;
+use rusqlite::{params, params_from_iter, types::Value, Connection, OpenFlags};
+
+use super::queries;
+
+/// Connection columns read from the GUI database, in insert order
+const CONNECTION_COLUMNS: &[&str] = &[
+    "time", "node", "action", "protocol", "src_ip", "src_port", "dst_ip", "dst_host",
+    "dst_port", "uid", "pid", "process", "process_args", "process_cwd", "rule",
+];
+
+/// Rule columns read from the GUI database, in insert order
+const RULE_COLUMNS: &[&str] = &[
+    "time", "node", "name", "enabled", "precedence", "action", "duration",
+    "operator_type", "operator_sensitive", "operator_operand", "operator_data",
+    "description", "nolog", "created",
+];
+
+/// Maximum number of row errors kept in the report
+const MAX_REPORTED_ERRORS: usize = 20;
+
+/// Outcome of a GUI database import
+#[derive(Debug, Default, Clone)]
+pub struct ImportReport {
+    pub connections_imported: usize,
+    pub connections_skipped: usize,
+    pub rules_imported: usize,
+    pub rules_skipped: usize,
+    pub errors: Vec<String>,
+}
+
+impl ImportReport {
+    pub fn summary(&self) -> String {
+        format!(
+            "Connections: {} imported, {} skipped | Rules: {} imported, {} skipped",
+            self.connections_imported,
+            self.connections_skipped,
+            self.rules_imported,
+            self.rules_skipped
+        )
+    }
+
+    fn record_error(&mut self, error: String) {
+        if self.errors.len() < MAX_REPORTED_ERRORS {
+            self.errors.push(error);
+        }
+    }
+}
+
+/// Default location of the GUI database
+pub fn default_gui_db_path() -> PathBuf {
+    dirs::data_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("opensnitch")
+        .join("opensnitch.sqlite3")
+}
+
+/// Copy connections and rules from the GUI database at `path` into `dest`
+pub fn import_gui_database(dest: &mut Connection, path: &str) -> Result<ImportReport> {
+    if !std::path::Path::new(path).exists() {
+        anyhow::bail!("GUI database not found: {}", path);
+    }
+
+    let src = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+    let mut report = ImportReport::default();
+
+    let tx = dest.transaction()?;
+    import_connections(&src, &tx, &mut report)?;
+    import_rules(&src, &tx, &mut report)?;
+    tx.commit()?;
+
+    Ok(report)
+}
+
+fn import_connections(src: &Connection, dest: &Connection, report: &mut ImportReport) -> Result<()> {
+    let Some(rows) = read_table(src, "connections", CONNECTION_COLUMNS)? else {
+        report.record_error("No connections table in GUI database".to_string());
+        return Ok(());
+    };
+
+    for (i, row) in rows.into_iter().enumerate() {
+        // time, process and dst_ip are required to make sense of an event
+        if row[0].is_empty() || (row[11].is_empty() && row[6].is_empty()) {
+            report.connections_skipped += 1;
+            report.record_error(format!("connections row {}: missing time or process", i + 1));
+            continue;
+        }
+
+        match dest.execute(queries::IMPORT_CONNECTION, params_from_iter(row.iter())) {
+            Ok(0) => report.connections_skipped += 1,
+            Ok(_) => {
+                report.connections_imported += 1;
+                update_stats(dest, &row)?;
+            }
+            Err(e) => {
+                report.connections_skipped += 1;
+                report.record_error(format!("connections row {}: {}", i + 1, e));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn import_rules(src: &Connection, dest: &Connection, report: &mut ImportReport) -> Result<()> {
+    let Some(rows) = read_table(src, "rules", RULE_COLUMNS)? else {
+        report.record_error("No rules table in GUI database".to_string());
+        return Ok(());
+    };
+
+    for (i, mut row) in rows.into_iter().enumerate() {
+        if row[2].is_empty() {
+            report.rules_skipped += 1;
+            report.record_error(format!("rules row {}: missing name", i + 1));
+            continue;
+        }
+        // The GUI stores booleans as Python "True"/"False"
+        for idx in [3, 4, 8, 12] {
+            row[idx] = row[idx].to_lowercase();
+        }
+        // Older GUI versions have no created column
+        if row[13].is_empty() {
+            row[13] = row[0].clone();
+        }
+
+        match dest.execute(queries::IMPORT_RULE, params_from_iter(row.iter())) {
+            Ok(0) => report.rules_skipped += 1,
+            Ok(_) => report.rules_imported += 1,
+            Err(e) => {
+                report.rules_skipped += 1;
+                report.record_error(format!("rules row {} ({}): {}", i + 1, row[2], e));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn update_stats(dest: &Connection, row: &[String]) -> Result<()> {
+    let (dst_ip, dst_host, dst_port, uid, process) = (&row[6], &row[7], &row[8], &row[9], &row[11]);
+
+    if !dst_host.is_empty() {
+        dest.execute(queries::UPDATE_STATS_HOST, params![dst_host, 1])?;
+    }
+    if !process.is_empty() {
+        dest.execute(queries::UPDATE_STATS_PROC, params![process, 1])?;
+    }
+    if !dst_ip.is_empty() {
+        dest.execute(queries::UPDATE_STATS_ADDR, params![dst_ip, 1])?;
+    }
+    dest.execute(queries::UPDATE_STATS_PORT, params![dst_port, 1])?;
+    dest.execute(queries::UPDATE_STATS_USER, params![uid, 1])?;
+    Ok(())
+}
+
+/// Read all rows of `table`, substituting empty strings for missing columns.
+/// Returns None if the table does not exist.
+fn read_table(src: &Connection, table: &str, columns: &[&str]) -> Result<Option<Vec<Vec<String>>>> {
+    let existing = table_columns(src, table)?;
+    if existing.is_empty() {
+        return Ok(None);
+    }
+
+    let select: Vec<String> = columns
+        .iter()
+        .map(|c| {
+            if existing.contains(*c) {
+                format!("\"{}\"", c)
+            } else {
+                format!("'' AS \"{}\"", c)
+            }
+        })
+        .collect();
+
+    let query = format!("SELECT {} FROM {}", select.join(", "), table);
+    let mut stmt = src.prepare(&query)?;
+    let rows = stmt.query_map([], |row| {
+        (0..columns.len())
+            .map(|i| row.get::<_, Value>(i).map(value_to_string))
+            .collect::<rusqlite::Result<Vec<String>>>()
+    })?;
+
+    let mut result = Vec::new();
+    for row in rows {
+        result.push(row?);
+    }
+    Ok(Some(result))
+}
+
+fn table_columns(src: &Connection, table: &str) -> Result<HashSet<String>> {
+    let mut stmt = src.prepare(&format!("PRAGMA table_info({})", table))?;
+    let rows = stmt.query_map([], |row| row.get::<_, String>(1))?;
+
+    let mut columns = HashSet::new();
+    for row in rows {
+        columns.insert(row?);
+    }
+    Ok(columns)
+}
+
+fn value_to_string(value: Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::Integer(i) => i.to_string(),
+        Value::Real(f) => f.to_string(),
+        Value::Text(s) => s,
+        Value::Blob(b) => String::from_utf8_lossy(&b).to_string(),
+    }
+}
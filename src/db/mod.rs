@@ -1,5 +1,8 @@
+pub mod import;
 pub mod queries;
+pub mod rules_io;
 pub mod schema;
+pub mod search;
 pub mod sqlite;
 
-pub use sqlite::Database;
+pub use sqlite::{Database, DbSize};
@@ -1,14 +1,59 @@
 //! Database query definitions
 
+/// The same connection again adds to the count of its row
 pub const INSERT_CONNECTION: &str = r#"
-    INSERT OR REPLACE INTO connections (
+    INSERT INTO connections (
+        time, node, action, protocol, src_ip, src_port, dst_ip, dst_host,
+        dst_port, uid, pid, process, process_args, process_cwd, rule,
+        iface_out, src_network, count
+    ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18)
+    ON CONFLICT(node, action, protocol, src_ip, src_port, dst_ip, dst_port, uid, pid, process, process_args)
+    DO UPDATE SET time = excluded.time, dst_host = excluded.dst_host, process_cwd = excluded.process_cwd,
+        rule = excluded.rule, iface_out = excluded.iface_out,
+        src_network = excluded.src_network, count = count + excluded.count
+"#;
+
+pub const IMPORT_CONNECTION: &str = r#"
+    INSERT OR IGNORE INTO connections (
         time, node, action, protocol, src_ip, src_port, dst_ip, dst_host,
         dst_port, uid, pid, process, process_args, process_cwd, rule
     ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15)
 "#;
 
+// Replacing a rule keeps its hits
 pub const INSERT_RULE: &str = r#"
-    INSERT OR REPLACE INTO rules (
+    INSERT INTO rules (
+        time, node, name, enabled, precedence, action, duration,
+        operator_type, operator_sensitive, operator_operand, operator_data,
+        description, nolog, created
+    ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)
+    ON CONFLICT(node, name) DO UPDATE SET
+        time = excluded.time,
+        enabled = excluded.enabled,
+        precedence = excluded.precedence,
+        action = excluded.action,
+        duration = excluded.duration,
+        operator_type = excluded.operator_type,
+        operator_sensitive = excluded.operator_sensitive,
+        operator_operand = excluded.operator_operand,
+        operator_data = excluded.operator_data,
+        description = excluded.description,
+        nolog = excluded.nolog,
+        created = excluded.created
+"#;
+
+// Rules the daemon sent are stored along with their first hits
+pub const UPSERT_RULE_HITS: &str = r#"
+    INSERT INTO rules (
+        time, node, name, enabled, precedence, action, duration,
+        operator_type, operator_sensitive, operator_operand, operator_data,
+        description, nolog, created, hits, last_hit
+    ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16)
+    ON CONFLICT(node, name) DO UPDATE SET hits = excluded.hits, last_hit = excluded.last_hit
+"#;
+
+pub const IMPORT_RULE: &str = r#"
+    INSERT OR IGNORE INTO rules (
         time, node, name, enabled, precedence, action, duration,
         operator_type, operator_sensitive, operator_operand, operator_data,
         description, nolog, created
@@ -40,14 +85,124 @@ pub const INSERT_ALERT: &str = r#"
     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
 "#;
 
+pub const ACK_ALERT: &str = r#"
+    UPDATE alerts SET status = 1 WHERE node = ?1 AND time = ?2
+"#;
+
+pub const ACK_ALL_ALERTS: &str = r#"
+    UPDATE alerts SET status = 1 WHERE status = 0
+"#;
+
+pub const UPSERT_DNS: &str = r#"
+    INSERT INTO dns (node, host, ip, first_seen, last_seen, count)
+    VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+    ON CONFLICT(node, host, ip) DO UPDATE SET last_seen = excluded.last_seen, count = excluded.count
+"#;
+
+pub const INSERT_ARCHIVED_NODE: &str = r#"
+    INSERT OR REPLACE INTO archived_nodes (addr, time, snapshot) VALUES (?1, ?2, ?3)
+"#;
+
+pub const DELETE_ARCHIVED_NODE: &str = r#"
+    DELETE FROM archived_nodes WHERE addr = ?1
+"#;
+
+pub const SELECT_ARCHIVED_NODES: &str = r#"
+    SELECT snapshot FROM archived_nodes ORDER BY time DESC
+"#;
+
+pub const INSERT_RULE_VERSION: &str = r#"
+    INSERT INTO rule_versions (time, node, name, change, rule) VALUES (?1, ?2, ?3, ?4, ?5)
+"#;
+
+pub const UNDO_RULE_VERSION: &str = r#"
+    UPDATE rule_versions SET undone = 1 WHERE id = ?1
+"#;
+
+// Reverts are never undone themselves, undoing again goes further back
+pub const SELECT_LAST_RULE_VERSION: &str = r#"
+    SELECT id, time, name, change, rule, undone FROM rule_versions
+    WHERE node = ?1 AND undone = 0 AND change != 'reverted'
+    ORDER BY id DESC
+    LIMIT 1
+"#;
+
+pub const SELECT_RULE_VERSIONS: &str = r#"
+    SELECT id, time, name, change, rule, undone FROM rule_versions
+    WHERE node = ?1 AND name = ?2
+    ORDER BY id DESC
+    LIMIT ?3
+"#;
+
+pub const INSERT_RULE_SNAPSHOT: &str = r#"
+    INSERT INTO rule_snapshots (node, label, time, rules) VALUES (?1, ?2, ?3, ?4)
+"#;
+
+pub const SELECT_RULE_SNAPSHOT: &str = r#"
+    SELECT time, rules FROM rule_snapshots WHERE node = ?1 AND label = ?2
+"#;
+
+pub const DELETE_RULE_SNAPSHOT: &str = r#"
+    DELETE FROM rule_snapshots WHERE node = ?1 AND label = ?2
+"#;
+
+pub const INSERT_PANIC: &str = r#"
+    INSERT OR REPLACE INTO panics (node, time, config) VALUES (?1, ?2, ?3)
+"#;
+
+pub const SELECT_PANICS: &str = r#"
+    SELECT node, config FROM panics
+"#;
+
+pub const DELETE_PANIC: &str = r#"
+    DELETE FROM panics WHERE node = ?1
+"#;
+
+pub const INSERT_PROMPT: &str = r#"
+    INSERT INTO prompts (time, node, connection, status) VALUES (?1, ?2, ?3, 'pending')
+"#;
+
+pub const UPDATE_PROMPT_STATUS: &str = r#"
+    UPDATE prompts SET status = ?2 WHERE id = ?1
+"#;
+
+pub const MARK_PENDING_PROMPTS_LOST: &str = r#"
+    UPDATE prompts SET status = 'lost' WHERE status = 'pending'
+"#;
+
+pub const COUNT_PENDING_PROMPTS: &str = r#"
+    SELECT COUNT(*) FROM prompts WHERE status = 'pending'
+"#;
+
+pub const SELECT_MISSED_PROMPTS: &str = r#"
+    SELECT id, time, node, connection, status FROM prompts
+    WHERE status IN ('timed_out', 'lost')
+    ORDER BY time DESC
+    LIMIT ?1
+"#;
+
 pub const SELECT_CONNECTIONS: &str = r#"
     SELECT time, node, action, protocol, src_ip, src_port, dst_ip, dst_host,
-           dst_port, uid, pid, process, process_args, process_cwd, rule
+           dst_port, uid, pid, process, process_args, process_cwd, rule,
+           iface_out, src_network, count
     FROM connections
     ORDER BY time DESC
     LIMIT ?1
 "#;
 
+/// Followed by the search condition and `SEARCH_PAGE`
+pub const SEARCH_CONNECTIONS: &str = r#"
+    SELECT time, node, action, protocol, src_ip, src_port, dst_ip, dst_host,
+           dst_port, uid, pid, process, process_args, process_cwd, rule,
+           iface_out, src_network, count
+    FROM connections
+"#;
+
+pub const SEARCH_PAGE: &str = r#"
+    ORDER BY time DESC
+    LIMIT ? OFFSET ?
+"#;
+
 pub const SELECT_RULES: &str = r#"
     SELECT time, node, name, enabled, precedence, action, duration,
            operator_type, operator_sensitive, operator_operand, operator_data,
@@ -64,29 +219,42 @@ pub const SELECT_ALERTS: &str = r#"
     LIMIT ?1
 "#;
 
+pub const SELECT_RULE_HITS: &str = r#"
+    SELECT node, name, hits, last_hit
+    FROM rules
+    WHERE hits > 0
+"#;
+
+pub const SELECT_DNS: &str = r#"
+    SELECT node, host, ip, first_seen, last_seen, count
+    FROM dns
+    ORDER BY last_seen DESC
+    LIMIT ?1
+"#;
+
 pub const UPDATE_STATS_HOST: &str = r#"
-    INSERT INTO hosts (what, hits) VALUES (?1, 1)
-    ON CONFLICT(what) DO UPDATE SET hits = hits + 1
+    INSERT INTO hosts (what, hits) VALUES (?1, ?2)
+    ON CONFLICT(what) DO UPDATE SET hits = hits + ?2
 "#;
 
 pub const UPDATE_STATS_PROC: &str = r#"
-    INSERT INTO procs (what, hits) VALUES (?1, 1)
-    ON CONFLICT(what) DO UPDATE SET hits = hits + 1
+    INSERT INTO procs (what, hits) VALUES (?1, ?2)
+    ON CONFLICT(what) DO UPDATE SET hits = hits + ?2
 "#;
 
 pub const UPDATE_STATS_ADDR: &str = r#"
-    INSERT INTO addrs (what, hits) VALUES (?1, 1)
-    ON CONFLICT(what) DO UPDATE SET hits = hits + 1
+    INSERT INTO addrs (what, hits) VALUES (?1, ?2)
+    ON CONFLICT(what) DO UPDATE SET hits = hits + ?2
 "#;
 
 pub const UPDATE_STATS_PORT: &str = r#"
-    INSERT INTO ports (what, hits) VALUES (?1, 1)
-    ON CONFLICT(what) DO UPDATE SET hits = hits + 1
+    INSERT INTO ports (what, hits) VALUES (?1, ?2)
+    ON CONFLICT(what) DO UPDATE SET hits = hits + ?2
 "#;
 
 pub const UPDATE_STATS_USER: &str = r#"
-    INSERT INTO users (what, hits) VALUES (?1, 1)
-    ON CONFLICT(what) DO UPDATE SET hits = hits + 1
+    INSERT INTO users (what, hits) VALUES (?1, ?2)
+    ON CONFLICT(what) DO UPDATE SET hits = hits + ?2
 "#;
 
 pub const PURGE_OLD_CONNECTIONS: &str = r#"
@@ -96,3 +264,9 @@ pub const PURGE_OLD_CONNECTIONS: &str = r#"
 pub const PURGE_OLD_ALERTS: &str = r#"
     DELETE FROM alerts WHERE time < ?1
 "#;
+
+pub const VACUUM: &str = r#"
+    PRAGMA wal_checkpoint(TRUNCATE);
+    VACUUM;
+    ANALYZE;
+"#;
@@ -0,0 +1,94 @@
+//! Rules directory of the daemon, one JSON file per rule
+//!
+//! opensnitchd loads its rules from this directory and the official GUI
+//! writes them there, named after `Rule::filename()`.
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use anyhow::Result;
+
+use crate::models::{Rule, RuleDuration};
+
+/// Where opensnitchd keeps its rules
+pub const DEFAULT_RULES_DIR: &str = "/etc/opensnitchd/rules";
+
+/// Maximum number of file errors kept in the report
+const MAX_REPORTED_ERRORS: usize = 20;
+
+/// Outcome of reading or writing a rules directory
+#[derive(Debug, Default, Clone)]
+pub struct RulesDirReport {
+    /// Rules read or written
+    pub rules: usize,
+    /// Rules left out: already on the node, or not kept on disk
+    pub skipped: usize,
+    pub errors: Vec<String>,
+}
+
+impl RulesDirReport {
+    pub fn summary(&self) -> String {
+        format!("Rules: {} copied, {} skipped, {} errors", self.rules, self.skipped, self.errors.len())
+    }
+
+    fn record_error(&mut self, error: String) {
+        if self.errors.len() < MAX_REPORTED_ERRORS {
+            self.errors.push(error);
+        }
+    }
+}
+
+/// Rules of the `.json` files in `dir`, in file name order
+pub fn read_rules_dir(dir: &Path) -> Result<(Vec<Rule>, RulesDirReport)> {
+    if !dir.is_dir() {
+        anyhow::bail!("rules directory not found: {}", dir.display());
+    }
+
+    let mut paths: Vec<_> = std::fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .collect();
+    paths.sort();
+
+    let mut report = RulesDirReport::default();
+    let mut rules = Vec::new();
+    for path in paths {
+        let file = path.file_name().unwrap_or_default().to_string_lossy().to_string();
+        let parsed = std::fs::read_to_string(&path)
+            .map_err(anyhow::Error::from)
+            .and_then(|json| Ok(serde_json::from_str::<Rule>(&json)?));
+        match parsed {
+            Ok(rule) if rule.name.is_empty() => report.record_error(format!("{}: rule without a name", file)),
+            Ok(rule) => rules.push(rule),
+            Err(e) => report.record_error(format!("{}: {}", file, e)),
+        }
+    }
+    report.rules = rules.len();
+    Ok((rules, report))
+}
+
+/// Write `rules` into `dir` the way the daemon does, replacing files of the same name
+///
+/// Only rules lasting forever are kept on disk by the daemon, the others are skipped.
+pub fn write_rules_dir(dir: &Path, rules: &[Rule]) -> Result<RulesDirReport> {
+    std::fs::create_dir_all(dir)?;
+
+    let mut report = RulesDirReport::default();
+    // Names that slug to the same file would overwrite each other
+    let mut written: HashMap<String, &str> = HashMap::new();
+    for rule in rules {
+        if rule.duration != RuleDuration::Always {
+            report.skipped += 1;
+            continue;
+        }
+        let file = rule.filename();
+        if let Some(other) = written.get(&file) {
+            report.record_error(format!("{}: same file as rule {}", rule.name, other));
+            continue;
+        }
+        std::fs::write(dir.join(&file), serde_json::to_string_pretty(rule)?)?;
+        written.insert(file, &rule.name);
+        report.rules += 1;
+    }
+    Ok(report)
+}
@@ -1,6 +1,23 @@
 //! Database schema definitions
 
-pub const SCHEMA_VERSION: i32 = 3;
+pub const SCHEMA_VERSION: i32 = 7;
+
+/// Hit counters, missing from rules tables created by older versions
+pub const ADD_RULE_HITS: &str = r#"
+    ALTER TABLE rules ADD COLUMN hits INTEGER NOT NULL DEFAULT 0;
+    ALTER TABLE rules ADD COLUMN last_hit TEXT;
+"#;
+
+/// Identical events each row stands for, missing from connections tables created by older versions
+pub const ADD_CONNECTION_COUNT: &str = r#"
+    ALTER TABLE connections ADD COLUMN count INTEGER NOT NULL DEFAULT 1;
+"#;
+
+/// Interface and source network, missing from connections tables created by older versions
+pub const ADD_CONNECTION_INTERFACES: &str = r#"
+    ALTER TABLE connections ADD COLUMN iface_out TEXT;
+    ALTER TABLE connections ADD COLUMN src_network TEXT;
+"#;
 
 pub const CREATE_TABLES: &str = r#"
     CREATE TABLE IF NOT EXISTS schema_version (
@@ -24,6 +41,9 @@ pub const CREATE_TABLES: &str = r#"
         process_args TEXT,
         process_cwd TEXT,
         rule TEXT,
+        iface_out TEXT,
+        src_network TEXT,
+        count INTEGER NOT NULL DEFAULT 1,
         UNIQUE(node, action, protocol, src_ip, src_port, dst_ip, dst_port, uid, pid, process, process_args)
     );
 
@@ -40,6 +60,22 @@ pub const CREATE_TABLES: &str = r#"
         last_connection TEXT
     );
 
+    -- Last known state of nodes that went offline, as JSON
+    CREATE TABLE IF NOT EXISTS archived_nodes (
+        addr TEXT PRIMARY KEY,
+        time TEXT NOT NULL,
+        snapshot TEXT NOT NULL
+    );
+
+    -- Prompts written ahead of being shown, with their outcome
+    CREATE TABLE IF NOT EXISTS prompts (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        time TEXT NOT NULL,
+        node TEXT NOT NULL,
+        connection TEXT NOT NULL,
+        status TEXT NOT NULL
+    );
+
     CREATE TABLE IF NOT EXISTS rules (
         id INTEGER PRIMARY KEY AUTOINCREMENT,
         time TEXT NOT NULL,
@@ -56,9 +92,38 @@ pub const CREATE_TABLES: &str = r#"
         description TEXT,
         nolog TEXT,
         created TEXT,
+        hits INTEGER NOT NULL DEFAULT 0,
+        last_hit TEXT,
         UNIQUE(node, name)
     );
 
+    -- Rules as they were before changes made from the TUI, as JSON
+    CREATE TABLE IF NOT EXISTS rule_versions (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        time TEXT NOT NULL,
+        node TEXT NOT NULL,
+        name TEXT NOT NULL,
+        change TEXT NOT NULL,
+        rule TEXT,
+        undone INTEGER NOT NULL DEFAULT 0
+    );
+
+    -- A node's rules saved before a temporary rule set replaced them, as JSON
+    CREATE TABLE IF NOT EXISTS rule_snapshots (
+        node TEXT NOT NULL,
+        label TEXT NOT NULL,
+        time TEXT NOT NULL,
+        rules TEXT NOT NULL,
+        PRIMARY KEY (node, label)
+    );
+
+    -- Daemon configuration of nodes cut off with the panic button, as it was before
+    CREATE TABLE IF NOT EXISTS panics (
+        node TEXT PRIMARY KEY,
+        time TEXT NOT NULL,
+        config TEXT NOT NULL
+    );
+
     CREATE TABLE IF NOT EXISTS alerts (
         id INTEGER PRIMARY KEY AUTOINCREMENT,
         time TEXT NOT NULL,
@@ -71,6 +136,17 @@ pub const CREATE_TABLES: &str = r#"
         status INTEGER DEFAULT 0
     );
 
+    -- Hostnames and the addresses they resolved to, per node
+    CREATE TABLE IF NOT EXISTS dns (
+        node TEXT NOT NULL,
+        host TEXT NOT NULL,
+        ip TEXT NOT NULL,
+        first_seen TEXT NOT NULL,
+        last_seen TEXT NOT NULL,
+        count INTEGER DEFAULT 1,
+        UNIQUE(node, host, ip)
+    );
+
     -- Statistics tables
     CREATE TABLE IF NOT EXISTS hosts (
         what TEXT PRIMARY KEY,
@@ -103,8 +179,15 @@ pub const CREATE_TABLES: &str = r#"
     CREATE INDEX IF NOT EXISTS idx_conn_process ON connections(process);
     CREATE INDEX IF NOT EXISTS idx_conn_rule ON connections(rule);
     CREATE INDEX IF NOT EXISTS idx_conn_node ON connections(node);
+    -- Hosts are searched by substring, which an index doesn't help
+    DROP INDEX IF EXISTS idx_conn_dst_host;
+    CREATE INDEX IF NOT EXISTS idx_conn_dst_port ON connections(dst_port);
+    CREATE INDEX IF NOT EXISTS idx_conn_uid ON connections(uid);
     CREATE INDEX IF NOT EXISTS idx_rules_time ON rules(time);
     CREATE INDEX IF NOT EXISTS idx_rules_node ON rules(node);
+    CREATE INDEX IF NOT EXISTS idx_rule_versions_node ON rule_versions(node, name);
     CREATE INDEX IF NOT EXISTS idx_alerts_time ON alerts(time);
     CREATE INDEX IF NOT EXISTS idx_alerts_node ON alerts(node);
+    CREATE INDEX IF NOT EXISTS idx_prompts_status ON prompts(status);
+    CREATE INDEX IF NOT EXISTS idx_dns_last_seen ON dns(last_seen);
 "#;
@@ -0,0 +1,159 @@
+//! Search of the stored connections
+//!
+//! A query is made of words and `key:value` terms, e.g.
+//! `firefox port:443 action:deny since:2h`. Words match the process path,
+//! destination host or IP; every word and term has to match.
+
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use rusqlite::types::Value;
+
+use crate::models::Event;
+
+/// Connections fetched per page of results
+pub const SEARCH_PAGE: usize = 100;
+
+/// A stored time as UTC `YYYY-MM-DD HH:MM:SS`. Daemons send their local
+/// time in that format, without a zone, events made here are RFC 3339.
+const STORED_TIME_UTC: &str = "CASE WHEN time LIKE '____-__-__T%' THEN datetime(time) ELSE datetime(time, 'utc') END";
+
+/// Parsed search of the connections table
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ConnectionQuery {
+    /// Substrings of the process path
+    pub process: Vec<String>,
+    /// Substrings of the destination host or IP
+    pub host: Vec<String>,
+    pub port: Option<u16>,
+    pub user: Option<u32>,
+    /// allow, deny or reject
+    pub action: Option<String>,
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+    /// Substrings of the process path, host or IP
+    pub words: Vec<String>,
+    /// Node the connections came from, any when unset
+    pub node: Option<String>,
+}
+
+impl ConnectionQuery {
+    /// Parse a query typed in the search bar, with `now` for relative times
+    ///
+    /// Times are dates (`2024-03-01`), local date and times (`2024-03-01T14:30`)
+    /// or ages (`30m`, `2h`, `7d`). Other `key:value` words, like IPv6
+    /// addresses, are searched as they are.
+    pub fn parse(query: &str, now: DateTime<Utc>) -> Result<Self, String> {
+        let mut parsed = Self::default();
+        for word in query.split_whitespace() {
+            let Some((key, value)) = word.split_once(':').filter(|(_, v)| !v.is_empty()) else {
+                parsed.words.push(word.to_string());
+                continue;
+            };
+            match key.to_lowercase().as_str() {
+                "process" | "proc" => parsed.process.push(value.to_string()),
+                "host" | "ip" => parsed.host.push(value.to_string()),
+                "port" => parsed.port = Some(value.parse().map_err(|_| format!("invalid port: {}", value))?),
+                "user" | "uid" => parsed.user = Some(value.parse().map_err(|_| format!("invalid user id: {}", value))?),
+                "action" => match value.to_lowercase().as_str() {
+                    action @ ("allow" | "deny" | "reject") => parsed.action = Some(action.to_string()),
+                    _ => return Err(format!("invalid action: {}", value)),
+                },
+                "since" => parsed.since = Some(parse_time(value, now)?),
+                "until" => parsed.until = Some(parse_time(value, now)?),
+                _ => parsed.words.push(word.to_string()),
+            }
+        }
+        Ok(parsed)
+    }
+
+    /// SQL condition and its parameters, matching everything when empty
+    pub(crate) fn where_clause(&self) -> (String, Vec<Value>) {
+        let mut conditions = Vec::new();
+        let mut params = Vec::new();
+        let mut like = |columns: &[&str], value: &str, params: &mut Vec<Value>| {
+            let any: Vec<String> = columns.iter().map(|c| format!("{} LIKE ? ESCAPE '\\'", c)).collect();
+            conditions.push(format!("({})", any.join(" OR ")));
+            params.extend(columns.iter().map(|_| Value::Text(format!("%{}%", escape_like(value)))));
+        };
+        for process in &self.process {
+            like(&["process"], process, &mut params);
+        }
+        for host in &self.host {
+            like(&["dst_host", "dst_ip"], host, &mut params);
+        }
+        for word in &self.words {
+            like(&["process", "dst_host", "dst_ip"], word, &mut params);
+        }
+
+        let mut equal = |column: &str, value: String| {
+            conditions.push(format!("{} = ?", column));
+            params.push(Value::Text(value));
+        };
+        if let Some(port) = self.port {
+            equal("dst_port", port.to_string());
+        }
+        if let Some(user) = self.user {
+            equal("uid", user.to_string());
+        }
+        if let Some(action) = &self.action {
+            equal("action", action.clone());
+        }
+        if let Some(since) = self.since {
+            conditions.push(format!("{} >= ?", STORED_TIME_UTC));
+            params.push(Value::Text(since.format("%Y-%m-%d %H:%M:%S").to_string()));
+        }
+        if let Some(until) = self.until {
+            conditions.push(format!("{} < ?", STORED_TIME_UTC));
+            params.push(Value::Text(until.format("%Y-%m-%d %H:%M:%S").to_string()));
+        }
+
+        // Connections stored before the node was recorded have none
+        if let Some(node) = &self.node {
+            conditions.push("(node = ? OR node = '')".to_string());
+            params.push(Value::Text(node.clone()));
+        }
+
+        if conditions.is_empty() {
+            ("1".to_string(), params)
+        } else {
+            (conditions.join(" AND "), params)
+        }
+    }
+}
+
+/// One page of matching connections, newest first
+#[derive(Debug, Default, Clone)]
+pub struct SearchPage {
+    pub events: Vec<Event>,
+    /// Matches before this page
+    pub offset: usize,
+    /// All matches of the query
+    pub total: usize,
+}
+
+fn escape_like(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_")
+}
+
+fn parse_time(value: &str, now: DateTime<Utc>) -> Result<DateTime<Utc>, String> {
+    let invalid = || format!("invalid time: {}", value);
+    let local = |naive: NaiveDateTime| {
+        Local.from_local_datetime(&naive).earliest().map(|t| t.with_timezone(&Utc)).ok_or_else(invalid)
+    };
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        return local(date.and_hms_opt(0, 0, 0).ok_or_else(invalid)?);
+    }
+    if let Ok(naive) = NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M") {
+        return local(naive);
+    }
+
+    let split = value.find(|c: char| !c.is_ascii_digit()).ok_or_else(invalid)?;
+    let amount: i64 = value[..split].parse().map_err(|_| invalid())?;
+    let age = match &value[split..] {
+        "m" => chrono::Duration::minutes(amount),
+        "h" => chrono::Duration::hours(amount),
+        "d" => chrono::Duration::days(amount),
+        "w" => chrono::Duration::weeks(amount),
+        _ => return Err(invalid()),
+    };
+    Ok(now - age)
+}
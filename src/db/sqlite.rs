@@ -2,25 +2,80 @@
 
 use anyhow::Result;
 use chrono::{DateTime, Utc};
-use rusqlite::{params, Connection, Row};
+use rusqlite::{params, params_from_iter, types::Value, Connection, Row};
 use std::collections::HashMap;
 use std::sync::Mutex;
 
 use crate::models::{
-    Alert, AlertAction, AlertData, AlertPriority, AlertType, AlertWhat,
-    Event, Operator, OperatorType, Rule, RuleAction, RuleDuration,
+    Alert, AlertAction, AlertData, AlertPriority, AlertType, AlertWhat, DnsEntry,
+    Event, MissedPrompt, Node, Operator, OperatorType, PromptOutcome, Rule, RuleAction, RuleChange, RuleDuration, RuleHits,
+    RuleSnapshot, RuleVersion,
 };
 
+use super::import::{self, ImportReport};
+use super::search::{ConnectionQuery, SearchPage};
 use super::{queries, schema};
 
+/// Size of the database and its largest tables
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DbSize {
+    pub bytes: u64,
+    /// Unused pages a vacuum gives back
+    pub free_bytes: u64,
+    pub connections: u64,
+    pub alerts: u64,
+    pub rules: u64,
+    pub dns: u64,
+}
+
 /// SQLite database wrapper
 pub struct Database {
     conn: Mutex<Connection>,
+    /// File that couldn't be opened, kept in memory meanwhile
+    unavailable: Mutex<Option<String>>,
 }
 
 impl Database {
     /// Open or create database at the specified path
     pub fn open(path: &str) -> Result<Self> {
+        Ok(Self {
+            conn: Mutex::new(Self::connect(path)?),
+            unavailable: Mutex::new(None),
+        })
+    }
+
+    /// Open the database at `path`, or one in memory with the reason when
+    /// that fails, until `reopen` succeeds
+    pub fn open_or_memory(path: &str) -> (Self, Option<anyhow::Error>) {
+        match Self::connect(path) {
+            Ok(conn) => (
+                Self {
+                    conn: Mutex::new(conn),
+                    unavailable: Mutex::new(None),
+                },
+                None,
+            ),
+            Err(e) => {
+                let db = Self::open(":memory:").expect("in-memory database");
+                *db.unavailable.lock().unwrap() = Some(path.to_string());
+                (db, Some(e))
+            }
+        }
+    }
+
+    /// Try again to open the file the database couldn't be opened at,
+    /// Ok once it isn't in memory instead
+    pub fn reopen(&self) -> Result<()> {
+        let mut unavailable = self.unavailable.lock().unwrap();
+        if let Some(path) = unavailable.as_deref() {
+            *self.conn.lock().unwrap() = Self::connect(path)?;
+            tracing::info!("Database {} opened", path);
+            *unavailable = None;
+        }
+        Ok(())
+    }
+
+    fn connect(path: &str) -> Result<Connection> {
         let conn = if path == ":memory:" {
             Connection::open_in_memory()?
         } else {
@@ -36,22 +91,44 @@ impl Database {
 
         // Create tables
         conn.execute_batch(schema::CREATE_TABLES)?;
+        Self::add_missing_columns(&conn)?;
 
-        Ok(Self {
-            conn: Mutex::new(conn),
-        })
+        Ok(conn)
+    }
+
+    /// Bring tables created by older versions up to date
+    fn add_missing_columns(conn: &Connection) -> Result<()> {
+        let mut stmt = conn.prepare("PRAGMA table_info(rules)")?;
+        let columns = stmt
+            .query_map([], |row| row.get::<_, String>(1))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        if !columns.iter().any(|c| c == "hits") {
+            conn.execute_batch(schema::ADD_RULE_HITS)?;
+        }
+        let mut stmt = conn.prepare("PRAGMA table_info(connections)")?;
+        let columns = stmt
+            .query_map([], |row| row.get::<_, String>(1))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        if !columns.iter().any(|c| c == "iface_out") {
+            conn.execute_batch(schema::ADD_CONNECTION_INTERFACES)?;
+        }
+        if !columns.iter().any(|c| c == "count") {
+            conn.execute_batch(schema::ADD_CONNECTION_COUNT)?;
+        }
+        Ok(())
     }
 
     /// Insert a connection event
     pub fn insert_connection(&self, event: &Event) -> Result<()> {
         let conn = self.conn.lock().unwrap();
         let c = &event.connection;
+        let count = event.count.max(1) as i64;
 
         conn.execute(
             queries::INSERT_CONNECTION,
             params![
                 event.time,
-                "", // node - set by caller
+                event.node,
                 event.rule.as_ref().map(|r| r.action.to_string()).unwrap_or_default(),
                 c.protocol,
                 c.src_ip,
@@ -65,19 +142,22 @@ impl Database {
                 c.process_args.join(" "),
                 c.process_cwd,
                 event.rule.as_ref().map(|r| &r.name).unwrap_or(&String::new()),
+                c.interface,
+                c.src_network,
+                count,
             ],
         )?;
 
-        // Update statistics
+        // Update statistics, a coalesced event counts as many as it merged
         if !c.dst_host.is_empty() {
-            conn.execute(queries::UPDATE_STATS_HOST, params![c.dst_host])?;
+            conn.execute(queries::UPDATE_STATS_HOST, params![c.dst_host, count])?;
         }
-        conn.execute(queries::UPDATE_STATS_PROC, params![c.process_path])?;
+        conn.execute(queries::UPDATE_STATS_PROC, params![c.process_path, count])?;
         if !c.dst_ip.is_empty() {
-            conn.execute(queries::UPDATE_STATS_ADDR, params![c.dst_ip])?;
+            conn.execute(queries::UPDATE_STATS_ADDR, params![c.dst_ip, count])?;
         }
-        conn.execute(queries::UPDATE_STATS_PORT, params![c.dst_port.to_string()])?;
-        conn.execute(queries::UPDATE_STATS_USER, params![c.user_id.to_string()])?;
+        conn.execute(queries::UPDATE_STATS_PORT, params![c.dst_port.to_string(), count])?;
+        conn.execute(queries::UPDATE_STATS_USER, params![c.user_id.to_string(), count])?;
 
         Ok(())
     }
@@ -109,6 +189,58 @@ impl Database {
         Ok(())
     }
 
+    /// Store the hits of a rule, with the rule itself when not stored yet
+    pub fn store_rule_hits(&self, node: &str, rule: &Rule, hits: &RuleHits) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+
+        conn.execute(
+            queries::UPSERT_RULE_HITS,
+            params![
+                Utc::now().to_rfc3339(),
+                node,
+                rule.name,
+                rule.enabled.to_string(),
+                rule.precedence.to_string(),
+                rule.action.to_string(),
+                rule.duration.to_string(),
+                rule.operator.op_type.to_string(),
+                rule.operator.sensitive.to_string(),
+                rule.operator.operand,
+                rule.operator.data,
+                rule.description,
+                rule.nolog.to_string(),
+                rule.created.to_rfc3339(),
+                hits.count as i64,
+                hits.last_hit.map(|t| t.to_rfc3339()),
+            ],
+        )?;
+
+        Ok(())
+    }
+
+    /// Load the hits of every rule matched at least once, by node and rule name
+    pub fn select_rule_hits(&self) -> Result<Vec<(String, String, RuleHits)>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(queries::SELECT_RULE_HITS)?;
+        let rows = stmt.query_map([], |row| {
+            let last_hit = row
+                .get::<_, Option<String>>(3)?
+                .and_then(|t| DateTime::parse_from_rfc3339(&t).ok())
+                .map(|t| t.with_timezone(&Utc));
+            let hits = RuleHits {
+                count: row.get::<_, i64>(2)?.max(0) as u64,
+                last_hit,
+            };
+            Ok((row.get(0)?, row.get(1)?, hits))
+        })?;
+
+        let mut hits = Vec::new();
+        for row in rows {
+            hits.push(row?);
+        }
+        Ok(hits)
+    }
+
     /// Update an existing rule
     pub fn update_rule(&self, node: &str, rule: &Rule) -> Result<()> {
         let conn = self.conn.lock().unwrap();
@@ -163,6 +295,250 @@ impl Database {
         Ok(())
     }
 
+    /// Mark the alert of a node received at `time` as acknowledged
+    pub fn ack_alert(&self, node: &str, time: &DateTime<Utc>) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(queries::ACK_ALERT, params![node, time.to_rfc3339()])?;
+        Ok(())
+    }
+
+    /// Mark every stored alert as acknowledged
+    pub fn ack_all_alerts(&self) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(queries::ACK_ALL_ALERTS, [])?;
+        Ok(())
+    }
+
+    /// Insert a DNS mapping or update when it was last seen
+    pub fn upsert_dns(&self, entry: &DnsEntry) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            queries::UPSERT_DNS,
+            params![
+                entry.node,
+                entry.host,
+                entry.ip,
+                entry.first_seen.to_rfc3339(),
+                entry.last_seen.to_rfc3339(),
+                entry.count as i64,
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Load the most recently seen DNS mappings
+    pub fn select_dns(&self, limit: i64) -> Result<Vec<DnsEntry>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(queries::SELECT_DNS)?;
+        let time = |row: &Row, i| {
+            row.get::<_, String>(i)
+                .ok()
+                .and_then(|t| DateTime::parse_from_rfc3339(&t).ok())
+                .map(|t| t.with_timezone(&Utc))
+                .unwrap_or_default()
+        };
+        let rows = stmt.query_map(params![limit], |row| {
+            Ok(DnsEntry {
+                node: row.get(0)?,
+                host: row.get(1)?,
+                ip: row.get(2)?,
+                first_seen: time(row, 3),
+                last_seen: time(row, 4),
+                count: row.get::<_, i64>(5)?.max(0) as u64,
+            })
+        })?;
+
+        let mut entries = Vec::new();
+        for row in rows {
+            entries.push(row?);
+        }
+        Ok(entries)
+    }
+
+    /// Keep a snapshot of a node that went offline
+    pub fn archive_node(&self, node: &Node) -> Result<()> {
+        let snapshot = serde_json::to_string(node)?;
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            queries::INSERT_ARCHIVED_NODE,
+            params![node.addr, node.last_seen.to_rfc3339(), snapshot],
+        )?;
+        Ok(())
+    }
+
+    /// Forget the snapshot of a node, once it is back or no longer wanted
+    pub fn delete_archived_node(&self, addr: &str) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(queries::DELETE_ARCHIVED_NODE, params![addr])?;
+        Ok(())
+    }
+
+    /// Load archived node snapshots, most recently seen first
+    pub fn select_archived_nodes(&self) -> Result<Vec<Node>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(queries::SELECT_ARCHIVED_NODES)?;
+        let rows = stmt.query_map([], |row| row.get::<_, String>(0))?;
+
+        let mut nodes = Vec::new();
+        for row in rows {
+            match serde_json::from_str(&row?) {
+                Ok(node) => nodes.push(node),
+                Err(e) => tracing::warn!("Skipping unreadable archived node: {}", e),
+            }
+        }
+        Ok(nodes)
+    }
+
+    /// Keep a rule as it was before a change, None when it didn't exist
+    pub fn insert_rule_version(&self, node: &str, name: &str, change: RuleChange, previous: Option<&Rule>) -> Result<()> {
+        let json = previous.map(serde_json::to_string).transpose()?;
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            queries::INSERT_RULE_VERSION,
+            params![Utc::now().to_rfc3339(), node, name, change.to_string(), json],
+        )?;
+        Ok(())
+    }
+
+    pub fn mark_rule_version_undone(&self, id: i64) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(queries::UNDO_RULE_VERSION, params![id])?;
+        Ok(())
+    }
+
+    /// The latest change on a node still to undo
+    pub fn last_rule_version(&self, node: &str) -> Result<Option<RuleVersion>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(queries::SELECT_LAST_RULE_VERSION)?;
+        let mut versions = stmt.query_map(params![node], Self::row_to_rule_version)?;
+        Ok(versions.next().transpose()?)
+    }
+
+    /// Changes made to a rule, most recent first
+    pub fn select_rule_versions(&self, node: &str, name: &str, limit: i64) -> Result<Vec<RuleVersion>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(queries::SELECT_RULE_VERSIONS)?;
+        let versions = stmt.query_map(params![node, name, limit], Self::row_to_rule_version)?;
+        Ok(versions.collect::<rusqlite::Result<_>>()?)
+    }
+
+    /// Save a node's rules under a label, failing when one is saved already
+    pub fn insert_rule_snapshot(&self, node: &str, label: &str, rules: &[Rule]) -> Result<()> {
+        let json = serde_json::to_string(rules)?;
+        let conn = self.conn.lock().unwrap();
+        conn.execute(queries::INSERT_RULE_SNAPSHOT, params![node, label, Utc::now().to_rfc3339(), json])?;
+        Ok(())
+    }
+
+    pub fn select_rule_snapshot(&self, node: &str, label: &str) -> Result<Option<RuleSnapshot>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(queries::SELECT_RULE_SNAPSHOT)?;
+        let mut rows = stmt.query_map(params![node, label], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })?;
+        let Some((time, rules)) = rows.next().transpose()? else {
+            return Ok(None);
+        };
+        Ok(Some(RuleSnapshot {
+            time: DateTime::parse_from_rfc3339(&time)
+                .map(|t| t.with_timezone(&Utc))
+                .unwrap_or_else(|_| Utc::now()),
+            rules: serde_json::from_str(&rules)?,
+        }))
+    }
+
+    pub fn delete_rule_snapshot(&self, node: &str, label: &str) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(queries::DELETE_RULE_SNAPSHOT, params![node, label])?;
+        Ok(())
+    }
+
+    /// Keep the configuration a node had before the panic button cut it off
+    pub fn insert_panic(&self, node: &str, config: &str) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(queries::INSERT_PANIC, params![node, Utc::now().to_rfc3339(), config])?;
+        Ok(())
+    }
+
+    /// Nodes still in panic with their configuration from before
+    pub fn select_panics(&self) -> Result<HashMap<String, String>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(queries::SELECT_PANICS)?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+
+    pub fn delete_panic(&self, node: &str) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(queries::DELETE_PANIC, params![node])?;
+        Ok(())
+    }
+
+    /// Write a prompt down before it is shown, returns its id
+    pub fn insert_prompt(&self, node: &str, connection: &crate::models::Connection) -> Result<i64> {
+        let json = serde_json::to_string(connection)?;
+        let conn = self.conn.lock().unwrap();
+        conn.execute(queries::INSERT_PROMPT, params![Utc::now().to_rfc3339(), node, json])?;
+        Ok(conn.last_insert_rowid())
+    }
+
+    pub fn set_prompt_outcome(&self, id: i64, outcome: PromptOutcome) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(queries::UPDATE_PROMPT_STATUS, params![id, outcome.to_string()])?;
+        Ok(())
+    }
+
+    /// Mark prompts still pending from a previous run as lost, returns how many
+    pub fn mark_pending_prompts_lost(&self) -> Result<usize> {
+        let conn = self.conn.lock().unwrap();
+        Ok(conn.execute(queries::MARK_PENDING_PROMPTS_LOST, [])?)
+    }
+
+    /// Prompts waiting for an answer, the one on screen included
+    pub fn pending_prompt_count(&self) -> Result<i64> {
+        let conn = self.conn.lock().unwrap();
+        Ok(conn.query_row(queries::COUNT_PENDING_PROMPTS, [], |row| row.get(0))?)
+    }
+
+    /// Timed out and lost prompts not followed up yet, most recent first
+    pub fn select_missed_prompts(&self, limit: i64) -> Result<Vec<MissedPrompt>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(queries::SELECT_MISSED_PROMPTS)?;
+        let rows = stmt.query_map(params![limit], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, String>(3)?,
+                row.get::<_, String>(4)?,
+            ))
+        })?;
+
+        let mut prompts = Vec::new();
+        for row in rows {
+            let (id, time, node_addr, connection, status) = row?;
+            match serde_json::from_str(&connection) {
+                Ok(connection) => prompts.push(MissedPrompt {
+                    id,
+                    time: DateTime::parse_from_rfc3339(&time)
+                        .map(|t| t.with_timezone(&Utc))
+                        .unwrap_or_else(|_| Utc::now()),
+                    node_addr,
+                    connection,
+                    outcome: PromptOutcome::from(status.as_str()),
+                }),
+                Err(e) => tracing::warn!("Skipping unreadable prompt {}: {}", id, e),
+            }
+        }
+        Ok(prompts)
+    }
+
+    /// Import connections and rules from the official GUI database
+    pub fn import_gui_database(&self, path: &str) -> Result<ImportReport> {
+        let mut conn = self.conn.lock().unwrap();
+        import::import_gui_database(&mut conn, path)
+    }
+
     /// Purge old connections
     pub fn purge_connections_before(&self, before: &str) -> Result<usize> {
         let conn = self.conn.lock().unwrap();
@@ -177,6 +553,33 @@ impl Database {
         Ok(count)
     }
 
+    /// Reclaim the space left by purges and refresh the query planner's
+    /// statistics. Checkpoints the WAL first so the file really shrinks.
+    pub fn vacuum(&self) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute_batch(queries::VACUUM)?;
+        Ok(())
+    }
+
+    /// Size of the database file and the rows it keeps
+    pub fn size(&self) -> Result<DbSize> {
+        let conn = self.conn.lock().unwrap();
+        let pragma = |name: &str| conn.query_row(&format!("PRAGMA {}", name), [], |row| row.get::<_, i64>(0));
+        let page_size = pragma("page_size")? as u64;
+        let count = |table: &str| {
+            conn.query_row(&format!("SELECT COUNT(*) FROM {}", table), [], |row| row.get::<_, i64>(0))
+                .map(|n| n as u64)
+        };
+        Ok(DbSize {
+            bytes: pragma("page_count")? as u64 * page_size,
+            free_bytes: pragma("freelist_count")? as u64 * page_size,
+            connections: count("connections")?,
+            alerts: count("alerts")?,
+            rules: count("rules")?,
+            dns: count("dns")?,
+        })
+    }
+
     /// Get connection count
     pub fn connection_count(&self) -> Result<i64> {
         let conn = self.conn.lock().unwrap();
@@ -225,6 +628,33 @@ impl Database {
         Ok(events)
     }
 
+    /// One page of the connections matching `query`, newest first
+    pub fn search_connections(&self, query: &ConnectionQuery, offset: usize, limit: usize) -> Result<SearchPage> {
+        let conn = self.conn.lock().unwrap();
+        let (condition, mut values) = query.where_clause();
+
+        let total: i64 = conn.query_row(
+            &format!("SELECT COUNT(*) FROM connections WHERE {}", condition),
+            params_from_iter(values.iter()),
+            |row| row.get(0),
+        )?;
+
+        values.push(Value::Integer(limit as i64));
+        values.push(Value::Integer(offset as i64));
+        let mut stmt = conn.prepare(&format!("{} WHERE {} {}", queries::SEARCH_CONNECTIONS, condition, queries::SEARCH_PAGE))?;
+        let rows = stmt.query_map(params_from_iter(values.iter()), |row| Ok(Self::row_to_event(row)))?;
+
+        let mut events = Vec::new();
+        for row in rows {
+            events.push(row?);
+        }
+        Ok(SearchPage {
+            events,
+            offset,
+            total: total as usize,
+        })
+    }
+
     /// Load rules for a specific node from database
     pub fn select_rules(&self, node: &str) -> Result<Vec<Rule>> {
         let conn = self.conn.lock().unwrap();
@@ -301,9 +731,30 @@ impl Database {
         Ok(map)
     }
 
+    fn row_to_rule_version(row: &Row) -> rusqlite::Result<RuleVersion> {
+        let time: String = row.get(1)?;
+        let change: String = row.get(3)?;
+        let rule: Option<String> = row.get(4)?;
+        // An unreadable version would be taken for a rule that didn't exist
+        let previous = rule
+            .map(|json| serde_json::from_str(&json))
+            .transpose()
+            .map_err(|e| rusqlite::Error::FromSqlConversionFailure(4, rusqlite::types::Type::Text, Box::new(e)))?;
+        Ok(RuleVersion {
+            id: row.get(0)?,
+            time: DateTime::parse_from_rfc3339(&time)
+                .map(|t| t.with_timezone(&Utc))
+                .unwrap_or_else(|_| Utc::now()),
+            name: row.get(2)?,
+            change: RuleChange::from(change.as_str()),
+            previous,
+            undone: row.get::<_, i64>(5)? != 0,
+        })
+    }
+
     fn row_to_event(row: &Row) -> Event {
         let time: String = row.get(0).unwrap_or_default();
-        let _node: String = row.get(1).unwrap_or_default();
+        let node: String = row.get(1).unwrap_or_default();
         let action: String = row.get(2).unwrap_or_default();
         let protocol: String = row.get(3).unwrap_or_default();
         let src_ip: String = row.get(4).unwrap_or_default();
@@ -317,6 +768,9 @@ impl Database {
         let process_args: String = row.get(12).unwrap_or_default();
         let process_cwd: String = row.get(13).unwrap_or_default();
         let rule_name: String = row.get(14).unwrap_or_default();
+        let iface_out: String = row.get(15).unwrap_or_default();
+        let src_network: String = row.get(16).unwrap_or_default();
+        let count: i64 = row.get(17).unwrap_or(1);
 
         let connection = crate::models::Connection {
             protocol,
@@ -342,6 +796,9 @@ impl Database {
                 .ok(),
             action: Some(action),
             rule_name: if rule_name.is_empty() { None } else { Some(rule_name) },
+            interface: iface_out,
+            src_network,
+            enriched: Vec::new(),
         };
 
         Event {
@@ -349,6 +806,9 @@ impl Database {
             connection,
             rule: None,
             unix_nano: 0,
+            node,
+            count: count.max(1) as u64,
+            restored: false,
         }
     }
 
@@ -387,6 +847,7 @@ impl Database {
                 .map(|dt| dt.with_timezone(&Utc))
                 .unwrap_or_else(|_| Utc::now()),
             updated: None,
+            expires: None,
         }
     }
 
@@ -445,6 +906,7 @@ impl Database {
                 .map(|dt| dt.with_timezone(&Utc))
                 .unwrap_or_else(|_| Utc::now()),
             acknowledged: status != 0,
+            restored: false,
         }
     }
 }
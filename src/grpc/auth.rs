@@ -0,0 +1,71 @@
+//! Shared-secret authentication for daemon requests
+//!
+//! opensnitchd itself never sends an `authorization` token: the check only
+//! lets through requests relayed by a proxy that adds it, or sent by
+//! clients of our own. Daemons connecting directly are authenticated with
+//! TLS client certificates instead (`tls_client_ca`).
+
+use std::sync::Arc;
+
+use tokio::sync::mpsc;
+use tonic::service::Interceptor;
+use tonic::{Request, Status};
+
+use crate::app::state::AppMessage;
+use crate::models::node::AuthStatus;
+
+/// Metadata key carrying the token, as `Bearer <token>` or the bare token
+const AUTH_METADATA_KEY: &str = "authorization";
+
+/// Checks every incoming request against the configured token and tags
+/// accepted requests with their AuthStatus
+#[derive(Clone)]
+pub struct AuthInterceptor {
+    token: Option<Arc<str>>,
+    state_tx: mpsc::Sender<AppMessage>,
+}
+
+impl AuthInterceptor {
+    pub fn new(token: Option<String>, state_tx: mpsc::Sender<AppMessage>) -> Self {
+        Self {
+            token: token.map(Arc::from),
+            state_tx,
+        }
+    }
+}
+
+impl Interceptor for AuthInterceptor {
+    fn call(&mut self, mut request: Request<()>) -> Result<Request<()>, Status> {
+        let Some(token) = &self.token else {
+            request.extensions_mut().insert(AuthStatus::None);
+            return Ok(request);
+        };
+
+        let provided = request
+            .metadata()
+            .get(AUTH_METADATA_KEY)
+            .and_then(|v| v.to_str().ok())
+            .map(|v| v.strip_prefix("Bearer ").unwrap_or(v));
+
+        if provided.is_some_and(|p| constant_time_eq(p.as_bytes(), token.as_bytes())) {
+            request.extensions_mut().insert(AuthStatus::Verified);
+            return Ok(request);
+        }
+
+        let addr = request
+            .remote_addr()
+            .map(|a| a.to_string())
+            .unwrap_or_else(|| "unknown".to_string());
+        let _ = self.state_tx.try_send(AppMessage::NodeAuthRejected { addr });
+
+        Err(Status::unauthenticated("missing or invalid auth token"))
+    }
+}
+
+/// Compare without short-circuiting on the first differing byte
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
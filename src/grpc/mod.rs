@@ -1,3 +1,4 @@
+pub mod auth;
 pub mod notifications;
 pub mod server;
 pub mod service;
@@ -1,8 +1,15 @@
 //! Notification handling for daemon communication
 
+use std::collections::VecDeque;
+
+use chrono::{DateTime, Utc};
+
 use crate::grpc::proto;
 use crate::models;
 
+/// Entries kept in the log of sent changes
+pub const MAX_SENT_NOTIFICATIONS: usize = 200;
+
 /// Actions that can be sent to daemons via notifications
 #[derive(Debug, Clone)]
 pub enum NotificationAction {
@@ -15,6 +22,9 @@ pub enum NotificationAction {
     EnableRule(String),
     DisableRule(String),
     DeleteRule(String),
+    /// Several rules switched together, in one notification
+    EnableRules(Vec<models::Rule>),
+    DisableRules(Vec<models::Rule>),
     ChangeRule(models::Rule),
     SetLogLevel(u32),
     Stop,
@@ -35,6 +45,8 @@ impl NotificationAction {
             Self::EnableRule(_) => proto::Action::EnableRule as i32,
             Self::DisableRule(_) => proto::Action::DisableRule as i32,
             Self::DeleteRule(_) => proto::Action::DeleteRule as i32,
+            Self::EnableRules(_) => proto::Action::EnableRule as i32,
+            Self::DisableRules(_) => proto::Action::DisableRule as i32,
             Self::ChangeRule(_) => proto::Action::ChangeRule as i32,
             Self::SetLogLevel(_) => proto::Action::LogLevel as i32,
             Self::Stop => proto::Action::Stop as i32,
@@ -62,10 +74,83 @@ impl NotificationAction {
         }
     }
 
+    /// What was asked, for the log of sent changes
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::EnableInterception => "enable interception",
+            Self::DisableInterception => "disable interception",
+            Self::EnableFirewall => "enable firewall",
+            Self::DisableFirewall => "disable firewall",
+            Self::ReloadFwRules => "reload firewall",
+            Self::ChangeConfig(_) => "change config",
+            Self::EnableRule(_) => "enable rule",
+            Self::DisableRule(_) => "disable rule",
+            Self::DeleteRule(_) => "delete rule",
+            Self::EnableRules(_) => "enable rules",
+            Self::DisableRules(_) => "disable rules",
+            Self::ChangeRule(_) => "change rule",
+            Self::SetLogLevel(_) => "log level",
+            Self::Stop => "stop daemon",
+            Self::TaskStart { .. } => "start task",
+            Self::TaskStop { .. } => "stop task",
+        }
+    }
+
+    /// One line on what the payload does
+    pub fn summary(&self) -> String {
+        match self {
+            Self::ChangeConfig(config) => {
+                // Only the keys, the values are in the payload
+                match serde_json::from_str::<serde_json::Map<String, serde_json::Value>>(config) {
+                    Ok(map) => map.keys().cloned().collect::<Vec<_>>().join(", "),
+                    Err(_) => format!("{} bytes", config.len()),
+                }
+            }
+            Self::EnableRule(name) | Self::DisableRule(name) | Self::DeleteRule(name) => name.clone(),
+            Self::EnableRules(rules) | Self::DisableRules(rules) => {
+                rules.iter().map(|r| r.name.as_str()).collect::<Vec<_>>().join(", ")
+            }
+            Self::ChangeRule(rule) => format!(
+                "{}: {} {} if {} {}{}",
+                rule.name,
+                rule.action,
+                rule.duration,
+                rule.operator.operand,
+                rule.operator.data,
+                if rule.enabled { "" } else { " (disabled)" }
+            ),
+            Self::SetLogLevel(level) => level.to_string(),
+            Self::TaskStart { name, .. } | Self::TaskStop { name } => name.clone(),
+            _ => String::new(),
+        }
+    }
+
+    /// Names of the rules the action changes on the daemon
+    pub fn rule_names(&self) -> Vec<String> {
+        match self {
+            Self::EnableRule(name) | Self::DisableRule(name) | Self::DeleteRule(name) => vec![name.clone()],
+            Self::EnableRules(rules) | Self::DisableRules(rules) => rules.iter().map(|r| r.name.clone()).collect(),
+            Self::ChangeRule(rule) => vec![rule.name.clone()],
+            _ => Vec::new(),
+        }
+    }
+
+    /// What the action does to a rule that existed before or not
+    pub fn rule_change(&self, existed: bool) -> models::RuleChange {
+        match self {
+            Self::DeleteRule(_) => models::RuleChange::Deleted,
+            _ if !existed => models::RuleChange::Added,
+            Self::EnableRule(_) | Self::EnableRules(_) => models::RuleChange::Enabled,
+            Self::DisableRule(_) | Self::DisableRules(_) => models::RuleChange::Disabled,
+            _ => models::RuleChange::Modified,
+        }
+    }
+
     /// Get rules to include in notification (for rule changes)
     pub fn rules(&self) -> Vec<models::Rule> {
         match self {
             Self::ChangeRule(rule) => vec![rule.clone()],
+            Self::EnableRules(rules) | Self::DisableRules(rules) => rules.clone(),
             _ => Vec::new(),
         }
     }
@@ -90,6 +175,127 @@ pub fn create_notification(
     }
 }
 
+/// How far a sent change got
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Delivery {
+    /// The node had no open notification stream
+    NotSent,
+    /// Sent, no reply yet
+    Pending,
+    Ok(String),
+    Error(String),
+}
+
+impl Delivery {
+    /// From the code and data of a daemon reply
+    pub fn from_reply(code: i32, data: String) -> Self {
+        if code == proto::NotificationReplyCode::Ok as i32 {
+            Self::Ok(data)
+        } else {
+            Self::Error(data)
+        }
+    }
+}
+
+/// A notification sent to a daemon, for auditing what the UI asked for
+#[derive(Debug, Clone)]
+pub struct SentNotification {
+    pub id: u64,
+    pub time: DateTime<Utc>,
+    pub node_addr: String,
+    pub action: &'static str,
+    pub summary: String,
+    /// Data and rules as sent, pretty-printed when JSON
+    pub payload: String,
+    pub delivery: Delivery,
+    /// Rules as they were before the change was made locally, None for
+    /// ones it added, put back if the daemon rejects it
+    pub undo: Vec<(String, Option<models::Rule>)>,
+}
+
+impl SentNotification {
+    pub fn new(id: u64, node_addr: &str, action: &NotificationAction) -> Self {
+        let mut payload = action.data();
+        if let Ok(value) = serde_json::from_str::<serde_json::Value>(&payload) {
+            if value.is_object() || value.is_array() {
+                payload = serde_json::to_string_pretty(&value).unwrap_or(payload);
+            }
+        }
+        Self {
+            id,
+            time: Utc::now(),
+            node_addr: node_addr.to_string(),
+            action: action.name(),
+            summary: action.summary(),
+            payload,
+            delivery: Delivery::Pending,
+            undo: Vec::new(),
+        }
+    }
+}
+
+/// Log a sent notification, most recent first
+pub fn record(log: &mut VecDeque<SentNotification>, sent: SentNotification) {
+    log.push_front(sent);
+    log.truncate(MAX_SENT_NOTIFICATIONS);
+}
+
+/// Attach a daemon reply to the notification it answers, returns whether one matched
+pub fn record_reply(log: &mut VecDeque<SentNotification>, node_addr: &str, id: u64, delivery: Delivery) -> bool {
+    match log.iter_mut().find(|sent| sent.id == id && sent.node_addr == node_addr) {
+        Some(sent) => {
+            sent.delivery = delivery;
+            true
+        }
+        None => false,
+    }
+}
+
+/// A rule change the daemon rejected, with the rules to put back
+#[derive(Debug, Clone)]
+pub struct RejectedChange {
+    pub node_addr: String,
+    pub action: &'static str,
+    pub summary: String,
+    pub reason: String,
+    pub undo: Vec<(String, Option<models::Rule>)>,
+}
+
+impl RejectedChange {
+    /// What was rejected and undone, for the notice shown
+    pub fn message(&self) -> String {
+        let reason = if self.reason.is_empty() { "no reason given" } else { self.reason.as_str() };
+        let names: Vec<_> = self.undo.iter().map(|(name, _)| name.as_str()).collect();
+        format!(
+            "{} rejected {} {}: {}. Put back as before: {}.",
+            self.node_addr,
+            self.action,
+            self.summary,
+            reason,
+            names.join(", ")
+        )
+    }
+}
+
+/// Take the rules to put back from a notification the daemon answered with
+/// an error, None if it succeeded or changed no rules
+pub fn take_rejected(log: &mut VecDeque<SentNotification>, node_addr: &str, id: u64) -> Option<RejectedChange> {
+    let sent = log.iter_mut().find(|sent| sent.id == id && sent.node_addr == node_addr)?;
+    let Delivery::Error(reason) = &sent.delivery else {
+        return None;
+    };
+    if sent.undo.is_empty() {
+        return None;
+    }
+    Some(RejectedChange {
+        node_addr: sent.node_addr.clone(),
+        action: sent.action,
+        summary: sent.summary.clone(),
+        reason: reason.clone(),
+        undo: std::mem::take(&mut sent.undo),
+    })
+}
+
 /// Notification ID generator
 pub struct NotificationIdGenerator {
     next_id: std::sync::atomic::AtomicU64,
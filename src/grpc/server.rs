@@ -1,14 +1,20 @@
 //! gRPC server setup and lifecycle
 
 use std::sync::Arc;
-use anyhow::Result;
+use anyhow::{bail, Context, Result};
 use tokio::sync::mpsc;
-use tonic::transport::Server;
+use tonic::service::interceptor::InterceptedService;
+use tonic::transport::{Certificate, Identity, Server, ServerTlsConfig};
 
 use crate::app::state::{AppMessage, AppState};
+use crate::config::{paths, ListenAddress};
+use crate::grpc::auth::AuthInterceptor;
 use crate::grpc::proto::ui_server::UiServer;
 use crate::grpc::service::UiService;
 
+/// UI service wrapped with the authentication check
+type AuthenticatedService = InterceptedService<UiServer<UiService>, AuthInterceptor>;
+
 #[cfg(unix)]
 mod uds {
     use std::pin::Pin;
@@ -80,93 +86,276 @@ mod uds {
     }
 }
 
+/// Socket the gRPC server accepts daemon connections on
+pub enum ServerListener {
+    Tcp(tokio::net::TcpListener),
+    #[cfg(unix)]
+    Unix(tokio::net::UnixListener, String),
+}
+
+impl ServerListener {
+    /// Bind an address, `unix:///path` or `host:port`
+    ///
+    /// A socket file is only replaced when nothing answers on it, so a
+    /// second instance can't take over the socket of a running one.
+    pub async fn bind(address: &str) -> std::io::Result<Self> {
+        let Some(path) = address.strip_prefix("unix://") else {
+            let addr: std::net::SocketAddr = address
+                .parse()
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e))?;
+            return Ok(Self::Tcp(tokio::net::TcpListener::bind(addr).await?));
+        };
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+
+            if tokio::net::UnixStream::connect(path).await.is_ok() {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::AddrInUse,
+                    format!("{} is served by another process", path),
+                ));
+            }
+            // Our own sockets are created closed to others in the private
+            // runtime directory, the daemon connects as root regardless
+            let runtime_dir = paths::runtime_dir();
+            let private = std::path::Path::new(path).parent() == Some(runtime_dir.as_path());
+            if private {
+                paths::create_private_dir(&runtime_dir)?;
+            }
+
+            // Left behind by a previous run
+            let _ = std::fs::remove_file(path);
+            if private {
+                let listener = paths::bind_private_socket(std::path::Path::new(path))?;
+                return Ok(Self::Unix(listener, path.to_string()));
+            }
+            let listener = tokio::net::UnixListener::bind(path)?;
+
+            // Set permissions to allow daemon to connect
+            std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o666))?;
+            Ok(Self::Unix(listener, path.to_string()))
+        }
+
+        #[cfg(not(unix))]
+        {
+            let _ = path;
+            Err(std::io::Error::new(
+                std::io::ErrorKind::Unsupported,
+                "Unix sockets not supported on this platform",
+            ))
+        }
+    }
+
+    /// Whether TLS applies, local sockets stay plain
+    pub fn is_tcp(&self) -> bool {
+        matches!(self, Self::Tcp(_))
+    }
+
+    /// Address to give the daemon, in the form `bind` accepts
+    pub fn address(&self) -> String {
+        match self {
+            Self::Tcp(listener) => listener
+                .local_addr()
+                .map(|addr| addr.to_string())
+                .unwrap_or_default(),
+            #[cfg(unix)]
+            Self::Unix(_, path) => format!("unix://{}", path),
+        }
+    }
+}
+
+/// Outcome of binding the preferred address or one of its fallbacks
+pub struct BindOutcome {
+    pub listener: Option<ServerListener>,
+    /// Addresses that couldn't be bound, with the reason
+    pub failures: Vec<(String, std::io::Error)>,
+}
+
+impl BindOutcome {
+    /// Bind the first address that is free, in order
+    pub async fn first_free(addresses: &[String]) -> Self {
+        let mut failures = Vec::new();
+        for address in addresses {
+            match ServerListener::bind(address).await {
+                Ok(listener) => {
+                    return Self {
+                        listener: Some(listener),
+                        failures,
+                    }
+                }
+                Err(e) => {
+                    tracing::warn!("Cannot listen on {}: {}", address, e);
+                    failures.push((address.clone(), e));
+                }
+            }
+        }
+        Self {
+            listener: None,
+            failures,
+        }
+    }
+
+    /// What went wrong, for the startup dialog (None when the preferred address was bound)
+    pub fn notice(&self) -> Option<String> {
+        let (address, error) = self.failures.first()?;
+        let reason = if error.kind() == std::io::ErrorKind::AddrInUse {
+            format!("{} is already in use, is another UI running?", address)
+        } else {
+            format!("Cannot listen on {}: {}", address, error)
+        };
+        Some(match &self.listener {
+            Some(listener) => format!(
+                "{}\n\nListening on {} instead, the daemon config was updated to match.",
+                reason,
+                listener.address()
+            ),
+            None => format!(
+                "{}\n\nNo fallback address was free, daemons can't connect to this UI.",
+                reason
+            ),
+        })
+    }
+}
+
+/// TLS for TCP listeners from PEM files, None when no certificate is set.
+/// With a client CA, daemons must present a certificate it signed.
+pub fn load_tls(cert: &str, key: &str, client_ca: &str) -> Result<Option<ServerTlsConfig>> {
+    if cert.is_empty() && key.is_empty() {
+        if !client_ca.is_empty() {
+            bail!("tls_client_ca is set but tls_cert and tls_key are not");
+        }
+        return Ok(None);
+    }
+    if cert.is_empty() || key.is_empty() {
+        bail!("TLS needs both tls_cert and tls_key");
+    }
+    let read = |path: &str| std::fs::read(path).with_context(|| format!("Cannot read {}", path));
+    let mut config = ServerTlsConfig::new().identity(Identity::from_pem(read(cert)?, read(key)?));
+    if !client_ca.is_empty() {
+        config = config.client_ca_root(Certificate::from_pem(read(client_ca)?));
+    }
+    Ok(Some(config))
+}
+
 /// gRPC server for daemon connections
 pub struct GrpcServer {
-    address: String,
     state: Arc<AppState>,
     state_tx: mpsc::Sender<AppMessage>,
+    auth_token: Option<String>,
+    /// Applied to TCP listeners only
+    tls: Option<ServerTlsConfig>,
+    /// Further address with its label and policy, None for the main one
+    listener: Option<ListenAddress>,
 }
 
 impl GrpcServer {
     pub fn new(
-        address: String,
         state: Arc<AppState>,
         state_tx: mpsc::Sender<AppMessage>,
+        auth_token: Option<String>,
     ) -> Self {
         Self {
-            address,
             state,
             state_tx,
+            auth_token,
+            tls: None,
+            listener: None,
         }
     }
 
-    pub async fn run(self) -> Result<()> {
-        let address = self.address;
-        let service = UiService::new(self.state, self.state_tx);
+    pub fn with_tls(mut self, tls: Option<ServerTlsConfig>) -> Self {
+        self.tls = tls;
+        self
+    }
 
-        if address.starts_with("unix://") {
-            Self::run_unix_server(address, service).await
-        } else {
-            Self::run_tcp_server(address, service).await
-        }
+    /// Serve one of the further addresses, tagging its nodes
+    pub fn with_listener(mut self, listener: ListenAddress) -> Self {
+        self.listener = Some(listener);
+        self
     }
 
-    async fn run_unix_server(address: String, service: UiService) -> Result<()> {
-        let path = address.strip_prefix("unix://").unwrap_or(&address);
+    /// Label of the listener, if it has one
+    pub fn label(&self) -> Option<&str> {
+        self.listener.as_ref().and_then(ListenAddress::tag)
+    }
 
-        // Remove existing socket file if present
-        let _ = std::fs::remove_file(path);
+    pub fn has_tls(&self) -> bool {
+        self.tls.is_some()
+    }
 
-        tracing::info!("Starting gRPC server on unix://{}", path);
+    pub async fn run(self, listener: ServerListener) -> Result<()> {
+        let interceptor = AuthInterceptor::new(self.auth_token, self.state_tx.clone());
+        let mut service = UiService::new(self.state, self.state_tx);
+        if let Some(listener) = self.listener {
+            service = service.with_listener(listener);
+        }
+        let service = UiServer::with_interceptor(service, interceptor);
 
-        #[cfg(unix)]
-        {
-            use tokio::net::UnixListener;
-            use std::os::unix::fs::PermissionsExt;
-            use uds::UnixStreamWrapper;
+        match listener {
+            ServerListener::Tcp(listener) => Self::run_tcp_server(listener, service, self.tls).await,
+            #[cfg(unix)]
+            ServerListener::Unix(listener, path) => Self::run_unix_server(listener, path, service).await,
+        }
+    }
 
-            let listener = UnixListener::bind(path)?;
+    #[cfg(unix)]
+    async fn run_unix_server(
+        listener: tokio::net::UnixListener,
+        path: String,
+        service: AuthenticatedService,
+    ) -> Result<()> {
+        use uds::UnixStreamWrapper;
 
-            // Set permissions to allow daemon to connect
-            std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o666))?;
+        tracing::info!("Starting gRPC server on unix://{}", path);
 
-            // Create a custom incoming stream that wraps UnixStream
-            let incoming = async_stream::stream! {
-                loop {
-                    match listener.accept().await {
-                        Ok((stream, _addr)) => {
-                            yield Ok::<_, std::io::Error>(UnixStreamWrapper::new(stream));
-                        }
-                        Err(e) => {
-                            tracing::error!("Failed to accept Unix connection: {}", e);
-                            yield Err(e);
-                        }
+        // Create a custom incoming stream that wraps UnixStream
+        let incoming = async_stream::stream! {
+            loop {
+                match listener.accept().await {
+                    Ok((stream, _addr)) => {
+                        yield Ok::<_, std::io::Error>(UnixStreamWrapper::new(stream));
+                    }
+                    Err(e) => {
+                        tracing::error!("Failed to accept Unix connection: {}", e);
+                        yield Err(e);
                     }
                 }
-            };
-
-            Server::builder()
-                .add_service(UiServer::new(service))
-                .serve_with_incoming(incoming)
-                .await?;
-        }
+            }
+        };
 
-        #[cfg(not(unix))]
-        {
-            anyhow::bail!("Unix sockets not supported on this platform");
-        }
+        Server::builder()
+            .add_service(service)
+            .serve_with_incoming(incoming)
+            .await?;
 
         Ok(())
     }
 
-    async fn run_tcp_server(address: String, service: UiService) -> Result<()> {
-        let addr = address.parse()?;
+    async fn run_tcp_server(
+        listener: tokio::net::TcpListener,
+        service: AuthenticatedService,
+        tls: Option<ServerTlsConfig>,
+    ) -> Result<()> {
+        tracing::info!(
+            "Starting gRPC server on {}{}",
+            listener.local_addr()?,
+            if tls.is_some() { " with TLS" } else { "" }
+        );
 
-        tracing::info!("Starting gRPC server on {}", addr);
+        let incoming = async_stream::stream! {
+            loop {
+                yield listener.accept().await.map(|(stream, _addr)| stream);
+            }
+        };
 
-        Server::builder()
-            .add_service(UiServer::new(service))
-            .serve(addr)
+        let mut builder = Server::builder();
+        if let Some(tls) = tls {
+            builder = builder.tls_config(tls)?;
+        }
+        builder
+            .add_service(service)
+            .serve_with_incoming(incoming)
             .await?;
 
         Ok(())
@@ -2,31 +2,25 @@
 
 use std::pin::Pin;
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::Instant;
 
 use tokio::sync::{mpsc, oneshot, RwLock};
 use tokio_stream::{Stream, StreamExt};
 use tonic::{Request, Response, Status, Streaming};
 
 use crate::app::state::{AppMessage, AppState};
+use crate::config::{InterceptionMode, ListenAddress};
 use crate::grpc::proto;
 use crate::grpc::proto::ui_server::Ui;
 use crate::models;
-
-/// Pending connection prompt waiting for user response
-pub struct PendingPrompt {
-    pub connection: models::Connection,
-    pub node_addr: String,
-    pub response_tx: oneshot::Sender<models::Rule>,
-}
+use crate::models::node::AuthStatus;
 
 /// UI service implementation
 pub struct UiService {
     state: Arc<AppState>,
     state_tx: mpsc::Sender<AppMessage>,
-    default_action: models::RuleAction,
-    default_duration: models::RuleDuration,
-    prompt_timeout: Duration,
+    /// Further address the service answers on, None for the main one
+    listener: Option<ListenAddress>,
 }
 
 impl UiService {
@@ -37,24 +31,73 @@ impl UiService {
         Self {
             state,
             state_tx,
-            default_action: models::RuleAction::Allow, // User preference: permissive
-            default_duration: models::RuleDuration::Once,
-            prompt_timeout: Duration::from_secs(15),
+            listener: None,
         }
     }
 
-    fn create_default_rule(&self, conn: &models::Connection) -> models::Rule {
+    /// Tag nodes with the listener and answer with its default
+    pub fn with_listener(mut self, listener: ListenAddress) -> Self {
+        self.listener = Some(listener);
+        self
+    }
+
+    /// Answer for a connection, following the listener's or the current network's policy
+    async fn create_default_rule(&self, conn: &models::Connection) -> models::Rule {
+        let policy = match self.listener.as_ref().and_then(ListenAddress::prompt_policy) {
+            Some(policy) => policy,
+            None => self.state.prompt_policy.read().await.clone(),
+        };
+        models::Rule::new(
+            &format!("{}-{}", conn.process_name(), conn.dst_port),
+            policy.default_action,
+            policy.default_duration.clone(),
+            models::Operator::simple("process.path", &conn.process_path),
+        )
+    }
+
+    /// Answer for a connection without asking, in the allow-all and deny-all modes
+    fn create_mode_rule(conn: &models::Connection, action: models::RuleAction) -> models::Rule {
         models::Rule::new(
             &format!("{}-{}", conn.process_name(), conn.dst_port),
-            self.default_action,
-            self.default_duration.clone(),
+            action,
+            models::RuleDuration::Once,
             models::Operator::simple("process.path", &conn.process_path),
         )
     }
 
-    fn peer_addr(req: &Request<impl std::any::Any>) -> String {
+    /// Queue a prompt and wait for its answer, the default answers if none comes in time
+    async fn prompt(&self, node_addr: String, connection: &models::Connection) -> models::Rule {
+        let (response_tx, response_rx) = oneshot::channel();
+        let queued = self.state_tx.send(AppMessage::ConnectionPrompt {
+            node_addr,
+            connection: connection.clone(),
+            response_tx,
+        }).await;
+        if queued.is_ok() {
+            // Read per ask, the timeout may change while running
+            if let Ok(Ok(rule)) = tokio::time::timeout(self.state.prompt_timeout(), response_rx).await {
+                return rule;
+            }
+        }
+        let rule = self.create_default_rule(connection).await;
+        tracing::debug!("Prompt unanswered: {} ({})", connection.process_name(), rule.action);
+        rule
+    }
+
+    /// Report how long answering an ask took, the daemon only waits so long
+    async fn answered(&self, node_addr: String, started: Instant) {
+        let _ = self.state_tx.send(AppMessage::AskAnswered {
+            node_addr,
+            latency: started.elapsed(),
+        }).await;
+    }
+
+    /// Address of the daemon, or of the listener it came in on when it has none,
+    /// telling apart daemons on separate Unix sockets
+    fn peer_addr(&self, req: &Request<impl std::any::Any>) -> String {
         req.remote_addr()
             .map(|a| a.to_string())
+            .or_else(|| self.listener.as_ref().map(|l| l.address.clone()))
             .unwrap_or_else(|| "unknown".to_string())
     }
 }
@@ -66,7 +109,7 @@ impl Ui for UiService {
         &self,
         request: Request<proto::PingRequest>,
     ) -> Result<Response<proto::PingReply>, Status> {
-        let peer = Self::peer_addr(&request);
+        let peer = self.peer_addr(&request);
         let ping = request.into_inner();
 
         tracing::debug!("Ping from {} (id: {})", peer, ping.id);
@@ -82,15 +125,23 @@ impl Ui for UiService {
         Ok(Response::new(proto::PingReply { id: ping.id }))
     }
 
-    /// Connection notification - auto-allow and log for monitoring
+    /// Connection the daemon asks about, answered as the interception mode says
     async fn ask_rule(
         &self,
         request: Request<proto::Connection>,
     ) -> Result<Response<proto::Rule>, Status> {
-        let peer = Self::peer_addr(&request);
+        let started = Instant::now();
+        let peer = self.peer_addr(&request);
         let proto_conn = request.into_inner();
         let connection: models::Connection = proto_conn.into();
 
+        // Maintenance: answer straight away and leave no trace
+        if self.state.skip_events(1) {
+            let rule = self.create_default_rule(&connection).await;
+            self.answered(peer, started).await;
+            return Ok(Response::new(rule.into()));
+        }
+
         tracing::info!(
             "Connection from {}: {} -> {}",
             peer,
@@ -98,15 +149,18 @@ impl Ui for UiService {
             connection.destination()
         );
 
-        // Log connection for monitoring (no popup)
         let _ = self.state_tx.send(AppMessage::NewConnection {
             node_addr: peer.clone(),
             connection: connection.clone(),
         }).await;
 
-        // Auto-allow with default rule (monitoring mode)
-        let rule = self.create_default_rule(&connection);
-        tracing::debug!("Auto-allowing: {} ({})", connection.process_name(), rule.action);
+        let rule = match self.state.interception() {
+            InterceptionMode::Ask => self.prompt(peer.clone(), &connection).await,
+            InterceptionMode::AllowAll => Self::create_mode_rule(&connection, models::RuleAction::Allow),
+            InterceptionMode::DenyAll => Self::create_mode_rule(&connection, models::RuleAction::Deny),
+        };
+        tracing::debug!("Answered: {} ({})", connection.process_name(), rule.action);
+        self.answered(peer, started).await;
         Ok(Response::new(rule.into()))
     }
 
@@ -115,7 +169,8 @@ impl Ui for UiService {
         &self,
         request: Request<proto::ClientConfig>,
     ) -> Result<Response<proto::ClientConfig>, Status> {
-        let peer = Self::peer_addr(&request);
+        let peer = self.peer_addr(&request);
+        let auth = request.extensions().get::<AuthStatus>().copied().unwrap_or_default();
         let config = request.into_inner();
 
         tracing::info!(
@@ -132,6 +187,8 @@ impl Ui for UiService {
         let _ = self.state_tx.send(AppMessage::NodeConnected {
             addr: peer,
             config: client_config,
+            auth,
+            listener: self.listener.as_ref().and_then(|l| l.tag()).map(str::to_string),
         }).await;
 
         // Return config (potentially modified)
@@ -145,7 +202,7 @@ impl Ui for UiService {
         &self,
         request: Request<Streaming<proto::NotificationReply>>,
     ) -> Result<Response<Self::NotificationsStream>, Status> {
-        let peer = Self::peer_addr(&request);
+        let peer = self.peer_addr(&request);
         let mut inbound = request.into_inner();
 
         tracing::info!("Notifications stream opened from {}", peer);
@@ -205,7 +262,7 @@ impl Ui for UiService {
         &self,
         request: Request<proto::Alert>,
     ) -> Result<Response<proto::MsgResponse>, Status> {
-        let peer = Self::peer_addr(&request);
+        let peer = self.peer_addr(&request);
         let alert = request.into_inner();
 
         tracing::info!(
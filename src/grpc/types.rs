@@ -26,6 +26,9 @@ impl From<proto::Connection> for models::Connection {
             timestamp: None,
             action: None,
             rule_name: None,
+            interface: String::new(),
+            src_network: String::new(),
+            enriched: Vec::new(),
         }
     }
 }
@@ -90,7 +93,8 @@ impl From<proto::Rule> for models::Rule {
             operator: r.operator.map(Into::into).unwrap_or_default(),
             created: chrono::DateTime::from_timestamp(r.created, 0)
                 .unwrap_or_else(chrono::Utc::now),
-            updated: None,
+            updated: timestamp(r.updated),
+            expires: timestamp(r.expires),
         }
     }
 }
@@ -107,10 +111,17 @@ impl From<models::Rule> for proto::Rule {
             action: r.action.to_string(),
             duration: r.duration.to_string(),
             operator: Some(r.operator.into()),
+            updated: r.updated.map(|t| t.timestamp()).unwrap_or_default(),
+            expires: r.expires.map(|t| t.timestamp()).unwrap_or_default(),
         }
     }
 }
 
+/// Optional timestamps are 0 when the daemon doesn't send them
+fn timestamp(secs: i64) -> Option<chrono::DateTime<chrono::Utc>> {
+    (secs > 0).then(|| chrono::DateTime::from_timestamp(secs, 0)).flatten()
+}
+
 // Statistics conversions
 impl From<proto::Statistics> for models::Statistics {
     fn from(s: proto::Statistics) -> Self {
@@ -144,6 +155,9 @@ impl From<proto::Event> for models::Event {
             connection: e.connection.map(Into::into).unwrap_or_default(),
             rule: e.rule.map(Into::into),
             unix_nano: e.unixnano,
+            node: String::new(),
+            count: 1,
+            restored: false,
         }
     }
 }
@@ -170,6 +184,7 @@ impl From<proto::Alert> for models::Alert {
             node: String::new(),
             timestamp: chrono::Utc::now(),
             acknowledged: false,
+            restored: false,
         }
     }
 }
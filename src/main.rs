@@ -1,5 +1,5 @@
-use anyhow::{bail, Result};
-use clap::Parser;
+use anyhow::{bail, Context, Result};
+use clap::{Parser, Subcommand};
 use std::process::Command;
 use std::sync::Arc;
 use tokio::sync::{broadcast, mpsc};
@@ -13,9 +13,12 @@ mod ui;
 mod utils;
 
 use app::state::AppState;
-use config::settings::Settings;
-use grpc::server::GrpcServer;
+use models::{Automations, RuleAction, RuleDuration};
+use app::suggestions::DenialTracker;
+use config::settings::{HostMode, Settings};
+use grpc::server::{BindOutcome, GrpcServer, ServerListener};
 use ui::app::TuiApp;
+use utils::Host;
 
 const DAEMON_CONFIG_PATH: &str = "/etc/opensnitchd/default-config.json";
 const SERVER_ADDR: &str = "127.0.0.1:50051";
@@ -32,6 +35,222 @@ struct Args {
     /// Configuration file path
     #[arg(short, long)]
     config: Option<String>,
+
+    /// Directory for the database, session state and exports (defaults to the XDG directories)
+    #[arg(long)]
+    data_dir: Option<String>,
+
+    /// Run as a remote console: listen for daemons without managing one on this host
+    #[arg(long)]
+    console: bool,
+
+    /// Wall display: ignore every key but quit and cycle through Connections,
+    /// Statistics and Alerts with large counters
+    #[arg(long)]
+    kiosk: bool,
+
+    /// Monitor without root: leave the daemon's config and service alone and
+    /// listen on a socket in the state directory for a daemon set up to use it
+    #[arg(long, visible_alias = "no-daemon-config")]
+    read_only: bool,
+
+    /// Replay a JSONL capture of daemon calls instead of listening for daemons,
+    /// for demos and reproducing bugs
+    #[arg(long, value_name = "FILE")]
+    replay: Option<String>,
+
+    #[command(subcommand)]
+    command: Option<Commands>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Commands {
+    /// Import rules and events from the official OpenSnitch GUI database
+    ImportGui {
+        /// GUI database path (defaults to ~/.local/share/opensnitch/opensnitch.sqlite3)
+        path: Option<String>,
+    },
+    /// Mirror a running instance read-only through its control socket
+    Attach {
+        /// Control socket path (defaults to the control_socket setting)
+        socket: Option<String>,
+    },
+    /// Print the status of the running instance, e.g. for tmux or i3status
+    Status {
+        /// A single line for status bars
+        #[arg(long)]
+        oneline: bool,
+    },
+    /// Answer a pending prompt of the running instance, or add a rule when there is none
+    Answer {
+        /// Process the prompt is for
+        #[arg(long, required_unless_present = "process")]
+        pid: Option<u32>,
+        /// Executable path, instead of the pid
+        #[arg(long)]
+        process: Option<String>,
+        /// allow, deny or reject
+        #[arg(long, value_parser = app::answer::parse_action)]
+        action: RuleAction,
+        /// once, until restart, always, 5m, 15m, 30m, 1h, 12h or 24h
+        #[arg(long, value_parser = app::answer::parse_duration, default_value = "once")]
+        duration: RuleDuration,
+        /// Control socket path (defaults to the control_socket setting)
+        #[arg(long)]
+        socket: Option<String>,
+    },
+}
+
+/// Settings with the command line overrides applied
+fn load_settings(args: &Args) -> Result<Settings> {
+    let mut settings = Settings::load(args.config.as_deref())?;
+    if let Some(dir) = &args.data_dir {
+        settings.data_dir = dir.clone();
+    }
+    if let Some(path) = &args.database {
+        settings.database_path = path.clone();
+    }
+    if args.console {
+        settings.host_mode = HostMode::Console;
+    }
+    Ok(settings)
+}
+
+fn run_import(args: &Args, path: Option<&str>) -> Result<()> {
+    let settings = load_settings(args)?;
+    let db = db::Database::open(&settings.database_file())?;
+
+    let path = path
+        .map(str::to_string)
+        .unwrap_or_else(|| db::import::default_gui_db_path().to_string_lossy().to_string());
+
+    let report = db.import_gui_database(&path)?;
+    println!("{}", report.summary());
+    for error in &report.errors {
+        eprintln!("  {}", error);
+    }
+    Ok(())
+}
+
+async fn run_attach(args: &Args, socket: Option<&str>) -> Result<()> {
+    let settings = load_settings(args)?;
+    let path = socket.unwrap_or(&settings.control_socket).to_string();
+    if path.is_empty() {
+        bail!("No control socket configured");
+    }
+
+    // Mirrors keep nothing, everything shown comes from the primary instance
+    let db = db::Database::open(":memory:")?;
+    let (state_tx, _state_rx) = mpsc::channel(1);
+    let (ui_update_tx, _) = broadcast::channel(100);
+    let state = Arc::new(AppState::new(db, ui_update_tx));
+
+    // Fail before taking over the terminal if nothing is listening
+    let stream = tokio::net::UnixStream::connect(&path).await
+        .map_err(|e| anyhow::anyhow!("Cannot attach to {}: {}", path, e))?;
+    drop(stream);
+
+    std::panic::set_hook(Box::new(|_| {}));
+
+    let state_clone = state.clone();
+    let mirror_handle = tokio::spawn(async move {
+        if let Err(e) = app::mirror::attach(&path, state_clone).await {
+            tracing::error!("Mirror connection lost: {}", e);
+        }
+    });
+
+    let mut tui = TuiApp::new(state, state_tx, &settings)?;
+    tui.set_read_only();
+    if args.kiosk {
+        tui.set_kiosk();
+    }
+    let result = tui.run().await;
+
+    mirror_handle.abort();
+    result
+}
+
+async fn run_replay(args: &Args, path: &str) -> Result<()> {
+    let settings = load_settings(args)?;
+    // Fail before taking over the terminal on a bad capture
+    let steps = app::replay::load(std::path::Path::new(path))?;
+
+    // Nothing replayed is kept
+    let db = db::Database::open(":memory:")?;
+    let (state_tx, state_rx) = mpsc::channel(1000);
+    let (ui_update_tx, _) = broadcast::channel(100);
+    let state = Arc::new(
+        AppState::new(db, ui_update_tx.clone())
+            .with_interception(settings.interception_mode)
+            .with_prompt_policy(settings.prompt_policy(&utils::NetworkState::default()))
+            .with_prompt_timeout(std::time::Duration::from_secs(settings.prompt_timeout))
+            .with_limits(settings.max_connections, settings.max_alerts),
+    );
+
+    std::panic::set_hook(Box::new(|_| {}));
+
+    let state_clone = state.clone();
+    let denials = DenialTracker::new(
+        settings.suggestion_threshold,
+        std::time::Duration::from_secs(settings.suggestion_window_secs),
+    );
+    let state_manager_handle = tokio::spawn(async move {
+        app::state::run_state_manager(state_clone, state_rx, ui_update_tx, denials).await;
+    });
+    let replay_handle = tokio::spawn(app::replay::run(steps, state.clone(), state_tx.clone()));
+
+    let mut tui = TuiApp::new(state.clone(), state_tx, &settings)?;
+    tui.set_host(Host::console());
+    tui.set_replay(path);
+    if args.kiosk {
+        tui.set_kiosk();
+    }
+    let result = tui.run().await;
+
+    replay_handle.abort();
+    state_manager_handle.abort();
+    state.drain_coalesced().await;
+    result
+}
+
+fn run_status(args: &Args, oneline: bool) -> Result<()> {
+    let settings = load_settings(args)?;
+    let path = settings.status_file();
+    let summary = app::status::StatusSummary::load(&path)
+        .filter(|summary| !summary.is_stale(chrono::Utc::now()));
+    let Some(summary) = summary else {
+        if oneline {
+            println!("OpenSnitch not running");
+            return Ok(());
+        }
+        bail!("No running instance updates {}", path.display());
+    };
+
+    if oneline {
+        println!("{}", summary.oneline());
+    } else {
+        println!("Connected nodes:  {}", summary.connected_nodes);
+        println!("Pending prompts:  {}", summary.pending_prompts);
+        println!("Denied last hour: {}", summary.denied_last_hour);
+        println!("Updated:          {}", summary.updated.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M:%S"));
+    }
+    Ok(())
+}
+
+async fn run_answer(args: &Args, request: app::answer::AnswerRequest, socket: Option<&str>) -> Result<()> {
+    let settings = load_settings(args)?;
+    let path = socket.unwrap_or(&settings.control_socket);
+    if path.is_empty() {
+        bail!("No control socket configured");
+    }
+    let reply = app::answer::send(path, app::answer::ControlRequest::Answer(request))
+        .await
+        .with_context(|| format!("Cannot reach the running instance on {}", path))?;
+    if !reply.ok {
+        bail!("{}", reply.message);
+    }
+    println!("{}", reply.message);
+    Ok(())
 }
 
 fn check_root() -> Result<()> {
@@ -41,7 +260,7 @@ fn check_root() -> Result<()> {
     Ok(())
 }
 
-fn configure_daemon() -> Result<()> {
+fn configure_daemon(address: &str) -> Result<()> {
     // Read current config
     let config_content = std::fs::read_to_string(DAEMON_CONFIG_PATH)
         .unwrap_or_else(|_| default_daemon_config());
@@ -52,7 +271,7 @@ fn configure_daemon() -> Result<()> {
 
     if let Some(server) = config.get_mut("Server") {
         if let Some(obj) = server.as_object_mut() {
-            obj.insert("Address".to_string(), serde_json::Value::String(SERVER_ADDR.to_string()));
+            obj.insert("Address".to_string(), serde_json::Value::String(address.to_string()));
         }
     }
 
@@ -104,6 +323,27 @@ fn restart_daemon() -> Result<()> {
     }
 }
 
+/// Serve daemons on a listener, shown in the Nodes tab
+async fn spawn_grpc_server(
+    server: GrpcServer,
+    listener: ServerListener,
+    state: &AppState,
+) -> tokio::task::JoinHandle<()> {
+    let mut label = listener.address();
+    if listener.is_tcp() && server.has_tls() {
+        label.push_str(" (TLS)");
+    }
+    if let Some(tag) = server.label() {
+        label.push_str(&format!(" [{}]", tag));
+    }
+    state.listeners.write().await.push(label);
+    tokio::spawn(async move {
+        if let Err(e) = server.run(listener).await {
+            tracing::error!("gRPC server failed: {}", e);
+        }
+    })
+}
+
 fn stop_daemon() -> Result<()> {
     let _ = Command::new("systemctl")
         .args(["stop", "opensnitch"])
@@ -115,55 +355,269 @@ fn stop_daemon() -> Result<()> {
 async fn main() -> Result<()> {
     let args = Args::parse();
 
-    // Check root
-    check_root()?;
+    if let Some(Commands::ImportGui { path }) = &args.command {
+        return run_import(&args, path.as_deref());
+    }
+    if let Some(Commands::Attach { socket }) = &args.command {
+        return run_attach(&args, socket.as_deref()).await;
+    }
+    if let Some(Commands::Status { oneline }) = &args.command {
+        return run_status(&args, *oneline);
+    }
+    if let Some(Commands::Answer { pid, process, action, duration, socket }) = &args.command {
+        let request = app::answer::AnswerRequest {
+            pid: *pid,
+            process: process.clone(),
+            action: *action,
+            duration: duration.clone(),
+        };
+        return run_answer(&args, request, socket.as_deref()).await;
+    }
+    if let Some(path) = &args.replay {
+        return run_replay(&args, path).await;
+    }
 
-    // Suppress all panic output in TUI mode
-    std::panic::set_hook(Box::new(|_| {}));
+    // Load settings
+    let settings = load_settings(&args)?;
 
-    // Configure daemon to use our socket
-    configure_daemon()?;
+    // Managing the local daemon needs root, a remote console only its port
+    let mut host = Host::detect(settings.host_mode);
+    if args.read_only {
+        host.manage_daemon = false;
+    }
+    if host.manage_daemon {
+        check_root()?;
+    }
 
-    // Load settings
-    let settings = Settings::load(args.config.as_deref())?;
+    // Suppress all panic output in TUI mode
+    std::panic::set_hook(Box::new(|_| {}));
 
-    // Initialize database
-    let db = db::Database::open(args.database.as_deref().unwrap_or(&settings.database_path))?;
+    // Initialize database, in memory until the file can be written
+    let (db, db_error) = db::Database::open_or_memory(&settings.database_file());
 
     // Create channels for communication
     let (state_tx, state_rx) = mpsc::channel(1000);
     let (ui_update_tx, _) = broadcast::channel(100);
 
     // Create shared application state
-    let state = Arc::new(AppState::new(db, ui_update_tx.clone()));
-
-    // Start gRPC server FIRST (so it's ready when daemon starts)
-    let grpc_server = GrpcServer::new(SERVER_ADDR.to_string(), state.clone(), state_tx.clone());
-    let grpc_handle = tokio::spawn(async move {
-        let _ = grpc_server.run().await;
-    });
-
-    // Give server a moment to bind
-    tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+    let sampler = app::sampling::EventSampler::new(settings.sampling_threshold, settings.sampling_rate);
+    let hooks = app::hooks::RuleHooks::new(settings.rule_hooks.clone());
+    let state = Arc::new(
+        AppState::new(db, ui_update_tx.clone())
+            .with_sampler(sampler)
+            .with_dedup(std::time::Duration::from_millis(settings.dedup_window_ms))
+            .with_hooks(hooks)
+            .with_automations(if args.read_only {
+                Automations::default()
+            } else {
+                Automations::load(&settings.data_dirs().automations())
+            })
+            .with_interface_lookup(host.manage_daemon)
+            .with_proc_fallback(host.manage_daemon.then(|| std::path::PathBuf::from("/proc")))
+            .with_interception(settings.interception_mode)
+            .with_prompt_policy(settings.prompt_policy(&utils::NetworkState::default()))
+            .with_prompt_timeout(std::time::Duration::from_secs(settings.prompt_timeout))
+            .with_limits(settings.max_connections, settings.max_alerts),
+    );
+    if let Some(e) = db_error {
+        state.db_failed(format!("{:#}", e));
+    }
+    state.restore_archived_nodes().await;
+    state.restore_dns();
+    state.restore_rule_hits();
+    state.restore_panics();
+    state.restore_history().await;
+    state.recover_missed_prompts().await;
+
+    // Bind FIRST (so it's ready when daemon starts), falling back if another UI holds the address
+    let auth_token = settings.grpc_auth_token()?;
+    let addresses: Vec<String> = if args.read_only {
+        let socket = settings.data_dirs().daemon_socket();
+        if let Some(dir) = socket.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        vec![format!("unix://{}", socket.display())]
+    } else if host.manage_daemon {
+        std::iter::once(SERVER_ADDR.to_string())
+            .chain(settings.server_fallback.iter().cloned())
+            .collect()
+    } else {
+        settings.check_console_address()?;
+        vec![settings.console_address.clone()]
+    };
+    let tls = grpc::server::load_tls(&settings.tls_cert, &settings.tls_key, &settings.tls_client_ca)?;
+    let mut bound = BindOutcome::first_free(&addresses).await;
+    let mut startup_notice = bound.notice();
+
+    let grpc_handle = match bound.listener.take() {
+        Some(listener) => {
+            // Configure daemon to use our socket
+            if host.manage_daemon {
+                if let Err(e) = configure_daemon(&listener.address()) {
+                    eprintln!("Warning: cannot update daemon config: {}", e);
+                }
+            }
+            let server = GrpcServer::new(state.clone(), state_tx.clone(), auth_token.clone())
+                .with_tls(tls.clone());
+            Some(spawn_grpc_server(server, listener, &state).await)
+        }
+        None => None,
+    };
+
+    // Remote daemons on more addresses at the same time
+    let mut listen_handles = Vec::new();
+    for listen in &settings.listen_addresses {
+        let address = &listen.address;
+        match ServerListener::bind(address).await {
+            Ok(listener) => {
+                let server = GrpcServer::new(state.clone(), state_tx.clone(), auth_token.clone())
+                    .with_tls(tls.clone())
+                    .with_listener(listen.clone());
+                listen_handles.push(spawn_grpc_server(server, listener, &state).await);
+            }
+            Err(e) => {
+                tracing::warn!("Cannot listen on {}: {}", address, e);
+                let failure = format!("Cannot listen on {}: {}", address, e);
+                startup_notice = Some(match startup_notice {
+                    Some(notice) => format!("{}\n\n{}", notice, failure),
+                    None => failure,
+                });
+            }
+        }
+    }
 
     // Restart daemon to connect to our socket
-    if let Err(e) = restart_daemon() {
-        eprintln!("Warning: {}", e);
+    if grpc_handle.is_some() && host.manage_daemon {
+        if let Err(e) = restart_daemon() {
+            eprintln!("Warning: {}", e);
+        }
     }
 
     // Start state manager
     let state_clone = state.clone();
+    let denials = DenialTracker::new(
+        settings.suggestion_threshold,
+        std::time::Duration::from_secs(settings.suggestion_window_secs),
+    );
     let state_manager_handle = tokio::spawn(async move {
-        app::state::run_state_manager(state_clone, state_rx, ui_update_tx).await;
+        app::state::run_state_manager(state_clone, state_rx, ui_update_tx, denials).await;
+    });
+
+    // Follow network changes to pick the matching prompt policy (a console's network says nothing about its nodes)
+    let network_handle = (host.manage_daemon && settings.network_check_secs > 0).then(|| {
+        let mut network_rx = utils::network::spawn_watcher(
+            std::time::Duration::from_secs(settings.network_check_secs),
+        );
+        let state = state.clone();
+        let settings = settings.clone();
+        tokio::spawn(async move {
+            loop {
+                let network = network_rx.borrow_and_update().clone();
+                let policy = settings.prompt_policy(&network);
+                state.set_network(network, policy).await;
+                if network_rx.changed().await.is_err() {
+                    break;
+                }
+            }
+        })
     });
 
+    // Let read-only mirrors attach
+    let control_handle = (!settings.control_socket.is_empty()).then(|| {
+        let path = settings.control_socket.clone();
+        let (state, state_tx) = (state.clone(), state_tx.clone());
+        tokio::spawn(async move {
+            if let Err(e) = app::mirror::serve(path, state, state_tx).await {
+                tracing::error!("Control socket failed: {}", e);
+            }
+        })
+    });
+
+    // Stream events to local web dashboards
+    let stream_handle = if settings.event_stream.is_empty() {
+        None
+    } else {
+        match app::bridge::bind(&settings.event_stream).await {
+            Ok(listener) => {
+                let (state, origins) = (state.clone(), settings.event_stream_origins.clone());
+                let token = settings.event_stream_token.clone();
+                Some(tokio::spawn(async move {
+                    if let Err(e) = app::bridge::serve(listener, state, origins, token).await {
+                        tracing::error!("Event stream failed: {}", e);
+                    }
+                }))
+            }
+            Err(e) => {
+                tracing::error!("Event stream disabled: {}", e);
+                None
+            }
+        }
+    };
+
+    // Maintenance and reports from the config, on their schedule
+    let data_dirs = settings.data_dirs();
+    let scheduler = app::scheduler::Scheduler::new(settings.scheduled_tasks.clone())
+        .with_state_file(data_dirs.schedule());
+    let scheduler_handle = (!scheduler.is_empty()).then(|| {
+        let state = state.clone();
+        tokio::spawn(app::scheduler::run(state, scheduler, data_dirs.exports()))
+    });
+
+    // Try an unavailable database again and write out what piled up
+    let persistence_handle = tokio::spawn(app::persistence::run(state.clone()));
+
+    // New releases, only when asked for
+    let update_handle = settings
+        .update_check
+        .then(|| tokio::spawn(app::updates::run(state.clone(), settings.update_command.clone())));
+
+    // Summary for status bars
+    let status_handle = tokio::spawn(app::status::run(state.clone(), settings.status_file()));
+
     // Run TUI (blocks until user quits)
-    let mut tui = TuiApp::new(state.clone(), state_tx)?;
+    let mut tui = TuiApp::new(state.clone(), state_tx, &settings)?;
+    tui.set_host(host);
+    if args.read_only {
+        tui.set_monitor_only();
+    }
+    if args.kiosk {
+        tui.set_kiosk();
+    }
+    tui.set_config_path(args.config.clone());
+    if let Some(message) = &startup_notice {
+        tui.show_notice("Daemon socket", message);
+    }
     let result = tui.run().await;
 
     // Cleanup
-    grpc_handle.abort();
+    for handle in grpc_handle.into_iter().chain(listen_handles) {
+        handle.abort();
+    }
     state_manager_handle.abort();
+    // Bursts still held back are stored before the database is left
+    state.drain_coalesced().await;
+    if let Some(handle) = network_handle {
+        handle.abort();
+    }
+    status_handle.abort();
+    let _ = std::fs::remove_file(settings.status_file());
+    persistence_handle.abort();
+    if state.db_outage().is_some() && !state.retry_db() {
+        eprintln!("Warning: database unavailable, buffered events were not stored");
+    }
+    if let Some(handle) = scheduler_handle {
+        handle.abort();
+    }
+    if let Some(handle) = update_handle {
+        handle.abort();
+    }
+    if let Some(handle) = stream_handle {
+        handle.abort();
+    }
+    if let Some(handle) = control_handle {
+        handle.abort();
+        let _ = std::fs::remove_file(&settings.control_socket);
+    }
 
     // Stop daemon on exit (optional - comment out to keep daemon running)
     // stop_daemon()?;
@@ -157,6 +157,9 @@ pub struct Alert {
     pub timestamp: DateTime<Utc>,
     #[serde(default)]
     pub acknowledged: bool,
+    /// Loaded from the database at startup rather than received in this run
+    #[serde(default)]
+    pub restored: bool,
 }
 
 impl Alert {
@@ -177,6 +180,7 @@ impl Alert {
             node: String::new(),
             timestamp: Utc::now(),
             acknowledged: false,
+            restored: false,
         }
     }
 
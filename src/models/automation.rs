@@ -0,0 +1,152 @@
+//! Actions taken on their own when alerts arrive
+//!
+//! An automation matches alerts by kind and text, then denies the binary
+//! the alert is about and/or acknowledges it, e.g. to block a binary the
+//! kernel reports as soon as it shows up. Automations are kept in a local
+//! file; the state manager runs them and logs every run.
+
+use std::collections::VecDeque;
+use std::path::Path;
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::{Alert, AlertData, AlertWhat, Operator, Rule, RuleAction, RuleDuration};
+use crate::utils::format::short_hash;
+
+/// Runs kept in the audit trail
+pub const MAX_AUTOMATION_RUNS: usize = 200;
+
+/// When an alert matches, what to do
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AlertAutomation {
+    pub name: String,
+    pub enabled: bool,
+    /// Kind of alert matched, any when None
+    pub what: Option<AlertWhat>,
+    /// Text the alert or its binary contains (empty = any)
+    pub contains: String,
+    /// Add a rule denying the binary the alert is about
+    pub deny_binary: bool,
+    pub acknowledge: bool,
+}
+
+impl Default for AlertAutomation {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            enabled: true,
+            what: None,
+            contains: String::new(),
+            deny_binary: false,
+            acknowledge: true,
+        }
+    }
+}
+
+impl AlertAutomation {
+    pub fn matches(&self, alert: &Alert) -> bool {
+        self.enabled
+            && self.what.is_none_or(|what| what == alert.what)
+            && (self.contains.is_empty()
+                || alert.text().contains(&self.contains)
+                || alert_binary(alert).is_some_and(|binary| binary.contains(&self.contains)))
+    }
+
+    /// Condition and actions in a few words, for lists
+    pub fn summary(&self) -> String {
+        let mut condition = match self.what {
+            Some(what) => format!("{} alerts", what),
+            None => "alerts".to_string(),
+        };
+        if !self.contains.is_empty() {
+            condition.push_str(&format!(" with \"{}\"", self.contains));
+        }
+        let mut actions = Vec::new();
+        if self.deny_binary {
+            actions.push("deny the binary");
+        }
+        if self.acknowledge {
+            actions.push("acknowledge");
+        }
+        if actions.is_empty() {
+            actions.push("log only");
+        }
+        format!("{} → {}", condition, actions.join(", "))
+    }
+}
+
+/// Executable an alert is about, if it names one
+pub fn alert_binary(alert: &Alert) -> Option<&str> {
+    let path = match &alert.data {
+        Some(AlertData::Process(process)) => &process.path,
+        Some(AlertData::Connection(connection)) => &connection.process_path,
+        _ => return None,
+    };
+    Some(path.as_str()).filter(|path| !path.is_empty())
+}
+
+/// Rule an automation adds to deny a binary, named after its file name
+/// and a hash of its path so binaries of the same name get their own
+pub fn deny_rule(binary: &str) -> Rule {
+    let name = binary.rsplit('/').next().unwrap_or(binary);
+    let mut rule = Rule::new(
+        &format!("auto-deny-{}-{}", name, short_hash(binary)),
+        RuleAction::Deny,
+        RuleDuration::Always,
+        Operator::simple("process.path", binary),
+    );
+    rule.description = "Added by an alert automation".to_string();
+    rule
+}
+
+/// Automations in the order they run
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Automations {
+    pub automations: Vec<AlertAutomation>,
+}
+
+impl Automations {
+    /// Load automations, falling back to none if missing or unreadable
+    pub fn load(path: &Path) -> Self {
+        std::fs::read_to_string(path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.automations.is_empty()
+    }
+
+    pub fn matching<'a>(&'a self, alert: &'a Alert) -> impl Iterator<Item = &'a AlertAutomation> {
+        self.automations.iter().filter(|automation| automation.matches(alert))
+    }
+}
+
+/// What an automation did for an alert
+#[derive(Debug, Clone)]
+pub struct AutomationRun {
+    pub time: DateTime<Utc>,
+    pub automation: String,
+    pub node: String,
+    pub alert: String,
+    pub actions: Vec<String>,
+}
+
+/// Add a run to the trail, most recent first
+pub fn record(log: &mut VecDeque<AutomationRun>, run: AutomationRun) {
+    log.push_front(run);
+    log.truncate(MAX_AUTOMATION_RUNS);
+}
@@ -0,0 +1,112 @@
+//! Daemon version compatibility matrix
+
+use std::fmt;
+
+/// Parsed daemon version (major.minor.patch)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct DaemonVersion(pub u32, pub u32, pub u32);
+
+impl DaemonVersion {
+    /// Parse versions like "1.6.5", "v1.6.0-rc1" or "1.7.0.1", None if unrecognisable
+    pub fn parse(version: &str) -> Option<Self> {
+        let version = version.trim().trim_start_matches(['v', 'V']);
+        let core = version
+            .split(|c: char| !c.is_ascii_digit() && c != '.')
+            .next()?;
+        let mut parts = core.split('.').map(|p| p.parse::<u32>());
+        let major = parts.next()?.ok()?;
+        let minor = parts.next().and_then(Result::ok).unwrap_or(0);
+        let patch = parts.next().and_then(Result::ok).unwrap_or(0);
+        Some(Self(major, minor, patch))
+    }
+}
+
+impl fmt::Display for DaemonVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.0, self.1, self.2)
+    }
+}
+
+/// UI features that depend on the daemon version
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Feature {
+    /// process.hash.* and lists.hash.* operands
+    ChecksumOperands,
+    /// process.parent.path operand
+    ParentPathOperand,
+    /// Editing the system firewall over gRPC
+    SystemFirewall,
+    /// quota and limit firewall statements
+    FwRateStatements,
+}
+
+impl Feature {
+    pub const ALL: [Feature; 4] = [
+        Feature::ChecksumOperands,
+        Feature::ParentPathOperand,
+        Feature::SystemFirewall,
+        Feature::FwRateStatements,
+    ];
+
+    /// Oldest daemon version that honors this feature
+    pub fn min_version(self) -> DaemonVersion {
+        match self {
+            Feature::SystemFirewall => DaemonVersion(1, 5, 0),
+            Feature::ChecksumOperands => DaemonVersion(1, 6, 0),
+            Feature::FwRateStatements => DaemonVersion(1, 6, 6),
+            Feature::ParentPathOperand => DaemonVersion(1, 7, 0),
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Feature::ChecksumOperands => "checksum operands",
+            Feature::ParentPathOperand => "parent process operand",
+            Feature::SystemFirewall => "system firewall",
+            Feature::FwRateStatements => "quota/limit statements",
+        }
+    }
+
+    /// Feature required by a rule operand, if any
+    pub fn for_operand(operand: &str) -> Option<Feature> {
+        match operand {
+            "process.hash.md5" | "process.hash.sha1" | "lists.hash.md5" => {
+                Some(Feature::ChecksumOperands)
+            }
+            "process.parent.path" => Some(Feature::ParentPathOperand),
+            _ => None,
+        }
+    }
+
+    /// Feature required by a firewall statement, if any
+    pub fn for_statement(name: &str) -> Option<Feature> {
+        match name {
+            "quota" | "limit" => Some(Feature::FwRateStatements),
+            _ => None,
+        }
+    }
+
+    /// Whether a daemon of this version honors the feature. Unknown
+    /// versions are assumed to support everything rather than block the UI.
+    pub fn supported_by(self, version: Option<DaemonVersion>) -> bool {
+        version.is_none_or(|v| v >= self.min_version())
+    }
+
+    /// Warning shown when the feature is used against an older daemon
+    pub fn unsupported_message(self, version: DaemonVersion) -> String {
+        format!(
+            "{}: needs daemon ≥{} (node runs {})",
+            self.label(),
+            self.min_version(),
+            version
+        )
+    }
+}
+
+/// Features the given daemon version can't honor
+pub fn unsupported_features(version: Option<DaemonVersion>) -> Vec<Feature> {
+    Feature::ALL
+        .into_iter()
+        .filter(|f| !f.supported_by(version))
+        .collect()
+}
@@ -62,6 +62,15 @@ pub struct Connection {
     pub action: Option<String>,
     #[serde(default)]
     pub rule_name: Option<String>,
+    /// Local interface of the source address, looked up by the TUI (empty = unknown)
+    #[serde(default)]
+    pub interface: String,
+    /// Network of the source address, from the routes of a local node (empty = unknown)
+    #[serde(default)]
+    pub src_network: String,
+    /// Process fields the daemon left out and the TUI read from /proc
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub enriched: Vec<String>,
 }
 
 impl Connection {
@@ -84,6 +93,19 @@ impl Connection {
             .unwrap_or(&self.process_path)
     }
 
+    /// Name for a rule on this connection, made of the process and destination
+    pub fn rule_name(&self) -> String {
+        format!(
+            "{}-{}",
+            self.process_name(),
+            if !self.dst_host.is_empty() {
+                self.dst_host.split('.').next().unwrap_or("unknown")
+            } else {
+                &self.dst_ip
+            }
+        )
+    }
+
     pub fn command_line(&self) -> String {
         if self.process_args.is_empty() {
             self.process_path.clone()
@@ -100,6 +122,19 @@ pub struct Event {
     pub connection: Connection,
     pub rule: Option<super::Rule>,
     pub unix_nano: i64,
+    /// Address of the node that reported it
+    #[serde(default)]
+    pub node: String,
+    /// Identical events coalesced into this one, itself included
+    #[serde(default = "one")]
+    pub count: u64,
+    /// Loaded from the database at startup rather than received in this run
+    #[serde(default)]
+    pub restored: bool,
+}
+
+fn one() -> u64 {
+    1
 }
 
 impl Event {
@@ -109,6 +144,9 @@ impl Event {
             connection,
             rule,
             unix_nano: Utc::now().timestamp_nanos_opt().unwrap_or(0),
+            node: String::new(),
+            count: 1,
+            restored: false,
         }
     }
 }
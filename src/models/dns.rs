@@ -0,0 +1,103 @@
+//! Hostnames and the addresses they resolved to
+//!
+//! The daemon only counts DNS responses in its statistics, but every event
+//! carries the hostname it resolved for the destination address. Those
+//! pairs are collected per node, so a lookup that happened is still known
+//! after its connections scrolled away.
+
+use std::collections::HashMap;
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::Event;
+
+/// Mappings kept in memory, the least recently seen are dropped beyond
+pub const MAX_DNS_ENTRIES: usize = 5000;
+
+/// A mapping seen again is stored again after this long, so the database
+/// isn't written for every event
+const STORE_INTERVAL: Duration = Duration::seconds(60);
+
+/// A hostname that resolved to an address on a node
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DnsEntry {
+    pub node: String,
+    pub host: String,
+    pub ip: String,
+    pub first_seen: DateTime<Utc>,
+    pub last_seen: DateTime<Utc>,
+    /// Events with this mapping
+    pub count: u64,
+}
+
+impl DnsEntry {
+    /// The mapping in an event, None when the destination has no hostname
+    pub fn of(node: &str, event: &Event, now: DateTime<Utc>) -> Option<Self> {
+        let connection = &event.connection;
+        let host = connection.dst_host.trim_end_matches('.');
+        if host.is_empty() || connection.dst_ip.is_empty() || host.parse::<std::net::IpAddr>().is_ok() {
+            return None;
+        }
+        let seen = DateTime::parse_from_rfc3339(&event.time).map(|t| t.with_timezone(&Utc)).unwrap_or(now);
+        Some(Self {
+            node: node.to_string(),
+            host: host.to_lowercase(),
+            ip: connection.dst_ip.clone(),
+            first_seen: seen,
+            last_seen: seen,
+            count: event.count.max(1),
+        })
+    }
+
+    fn key(&self) -> (String, String, String) {
+        (self.node.clone(), self.host.clone(), self.ip.clone())
+    }
+}
+
+/// Mappings by node, hostname and address
+#[derive(Debug, Default)]
+pub struct DnsLog {
+    entries: HashMap<(String, String, String), (DnsEntry, DateTime<Utc>)>,
+}
+
+impl DnsLog {
+    /// Count a mapping in, returns it when it should be stored: when it is
+    /// new or was last stored a while ago
+    pub fn record(&mut self, entry: DnsEntry) -> Option<DnsEntry> {
+        let key = entry.key();
+        if let Some((known, stored)) = self.entries.get_mut(&key) {
+            known.last_seen = known.last_seen.max(entry.last_seen);
+            known.count += entry.count;
+            if known.last_seen - *stored < STORE_INTERVAL {
+                return None;
+            }
+            *stored = known.last_seen;
+            return Some(known.clone());
+        }
+
+        if self.entries.len() >= MAX_DNS_ENTRIES {
+            let oldest = self.entries.iter().min_by_key(|(_, (e, _))| e.last_seen).map(|(k, _)| k.clone());
+            if let Some(oldest) = oldest {
+                self.entries.remove(&oldest);
+            }
+        }
+        self.entries.insert(key, (entry.clone(), entry.last_seen));
+        Some(entry)
+    }
+
+    /// Take in mappings stored by previous runs
+    pub fn load(&mut self, entries: Vec<DnsEntry>) {
+        for entry in entries.into_iter().take(MAX_DNS_ENTRIES) {
+            let stored = entry.last_seen;
+            self.entries.insert(entry.key(), (entry, stored));
+        }
+    }
+
+    /// Every mapping, most recently seen first
+    pub fn entries(&self) -> Vec<DnsEntry> {
+        let mut entries: Vec<DnsEntry> = self.entries.values().map(|(entry, _)| entry.clone()).collect();
+        entries.sort_by(|a, b| b.last_seen.cmp(&a.last_seen).then_with(|| a.host.cmp(&b.host)));
+        entries
+    }
+}
@@ -15,6 +15,90 @@ pub struct Statement {
     pub values: Vec<StatementValue>,
 }
 
+/// Comparison operators a statement can use
+pub const STATEMENT_OPS: &[&str] = &["==", "!=", "<", ">", "<=", ">="];
+
+impl Statement {
+    /// Parse the one-line nft-like form used by the firewall rule editor:
+    /// `name [key] [op] value…`, e.g. `ct state {established, related}`,
+    /// `iifname != lo` or `tcp dport 1000-2000`. Without an operator the
+    /// statement matches with `==`, and a lone value gets the `value` key.
+    /// Values written `key=value` keep their own key, for statements such
+    /// as `log` or `limit` that take several parameters.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let text = text.replace(['{', '}', ','], " ");
+        let tokens: Vec<&str> = text.split_whitespace().collect();
+        let Some((name, rest)) = tokens.split_first() else {
+            return Err("missing statement name".to_string());
+        };
+
+        let (op, key, values) = match rest.iter().position(|t| STATEMENT_OPS.contains(t)) {
+            Some(i) => match &rest[..i] {
+                [] => (rest[i], "value", &rest[i + 1..]),
+                [key] => (rest[i], *key, &rest[i + 1..]),
+                _ => return Err(format!("{}: expected at most one key before {}", name, rest[i])),
+            },
+            // Statements such as `counter` take no value at all
+            None if rest.is_empty() => ("", "value", rest),
+            None => match rest {
+                [first, values @ ..] if !values.is_empty() && !first.contains('=') => ("==", *first, values),
+                _ => ("==", "value", rest),
+            },
+        };
+        if values.is_empty() && !op.is_empty() {
+            return Err(format!("{}: missing value after {}", name, op));
+        }
+
+        let values = values
+            .iter()
+            .map(|token| {
+                let (key, value) = token.split_once('=').unwrap_or((key, token));
+                check_range(value).map_err(|e| format!("{}: {}", name, e))?;
+                Ok(StatementValue { key: key.to_string(), value: value.to_string() })
+            })
+            .collect::<Result<_, String>>()?;
+
+        Ok(Self { op: op.to_string(), name: name.to_string(), values })
+    }
+}
+
+/// Reject numeric ranges written backwards, such as `2000-1000`
+fn check_range(value: &str) -> Result<(), String> {
+    let bounds = value.split_once('-').and_then(|(lo, hi)| Some((lo.parse::<u64>().ok()?, hi.parse::<u64>().ok()?)));
+    match bounds {
+        Some((lo, hi)) if lo > hi => Err(format!("range {} starts after it ends", value)),
+        _ => Ok(()),
+    }
+}
+
+impl std::fmt::Display for Statement {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name)?;
+        let key = self.values.first().map(|v| v.key.as_str()).unwrap_or("value");
+        let same_key = self.values.iter().all(|v| v.key == key);
+        if same_key && key != "value" {
+            write!(f, " {}", key)?;
+        }
+        if !self.op.is_empty() {
+            write!(f, " {}", self.op)?;
+        }
+        if !same_key {
+            for v in &self.values {
+                write!(f, " {}={}", v.key, v.value)?;
+            }
+            return Ok(());
+        }
+        match self.values.as_slice() {
+            [] => Ok(()),
+            [v] => write!(f, " {}", v.value),
+            values => {
+                let values: Vec<_> = values.iter().map(|v| v.value.as_str()).collect();
+                write!(f, " {{{}}}", values.join(", "))
+            }
+        }
+    }
+}
+
 /// nftables expression
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Expression {
@@ -107,6 +191,16 @@ impl FwChain {
         }
     }
 
+    /// Whether this is the chain of that name in that table. Names are
+    /// only unique within a table, e.g. input in filter and in mangle.
+    pub fn is(&self, table: &str, family: &str, name: &str) -> bool {
+        self.table == table && self.family == family && self.name == name
+    }
+
+    pub fn same_chain(&self, other: &FwChain) -> bool {
+        other.is(&self.table, &self.family, &self.name)
+    }
+
     pub fn with_policy(mut self, policy: &str) -> Self {
         self.policy = policy.to_string();
         self
@@ -164,12 +258,29 @@ impl SysFirewall {
         self.system_rules.iter_mut().flat_map(|fc| fc.chains.iter_mut())
     }
 
-    pub fn find_chain(&self, name: &str) -> Option<&FwChain> {
-        self.all_chains().find(|c| c.name == name)
+    pub fn find_chain(&self, table: &str, family: &str, name: &str) -> Option<&FwChain> {
+        self.all_chains().find(|c| c.is(table, family, name))
+    }
+
+    pub fn find_chain_mut(&mut self, table: &str, family: &str, name: &str) -> Option<&mut FwChain> {
+        self.all_chains_mut().find(|c| c.is(table, family, name))
     }
 
-    pub fn find_chain_mut(&mut self, name: &str) -> Option<&mut FwChain> {
-        self.all_chains_mut().find(|c| c.name == name)
+    /// Add a chain to the first chain set, starting one if there's none
+    pub fn add_chain(&mut self, chain: FwChain) {
+        match self.system_rules.first_mut() {
+            Some(fc) => fc.chains.push(chain),
+            None => self.system_rules.push(FwChains { rule: None, chains: vec![chain] }),
+        }
+    }
+
+    /// Remove a chain with its rules, returns whether there was one
+    pub fn remove_chain(&mut self, table: &str, family: &str, name: &str) -> bool {
+        let before = self.chain_count();
+        for fc in &mut self.system_rules {
+            fc.chains.retain(|c| !c.is(table, family, name));
+        }
+        self.chain_count() != before
     }
 
     pub fn rule_count(&self) -> usize {
@@ -0,0 +1,72 @@
+//! Earlier versions of rules changed from the TUI
+//!
+//! Each rule change sent to a daemon keeps the rule as it was before, so
+//! the change can be looked back on and undone. Undoing a change is itself
+//! a change, logged as such but never undone in turn: undoing again goes
+//! further back instead.
+//!
+//! Whole rule sets are kept too, as snapshots taken before a temporary rule
+//! set replaces a node's rules.
+
+use chrono::{DateTime, Utc};
+use std::fmt;
+
+use super::Rule;
+
+/// What a change did to a rule
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RuleChange {
+    Added,
+    Modified,
+    Enabled,
+    Disabled,
+    Deleted,
+    /// An earlier change was undone
+    Reverted,
+}
+
+impl fmt::Display for RuleChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Added => write!(f, "added"),
+            Self::Modified => write!(f, "modified"),
+            Self::Enabled => write!(f, "enabled"),
+            Self::Disabled => write!(f, "disabled"),
+            Self::Deleted => write!(f, "deleted"),
+            Self::Reverted => write!(f, "reverted"),
+        }
+    }
+}
+
+impl From<&str> for RuleChange {
+    fn from(s: &str) -> Self {
+        match s {
+            "added" => Self::Added,
+            "enabled" => Self::Enabled,
+            "disabled" => Self::Disabled,
+            "deleted" => Self::Deleted,
+            "reverted" => Self::Reverted,
+            _ => Self::Modified,
+        }
+    }
+}
+
+/// A rule as it was before a change
+#[derive(Debug, Clone)]
+pub struct RuleVersion {
+    pub id: i64,
+    pub time: DateTime<Utc>,
+    pub name: String,
+    pub change: RuleChange,
+    /// The rule before the change, None when there was none
+    pub previous: Option<Rule>,
+    /// The change was undone since
+    pub undone: bool,
+}
+
+/// A node's rules as they were, saved under a label to be put back later
+#[derive(Debug, Clone)]
+pub struct RuleSnapshot {
+    pub time: DateTime<Utc>,
+    pub rules: Vec<Rule>,
+}
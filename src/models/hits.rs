@@ -0,0 +1,69 @@
+//! How often each rule matched, and when it last did
+//!
+//! Every event names the rule it matched. Counting those per node and rule
+//! shows which rules still do something and which ones nothing reaches any
+//! more. The counts are kept with the rules in the database, so they add up
+//! across runs.
+
+use std::collections::HashMap;
+
+use chrono::{DateTime, Duration, Utc};
+
+/// A rule matched again is stored again after this long, so the database
+/// isn't written for every event
+const STORE_INTERVAL: Duration = Duration::seconds(60);
+
+/// Events a rule matched
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RuleHits {
+    pub count: u64,
+    /// None for a rule never matched
+    pub last_hit: Option<DateTime<Utc>>,
+}
+
+/// Hits by node and rule name
+#[derive(Debug, Default)]
+pub struct RuleHitLog {
+    hits: HashMap<(String, String), (RuleHits, DateTime<Utc>)>,
+}
+
+impl RuleHitLog {
+    /// Count `count` events matching a rule in, returns the hits when they
+    /// should be stored: the first time or when last stored a while ago
+    pub fn record(&mut self, node: &str, rule: &str, count: u64, at: DateTime<Utc>) -> Option<RuleHits> {
+        let key = (node.to_string(), rule.to_string());
+        match self.hits.get_mut(&key) {
+            Some((hits, stored)) => {
+                hits.count += count;
+                hits.last_hit = hits.last_hit.max(Some(at));
+                if at - *stored < STORE_INTERVAL {
+                    return None;
+                }
+                *stored = at;
+                Some(*hits)
+            }
+            None => {
+                let hits = RuleHits { count, last_hit: Some(at) };
+                self.hits.insert(key, (hits, at));
+                Some(hits)
+            }
+        }
+    }
+
+    /// Take in the hits stored by previous runs
+    pub fn load(&mut self, stored: Vec<(String, String, RuleHits)>) {
+        for (node, rule, hits) in stored {
+            let time = hits.last_hit.unwrap_or_default();
+            self.hits.insert((node, rule), (hits, time));
+        }
+    }
+
+    /// Hits of the rules of a node, by rule name
+    pub fn node_hits(&self, node: &str) -> HashMap<String, RuleHits> {
+        self.hits
+            .iter()
+            .filter(|((n, _), _)| n == node)
+            .map(|((_, rule), (hits, _))| (rule.clone(), *hits))
+            .collect()
+    }
+}
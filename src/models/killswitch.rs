@@ -0,0 +1,135 @@
+//! VPN kill-switch in the system firewall
+//!
+//! Outgoing traffic may only leave through the tunnel interfaces, or go to
+//! the VPN endpoint to bring the tunnel up. Everything else is dropped, so
+//! nothing leaks while the tunnel is down. The rules live in a chain of
+//! their own, which is enabled and disabled as a whole.
+
+use std::net::IpAddr;
+
+use super::{Expression, FwChain, FwChains, FwRule, Statement, StatementValue, SysFirewall};
+
+/// Name of the chain holding the kill-switch
+pub const KILLSWITCH_CHAIN: &str = "vpn-killswitch";
+
+/// Table and family the kill-switch chain is added to
+const KILLSWITCH_TABLE: &str = "filter";
+const KILLSWITCH_FAMILY: &str = "inet";
+
+/// Private ranges reachable with "allow LAN"
+const LAN_NETWORKS: &[&str] = &["10.0.0.0/8", "172.16.0.0/12", "192.168.0.0/16", "fe80::/10"];
+
+/// What the kill-switch lets through
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KillSwitch {
+    /// Tunnel interfaces, e.g. tun0 and wg0
+    pub interfaces: Vec<String>,
+    /// Address of the VPN server
+    pub endpoint: String,
+    pub port: u16,
+    /// "udp" or "tcp"
+    pub protocol: String,
+    /// Keep the local network reachable, e.g. for printers
+    pub allow_lan: bool,
+}
+
+impl Default for KillSwitch {
+    fn default() -> Self {
+        Self {
+            interfaces: vec!["tun0".to_string(), "wg0".to_string()],
+            endpoint: String::new(),
+            port: 51820,
+            protocol: "udp".to_string(),
+            allow_lan: false,
+        }
+    }
+}
+
+impl KillSwitch {
+    /// Why the settings can't make a working kill-switch
+    pub fn validate(&self) -> Result<(), String> {
+        if self.interfaces.is_empty() {
+            return Err("name at least one tunnel interface".to_string());
+        }
+        if self.endpoint.parse::<IpAddr>().is_err() {
+            return Err(format!("'{}' is not an IP address, the endpoint has to be one", self.endpoint));
+        }
+        if self.port == 0 {
+            return Err("the endpoint port is missing".to_string());
+        }
+        Ok(())
+    }
+
+    /// Output chain dropping what the rules don't allow
+    pub fn chain(&self) -> FwChain {
+        let mut rules = vec![FwRule::new("Kill-switch: loopback", "accept")
+            .with_expressions(vec![matching("oifname", "lo")])];
+        rules.extend(self.interfaces.iter().map(|iface| {
+            FwRule::new(&format!("Kill-switch: tunnel {}", iface), "accept")
+                .with_expressions(vec![matching("oifname", iface)])
+        }));
+        rules.push(
+            FwRule::new("Kill-switch: VPN endpoint", "accept").with_expressions(vec![
+                matching("protocol", &self.protocol),
+                matching("daddr", &self.endpoint),
+                matching("dport", &self.port.to_string()),
+            ]),
+        );
+        if self.allow_lan {
+            rules.extend(LAN_NETWORKS.iter().map(|network| {
+                FwRule::new(&format!("Kill-switch: LAN {}", network), "accept")
+                    .with_expressions(vec![matching("daddr", network)])
+            }));
+        }
+        let rules = rules
+            .into_iter()
+            .enumerate()
+            .map(|(i, rule)| rule.with_position(i as u64))
+            .collect();
+
+        FwChain::new(KILLSWITCH_CHAIN, KILLSWITCH_TABLE, "output")
+            .with_policy("drop")
+            .with_rules(rules)
+    }
+}
+
+fn matching(name: &str, value: &str) -> Expression {
+    Expression {
+        statement: Statement {
+            op: "==".to_string(),
+            name: name.to_string(),
+            values: vec![StatementValue {
+                key: "value".to_string(),
+                value: value.to_string(),
+            }],
+        },
+    }
+}
+
+/// Add the kill-switch chain, replacing an earlier one
+pub fn install(firewall: &mut SysFirewall, killswitch: &KillSwitch) {
+    let chain = killswitch.chain();
+    match firewall.find_chain_mut(KILLSWITCH_TABLE, KILLSWITCH_FAMILY, KILLSWITCH_CHAIN) {
+        Some(existing) => *existing = chain,
+        None => firewall.system_rules.push(FwChains {
+            rule: None,
+            chains: vec![chain],
+        }),
+    }
+}
+
+/// Whether the kill-switch is installed and enforcing, None if not installed
+pub fn enabled(firewall: &SysFirewall) -> Option<bool> {
+    firewall.find_chain(KILLSWITCH_TABLE, KILLSWITCH_FAMILY, KILLSWITCH_CHAIN).map(|chain| chain.policy == "drop")
+}
+
+/// Turn the whole kill-switch on or off, returns false if not installed.
+/// Off, the chain accepts everything, so its rules no longer matter.
+pub fn set_enabled(firewall: &mut SysFirewall, enable: bool) -> bool {
+    let Some(chain) = firewall.find_chain_mut(KILLSWITCH_TABLE, KILLSWITCH_FAMILY, KILLSWITCH_CHAIN) else {
+        return false;
+    };
+    chain.policy = if enable { "drop" } else { "accept" }.to_string();
+    chain.rules.iter_mut().for_each(|rule| rule.enabled = enable);
+    true
+}
@@ -0,0 +1,88 @@
+//! Folding a new rule into a nearly identical existing one
+//!
+//! Answering prompts one destination at a time leaves a rule per port or
+//! host of the same program. When a new rule differs from an existing one
+//! in a single destination condition, that condition can take the new
+//! value as an alternative instead.
+//!
+//! The alternatives go into an anchored regexp. A list operator would not
+//! do: the daemon requires every condition of a list to match.
+
+use super::{Operator, OperatorType, Rule};
+
+/// An existing rule that can cover a new rule's connections too
+#[derive(Debug, Clone)]
+pub struct Extension {
+    /// The existing rule, keeping its name, with the new value added
+    pub rule: Rule,
+    /// Condition that gained an alternative
+    pub operand: String,
+    pub value: String,
+}
+
+/// First enabled rule with the same action and duration as `new_rule`
+/// whose conditions are the same but for one destination value
+pub fn extension(existing: &[Rule], new_rule: &Rule) -> Option<Extension> {
+    let wanted = leaves(&new_rule.operator);
+    existing
+        .iter()
+        .filter(|r| r.enabled && r.action == new_rule.action && r.duration == new_rule.duration)
+        .find_map(|r| extend(r, &wanted))
+}
+
+fn leaves(operator: &Operator) -> Vec<&Operator> {
+    if operator.op_type == OperatorType::List {
+        operator.list.iter().flat_map(leaves).collect()
+    } else {
+        vec![operator]
+    }
+}
+
+fn extend(rule: &Rule, wanted: &[&Operator]) -> Option<Extension> {
+    let have = leaves(&rule.operator);
+    if have.len() != wanted.len() {
+        return None;
+    }
+
+    let mut differing = None;
+    for new in wanted {
+        let old = have.iter().find(|op| op.operand == new.operand)?;
+        let same = old.op_type == new.op_type && old.data == new.data && old.sensitive == new.sensitive;
+        if !same && differing.replace((*old, *new)).is_some() {
+            return None;
+        }
+    }
+
+    // Another program is another rule
+    let (old, new) = differing?;
+    if new.op_type != OperatorType::Simple || old.sensitive != new.sensitive || old.operand.starts_with("process.") {
+        return None;
+    }
+    let alternatives = match old.op_type {
+        OperatorType::Simple => regex::escape(&old.data),
+        // Only alternatives added here, other patterns are left alone
+        OperatorType::Regexp => old.data.strip_prefix("^(")?.strip_suffix(")$")?.to_string(),
+        _ => return None,
+    };
+
+    let replacement = Operator::regexp(&old.operand, &format!("^({}|{})$", alternatives, regex::escape(&new.data)))
+        .with_sensitive(old.sensitive);
+    let mut extended = rule.clone();
+    replace_leaf(&mut extended.operator, &old.operand, &replacement);
+    Some(Extension {
+        rule: extended,
+        operand: new.operand.clone(),
+        value: new.data.clone(),
+    })
+}
+
+fn replace_leaf(operator: &mut Operator, operand: &str, replacement: &Operator) {
+    if operator.op_type == OperatorType::List {
+        operator
+            .list
+            .iter_mut()
+            .for_each(|op| replace_leaf(op, operand, replacement));
+    } else if operator.operand == operand {
+        *operator = replacement.clone();
+    }
+}
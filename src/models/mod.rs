@@ -1,15 +1,38 @@
 pub mod alert;
+pub mod automation;
+pub mod compat;
 pub mod connection;
+pub mod dns;
 pub mod firewall;
+pub mod history;
+pub mod hits;
+pub mod killswitch;
+pub mod merge;
 pub mod node;
 pub mod operator;
+pub mod policy;
+pub mod precedence;
+pub mod profile;
+pub mod prompt;
+pub mod quarantine;
+pub mod report;
 pub mod rule;
 pub mod statistics;
+pub mod template;
 
 pub use alert::{Alert, AlertAction, AlertData, AlertPriority, AlertType, AlertWhat};
+pub use automation::{AlertAutomation, AutomationRun, Automations};
+pub use compat::{DaemonVersion, Feature};
 pub use connection::{Connection, Event};
+pub use dns::{DnsEntry, DnsLog};
 pub use firewall::{Expression, FwChain, FwChains, FwRule, Statement, StatementValue, SysFirewall};
-pub use node::{Node, NodeManager};
+pub use history::{RuleChange, RuleSnapshot, RuleVersion};
+pub use hits::{RuleHitLog, RuleHits};
+pub use killswitch::KillSwitch;
+pub use node::{Node, NodeManager, RuleIndex, RulePage};
 pub use operator::{Operand, Operator, OperatorType};
+pub use policy::Policies;
+pub use profile::AppProfile;
+pub use prompt::{MissedPrompt, PromptOutcome};
 pub use rule::{Rule, RuleAction, RuleDuration};
-pub use statistics::Statistics;
+pub use statistics::{RateSample, Statistics, StatsFormat, TrafficHistory};
@@ -1,8 +1,11 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::sync::Mutex;
+use std::time::Duration;
 
-use super::{Rule, Statistics, SysFirewall};
+use super::{DaemonVersion, Rule, RuleAction, Statistics, SysFirewall};
 
 /// Node connection status
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -30,6 +33,25 @@ impl std::fmt::Display for NodeStatus {
     }
 }
 
+/// Authentication result for a node's gRPC requests
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum AuthStatus {
+    /// Authentication is disabled
+    #[default]
+    None,
+    /// The node presented the configured token
+    Verified,
+}
+
+impl std::fmt::Display for AuthStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::None => write!(f, "None"),
+            Self::Verified => write!(f, "Verified"),
+        }
+    }
+}
+
 /// A connected daemon node
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Node {
@@ -47,6 +69,20 @@ pub struct Node {
     pub connected_at: Option<DateTime<Utc>>,
     #[serde(default)]
     pub notifications_enabled: bool,
+    #[serde(default)]
+    pub auth: AuthStatus,
+    /// Gone offline, kept as a read-only snapshot for browsing
+    #[serde(default)]
+    pub archived: bool,
+    /// How fast the node's connection asks were answered
+    #[serde(default)]
+    pub ask_latency: AskLatency,
+    /// Events per local interface, counted by the TUI
+    #[serde(default)]
+    pub by_interface: HashMap<String, u64>,
+    /// Label of the listener the node came in on, e.g. a network namespace
+    #[serde(default)]
+    pub listener: Option<String>,
 }
 
 impl Node {
@@ -65,6 +101,11 @@ impl Node {
             last_seen: Utc::now(),
             connected_at: None,
             notifications_enabled: false,
+            auth: AuthStatus::None,
+            archived: false,
+            ask_latency: AskLatency::default(),
+            by_interface: HashMap::new(),
+            listener: None,
         }
     }
 
@@ -77,6 +118,7 @@ impl Node {
         self.rules = config.rules.clone();
         self.firewall = config.system_firewall.clone();
         self.status = NodeStatus::Connected;
+        self.archived = false;
         self.connected_at = Some(Utc::now());
         self.last_seen = Utc::now();
     }
@@ -85,6 +127,13 @@ impl Node {
         self.status = NodeStatus::Disconnected;
     }
 
+    /// Freeze the node's last known rules, stats and events after it went away
+    pub fn archive(&mut self) {
+        self.disconnect();
+        self.archived = true;
+        self.notifications_enabled = false;
+    }
+
     pub fn update_stats(&mut self, stats: Statistics) {
         self.statistics = Some(stats);
         self.last_seen = Utc::now();
@@ -94,6 +143,60 @@ impl Node {
         self.statistics.as_ref().map(|s| s.uptime)
     }
 
+    /// Parsed daemon version, None until the node reports a recognisable one
+    pub fn daemon_version(&self) -> Option<DaemonVersion> {
+        DaemonVersion::parse(&self.version)
+    }
+
+    /// Field of the daemon's JSON configuration
+    pub fn config_value(&self, key: &str) -> Option<serde_json::Value> {
+        let config: serde_json::Value = serde_json::from_str(&self.config).ok()?;
+        config.get(key).cloned()
+    }
+
+    /// Daemon configuration with one field replaced, None if it can't be parsed
+    pub fn config_with(&self, key: &str, value: serde_json::Value) -> Option<String> {
+        let mut config: serde_json::Value = serde_json::from_str(&self.config).ok()?;
+        config.as_object_mut()?.insert(key.to_string(), value);
+        serde_json::to_string_pretty(&config).ok()
+    }
+
+    /// File the daemon logs to, None when its configuration doesn't say
+    pub fn log_file(&self) -> Option<String> {
+        let file = self.config_value("Server")?.get("LogFile")?.as_str()?.trim().to_string();
+        (!file.is_empty()).then_some(file)
+    }
+
+    /// Action the daemon applies to connections it can't ask about
+    pub fn default_action(&self) -> Option<RuleAction> {
+        match self.config_value("DefaultAction")?.as_str()? {
+            "allow" => Some(RuleAction::Allow),
+            "deny" => Some(RuleAction::Deny),
+            "reject" => Some(RuleAction::Reject),
+            _ => None,
+        }
+    }
+
+    /// Configuration cutting every new connection off: denied by default,
+    /// unknown processes intercepted too and nothing let through while the
+    /// queue has no listener. None if the configuration can't be parsed
+    pub fn lockdown_config(&self) -> Option<String> {
+        let mut config: serde_json::Value = serde_json::from_str(&self.config).ok()?;
+        let fields = config.as_object_mut()?;
+        fields.insert("DefaultAction".to_string(), serde_json::Value::String(RuleAction::Deny.to_string()));
+        fields.insert("InterceptUnknown".to_string(), serde_json::Value::Bool(true));
+        let options = fields
+            .entry("FwOptions")
+            .or_insert_with(|| serde_json::Value::Object(Default::default()));
+        options.as_object_mut()?.insert("QueueBypass".to_string(), serde_json::Value::Bool(false));
+        serde_json::to_string_pretty(&config).ok()
+    }
+
+    /// Whether the daemon intercepts connections of unknown processes
+    pub fn intercept_unknown(&self) -> Option<bool> {
+        self.config_value("InterceptUnknown")?.as_bool()
+    }
+
     pub fn rule_count(&self) -> usize {
         self.rules.len()
     }
@@ -113,6 +216,62 @@ impl Default for Node {
     }
 }
 
+/// Seconds a daemon is assumed to wait for an answer before applying its
+/// default action. opensnitchd's configuration has no key for it (the
+/// `default_timeout` of CHANGE_CONFIG in ui.proto is not read back), so
+/// every node gets the same.
+pub const DEFAULT_ASK_TIMEOUT: u64 = 15;
+
+/// Share of the ask timeout kept as margin for the answer's trip back
+const ASK_MARGIN_PERCENT: u64 = 20;
+
+/// Latest time an answer is safely within an ask timeout of `timeout_secs`
+pub fn ask_deadline(timeout_secs: u64) -> Duration {
+    Duration::from_millis(timeout_secs * 1000 * (100 - ASK_MARGIN_PERCENT) / 100)
+}
+
+/// Answer times of a node's connection asks
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AskLatency {
+    /// Most recent answer times in milliseconds, oldest first
+    recent: VecDeque<u64>,
+    pub answered: u64,
+    /// Answers past the deadline, which the daemon may have given up on
+    pub slow: u64,
+}
+
+impl AskLatency {
+    /// Answer times kept for the average and maximum
+    const KEEP: usize = 100;
+
+    /// Count an answer, true if it came later than `deadline`
+    pub fn record(&mut self, latency: Duration, deadline: Duration) -> bool {
+        if self.recent.len() == Self::KEEP {
+            self.recent.pop_front();
+        }
+        self.recent.push_back(latency.as_millis() as u64);
+        self.answered += 1;
+        let slow = latency > deadline;
+        if slow {
+            self.slow += 1;
+        }
+        slow
+    }
+
+    pub fn last(&self) -> Option<Duration> {
+        self.recent.back().map(|&ms| Duration::from_millis(ms))
+    }
+
+    pub fn average(&self) -> Option<Duration> {
+        let total: u64 = self.recent.iter().sum();
+        (!self.recent.is_empty()).then(|| Duration::from_millis(total / self.recent.len() as u64))
+    }
+
+    pub fn max(&self) -> Option<Duration> {
+        self.recent.iter().max().map(|&ms| Duration::from_millis(ms))
+    }
+}
+
 /// Client configuration received during Subscribe
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct ClientConfig {
@@ -126,11 +285,106 @@ pub struct ClientConfig {
     pub system_firewall: Option<SysFirewall>,
 }
 
+/// A window of a node's rules matching a filter
+///
+/// Nodes can carry tens of thousands of rules, the UI only ever copies
+/// the window it shows. Windows are cut from the rules the node sent, the
+/// database only keeps the ones changed from here.
+#[derive(Debug, Clone, Default)]
+pub struct RulePage {
+    /// Position of the first rule among the matching ones
+    pub offset: usize,
+    pub rules: Vec<Rule>,
+    /// Rules matching the filter
+    pub matched: usize,
+    /// All rules of the node
+    pub total: usize,
+}
+
+impl RulePage {
+    /// Up to `limit` of the rules at `positions` from `offset` on, moved back
+    /// to stay full when the offset runs past the end. Only these are cloned.
+    pub fn window(rules: &[Rule], positions: &[usize], offset: usize, limit: usize) -> Self {
+        let offset = offset.min(positions.len().saturating_sub(limit));
+        Self {
+            offset,
+            rules: positions.iter().skip(offset).take(limit).map(|&i| rules[i].clone()).collect(),
+            matched: positions.len(),
+            total: rules.len(),
+        }
+    }
+}
+
+/// Lowercased text of each rule of a list, for the rules filter
+///
+/// Built once per rule list instead of lowercasing every rule on each key
+/// press. A filter extending the previous one only looks among the rules
+/// that matched it, so typing narrows down what's left.
+#[derive(Debug, Default)]
+pub struct RuleIndex {
+    /// Of the rules the index was built from, to notice when they change
+    fingerprint: u64,
+    texts: Vec<String>,
+    /// Filter the matches are for
+    query: String,
+    /// Positions of the rules matching the filter
+    matches: Vec<usize>,
+}
+
+impl RuleIndex {
+    /// Build the index again, unless it is for these rules already
+    pub fn update(&mut self, rules: &[Rule]) {
+        let mut hasher = DefaultHasher::new();
+        for rule in rules {
+            (&rule.name, &rule.description, &rule.operator.operand, &rule.operator.data).hash(&mut hasher);
+        }
+        let fingerprint = hasher.finish();
+        if fingerprint == self.fingerprint && self.texts.len() == rules.len() {
+            return;
+        }
+        self.fingerprint = fingerprint;
+        self.texts = rules.iter().map(Rule::search_text).collect();
+        self.query.clear();
+        self.matches = (0..rules.len()).collect();
+    }
+
+    /// Positions of the rules matching `query`, in list order
+    pub fn filter(&mut self, query: &str) -> &[usize] {
+        let query = query.to_lowercase();
+        if query != self.query {
+            if !query.starts_with(&self.query) {
+                self.matches = (0..self.texts.len()).collect();
+            }
+            let texts = &self.texts;
+            self.matches.retain(|&i| texts[i].contains(&query));
+            self.query = query;
+        }
+        &self.matches
+    }
+}
+
+/// Addresses rejected by authentication kept track of, the one quiet the
+/// longest is forgotten first
+pub const MAX_REJECTED_PEERS: usize = 16;
+
+/// Requests rejected by authentication from one IP address
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RejectedPeer {
+    pub ip: String,
+    pub requests: u64,
+    pub last_seen: DateTime<Utc>,
+}
+
 /// Node manager for handling multiple daemon connections
 #[derive(Debug, Default)]
 pub struct NodeManager {
     pub nodes: HashMap<String, Node>,
     pub active_node: Option<String>,
+    /// Requests rejected by authentication, by IP rather than by the
+    /// ephemeral address each one comes from
+    pub rejected: Vec<RejectedPeer>,
+    /// Filter index of each node's rules, kept across refreshes
+    rule_indexes: Mutex<HashMap<String, RuleIndex>>,
 }
 
 impl NodeManager {
@@ -154,20 +408,67 @@ impl NodeManager {
         node
     }
 
+    /// Count a request rejected by authentication against its IP, returns
+    /// the requests rejected from there so far
+    pub fn record_rejection(&mut self, addr: &str) -> u64 {
+        let ip = addr
+            .parse::<std::net::SocketAddr>()
+            .map_or_else(|_| addr.to_string(), |a| a.ip().to_string());
+        let index = match self.rejected.iter().position(|peer| peer.ip == ip) {
+            Some(index) => index,
+            None => {
+                if self.rejected.len() >= MAX_REJECTED_PEERS {
+                    let quietest = (0..self.rejected.len()).min_by_key(|&i| self.rejected[i].last_seen);
+                    if let Some(i) = quietest {
+                        self.rejected.remove(i);
+                    }
+                }
+                self.rejected.push(RejectedPeer { ip, requests: 0, last_seen: Utc::now() });
+                self.rejected.len() - 1
+            }
+        };
+        let peer = &mut self.rejected[index];
+        peer.requests += 1;
+        peer.last_seen = Utc::now();
+        peer.requests
+    }
+
+    /// Archive a node that went away
     pub fn remove_node(&mut self, addr: &str) {
         if let Some(node) = self.nodes.get_mut(addr) {
-            node.disconnect();
+            node.archive();
         }
 
-        // If this was the active node, switch to another
+        // If this was the active node, switch to another live one, or keep
+        // showing the archive when there is none
         if self.active_node.as_deref() == Some(addr) {
-            self.active_node = self.nodes
-                .iter()
-                .find(|(_, n)| n.status == NodeStatus::Connected)
-                .map(|(a, _)| a.clone());
+            let live = self.connected_nodes().next().map(|n| n.addr.clone());
+            if live.is_some() {
+                self.active_node = live;
+            }
         }
     }
 
+    /// Add an archived node loaded from the database, unless it is known already
+    pub fn restore_archived(&mut self, mut node: Node) {
+        node.archive();
+        self.nodes.entry(node.addr.clone()).or_insert(node);
+    }
+
+    /// Drop an archived node, live nodes are kept
+    pub fn forget_archived(&mut self, addr: &str) -> bool {
+        if !self.nodes.get(addr).is_some_and(|n| n.archived) {
+            return false;
+        }
+        self.nodes.remove(addr);
+        self.rule_indexes.lock().unwrap().remove(addr);
+        if self.active_node.as_deref() == Some(addr) {
+            let live = self.connected_nodes().next().map(|n| n.addr.clone());
+            self.active_node = live;
+        }
+        true
+    }
+
     pub fn get_node(&self, addr: &str) -> Option<&Node> {
         self.nodes.get(addr)
     }
@@ -207,6 +508,33 @@ impl NodeManager {
         self.nodes.len()
     }
 
+    /// Whether the active node is an archive that can only be browsed
+    pub fn active_archived(&self) -> bool {
+        self.active_node().is_some_and(|n| n.archived)
+    }
+
+    /// Window of the node's rules matching `query`, as the node has them
+    pub fn rule_page(&self, addr: &str, query: &str, offset: usize, limit: usize) -> RulePage {
+        let Some(node) = self.nodes.get(addr) else {
+            return RulePage::default();
+        };
+        let mut indexes = self.rule_indexes.lock().unwrap();
+        let index = indexes.entry(addr.to_string()).or_default();
+        index.update(&node.rules);
+        RulePage::window(&node.rules, index.filter(query), offset, limit)
+    }
+
+    /// Positions of the node's rules matching `query`, for ordering them otherwise
+    pub fn matching_rules(&self, addr: &str, query: &str) -> Vec<usize> {
+        let Some(node) = self.nodes.get(addr) else {
+            return Vec::new();
+        };
+        let mut indexes = self.rule_indexes.lock().unwrap();
+        let index = indexes.entry(addr.to_string()).or_default();
+        index.update(&node.rules);
+        index.filter(query).to_vec()
+    }
+
     pub fn connected_count(&self) -> usize {
         self.nodes.values().filter(|n| n.status == NodeStatus::Connected).count()
     }
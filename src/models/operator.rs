@@ -1,5 +1,8 @@
 use serde::{Deserialize, Serialize};
 use std::fmt;
+use std::net::IpAddr;
+
+use super::Connection;
 
 /// Operator types for rule matching
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -191,10 +194,15 @@ pub struct Operator {
     pub data: String,
     #[serde(default)]
     pub sensitive: bool,
-    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    /// The daemon writes `"list": null` for operators that aren't lists
+    #[serde(default, deserialize_with = "null_as_empty", skip_serializing_if = "Vec::is_empty")]
     pub list: Vec<Operator>,
 }
 
+fn null_as_empty<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Vec<Operator>, D::Error> {
+    Option::deserialize(deserializer).map(Option::unwrap_or_default)
+}
+
 impl Operator {
     pub fn new(op_type: OperatorType, operand: &str, data: &str) -> Self {
         Self {
@@ -233,3 +241,75 @@ impl Operator {
         self
     }
 }
+
+impl Operator {
+    /// Evaluate this operator against a connection the way the daemon would.
+    ///
+    /// Operands whose value is only known to the daemon (lists on disk,
+    /// interfaces, user names) never match.
+    pub fn matches(&self, conn: &Connection) -> bool {
+        if self.op_type == OperatorType::List {
+            return !self.list.is_empty() && self.list.iter().all(|op| op.matches(conn));
+        }
+        if self.operand == "true" {
+            return true;
+        }
+        let Some(value) = operand_value(&Operand::from(self.operand.as_str()), conn) else {
+            return false;
+        };
+        match self.op_type {
+            OperatorType::Simple if self.sensitive => value == self.data,
+            OperatorType::Simple => value.eq_ignore_ascii_case(&self.data),
+            OperatorType::Regexp => {
+                let pattern = if self.sensitive {
+                    self.data.clone()
+                } else {
+                    format!("(?i){}", self.data)
+                };
+                regex::Regex::new(&pattern).is_ok_and(|re| re.is_match(&value))
+            }
+            OperatorType::Network => cidr_contains(&self.data, &value),
+            OperatorType::List | OperatorType::Lists => false,
+        }
+    }
+}
+
+/// Value of an operand for a connection, if the TUI can know it
+fn operand_value(operand: &Operand, conn: &Connection) -> Option<String> {
+    let value = match operand {
+        Operand::ProcessId => conn.process_id.to_string(),
+        Operand::ProcessPath => conn.process_path.clone(),
+        Operand::ProcessCommand => conn.process_args.join(" "),
+        Operand::ProcessEnv(name) => conn.process_env.get(name)?.clone(),
+        Operand::ProcessHashMd5 => conn.process_checksums.get("md5")?.clone(),
+        Operand::ProcessHashSha1 => conn.process_checksums.get("sha1")?.clone(),
+        Operand::UserId => conn.user_id.to_string(),
+        Operand::SourceIp | Operand::SourceNetwork => conn.src_ip.clone(),
+        Operand::SourcePort => conn.src_port.to_string(),
+        Operand::DestIp | Operand::DestNetwork => conn.dst_ip.clone(),
+        Operand::DestHost => conn.dst_host.clone(),
+        Operand::DestPort => conn.dst_port.to_string(),
+        Operand::Protocol => conn.protocol.clone(),
+        _ => return None,
+    };
+    Some(value)
+}
+
+/// Whether `ip` falls inside the `cidr` network ("10.0.0.0/8", or a bare address)
+fn cidr_contains(cidr: &str, ip: &str) -> bool {
+    let (net, bits) = cidr.split_once('/').unwrap_or((cidr, ""));
+    let (Ok(net), Ok(ip)) = (net.parse::<IpAddr>(), ip.parse::<IpAddr>()) else {
+        return false;
+    };
+    let (net, ip, width) = match (net, ip) {
+        (IpAddr::V4(n), IpAddr::V4(i)) => (u32::from(n) as u128, u32::from(i) as u128, 32),
+        (IpAddr::V6(n), IpAddr::V6(i)) => (u128::from(n), u128::from(i), 128),
+        _ => return false,
+    };
+    let bits: u32 = if bits.is_empty() { width } else { bits.parse().unwrap_or(u32::MAX) };
+    if bits > width {
+        return false;
+    }
+    let shift = width - bits;
+    shift >= width || (net >> shift) == (ip >> shift)
+}
@@ -0,0 +1,75 @@
+//! Named policies grouping the rules of a node
+//!
+//! The daemon knows nothing about them: which rule belongs to which policy
+//! is kept in a local file, and switching a policy on or off enables or
+//! disables its rules on the node.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use super::Rule;
+
+/// Policy of each rule, per node
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Policies {
+    /// Node address to rule name to policy name
+    nodes: BTreeMap<String, BTreeMap<String, String>>,
+}
+
+impl Policies {
+    /// Load policies, falling back to none if missing or unreadable
+    pub fn load(path: &Path) -> Self {
+        std::fs::read_to_string(path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Policy a rule of a node belongs to
+    pub fn policy_of(&self, node: &str, rule: &str) -> Option<&str> {
+        self.nodes.get(node)?.get(rule).map(String::as_str)
+    }
+
+    /// Put a rule into a policy, or take it out of its policy with a blank name
+    pub fn assign(&mut self, node: &str, rule: &str, policy: &str) {
+        let policy = policy.trim();
+        if policy.is_empty() {
+            if let Some(rules) = self.nodes.get_mut(node) {
+                rules.remove(rule);
+                if rules.is_empty() {
+                    self.nodes.remove(node);
+                }
+            }
+            return;
+        }
+        self.nodes
+            .entry(node.to_string())
+            .or_default()
+            .insert(rule.to_string(), policy.to_string());
+    }
+
+    /// Rules of a node by policy name, sorted by name, and the rules in none
+    pub fn group<'a>(&self, node: &str, rules: &'a [Rule]) -> (BTreeMap<String, Vec<&'a Rule>>, Vec<&'a Rule>) {
+        let mut policies: BTreeMap<String, Vec<&Rule>> = BTreeMap::new();
+        let mut unassigned = Vec::new();
+        for rule in rules {
+            match self.policy_of(node, &rule.name) {
+                Some(policy) => policies.entry(policy.to_string()).or_default().push(rule),
+                None => unassigned.push(rule),
+            }
+        }
+        (policies, unassigned)
+    }
+}
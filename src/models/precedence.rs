@@ -0,0 +1,193 @@
+//! Simulation of the daemon's rule evaluation order
+
+use super::{Connection, Event, Operator, OperatorType, Rule, RuleAction};
+
+/// Rule the daemon would apply to a connection.
+///
+/// Enabled rules are checked in name order. A matching deny, reject or
+/// precedence rule decides immediately; otherwise the last matching allow
+/// rule wins.
+pub fn verdict<'a>(rules: impl IntoIterator<Item = &'a Rule>, conn: &Connection) -> Option<&'a Rule> {
+    let mut active: Vec<&Rule> = rules.into_iter().filter(|r| r.enabled).collect();
+    active.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let mut matched = None;
+    for rule in active {
+        if !rule.operator.matches(conn) {
+            continue;
+        }
+        if rule.precedence || rule.action != RuleAction::Allow {
+            return Some(rule);
+        }
+        matched = Some(rule);
+    }
+    matched
+}
+
+/// A past connection evaluated again against the current rules
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Replay {
+    /// Rule that applied when the connection happened
+    pub then: Option<(String, RuleAction)>,
+    /// Rule the daemon would apply now
+    pub now: Option<(String, RuleAction)>,
+}
+
+impl Replay {
+    pub fn of(rules: &[Rule], event: &Event) -> Self {
+        Self {
+            then: event.rule.as_ref().map(|r| (r.name.clone(), r.action)),
+            now: verdict(rules, &event.connection).map(|r| (r.name.clone(), r.action)),
+        }
+    }
+
+    /// Whether today's rules treat the connection differently
+    pub fn changed(&self) -> bool {
+        self.then.as_ref().map(|(_, action)| action) != self.now.as_ref().map(|(_, action)| action)
+    }
+
+    pub fn message(&self) -> String {
+        let describe = |rule: &Option<(String, RuleAction)>| match rule {
+            Some((name, action)) => format!("{} by rule '{}'", action, name),
+            None => "no rule".to_string(),
+        };
+        let outcome = match &self.now {
+            // Lists, interfaces and user names are only known to the daemon
+            None => "No rule matches now, the daemon would ask or apply its default action.".to_string(),
+            Some((_, action)) if self.changed() => format!("Changed: today's rules would {} it.", action),
+            Some((_, action)) => format!("Unchanged: today's rules would still {} it.", action),
+        };
+        format!("Then: {}\nNow:  {}\n\n{}", describe(&self.then), describe(&self.now), outcome)
+    }
+}
+
+/// How an existing rule gets in the way of a new one
+#[derive(Debug, Clone, Copy)]
+pub enum Shadow<'a> {
+    /// The existing rule wins and applies a different action
+    Overridden(&'a Rule),
+    /// The existing rule already applies the same action
+    Redundant(&'a Rule),
+}
+
+impl Shadow<'_> {
+    pub fn message(&self) -> String {
+        match self {
+            Self::Overridden(rule) => format!(
+                "Ineffective: rule '{}' ({}) is applied first",
+                rule.name, rule.action
+            ),
+            Self::Redundant(rule) => format!(
+                "Redundant: rule '{}' already applies {}",
+                rule.name, rule.action
+            ),
+        }
+    }
+}
+
+/// Check whether adding `new_rule` to `existing` would change the verdict
+/// for `conn`. A rule with the same name replaces the existing one, as it
+/// does on the daemon.
+pub fn shadowing<'a>(existing: &'a [Rule], new_rule: &Rule, conn: &Connection) -> Option<Shadow<'a>> {
+    let others: Vec<&Rule> = existing.iter().filter(|r| r.name != new_rule.name).collect();
+    let before = verdict(others.iter().copied(), conn);
+    let after = verdict(others.iter().copied().chain(std::iter::once(new_rule)), conn);
+
+    let new_wins = after.is_some_and(|winner| std::ptr::eq(winner, new_rule));
+
+    // When the new rule loses, the verdict is the one it already was
+    let existing = before?;
+    if existing.action == new_rule.action {
+        Some(Shadow::Redundant(existing))
+    } else if new_wins {
+        None
+    } else {
+        Some(Shadow::Overridden(existing))
+    }
+}
+
+/// An enabled rule applying the opposite action to connections a new rule
+/// also matches
+#[derive(Debug, Clone, Copy)]
+pub struct Conflict<'a> {
+    pub rule: &'a Rule,
+    /// Where both match, the new rule is the one applied
+    pub new_rule_wins: bool,
+}
+
+impl Conflict<'_> {
+    pub fn message(&self) -> String {
+        format!(
+            "'{}' would {} the same connections, {} applied first",
+            self.rule.name,
+            self.rule.action,
+            if self.new_rule_wins { "this rule is" } else { "it is" }
+        )
+    }
+}
+
+/// Existing rules that overlap `new_rule` with the opposite action. A rule
+/// with the same name is replaced rather than conflicting.
+pub fn conflicts<'a>(existing: &'a [Rule], new_rule: &Rule) -> Vec<Conflict<'a>> {
+    if !new_rule.enabled {
+        return Vec::new();
+    }
+    let allows = new_rule.action == RuleAction::Allow;
+    existing
+        .iter()
+        .filter(|r| r.enabled && r.name != new_rule.name)
+        .filter(|r| (r.action == RuleAction::Allow) != allows && overlaps(r, new_rule))
+        .map(|rule| Conflict {
+            rule,
+            new_rule_wins: std::ptr::eq(applied_first(rule, new_rule), new_rule),
+        })
+        .collect()
+}
+
+/// Which of two rules matching a connection the daemon applies, see `verdict`
+fn applied_first<'a>(a: &'a Rule, b: &'a Rule) -> &'a Rule {
+    let (first, second) = if a.name <= b.name { (a, b) } else { (b, a) };
+    if first.precedence || first.action != RuleAction::Allow {
+        first
+    } else {
+        second
+    }
+}
+
+/// A single condition of a rule
+#[derive(PartialEq)]
+struct Condition {
+    operand: String,
+    value: String,
+    exact: bool,
+}
+
+fn leaf_conditions(operator: &Operator, out: &mut Vec<Condition>) {
+    if operator.op_type == OperatorType::List {
+        operator.list.iter().for_each(|op| leaf_conditions(op, out));
+        return;
+    }
+    out.push(Condition {
+        operand: operator.operand.clone(),
+        value: if operator.sensitive { operator.data.clone() } else { operator.data.to_lowercase() },
+        exact: operator.op_type == OperatorType::Simple,
+    });
+}
+
+/// Whether two rules can match the same connection, judged without one:
+/// they share a condition, or one matches everything, and no operand is
+/// required to have two different exact values
+fn overlaps(a: &Rule, b: &Rule) -> bool {
+    let (mut left, mut right) = (Vec::new(), Vec::new());
+    leaf_conditions(&a.operator, &mut left);
+    leaf_conditions(&b.operator, &mut right);
+
+    let contradicts = left.iter().any(|l| {
+        right
+            .iter()
+            .any(|r| l.exact && r.exact && l.operand == r.operand && l.value != r.value)
+    });
+    let matches_all = |conditions: &[Condition]| conditions.iter().any(|c| c.operand == "true");
+    let shared = left.iter().any(|l| right.contains(l));
+    (shared || matches_all(&left) || matches_all(&right)) && !contradicts
+}
@@ -0,0 +1,173 @@
+//! Curated rule bundles for common applications
+//!
+//! A profile allows what an application needs to work: its executables
+//! talking to the ports it uses. Rules generated from a profile carry its id
+//! in the description, so they can be found and removed together later.
+
+use super::{Operator, Rule, RuleAction, RuleDuration};
+
+/// Description prefix marking the rules of a profile
+const PROFILE_TAG: &str = "[profile:";
+
+/// Executables of one application reaching some ports
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProfileRule {
+    pub name: &'static str,
+    /// Regexp on process.path
+    pub process: &'static str,
+    /// Regexp on dest.port
+    pub ports: &'static str,
+    /// "tcp" or "udp", None for both
+    pub protocol: Option<&'static str>,
+}
+
+/// An application and the rules it needs
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AppProfile {
+    pub id: &'static str,
+    pub name: &'static str,
+    pub category: &'static str,
+    pub description: &'static str,
+    pub rules: Vec<ProfileRule>,
+}
+
+impl AppProfile {
+    /// Allow rules for the profile, tagged with its id
+    pub fn rules(&self) -> Vec<Rule> {
+        self.rules
+            .iter()
+            .map(|rule| {
+                let mut operators = vec![
+                    Operator::regexp("process.path", rule.process),
+                    Operator::regexp("dest.port", rule.ports),
+                ];
+                if let Some(protocol) = rule.protocol {
+                    operators.push(Operator::simple("protocol", protocol));
+                }
+                Rule::new(
+                    &format!("profile-{}-{}", self.id, rule.name),
+                    RuleAction::Allow,
+                    RuleDuration::Always,
+                    Operator::list(operators),
+                )
+                .with_description(&format!("{}{}] {}: {}", PROFILE_TAG, self.id, self.name, rule.name))
+            })
+            .collect()
+    }
+
+    /// Rules among `rules` that came from this profile
+    pub fn applied<'a>(&self, rules: &'a [Rule]) -> Vec<&'a Rule> {
+        rules.iter().filter(|rule| rule.profile() == Some(self.id)).collect()
+    }
+}
+
+impl Rule {
+    /// Id of the profile the rule came from
+    pub fn profile(&self) -> Option<&str> {
+        let rest = self.description.strip_prefix(PROFILE_TAG)?;
+        rest.split_once(']').map(|(id, _)| id)
+    }
+}
+
+/// Web ports, for everything talking HTTP
+const WEB: &str = "^(80|443)$";
+
+/// Steam's client and web helper, as packaged or in a user's Steam runtime
+const STEAM: &str = r"^(/usr/games|/home/[^/]+/\.local/share/Steam/.*)/(steam|steamwebhelper)$";
+
+/// The shipped profiles
+pub fn catalog() -> Vec<AppProfile> {
+    let rule = |name, process, ports, protocol| ProfileRule { name, process, ports, protocol };
+    vec![
+        AppProfile {
+            id: "firefox",
+            name: "Firefox",
+            category: "Browsers",
+            description: "Web browsing over HTTP, HTTPS and HTTP/3",
+            rules: vec![
+                rule("web", "^/(usr/lib(64)?/firefox[^/]*|opt/firefox)/firefox(-bin)?$", WEB, Some("tcp")),
+                rule("http3", "^/(usr/lib(64)?/firefox[^/]*|opt/firefox)/firefox(-bin)?$", "^443$", Some("udp")),
+            ],
+        },
+        AppProfile {
+            id: "chromium",
+            name: "Chromium and Chrome",
+            category: "Browsers",
+            description: "Web browsing over HTTP, HTTPS and QUIC",
+            rules: vec![
+                rule("web", "^/(usr/lib(64)?/chromium[^/]*|opt/google/chrome)/(chromium|chrome)$", WEB, Some("tcp")),
+                rule("quic", "^/(usr/lib(64)?/chromium[^/]*|opt/google/chrome)/(chromium|chrome)$", "^443$", Some("udp")),
+            ],
+        },
+        AppProfile {
+            id: "apt",
+            name: "APT",
+            category: "Package managers",
+            description: "Debian and Ubuntu package downloads",
+            rules: vec![rule("mirrors", "^/usr/lib/apt/methods/https?$", WEB, Some("tcp"))],
+        },
+        AppProfile {
+            id: "dnf",
+            name: "DNF",
+            category: "Package managers",
+            // dnf 4 runs as python3, allowing that would allow any script
+            description: "Fedora package downloads with dnf5 and PackageKit",
+            rules: vec![
+                rule("mirrors", "^/usr/bin/dnf5$", WEB, Some("tcp")),
+                rule("packagekit", "^/usr/libexec/packagekitd$", WEB, Some("tcp")),
+            ],
+        },
+        AppProfile {
+            id: "pacman",
+            name: "pacman",
+            category: "Package managers",
+            description: "Arch Linux package downloads",
+            rules: vec![rule("mirrors", "^/usr/bin/pacman$", WEB, Some("tcp"))],
+        },
+        AppProfile {
+            id: "flatpak",
+            name: "Flatpak",
+            category: "Package managers",
+            description: "Flathub installs and updates",
+            rules: vec![rule("remotes", "^/usr/(bin/flatpak|libexec/flatpak-system-helper)$", WEB, Some("tcp"))],
+        },
+        AppProfile {
+            id: "thunderbird",
+            name: "Thunderbird",
+            category: "Email clients",
+            description: "IMAP, POP3 and SMTP with or without TLS, plus web content",
+            rules: vec![
+                rule(
+                    "mail",
+                    "^/(usr/lib(64)?/thunderbird|opt/thunderbird)/thunderbird(-bin)?$",
+                    "^(25|110|143|465|587|993|995)$",
+                    Some("tcp"),
+                ),
+                rule("web", "^/(usr/lib(64)?/thunderbird|opt/thunderbird)/thunderbird(-bin)?$", WEB, Some("tcp")),
+            ],
+        },
+        AppProfile {
+            id: "evolution",
+            name: "Evolution",
+            category: "Email clients",
+            description: "IMAP, POP3, SMTP and calendars",
+            rules: vec![rule(
+                "mail",
+                "^/usr/(bin/evolution|libexec/evolution-(source-registry|calendar-factory|addressbook-factory))$",
+                "^(25|110|143|443|465|587|993|995)$",
+                Some("tcp"),
+            )],
+        },
+        AppProfile {
+            id: "steam",
+            name: "Steam",
+            category: "Games",
+            description: "Store, downloads and game traffic",
+            rules: vec![
+                rule("store", STEAM, WEB, Some("tcp")),
+                rule("games", STEAM, "^270(1[5-9]|[2-4][0-9]|50)$", None),
+                rule("voice", STEAM, "^(3478|4379|4380)$", Some("udp")),
+            ],
+        },
+    ]
+}
@@ -0,0 +1,54 @@
+//! Connection prompts and what became of them
+
+use chrono::{DateTime, Utc};
+use std::fmt;
+
+use super::Connection;
+
+/// What became of a prompt
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PromptOutcome {
+    /// Queued or on screen
+    Pending,
+    Answered,
+    /// The countdown ran out, the daemon applied its default
+    TimedOut,
+    /// Still pending when the TUI went away
+    Lost,
+    /// Followed up with a rule, or dismissed
+    Resolved,
+}
+
+impl fmt::Display for PromptOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Pending => write!(f, "pending"),
+            Self::Answered => write!(f, "answered"),
+            Self::TimedOut => write!(f, "timed_out"),
+            Self::Lost => write!(f, "lost"),
+            Self::Resolved => write!(f, "resolved"),
+        }
+    }
+}
+
+impl From<&str> for PromptOutcome {
+    fn from(s: &str) -> Self {
+        match s {
+            "answered" => Self::Answered,
+            "timed_out" => Self::TimedOut,
+            "lost" => Self::Lost,
+            "resolved" => Self::Resolved,
+            _ => Self::Pending,
+        }
+    }
+}
+
+/// A prompt that timed out or was lost, kept until a rule is made for it
+#[derive(Debug, Clone)]
+pub struct MissedPrompt {
+    pub id: i64,
+    pub time: DateTime<Utc>,
+    pub node_addr: String,
+    pub connection: Connection,
+    pub outcome: PromptOutcome,
+}
@@ -0,0 +1,82 @@
+//! Quarantine of a node
+//!
+//! A quarantined node may only resolve names, renew its DHCP lease and
+//! reach the management hosts. The rules allowing that are important, so
+//! they win over others, and are named to sort ahead of the rule denying
+//! everything else. The node's own rules are disabled meanwhile.
+
+use std::net::IpAddr;
+
+use super::{Operator, Rule, RuleAction, RuleDuration};
+
+/// Names of the quarantine rules start with this
+pub const RULE_PREFIX: &str = "000-quarantine-";
+
+/// Name of the rule denying what the others don't allow
+pub const DENY_RULE: &str = "000-quarantine-deny-all";
+
+/// Rules quarantining a node, `allowlist` naming the management hosts by
+/// address, network or host name
+pub fn rules(allowlist: &[String]) -> Result<Vec<Rule>, String> {
+    let mut rules = vec![
+        allow("dns", "name resolution", Operator::simple("dest.port", "53")),
+        allow(
+            "dhcp",
+            "DHCP leases",
+            Operator::list(vec![Operator::simple("protocol", "udp"), Operator::regexp("dest.port", "^(67|547)$")]),
+        ),
+    ];
+    for host in allowlist.iter().map(|h| h.trim()).filter(|h| !h.is_empty()) {
+        let rule = allow(&format!("host-{}", slug(host)), &format!("management host {}", host), host_operator(host)?);
+        if !rules.iter().any(|r| r.name == rule.name) {
+            rules.push(rule);
+        }
+    }
+    rules.push(
+        Rule::new(DENY_RULE, RuleAction::Deny, RuleDuration::Always, Operator::simple("true", ""))
+            .with_description("Quarantine: everything else"),
+    );
+    Ok(rules)
+}
+
+pub fn is_quarantine_rule(name: &str) -> bool {
+    name.starts_with(RULE_PREFIX)
+}
+
+fn allow(name: &str, what: &str, operator: Operator) -> Rule {
+    Rule::new(&format!("{}allow-{}", RULE_PREFIX, name), RuleAction::Allow, RuleDuration::Always, operator)
+        .with_precedence(true)
+        .with_description(&format!("Quarantine: {}", what))
+}
+
+fn host_operator(host: &str) -> Result<Operator, String> {
+    if host.parse::<IpAddr>().is_ok() {
+        return Ok(Operator::simple("dest.ip", host));
+    }
+    if let Some((ip, prefix)) = host.split_once('/') {
+        let max = match ip.parse::<IpAddr>() {
+            Ok(IpAddr::V4(_)) => 32,
+            Ok(IpAddr::V6(_)) => 128,
+            Err(_) => return Err(format!("'{}' is not a network, {} is no address", host, ip)),
+        };
+        return match prefix.parse::<u8>() {
+            Ok(bits) if bits <= max => Ok(Operator::network("dest.network", host)),
+            _ => Err(format!("'{}' is not a network, the prefix goes up to /{}", host, max)),
+        };
+    }
+    let valid = host.split('.').all(|label| {
+        !label.is_empty() && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+    });
+    if valid {
+        Ok(Operator::simple("dest.host", host))
+    } else {
+        Err(format!("'{}' is neither an address, a network nor a host name", host))
+    }
+}
+
+/// Rule names end up as file names on the node
+fn slug(host: &str) -> String {
+    host.chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '.' || c == '-' { c } else { '-' })
+        .collect()
+}
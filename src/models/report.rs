@@ -0,0 +1,207 @@
+//! Human-readable rule reports
+//!
+//! Rules are grouped by the application they apply to and each one is
+//! spelled out as a sentence, e.g. "deny firefox → *.doubleclick.net
+//! always", for review or compliance documentation.
+
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Utc};
+
+use super::{Operand, Operator, OperatorType, Rule, RuleDuration};
+
+/// Group for rules that don't name an application
+const ANY_APPLICATION: &str = "any application";
+
+/// Output format of a rules report
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportFormat {
+    Markdown,
+    Text,
+}
+
+impl ReportFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Markdown => "md",
+            Self::Text => "txt",
+        }
+    }
+}
+
+/// One rule as a sentence: action, application, destination, duration
+pub fn rule_summary(rule: &Rule) -> String {
+    let conditions = conditions(&rule.operator);
+    let app = application(&conditions).unwrap_or_else(|| ANY_APPLICATION.to_string());
+
+    let mut target: Vec<&str> = conditions
+        .iter()
+        .filter(|c| c.destination)
+        .map(|c| c.text.as_str())
+        .collect();
+    if target.is_empty() {
+        target.push("*");
+    }
+    let extra: Vec<&str> = conditions
+        .iter()
+        .filter(|c| !c.destination && !c.application)
+        .map(|c| c.text.as_str())
+        .collect();
+
+    let mut summary = format!("{} {} → {}", rule.action, app, target.join(" "));
+    if !extra.is_empty() {
+        summary.push(' ');
+        summary.push_str(&extra.join(" "));
+    }
+    summary.push(' ');
+    summary.push_str(&duration_prose(&rule.duration));
+    summary
+}
+
+/// Report of every rule of a node, grouped by application
+pub fn rules_report(node: &str, rules: &[Rule], format: ReportFormat, generated: DateTime<Utc>) -> String {
+    let mut groups: BTreeMap<(bool, String), Vec<&Rule>> = BTreeMap::new();
+    for rule in rules {
+        let app = application(&conditions(&rule.operator));
+        // Rules for any application go last
+        let key = (app.is_none(), app.unwrap_or_else(|| ANY_APPLICATION.to_string()));
+        groups.entry(key).or_default().push(rule);
+    }
+
+    let disabled = rules.iter().filter(|r| !r.enabled).count();
+    let title = format!("OpenSnitch rules: {}", node);
+    let overview = format!(
+        "Generated {}, {} rules ({} disabled) for {} applications",
+        generated.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M"),
+        rules.len(),
+        disabled,
+        groups.len()
+    );
+
+    let mut out = String::new();
+    match format {
+        ReportFormat::Markdown => out.push_str(&format!("# {}\n\n{}\n", title, overview)),
+        ReportFormat::Text => out.push_str(&format!("{}\n{}\n\n{}\n", title, "=".repeat(title.chars().count()), overview)),
+    }
+
+    for ((_, app), rules) in &groups {
+        match format {
+            ReportFormat::Markdown => out.push_str(&format!("\n## {}\n\n", app)),
+            ReportFormat::Text => out.push_str(&format!("\n{}\n{}\n", app, "-".repeat(app.chars().count()))),
+        }
+        for rule in rules {
+            let mut flags = Vec::new();
+            if !rule.enabled {
+                flags.push("disabled");
+            }
+            if rule.precedence {
+                flags.push("priority");
+            }
+            if rule.nolog {
+                flags.push("not logged");
+            }
+            let flags = if flags.is_empty() {
+                String::new()
+            } else {
+                format!(" ({})", flags.join(", "))
+            };
+
+            match format {
+                ReportFormat::Markdown => {
+                    out.push_str(&format!("- `{}`: {}{}\n", rule.name, rule_summary(rule), flags));
+                    if !rule.description.is_empty() {
+                        out.push_str(&format!("  {}\n", rule.description));
+                    }
+                }
+                ReportFormat::Text => {
+                    out.push_str(&format!("  * {}: {}{}\n", rule.name, rule_summary(rule), flags));
+                    if !rule.description.is_empty() {
+                        out.push_str(&format!("    {}\n", rule.description));
+                    }
+                }
+            }
+        }
+    }
+    out
+}
+
+/// A single matching condition in prose
+struct Condition {
+    text: String,
+    /// Names the application the rule applies to
+    application: bool,
+    /// Describes where the connection goes
+    destination: bool,
+}
+
+/// Flatten an operator into its conditions, all of which must match
+fn conditions(operator: &Operator) -> Vec<Condition> {
+    if operator.op_type == OperatorType::List {
+        return operator.list.iter().flat_map(conditions).collect();
+    }
+
+    let value = match operator.op_type {
+        OperatorType::Regexp => pattern_prose(&operator.data),
+        _ => operator.data.clone(),
+    };
+    let (text, application, destination) = match Operand::from(operator.operand.as_str()) {
+        Operand::ProcessPath | Operand::ProcessCommand => (program_name(&value), true, false),
+        Operand::DestHost | Operand::DestIp | Operand::DestNetwork => (value, false, true),
+        Operand::DestPort => (format!("port {}", value), false, true),
+        Operand::ListsDomains | Operand::ListsDomainsRegexp => (format!("domains listed in {}", value), false, true),
+        Operand::ListsIps | Operand::ListsNets => (format!("addresses listed in {}", value), false, true),
+        Operand::Protocol => (format!("over {}", value), false, false),
+        Operand::UserId | Operand::UserName => (format!("as user {}", value), false, false),
+        Operand::SourceIp | Operand::SourceNetwork => (format!("from {}", value), false, false),
+        Operand::SourcePort => (format!("from port {}", value), false, false),
+        Operand::IfaceIn => (format!("in on {}", value), false, false),
+        Operand::IfaceOut => (format!("out on {}", value), false, false),
+        Operand::ProcessParentPath => (format!("launched by {}", program_name(&value)), false, false),
+        Operand::ProcessId => (format!("with pid {}", value), false, false),
+        Operand::ProcessEnv(name) => (format!("with {}={}", name, value), false, false),
+        Operand::ProcessHashMd5 | Operand::ProcessHashSha1 | Operand::ListsHashMd5 => {
+            (format!("with binary hash {}", value), false, false)
+        }
+        Operand::List => (value, false, false),
+        Operand::Unknown(operand) => (format!("where {} is {}", operand, value), false, false),
+    };
+    vec![Condition { text, application, destination }]
+}
+
+/// The application a rule applies to, if it names one
+fn application(conditions: &[Condition]) -> Option<String> {
+    let apps: Vec<&str> = conditions
+        .iter()
+        .filter(|c| c.application)
+        .map(|c| c.text.as_str())
+        .collect();
+    (!apps.is_empty()).then(|| apps.join(" "))
+}
+
+/// Short program name, `/usr/lib/firefox/firefox` reads as `firefox`
+fn program_name(path: &str) -> String {
+    let program = path.split_whitespace().next().unwrap_or(path);
+    match program.rsplit_once('/') {
+        Some((_, name)) if !name.is_empty() => name.to_string(),
+        _ => path.to_string(),
+    }
+}
+
+/// Simple regexps read as globs, anything fancier is kept as a pattern
+fn pattern_prose(pattern: &str) -> String {
+    let inner = pattern.strip_prefix('^').unwrap_or(pattern);
+    let inner = inner.strip_suffix('$').unwrap_or(inner);
+    let glob = inner.replace(".*", "*").replace("\\.", ".");
+    if glob.contains(['(', ')', '[', ']', '|', '+', '?', '{', '}', '\\']) {
+        format!("/{}/", pattern)
+    } else {
+        glob
+    }
+}
+
+fn duration_prose(duration: &RuleDuration) -> String {
+    match duration {
+        RuleDuration::Once | RuleDuration::UntilRestart | RuleDuration::Always => duration.to_string(),
+        timed => format!("for {}", timed),
+    }
+}
@@ -1,4 +1,6 @@
 use super::operator::Operator;
+use super::Connection;
+use crate::utils::format::short_hash;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::fmt;
@@ -150,6 +152,10 @@ pub struct Rule {
     pub created: DateTime<Utc>,
     #[serde(default)]
     pub updated: Option<DateTime<Utc>>,
+    /// When the daemon deletes the rule, reported by newer daemons for
+    /// temporary rules
+    #[serde(default)]
+    pub expires: Option<DateTime<Utc>>,
 }
 
 fn default_true() -> bool {
@@ -169,9 +175,38 @@ impl Rule {
             operator,
             created: Utc::now(),
             updated: None,
+            expires: None,
         }
     }
 
+    /// Rule a prompt answers with unless told otherwise: the executable
+    /// and, when known, the destination host
+    pub fn for_connection(conn: &Connection, action: RuleAction, duration: RuleDuration) -> Self {
+        let process = Operator::simple("process.path", &conn.process_path);
+        let operator = if conn.dst_host.is_empty() {
+            process
+        } else {
+            Operator::list(vec![process, Operator::simple("dest.host", &conn.dst_host)])
+        };
+        Self::new(&conn.rule_name(), action, duration, operator)
+    }
+
+    /// Temporary rule cutting a process off the network, ahead of any
+    /// rule that allows it. Named after a hash of the full path too, so
+    /// sandboxing one binary leaves another of the same name alone
+    pub fn sandbox(process_path: &str, duration: RuleDuration) -> Self {
+        let name = process_path.rsplit('/').next().unwrap_or(process_path);
+        let description = format!("Network cut off for {}", duration);
+        let mut rule = Self::new(
+            &format!("sandbox-{}-{}", name, short_hash(process_path)),
+            RuleAction::Deny,
+            duration,
+            Operator::simple("process.path", process_path),
+        );
+        rule.precedence = true;
+        rule.with_description(&description)
+    }
+
     pub fn with_description(mut self, description: &str) -> Self {
         self.description = description.to_string();
         self
@@ -187,6 +222,39 @@ impl Rule {
         self
     }
 
+    /// When the rule goes away: as reported by the daemon, or else its
+    /// duration counted from when it was last changed
+    pub fn expiry(&self) -> Option<DateTime<Utc>> {
+        self.expires.or_else(|| {
+            let secs = self.duration.as_seconds()?;
+            // Older daemons may leave the creation time out
+            let since = self.updated.unwrap_or(self.created);
+            (since.timestamp() > 0).then(|| since + chrono::Duration::seconds(secs as i64))
+        })
+    }
+
+    /// Whether the expiry is only worked out from the duration
+    pub fn expiry_estimated(&self) -> bool {
+        self.expires.is_none() && self.expiry().is_some()
+    }
+
+    /// Whether the rule's time is up by `now`
+    pub fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        self.expiry().is_some_and(|expires| expires <= now)
+    }
+
+    /// Time left before the rule expires, None for rules that don't
+    pub fn remaining(&self, now: DateTime<Utc>) -> Option<std::time::Duration> {
+        self.expiry()
+            .map(|expires| expires.signed_duration_since(now).to_std().unwrap_or_default())
+    }
+
+    /// Name, description and operator lowercased, what the rules filter looks in
+    pub fn search_text(&self) -> String {
+        // Lines apart, a filter never matches across two fields
+        format!("{}\n{}\n{}\n{}", self.name, self.description, self.operator.operand, self.operator.data).to_lowercase()
+    }
+
     /// Generate a slug-based filename for this rule
     pub fn filename(&self) -> String {
         let slug: String = self
@@ -217,6 +285,7 @@ impl Default for Rule {
             operator: Operator::default(),
             created: Utc::now(),
             updated: None,
+            expires: None,
         }
     }
 }
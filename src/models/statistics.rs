@@ -1,5 +1,6 @@
+use chrono::{DateTime, Duration, Utc};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 
 use super::Event;
 
@@ -57,6 +58,112 @@ impl Statistics {
     }
 }
 
+/// File format of a statistics export
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatsFormat {
+    Json,
+    Csv,
+}
+
+impl StatsFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Json => "json",
+            Self::Csv => "csv",
+        }
+    }
+}
+
+impl Statistics {
+    /// Summary counters, in export order
+    fn counters(&self) -> [(&'static str, u64); 9] {
+        [
+            ("uptime", self.uptime),
+            ("connections", self.connections),
+            ("accepted", self.accepted),
+            ("dropped", self.dropped),
+            ("ignored", self.ignored),
+            ("dns_responses", self.dns_responses),
+            ("rules", self.rules),
+            ("rule_hits", self.rule_hits),
+            ("rule_misses", self.rule_misses),
+        ]
+    }
+
+    /// Breakdowns, each sorted by count
+    fn breakdowns(&self) -> [(&'static str, Vec<(&String, &u64)>); 6] {
+        let all = |map| Self::top_n(map, usize::MAX);
+        [
+            ("by_protocol", all(&self.by_proto)),
+            ("by_host", all(&self.by_host)),
+            ("by_address", all(&self.by_address)),
+            ("by_port", all(&self.by_port)),
+            ("by_user", all(&self.by_uid)),
+            ("by_executable", all(&self.by_executable)),
+        ]
+    }
+
+    /// Counters and breakdowns of a node for spreadsheets or dashboards
+    pub fn export(&self, node_addr: &str, node_name: &str, generated: DateTime<Utc>, format: StatsFormat) -> String {
+        match format {
+            StatsFormat::Json => {
+                let summary: serde_json::Map<String, serde_json::Value> = self
+                    .counters()
+                    .into_iter()
+                    .map(|(name, value)| (name.to_string(), value.into()))
+                    .collect();
+                let mut export = serde_json::json!({
+                    "node": node_addr,
+                    "name": node_name,
+                    "generated": generated.to_rfc3339(),
+                    "daemon_version": self.daemon_version,
+                    "summary": summary,
+                });
+                for (name, entries) in self.breakdowns() {
+                    export[name] = entries
+                        .into_iter()
+                        .map(|(value, count)| serde_json::json!({ "value": value, "count": count }))
+                        .collect();
+                }
+                serde_json::to_string_pretty(&export).unwrap_or_default()
+            }
+            StatsFormat::Csv => {
+                let generated = generated.to_rfc3339();
+                let mut out = String::from("node,name,generated,section,key,count\n");
+                let mut row = |section: &str, key: &str, count: u64| {
+                    out.push_str(&format!(
+                        "{},{},{},{},{},{}\n",
+                        csv_field(node_addr),
+                        csv_field(node_name),
+                        generated,
+                        section,
+                        csv_field(key),
+                        count
+                    ));
+                };
+                for (name, value) in self.counters() {
+                    row("summary", name, value);
+                }
+                for (name, entries) in self.breakdowns() {
+                    for (key, count) in entries {
+                        row(name, key, *count);
+                    }
+                }
+                out
+            }
+        }
+    }
+}
+
+/// Quote a CSV field when it holds a separator, quote or line break
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
 /// Aggregated statistics for display
 #[derive(Debug, Clone, Default)]
 pub struct AggregatedStats {
@@ -95,3 +202,59 @@ impl AggregatedStats {
         }
     }
 }
+
+/// How far back traffic rates are kept
+pub const RATE_WINDOW: Duration = Duration::minutes(10);
+
+/// Connections and drops per second between two statistics updates
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateSample {
+    pub time: DateTime<Utc>,
+    pub connections: f64,
+    pub dropped: f64,
+}
+
+/// Running counters of the last update and the rates since
+#[derive(Debug, Default)]
+struct NodeRates {
+    last: Option<(DateTime<Utc>, u64, u64)>,
+    samples: VecDeque<RateSample>,
+}
+
+/// Recent traffic rates per node, worked out from the daemon's counters
+#[derive(Debug, Default)]
+pub struct TrafficHistory {
+    nodes: HashMap<String, NodeRates>,
+}
+
+impl TrafficHistory {
+    /// Take in a statistics update of a node
+    ///
+    /// Counters going back mean the daemon restarted, the update then only
+    /// starts counting again.
+    pub fn record(&mut self, node: &str, stats: &Statistics, now: DateTime<Utc>) {
+        let rates = self.nodes.entry(node.to_string()).or_default();
+        if let Some((time, connections, dropped)) = rates.last {
+            let secs = (now - time).num_milliseconds() as f64 / 1000.0;
+            if secs > 0.0 && stats.connections >= connections && stats.dropped >= dropped {
+                rates.samples.push_back(RateSample {
+                    time: now,
+                    connections: (stats.connections - connections) as f64 / secs,
+                    dropped: (stats.dropped - dropped) as f64 / secs,
+                });
+            }
+        }
+        rates.last = Some((now, stats.connections, stats.dropped));
+        while rates.samples.front().is_some_and(|s| now - s.time > RATE_WINDOW) {
+            rates.samples.pop_front();
+        }
+    }
+
+    /// Rates of a node within the window, oldest first
+    pub fn samples(&self, node: &str) -> Vec<RateSample> {
+        self.nodes
+            .get(node)
+            .map(|rates| rates.samples.iter().copied().collect())
+            .unwrap_or_default()
+    }
+}
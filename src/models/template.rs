@@ -0,0 +1,128 @@
+//! Placeholders in operator data
+//!
+//! Shared rules can name paths relative to the user or the program they are
+//! for, e.g. `{home}/.local/bin/syncthing`. Placeholders are replaced when
+//! the rule is saved, so the daemon only ever gets the expanded data. Other
+//! braces, like regexp repetitions, are left as they are.
+
+use super::{Operator, OperatorType, Rule};
+use crate::utils::host::is_local_node;
+
+/// Supported placeholders and what they stand for
+pub const PLACEHOLDERS: &[(&str, &str)] = &[
+    ("{home}", "home directory of the user"),
+    ("{user}", "name of the user"),
+    ("{uid}", "id of the user"),
+    ("{exe_dir}", "directory of the rule's process.path"),
+];
+
+/// Values of the placeholders for one rule, None when unknown
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct TemplateVars {
+    pub uid: Option<u32>,
+    pub user: Option<String>,
+    pub home: Option<String>,
+    pub exe_dir: Option<String>,
+}
+
+impl TemplateVars {
+    /// Values for `rule`: the user it is restricted to, else `default_uid`,
+    /// looked up in the `passwd` file content, and the directory of its
+    /// executable
+    pub fn for_rule(rule: &Rule, passwd: &str, default_uid: u32) -> Self {
+        let data = |operand: &str| rule_data(rule, operand);
+
+        let entries: Vec<Vec<&str>> = passwd
+            .lines()
+            .map(|line| line.split(':').collect::<Vec<_>>())
+            .filter(|fields| fields.len() >= 6)
+            .collect();
+        let entry = match (data("user.id").and_then(|uid| uid.parse::<u32>().ok()), data("user.name")) {
+            (Some(uid), _) => entries.iter().find(|e| e[2].parse::<u32>() == Ok(uid)),
+            (None, Some(name)) => entries.iter().find(|e| e[0] == name),
+            (None, None) => entries.iter().find(|e| e[2].parse::<u32>() == Ok(default_uid)),
+        };
+
+        Self {
+            uid: entry.and_then(|e| e[2].parse().ok()),
+            user: entry.map(|e| e[0].to_string()),
+            home: entry.map(|e| e[5].to_string()).filter(|home| !home.is_empty()),
+            exe_dir: exe_dir(rule),
+        }
+    }
+
+    /// Values for `rule` on the node at `node_addr`, for `uid` unless the
+    /// rule names its user. User names and homes are only looked up for a
+    /// node on this host, where the user running the TUI is the default.
+    pub fn for_node(rule: &Rule, node_addr: &str, uid: Option<u32>) -> Self {
+        if is_local_node(node_addr) {
+            let passwd = std::fs::read_to_string("/etc/passwd").unwrap_or_default();
+            return Self::for_rule(rule, &passwd, uid.unwrap_or_else(|| unsafe { libc::getuid() }));
+        }
+        Self {
+            uid: rule_data(rule, "user.id").and_then(|uid| uid.parse().ok()).or(uid),
+            exe_dir: exe_dir(rule),
+            ..Default::default()
+        }
+    }
+
+    fn value(&self, placeholder: &str) -> Option<String> {
+        match placeholder {
+            "{home}" => self.home.clone(),
+            "{user}" => self.user.clone(),
+            "{uid}" => self.uid.map(|uid| uid.to_string()),
+            "{exe_dir}" => self.exe_dir.clone(),
+            _ => None,
+        }
+    }
+}
+
+/// Whether the data uses any supported placeholder
+pub fn has_placeholders(data: &str) -> bool {
+    PLACEHOLDERS.iter().any(|(placeholder, _)| data.contains(placeholder))
+}
+
+/// Data of the rule's first plain `operand` operator without placeholders
+fn rule_data<'a>(rule: &'a Rule, operand: &str) -> Option<&'a str> {
+    operators(&rule.operator)
+        .into_iter()
+        .find(|op| op.operand == operand && op.op_type == OperatorType::Simple && !has_placeholders(&op.data))
+        .map(|op| op.data.as_str())
+}
+
+fn exe_dir(rule: &Rule) -> Option<String> {
+    rule_data(rule, "process.path")
+        .and_then(|path| path.rsplit_once('/'))
+        .map(|(dir, _)| if dir.is_empty() { "/" } else { dir }.to_string())
+}
+
+/// The operator and those of its list, depth first
+fn operators(operator: &Operator) -> Vec<&Operator> {
+    let mut all = vec![operator];
+    for op in &operator.list {
+        all.extend(operators(op));
+    }
+    all
+}
+
+fn expand_operator(operator: &mut Operator, vars: &TemplateVars) -> Result<(), String> {
+    for (placeholder, _) in PLACEHOLDERS {
+        if !operator.data.contains(placeholder) {
+            continue;
+        }
+        let value = vars
+            .value(placeholder)
+            .ok_or_else(|| format!("{} in {} has no value", placeholder, operator.operand))?;
+        // Paths are matched literally inside a regexp
+        let value = if operator.op_type == OperatorType::Regexp { regex::escape(&value) } else { value };
+        operator.data = operator.data.replace(placeholder, &value);
+    }
+    operator.list.iter_mut().try_for_each(|op| expand_operator(op, vars))
+}
+
+impl Rule {
+    /// Replace the placeholders of the operators' data, failing on one without a value
+    pub fn expand_placeholders(&mut self, vars: &TemplateVars) -> Result<(), String> {
+        expand_operator(&mut self.operator, vars)
+    }
+}
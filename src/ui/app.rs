@@ -1,41 +1,91 @@
 //! Main TUI application
 
+use std::collections::{HashMap, HashSet};
 use std::io::{self, Stdout};
+use std::path::Path;
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use anyhow::Result;
+use chrono::{DateTime, Utc};
 use crossterm::{
-    event::{DisableMouseCapture, EnableMouseCapture},
+    event::{DisableFocusChange, DisableMouseCapture, EnableFocusChange, EnableMouseCapture},
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
 use ratatui::{
     backend::CrosstermBackend,
     layout::Constraint,
-    style::{Color, Style},
+    style::{Color, Modifier, Style},
     text::{Line, Span},
     widgets::{Block, Borders, Paragraph, Tabs},
     Frame, Terminal,
 };
 use tokio::sync::{broadcast, mpsc};
+use tokio::task::JoinHandle;
 
-use crate::app::events::{AppEvent, EventHandler, is_quit, tab_delta, tab_number};
-use crate::app::state::{AppMessage, AppState, UiUpdateSignal};
+use crate::app::answer::RemoteAnswer;
+use crate::app::blocklists::{self, blocklist_rule, BlocklistReport, RULE_NAME};
+use crate::app::desktop::DesktopNotifier;
+use crate::app::scheduler;
+use crate::app::snapshots;
+use crate::app::export::{export_rules_report, export_stats};
+use crate::app::events::{click_position, scroll_delta, AppEvent, EventHandler, is_quit, tab_delta, tab_number};
+use crate::app::state::{AppMessage, AppState, PendingPrompt, PromptBatch, UiUpdateSignal};
+use crate::app::xref::ConnectionRef;
+use crate::app::updates::CURRENT_VERSION;
+use crate::config::{DataDirs, InterceptionMode, PromptPolicy, SessionState, Settings};
+use crate::db::rules_io::{read_rules_dir, write_rules_dir};
+use crate::db::search::{ConnectionQuery, SearchPage, SEARCH_PAGE};
+use crate::models::node::{ask_deadline, DEFAULT_ASK_TIMEOUT};
+use crate::models::precedence::Replay;
+use crate::models::template::TemplateVars;
+use crate::models::{AlertPriority, Connection, Policies, PromptOutcome, Rule, RuleAction, RuleDuration, Statistics};
+use crate::ui::dialogs::alert::AlertDialog;
+use crate::ui::dialogs::automations::{AutomationsDialog, AutomationsResult};
+use crate::ui::dialogs::changelog::ChangelogDialog;
+use crate::ui::dialogs::confirm::ConfirmDialog;
+use crate::ui::dialogs::lock::IdleLock;
+use crate::ui::dialogs::notice::NoticeDialog;
+use crate::ui::dialogs::hooks::HookLogDialog;
+use crate::ui::dialogs::sent_changes::SentChangesDialog;
+use crate::ui::dialogs::preferences::{PreferencesDialog, PreferencesResult};
+use crate::ui::dialogs::prompt_settings::{PromptSettingsDialog, PromptSettingsResult};
+use crate::ui::dialogs::missed::{MissedPromptsDialog, MissedPromptsResult};
 use crate::ui::dialogs::prompt::PromptDialog;
+use crate::ui::dialogs::suggestion::{SuggestionDialog, SuggestionResult};
+use crate::ui::dialogs::theme::{ThemeDialog, ThemeDialogResult};
 use crate::ui::layout::AppLayout;
+use crate::grpc::notifications::NotificationAction;
 use crate::ui::tabs::{
     alerts::AlertsTab,
     connections::ConnectionsTab,
-    firewall::FirewallTab,
+    daemon_log::DaemonLogTab,
+    dns::DnsTab,
+    firewall::{save_firewall_config, FirewallTab},
     nodes::NodesTab,
     rules::RulesTab,
     statistics::StatisticsTab,
+    Jump, Tab, TabCommand,
 };
+use crate::ui::terminal::Capabilities;
 use crate::ui::theme::Theme;
+use crate::ui::widgets::bignum::big_lines;
+use crate::ui::widgets::searchbar::SearchBar;
+use crate::utils::host::is_local_node;
+use crate::utils::{text, Formats, Host};
+
+/// Tabs a kiosk cycles through
+const KIOSK_TABS: [TabId; 3] = [TabId::Connections, TabId::Statistics, TabId::Alerts];
+
+/// How long a kiosk shows each tab
+const KIOSK_CYCLE: Duration = Duration::from_secs(20);
+
+/// Tabs out of sight are refreshed at most this often while their data changes
+const BACKGROUND_REFRESH: Duration = Duration::from_secs(1);
 
 /// Tab identifiers
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum TabId {
     Connections = 0,
     Rules = 1,
@@ -43,6 +93,8 @@ pub enum TabId {
     Statistics = 3,
     Alerts = 4,
     Nodes = 5,
+    Dns = 6,
+    DaemonLog = 7,
 }
 
 impl TabId {
@@ -54,6 +106,8 @@ impl TabId {
             Self::Statistics => "Statistics",
             Self::Alerts => "Alerts",
             Self::Nodes => "Nodes",
+            Self::Dns => "DNS",
+            Self::DaemonLog => "Daemon Log",
         }
     }
 
@@ -65,10 +119,112 @@ impl TabId {
             Self::Statistics,
             Self::Alerts,
             Self::Nodes,
+            Self::Dns,
+            Self::DaemonLog,
         ]
     }
 }
 
+impl TabId {
+    /// Tabs showing data the signal says changed
+    pub fn affected_by(signal: &UiUpdateSignal) -> &'static [TabId] {
+        match signal {
+            // Another node, or one coming or going, changes what every tab shows
+            UiUpdateSignal::NodeChanged => Self::all(),
+            UiUpdateSignal::StatsUpdated => &[Self::Statistics, Self::Nodes],
+            UiUpdateSignal::ConnectionsUpdated => &[Self::Connections, Self::Statistics, Self::Dns],
+            UiUpdateSignal::RulesUpdated | UiUpdateSignal::ChangeRejected => &[Self::Rules, Self::Nodes],
+            UiUpdateSignal::FirewallUpdated => &[Self::Firewall],
+            UiUpdateSignal::AlertsUpdated => &[Self::Alerts],
+            UiUpdateSignal::PromptReceived
+            | UiUpdateSignal::RemoteAnswer
+            | UiUpdateSignal::SuggestionReceived
+            | UiUpdateSignal::NetworkChanged
+            | UiUpdateSignal::Redraw => &[],
+        }
+    }
+}
+
+/// Tabs whose data changed since their cache was last refreshed
+///
+/// The visible tab is refreshed before every draw. The others catch up in
+/// the background as their data changes, each at most once per
+/// [`BACKGROUND_REFRESH`] so a stream of events doesn't refresh every tab
+/// for each one.
+#[derive(Debug, Default)]
+pub struct StaleTabs {
+    changed: HashSet<TabId>,
+    refreshed: HashMap<TabId, Instant>,
+}
+
+impl StaleTabs {
+    pub fn mark(&mut self, signal: &UiUpdateSignal) {
+        self.changed.extend(TabId::affected_by(signal));
+    }
+
+    pub fn refreshed(&mut self, tab: TabId, now: Instant) {
+        self.changed.remove(&tab);
+        self.refreshed.insert(tab, now);
+    }
+
+    /// Tabs other than the visible one due a refresh, in tab order
+    pub fn due(&self, visible: TabId, now: Instant) -> Vec<TabId> {
+        TabId::all()
+            .iter()
+            .copied()
+            .filter(|tab| *tab != visible && self.changed.contains(tab))
+            .filter(|tab| {
+                self.refreshed
+                    .get(tab)
+                    .is_none_or(|last| now.duration_since(*last) >= BACKGROUND_REFRESH)
+            })
+            .collect()
+    }
+}
+
+/// Wall display ignoring every key but quit, cycling through [`KIOSK_TABS`]
+#[derive(Debug, Clone, Copy)]
+pub struct Kiosk {
+    /// Position of the tab on screen among the kiosk tabs
+    current: usize,
+    since: Instant,
+}
+
+impl Kiosk {
+    pub fn new(now: Instant) -> Self {
+        Self { current: 0, since: now }
+    }
+
+    pub fn tab(&self) -> TabId {
+        KIOSK_TABS[self.current]
+    }
+
+    /// Whether a key ends the kiosk, every other one is swallowed
+    pub fn quits(&self, key: &crossterm::event::KeyEvent) -> bool {
+        is_quit(key)
+    }
+
+    /// Move on to the next tab once the current one had its time
+    pub fn cycle(&mut self, now: Instant) -> Option<TabId> {
+        if now.duration_since(self.since) < KIOSK_CYCLE {
+            return None;
+        }
+        self.current = (self.current + 1) % KIOSK_TABS.len();
+        self.since = now;
+        Some(self.tab())
+    }
+}
+
+/// Daemon configuration change waiting for confirmation
+struct PendingConfigChange {
+    node_addr: String,
+    config: String,
+    /// Lifting a panic left by an earlier run, which restores the
+    /// configuration from before it instead
+    lift_panic: bool,
+    dialog: ConfirmDialog,
+}
+
 /// Main TUI application
 pub struct TuiApp {
     state: Arc<AppState>,
@@ -83,6 +239,50 @@ pub struct TuiApp {
     show_help: bool,
     show_prompt: bool,
     prompt_dialog: Option<PromptDialog>,
+    /// Answer given ahead for prompts identical to one answered
+    prompt_batch: Option<PromptBatch>,
+    alert_popup: Option<AlertDialog>,
+    popup_high_alerts: bool,
+    /// Id and timestamp of the last alert shown in a popup
+    last_popup_alert: Option<(u64, DateTime<Utc>)>,
+    /// Prompts and alerts told to the desktop, None when disabled
+    desktop: Option<DesktopNotifier>,
+    /// Node and connection of the last prompt told to the desktop
+    notified_prompt: Option<(String, Connection)>,
+    suggestion: Option<SuggestionDialog>,
+    missed_prompts: Option<MissedPromptsDialog>,
+    hook_log: Option<HookLogDialog>,
+    automations: Option<AutomationsDialog>,
+    sent_changes: Option<SentChangesDialog>,
+    preferences: Option<PreferencesDialog>,
+    prompt_settings: Option<PromptSettingsDialog>,
+    changelog: Option<ChangelogDialog>,
+    config_change: Option<PendingConfigChange>,
+    notice: Option<NoticeDialog>,
+    theme_dialog: Option<ThemeDialog>,
+    /// Settings name of the theme in use
+    theme_name: String,
+    idle_lock: IdleLock,
+    /// Mirroring another instance or monitoring without root, nothing may be sent or changed
+    read_only: bool,
+    /// Wall display ignoring every key but quit, with when it last switched tabs
+    kiosk: Option<Kiosk>,
+    /// Read-only with a daemon of its own rather than mirroring another instance
+    monitor_only: bool,
+    /// Capture replayed instead of live daemons
+    replay: Option<String>,
+    /// Tabs out of sight with data to catch up on
+    stale_tabs: StaleTabs,
+    /// Blocklists being downloaded for the Rules tab
+    blocklist_download: Option<JoinHandle<BlocklistReport>>,
+    /// Purge and compaction of the database, run from the Statistics tab
+    purge: Option<JoinHandle<Result<String, String>>>,
+    /// Search of the stored connections for the Connections tab
+    search: Option<JoinHandle<Result<SearchPage, String>>>,
+    /// Columns each tab title spans in the tab bar, for clicks
+    tab_columns: Vec<std::ops::Range<u16>>,
+    /// What may be managed on the host the TUI runs on
+    host: Host,
 
     // Tabs
     connections_tab: ConnectionsTab,
@@ -90,21 +290,41 @@ pub struct TuiApp {
     firewall_tab: FirewallTab,
     statistics_tab: StatisticsTab,
     alerts_tab: AlertsTab,
+    dns_tab: DnsTab,
+    daemon_log_tab: DaemonLogTab,
     nodes_tab: NodesTab,
+
+    session: SessionState,
+    data_dirs: DataDirs,
+    /// Settings as loaded, shown on request
+    settings: Settings,
+    /// Settings file changes made from the UI are saved to, None for the default
+    config_path: Option<String>,
+    formats: Formats,
+    capabilities: Capabilities,
 }
 
 impl TuiApp {
-    pub fn new(state: Arc<AppState>, state_tx: mpsc::Sender<AppMessage>) -> Result<Self> {
+    pub fn new(
+        state: Arc<AppState>,
+        state_tx: mpsc::Sender<AppMessage>,
+        settings: &Settings,
+    ) -> Result<Self> {
         // Setup terminal
         enable_raw_mode()?;
         let mut stdout = io::stdout();
-        execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
+        execute!(stdout, EnterAlternateScreen, EnableMouseCapture, EnableFocusChange)?;
         let backend = CrosstermBackend::new(stdout);
         let terminal = Terminal::new(backend)?;
 
         let ui_update_rx = state.ui_update_tx.subscribe();
 
-        Ok(Self {
+        // A theme picked in the UI outlives the configured one
+        let session = SessionState::load(&settings.data_dirs().session());
+        let theme_name = session.theme.clone().unwrap_or_else(|| settings.theme.clone());
+        let formats = settings.formats();
+
+        let mut app = Self {
             state,
             state_tx,
             terminal,
@@ -112,56 +332,473 @@ impl TuiApp {
             ui_update_rx,
 
             current_tab: 0,
-            theme: Theme::default(),
+            theme: Theme::named(&theme_name).unwrap_or_default(),
             show_help: false,
             show_prompt: false,
             prompt_dialog: None,
+            prompt_batch: None,
+            alert_popup: None,
+            popup_high_alerts: settings.popup_high_alerts,
+            last_popup_alert: None,
+            desktop: DesktopNotifier::from_settings(settings),
+            notified_prompt: None,
+            suggestion: None,
+            missed_prompts: None,
+            hook_log: None,
+            automations: None,
+            sent_changes: None,
+            preferences: None,
+            prompt_settings: None,
+            changelog: None,
+            config_change: None,
+            notice: None,
+            theme_dialog: None,
+            theme_name,
+            idle_lock: IdleLock::new(settings.idle_lock_minutes, &settings.idle_lock_passphrase),
+            read_only: false,
+            kiosk: None,
+            monitor_only: false,
+            replay: None,
+            stale_tabs: StaleTabs::default(),
+            blocklist_download: None,
+            purge: None,
+            search: None,
+            tab_columns: Vec::new(),
+            host: Host::local(),
+
+            connections_tab: ConnectionsTab::new()
+                .with_proxies(settings.proxies.clone())
+                .with_sandbox_duration(settings.sandbox_duration.clone())
+                .with_formats(formats.clone()),
+            rules_tab: RulesTab::new()
+                .with_formats(formats.clone())
+                .with_policies(Policies::load(&settings.data_dirs().policies()))
+                .with_sandbox_duration(settings.sandbox_duration.clone()),
+            firewall_tab: FirewallTab::new().with_formats(formats.clone()),
+            statistics_tab: StatisticsTab::new(settings),
+            alerts_tab: AlertsTab::new().with_formats(formats.clone()),
+            dns_tab: DnsTab::new().with_formats(formats.clone()),
+            daemon_log_tab: DaemonLogTab::new().with_formats(formats.clone()),
+            nodes_tab: NodesTab::new().with_formats(formats.clone()),
+
+            session,
+            data_dirs: settings.data_dirs(),
+            settings: settings.clone(),
+            config_path: None,
+            formats,
+            capabilities: Capabilities::detect(settings.terminal_mode),
+        };
+        app.restore_search_history();
+        Ok(app)
+    }
+
+    /// Show a notice until the user dismisses it
+    pub fn show_notice(&mut self, title: &str, message: &str) {
+        self.notice = Some(NoticeDialog::new(title, message));
+    }
+
+    /// Only browse the state, as a mirror of another instance
+    pub fn set_read_only(&mut self) {
+        self.read_only = true;
+    }
+
+    /// Watch a daemon without root: read-only, as nothing may be changed
+    pub fn set_monitor_only(&mut self) {
+        self.read_only = true;
+        self.monitor_only = true;
+    }
+
+    /// Show a replayed capture, named in the status bar so it isn't taken for live traffic
+    pub fn set_replay(&mut self, path: &str) {
+        self.replay = Some(path.to_string());
+    }
+
+    /// Run as a wall display: read-only, no input but quit, cycling through
+    /// the tabs worth watching with large counters on top
+    pub fn set_kiosk(&mut self) {
+        self.read_only = true;
+        let kiosk = Kiosk::new(Instant::now());
+        self.current_tab = kiosk.tab() as usize;
+        self.kiosk = Some(kiosk);
+    }
 
-            connections_tab: ConnectionsTab::new(),
-            rules_tab: RulesTab::new(),
-            firewall_tab: FirewallTab::new(),
-            statistics_tab: StatisticsTab::new(),
-            alerts_tab: AlertsTab::new(),
-            nodes_tab: NodesTab::new(),
-        })
+    /// Whether a dialog of the app, rather than of a tab, takes the input
+    fn app_dialog_open(&self) -> bool {
+        self.show_prompt
+            || self.show_help
+            || self.alert_popup.is_some()
+            || self.notice.is_some()
+            || self.hook_log.is_some()
+            || self.automations.is_some()
+            || self.sent_changes.is_some()
+            || self.preferences.is_some()
+            || self.prompt_settings.is_some()
+            || self.changelog.is_some()
+            || self.suggestion.is_some()
+            || self.missed_prompts.is_some()
+            || self.config_change.is_some()
+            || self.theme_dialog.is_some()
+    }
+
+    /// Handle a mouse event, returns the key a click on a dialog button stands for
+    async fn handle_mouse(&mut self, mouse: crossterm::event::MouseEvent) -> Option<crossterm::event::KeyEvent> {
+        let click = click_position(&mouse);
+        if self.app_dialog_open() {
+            if let Some((column, row)) = click {
+                if self.show_prompt {
+                    return self.prompt_dialog.as_ref()?.click(column, row);
+                }
+                return self.config_change.as_ref()?.dialog.click(column, row);
+            }
+            // The wheel moves through the dialog's lists like the arrow keys
+            let code = match scroll_delta(&mouse)? {
+                delta if delta < 0 => crossterm::event::KeyCode::Up,
+                _ => crossterm::event::KeyCode::Down,
+            };
+            return Some(crossterm::event::KeyEvent::new(code, crossterm::event::KeyModifiers::NONE));
+        }
+
+        if let Some((column, 0)) = click.filter(|_| !self.active_tab().showing_dialog()) {
+            if let Some(tab) = self.tab_columns.iter().position(|columns| columns.contains(&column)) {
+                self.current_tab = tab;
+            }
+            return None;
+        }
+        let commands = self.active_tab_mut().handle_mouse(mouse);
+        self.run_commands(commands).await;
+        None
+    }
+
+    /// Move on to the next kiosk tab once the current one had its time
+    fn cycle_kiosk(&mut self) {
+        if let Some(tab) = self.kiosk.as_mut().and_then(|kiosk| kiosk.cycle(Instant::now())) {
+            self.current_tab = tab as usize;
+        }
+    }
+
+    /// Mask hostnames, addresses and paths on screen, or show them again.
+    /// Only the display changes, memory and database keep everything.
+    fn toggle_privacy(&mut self) {
+        self.formats = self.formats.clone().with_private(!self.formats.is_private());
+        self.connections_tab.set_formats(self.formats.clone());
+        self.rules_tab.set_formats(self.formats.clone());
+        self.statistics_tab.set_formats(self.formats.clone());
+        self.dns_tab.set_formats(self.formats.clone());
+        self.daemon_log_tab.set_formats(self.formats.clone());
+        self.alerts_tab.set_formats(self.formats.clone());
+        self.nodes_tab.set_formats(self.formats.clone());
+        self.firewall_tab.set_formats(self.formats.clone());
+        self.alert_popup = self.alert_popup.take().map(|d| d.with_formats(self.formats.clone()));
+        self.prompt_dialog = self.prompt_dialog.take().map(|d| d.with_formats(self.formats.clone()));
+        self.suggestion = self.suggestion.take().map(|d| d.with_formats(self.formats.clone()));
+        self.missed_prompts = self.missed_prompts.take().map(|d| d.with_formats(self.formats.clone()));
+        self.hook_log = self.hook_log.take().map(|d| d.with_formats(self.formats.clone()));
+        self.automations = self.automations.take().map(|d| d.with_formats(self.formats.clone()));
+        self.sent_changes = self.sent_changes.take().map(|d| d.with_formats(self.formats.clone()));
+        self.preferences = self.preferences.take().map(|d| d.with_formats(self.formats.clone()));
+        self.changelog = self.changelog.take().map(|d| d.with_formats(self.formats.clone()));
+    }
+
+    /// Save changes made from the UI to this settings file
+    pub fn set_config_path(&mut self, path: Option<String>) {
+        self.config_path = path;
+    }
+
+    /// Switch to the next interception mode and remember it for the next start
+    fn cycle_interception(&mut self) {
+        let mode = self.state.interception().next();
+        self.state.set_interception(mode);
+        self.settings.interception_mode = mode;
+
+        // Only this setting, command line overrides stay out of the file
+        let path = self.config_path.as_deref();
+        let saved = Settings::load(path).and_then(|mut saved| {
+            saved.interception_mode = mode;
+            saved.save(path)
+        });
+        if let Err(e) = saved {
+            tracing::warn!("Failed to save the interception mode: {}", e);
+        }
+    }
+
+    /// Apply new prompt defaults and remember them for the next start
+    async fn save_prompt_defaults(&mut self, action: RuleAction, duration: RuleDuration, timeout: Duration) {
+        let policy = PromptPolicy { profile: None, default_action: action, default_duration: duration.clone() };
+        self.state.set_prompt_defaults(policy, timeout).await;
+        self.settings.default_action = action;
+        self.settings.default_duration = duration.clone();
+        self.settings.prompt_timeout = timeout.as_secs();
+
+        // Only these settings, command line overrides stay out of the file
+        let path = self.config_path.as_deref();
+        let saved = Settings::load(path).and_then(|mut saved| {
+            saved.default_action = action;
+            saved.default_duration = duration;
+            saved.prompt_timeout = timeout.as_secs();
+            saved.save(path)
+        });
+        if let Err(e) = saved {
+            tracing::warn!("Failed to save the prompt defaults: {}", e);
+            self.show_notice("Prompt defaults", &format!("Applied until restart, saving failed: {}", e));
+        }
+    }
+
+    /// Leave the local host alone when running as a remote console
+    pub fn set_host(&mut self, host: Host) {
+        self.host = host;
+    }
+
+    /// Search bars whose history is kept in the session state
+    fn search_bars(&mut self) -> [(&'static str, &mut SearchBar); 6] {
+        [
+            ("connections", self.connections_tab.search_bar_mut()),
+            ("rules", self.rules_tab.search_bar_mut()),
+            ("statistics", self.statistics_tab.search_bar_mut()),
+            ("alerts", self.alerts_tab.search_bar_mut()),
+            ("dns", self.dns_tab.search_bar_mut()),
+            ("daemon_log", self.daemon_log_tab.search_bar_mut()),
+        ]
+    }
+
+    fn restore_search_history(&mut self) {
+        let history = self.session.search_history.clone();
+        for (tab, bar) in self.search_bars() {
+            bar.set_history(history.get(tab).cloned().unwrap_or_default());
+        }
+    }
+
+    fn save_session(&mut self) {
+        let history = self
+            .search_bars()
+            .into_iter()
+            .map(|(tab, bar)| (tab.to_string(), bar.history().to_vec()))
+            .collect();
+        self.session.search_history = history;
+        if let Err(e) = self.session.save(&self.data_dirs.session()) {
+            tracing::warn!("Failed to save session state: {}", e);
+        }
     }
 
     pub async fn run(&mut self) -> Result<()> {
+        if !self.read_only {
+            self.open_missed_prompts().await;
+        }
+
         loop {
             // Check for UI update signals
             while let Ok(signal) = self.ui_update_rx.try_recv() {
+                self.stale_tabs.mark(&signal);
                 match signal {
                     UiUpdateSignal::PromptReceived => {
-                        let mut prompts = self.state.pending_prompts.write().await;
-                        if let Some(pending) = prompts.pop_front() {
-                            self.prompt_dialog = Some(PromptDialog::new(
-                                pending.connection,
-                                pending.node_addr,
-                                pending.response_tx,
-                            ));
-                            self.show_prompt = true;
+                        self.notify_prompt().await;
+                        self.apply_prompt_batch().await;
+                        self.show_next_prompt().await;
+                    }
+                    UiUpdateSignal::RemoteAnswer => self.answer_remotely().await,
+                    UiUpdateSignal::AlertsUpdated => {
+                        self.notify_alert().await;
+                        if self.popup_high_alerts {
+                            self.check_alert_popup().await;
                         }
                     }
+                    UiUpdateSignal::SuggestionReceived => self.next_suggestion().await,
+                    UiUpdateSignal::ChangeRejected => self.show_rejected_changes().await,
+                    UiUpdateSignal::NodeChanged => self.offer_panic_lift().await,
                     _ => {}
                 }
             }
 
+            if self.blocklist_download.as_ref().is_some_and(|task| task.is_finished()) {
+                if let Some(task) = self.blocklist_download.take() {
+                    self.rules_tab.set_blocklists_result(task.await.map_err(|e| e.to_string()));
+                }
+            }
+            if self.purge.as_ref().is_some_and(|task| task.is_finished()) {
+                if let Some(task) = self.purge.take() {
+                    match task.await.map_err(|e| e.to_string()).and_then(|result| result) {
+                        Ok(message) => self.show_notice("Database purged", &message),
+                        Err(e) => self.show_notice("Purge failed", &e),
+                    }
+                    self.statistics_tab.reread_db_size();
+                }
+            }
+            if self.search.as_ref().is_some_and(|task| task.is_finished()) {
+                if let Some(task) = self.search.take() {
+                    let result = task.await.map_err(|e| e.to_string()).and_then(|result| result);
+                    self.connections_tab.set_search_results(result);
+                }
+            }
+
             // Update tab caches before drawing
             self.update_tab_caches().await;
+            if let Some(dialog) = &mut self.prompt_dialog {
+                dialog.queued = self.state.pending_prompts.read().await.len();
+            }
 
             // Draw UI
             self.draw()?;
 
             // Handle input events
             if let Some(event) = self.event_handler.next() {
+                // Clicks on buttons count as their key, the rest is handled here
+                let event = match event {
+                    AppEvent::Mouse(mouse) if self.kiosk.is_none() && !self.idle_lock.is_locked() => {
+                        self.idle_lock.touch();
+                        match self.handle_mouse(mouse).await {
+                            Some(key) => AppEvent::Key(key),
+                            None => continue,
+                        }
+                    }
+                    event => event,
+                };
                 match event {
+                    AppEvent::Mouse(_) => {}
+                    AppEvent::Key(key) if self.idle_lock.is_locked() => {
+                        self.idle_lock.handle_key(key);
+                    }
+                    AppEvent::Key(key) if self.kiosk.is_some() => {
+                        if self.kiosk.is_some_and(|kiosk| kiosk.quits(&key)) {
+                            break;
+                        }
+                    }
                     AppEvent::Key(key) => {
+                        self.idle_lock.touch();
+
+                        // Works over any dialog, a prompt may pop up mid screen share
+                        if key.code == crossterm::event::KeyCode::Char('p')
+                            && key.modifiers.contains(crossterm::event::KeyModifiers::CONTROL)
+                        {
+                            self.toggle_privacy();
+                            continue;
+                        }
+                        if key.code == crossterm::event::KeyCode::Char('k')
+                            && key.modifiers.contains(crossterm::event::KeyModifiers::CONTROL)
+                            && !self.read_only
+                        {
+                            self.toggle_panic().await;
+                            continue;
+                        }
+
                         if self.show_prompt {
                             if let Some(dialog) = &mut self.prompt_dialog {
                                 if dialog.handle_key(key) {
-                                    self.show_prompt = false;
-                                    self.prompt_dialog = None;
+                                    if let Some(dialog) = self.prompt_dialog.take() {
+                                        self.close_prompt(dialog).await;
+                                    }
+                                }
+                            }
+                        } else if let Some(dialog) = &mut self.alert_popup {
+                            if dialog.handle_key(key) {
+                                if dialog.acknowledged && !self.read_only {
+                                    let alert = &dialog.alert;
+                                    self.state.acknowledge_alert(alert.id, alert.timestamp).await;
+                                }
+                                self.alert_popup = None;
+                            }
+                        } else if let Some(dialog) = &mut self.notice {
+                            if dialog.handle_key(key) {
+                                self.notice = None;
+                            }
+                        } else if let Some(dialog) = &mut self.hook_log {
+                            if dialog.handle_key(key) {
+                                self.hook_log = None;
+                            }
+                        } else if let Some(dialog) = &mut self.automations {
+                            match dialog.handle_key(key) {
+                                Some(AutomationsResult::Save(automations)) => {
+                                    self.automations = None;
+                                    if let Err(e) = automations.save(&self.data_dirs.automations()) {
+                                        self.show_notice("Automations", &format!("Failed to save automations: {}", e));
+                                    }
+                                    self.state.set_automations(automations);
+                                }
+                                Some(AutomationsResult::Close) => self.automations = None,
+                                None => {}
+                            }
+                        } else if let Some(dialog) = &mut self.sent_changes {
+                            if dialog.handle_key(key) {
+                                self.sent_changes = None;
+                            }
+                        } else if let Some(dialog) = &mut self.prompt_settings {
+                            if let Some(result) = dialog.handle_key(key) {
+                                self.prompt_settings = None;
+                                if let PromptSettingsResult::Save { default_action, default_duration, prompt_timeout } = result {
+                                    self.save_prompt_defaults(default_action, default_duration, prompt_timeout).await;
+                                }
+                            }
+                        } else if let Some(dialog) = &mut self.preferences {
+                            match dialog.handle_key(key) {
+                                Some(PreferencesResult::Close) => self.preferences = None,
+                                // A mirror can't change how its source answers
+                                Some(PreferencesResult::EditPromptDefaults) if !self.read_only => {
+                                    self.preferences = None;
+                                    self.prompt_settings = Some(PromptSettingsDialog::new(&self.settings));
                                 }
+                                _ => {}
+                            }
+                        } else if let Some(dialog) = &mut self.changelog {
+                            if dialog.handle_key(key) {
+                                self.changelog = None;
+                            }
+                        } else if let Some(dialog) = &mut self.suggestion {
+                            if let Some(result) = dialog.handle_key(key) {
+                                if let SuggestionResult::Create(action) = result {
+                                    let suggestion = &dialog.suggestion;
+                                    let rule = suggestion.rule(action);
+                                    let _ = self.state_tx.send(AppMessage::RuleAdded {
+                                        node_addr: suggestion.node_addr.clone(),
+                                        rule: rule.clone(),
+                                    }).await;
+                                    let _ = self.state_tx.send(AppMessage::SendNotification {
+                                        node_addr: suggestion.node_addr.clone(),
+                                        action: NotificationAction::ChangeRule(rule),
+                                    }).await;
+                                }
+                                self.suggestion = None;
+                                self.next_suggestion().await;
+                            }
+                        } else if let Some(dialog) = &mut self.missed_prompts {
+                            match dialog.handle_key(key) {
+                                Some(MissedPromptsResult::Create { id, node_addr, rule }) => {
+                                    let _ = self.state_tx.send(AppMessage::RuleAdded {
+                                        node_addr: node_addr.clone(),
+                                        rule: rule.clone(),
+                                    }).await;
+                                    let _ = self.state_tx.send(AppMessage::SendNotification {
+                                        node_addr,
+                                        action: NotificationAction::ChangeRule(rule),
+                                    }).await;
+                                    self.state.set_prompt_outcome(Some(id), PromptOutcome::Resolved).await;
+                                }
+                                Some(MissedPromptsResult::Dismiss { id }) => {
+                                    self.state.set_prompt_outcome(Some(id), PromptOutcome::Resolved).await;
+                                }
+                                Some(MissedPromptsResult::Close) => self.missed_prompts = None,
+                                None => {}
+                            }
+                            if self.missed_prompts.as_ref().is_some_and(|d| d.is_empty()) {
+                                self.missed_prompts = None;
+                            }
+                        } else if let Some(change) = &mut self.config_change {
+                            if change.dialog.handle_key(key) {
+                                let confirmed = change.dialog.result == Some(true);
+                                if let (true, Some(change)) = (confirmed, self.config_change.take()) {
+                                    self.apply_config_change(change).await;
+                                }
+                            }
+                        } else if let Some(dialog) = &mut self.theme_dialog {
+                            let result = dialog.handle_key(key);
+                            let name = match result {
+                                Some(ThemeDialogResult::Keep(name) | ThemeDialogResult::Revert(name)) => name,
+                                None => dialog.selected_name(),
+                            };
+                            self.theme = Theme::named(name).unwrap_or_default();
+                            if result.is_some() {
+                                self.theme_name = name.to_string();
+                                self.theme_dialog = None;
+                            }
+                            if let Some(ThemeDialogResult::Keep(name)) = result {
+                                self.session.pick_theme(name, &self.settings.theme);
                             }
                         } else if self.show_help {
                             self.show_help = false;
@@ -178,12 +815,7 @@ impl TuiApp {
                             }
 
                             // Check if current tab has a dialog open - if so, pass keys to it first
-                            let has_dialog = match TabId::all()[self.current_tab] {
-                                TabId::Connections => self.connections_tab.showing_dialog(),
-                                TabId::Rules => self.rules_tab.showing_dialog(),
-                                TabId::Firewall => self.firewall_tab.showing_dialog(),
-                                _ => false,
-                            };
+                            let has_dialog = self.active_tab().showing_dialog();
 
                             // Only handle tab switching if no dialog is open
                             if !has_dialog {
@@ -199,35 +831,772 @@ impl TuiApp {
                                     self.current_tab = ((self.current_tab as i32 + delta).rem_euclid(len)) as usize;
                                     continue;
                                 }
-                            }
 
-                            match TabId::all()[self.current_tab] {
-                                TabId::Connections => self.connections_tab.handle_key(key, &self.state, &self.state_tx).await,
-                                TabId::Rules => self.rules_tab.handle_key(key, &self.state, &self.state_tx).await,
-                                TabId::Firewall => self.firewall_tab.handle_key(key, &self.state, &self.state_tx).await,
-                                TabId::Statistics => self.statistics_tab.handle_key(key, &self.state).await,
-                                TabId::Alerts => self.alerts_tab.handle_key(key, &self.state).await,
-                                TabId::Nodes => self.nodes_tab.handle_key(key, &self.state, &self.state_tx).await,
+                                if key.code == crossterm::event::KeyCode::Char('t')
+                                    && key.modifiers.contains(crossterm::event::KeyModifiers::CONTROL)
+                                {
+                                    self.theme_dialog = Some(ThemeDialog::new(&self.theme_name));
+                                    continue;
+                                }
+                                // F5 reloads the Firewall tab
+                                if key.code == crossterm::event::KeyCode::Char('o')
+                                    && key.modifiers.contains(crossterm::event::KeyModifiers::CONTROL)
+                                    && !self.read_only
+                                {
+                                    self.state.set_maintenance(!self.state.in_maintenance());
+                                    continue;
+                                }
+
+                                let code = key.code;
+                                if !self.read_only
+                                    && matches!(code, crossterm::event::KeyCode::F(3) | crossterm::event::KeyCode::F(4))
+                                {
+                                    self.propose_config_toggle(code == crossterm::event::KeyCode::F(4)).await;
+                                    continue;
+                                }
+                                // F2 is taken by the Firewall tab
+                                if !self.read_only && code == crossterm::event::KeyCode::F(12) {
+                                    self.cycle_interception();
+                                    continue;
+                                }
+                                if !self.read_only && code == crossterm::event::KeyCode::F(6) {
+                                    self.open_missed_prompts().await;
+                                    continue;
+                                }
+                                if code == crossterm::event::KeyCode::F(7) {
+                                    let runs = self.state.hook_log.read().await.iter().cloned().collect();
+                                    self.hook_log = Some(HookLogDialog::new(runs).with_formats(self.formats.clone()));
+                                    continue;
+                                }
+                                if !self.read_only && code == crossterm::event::KeyCode::F(11) {
+                                    let runs = self.state.automation_log.read().await.iter().cloned().collect();
+                                    self.automations = Some(
+                                        AutomationsDialog::new(self.state.automations(), runs)
+                                            .with_formats(self.formats.clone()),
+                                    );
+                                    continue;
+                                }
+                                if code == crossterm::event::KeyCode::F(8) {
+                                    let schedule = self.state.schedule.read().await.clone();
+                                    self.preferences = Some(
+                                        PreferencesDialog::new(&self.settings, schedule).with_formats(self.formats.clone()),
+                                    );
+                                    continue;
+                                }
+                                if code == crossterm::event::KeyCode::F(9) {
+                                    let sent = self.state.sent_notifications.read().await.iter().cloned().collect();
+                                    self.sent_changes = Some(SentChangesDialog::new(sent).with_formats(self.formats.clone()));
+                                    continue;
+                                }
+                                if code == crossterm::event::KeyCode::F(10) {
+                                    self.open_changelog();
+                                    continue;
+                                }
                             }
+
+                            let commands = self.active_tab_mut().handle_key(key);
+                            self.run_commands(commands).await;
                         }
                     }
                     AppEvent::Resize(_, _) => {}
-                    AppEvent::Tick => {}
+                    AppEvent::Focus(focused) => {
+                        if let Some(desktop) = &mut self.desktop {
+                            desktop.set_focused(focused);
+                        }
+                    }
+                    AppEvent::Tick if self.kiosk.is_some() => self.cycle_kiosk(),
+                    AppEvent::Tick => {
+                        self.idle_lock.check();
+                        self.check_prompt_timeout().await;
+                    }
                 }
             }
         }
 
+        self.save_session();
         Ok(())
     }
 
-    async fn update_tab_caches(&mut self) {
+    /// Release notes of newer releases, or why none are known
+    fn open_changelog(&mut self) {
+        let update = self.state.update.lock().unwrap().clone();
+        match update {
+            Some(update) => self.changelog = Some(ChangelogDialog::new(update).with_formats(self.formats.clone())),
+            None if self.settings.update_check => {
+                self.show_notice("Updates", &format!("No release newer than {} is known.", CURRENT_VERSION))
+            }
+            None => self.show_notice(
+                "Updates",
+                "Checking for new releases is off. Set update_check in the config file to look for them daily.",
+            ),
+        }
+    }
+
+    /// Cut the active node off, or resume it if it already is
+    async fn toggle_panic(&mut self) {
+        let node_addr = match self.state.nodes.read().await.active_node().filter(|n| !n.archived) {
+            Some(node) => node.addr.clone(),
+            None => return,
+        };
+        let engage = !self.state.in_panic(&node_addr);
+        let _ = self.state_tx.send(AppMessage::Panic { node_addr, engage }).await;
+    }
+
+    /// Ask to flip DefaultAction (F3) or InterceptUnknown (F4) of the active node
+    async fn propose_config_toggle(&mut self, intercept: bool) {
+        let nodes = self.state.nodes.read().await;
+        let Some(node) = nodes.active_node().filter(|n| !n.archived) else {
+            return;
+        };
+        let (message, config) = if intercept {
+            let enable = !node.intercept_unknown().unwrap_or(false);
+            (
+                format!(
+                    "{} interception of unknown connections on {}?",
+                    if enable { "Enable" } else { "Disable" },
+                    node.display_name()
+                ),
+                node.config_with("InterceptUnknown", serde_json::Value::Bool(enable)),
+            )
+        } else {
+            let action = match node.default_action() {
+                Some(RuleAction::Allow) => RuleAction::Deny,
+                _ => RuleAction::Allow,
+            };
+            (
+                format!("Set the default action of {} to {}?", node.display_name(), action),
+                node.config_with("DefaultAction", serde_json::Value::String(action.to_string())),
+            )
+        };
+        let Some(config) = config else {
+            tracing::warn!("Daemon config of {} can't be parsed", node.addr);
+            return;
+        };
+        self.config_change = Some(PendingConfigChange {
+            node_addr: node.addr.clone(),
+            config,
+            lift_panic: false,
+            dialog: ConfirmDialog::new("Daemon Configuration", &message),
+        });
+    }
+
+    /// Ask to lift the panic an earlier run left on a node, once it is connected
+    async fn offer_panic_lift(&mut self) {
+        if self.read_only || self.config_change.is_some() {
+            return;
+        }
+        let Some((node_addr, name)) = self.state.take_restored_panic().await else {
+            return;
+        };
+        let message = format!("Panic mode is still on for {} since an earlier run. Lift it?", name);
+        self.config_change = Some(PendingConfigChange {
+            node_addr,
+            config: String::new(),
+            lift_panic: true,
+            dialog: ConfirmDialog::new("Panic Mode", &message),
+        });
+    }
+
+    /// Send a confirmed configuration change and show it right away
+    async fn apply_config_change(&mut self, change: PendingConfigChange) {
+        if change.lift_panic {
+            let _ = self.state_tx.send(AppMessage::Panic { node_addr: change.node_addr, engage: false }).await;
+            return;
+        }
+        if let Some(node) = self.state.nodes.write().await.get_node_mut(&change.node_addr) {
+            node.config = change.config.clone();
+        }
+        let _ = self.state_tx.send(AppMessage::SendNotification {
+            node_addr: change.node_addr,
+            action: NotificationAction::ChangeConfig(change.config),
+        }).await;
+        self.state.notify_ui(UiUpdateSignal::NodeChanged);
+    }
+
+    /// List the prompts that timed out or were lost, if there are any
+    async fn open_missed_prompts(&mut self) {
+        let missed = self.state.missed_prompts.read().await.clone();
+        if !missed.is_empty() {
+            self.missed_prompts = Some(MissedPromptsDialog::new(missed).with_formats(self.formats.clone()));
+        }
+    }
+
+    /// Answer the prompt on screen for the command line, if it asked for it
+    async fn answer_remotely(&mut self) {
+        let answers: Vec<_> = self.state.remote_answers.write().await.drain(..).collect();
+        for RemoteAnswer { request, done } in answers {
+            let matched = self.show_prompt
+                && self.prompt_dialog.as_ref().is_some_and(|d| request.matches(&d.connection));
+            if let Some(mut dialog) = self.prompt_dialog.take().filter(|_| matched) {
+                dialog.answer(request.action, request.duration);
+                self.state.set_prompt_outcome(dialog.id, PromptOutcome::Answered).await;
+                self.show_prompt = false;
+            }
+            let _ = done.send(matched);
+        }
+        self.show_next_prompt().await;
+    }
+
+    /// Close a prompt whose countdown ran out, leaving the answer to the daemon
+    async fn check_prompt_timeout(&mut self) {
+        if !self.show_prompt || self.prompt_dialog.as_ref().is_none_or(|d| d.remaining_secs() > 0) {
+            return;
+        }
+        if let Some(dialog) = self.prompt_dialog.take() {
+            self.state.set_prompt_outcome(dialog.id, PromptOutcome::TimedOut).await;
+        }
+        self.show_prompt = false;
+        self.show_next_prompt().await;
+    }
+
+    /// Show the next queued prompt, unless one is on screen
+    async fn show_next_prompt(&mut self) {
+        if self.kiosk.is_some() || self.read_only {
+            // Nobody answers a wall display or a read-only monitor, the daemon applies its default now
+            while let Some(pending) = self.state.next_prompt().await {
+                self.state.set_prompt_outcome(pending.id, PromptOutcome::TimedOut).await;
+            }
+            return;
+        }
+        if self.prompt_dialog.is_some() {
+            return;
+        }
+        let Some(pending) = self.state.next_prompt().await else {
+            return;
+        };
+        let nodes = self.state.nodes.read().await;
+        let rules = nodes.get_node(&pending.node_addr).map(|node| node.rules.clone()).unwrap_or_default();
+        drop(nodes);
+        let policy = self.state.prompt_policy.read().await.clone();
+        self.prompt_dialog = Some(
+            PromptDialog::new(pending.connection, pending.node_addr, pending.response_tx)
+                .with_rules(rules)
+                .with_defaults(policy.default_action, policy.default_duration)
+                .with_timeout(self.state.prompt_timeout())
+                .with_deadline(ask_deadline(DEFAULT_ASK_TIMEOUT))
+                .with_formats(self.formats.clone())
+                .with_position(self.settings.prompt_position)
+                .with_id(pending.id),
+        );
+        self.show_prompt = true;
+    }
+
+    /// A prompt closed with a key: answered, maybe with a batch, or skipped
+    async fn close_prompt(&mut self, dialog: PromptDialog) {
+        self.show_prompt = false;
+        match dialog.answered().cloned() {
+            Some(rule) => {
+                self.state.set_prompt_outcome(dialog.id, PromptOutcome::Answered).await;
+                if dialog.batch > 0 {
+                    self.prompt_batch = Some(PromptBatch {
+                        node_addr: dialog.node_addr,
+                        connection: dialog.connection,
+                        rule,
+                        remaining: dialog.batch,
+                    });
+                    self.apply_prompt_batch().await;
+                }
+            }
+            // Skipped, back behind the others
+            None => {
+                if let Some(response_tx) = dialog.response_tx {
+                    self.state.pending_prompts.write().await.push_back(PendingPrompt {
+                        connection: dialog.connection,
+                        node_addr: dialog.node_addr,
+                        id: dialog.id,
+                        response_tx,
+                    });
+                }
+            }
+        }
+        self.show_next_prompt().await;
+    }
+
+    /// Tell the desktop about the prompts queued since the last one it
+    /// heard of, unless they're answered without asking
+    async fn notify_prompt(&mut self) {
+        let Some(desktop) = &self.desktop else {
+            return;
+        };
+        if self.kiosk.is_some() || self.read_only {
+            return;
+        }
+        let prompts = self.state.pending_prompts.read().await;
+        // Signals may come in together, each prompt is told once
+        let start = self
+            .notified_prompt
+            .as_ref()
+            .and_then(|(addr, connection)| prompts.iter().rposition(|p| p.is_identical(addr, connection)))
+            .map_or(0, |i| i + 1);
+        for prompt in prompts.iter().skip(start) {
+            if !self.prompt_batch.as_ref().is_some_and(|b| prompt.is_identical(&b.node_addr, &b.connection)) {
+                desktop.prompt(&prompt.connection, &self.formats);
+            }
+        }
+        if let Some(prompt) = prompts.back() {
+            self.notified_prompt = Some((prompt.node_addr.clone(), prompt.connection.clone()));
+        }
+    }
+
+    /// Tell the desktop about a new high-priority alert
+    async fn notify_alert(&mut self) {
+        let Some(desktop) = &mut self.desktop else {
+            return;
+        };
+        if let Some(alert) = self.state.alerts.read().await.front() {
+            desktop.alert(alert, &self.formats);
+        }
+    }
+
+    /// Answer the queued prompts the batch in progress covers
+    async fn apply_prompt_batch(&mut self) {
+        if let Some(batch) = &mut self.prompt_batch {
+            self.state.answer_batch(batch).await;
+            if batch.remaining == 0 {
+                self.prompt_batch = None;
+            }
+        }
+    }
+
+    /// Show the next queued rule suggestion, if none is open
+    async fn next_suggestion(&mut self) {
+        // Popups would stay up on a kiosk with nobody to close them
+        if self.suggestion.is_some() || self.kiosk.is_some() {
+            return;
+        }
+        if let Some(suggestion) = self.state.suggestions.write().await.pop_front() {
+            self.suggestion = Some(SuggestionDialog::new(suggestion).with_formats(self.formats.clone()));
+        }
+    }
+
+    /// Tell about rule changes daemons rejected, which were put back as before
+    async fn show_rejected_changes(&mut self) {
+        if self.kiosk.is_some() {
+            return;
+        }
+        let rejected: Vec<_> = self.state.rejected.write().await.drain(..).map(|r| r.message()).collect();
+        if !rejected.is_empty() {
+            let title = if rejected.len() == 1 { "Rule change rejected" } else { "Rule changes rejected" };
+            self.show_notice(title, &rejected.join("\n"));
+        }
+    }
+
+    /// Open a popup for the newest alert if it is high priority and unseen
+    async fn check_alert_popup(&mut self) {
+        if self.alert_popup.is_some() || self.kiosk.is_some() {
+            return;
+        }
+        let alerts = self.state.alerts.read().await;
+        let Some(alert) = alerts.front() else {
+            return;
+        };
+        let key = (alert.id, alert.timestamp);
+        if alert.priority == AlertPriority::High
+            && !alert.acknowledged
+            && self.last_popup_alert != Some(key)
+        {
+            self.last_popup_alert = Some(key);
+            self.alert_popup = Some(AlertDialog::new(alert.clone()).with_formats(self.formats.clone()));
+        }
+    }
+
+    fn active_tab(&self) -> &dyn Tab {
+        match TabId::all()[self.current_tab] {
+            TabId::Connections => &self.connections_tab,
+            TabId::Rules => &self.rules_tab,
+            TabId::Firewall => &self.firewall_tab,
+            TabId::Statistics => &self.statistics_tab,
+            TabId::Alerts => &self.alerts_tab,
+            TabId::Nodes => &self.nodes_tab,
+            TabId::Dns => &self.dns_tab,
+            TabId::DaemonLog => &self.daemon_log_tab,
+        }
+    }
+
+    fn active_tab_mut(&mut self) -> &mut dyn Tab {
         match TabId::all()[self.current_tab] {
+            TabId::Connections => &mut self.connections_tab,
+            TabId::Rules => &mut self.rules_tab,
+            TabId::Firewall => &mut self.firewall_tab,
+            TabId::Statistics => &mut self.statistics_tab,
+            TabId::Alerts => &mut self.alerts_tab,
+            TabId::Nodes => &mut self.nodes_tab,
+            TabId::Dns => &mut self.dns_tab,
+            TabId::DaemonLog => &mut self.daemon_log_tab,
+        }
+    }
+
+    /// Path and size of an exported file
+    fn describe_export(&self, path: &Path) -> String {
+        match std::fs::metadata(path) {
+            Ok(meta) => format!("{} ({})", path.display(), self.formats.bytes(meta.len())),
+            Err(_) => path.display().to_string(),
+        }
+    }
+
+    /// Switch to the tab with the items related to the one selected
+    async fn jump(&mut self, jump: Jump) {
+        match jump {
+            Jump::RuleEvents(rule) => {
+                let connections = self.state.xref.lock().unwrap().rule_events(&rule);
+                self.connections_tab.show_related(format!("rule {}", rule), connections);
+                self.current_tab = TabId::Connections as usize;
+            }
+            Jump::ConnectionEvents(conn) => {
+                let destination = if conn.dst_host.is_empty() { &conn.dst_ip } else { &conn.dst_host };
+                let label = format!("{} → {}:{}", conn.process_name(), self.formats.host(destination), conn.dst_port);
+                self.connections_tab.show_related(label, vec![ConnectionRef::of(&conn)]);
+                self.current_tab = TabId::Connections as usize;
+            }
+            Jump::Rule(name) => self.jump_to_rule(&name).await,
+            Jump::ConnectionRule(conn) => {
+                let rule = self.state.xref.lock().unwrap().connection_rule(&conn).map(str::to_string);
+                match rule {
+                    Some(name) => self.jump_to_rule(&name).await,
+                    None => self.show_notice("No rule", "No recent event of this connection matched a rule."),
+                }
+            }
+            Jump::FirewallEvents { uuid, description } => {
+                let alerts = self.state.xref.lock().unwrap().firewall_alerts(&uuid);
+                let label = if description.is_empty() { uuid } else { description };
+                self.alerts_tab.show_related(format!("firewall rule {}", label), alerts);
+                self.current_tab = TabId::Alerts as usize;
+            }
+        }
+    }
+
+    /// Select a rule of the active node on the Rules tab
+    async fn jump_to_rule(&mut self, name: &str) {
+        let nodes = self.state.nodes.read().await;
+        let index = nodes.active_node().and_then(|node| node.rules.iter().position(|rule| rule.name == name));
+        drop(nodes);
+        match index {
+            Some(index) => {
+                self.rules_tab.select_rule(index);
+                self.current_tab = TabId::Rules as usize;
+            }
+            None => self.show_notice("Rule not found", &format!("The active node has no rule named {}.", name)),
+        }
+    }
+
+    /// Run the side effects a tab requested while handling input
+    async fn run_commands(&mut self, commands: Vec<TabCommand>) {
+        for command in commands {
+            // A mirror may only pick which node it looks at and read it out
+            if self.read_only
+                && !matches!(
+                    command,
+                    TabCommand::SetActiveNode(_)
+                        | TabCommand::ExportRules { .. }
+                        | TabCommand::ExportRulesDir { .. }
+                        | TabCommand::ExportStats(_)
+                        | TabCommand::FetchRules
+                        | TabCommand::SearchConnections { .. }
+                        | TabCommand::ReplayEvent(_)
+                        | TabCommand::Jump(_)
+                        | TabCommand::SavePolicies(_)
+                )
+            {
+                continue;
+            }
+            // An archived node can be browsed but not changed
+            if matches!(
+                command,
+                TabCommand::Send(_)
+                    | TabCommand::SaveFirewall { .. }
+                    | TabCommand::ImportRulesDir { .. }
+                    | TabCommand::DownloadBlocklists { .. }
+                    | TabCommand::UndoRuleChange { .. }
+                    | TabCommand::SetQuarantine { .. }
+            )
+                && self.state.nodes.read().await.active_archived()
+            {
+                continue;
+            }
+            match command {
+                TabCommand::Send(msg) => {
+                    let _ = self.state_tx.send(*msg).await;
+                }
+                TabCommand::AcknowledgeAlert { id, timestamp } => {
+                    self.state.acknowledge_alert(id, timestamp).await;
+                }
+                TabCommand::AcknowledgeAllAlerts => self.state.acknowledge_all_alerts().await,
+                TabCommand::SetActiveNode(addr) => {
+                    let mut nodes = self.state.nodes.write().await;
+                    if nodes.set_active(&addr) {
+                        self.state.notify_ui(UiUpdateSignal::NodeChanged);
+                    }
+                }
+                TabCommand::ForgetArchivedNode(addr) => self.state.forget_archived_node(&addr).await,
+                TabCommand::ImportGuiDatabase(path) => {
+                    let result = self.state.db.import_gui_database(&path).map_err(|e| e.to_string());
+                    self.nodes_tab.set_import_result(result);
+                }
+                TabCommand::ExportRules { node_addr, format } => {
+                    let nodes = self.state.nodes.read().await;
+                    let rules = nodes.get_node(&node_addr).map(|n| n.rules.as_slice()).unwrap_or_default();
+                    let result = export_rules_report(&self.data_dirs.exports(), &node_addr, rules, format)
+                        .map(|path| self.describe_export(&path))
+                        .map_err(|e| e.to_string());
+                    drop(nodes);
+                    self.rules_tab.set_export_result(result);
+                }
+                TabCommand::ImportRulesDir { node_addr, path } => {
+                    let result = match read_rules_dir(Path::new(&path)) {
+                        Ok((rules, mut report)) => {
+                            let nodes = self.state.nodes.read().await;
+                            let existing = nodes.get_node(&node_addr).map(|n| n.rules.as_slice()).unwrap_or_default();
+                            let (kept, missing): (Vec<Rule>, Vec<Rule>) =
+                                rules.into_iter().partition(|rule| existing.iter().any(|r| r.name == rule.name));
+                            drop(nodes);
+                            report.rules = missing.len();
+                            report.skipped = kept.len();
+                            for mut rule in missing {
+                                // Shared rules may name the user's paths with placeholders
+                                if let Err(e) = rule.expand_placeholders(&TemplateVars::for_node(&rule, &node_addr, None)) {
+                                    report.rules -= 1;
+                                    report.errors.push(format!("{}: {}", rule.name, e));
+                                    continue;
+                                }
+                                let _ = self
+                                    .state_tx
+                                    .send(AppMessage::RuleAdded { node_addr: node_addr.clone(), rule: rule.clone() })
+                                    .await;
+                                let _ = self
+                                    .state_tx
+                                    .send(AppMessage::SendNotification {
+                                        node_addr: node_addr.clone(),
+                                        action: NotificationAction::ChangeRule(rule),
+                                    })
+                                    .await;
+                            }
+                            Ok(report)
+                        }
+                        Err(e) => Err(e.to_string()),
+                    };
+                    self.rules_tab.set_rules_dir_result(result);
+                }
+                TabCommand::ExportRulesDir { node_addr, path } => {
+                    let nodes = self.state.nodes.read().await;
+                    let rules = nodes.get_node(&node_addr).map(|n| n.rules.as_slice()).unwrap_or_default();
+                    let result = write_rules_dir(Path::new(&path), rules).map_err(|e| e.to_string());
+                    drop(nodes);
+                    self.rules_tab.set_rules_dir_result(result);
+                }
+                TabCommand::DownloadBlocklists { node_addr, dir, lists } => {
+                    if !is_local_node(&node_addr) {
+                        self.rules_tab.set_blocklists_result(Err(format!(
+                            "{} runs on another host, its lists can't be written from here",
+                            node_addr
+                        )));
+                        continue;
+                    }
+                    let existing = self
+                        .state
+                        .nodes
+                        .read()
+                        .await
+                        .get_node(&node_addr)
+                        .and_then(|node| node.rules.iter().find(|rule| rule.name == RULE_NAME).cloned());
+                    let state_tx = self.state_tx.clone();
+                    // Downloads take a while, the UI goes on meanwhile
+                    self.blocklist_download = Some(tokio::spawn(async move {
+                        let mut report = blocklists::download(lists.iter().map(|list| list.task(&dir)).collect()).await;
+                        let rule = report.written().then(|| blocklist_rule(&dir, existing.as_ref())).flatten();
+                        if let Some(rule) = rule {
+                            report.rule = Some(rule.name.clone());
+                            let local = if existing.is_some() {
+                                AppMessage::RuleModified { node_addr: node_addr.clone(), rule: rule.clone() }
+                            } else {
+                                AppMessage::RuleAdded { node_addr: node_addr.clone(), rule: rule.clone() }
+                            };
+                            let _ = state_tx.send(local).await;
+                            let _ = state_tx
+                                .send(AppMessage::SendNotification { node_addr, action: NotificationAction::ChangeRule(rule) })
+                                .await;
+                        }
+                        report
+                    }));
+                }
+                TabCommand::UndoRuleChange { node_addr } => {
+                    let result = self.state.undo_rule_change(&node_addr).await;
+                    self.rules_tab.set_undo_result(result);
+                }
+                TabCommand::CopyRules { node_addr, rules } => {
+                    let nodes = self.state.nodes.read().await;
+                    let Some(target) = nodes.get_node(&node_addr) else {
+                        continue;
+                    };
+                    let name = target.display_name().to_string();
+                    let existing: Vec<bool> =
+                        rules.iter().map(|rule| target.rules.iter().any(|r| r.name == rule.name)).collect();
+                    drop(nodes);
+                    let replaced = existing.iter().filter(|e| **e).count();
+                    let count = rules.len();
+                    for (rule, exists) in rules.into_iter().zip(existing) {
+                        let local = if exists {
+                            AppMessage::RuleModified { node_addr: node_addr.clone(), rule: rule.clone() }
+                        } else {
+                            AppMessage::RuleAdded { node_addr: node_addr.clone(), rule: rule.clone() }
+                        };
+                        let _ = self.state_tx.send(local).await;
+                        let _ = self
+                            .state_tx
+                            .send(AppMessage::SendNotification {
+                                node_addr: node_addr.clone(),
+                                action: NotificationAction::ChangeRule(rule),
+                            })
+                            .await;
+                    }
+                    let mut message = format!("Sent {} rule{} to {}", count, if count == 1 { "" } else { "s" }, name);
+                    if replaced > 0 {
+                        message.push_str(&format!(", replacing {} of the same name", replaced));
+                    }
+                    message.push('.');
+                    self.show_notice("Rules copied", &message);
+                }
+                TabCommand::SetQuarantine { node_addr, quarantine: true } => {
+                    match snapshots::quarantine(&self.state, &self.state_tx, &node_addr, &self.settings.quarantine_allowlist).await {
+                        Ok(message) => self.show_notice("Node quarantined", &message),
+                        Err(e) => self.show_notice("Quarantine failed", &e),
+                    }
+                }
+                TabCommand::SetQuarantine { node_addr, quarantine: false } => {
+                    match snapshots::lift_quarantine(&self.state, &self.state_tx, &node_addr).await {
+                        Ok(snapshot) => {
+                            let message = format!(
+                                "{} rules are back as they were on {}.",
+                                snapshot.rules.len(),
+                                self.formats.date_time(&snapshot.time)
+                            );
+                            self.show_notice("Quarantine lifted", &message);
+                        }
+                        Err(e) => self.show_notice("Quarantine not lifted", &e),
+                    }
+                }
+                TabCommand::PurgeDatabase { keep_days } => {
+                    if self.purge.is_some() {
+                        self.show_notice("Purge running", "The database is already being purged.");
+                        continue;
+                    }
+                    // Compacting a big database takes a while, the UI goes on meanwhile
+                    let state = self.state.clone();
+                    self.purge = Some(tokio::task::spawn_blocking(move || {
+                        scheduler::purge(&state.db, keep_days)
+                            .and_then(|purged| Ok(format!("{}, {}.", purged, scheduler::vacuum(&state.db)?)))
+                    }));
+                }
+                TabCommand::FetchRules => self.rules_tab.update_cache(&self.state).await,
+                TabCommand::SearchConnections { query, offset } => {
+                    let mut query = match ConnectionQuery::parse(&query, Utc::now()) {
+                        Ok(query) => query,
+                        Err(e) => {
+                            self.connections_tab.set_search_results(Err(e));
+                            continue;
+                        }
+                    };
+                    query.node = self.state.nodes.read().await.active_node().map(|node| node.addr.clone());
+                    // A newer search replaces one still running, whose results are dropped
+                    let state = self.state.clone();
+                    self.search = Some(tokio::task::spawn_blocking(move || {
+                        state.db.search_connections(&query, offset, SEARCH_PAGE).map_err(|e| e.to_string())
+                    }));
+                }
+                TabCommand::ExportStats(format) => {
+                    let nodes = self.state.nodes.read().await;
+                    let result = match nodes.active_node() {
+                        Some(node) => export_stats(
+                            &self.data_dirs.exports(),
+                            &node.addr,
+                            node.display_name(),
+                            node.statistics.as_ref().unwrap_or(&Statistics::default()),
+                            format,
+                        )
+                        .map(|path| self.describe_export(&path))
+                        .map_err(|e| e.to_string()),
+                        None => Err("no active node".to_string()),
+                    };
+                    drop(nodes);
+                    self.statistics_tab.set_export_result(result);
+                }
+                TabCommand::ReplayEvent(event) => {
+                    let nodes = self.state.nodes.read().await;
+                    let rules = nodes.active_node().map(|n| n.rules.as_slice()).unwrap_or_default();
+                    let replay = Replay::of(rules, &event);
+                    drop(nodes);
+                    let conn = &event.connection;
+                    let destination = if conn.dst_host.is_empty() { &conn.dst_ip } else { &conn.dst_host };
+                    let program = conn.process_path.rsplit('/').next().unwrap_or(&conn.process_path);
+                    self.show_notice(
+                        &format!("Replay {} → {}:{}", program, self.formats.host(destination), conn.dst_port),
+                        &replay.message(),
+                    );
+                }
+                TabCommand::Jump(jump) => self.jump(jump).await,
+                TabCommand::NewRule { node_addr, rule } => {
+                    let mut nodes = self.state.nodes.write().await;
+                    if nodes.get_node(&node_addr).is_none_or(|node| node.archived) {
+                        drop(nodes);
+                        self.show_notice("New rule", &format!("{} is not connected, rules can't be sent to it.", node_addr));
+                        continue;
+                    }
+                    if nodes.set_active(&node_addr) {
+                        self.state.notify_ui(UiUpdateSignal::NodeChanged);
+                    }
+                    drop(nodes);
+                    self.current_tab = TabId::Rules as usize;
+                    self.rules_tab.update_cache(&self.state).await;
+                    self.rules_tab.new_rule(&rule);
+                }
+                TabCommand::SavePolicies(policies) => {
+                    if let Err(e) = policies.save(&self.data_dirs.policies()) {
+                        self.show_notice("Policies", &format!("Failed to save policies: {}", e));
+                    }
+                }
+                TabCommand::SaveFirewall { .. } if !self.host.manage_daemon => {
+                    self.show_notice(
+                        "Remote console",
+                        "Firewall rules are saved to the daemon's config file, which is on another host.",
+                    );
+                }
+                TabCommand::SaveFirewall { node_addr, firewall } => {
+                    if let Err(e) = save_firewall_config(&firewall) {
+                        tracing::error!("Failed to save firewall config: {}", e);
+                        continue;
+                    }
+                    if let Some(addr) = node_addr {
+                        let _ = self.state_tx.send(AppMessage::SendNotification {
+                            node_addr: addr,
+                            action: NotificationAction::ReloadFwRules,
+                        }).await;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Refresh the visible tab, and the others whose data changed
+    async fn update_tab_caches(&mut self) {
+        let visible = TabId::all()[self.current_tab];
+        let now = Instant::now();
+        for tab in self.stale_tabs.due(visible, now) {
+            self.update_tab_cache(tab).await;
+            self.stale_tabs.refreshed(tab, now);
+        }
+        self.update_tab_cache(visible).await;
+        self.stale_tabs.refreshed(visible, now);
+    }
+
+    async fn update_tab_cache(&mut self, tab: TabId) {
+        match tab {
             TabId::Connections => self.connections_tab.update_cache(&self.state).await,
             TabId::Rules => self.rules_tab.update_cache(&self.state).await,
             TabId::Firewall => self.firewall_tab.update_cache(&self.state).await,
             TabId::Statistics => self.statistics_tab.update_cache(&self.state).await,
             TabId::Alerts => self.alerts_tab.update_cache(&self.state).await,
             TabId::Nodes => self.nodes_tab.update_cache(&self.state).await,
+            TabId::Dns => self.dns_tab.update_cache(&self.state).await,
+            TabId::DaemonLog => self.daemon_log_tab.update_cache(&self.state).await,
         }
     }
 
@@ -236,9 +1605,10 @@ impl TuiApp {
         let current_tab = self.current_tab;
         let show_help = self.show_help;
         let show_prompt = self.show_prompt;
+        let capabilities = self.capabilities;
 
         // Get status bar data synchronously using try_read
-        let (connected_nodes, firewall_enabled, rule_count, connection_count, alert_count, uptime) = {
+        let (connected_nodes, firewall_enabled, rule_count, connection_count, alert_count, high_alert_count, uptime) = {
             // Try to get node info - use defaults if lock not available
             let nodes_guard = self.state.nodes.try_read();
             let (connected, fw, rules, up) = if let Ok(nodes) = nodes_guard {
@@ -263,12 +1633,116 @@ impl TuiApp {
             let alert_cnt = self.state.alerts.try_read()
                 .map(|a| a.len())
                 .unwrap_or(0);
+            let high_cnt = self.state.unacknowledged_high_alerts();
 
-            (connected, fw, rules, conn_count, alert_cnt, up)
+            (connected, fw, rules, conn_count, alert_cnt, high_cnt, up)
         };
 
+        // Daemon mode of the active node, unless it is only an archive
+        let browsing_archive = self.state.nodes.try_read().is_ok_and(|nodes| nodes.active_archived());
+        let daemon_mode = self.state.nodes.try_read().ok().and_then(|nodes| {
+            nodes
+                .active_node()
+                .filter(|n| !n.archived)
+                .map(|n| (n.default_action(), n.intercept_unknown()))
+        });
+
+        // Prompts waiting behind the one on screen
+        let queued_prompts = self.state.pending_prompts.try_read().map(|p| p.len()).unwrap_or(0);
+        let batch_remaining = self.prompt_batch.as_ref().map(|batch| batch.remaining);
+
+        // Current network and the profile it selected, hidden until detected
+        let network_status = match (self.state.network.try_read(), self.state.prompt_policy.try_read()) {
+            (Ok(network), Ok(policy)) if !network.interface.is_empty() => {
+                let text = match &policy.profile {
+                    Some(profile) => format!("Net: {} [{}: {}]", network.label(), profile, policy.default_action),
+                    None => format!("Net: {}", network.label()),
+                };
+                let style = if policy.default_action == RuleAction::Allow {
+                    Style::default()
+                } else {
+                    Style::default().fg(Color::Yellow)
+                };
+                Some(Span::styled(text, style))
+            }
+            _ => None,
+        };
+
+        let panicked = self.state.panicked_nodes();
+
+        // Tab titles as drawn below, padded by a space and divided by a bar
+        let mut x = 0;
+        self.tab_columns = TabId::all()
+            .iter()
+            .map(|tab| {
+                let mut width = tab.title().chars().count() as u16 + 4;
+                if *tab == TabId::Alerts && high_alert_count > 0 {
+                    width += format!("({}) ", high_alert_count).len() as u16;
+                }
+                let columns = x..x + width;
+                x += width + 1;
+                columns
+            })
+            .collect();
+
+        // Counters a kiosk shows in large digits
+        let kiosk_counters = self.kiosk.map(|_| {
+            let stats = self
+                .state
+                .nodes
+                .try_read()
+                .ok()
+                .and_then(|nodes| nodes.active_node().and_then(|n| n.statistics.clone()));
+            let unacknowledged = self
+                .state
+                .alerts
+                .try_read()
+                .map(|alerts| alerts.iter().filter(|a| !a.acknowledged).count())
+                .unwrap_or(0);
+            let f = &self.formats;
+            [
+                ("Connections", f.number(stats.as_ref().map_or(connection_count as u64, |s| s.connections))),
+                ("Dropped", f.number(stats.as_ref().map_or(0, |s| s.dropped))),
+                ("Rules", f.number(rule_count as u64)),
+                ("Open alerts", f.number(unacknowledged as u64)),
+            ]
+        });
+
         self.terminal.draw(|frame| {
-            let layout = AppLayout::new(frame.area());
+            let mut layout = AppLayout::new(frame.area());
+            if let Some(counters) = &kiosk_counters {
+                layout = layout.with_counters(5);
+                let areas = ratatui::layout::Layout::default()
+                    .direction(ratatui::layout::Direction::Horizontal)
+                    .constraints([Constraint::Ratio(1, 4); 4])
+                    .split(layout.counters);
+                for ((label, value), area) in counters.iter().zip(areas.iter()) {
+                    let lines: Vec<Line> = big_lines(value, capabilities.unicode).into_iter().map(Line::from).collect();
+                    let counter = Paragraph::new(lines)
+                        .alignment(ratatui::layout::Alignment::Center)
+                        .style(theme.accent().add_modifier(Modifier::BOLD))
+                        .block(
+                            Block::default()
+                                .title(format!(" {} ", label))
+                                .borders(Borders::ALL)
+                                .border_style(theme.border()),
+                        );
+                    frame.render_widget(counter, *area);
+                }
+            }
+            if !panicked.is_empty() {
+                layout = layout.with_banner();
+                let banner = format!(
+                    " PANIC: new connections denied on {} — Ctrl+K on the node resumes ",
+                    panicked.join(", ")
+                );
+                frame.render_widget(
+                    Paragraph::new(banner)
+                        .alignment(ratatui::layout::Alignment::Center)
+                        .style(Style::default().fg(Color::White).bg(Color::Red).add_modifier(Modifier::BOLD)),
+                    layout.banner,
+                );
+            }
 
             // Tab bar
             let tab_titles: Vec<Line> = TabId::all()
@@ -280,7 +1754,14 @@ impl TuiApp {
                     } else {
                         theme.tab_inactive()
                     };
-                    Line::from(Span::styled(format!(" {} ", tab.title()), style))
+                    let mut spans = vec![Span::styled(format!(" {} ", tab.title()), style)];
+                    if *tab == TabId::Alerts && high_alert_count > 0 {
+                        spans.push(Span::styled(
+                            format!("({}) ", high_alert_count),
+                            Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+                        ));
+                    }
+                    Line::from(spans)
                 })
                 .collect();
 
@@ -303,10 +1784,12 @@ impl TuiApp {
             match TabId::all()[current_tab] {
                 TabId::Connections => self.connections_tab.render(frame, inner, theme),
                 TabId::Rules => self.rules_tab.render(frame, inner, theme),
-                TabId::Firewall => self.firewall_tab.render(frame, inner, &self.state, theme),
-                TabId::Statistics => self.statistics_tab.render(frame, inner, &self.state, theme),
+                TabId::Firewall => self.firewall_tab.render(frame, inner, theme),
+                TabId::Statistics => self.statistics_tab.render(frame, inner, theme),
                 TabId::Alerts => self.alerts_tab.render(frame, inner, theme),
                 TabId::Nodes => self.nodes_tab.render(frame, inner, theme),
+                TabId::Dns => self.dns_tab.render(frame, inner, theme),
+                TabId::DaemonLog => self.daemon_log_tab.render(frame, inner, theme),
             }
 
             // Status bar
@@ -322,11 +1805,110 @@ impl TuiApp {
                 Span::styled("FW: OFF", Style::default().fg(Color::Yellow))
             };
 
-            let status_line = Line::from(vec![
-                Span::raw(" "),
+            let mut status_spans = vec![Span::raw(" ")];
+            if self.read_only {
+                status_spans.push(Span::styled(
+                    if self.monitor_only { "MONITOR (read-only)" } else { "MIRROR (read-only)" },
+                    Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD),
+                ));
+                status_spans.push(Span::raw(" │ "));
+            }
+            if let Some(path) = &self.replay {
+                status_spans.push(Span::styled(
+                    format!("REPLAY {}", text::truncate(path, 40)),
+                    Style::default().fg(Color::Magenta).add_modifier(Modifier::BOLD),
+                ));
+                status_spans.push(Span::raw(" │ "));
+            }
+            if self.state.in_maintenance() {
+                status_spans.push(Span::styled(
+                    format!("MAINTENANCE ({} skipped, Ctrl+O)", self.state.maintenance_skipped()),
+                    Style::default().fg(Color::Black).bg(Color::Yellow).add_modifier(Modifier::BOLD),
+                ));
+                status_spans.push(Span::raw(" │ "));
+            }
+            if let Some(outage) = self.state.db_outage() {
+                let mut banner = format!(
+                    "DB UNAVAILABLE: {} ({} buffered",
+                    text::truncate(&outage.error, 40),
+                    outage.buffered
+                );
+                if outage.dropped > 0 {
+                    banner.push_str(&format!(", {} dropped", outage.dropped));
+                }
+                banner.push(')');
+                status_spans.push(Span::styled(
+                    banner,
+                    Style::default().fg(Color::White).bg(Color::Red).add_modifier(Modifier::BOLD),
+                ));
+                status_spans.push(Span::raw(" │ "));
+            }
+            if self.formats.is_private() {
+                status_spans.push(Span::styled(
+                    "PRIVATE",
+                    Style::default().fg(Color::Black).bg(Color::Cyan).add_modifier(Modifier::BOLD),
+                ));
+                status_spans.push(Span::raw(" │ "));
+            }
+            if !self.host.manage_daemon && !self.read_only {
+                status_spans.push(Span::styled(
+                    if self.host.container { "CONSOLE (container)" } else { "CONSOLE" },
+                    Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD),
+                ));
+                status_spans.push(Span::raw(" │ "));
+            }
+            if browsing_archive {
+                status_spans.push(Span::styled(
+                    "ARCHIVED (read-only)",
+                    Style::default().fg(Color::Magenta).add_modifier(Modifier::BOLD),
+                ));
+                status_spans.push(Span::raw(" │ "));
+            }
+            status_spans.extend([
                 daemon_status,
                 Span::raw(" │ "),
                 firewall_status,
+            ]);
+            if !self.read_only {
+                let mode = self.state.interception();
+                let style = match mode {
+                    InterceptionMode::Ask => theme.normal(),
+                    InterceptionMode::AllowAll => Style::default().fg(Color::Yellow),
+                    InterceptionMode::DenyAll => Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+                };
+                status_spans.push(Span::raw(" │ "));
+                status_spans.push(Span::styled(format!("Mode: {} (F12)", mode.label()), style));
+            }
+            if queued_prompts > 0 || batch_remaining.is_some() {
+                let mut text = format!("Prompts: {} queued", queued_prompts);
+                if let Some(remaining) = batch_remaining {
+                    text.push_str(&format!(", next {} alike answered", remaining));
+                }
+                status_spans.push(Span::raw(" │ "));
+                status_spans.push(Span::styled(text, Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)));
+            }
+            if let Some((action, intercept)) = daemon_mode {
+                if let Some(action) = action {
+                    let style = if action == RuleAction::Allow {
+                        Style::default().fg(Color::Green)
+                    } else {
+                        Style::default().fg(Color::Red).add_modifier(Modifier::BOLD)
+                    };
+                    status_spans.push(Span::raw(" │ "));
+                    status_spans.push(Span::styled(
+                        format!("Default: {}", action.to_string().to_uppercase()),
+                        style,
+                    ));
+                }
+                if let Some(intercept) = intercept {
+                    status_spans.push(Span::raw(" │ "));
+                    status_spans.push(Span::styled(
+                        format!("Intercept: {}", if intercept { "ON" } else { "OFF" }),
+                        if intercept { Style::default().fg(Color::Yellow) } else { theme.normal() },
+                    ));
+                }
+            }
+            status_spans.extend([
                 Span::raw(" │ "),
                 Span::styled(format!("Rules: {}", rule_count), theme.normal()),
                 Span::raw(" │ "),
@@ -336,10 +1918,20 @@ impl TuiApp {
                 Span::raw(" │ "),
                 Span::styled(format!("Up: {}", uptime), theme.normal()),
                 Span::raw(" │ "),
-                Span::styled("?=help q=quit", theme.dim()),
             ]);
+            if let Some(network) = network_status {
+                status_spans.extend([network, Span::raw(" │ ")]);
+            }
+            if let Some(update) = self.state.update.lock().unwrap().as_ref() {
+                status_spans.push(Span::styled(
+                    format!("Update: {} (F10)", update.latest().tag_name),
+                    Style::default().fg(Color::Green),
+                ));
+                status_spans.push(Span::raw(" │ "));
+            }
+            status_spans.push(Span::styled("?=help q=quit", theme.dim()));
 
-            let status_bar = Paragraph::new(status_line);
+            let status_bar = Paragraph::new(Line::from(status_spans));
             frame.render_widget(status_bar, layout.status);
 
             // Help overlay
@@ -347,12 +1939,70 @@ impl TuiApp {
                 render_help(frame, theme);
             }
 
+            // Rule suggestion popup
+            if let Some(dialog) = &self.suggestion {
+                dialog.render(frame, theme);
+            }
+
+            if let Some(dialog) = &self.missed_prompts {
+                dialog.render(frame, theme);
+            }
+
+            // Daemon configuration confirmation
+            if let Some(change) = &self.config_change {
+                change.dialog.render(frame, theme);
+            }
+
+            if let Some(dialog) = &self.notice {
+                dialog.render(frame, theme);
+            }
+
+            if let Some(dialog) = &self.hook_log {
+                dialog.render(frame, theme);
+            }
+
+            if let Some(dialog) = &self.automations {
+                dialog.render(frame, theme);
+            }
+
+            if let Some(dialog) = &self.sent_changes {
+                dialog.render(frame, theme);
+            }
+
+            if let Some(dialog) = &self.preferences {
+                dialog.render(frame, theme);
+            }
+
+            if let Some(dialog) = &self.prompt_settings {
+                dialog.render(frame, theme);
+            }
+
+            if let Some(dialog) = &self.changelog {
+                dialog.render(frame, theme);
+            }
+
+            if let Some(dialog) = &self.theme_dialog {
+                dialog.render(frame, theme);
+            }
+
             // Prompt dialog
             if show_prompt {
                 if let Some(dialog) = &self.prompt_dialog {
                     dialog.render(frame, theme);
                 }
             }
+
+            // High-priority alert popup
+            if let Some(dialog) = &self.alert_popup {
+                dialog.render(frame, theme);
+            }
+
+            // Idle lock covers everything
+            if self.idle_lock.is_locked() {
+                self.idle_lock.render(frame, theme);
+            }
+
+            capabilities.downgrade(frame.buffer_mut());
         })?;
 
         Ok(())
@@ -365,7 +2015,8 @@ impl Drop for TuiApp {
         let _ = execute!(
             self.terminal.backend_mut(),
             LeaveAlternateScreen,
-            DisableMouseCapture
+            DisableMouseCapture,
+            DisableFocusChange
         );
         let _ = self.terminal.show_cursor();
     }
@@ -373,7 +2024,7 @@ impl Drop for TuiApp {
 
 fn render_help(frame: &mut Frame, theme: &Theme) {
     let area = frame.area();
-    let help_area = crate::ui::layout::DialogLayout::centered(area, 60, 20).dialog;
+    let help_area = crate::ui::layout::DialogLayout::centered(area, 64, 31).dialog;
 
     let help_text = vec![
         "",
@@ -381,10 +2032,11 @@ fn render_help(frame: &mut Frame, theme: &Theme) {
         "  ────────────────────────────────────",
         "",
         "  Navigation:",
-        "    1-6, Tab      Switch tabs",
+        "    1-8, Tab      Switch tabs",
         "    ↑/↓, j/k      Navigate list",
         "    PgUp/PgDn     Page up/down",
         "    Home/End      Go to top/bottom",
+        "    Mouse         Click tabs, rows and buttons, wheel scrolls",
         "",
         "  Actions:",
         "    Enter         Select/confirm",
@@ -393,6 +2045,21 @@ fn render_help(frame: &mut Frame, theme: &Theme) {
         "    n             New item",
         "    /             Filter",
         "    Esc           Clear filter/cancel",
+        "    Ctrl+T        Pick a theme",
+        "    Ctrl+P        Toggle privacy mode (mask hosts and paths)",
+        "    Ctrl+K        Panic: deny all new connections of the node, again to resume",
+        "    Ctrl+O        Toggle maintenance mode",
+        "",
+        "  Daemon:",
+        "    F3            Toggle default action",
+        "    F4            Toggle intercept unknown",
+        "    F6            Missed prompts",
+        "    F7            Rule hook log",
+        "    F8            Settings and scheduled tasks, e to change prompt defaults",
+        "    F9            Changes sent to daemons and their replies",
+        "    F10           What's new in newer releases",
+        "    F11           Alert automations and what they did",
+        "    F12           Cycle interception mode: ask, allow all, deny all",
         "",
         "  Press any key to close",
     ];
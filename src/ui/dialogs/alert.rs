@@ -0,0 +1,107 @@
+//! High-priority alert popup
+
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    layout::{Constraint, Direction, Layout},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph, Wrap},
+    Frame,
+};
+
+use crate::models::Alert;
+use crate::ui::layout::DialogLayout;
+use crate::ui::theme::Theme;
+use crate::utils::Formats;
+
+/// Modal shown when a high-priority alert arrives
+pub struct AlertDialog {
+    pub alert: Alert,
+    /// Set when the user acknowledged the alert rather than dismissing it
+    pub acknowledged: bool,
+    formats: Formats,
+}
+
+impl AlertDialog {
+    pub fn new(alert: Alert) -> Self {
+        Self {
+            alert,
+            acknowledged: false,
+            formats: Formats::default(),
+        }
+    }
+
+    /// Number and date formatting
+    pub fn with_formats(mut self, formats: Formats) -> Self {
+        self.formats = formats;
+        self
+    }
+
+    /// Handle key event, returns true if dialog should close
+    pub fn handle_key(&mut self, key: KeyEvent) -> bool {
+        match key.code {
+            KeyCode::Enter | KeyCode::Char('a') => {
+                self.acknowledged = true;
+                true
+            }
+            KeyCode::Esc => true,
+            _ => false,
+        }
+    }
+
+    pub fn render(&self, frame: &mut Frame, theme: &Theme) {
+        let area = frame.area();
+        let dialog_area = DialogLayout::centered(area, 64, 12).dialog;
+
+        frame.render_widget(Clear, dialog_area);
+
+        let block = Block::default()
+            .title(" High Priority Alert ")
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Red))
+            .style(theme.normal());
+
+        let inner = block.inner(dialog_area);
+        frame.render_widget(block, dialog_area);
+
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .margin(1)
+            .constraints([
+                Constraint::Length(3), // Header
+                Constraint::Min(2),    // Message
+                Constraint::Length(1), // Hints
+            ])
+            .split(inner);
+
+        let label = |text: &'static str| Span::styled(format!("{:8}", text), theme.dim());
+        let header = vec![
+            Line::from(vec![
+                label("Type:"),
+                Span::styled(
+                    format!("{}", self.alert.alert_type),
+                    Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+                ),
+                Span::raw(format!("  ({})", self.alert.what)),
+            ]),
+            Line::from(vec![label("Node:"), Span::raw(self.formats.host(&self.alert.node).into_owned())]),
+            Line::from(vec![
+                label("Time:"),
+                Span::raw(self.formats.date_time(&self.alert.timestamp)),
+            ]),
+        ];
+        frame.render_widget(Paragraph::new(header), chunks[0]);
+
+        frame.render_widget(
+            Paragraph::new(self.formats.alert(&self.alert))
+                .style(theme.normal())
+                .wrap(Wrap { trim: true }),
+            chunks[1],
+        );
+
+        frame.render_widget(
+            Paragraph::new("Enter/a=acknowledge  Esc=dismiss").style(theme.dim()),
+            chunks[2],
+        );
+    }
+}
@@ -0,0 +1,191 @@
+//! Alert details dialog, with everything the daemon sent along
+
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    layout::{Constraint, Direction, Layout},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph, Wrap},
+    Frame,
+};
+
+use crate::models::{Alert, AlertData};
+use crate::ui::layout::DialogLayout;
+use crate::ui::theme::Theme;
+use crate::utils::Formats;
+
+/// Outcome of the details dialog
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AlertDetailsResult {
+    Close,
+    Acknowledge,
+}
+
+pub struct AlertDetailsDialog {
+    alert: Alert,
+    scroll_offset: u16,
+    formats: Formats,
+}
+
+impl AlertDetailsDialog {
+    pub fn new(alert: Alert) -> Self {
+        Self {
+            alert,
+            scroll_offset: 0,
+            formats: Formats::default(),
+        }
+    }
+
+    /// Number and date formatting
+    pub fn with_formats(mut self, formats: Formats) -> Self {
+        self.formats = formats;
+        self
+    }
+
+    pub fn alert(&self) -> &Alert {
+        &self.alert
+    }
+
+    /// Handle key event, returns a result once the dialog should close
+    pub fn handle_key(&mut self, key: KeyEvent) -> Option<AlertDetailsResult> {
+        match key.code {
+            KeyCode::Esc | KeyCode::Char('q') | KeyCode::Enter => return Some(AlertDetailsResult::Close),
+            KeyCode::Char('a') => return Some(AlertDetailsResult::Acknowledge),
+            KeyCode::Up | KeyCode::Char('k') => self.scroll_offset = self.scroll_offset.saturating_sub(1),
+            KeyCode::Down | KeyCode::Char('j') => self.scroll_offset = self.scroll_offset.saturating_add(1),
+            KeyCode::PageUp => self.scroll_offset = self.scroll_offset.saturating_sub(10),
+            KeyCode::PageDown => self.scroll_offset = self.scroll_offset.saturating_add(10),
+            KeyCode::Home => self.scroll_offset = 0,
+            _ => {}
+        }
+        None
+    }
+
+    /// Lines for the alert and its payload
+    fn lines(&self) -> Vec<Line<'static>> {
+        let alert = &self.alert;
+        let f = &self.formats;
+        let section = |title: &'static str| {
+            Line::from(Span::styled(title, Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)))
+        };
+        let mut lines = vec![
+            section("ALERT"),
+            Line::from(format!("  Type:     {} ({})", alert.alert_type, alert.what)),
+            Line::from(format!("  Priority: {:?}", alert.priority)),
+            Line::from(format!("  Node:     {}", f.host(&alert.node))),
+            Line::from(format!("  Time:     {}", f.date_time(&alert.timestamp))),
+            Line::from(format!("  Status:   {}", if alert.acknowledged { "acknowledged" } else { "open" })),
+            Line::from(""),
+        ];
+
+        match &alert.data {
+            None => lines.push(Line::from("  The daemon sent no details.")),
+            Some(AlertData::Text(text)) => {
+                lines.push(section("MESSAGE"));
+                lines.extend(f.text(text).lines().map(|line| Line::from(format!("  {}", line))));
+            }
+            Some(AlertData::Process(process)) => {
+                lines.push(section("PROCESS"));
+                lines.push(Line::from(format!("  Path: {}", f.path(&process.path))));
+                lines.push(Line::from(format!("  Name: {}", process.comm)));
+                lines.push(Line::from(format!("  PID:  {} (parent {})", process.pid, process.ppid)));
+                lines.push(Line::from(format!("  UID:  {}", process.uid)));
+                lines.push(Line::from(format!("  CWD:  {}", f.path(&process.cwd))));
+                if !process.args.is_empty() {
+                    lines.push(Line::from(format!("  Args: {}", f.text(&process.args.join(" ")))));
+                }
+                for (name, pid) in &process.process_tree {
+                    lines.push(Line::from(format!("  ↳ {} ({})", f.path(name), pid)));
+                }
+                let mut checksums: Vec<_> = process.checksums.iter().collect();
+                checksums.sort();
+                for (algo, hash) in checksums {
+                    lines.push(Line::from(format!("  {}: {}", algo, hash)));
+                }
+            }
+            Some(AlertData::Connection(conn)) => {
+                lines.push(section("PROCESS"));
+                lines.push(Line::from(format!("  Path: {}", f.path(&conn.process_path))));
+                lines.push(Line::from(format!("  PID:  {}", conn.process_id)));
+                lines.push(Line::from(format!("  UID:  {}", conn.user_id)));
+                if !conn.process_args.is_empty() {
+                    lines.push(Line::from(format!("  Args: {}", f.text(&conn.process_args.join(" ")))));
+                }
+                lines.push(Line::from(""));
+                lines.push(section("CONNECTION"));
+                lines.push(Line::from(format!("  Protocol: {}", conn.protocol)));
+                lines.push(Line::from(format!("  Source:   {}:{}", f.host(&conn.src_ip), conn.src_port)));
+                let dest = if conn.dst_host.is_empty() {
+                    f.host(&conn.dst_ip).into_owned()
+                } else {
+                    format!("{} ({})", f.host(&conn.dst_host), f.host(&conn.dst_ip))
+                };
+                lines.push(Line::from(format!("  Dest:     {}:{}", dest, conn.dst_port)));
+            }
+            Some(AlertData::Rule(rule)) => {
+                let rule = f.rule(rule);
+                lines.push(section("RULE"));
+                lines.push(Line::from(format!("  Name:     {}", rule.name)));
+                lines.push(Line::from(format!("  Enabled:  {}", if rule.enabled { "yes" } else { "no" })));
+                lines.push(Line::from(format!("  Action:   {} {}", rule.action, rule.duration)));
+                lines.push(Line::from(format!("  Operator: {} {} {}", rule.operator.operand, rule.operator.op_type, rule.operator.data)));
+                if !rule.description.is_empty() {
+                    lines.push(Line::from(format!("  About:    {}", rule.description)));
+                }
+            }
+            Some(AlertData::FirewallRule(rule)) => {
+                lines.push(section("FIREWALL RULE"));
+                lines.push(Line::from(format!("  Description: {}", f.addresses(&rule.description))));
+                lines.push(Line::from(format!("  Enabled:     {}", if rule.enabled { "yes" } else { "no" })));
+                if !rule.chain.is_empty() {
+                    lines.push(Line::from(format!("  Chain:       {} {}", rule.table, rule.chain)));
+                }
+                lines.push(Line::from(format!("  Target:      {} {}", rule.target, rule.target_parameters)));
+                if !rule.parameters.is_empty() {
+                    lines.push(Line::from(format!("  Parameters:  {}", rule.parameters)));
+                }
+                for expression in &rule.expressions {
+                    let statement = &expression.statement;
+                    let values: Vec<_> =
+                        statement.values.iter().map(|v| format!("{} {}", v.key, f.addresses(&v.value))).collect();
+                    lines.push(Line::from(format!("  {} {} {}", statement.name, statement.op, values.join(", "))));
+                }
+            }
+        }
+        lines
+    }
+
+    pub fn render(&self, frame: &mut Frame, theme: &Theme) {
+        let area = frame.area();
+        let dialog_area = DialogLayout::centered(area, 80, 24).dialog;
+
+        frame.render_widget(Clear, dialog_area);
+
+        let block = Block::default()
+            .title(" Alert Details ")
+            .borders(Borders::ALL)
+            .border_style(theme.border_focused())
+            .style(theme.normal());
+
+        let inner = block.inner(dialog_area);
+        frame.render_widget(block, dialog_area);
+
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .margin(1)
+            .constraints([
+                Constraint::Min(3),    // Details
+                Constraint::Length(1), // Hints
+            ])
+            .split(inner);
+
+        frame.render_widget(
+            Paragraph::new(self.lines())
+                .wrap(Wrap { trim: false })
+                .scroll((self.scroll_offset, 0)),
+            chunks[0],
+        );
+        let hints = if self.alert.acknowledged { "↑↓ = scroll  Esc = close" } else { "↑↓ = scroll  a = acknowledge  Esc = close" };
+        frame.render_widget(Paragraph::new(hints).style(theme.dim()), chunks[1]);
+    }
+}
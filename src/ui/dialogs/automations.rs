@@ -0,0 +1,301 @@
+//! Automations run when alerts arrive, with the trail of what they did
+
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    layout::{Constraint, Direction, Layout},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Cell, Clear, Paragraph, Row, Table, TableState},
+    Frame,
+};
+
+use crate::models::{AlertAutomation, AlertWhat, AutomationRun, Automations};
+use crate::ui::layout::DialogLayout;
+use crate::ui::theme::Theme;
+use crate::utils::Formats;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+    Name,
+    What,
+    Contains,
+    DenyBinary,
+    Acknowledge,
+}
+
+const FIELDS: [Field; 5] = [Field::Name, Field::What, Field::Contains, Field::DenyBinary, Field::Acknowledge];
+
+/// Alert kinds to pick from, None for any
+const KINDS: [Option<AlertWhat>; 8] = [
+    None,
+    Some(AlertWhat::Generic),
+    Some(AlertWhat::ProcMonitor),
+    Some(AlertWhat::Firewall),
+    Some(AlertWhat::Connection),
+    Some(AlertWhat::Rule),
+    Some(AlertWhat::Netlink),
+    Some(AlertWhat::KernelEvent),
+];
+
+/// Outcome of the dialog
+pub enum AutomationsResult {
+    Save(Automations),
+    Close,
+}
+
+/// Automation being edited, None in the list of automations
+struct Form {
+    /// Position of the automation edited, None for a new one
+    index: Option<usize>,
+    automation: AlertAutomation,
+    focus: usize,
+    error: Option<String>,
+}
+
+pub struct AutomationsDialog {
+    automations: Automations,
+    runs: Vec<AutomationRun>,
+    selected: usize,
+    form: Option<Form>,
+    changed: bool,
+    formats: Formats,
+}
+
+impl AutomationsDialog {
+    pub fn new(automations: Automations, runs: Vec<AutomationRun>) -> Self {
+        Self {
+            automations,
+            runs,
+            selected: 0,
+            form: None,
+            changed: false,
+            formats: Formats::default(),
+        }
+    }
+
+    pub fn with_formats(mut self, formats: Formats) -> Self {
+        self.formats = formats;
+        self
+    }
+
+    /// Handle key event, returns a result once the dialog should close
+    pub fn handle_key(&mut self, key: KeyEvent) -> Option<AutomationsResult> {
+        if self.form.is_some() {
+            self.handle_form_key(key);
+            return None;
+        }
+
+        let count = self.automations.automations.len();
+        match key.code {
+            KeyCode::Down | KeyCode::Char('j') => self.selected = (self.selected + 1).min(count.saturating_sub(1)),
+            KeyCode::Up | KeyCode::Char('k') => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Char('n') => {
+                self.form = Some(Form { index: None, automation: AlertAutomation::default(), focus: 0, error: None });
+            }
+            KeyCode::Char('e') | KeyCode::Enter if count > 0 => {
+                let automation = self.automations.automations[self.selected].clone();
+                self.form = Some(Form { index: Some(self.selected), automation, focus: 0, error: None });
+            }
+            KeyCode::Char(' ') if count > 0 => {
+                let automation = &mut self.automations.automations[self.selected];
+                automation.enabled = !automation.enabled;
+                self.changed = true;
+            }
+            KeyCode::Char('d') | KeyCode::Delete if count > 0 => {
+                self.automations.automations.remove(self.selected);
+                self.selected = self.selected.min(count.saturating_sub(2));
+                self.changed = true;
+            }
+            KeyCode::Esc | KeyCode::Char('q') => {
+                return Some(if self.changed {
+                    AutomationsResult::Save(self.automations.clone())
+                } else {
+                    AutomationsResult::Close
+                });
+            }
+            _ => {}
+        }
+        None
+    }
+
+    fn handle_form_key(&mut self, key: KeyEvent) {
+        let Some(form) = &mut self.form else {
+            return;
+        };
+        form.error = None;
+        let automation = &mut form.automation;
+        match (key.code, FIELDS[form.focus]) {
+            (KeyCode::Esc, _) => self.form = None,
+            (KeyCode::Tab | KeyCode::Down, _) => form.focus = (form.focus + 1) % FIELDS.len(),
+            (KeyCode::BackTab | KeyCode::Up, _) => form.focus = (form.focus + FIELDS.len() - 1) % FIELDS.len(),
+            (KeyCode::Enter, _) => {
+                automation.name = automation.name.trim().to_string();
+                let taken = self
+                    .automations
+                    .automations
+                    .iter()
+                    .enumerate()
+                    .any(|(i, a)| a.name == automation.name && Some(i) != form.index);
+                if automation.name.is_empty() {
+                    form.error = Some("Name the automation".to_string());
+                } else if taken {
+                    form.error = Some(format!("There is already an automation named {}", automation.name));
+                } else if let Some(form) = self.form.take() {
+                    match form.index {
+                        Some(i) => self.automations.automations[i] = form.automation,
+                        None => {
+                            self.automations.automations.push(form.automation);
+                            self.selected = self.automations.automations.len() - 1;
+                        }
+                    }
+                    self.changed = true;
+                }
+            }
+            (KeyCode::Left | KeyCode::Right | KeyCode::Char(' '), Field::What) => {
+                let current = KINDS.iter().position(|kind| *kind == automation.what).unwrap_or(0);
+                let next = if key.code == KeyCode::Left { current + KINDS.len() - 1 } else { current + 1 };
+                automation.what = KINDS[next % KINDS.len()];
+            }
+            (KeyCode::Left | KeyCode::Right | KeyCode::Char(' '), Field::DenyBinary) => {
+                automation.deny_binary = !automation.deny_binary;
+            }
+            (KeyCode::Left | KeyCode::Right | KeyCode::Char(' '), Field::Acknowledge) => {
+                automation.acknowledge = !automation.acknowledge;
+            }
+            (KeyCode::Backspace, Field::Name) => {
+                automation.name.pop();
+            }
+            (KeyCode::Backspace, Field::Contains) => {
+                automation.contains.pop();
+            }
+            (KeyCode::Char(c), Field::Name) => automation.name.push(c),
+            (KeyCode::Char(c), Field::Contains) => automation.contains.push(c),
+            _ => {}
+        }
+    }
+
+    pub fn render(&self, frame: &mut Frame, theme: &Theme) {
+        let area = frame.area();
+        let dialog_area = DialogLayout::centered(area, 100, 26).dialog;
+
+        frame.render_widget(Clear, dialog_area);
+
+        let block = Block::default()
+            .title(" Alert Automations ")
+            .borders(Borders::ALL)
+            .border_style(theme.border_focused())
+            .style(theme.normal());
+
+        let inner = block.inner(dialog_area);
+        frame.render_widget(block, dialog_area);
+
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .margin(1)
+            .constraints([
+                Constraint::Length(8), // Automations or the form
+                Constraint::Min(3),    // Audit trail
+                Constraint::Length(1), // Hints
+            ])
+            .split(inner);
+
+        let hints = match &self.form {
+            Some(form) => {
+                self.render_form(frame, chunks[0], form, theme);
+                "Tab = next field  Space = toggle  Enter = keep  Esc = back"
+            }
+            None => {
+                self.render_list(frame, chunks[0], theme);
+                "n = new  e = edit  Space = on/off  d = delete  Esc = close"
+            }
+        };
+        self.render_runs(frame, chunks[1], theme);
+        frame.render_widget(Paragraph::new(hints).style(theme.dim()), chunks[2]);
+    }
+
+    fn render_list(&self, frame: &mut Frame, area: ratatui::layout::Rect, theme: &Theme) {
+        if self.automations.is_empty() {
+            frame.render_widget(
+                Paragraph::new("No automations. Press n to act on alerts as they arrive, e.g. deny the binary of a kernel event.")
+                    .style(theme.dim()),
+                area,
+            );
+            return;
+        }
+        let rows = self.automations.automations.iter().map(|automation| {
+            let state = if automation.enabled { Cell::from("on").style(theme.success()) } else { Cell::from("off").style(theme.dim()) };
+            Row::new(vec![state, Cell::from(automation.name.clone()), Cell::from(automation.summary())])
+        });
+        let table = Table::new(rows, [Constraint::Length(4), Constraint::Length(20), Constraint::Min(30)])
+            .row_highlight_style(theme.selected());
+        let mut state = TableState::default().with_selected(Some(self.selected));
+        frame.render_stateful_widget(table, area, &mut state);
+    }
+
+    fn render_form(&self, frame: &mut Frame, area: ratatui::layout::Rect, form: &Form, theme: &Theme) {
+        let automation = &form.automation;
+        let toggle = |on: bool| if on { "[x]" } else { "[ ]" }.to_string();
+        let value = |field: Field| match field {
+            Field::Name => automation.name.clone(),
+            Field::What => format!("◀ {} ▶", automation.what.map(|w| w.to_string()).unwrap_or_else(|| "Any".to_string())),
+            Field::Contains => automation.contains.clone(),
+            Field::DenyBinary => toggle(automation.deny_binary),
+            Field::Acknowledge => toggle(automation.acknowledge),
+        };
+        let label = |field: Field| match field {
+            Field::Name => "Name:",
+            Field::What => "When a ... alert",
+            Field::Contains => "mentions:",
+            Field::DenyBinary => "Deny its binary:",
+            Field::Acknowledge => "Acknowledge it:",
+        };
+        let mut lines: Vec<Line> = FIELDS
+            .iter()
+            .enumerate()
+            .map(|(i, field)| {
+                let style = if i == form.focus { theme.selected() } else { theme.normal() };
+                Line::from(vec![
+                    Span::styled(format!("{:18}", label(*field)), theme.dim()),
+                    Span::styled(value(*field), style),
+                ])
+            })
+            .collect();
+        if let Some(e) = &form.error {
+            lines.push(Line::from(Span::styled(format!("⚠ {}", e), Style::default().fg(Color::Yellow))));
+        }
+        frame.render_widget(Paragraph::new(lines), area);
+    }
+
+    fn render_runs(&self, frame: &mut Frame, area: ratatui::layout::Rect, theme: &Theme) {
+        let block = Block::default().title(" Audit trail ").borders(Borders::TOP).border_style(theme.border());
+        if self.runs.is_empty() {
+            frame.render_widget(Paragraph::new("No automation has run yet.").style(theme.dim()).block(block), area);
+            return;
+        }
+        let header = Row::new(["Time", "Automation", "Node", "Alert", "Done"])
+            .style(theme.accent().add_modifier(Modifier::BOLD));
+        let f = &self.formats;
+        let rows = self.runs.iter().map(|run| {
+            Row::new(vec![
+                Cell::from(f.time(&run.time)),
+                Cell::from(run.automation.clone()),
+                Cell::from(f.host(&run.node).into_owned()),
+                Cell::from(f.text(&run.alert).into_owned()),
+                Cell::from(f.text(&run.actions.join(", ")).into_owned()),
+            ])
+        });
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Length(11),
+                Constraint::Length(16),
+                Constraint::Length(16),
+                Constraint::Min(20),
+                Constraint::Min(20),
+            ],
+        )
+        .header(header)
+        .block(block);
+        frame.render_widget(table, area);
+    }
+}
@@ -0,0 +1,278 @@
+//! Download well-known blocklists for a `lists.domains` deny rule
+
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    layout::{Constraint, Direction, Layout},
+    style::Modifier,
+    text::{Line, Span},
+    widgets::{Block, Borders, Cell, Clear, Paragraph, Row, Table, TableState, Wrap},
+    Frame,
+};
+
+use crate::app::blocklists::{BlocklistReport, KnownList, ListFile, DEFAULT_LISTS_DIR, KNOWN_LISTS};
+use crate::ui::layout::DialogLayout;
+use crate::ui::theme::Theme;
+use crate::utils::text;
+
+/// Outcome of a blocklists dialog key press
+pub enum BlocklistsResult {
+    Close,
+    /// Download the lists into the directory, the dialog stays open for the report
+    Download { dir: String, lists: Vec<KnownList> },
+}
+
+pub struct BlocklistsDialog {
+    pub dir: String,
+    cursor_pos: usize,
+    /// Directories the node's lists rules read
+    dirs: Vec<String>,
+    /// The directory was typed or picked, configured ones no longer replace it
+    dir_picked: bool,
+    /// Focus on the lists rather than the directory
+    on_lists: bool,
+    selected: usize,
+    checked: [bool; KNOWN_LISTS.len()],
+    /// The lists as found in the directory
+    files: Vec<Option<ListFile>>,
+    downloading: bool,
+    result: Option<Result<BlocklistReport, String>>,
+}
+
+impl BlocklistsDialog {
+    pub fn new() -> Self {
+        let mut dialog = Self {
+            dir: String::new(),
+            cursor_pos: 0,
+            dirs: Vec::new(),
+            dir_picked: false,
+            on_lists: true,
+            selected: 0,
+            checked: [false; KNOWN_LISTS.len()],
+            files: Vec::new(),
+            downloading: false,
+            result: None,
+        };
+        dialog.set_dir(DEFAULT_LISTS_DIR.to_string());
+        dialog
+    }
+
+    /// Directories of the node's lists rules, the first one is used until
+    /// another is picked
+    pub fn set_dirs(&mut self, dirs: Vec<String>) {
+        if !self.dir_picked {
+            if let Some(dir) = dirs.first().filter(|dir| **dir != self.dir) {
+                self.set_dir(dir.clone());
+            }
+        }
+        self.dirs = dirs;
+    }
+
+    /// Show the outcome of the download
+    pub fn set_result(&mut self, result: Result<BlocklistReport, String>) {
+        self.downloading = false;
+        self.result = Some(result);
+        self.read_files();
+    }
+
+    fn set_dir(&mut self, dir: String) {
+        self.cursor_pos = dir.len();
+        self.dir = dir;
+        self.read_files();
+        // Lists already there are kept up to date
+        for (checked, file) in self.checked.iter_mut().zip(&self.files) {
+            *checked = file.is_some();
+        }
+    }
+
+    fn read_files(&mut self) {
+        self.files = KNOWN_LISTS.iter().map(|list| ListFile::read(&list.path(&self.dir))).collect();
+    }
+
+    /// Step through the configured directories
+    fn pick_dir(&mut self, forward: bool) {
+        if self.dirs.is_empty() {
+            return;
+        }
+        let next = match self.dirs.iter().position(|dir| *dir == self.dir) {
+            Some(i) if forward => (i + 1) % self.dirs.len(),
+            Some(i) => (i + self.dirs.len() - 1) % self.dirs.len(),
+            None => 0,
+        };
+        self.dir_picked = true;
+        self.set_dir(self.dirs[next].clone());
+    }
+
+    fn download(&mut self) -> Option<BlocklistsResult> {
+        let lists: Vec<KnownList> = KNOWN_LISTS
+            .iter()
+            .zip(self.checked)
+            .filter(|(_, checked)| *checked)
+            .map(|(list, _)| *list)
+            .collect();
+        if lists.is_empty() || self.dir.trim().is_empty() {
+            return None;
+        }
+        self.downloading = true;
+        self.result = None;
+        Some(BlocklistsResult::Download { dir: self.dir.trim().to_string(), lists })
+    }
+
+    /// Handle key event
+    pub fn handle_key(&mut self, key: KeyEvent) -> Option<BlocklistsResult> {
+        if key.code == KeyCode::Esc {
+            return Some(BlocklistsResult::Close);
+        }
+        // Nothing to change until the lists are in
+        if self.downloading {
+            return None;
+        }
+        match key.code {
+            KeyCode::Tab | KeyCode::BackTab => self.on_lists = !self.on_lists,
+            KeyCode::Enter => return self.download(),
+            _ if self.on_lists => match key.code {
+                KeyCode::Down | KeyCode::Char('j') => {
+                    self.selected = (self.selected + 1).min(KNOWN_LISTS.len() - 1);
+                }
+                KeyCode::Up | KeyCode::Char('k') => self.selected = self.selected.saturating_sub(1),
+                KeyCode::Char(' ') => self.checked[self.selected] = !self.checked[self.selected],
+                KeyCode::Char('q') => return Some(BlocklistsResult::Close),
+                _ => {}
+            },
+            KeyCode::Up => self.pick_dir(false),
+            KeyCode::Down => self.pick_dir(true),
+            KeyCode::Char(c) => {
+                text::insert(&mut self.dir, &mut self.cursor_pos, c);
+                self.dir_picked = true;
+                self.read_files();
+            }
+            KeyCode::Backspace => {
+                text::backspace(&mut self.dir, &mut self.cursor_pos);
+                self.dir_picked = true;
+                self.read_files();
+            }
+            KeyCode::Left => self.cursor_pos = text::prev_boundary(&self.dir, self.cursor_pos),
+            KeyCode::Right => self.cursor_pos = text::next_boundary(&self.dir, self.cursor_pos),
+            KeyCode::Home => self.cursor_pos = 0,
+            KeyCode::End => self.cursor_pos = self.dir.len(),
+            _ => {}
+        }
+        None
+    }
+
+    pub fn render(&self, frame: &mut Frame, theme: &Theme) {
+        let area = frame.area();
+        let dialog_area = DialogLayout::centered(area, 86, 22).dialog;
+
+        frame.render_widget(Clear, dialog_area);
+
+        let block = Block::default()
+            .title(" Blocklists ")
+            .borders(Borders::ALL)
+            .border_style(theme.border_focused())
+            .style(theme.normal());
+
+        let inner = block.inner(dialog_area);
+        frame.render_widget(block, dialog_area);
+
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .margin(1)
+            .constraints([
+                Constraint::Length(2),                            // Intro
+                Constraint::Length(3),                            // Directory input
+                Constraint::Length(KNOWN_LISTS.len() as u16 + 1), // Lists
+                Constraint::Min(2),                               // Report
+                Constraint::Length(1),                            // Hints
+            ])
+            .split(inner);
+
+        frame.render_widget(
+            Paragraph::new("Downloads hosts lists into a directory the daemon reads, and a deny rule for the domains in them.")
+                .style(theme.dim())
+                .wrap(Wrap { trim: true }),
+            chunks[0],
+        );
+
+        let configured = if self.dirs.contains(&self.dir) {
+            " (read by a lists rule) "
+        } else {
+            " "
+        };
+        let dir_block = Block::default()
+            .title(format!(" Lists directory{}", configured))
+            .borders(Borders::ALL)
+            .border_style(if self.on_lists { theme.border() } else { theme.border_focused() });
+        frame.render_widget(Paragraph::new(self.dir.as_str()).block(dir_block).style(theme.normal()), chunks[1]);
+        if !self.on_lists && !self.downloading {
+            frame.set_cursor_position((
+                chunks[1].x + 1 + text::column(&self.dir, self.cursor_pos) as u16,
+                chunks[1].y + 1,
+            ));
+        }
+
+        let header = Row::new(["", "List", "Content", "On disk"])
+            .style(theme.accent().add_modifier(Modifier::BOLD));
+        let rows = KNOWN_LISTS.iter().zip(self.checked).zip(&self.files).map(|((list, checked), file)| {
+            let on_disk = match file {
+                Some(ListFile { entries, modified: Some(time) }) => {
+                    format!("{} entries, {}", entries, time.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M"))
+                }
+                Some(file) => format!("{} entries", file.entries),
+                None => "not downloaded".to_string(),
+            };
+            Row::new(vec![
+                Cell::from(if checked { "[x]" } else { "[ ]" }),
+                Cell::from(list.name),
+                Cell::from(list.description).style(theme.dim()),
+                Cell::from(on_disk).style(if file.is_some() { theme.normal() } else { theme.dim() }),
+            ])
+        });
+        let table = Table::new(
+            rows,
+            [Constraint::Length(3), Constraint::Length(12), Constraint::Length(31), Constraint::Min(16)],
+        )
+        .header(header)
+        .row_highlight_style(if self.on_lists { theme.selected() } else { theme.normal() })
+        .highlight_symbol("▶ ");
+        let mut state = TableState::default().with_selected(Some(self.selected));
+        frame.render_stateful_widget(table, chunks[2], &mut state);
+
+        let report_lines: Vec<Line> = match (&self.result, self.downloading) {
+            (_, true) => vec![Line::styled("Downloading…", theme.accent())],
+            (None, false) => vec![],
+            (Some(Err(e)), false) => vec![Line::styled(format!("Lists not downloaded: {}", e), theme.error())],
+            (Some(Ok(report)), false) => {
+                let mut lines: Vec<Line> = report
+                    .lists
+                    .iter()
+                    .map(|(name, result)| match result {
+                        Ok(message) => Line::from(vec![Span::raw(format!("{}: ", name)), Span::styled(message.clone(), theme.success())]),
+                        Err(e) => Line::from(vec![Span::raw(format!("{}: ", name)), Span::styled(e.clone(), theme.error())]),
+                    })
+                    .collect();
+                match &report.rule {
+                    Some(rule) => lines.push(Line::styled(format!("Rule {} sent to the node", rule), theme.success())),
+                    None if report.written() => lines.push(Line::styled("The deny rule already reads the directory", theme.dim())),
+                    None => {}
+                }
+                lines
+            }
+        };
+        frame.render_widget(Paragraph::new(report_lines).wrap(Wrap { trim: false }), chunks[3]);
+
+        let hints = if self.downloading {
+            "Esc=close, the download goes on"
+        } else if self.on_lists {
+            "↑↓=list  Space=pick  Tab=directory  Enter=download  Esc=close"
+        } else {
+            "↑↓=directories of lists rules  Tab=lists  Enter=download  Esc=close"
+        };
+        frame.render_widget(Paragraph::new(hints).style(theme.dim()), chunks[4]);
+    }
+}
+
+impl Default for BlocklistsDialog {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,117 @@
+//! Release notes of the releases newer than the running TUI
+
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    layout::{Constraint, Direction, Layout},
+    style::Modifier,
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph, Wrap},
+    Frame,
+};
+
+use crate::app::updates::{UpdateInfo, CURRENT_VERSION};
+use crate::ui::layout::DialogLayout;
+use crate::ui::theme::Theme;
+use crate::utils::Formats;
+
+pub struct ChangelogDialog {
+    update: UpdateInfo,
+    scroll: u16,
+    formats: Formats,
+}
+
+impl ChangelogDialog {
+    pub fn new(update: UpdateInfo) -> Self {
+        Self {
+            update,
+            scroll: 0,
+            formats: Formats::default(),
+        }
+    }
+
+    pub fn with_formats(mut self, formats: Formats) -> Self {
+        self.formats = formats;
+        self
+    }
+
+    /// Handle key event, returns true if dialog should close
+    pub fn handle_key(&mut self, key: KeyEvent) -> bool {
+        match key.code {
+            KeyCode::Down | KeyCode::Char('j') => self.scroll = self.scroll.saturating_add(1),
+            KeyCode::Up | KeyCode::Char('k') => self.scroll = self.scroll.saturating_sub(1),
+            KeyCode::PageDown => self.scroll = self.scroll.saturating_add(10),
+            KeyCode::PageUp => self.scroll = self.scroll.saturating_sub(10),
+            KeyCode::Home | KeyCode::Char('g') => self.scroll = 0,
+            KeyCode::Esc | KeyCode::Enter | KeyCode::Char('q') => return true,
+            _ => {}
+        }
+        false
+    }
+
+    fn lines(&self, theme: &Theme) -> Vec<Line<'static>> {
+        let mut lines = Vec::new();
+        for release in &self.update.releases {
+            let mut heading = vec![Span::styled(
+                release.tag_name.clone(),
+                theme.accent().add_modifier(Modifier::BOLD),
+            )];
+            if let Some(name) = release.name.as_ref().filter(|name| !name.is_empty() && **name != release.tag_name) {
+                heading.push(Span::raw(format!("  {}", name)));
+            }
+            if let Some(published) = release.published_at {
+                heading.push(Span::styled(format!("  {}", self.formats.date_time(&published)), theme.dim()));
+            }
+            lines.push(Line::from(heading));
+            match release.body.as_deref().map(str::trim).filter(|body| !body.is_empty()) {
+                Some(body) => lines.extend(body.lines().map(|line| Line::from(line.trim_end().to_string()))),
+                None => lines.push(Line::styled("No release notes.", theme.dim())),
+            }
+            if !release.html_url.is_empty() {
+                lines.push(Line::styled(release.html_url.clone(), theme.dim()));
+            }
+            lines.push(Line::default());
+        }
+        lines
+    }
+
+    pub fn render(&self, frame: &mut Frame, theme: &Theme) {
+        let area = frame.area();
+        let dialog_area = DialogLayout::centered(area, 90, 24).dialog;
+
+        frame.render_widget(Clear, dialog_area);
+
+        let block = Block::default()
+            .title(format!(" What's new since {} ", CURRENT_VERSION))
+            .borders(Borders::ALL)
+            .border_style(theme.border_focused())
+            .style(theme.normal());
+
+        let inner = block.inner(dialog_area);
+        frame.render_widget(block, dialog_area);
+
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .margin(1)
+            .constraints([
+                Constraint::Min(3),    // Release notes
+                Constraint::Length(1), // Hints
+            ])
+            .split(inner);
+
+        frame.render_widget(
+            Paragraph::new(self.lines(theme))
+                .style(theme.normal())
+                .wrap(Wrap { trim: false })
+                .scroll((self.scroll, 0)),
+            chunks[0],
+        );
+        frame.render_widget(
+            Paragraph::new(format!(
+                "↑↓=scroll  Esc=close  checked {}",
+                self.formats.date_time(&self.update.checked)
+            ))
+            .style(theme.dim()),
+            chunks[1],
+        );
+    }
+}
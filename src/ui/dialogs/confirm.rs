@@ -11,6 +11,7 @@ use ratatui::{
 
 use crate::ui::layout::DialogLayout;
 use crate::ui::theme::Theme;
+use crate::ui::widgets::clickable::{span_areas, ClickTargets};
 
 pub struct ConfirmDialog {
     pub title: String,
@@ -19,6 +20,7 @@ pub struct ConfirmDialog {
     pub cancel_label: String,
     pub selected: bool, // true = confirm selected
     pub result: Option<bool>,
+    buttons: ClickTargets,
 }
 
 impl ConfirmDialog {
@@ -30,6 +32,7 @@ impl ConfirmDialog {
             cancel_label: "No".to_string(),
             selected: false,
             result: None,
+            buttons: ClickTargets::default(),
         }
     }
 
@@ -61,6 +64,11 @@ impl ConfirmDialog {
         false
     }
 
+    /// Key of the button at a clicked position
+    pub fn click(&self, column: u16, row: u16) -> Option<KeyEvent> {
+        self.buttons.key_at(column, row)
+    }
+
     pub fn render(&self, frame: &mut Frame, theme: &Theme) {
         let area = frame.area();
         let dialog_area = DialogLayout::centered(area, 50, 10).dialog;
@@ -111,6 +119,11 @@ impl ConfirmDialog {
             Span::styled(format!("[ {} ]", self.cancel_label), no_style),
         ]);
 
+        let areas = span_areas(chunks[1], &buttons.spans);
+        self.buttons.clear();
+        self.buttons.add(areas[1], KeyCode::Char('y'));
+        self.buttons.add(areas[3], KeyCode::Char('n'));
+
         let button_para = Paragraph::new(buttons);
         frame.render_widget(button_para, chunks[1]);
     }
@@ -1,5 +1,6 @@
 //! Connection details dialog with blocking capability
 
+use chrono::DateTime;
 use crossterm::event::{KeyCode, KeyEvent};
 use ratatui::{
     layout::{Constraint, Direction, Layout, Rect},
@@ -8,12 +9,9 @@ use ratatui::{
     widgets::{Block, Borders, Clear, List, ListItem, Paragraph},
     Frame,
 };
-use tokio::sync::mpsc;
-
-use crate::app::state::AppMessage;
-use crate::grpc::notifications::NotificationAction;
 use crate::models::{Event, Operator, Rule, RuleAction, RuleDuration};
 use crate::ui::theme::Theme;
+use crate::utils::{process, text, Formats};
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 enum DetailsFocus {
@@ -26,6 +24,7 @@ enum ActionItem {
     BlockProcess,
     BlockDestination,
     BlockPort,
+    SandboxProcess,
     AllowProcess,
     Close,
 }
@@ -36,27 +35,39 @@ impl ActionItem {
             ActionItem::BlockProcess,
             ActionItem::BlockDestination,
             ActionItem::BlockPort,
+            ActionItem::SandboxProcess,
             ActionItem::AllowProcess,
             ActionItem::Close,
         ]
     }
 
-    fn label(&self) -> &'static str {
+    fn label(&self, sandbox: &RuleDuration) -> String {
         match self {
-            ActionItem::BlockProcess => "Block this process",
-            ActionItem::BlockDestination => "Block this destination",
-            ActionItem::BlockPort => "Block this port",
-            ActionItem::AllowProcess => "Always allow this process",
-            ActionItem::Close => "Close",
+            ActionItem::BlockProcess => "Block this process".to_string(),
+            ActionItem::BlockDestination => "Block this destination".to_string(),
+            ActionItem::BlockPort => "Block this port".to_string(),
+            ActionItem::SandboxProcess => format!("No network for {}", sandbox),
+            ActionItem::AllowProcess => "Always allow this process".to_string(),
+            ActionItem::Close => "Close".to_string(),
         }
     }
 }
 
+/// Outcome of the details dialog
+pub enum DetailsResult {
+    Close,
+    /// Rule created from one of the block/allow actions
+    CreateRule(Rule),
+}
+
 pub struct ConnectionDetailsDialog {
     event: Event,
     focus: DetailsFocus,
     action_index: usize,
     scroll_offset: u16,
+    /// How long the sandbox action cuts the process off
+    sandbox_duration: RuleDuration,
+    formats: Formats,
 }
 
 impl ConnectionDetailsDialog {
@@ -66,17 +77,27 @@ impl ConnectionDetailsDialog {
             focus: DetailsFocus::Info,
             action_index: 0,
             scroll_offset: 0,
+            sandbox_duration: RuleDuration::ThirtyMinutes,
+            formats: Formats::default(),
         }
     }
 
-    pub fn handle_key(
-        &mut self,
-        key: KeyEvent,
-        state_tx: &mpsc::Sender<AppMessage>,
-        node_addr: Option<&str>,
-    ) -> bool {
+    /// How long the sandbox action cuts the process off
+    pub fn with_sandbox_duration(mut self, duration: RuleDuration) -> Self {
+        self.sandbox_duration = duration;
+        self
+    }
+
+    /// Number and date formatting
+    pub fn with_formats(mut self, formats: Formats) -> Self {
+        self.formats = formats;
+        self
+    }
+
+    /// Handle key event, returns a result once the dialog should close
+    pub fn handle_key(&mut self, key: KeyEvent) -> Option<DetailsResult> {
         match key.code {
-            KeyCode::Esc | KeyCode::Char('q') => return true,
+            KeyCode::Esc | KeyCode::Char('q') => return Some(DetailsResult::Close),
             KeyCode::Tab => {
                 self.focus = match self.focus {
                     DetailsFocus::Info => DetailsFocus::Actions,
@@ -104,29 +125,15 @@ impl ConnectionDetailsDialog {
             KeyCode::Enter => {
                 if self.focus == DetailsFocus::Actions {
                     let action = ActionItem::all()[self.action_index];
-                    if action == ActionItem::Close {
-                        return true;
-                    }
-                    if let Some(addr) = node_addr {
-                        if let Some(rule) = self.create_rule(action) {
-                            // Update local state
-                            let _ = state_tx.try_send(AppMessage::RuleAdded {
-                                node_addr: addr.to_string(),
-                                rule: rule.clone(),
-                            });
-                            // Send to daemon
-                            let _ = state_tx.try_send(AppMessage::SendNotification {
-                                node_addr: addr.to_string(),
-                                action: NotificationAction::ChangeRule(rule),
-                            });
-                        }
-                    }
-                    return true;
+                    return Some(match self.create_rule(action) {
+                        Some(rule) => DetailsResult::CreateRule(rule),
+                        None => DetailsResult::Close,
+                    });
                 }
             }
             _ => {}
         }
-        false
+        None
     }
 
     fn create_rule(&self, action: ActionItem) -> Option<Rule> {
@@ -165,6 +172,7 @@ impl ConnectionDetailsDialog {
                     Operator::simple("dest.port", &conn.dst_port.to_string()),
                 ))
             }
+            ActionItem::SandboxProcess => Some(Rule::sandbox(&conn.process_path, self.sandbox_duration.clone())),
             ActionItem::AllowProcess => {
                 let name = format!("allow-{}", conn.process_name());
                 Some(Rule::new(
@@ -218,14 +226,23 @@ impl ConnectionDetailsDialog {
             "PROCESS",
             Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD),
         )));
-        lines.push(Line::from(format!("  Path: {}", conn.process_path)));
+        let f = &self.formats;
+        // Fields the daemon left out, read by the TUI afterwards
+        let field = |label: &str, value: String, name: &str| {
+            let mut spans = vec![Span::raw(format!("  {}{}", label, value))];
+            if conn.enriched.iter().any(|e| e == name) {
+                spans.push(Span::styled("  (from /proc)", theme.dim()));
+            }
+            Line::from(spans)
+        };
+        lines.push(field("Path: ", f.path(&conn.process_path).into_owned(), process::ENRICHED_PATH));
         lines.push(Line::from(format!("  Name: {}", conn.process_name())));
         lines.push(Line::from(format!("  PID:  {}", conn.process_id)));
         lines.push(Line::from(format!("  UID:  {}", conn.user_id)));
-        lines.push(Line::from(format!("  CWD:  {}", conn.process_cwd)));
+        lines.push(field("CWD:  ", f.path(&conn.process_cwd).into_owned(), process::ENRICHED_CWD));
 
         if !conn.process_args.is_empty() {
-            lines.push(Line::from(format!("  Args: {}", conn.process_args.join(" "))));
+            lines.push(field("Args: ", f.text(&conn.process_args.join(" ")).into_owned(), process::ENRICHED_ARGS));
         }
 
         lines.push(Line::from(""));
@@ -236,12 +253,18 @@ impl ConnectionDetailsDialog {
             Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD),
         )));
         lines.push(Line::from(format!("  Protocol: {}", conn.protocol)));
-        lines.push(Line::from(format!("  Source:   {}:{}", conn.src_ip, conn.src_port)));
+        lines.push(Line::from(format!("  Source:   {}:{}", f.host(&conn.src_ip), conn.src_port)));
+        if !conn.src_network.is_empty() {
+            lines.push(Line::from(format!("  Network:  {}", conn.src_network)));
+        }
+        if !conn.interface.is_empty() {
+            lines.push(Line::from(format!("  Iface:    {}", conn.interface)));
+        }
 
         let dest = if !conn.dst_host.is_empty() {
-            format!("{} ({})", conn.dst_host, conn.dst_ip)
+            format!("{} ({})", f.host(&conn.dst_host), f.host(&conn.dst_ip))
         } else {
-            conn.dst_ip.clone()
+            f.host(&conn.dst_ip).into_owned()
         };
         lines.push(Line::from(format!("  Dest:     {}:{}", dest, conn.dst_port)));
 
@@ -268,10 +291,10 @@ impl ConnectionDetailsDialog {
             let important_vars = ["PATH", "HOME", "USER", "SHELL", "DISPLAY", "TERM"];
             for var in important_vars {
                 if let Some(val) = conn.process_env.get(var) {
-                    let truncated = if val.len() > 50 {
-                        format!("{}...", &val[..47])
+                    let truncated = if f.is_private() {
+                        f.text(val).into_owned()
                     } else {
-                        val.clone()
+                        text::elide(val, 50)
                     };
                     lines.push(Line::from(format!("  {}={}", var, truncated)));
                 }
@@ -284,7 +307,10 @@ impl ConnectionDetailsDialog {
             "TIMESTAMP",
             Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD),
         )));
-        lines.push(Line::from(format!("  {}", self.event.time)));
+        let time = DateTime::parse_from_rfc3339(&self.event.time)
+            .map(|t| self.formats.date_time(&t))
+            .unwrap_or_else(|_| self.event.time.clone());
+        lines.push(Line::from(format!("  {}", time)));
 
         // Apply scroll offset
         let visible_lines: Vec<Line> = lines
@@ -322,11 +348,12 @@ impl ConnectionDetailsDialog {
                         ActionItem::BlockProcess | ActionItem::BlockDestination | ActionItem::BlockPort => {
                             Style::default().fg(Color::Red)
                         }
+                        ActionItem::SandboxProcess => Style::default().fg(Color::Yellow),
                         ActionItem::AllowProcess => Style::default().fg(Color::Green),
                         ActionItem::Close => theme.normal(),
                     }
                 };
-                ListItem::new(action.label()).style(style)
+                ListItem::new(action.label(&self.sandbox_duration)).style(style)
             })
             .collect();
 
@@ -0,0 +1,106 @@
+//! Copy rules picked on one node to another connected node
+
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    layout::{Constraint, Direction, Layout},
+    widgets::{Block, Borders, Cell, Clear, Paragraph, Row, Table, TableState, Wrap},
+    Frame,
+};
+
+use crate::models::Rule;
+use crate::ui::layout::DialogLayout;
+use crate::ui::theme::Theme;
+
+/// Outcome of the dialog
+pub enum CopyRulesResult {
+    Copy { node_addr: String, rules: Vec<Rule> },
+    Close,
+}
+
+pub struct CopyRulesDialog {
+    rules: Vec<Rule>,
+    /// Nodes to copy to, address and name
+    nodes: Vec<(String, String)>,
+    selected: usize,
+}
+
+impl CopyRulesDialog {
+    pub fn new(rules: Vec<Rule>, nodes: Vec<(String, String)>) -> Self {
+        Self { rules, nodes, selected: 0 }
+    }
+
+    /// Handle key event, returns a result once the dialog should close
+    pub fn handle_key(&mut self, key: KeyEvent) -> Option<CopyRulesResult> {
+        match key.code {
+            KeyCode::Down | KeyCode::Char('j') => {
+                self.selected = (self.selected + 1).min(self.nodes.len().saturating_sub(1));
+            }
+            KeyCode::Up | KeyCode::Char('k') => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Enter => {
+                let (node_addr, _) = self.nodes.get(self.selected)?;
+                return Some(CopyRulesResult::Copy { node_addr: node_addr.clone(), rules: self.rules.clone() });
+            }
+            KeyCode::Esc | KeyCode::Char('q') => return Some(CopyRulesResult::Close),
+            _ => {}
+        }
+        None
+    }
+
+    pub fn render(&self, frame: &mut Frame, theme: &Theme) {
+        let area = frame.area();
+        let dialog_area = DialogLayout::centered(area, 70, 16).dialog;
+
+        frame.render_widget(Clear, dialog_area);
+
+        let title = match self.rules.len() {
+            1 => " Copy 1 rule to node ".to_string(),
+            n => format!(" Copy {} rules to node ", n),
+        };
+        let block = Block::default()
+            .title(title)
+            .borders(Borders::ALL)
+            .border_style(theme.border_focused())
+            .style(theme.normal());
+
+        let inner = block.inner(dialog_area);
+        frame.render_widget(block, dialog_area);
+
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .margin(1)
+            .constraints([
+                Constraint::Length(2), // Rules
+                Constraint::Min(3),    // Nodes
+                Constraint::Length(1), // Hints
+            ])
+            .split(inner);
+
+        let names: Vec<_> = self.rules.iter().map(|r| r.name.as_str()).collect();
+        frame.render_widget(
+            Paragraph::new(names.join(", ")).style(theme.dim()).wrap(Wrap { trim: true }),
+            chunks[0],
+        );
+
+        if self.nodes.is_empty() {
+            frame.render_widget(
+                Paragraph::new("No other node is connected to copy to.").style(theme.dim()),
+                chunks[1],
+            );
+        } else {
+            let rows = self
+                .nodes
+                .iter()
+                .map(|(addr, name)| Row::new(vec![Cell::from(name.clone()), Cell::from(addr.clone()).style(theme.dim())]));
+            let table = Table::new(rows, [Constraint::Percentage(40), Constraint::Percentage(60)])
+                .row_highlight_style(theme.selected())
+                .highlight_symbol("▶ ");
+            let mut state = TableState::default().with_selected(Some(self.selected));
+            frame.render_stateful_widget(table, chunks[1], &mut state);
+        }
+
+        frame.render_widget(
+            Paragraph::new("Enter = copy, replacing rules of the same name  Esc = cancel").style(theme.dim()),
+            chunks[2],
+        );
+    }
+}
@@ -0,0 +1,308 @@
+//! Firewall chain editor dialog
+//!
+//! Creates nftables chains, or changes the policy of an existing one. A
+//! chain's name, table, family, hook and priority identify it to the
+//! daemon, so changing those means deleting it and creating another.
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::{
+    layout::{Constraint, Direction, Layout},
+    style::{Color, Modifier, Style},
+    widgets::{Block, Borders, Clear, Paragraph, Wrap},
+    Frame,
+};
+
+use crate::models::FwChain;
+use crate::ui::layout::DialogLayout;
+use crate::ui::theme::Theme;
+use crate::utils::text;
+
+const FAMILIES: [&str; 3] = ["inet", "ip", "ip6"];
+const TYPES: [&str; 3] = ["filter", "mangle", "nat"];
+/// An empty hook makes a regular chain, only reached by jumps
+const HOOKS: [&str; 6] = ["input", "output", "forward", "prerouting", "postrouting", ""];
+const POLICIES: [&str; 2] = ["accept", "drop"];
+
+/// Which field is focused
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FwChainField {
+    Name,
+    Table,
+    Family,
+    Type,
+    Hook,
+    Priority,
+    Policy,
+}
+
+impl FwChainField {
+    const ALL: [Self; 7] = [
+        Self::Name,
+        Self::Table,
+        Self::Family,
+        Self::Type,
+        Self::Hook,
+        Self::Priority,
+        Self::Policy,
+    ];
+
+    fn label(self) -> &'static str {
+        match self {
+            Self::Name => "Name",
+            Self::Table => "Table",
+            Self::Family => "Family",
+            Self::Type => "Type",
+            Self::Hook => "Hook",
+            Self::Priority => "Priority",
+            Self::Policy => "Policy",
+        }
+    }
+
+    fn is_text(self) -> bool {
+        matches!(self, Self::Name | Self::Table | Self::Priority)
+    }
+}
+
+/// Chain editor result
+pub enum FwChainEditorResult {
+    Save(FwChain),
+    Cancel,
+}
+
+pub struct FwChainEditorDialog {
+    chain: FwChain,
+    /// Editing an existing chain, only its policy can change
+    existing: bool,
+    /// Chains already there, a name is only taken within the same table
+    taken: Vec<FwChain>,
+    pub focus: FwChainField,
+    editing_text: bool,
+    cursor_pos: usize,
+    error: Option<String>,
+}
+
+impl FwChainEditorDialog {
+    /// New input chain in the filter table, `taken` holding the existing chains
+    pub fn new(taken: Vec<FwChain>) -> Self {
+        Self {
+            chain: FwChain::new("", "filter", "input"),
+            existing: false,
+            taken,
+            focus: FwChainField::Name,
+            editing_text: false,
+            cursor_pos: 0,
+            error: None,
+        }
+    }
+
+    /// Change the policy of an existing chain
+    pub fn edit(chain: &FwChain) -> Self {
+        Self {
+            chain: chain.clone(),
+            existing: true,
+            taken: Vec::new(),
+            focus: FwChainField::Policy,
+            editing_text: false,
+            cursor_pos: 0,
+            error: None,
+        }
+    }
+
+    fn editable(&self, field: FwChainField) -> bool {
+        !self.existing || field == FwChainField::Policy
+    }
+
+    fn step_focus(&mut self, forward: bool) {
+        let fields: Vec<_> = FwChainField::ALL.into_iter().filter(|f| self.editable(*f)).collect();
+        let current = fields.iter().position(|f| *f == self.focus).unwrap_or(0);
+        let next = if forward { current + 1 } else { current + fields.len() - 1 };
+        self.focus = fields[next % fields.len()];
+    }
+
+    fn value(&self, field: FwChainField) -> &str {
+        match field {
+            FwChainField::Name => &self.chain.name,
+            FwChainField::Table => &self.chain.table,
+            FwChainField::Family => &self.chain.family,
+            FwChainField::Type => &self.chain.chain_type,
+            FwChainField::Hook => &self.chain.hook,
+            FwChainField::Priority => &self.chain.priority,
+            FwChainField::Policy => &self.chain.policy,
+        }
+    }
+
+    fn value_mut(&mut self, field: FwChainField) -> &mut String {
+        match field {
+            FwChainField::Name => &mut self.chain.name,
+            FwChainField::Table => &mut self.chain.table,
+            FwChainField::Family => &mut self.chain.family,
+            FwChainField::Type => &mut self.chain.chain_type,
+            FwChainField::Hook => &mut self.chain.hook,
+            FwChainField::Priority => &mut self.chain.priority,
+            FwChainField::Policy => &mut self.chain.policy,
+        }
+    }
+
+    /// Move a choice field to the next or previous value
+    fn cycle(&mut self, forward: bool) {
+        let choices: &[&str] = match self.focus {
+            FwChainField::Family => &FAMILIES,
+            FwChainField::Type => &TYPES,
+            FwChainField::Hook => &HOOKS,
+            FwChainField::Policy => &POLICIES,
+            _ => return,
+        };
+        let current = choices.iter().position(|c| c.eq_ignore_ascii_case(self.value(self.focus))).unwrap_or(0);
+        let next = if forward { current + 1 } else { current + choices.len() - 1 };
+        *self.value_mut(self.focus) = choices[next % choices.len()].to_string();
+    }
+
+    /// Why the chain can't be saved as it is
+    fn validate(&self) -> Result<(), String> {
+        let chain = &self.chain;
+        if chain.name.trim().is_empty() {
+            return Err("the chain needs a name".to_string());
+        }
+        if chain.table.trim().is_empty() {
+            return Err("the chain needs a table".to_string());
+        }
+        if self.taken.iter().any(|c| c.same_chain(chain)) {
+            return Err(format!("a chain named {} exists in {} {} already", chain.name, chain.family, chain.table));
+        }
+        if chain.priority.trim().parse::<i32>().is_err() {
+            return Err(format!("priority {} is not a number", chain.priority));
+        }
+        Ok(())
+    }
+
+    fn save(&mut self) -> Option<FwChainEditorResult> {
+        match self.validate() {
+            Ok(()) => {
+                let mut chain = self.chain.clone();
+                chain.priority = chain.priority.trim().to_string();
+                Some(FwChainEditorResult::Save(chain))
+            }
+            Err(e) => {
+                self.error = Some(e);
+                None
+            }
+        }
+    }
+
+    pub fn handle_key(&mut self, key: KeyEvent) -> Option<FwChainEditorResult> {
+        if self.editing_text {
+            self.handle_text_input(key);
+            return None;
+        }
+        self.error = None;
+
+        match key.code {
+            KeyCode::Tab | KeyCode::Down => self.step_focus(true),
+            KeyCode::BackTab | KeyCode::Up => self.step_focus(false),
+            KeyCode::Enter if self.focus.is_text() => {
+                self.editing_text = true;
+                self.cursor_pos = self.value(self.focus).len();
+            }
+            KeyCode::Enter | KeyCode::Char(' ') | KeyCode::Right => self.cycle(true),
+            KeyCode::Left => self.cycle(false),
+            KeyCode::F(2) => return self.save(),
+            KeyCode::Char('s') if key.modifiers.contains(KeyModifiers::CONTROL) => return self.save(),
+            KeyCode::Esc => return Some(FwChainEditorResult::Cancel),
+            _ => {}
+        }
+        None
+    }
+
+    fn handle_text_input(&mut self, key: KeyEvent) {
+        let field = self.focus;
+        match key.code {
+            KeyCode::Esc | KeyCode::Enter => self.editing_text = false,
+            KeyCode::Char(c) if !c.is_whitespace() => {
+                let mut cursor = self.cursor_pos.min(self.value(field).len());
+                text::insert(self.value_mut(field), &mut cursor, c);
+                self.cursor_pos = cursor;
+            }
+            KeyCode::Backspace => {
+                let mut cursor = self.cursor_pos;
+                text::backspace(self.value_mut(field), &mut cursor);
+                self.cursor_pos = cursor;
+            }
+            KeyCode::Delete => {
+                let cursor = self.cursor_pos;
+                text::delete(self.value_mut(field), cursor);
+            }
+            KeyCode::Left => self.cursor_pos = text::prev_boundary(self.value(field), self.cursor_pos),
+            KeyCode::Right => self.cursor_pos = text::next_boundary(self.value(field), self.cursor_pos),
+            KeyCode::Home => self.cursor_pos = 0,
+            KeyCode::End => self.cursor_pos = self.value(field).len(),
+            _ => {}
+        }
+    }
+
+    pub fn render(&self, frame: &mut Frame, theme: &Theme) {
+        let area = frame.area();
+        let dialog_area = DialogLayout::centered(area, 60, 16).dialog;
+
+        frame.render_widget(Clear, dialog_area);
+
+        let title = if self.existing {
+            format!(" Chain {} ", self.chain.name)
+        } else {
+            " Create Firewall Chain ".to_string()
+        };
+        let block = Block::default()
+            .title(title)
+            .borders(Borders::ALL)
+            .border_style(theme.border_focused())
+            .style(theme.normal());
+
+        let inner = block.inner(dialog_area);
+        frame.render_widget(block, dialog_area);
+
+        let mut constraints = vec![Constraint::Length(1); FwChainField::ALL.len()];
+        constraints.push(Constraint::Length(1)); // Separator
+        constraints.push(Constraint::Min(1)); // Hints
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .margin(1)
+            .constraints(constraints)
+            .split(inner);
+
+        for (i, field) in FwChainField::ALL.into_iter().enumerate() {
+            let value = self.value(field);
+            let shown = if field.is_text() {
+                value.to_string()
+            } else if field == FwChainField::Hook && value.is_empty() {
+                "◄ none (regular chain) ►".to_string()
+            } else {
+                format!("◄ {} ►", value)
+            };
+            let style = if self.focus == field {
+                if self.editing_text {
+                    Style::default().fg(Color::Yellow).add_modifier(Modifier::UNDERLINED)
+                } else {
+                    Style::default().add_modifier(Modifier::REVERSED)
+                }
+            } else if !self.editable(field) {
+                theme.dim()
+            } else if field == FwChainField::Policy {
+                theme.action_style(value)
+            } else {
+                theme.normal()
+            };
+            let text = format!("{:10} {}", format!("{}:", field.label()), shown);
+            frame.render_widget(Paragraph::new(text).style(style), chunks[i]);
+        }
+
+        let separator = FwChainField::ALL.len();
+        frame.render_widget(Paragraph::new("─".repeat(50)).style(theme.dim()), chunks[separator]);
+
+        let hints = match &self.error {
+            Some(e) => Paragraph::new(format!("⚠ {}", e)).style(Style::default().fg(Color::Red)),
+            None if self.editing_text => Paragraph::new("Enter/Esc=done  ←→=cursor").style(theme.dim()),
+            None => Paragraph::new("Tab/↑↓=navigate  Enter=edit  ←→/Space=change  F2/Ctrl+S=save  Esc=cancel")
+                .style(theme.dim()),
+        };
+        frame.render_widget(hints.wrap(Wrap { trim: true }), chunks[separator + 1]);
+    }
+}
@@ -4,13 +4,16 @@ use crossterm::event::{KeyCode, KeyEvent};
 use ratatui::{
     layout::{Constraint, Direction, Layout},
     style::{Color, Modifier, Style},
+    text::Line,
     widgets::{Block, Borders, Clear, Paragraph, Wrap},
     Frame,
 };
 
-use crate::models::{FwRule, Expression, Statement, StatementValue};
+use crate::models::{DaemonVersion, Feature, FwRule, Expression, Statement, StatementValue};
 use crate::ui::layout::DialogLayout;
 use crate::ui::theme::Theme;
+use crate::ui::widgets::textarea::TextArea;
+use crate::utils::text;
 
 /// Editor mode
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -30,6 +33,7 @@ pub enum FwEditorFocus {
     SourcePort,
     DestIp,
     DestPort,
+    Expressions,
 }
 
 impl FwEditorFocus {
@@ -42,13 +46,14 @@ impl FwEditorFocus {
             Self::SourceIp => Self::SourcePort,
             Self::SourcePort => Self::DestIp,
             Self::DestIp => Self::DestPort,
-            Self::DestPort => Self::Description,
+            Self::DestPort => Self::Expressions,
+            Self::Expressions => Self::Description,
         }
     }
 
     fn prev(self) -> Self {
         match self {
-            Self::Description => Self::DestPort,
+            Self::Description => Self::Expressions,
             Self::Target => Self::Description,
             Self::Enabled => Self::Target,
             Self::Protocol => Self::Enabled,
@@ -56,10 +61,31 @@ impl FwEditorFocus {
             Self::SourcePort => Self::SourceIp,
             Self::DestIp => Self::SourcePort,
             Self::DestPort => Self::DestIp,
+            Self::Expressions => Self::DestPort,
         }
     }
 }
 
+/// Most expression rows shown before the list scrolls
+const EXPRESSION_ROWS: usize = 6;
+
+/// An expression being typed on one line, see `Statement::parse`
+struct ExpressionInput {
+    /// Row being replaced, or None when adding
+    index: Option<usize>,
+    text: String,
+    cursor: usize,
+    error: Option<String>,
+}
+
+/// A lone `==` value with the plain key, which the form has a field for
+fn simple_value(stmt: &Statement) -> Option<&str> {
+    match stmt.values.as_slice() {
+        [v] if (stmt.op == "==" || stmt.op.is_empty()) && v.key == "value" => Some(&v.value),
+        _ => None,
+    }
+}
+
 /// Firewall rule editor result
 pub enum FwRuleEditorResult {
     Save(FwRule),
@@ -84,9 +110,23 @@ pub struct FwRuleEditorDialog {
 
     // Original UUID for edits
     pub original_uuid: Option<String>,
+    /// Statements the basic fields can't express, edited one per row
+    pub expressions: Vec<Expression>,
+    expr_selected: usize,
+    expr_input: Option<ExpressionInput>,
     pub position: u64,
 
     cursor_pos: usize,
+
+    /// Show the JSON preview pane
+    pub show_preview: bool,
+
+    /// Raw JSON editor, when active
+    raw_editor: Option<TextArea>,
+    raw_error: Option<String>,
+
+    /// Version of the node the rule is sent to, for feature gating
+    daemon_version: Option<DaemonVersion>,
 }
 
 impl FwRuleEditorDialog {
@@ -104,8 +144,15 @@ impl FwRuleEditorDialog {
             dest_ip: String::new(),
             dest_port: String::new(),
             original_uuid: None,
+            expressions: Vec::new(),
+            expr_selected: 0,
+            expr_input: None,
             position: 0,
             cursor_pos: 0,
+            show_preview: false,
+            raw_editor: None,
+            raw_error: None,
+            daemon_version: None,
         }
     }
 
@@ -116,36 +163,22 @@ impl FwRuleEditorDialog {
         let mut source_port = String::new();
         let mut dest_ip = String::new();
         let mut dest_port = String::new();
+        let mut expressions = Vec::new();
 
         for expr in &rule.expressions {
             let stmt = &expr.statement;
-            match stmt.name.as_str() {
-                "protocol" => {
-                    if let Some(v) = stmt.values.first() {
-                        protocol = v.value.clone();
-                    }
-                }
-                "saddr" => {
-                    if let Some(v) = stmt.values.first() {
-                        source_ip = v.value.clone();
-                    }
-                }
-                "sport" => {
-                    if let Some(v) = stmt.values.first() {
-                        source_port = v.value.clone();
-                    }
-                }
-                "daddr" => {
-                    if let Some(v) = stmt.values.first() {
-                        dest_ip = v.value.clone();
-                    }
-                }
-                "dport" => {
-                    if let Some(v) = stmt.values.first() {
-                        dest_port = v.value.clone();
-                    }
-                }
-                _ => {}
+            let field = match stmt.name.as_str() {
+                "protocol" => Some(&mut protocol),
+                "saddr" => Some(&mut source_ip),
+                "sport" => Some(&mut source_port),
+                "daddr" => Some(&mut dest_ip),
+                "dport" => Some(&mut dest_port),
+                _ => None,
+            };
+            // Negations, ranges of keys and sets stay in the expression list
+            match (field, simple_value(stmt)) {
+                (Some(field), Some(value)) if field.is_empty() => *field = value.to_string(),
+                _ => expressions.push(expr.clone()),
             }
         }
 
@@ -162,8 +195,15 @@ impl FwRuleEditorDialog {
             dest_ip,
             dest_port,
             original_uuid: Some(rule.uuid.clone()),
+            expressions,
+            expr_selected: 0,
+            expr_input: None,
             position: rule.position,
             cursor_pos: 0,
+            show_preview: false,
+            raw_editor: None,
+            raw_error: None,
+            daemon_version: None,
         }
     }
 
@@ -240,6 +280,8 @@ impl FwRuleEditorDialog {
             });
         }
 
+        expressions.extend(self.expressions.iter().cloned());
+
         FwRule {
             uuid: self.original_uuid.clone().unwrap_or_else(|| uuid::Uuid::new_v4().to_string()),
             enabled: self.enabled,
@@ -251,22 +293,86 @@ impl FwRuleEditorDialog {
         }
     }
 
+    /// Gate statements on the target node's daemon version
+    pub fn with_daemon_version(mut self, version: Option<DaemonVersion>) -> Self {
+        self.daemon_version = version;
+        self
+    }
+
+    /// Why the target node can't honor this rule, if it can't
+    pub fn compat_warning(&self, rule: &FwRule) -> Option<String> {
+        let version = self.daemon_version?;
+        rule.expressions
+            .iter()
+            .filter_map(|e| Feature::for_statement(&e.statement.name))
+            .find(|f| !f.supported_by(Some(version)))
+            .map(|f| f.unsupported_message(version))
+    }
+
+    /// JSON for the current fields, as saved to the firewall config
+    fn preview_json(&self) -> String {
+        serde_json::to_string_pretty(&self.build_rule())
+            .unwrap_or_else(|e| format!("Failed to serialize rule: {}", e))
+    }
+
+    /// Parse the raw editor contents, keeping this rule's uuid if none is given
+    fn parse_raw(&self, text: &str) -> Result<FwRule, String> {
+        let mut rule: FwRule = serde_json::from_str(text).map_err(|e| e.to_string())?;
+        if rule.uuid.is_empty() {
+            rule.uuid = self.original_uuid.clone().unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+        }
+        match self.compat_warning(&rule) {
+            Some(warning) => Err(warning),
+            None => Ok(rule),
+        }
+    }
+
     pub fn handle_key(&mut self, key: KeyEvent) -> Option<FwRuleEditorResult> {
+        if self.raw_editor.is_some() {
+            return self.handle_raw_input(key);
+        }
+
+        if self.expr_input.is_some() {
+            self.handle_expression_input(key);
+            return None;
+        }
+
         if self.editing_text {
             return self.handle_text_input(key);
         }
 
+        let in_list = self.focus == FwEditorFocus::Expressions;
         match key.code {
+            KeyCode::Down if in_list && self.expr_selected + 1 < self.expressions.len() => {
+                self.expr_selected += 1;
+            }
+            KeyCode::Up if in_list && self.expr_selected > 0 => {
+                self.expr_selected -= 1;
+            }
             KeyCode::Tab | KeyCode::Down => {
                 self.focus = self.focus.next();
+                self.expr_selected = 0;
             }
             KeyCode::BackTab | KeyCode::Up => {
                 self.focus = self.focus.prev();
+                self.expr_selected = self.expressions.len().saturating_sub(1);
+            }
+            KeyCode::Char('a') => {
+                self.focus = FwEditorFocus::Expressions;
+                self.open_expression(None);
+            }
+            KeyCode::Char('d') | KeyCode::Delete if in_list && self.expr_selected < self.expressions.len() => {
+                self.expressions.remove(self.expr_selected);
+                self.expr_selected = self.expr_selected.min(self.expressions.len().saturating_sub(1));
             }
             KeyCode::Enter => {
                 match self.focus {
                     FwEditorFocus::Enabled => self.enabled = !self.enabled,
                     FwEditorFocus::Target => self.cycle_target(true),
+                    FwEditorFocus::Expressions => {
+                        let index = (self.expr_selected < self.expressions.len()).then_some(self.expr_selected);
+                        self.open_expression(index);
+                    }
                     _ => {
                         self.editing_text = true;
                         self.cursor_pos = self.current_text().len();
@@ -285,59 +391,146 @@ impl FwRuleEditorDialog {
                     _ => {}
                 }
             }
+            KeyCode::Char('p') => {
+                self.show_preview = !self.show_preview;
+            }
+            KeyCode::Char('r') => {
+                self.raw_editor = Some(TextArea::new(&self.preview_json()));
+                self.raw_error = None;
+            }
             KeyCode::Esc => {
                 return Some(FwRuleEditorResult::Cancel);
             }
             KeyCode::F(2) => {
-                // Save
-                return Some(FwRuleEditorResult::Save(self.build_rule()));
+                return self.save_form();
             }
             KeyCode::Char('s') if key.modifiers.contains(crossterm::event::KeyModifiers::CONTROL) => {
-                return Some(FwRuleEditorResult::Save(self.build_rule()));
+                return self.save_form();
             }
             _ => {}
         }
         None
     }
 
+    fn handle_raw_input(&mut self, key: KeyEvent) -> Option<FwRuleEditorResult> {
+        let Some(editor) = &mut self.raw_editor else {
+            return None;
+        };
+
+        match key.code {
+            KeyCode::Esc => {
+                // Back to the structured form, discarding raw edits
+                self.raw_editor = None;
+                self.raw_error = None;
+            }
+            KeyCode::F(2) => {
+                return self.save_raw();
+            }
+            KeyCode::Char('s') if key.modifiers.contains(crossterm::event::KeyModifiers::CONTROL) => {
+                return self.save_raw();
+            }
+            _ => {
+                if editor.handle_key(key) {
+                    let text = editor.text();
+                    self.raw_error = self.parse_raw(&text).err();
+                }
+            }
+        }
+        None
+    }
+
+    /// Save the form, unless the node would silently ignore part of the rule
+    fn save_form(&self) -> Option<FwRuleEditorResult> {
+        let rule = self.build_rule();
+        if self.compat_warning(&rule).is_some() {
+            return None;
+        }
+        Some(FwRuleEditorResult::Save(rule))
+    }
+
+    fn save_raw(&mut self) -> Option<FwRuleEditorResult> {
+        let text = self.raw_editor.as_ref()?.text();
+        match self.parse_raw(&text) {
+            Ok(rule) => Some(FwRuleEditorResult::Save(rule)),
+            Err(e) => {
+                self.raw_error = Some(e);
+                None
+            }
+        }
+    }
+
+    /// Start typing a new expression, or edit the one at `index`
+    fn open_expression(&mut self, index: Option<usize>) {
+        let text = index
+            .and_then(|i| self.expressions.get(i))
+            .map(|e| e.statement.to_string())
+            .unwrap_or_default();
+        self.expr_input = Some(ExpressionInput { index, cursor: text.len(), text, error: None });
+    }
+
+    fn handle_expression_input(&mut self, key: KeyEvent) {
+        let Some(input) = &mut self.expr_input else {
+            return;
+        };
+
+        match key.code {
+            KeyCode::Esc => self.expr_input = None,
+            KeyCode::Enter => match Statement::parse(&input.text) {
+                Ok(statement) => {
+                    let expression = Expression { statement };
+                    match input.index {
+                        Some(i) if i < self.expressions.len() => {
+                            self.expressions[i] = expression;
+                            self.expr_selected = i;
+                        }
+                        _ => {
+                            self.expressions.push(expression);
+                            self.expr_selected = self.expressions.len() - 1;
+                        }
+                    }
+                    self.expr_input = None;
+                }
+                Err(e) => input.error = Some(e),
+            },
+            KeyCode::Char(c) => text::insert(&mut input.text, &mut input.cursor, c),
+            KeyCode::Backspace => {
+                text::backspace(&mut input.text, &mut input.cursor);
+            }
+            KeyCode::Delete => {
+                text::delete(&mut input.text, input.cursor);
+            }
+            KeyCode::Left => input.cursor = text::prev_boundary(&input.text, input.cursor),
+            KeyCode::Right => input.cursor = text::next_boundary(&input.text, input.cursor),
+            KeyCode::Home => input.cursor = 0,
+            KeyCode::End => input.cursor = input.text.len(),
+            _ => {}
+        }
+    }
+
     fn handle_text_input(&mut self, key: KeyEvent) -> Option<FwRuleEditorResult> {
         match key.code {
             KeyCode::Esc | KeyCode::Enter => {
                 self.editing_text = false;
             }
             KeyCode::Char(c) => {
-                let cursor = self.cursor_pos;
-                let text = self.current_text_mut();
-                if cursor <= text.len() {
-                    text.insert(cursor, c);
-                    self.cursor_pos = cursor + 1;
-                }
+                let mut cursor = self.cursor_pos.min(self.current_text().len());
+                text::insert(self.current_text_mut(), &mut cursor, c);
+                self.cursor_pos = cursor;
             }
             KeyCode::Backspace => {
-                if self.cursor_pos > 0 {
-                    self.cursor_pos -= 1;
-                    let cursor = self.cursor_pos;
-                    let text = self.current_text_mut();
-                    text.remove(cursor);
-                }
+                let mut cursor = self.cursor_pos;
+                text::backspace(self.current_text_mut(), &mut cursor);
+                self.cursor_pos = cursor;
             }
             KeyCode::Delete => {
                 let cursor = self.cursor_pos;
-                let text = self.current_text_mut();
-                if cursor < text.len() {
-                    text.remove(cursor);
-                }
+                text::delete(self.current_text_mut(), cursor);
             }
             KeyCode::Left => {
-                if self.cursor_pos > 0 {
-                    self.cursor_pos -= 1;
-                }
+                self.cursor_pos = text::prev_boundary(self.current_text(), self.cursor_pos);
             }
             KeyCode::Right => {
-                let len = self.current_text().len();
-                if self.cursor_pos < len {
-                    self.cursor_pos += 1;
-                }
+                self.cursor_pos = text::next_boundary(self.current_text(), self.cursor_pos);
             }
             KeyCode::Home => {
                 self.cursor_pos = 0;
@@ -385,9 +578,56 @@ impl FwRuleEditorDialog {
         self.target = targets[new_idx].to_string();
     }
 
+    fn render_raw(&self, frame: &mut Frame, editor: &TextArea, theme: &Theme) {
+        let area = frame.area();
+        let dialog_area = DialogLayout::centered(area, 90, 30).dialog;
+
+        frame.render_widget(Clear, dialog_area);
+
+        let block = Block::default()
+            .title(" Raw Firewall Rule JSON ")
+            .borders(Borders::ALL)
+            .border_style(theme.border_focused())
+            .style(theme.normal());
+
+        let inner = block.inner(dialog_area);
+        frame.render_widget(block, dialog_area);
+
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Min(5),    // Editor
+                Constraint::Length(2), // Validation
+                Constraint::Length(1), // Hints
+            ])
+            .split(inner);
+
+        editor.render(frame, chunks[0], " JSON ", theme.normal(), theme.border());
+
+        let status = match &self.raw_error {
+            Some(e) => Paragraph::new(format!("Invalid: {}", e))
+                .style(Style::default().fg(Color::Red))
+                .wrap(Wrap { trim: true }),
+            None => Paragraph::new("Valid firewall rule").style(Style::default().fg(Color::Green)),
+        };
+        frame.render_widget(status, chunks[1]);
+
+        frame.render_widget(
+            Paragraph::new("F2/Ctrl+S=save  Esc=back to form").style(theme.dim()),
+            chunks[2],
+        );
+    }
+
     pub fn render(&self, frame: &mut Frame, theme: &Theme) {
+        if let Some(editor) = &self.raw_editor {
+            self.render_raw(frame, editor, theme);
+            return;
+        }
+
         let area = frame.area();
-        let dialog_area = DialogLayout::centered(area, 65, 18).dialog;
+        let width = if self.show_preview { 120 } else { 65 };
+        let rows = self.expressions.len().clamp(1, EXPRESSION_ROWS);
+        let dialog_area = DialogLayout::centered(area, width, 20 + rows as u16).dialog;
 
         frame.render_widget(Clear, dialog_area);
 
@@ -404,7 +644,25 @@ impl FwRuleEditorDialog {
 
         frame.render_widget(block.clone(), dialog_area);
 
-        let inner = block.inner(dialog_area);
+        let mut inner = block.inner(dialog_area);
+
+        if self.show_preview {
+            let panes = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([Constraint::Length(63), Constraint::Min(20)])
+                .split(inner);
+            inner = panes[0];
+
+            let preview = Paragraph::new(self.preview_json())
+                .block(
+                    Block::default()
+                        .title(" JSON Preview ")
+                        .borders(Borders::LEFT)
+                        .border_style(theme.border()),
+                )
+                .style(theme.dim());
+            frame.render_widget(preview, panes[1]);
+        }
 
         let chunks = Layout::default()
             .direction(Direction::Vertical)
@@ -420,6 +678,9 @@ impl FwRuleEditorDialog {
                 Constraint::Length(1), // Dest IP
                 Constraint::Length(1), // Dest Port
                 Constraint::Length(1), // Separator
+                Constraint::Length(1), // Expressions header
+                Constraint::Length(rows as u16), // Expressions
+                Constraint::Length(1), // Expression input
                 Constraint::Min(1),    // Hints
             ])
             .split(inner);
@@ -488,14 +749,66 @@ impl FwRuleEditorDialog {
 
         frame.render_widget(Paragraph::new("─".repeat(55)).style(theme.dim()), chunks[9]);
 
-        let hints = if self.editing_text {
+        self.render_expressions(frame, &chunks[10..13], theme);
+
+        let hints = if self.expr_input.is_some() {
+            "Enter=done  Esc=cancel  e.g. ct state {established, related}  iifname != lo  tcp dport 1000-2000"
+        } else if self.focus == FwEditorFocus::Expressions {
+            "↑↓=select  Enter=edit  a=add  d=delete  Tab=navigate  p=preview  F2/Ctrl+S=save  Esc=cancel"
+        } else if self.editing_text {
             "Enter/Esc=done  ←→=cursor  Backspace=delete"
         } else {
-            "Tab/↑↓=navigate  Enter=edit  ←→/Space=change  F2/Ctrl+S=save  Esc=cancel"
+            "Tab/↑↓=navigate  Enter=edit  ←→/Space=change  p=preview  r=raw JSON  F2/Ctrl+S=save  Esc=cancel"
         };
-        let hint_para = Paragraph::new(hints)
-            .style(theme.dim())
-            .wrap(Wrap { trim: true });
-        frame.render_widget(hint_para, chunks[10]);
+        let input_error = self.expr_input.as_ref().and_then(|i| i.error.as_ref());
+        let hint_para = match (input_error, self.compat_warning(&self.build_rule())) {
+            (Some(error), _) => Paragraph::new(format!("Invalid: {}", error)).style(Style::default().fg(Color::Red)),
+            (None, Some(warning)) => Paragraph::new(format!("⚠ {} — edit the raw JSON to remove them", warning))
+                .style(Style::default().fg(Color::Red)),
+            (None, None) => Paragraph::new(hints).style(theme.dim()),
+        }
+        .wrap(Wrap { trim: true });
+        frame.render_widget(hint_para, chunks[13]);
+    }
+
+    /// Header, scrolling expression rows and the input line
+    fn render_expressions(&self, frame: &mut Frame, chunks: &[ratatui::layout::Rect], theme: &Theme) {
+        let focused = self.focus == FwEditorFocus::Expressions && self.expr_input.is_none();
+        frame.render_widget(
+            Paragraph::new(format!("Expressions ({}):", self.expressions.len())).style(theme.normal()),
+            chunks[0],
+        );
+
+        let rows = chunks[1].height as usize;
+        let lines: Vec<Line> = if self.expressions.is_empty() {
+            let style = if focused { theme.dim().add_modifier(Modifier::REVERSED) } else { theme.dim() };
+            vec![Line::styled("  (none, a=add)", style)]
+        } else {
+            let offset = self.expr_selected.saturating_sub(rows.saturating_sub(1));
+            self.expressions
+                .iter()
+                .enumerate()
+                .skip(offset)
+                .take(rows)
+                .map(|(i, e)| {
+                    let style = if focused && i == self.expr_selected {
+                        Style::default().add_modifier(Modifier::REVERSED)
+                    } else {
+                        theme.normal()
+                    };
+                    Line::styled(format!("  {}", e.statement), style)
+                })
+                .collect()
+        };
+        frame.render_widget(Paragraph::new(lines), chunks[1]);
+
+        if let Some(input) = &self.expr_input {
+            let label = if input.index.is_some() { "Edit:" } else { "Add:" };
+            frame.render_widget(
+                Paragraph::new(format!("{:14} {}", label, input.text))
+                    .style(Style::default().fg(Color::Yellow).add_modifier(Modifier::UNDERLINED)),
+                chunks[2],
+            );
+        }
     }
 }
@@ -0,0 +1,113 @@
+//! Execution log of the rule hooks
+
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    layout::{Constraint, Direction, Layout},
+    style::Modifier,
+    widgets::{Block, Borders, Cell, Clear, Paragraph, Row, Table, TableState},
+    Frame,
+};
+
+use crate::app::hooks::HookRun;
+use crate::ui::layout::DialogLayout;
+use crate::ui::theme::Theme;
+use crate::utils::Formats;
+
+pub struct HookLogDialog {
+    runs: Vec<HookRun>,
+    selected: usize,
+    formats: Formats,
+}
+
+impl HookLogDialog {
+    pub fn new(runs: Vec<HookRun>) -> Self {
+        Self {
+            runs,
+            selected: 0,
+            formats: Formats::default(),
+        }
+    }
+
+    pub fn with_formats(mut self, formats: Formats) -> Self {
+        self.formats = formats;
+        self
+    }
+
+    /// Handle key event, returns true if dialog should close
+    pub fn handle_key(&mut self, key: KeyEvent) -> bool {
+        match key.code {
+            KeyCode::Down | KeyCode::Char('j') => {
+                self.selected = (self.selected + 1).min(self.runs.len().saturating_sub(1));
+            }
+            KeyCode::Up | KeyCode::Char('k') => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Esc | KeyCode::Enter | KeyCode::Char('q') => return true,
+            _ => {}
+        }
+        false
+    }
+
+    pub fn render(&self, frame: &mut Frame, theme: &Theme) {
+        let area = frame.area();
+        let dialog_area = DialogLayout::centered(area, 100, 20).dialog;
+
+        frame.render_widget(Clear, dialog_area);
+
+        let block = Block::default()
+            .title(" Rule Hooks ")
+            .borders(Borders::ALL)
+            .border_style(theme.border_focused())
+            .style(theme.normal());
+
+        let inner = block.inner(dialog_area);
+        frame.render_widget(block, dialog_area);
+
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .margin(1)
+            .constraints([
+                Constraint::Min(3),    // Runs
+                Constraint::Length(1), // Hints
+            ])
+            .split(inner);
+
+        if self.runs.is_empty() {
+            frame.render_widget(
+                Paragraph::new("No hook has run yet. Hooks are set up with rule_hooks in the config file.")
+                    .style(theme.dim()),
+                chunks[0],
+            );
+        } else {
+            let header = Row::new(["Time", "Rule", "Action", "Result", "Skipped"])
+                .style(theme.accent().add_modifier(Modifier::BOLD));
+            let rows = self.runs.iter().map(|run| {
+                let result = match &run.result {
+                    Ok(message) => Cell::from(message.clone()).style(theme.success()),
+                    Err(e) => Cell::from(e.clone()).style(theme.error()),
+                };
+                Row::new(vec![
+                    Cell::from(self.formats.time(&run.time)),
+                    Cell::from(run.rule.clone()),
+                    Cell::from(self.formats.text(&run.sink).into_owned()),
+                    result,
+                    Cell::from(self.formats.number(run.suppressed)),
+                ])
+            });
+            let table = Table::new(
+                rows,
+                [
+                    Constraint::Length(11),
+                    Constraint::Length(20),
+                    Constraint::Min(24),
+                    Constraint::Min(16),
+                    Constraint::Length(7),
+                ],
+            )
+            .header(header)
+            .row_highlight_style(theme.selected());
+            let mut state = TableState::default().with_selected(Some(self.selected));
+            frame.render_stateful_widget(table, chunks[0], &mut state);
+        }
+
+        frame.render_widget(Paragraph::new("↑↓=scroll  Esc=close").style(theme.dim()), chunks[1]);
+    }
+}
@@ -0,0 +1,160 @@
+//! Import wizard for the official GUI database
+
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    layout::{Constraint, Direction, Layout},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph, Wrap},
+    Frame,
+};
+
+use crate::db::import::{default_gui_db_path, ImportReport};
+use crate::ui::layout::DialogLayout;
+use crate::ui::theme::Theme;
+use crate::utils::text;
+
+/// Requested outcome of an import dialog key press
+pub enum ImportDialogResult {
+    Close,
+    /// Run the import for this path, the dialog stays open for the report
+    Import(String),
+}
+
+/// Import dialog state
+pub struct ImportDialog {
+    pub path: String,
+    cursor_pos: usize,
+    result: Option<Result<ImportReport, String>>,
+}
+
+impl Default for ImportDialog {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ImportDialog {
+    pub fn new() -> Self {
+        let path = default_gui_db_path().to_string_lossy().to_string();
+        Self {
+            cursor_pos: path.len(),
+            path,
+            result: None,
+        }
+    }
+
+    /// Show the outcome of an import
+    pub fn set_result(&mut self, result: Result<ImportReport, String>) {
+        self.result = Some(result);
+    }
+
+    /// Handle key event
+    pub fn handle_key(&mut self, key: KeyEvent) -> Option<ImportDialogResult> {
+        // Once an import has run, the dialog only shows the report
+        if self.result.is_some() {
+            return matches!(key.code, KeyCode::Esc | KeyCode::Enter | KeyCode::Char('q'))
+                .then_some(ImportDialogResult::Close);
+        }
+
+        match key.code {
+            KeyCode::Esc => return Some(ImportDialogResult::Close),
+            KeyCode::Enter => return Some(ImportDialogResult::Import(self.path.clone())),
+            KeyCode::Char(c) => text::insert(&mut self.path, &mut self.cursor_pos, c),
+            KeyCode::Backspace => {
+                text::backspace(&mut self.path, &mut self.cursor_pos);
+            }
+            KeyCode::Delete => {
+                text::delete(&mut self.path, self.cursor_pos);
+            }
+            KeyCode::Left => self.cursor_pos = text::prev_boundary(&self.path, self.cursor_pos),
+            KeyCode::Right => self.cursor_pos = text::next_boundary(&self.path, self.cursor_pos),
+            KeyCode::Home => self.cursor_pos = 0,
+            KeyCode::End => self.cursor_pos = self.path.len(),
+            _ => {}
+        }
+        None
+    }
+
+    pub fn render(&self, frame: &mut Frame, theme: &Theme) {
+        let area = frame.area();
+        let dialog_area = DialogLayout::centered(area, 76, 18).dialog;
+
+        frame.render_widget(Clear, dialog_area);
+
+        let block = Block::default()
+            .title(" Import from OpenSnitch GUI ")
+            .borders(Borders::ALL)
+            .border_style(theme.border_focused())
+            .style(theme.normal());
+
+        let inner = block.inner(dialog_area);
+        frame.render_widget(block, dialog_area);
+
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .margin(1)
+            .constraints([
+                Constraint::Length(2), // Intro
+                Constraint::Length(3), // Path input
+                Constraint::Min(3),    // Report
+                Constraint::Length(1), // Hints
+            ])
+            .split(inner);
+
+        let intro = Paragraph::new("Copies historical connections and rules from the GUI's SQLite database. Existing entries are kept.")
+            .style(theme.dim())
+            .wrap(Wrap { trim: true });
+        frame.render_widget(intro, chunks[0]);
+
+        let editing = self.result.is_none();
+        let path_block = Block::default()
+            .title(" Database path ")
+            .borders(Borders::ALL)
+            .border_style(if editing { theme.border_focused() } else { theme.border() });
+        let path_para = Paragraph::new(self.path.as_str())
+            .block(path_block)
+            .style(theme.normal());
+        frame.render_widget(path_para, chunks[1]);
+
+        if editing {
+            frame.set_cursor_position((
+                chunks[1].x + 1 + text::column(&self.path, self.cursor_pos) as u16,
+                chunks[1].y + 1,
+            ));
+        }
+
+        let report_lines: Vec<Line> = match &self.result {
+            None => vec![],
+            Some(Err(e)) => vec![Line::from(Span::styled(
+                format!("Import failed: {}", e),
+                Style::default().fg(Color::Red),
+            ))],
+            Some(Ok(report)) => {
+                let mut lines = vec![Line::from(Span::styled(
+                    report.summary(),
+                    Style::default().fg(Color::Green).add_modifier(Modifier::BOLD),
+                ))];
+                if !report.errors.is_empty() {
+                    lines.push(Line::from(""));
+                    lines.push(Line::from(Span::styled("Skipped rows:", theme.accent())));
+                    for error in &report.errors {
+                        lines.push(Line::from(format!("  {}", error)));
+                    }
+                }
+                lines
+            }
+        };
+        frame.render_widget(
+            Paragraph::new(report_lines).wrap(Wrap { trim: false }).style(theme.normal()),
+            chunks[2],
+        );
+
+        let hints = if editing {
+            "Enter=import  Esc=cancel"
+        } else {
+            "Enter/Esc=close"
+        };
+        frame.render_widget(Paragraph::new(hints).style(theme.dim()), chunks[3]);
+    }
+}
@@ -0,0 +1,223 @@
+//! Assistant setting up a VPN kill-switch, previewed before it is saved
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::{
+    layout::{Constraint, Direction, Layout},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph, Wrap},
+    Frame,
+};
+
+use crate::models::{FwChain, KillSwitch};
+use crate::ui::layout::DialogLayout;
+use crate::ui::theme::Theme;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+    Interfaces,
+    Endpoint,
+    Port,
+    Protocol,
+    AllowLan,
+}
+
+const FIELDS: [Field; 5] = [Field::Interfaces, Field::Endpoint, Field::Port, Field::Protocol, Field::AllowLan];
+
+/// Outcome of the assistant
+pub enum KillSwitchResult {
+    Install(KillSwitch),
+    Cancel,
+}
+
+pub struct KillSwitchDialog {
+    interfaces: String,
+    endpoint: String,
+    port: String,
+    protocol: String,
+    allow_lan: bool,
+    focus: usize,
+    /// Chain about to be saved, once the form is filled in
+    preview: Option<FwChain>,
+    error: Option<String>,
+}
+
+impl Default for KillSwitchDialog {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl KillSwitchDialog {
+    pub fn new() -> Self {
+        Self::from_killswitch(&KillSwitch::default())
+    }
+
+    fn from_killswitch(killswitch: &KillSwitch) -> Self {
+        Self {
+            interfaces: killswitch.interfaces.join(" "),
+            endpoint: killswitch.endpoint.clone(),
+            port: killswitch.port.to_string(),
+            protocol: killswitch.protocol.clone(),
+            allow_lan: killswitch.allow_lan,
+            focus: 0,
+            preview: None,
+            error: None,
+        }
+    }
+
+    /// Settings of the form, or why they are not usable
+    fn killswitch(&self) -> Result<KillSwitch, String> {
+        let killswitch = KillSwitch {
+            interfaces: self.interfaces.split([' ', ',']).filter(|i| !i.is_empty()).map(String::from).collect(),
+            endpoint: self.endpoint.trim().to_string(),
+            port: self.port.trim().parse().unwrap_or(0),
+            protocol: self.protocol.clone(),
+            allow_lan: self.allow_lan,
+        };
+        killswitch.validate().map(|_| killswitch)
+    }
+
+    fn text_mut(&mut self) -> Option<&mut String> {
+        match FIELDS[self.focus] {
+            Field::Interfaces => Some(&mut self.interfaces),
+            Field::Endpoint => Some(&mut self.endpoint),
+            Field::Port => Some(&mut self.port),
+            Field::Protocol | Field::AllowLan => None,
+        }
+    }
+
+    /// Handle key event, returns a result once the dialog should close
+    pub fn handle_key(&mut self, key: KeyEvent) -> Option<KillSwitchResult> {
+        if self.preview.is_some() {
+            match key.code {
+                KeyCode::Enter | KeyCode::Char('y') => return self.killswitch().ok().map(KillSwitchResult::Install),
+                KeyCode::Esc | KeyCode::Char('n') => self.preview = None,
+                _ => {}
+            }
+            return None;
+        }
+
+        self.error = None;
+        match key.code {
+            KeyCode::Esc => return Some(KillSwitchResult::Cancel),
+            KeyCode::Tab | KeyCode::Down => self.focus = (self.focus + 1) % FIELDS.len(),
+            KeyCode::BackTab | KeyCode::Up => self.focus = (self.focus + FIELDS.len() - 1) % FIELDS.len(),
+            KeyCode::Enter | KeyCode::F(2) => match self.killswitch() {
+                Ok(killswitch) => self.preview = Some(killswitch.chain()),
+                Err(e) => self.error = Some(e),
+            },
+            KeyCode::Char('s') if key.modifiers.contains(KeyModifiers::CONTROL) => match self.killswitch() {
+                Ok(killswitch) => self.preview = Some(killswitch.chain()),
+                Err(e) => self.error = Some(e),
+            },
+            KeyCode::Char(' ') | KeyCode::Left | KeyCode::Right if FIELDS[self.focus] == Field::Protocol => {
+                self.protocol = if self.protocol == "udp" { "tcp" } else { "udp" }.to_string();
+            }
+            KeyCode::Char(' ') | KeyCode::Left | KeyCode::Right if FIELDS[self.focus] == Field::AllowLan => {
+                self.allow_lan = !self.allow_lan;
+            }
+            KeyCode::Backspace => {
+                if let Some(text) = self.text_mut() {
+                    text.pop();
+                }
+            }
+            KeyCode::Char(c) => {
+                if let Some(text) = self.text_mut() {
+                    text.push(c);
+                }
+            }
+            _ => {}
+        }
+        None
+    }
+
+    pub fn render(&self, frame: &mut Frame, theme: &Theme) {
+        let area = frame.area();
+        let dialog_area = DialogLayout::centered(area, 72, 20).dialog;
+
+        frame.render_widget(Clear, dialog_area);
+
+        let block = Block::default()
+            .title(" VPN Kill-Switch ")
+            .borders(Borders::ALL)
+            .border_style(theme.border_focused())
+            .style(theme.normal());
+
+        let inner = block.inner(dialog_area);
+        frame.render_widget(block, dialog_area);
+
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .margin(1)
+            .constraints([
+                Constraint::Length(2), // Explanation
+                Constraint::Min(5),    // Form or preview
+                Constraint::Length(1), // Hints
+            ])
+            .split(inner);
+
+        frame.render_widget(
+            Paragraph::new("Outgoing traffic may only use the tunnel or go to the VPN server, everything else is dropped.")
+                .style(theme.dim())
+                .wrap(Wrap { trim: true }),
+            chunks[0],
+        );
+
+        let hints = match &self.preview {
+            Some(chain) => {
+                let mut lines = vec![Line::from(Span::styled(
+                    format!("Chain {} ({}), policy {}:", chain.name, chain.hook, chain.policy),
+                    theme.accent().add_modifier(Modifier::BOLD),
+                ))];
+                lines.extend(chain.rules.iter().map(|rule| {
+                    let conditions: Vec<String> = rule
+                        .expressions
+                        .iter()
+                        .map(|e| {
+                            let value = e.statement.values.first().map(|v| v.value.as_str()).unwrap_or_default();
+                            format!("{} {}", e.statement.name, value)
+                        })
+                        .collect();
+                    Line::from(format!("  {} if {}", rule.target, conditions.join(" and ")))
+                }));
+                frame.render_widget(Paragraph::new(lines), chunks[1]);
+                "Enter/y = save to the firewall config  Esc = back"
+            }
+            None => {
+                let value = |field: Field| match field {
+                    Field::Interfaces => self.interfaces.clone(),
+                    Field::Endpoint => self.endpoint.clone(),
+                    Field::Port => self.port.clone(),
+                    Field::Protocol => format!("◀ {} ▶", self.protocol),
+                    Field::AllowLan => if self.allow_lan { "[x]" } else { "[ ]" }.to_string(),
+                };
+                let label = |field: Field| match field {
+                    Field::Interfaces => "Tunnel interfaces:",
+                    Field::Endpoint => "VPN server IP:",
+                    Field::Port => "Server port:",
+                    Field::Protocol => "Protocol:",
+                    Field::AllowLan => "Allow LAN:",
+                };
+                let mut lines: Vec<Line> = FIELDS
+                    .iter()
+                    .enumerate()
+                    .map(|(i, field)| {
+                        let style = if i == self.focus { theme.selected() } else { theme.normal() };
+                        Line::from(vec![
+                            Span::styled(format!("{:19}", label(*field)), theme.dim()),
+                            Span::styled(value(*field), style),
+                        ])
+                    })
+                    .collect();
+                if let Some(e) = &self.error {
+                    lines.push(Line::from(""));
+                    lines.push(Line::from(Span::styled(format!("⚠ {}", e), Style::default().fg(Color::Yellow))));
+                }
+                frame.render_widget(Paragraph::new(lines), chunks[1]);
+                "Tab = next field  Space = toggle  Enter = preview  Esc = cancel"
+            }
+        };
+        frame.render_widget(Paragraph::new(hints).style(theme.dim()), chunks[2]);
+    }
+}
@@ -0,0 +1,231 @@
+//! Editor for the entries of a list operator
+//!
+//! A rule of type list matches when all of its entries do, each entry an
+//! operator of its own. The prompt makes such rules when more than one
+//! property of a connection is picked. Opened from the rule editor's data
+//! field, the entries are handed back when it closes.
+
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    layout::{Constraint, Direction, Layout},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph},
+    Frame,
+};
+
+use crate::models::{Operator, OperatorType};
+use crate::ui::dialogs::rule_editor::OPERANDS;
+use crate::ui::layout::DialogLayout;
+use crate::ui::theme::Theme;
+use crate::utils::text;
+
+/// Types an entry may have, lists don't nest
+const ENTRY_TYPES: [OperatorType; 4] = [
+    OperatorType::Simple,
+    OperatorType::Regexp,
+    OperatorType::Network,
+    OperatorType::Lists,
+];
+
+/// Which part of the selected entry is focused
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ListField {
+    Type,
+    Operand,
+    Data,
+}
+
+impl ListField {
+    fn next(self) -> Self {
+        match self {
+            Self::Type => Self::Operand,
+            Self::Operand => Self::Data,
+            Self::Data => Self::Type,
+        }
+    }
+
+    fn prev(self) -> Self {
+        match self {
+            Self::Type => Self::Data,
+            Self::Operand => Self::Type,
+            Self::Data => Self::Operand,
+        }
+    }
+}
+
+pub struct ListOperatorEditor {
+    entries: Vec<Operator>,
+    selected: usize,
+    pub field: ListField,
+    editing_text: bool,
+    cursor_pos: usize,
+}
+
+impl ListOperatorEditor {
+    pub fn new(entries: Vec<Operator>) -> Self {
+        Self {
+            entries,
+            selected: 0,
+            field: ListField::Data,
+            editing_text: false,
+            cursor_pos: 0,
+        }
+    }
+
+    /// Add an entry below the selected one, with the operand of the
+    /// selected one so a second value is quick to add
+    fn add_entry(&mut self) {
+        let operand = self.entries.get(self.selected).map_or("process.path", |e| e.operand.as_str());
+        let entry = Operator::simple(operand, "");
+        let at = if self.entries.is_empty() { 0 } else { self.selected + 1 };
+        self.entries.insert(at, entry);
+        self.selected = at;
+        self.field = ListField::Data;
+        self.editing_text = true;
+        self.cursor_pos = 0;
+    }
+
+    fn remove_entry(&mut self) {
+        if self.selected < self.entries.len() {
+            self.entries.remove(self.selected);
+            self.selected = self.selected.min(self.entries.len().saturating_sub(1));
+        }
+    }
+
+    fn cycle(&mut self, forward: bool) {
+        let Some(entry) = self.entries.get_mut(self.selected) else {
+            return;
+        };
+        match self.field {
+            ListField::Type => {
+                let current = ENTRY_TYPES.iter().position(|t| *t == entry.op_type).unwrap_or(0);
+                let next = if forward { current + 1 } else { current + ENTRY_TYPES.len() - 1 };
+                entry.op_type = ENTRY_TYPES[next % ENTRY_TYPES.len()].clone();
+            }
+            ListField::Operand => {
+                let current = OPERANDS.iter().position(|o| *o == entry.operand).unwrap_or(0);
+                let next = if forward { current + 1 } else { current + OPERANDS.len() - 1 };
+                entry.operand = OPERANDS[next % OPERANDS.len()].to_string();
+            }
+            ListField::Data => {}
+        }
+    }
+
+    /// Handle a key, returns the entries once the editor closes
+    pub fn handle_key(&mut self, key: KeyEvent) -> Option<Vec<Operator>> {
+        if self.editing_text {
+            self.handle_text_input(key);
+            return None;
+        }
+        match key.code {
+            KeyCode::Up | KeyCode::Char('k') => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Down | KeyCode::Char('j') => {
+                self.selected = (self.selected + 1).min(self.entries.len().saturating_sub(1));
+            }
+            KeyCode::Tab => self.field = self.field.next(),
+            KeyCode::BackTab => self.field = self.field.prev(),
+            KeyCode::Left => self.cycle(false),
+            KeyCode::Right | KeyCode::Char(' ') => self.cycle(true),
+            KeyCode::Enter if self.field == ListField::Data && !self.entries.is_empty() => {
+                self.editing_text = true;
+                self.cursor_pos = self.entries[self.selected].data.len();
+            }
+            KeyCode::Enter => self.cycle(true),
+            KeyCode::Char('a') | KeyCode::Insert => self.add_entry(),
+            KeyCode::Char('d') | KeyCode::Delete => self.remove_entry(),
+            KeyCode::Esc => return Some(self.entries.clone()),
+            _ => {}
+        }
+        None
+    }
+
+    fn handle_text_input(&mut self, key: KeyEvent) {
+        let Some(entry) = self.entries.get_mut(self.selected) else {
+            self.editing_text = false;
+            return;
+        };
+        let data = &mut entry.data;
+        match key.code {
+            KeyCode::Esc | KeyCode::Enter => self.editing_text = false,
+            KeyCode::Char(c) => {
+                let mut cursor = self.cursor_pos.min(data.len());
+                text::insert(data, &mut cursor, c);
+                self.cursor_pos = cursor;
+            }
+            KeyCode::Backspace => {
+                text::backspace(data, &mut self.cursor_pos);
+            }
+            KeyCode::Delete => {
+                text::delete(data, self.cursor_pos);
+            }
+            KeyCode::Left => self.cursor_pos = text::prev_boundary(data, self.cursor_pos),
+            KeyCode::Right => self.cursor_pos = text::next_boundary(data, self.cursor_pos),
+            KeyCode::Home => self.cursor_pos = 0,
+            KeyCode::End => self.cursor_pos = data.len(),
+            _ => {}
+        }
+    }
+
+    pub fn render(&self, frame: &mut Frame, theme: &Theme) {
+        let area = frame.area();
+        let dialog_area = DialogLayout::centered(area, 76, 16).dialog;
+
+        frame.render_widget(Clear, dialog_area);
+
+        let block = Block::default()
+            .title(format!(" List: all {} entries must match ", self.entries.len()))
+            .borders(Borders::ALL)
+            .border_style(theme.border_focused())
+            .style(theme.normal());
+
+        let inner = block.inner(dialog_area);
+        frame.render_widget(block, dialog_area);
+
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .margin(1)
+            .constraints([Constraint::Min(3), Constraint::Length(1)])
+            .split(inner);
+
+        let field_style = |focused: bool| {
+            if !focused {
+                theme.normal()
+            } else if self.editing_text {
+                Style::default().fg(Color::Yellow).add_modifier(Modifier::UNDERLINED)
+            } else {
+                Style::default().add_modifier(Modifier::REVERSED)
+            }
+        };
+        let lines: Vec<Line> = if self.entries.is_empty() {
+            vec![Line::styled("No entries, a=add one", theme.dim())]
+        } else {
+            self.entries
+                .iter()
+                .enumerate()
+                .map(|(i, entry)| {
+                    let selected = i == self.selected;
+                    let focused = |field| selected && self.field == field;
+                    Line::from(vec![
+                        Span::raw(if selected { "▶ " } else { "  " }),
+                        Span::styled(format!("{:8}", entry.op_type.to_string()), field_style(focused(ListField::Type))),
+                        Span::raw(" "),
+                        Span::styled(format!("{:20}", entry.operand), field_style(focused(ListField::Operand))),
+                        Span::raw(" "),
+                        Span::styled(entry.data.clone(), field_style(focused(ListField::Data))),
+                    ])
+                })
+                .collect()
+        };
+        // Keep the selected entry in view
+        let scroll = self.selected.saturating_sub(chunks[0].height.saturating_sub(1) as usize);
+        frame.render_widget(Paragraph::new(lines).scroll((scroll as u16, 0)), chunks[0]);
+
+        let hints = if self.editing_text {
+            "Enter/Esc=done editing  ←→=move cursor"
+        } else {
+            "↑↓=entry  Tab=field  ←→=change  Enter=edit  a=add  d=remove  Esc=done"
+        };
+        frame.render_widget(Paragraph::new(hints).style(theme.dim()), chunks[1]);
+    }
+}
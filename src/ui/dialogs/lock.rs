@@ -0,0 +1,147 @@
+//! Idle lock screen
+
+use std::time::{Duration, Instant};
+
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    layout::{Alignment, Constraint, Direction, Layout},
+    style::{Color, Style},
+    text::Line,
+    widgets::{Block, Borders, Clear, Paragraph},
+    Frame,
+};
+
+use crate::ui::layout::DialogLayout;
+use crate::ui::theme::Theme;
+
+/// Tracks user inactivity and locks the UI after a configurable timeout
+pub struct IdleLock {
+    timeout: Option<Duration>,
+    passphrase: String,
+    last_input: Instant,
+    locked: bool,
+    input: String,
+    failed: bool,
+}
+
+impl IdleLock {
+    pub fn new(minutes: u64, passphrase: &str) -> Self {
+        Self {
+            timeout: if minutes > 0 {
+                Some(Duration::from_secs(minutes * 60))
+            } else {
+                None
+            },
+            passphrase: passphrase.to_string(),
+            last_input: Instant::now(),
+            locked: false,
+            input: String::new(),
+            failed: false,
+        }
+    }
+
+    pub fn is_locked(&self) -> bool {
+        self.locked
+    }
+
+    /// Record user activity
+    pub fn touch(&mut self) {
+        self.last_input = Instant::now();
+    }
+
+    /// Lock the UI if the idle timeout has elapsed
+    pub fn check(&mut self) {
+        if let Some(timeout) = self.timeout {
+            if !self.locked && self.last_input.elapsed() >= timeout {
+                self.locked = true;
+                self.input.clear();
+                self.failed = false;
+            }
+        }
+    }
+
+    /// Handle key while locked, returns true once unlocked
+    pub fn handle_key(&mut self, key: KeyEvent) -> bool {
+        match key.code {
+            KeyCode::Enter => {
+                if self.input == self.passphrase {
+                    self.locked = false;
+                    self.failed = false;
+                    self.touch();
+                } else {
+                    self.failed = true;
+                }
+                self.input.clear();
+            }
+            KeyCode::Esc => {
+                self.input.clear();
+            }
+            KeyCode::Backspace => {
+                self.input.pop();
+            }
+            KeyCode::Char(c) => {
+                self.input.push(c);
+                self.failed = false;
+            }
+            _ => {}
+        }
+        !self.locked
+    }
+
+    pub fn render(&self, frame: &mut Frame, theme: &Theme) {
+        let area = frame.area();
+
+        // Blank the whole screen so nothing behind the lock is visible
+        frame.render_widget(Clear, area);
+        frame.render_widget(Block::default().style(theme.normal()), area);
+
+        let dialog_area = DialogLayout::centered(area, 44, 9).dialog;
+
+        let block = Block::default()
+            .title(" Locked ")
+            .borders(Borders::ALL)
+            .border_style(theme.border_focused())
+            .style(theme.normal());
+
+        let inner = block.inner(dialog_area);
+        frame.render_widget(block, dialog_area);
+
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .margin(1)
+            .constraints([
+                Constraint::Length(1), // Message
+                Constraint::Length(1), // Spacer
+                Constraint::Length(1), // Input
+                Constraint::Min(1),    // Status
+            ])
+            .split(inner);
+
+        let message = if self.passphrase.is_empty() {
+            "Press Enter to unlock"
+        } else {
+            "Enter passphrase to unlock"
+        };
+        frame.render_widget(
+            Paragraph::new(message).alignment(Alignment::Center).style(theme.normal()),
+            chunks[0],
+        );
+
+        if !self.passphrase.is_empty() {
+            let masked = "*".repeat(self.input.chars().count());
+            frame.render_widget(
+                Paragraph::new(Line::from(format!("> {}", masked))).style(theme.accent()),
+                chunks[2],
+            );
+        }
+
+        if self.failed {
+            frame.render_widget(
+                Paragraph::new("Wrong passphrase")
+                    .alignment(Alignment::Center)
+                    .style(Style::default().fg(Color::Red)),
+                chunks[3],
+            );
+        }
+    }
+}
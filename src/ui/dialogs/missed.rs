@@ -0,0 +1,161 @@
+//! Prompts that timed out or were lost, to create rules for afterwards
+
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    layout::{Constraint, Direction, Layout},
+    style::Modifier,
+    widgets::{Block, Borders, Cell, Clear, Paragraph, Row, Table, TableState},
+    Frame,
+};
+
+use crate::app::suggestions::RuleSuggestion;
+use crate::models::{MissedPrompt, PromptOutcome, Rule, RuleAction};
+use crate::ui::layout::DialogLayout;
+use crate::ui::theme::Theme;
+use crate::utils::Formats;
+
+/// Outcome of a key in the missed prompts list
+pub enum MissedPromptsResult {
+    /// Create a permanent rule for a prompt, resolving it
+    Create { id: i64, node_addr: String, rule: Rule },
+    /// Resolve a prompt without a rule
+    Dismiss { id: i64 },
+    Close,
+}
+
+pub struct MissedPromptsDialog {
+    prompts: Vec<MissedPrompt>,
+    selected: usize,
+    formats: Formats,
+}
+
+impl MissedPromptsDialog {
+    pub fn new(prompts: Vec<MissedPrompt>) -> Self {
+        Self {
+            prompts,
+            selected: 0,
+            formats: Formats::default(),
+        }
+    }
+
+    /// Masking of hosts and paths
+    pub fn with_formats(mut self, formats: Formats) -> Self {
+        self.formats = formats;
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.prompts.is_empty()
+    }
+
+    /// Handle key event, returns a result for the app to act on
+    pub fn handle_key(&mut self, key: KeyEvent) -> Option<MissedPromptsResult> {
+        match key.code {
+            KeyCode::Down | KeyCode::Char('j') => {
+                self.selected = (self.selected + 1).min(self.prompts.len().saturating_sub(1));
+            }
+            KeyCode::Up | KeyCode::Char('k') => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Char('a') => return self.create(RuleAction::Allow),
+            KeyCode::Char('d') => return self.create(RuleAction::Deny),
+            KeyCode::Char('x') => {
+                let prompt = self.take_selected()?;
+                return Some(MissedPromptsResult::Dismiss { id: prompt.id });
+            }
+            KeyCode::Esc | KeyCode::Char('q') => return Some(MissedPromptsResult::Close),
+            _ => {}
+        }
+        None
+    }
+
+    fn create(&mut self, action: RuleAction) -> Option<MissedPromptsResult> {
+        let prompt = self.take_selected()?;
+        let connection = &prompt.connection;
+        let destination = if connection.dst_host.is_empty() {
+            connection.dst_ip.clone()
+        } else {
+            connection.dst_host.clone()
+        };
+        let suggestion = RuleSuggestion {
+            node_addr: prompt.node_addr.clone(),
+            process_path: connection.process_path.clone(),
+            destination,
+            count: 1,
+        };
+        Some(MissedPromptsResult::Create {
+            id: prompt.id,
+            rule: suggestion.rule(action),
+            node_addr: prompt.node_addr,
+        })
+    }
+
+    fn take_selected(&mut self) -> Option<MissedPrompt> {
+        if self.selected >= self.prompts.len() {
+            return None;
+        }
+        let prompt = self.prompts.remove(self.selected);
+        self.selected = self.selected.min(self.prompts.len().saturating_sub(1));
+        Some(prompt)
+    }
+
+    pub fn render(&self, frame: &mut Frame, theme: &Theme) {
+        let area = frame.area();
+        let dialog_area = DialogLayout::centered(area, 96, 20).dialog;
+
+        frame.render_widget(Clear, dialog_area);
+
+        let block = Block::default()
+            .title(format!(" Missed Prompts ({}) ", self.prompts.len()))
+            .borders(Borders::ALL)
+            .border_style(theme.border_focused())
+            .style(theme.normal());
+
+        let inner = block.inner(dialog_area);
+        frame.render_widget(block, dialog_area);
+
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .margin(1)
+            .constraints([
+                Constraint::Min(3),    // Prompts
+                Constraint::Length(1), // Hints
+            ])
+            .split(inner);
+
+        let header = Row::new(["Time", "Node", "Process", "Destination", ""])
+            .style(theme.accent().add_modifier(Modifier::BOLD));
+        let rows = self.prompts.iter().map(|prompt| {
+            let connection = &prompt.connection;
+            let host = if connection.dst_host.is_empty() { &connection.dst_ip } else { &connection.dst_host };
+            let outcome = match prompt.outcome {
+                PromptOutcome::Lost => Cell::from("lost").style(theme.warning()),
+                _ => Cell::from("timed out").style(theme.dim()),
+            };
+            Row::new(vec![
+                Cell::from(self.formats.date_time(&prompt.time)),
+                Cell::from(prompt.node_addr.clone()),
+                Cell::from(self.formats.path(&connection.process_path).into_owned()),
+                Cell::from(format!("{}:{}", self.formats.host(host), connection.dst_port)),
+                outcome,
+            ])
+        });
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Length(19),
+                Constraint::Length(14),
+                Constraint::Min(20),
+                Constraint::Min(20),
+                Constraint::Length(9),
+            ],
+        )
+        .header(header)
+        .row_highlight_style(theme.selected());
+        let mut state = TableState::default().with_selected(Some(self.selected));
+        frame.render_stateful_widget(table, chunks[0], &mut state);
+
+        frame.render_widget(
+            Paragraph::new("↑↓=select  a=allow always  d=deny always  x=dismiss  Esc=close").style(theme.dim()),
+            chunks[1],
+        );
+    }
+}
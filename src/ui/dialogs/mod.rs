@@ -1,6 +1,27 @@
+pub mod alert;
+pub mod alert_details;
+pub mod automations;
+pub mod blocklists;
+pub mod changelog;
 pub mod confirm;
 pub mod connection_details;
+pub mod copy_rules;
+pub mod fw_chain;
 pub mod fw_rule;
+pub mod hooks;
+pub mod import;
+pub mod killswitch;
+pub mod list_operator;
+pub mod lock;
+pub mod missed;
+pub mod notice;
 pub mod preferences;
+pub mod profiles;
 pub mod prompt;
+pub mod prompt_settings;
 pub mod rule_editor;
+pub mod rule_history;
+pub mod rules_dir;
+pub mod sent_changes;
+pub mod suggestion;
+pub mod theme;
@@ -0,0 +1,64 @@
+//! Notice shown until dismissed, e.g. a startup problem
+
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    layout::{Constraint, Direction, Layout},
+    style::{Color, Style},
+    widgets::{Block, Borders, Clear, Paragraph, Wrap},
+    Frame,
+};
+
+use crate::ui::layout::DialogLayout;
+use crate::ui::theme::Theme;
+
+pub struct NoticeDialog {
+    pub title: String,
+    pub message: String,
+}
+
+impl NoticeDialog {
+    pub fn new(title: &str, message: &str) -> Self {
+        Self {
+            title: title.to_string(),
+            message: message.to_string(),
+        }
+    }
+
+    /// Handle key event, returns true if dialog should close
+    pub fn handle_key(&mut self, key: KeyEvent) -> bool {
+        matches!(key.code, KeyCode::Enter | KeyCode::Esc)
+    }
+
+    pub fn render(&self, frame: &mut Frame, theme: &Theme) {
+        let area = frame.area();
+        let dialog_area = DialogLayout::centered(area, 64, 11).dialog;
+
+        frame.render_widget(Clear, dialog_area);
+
+        let block = Block::default()
+            .title(format!(" {} ", self.title))
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Yellow))
+            .style(theme.normal());
+
+        let inner = block.inner(dialog_area);
+        frame.render_widget(block, dialog_area);
+
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .margin(1)
+            .constraints([
+                Constraint::Min(2),    // Message
+                Constraint::Length(1), // Hints
+            ])
+            .split(inner);
+
+        frame.render_widget(
+            Paragraph::new(self.message.clone())
+                .style(theme.normal())
+                .wrap(Wrap { trim: true }),
+            chunks[0],
+        );
+        frame.render_widget(Paragraph::new("Enter/Esc=close").style(theme.dim()), chunks[1]);
+    }
+}
@@ -1,11 +1,170 @@
-//! Preferences dialog - placeholder for Phase 7
+//! Settings overview with the status of the scheduled tasks
+
+use chrono::Utc;
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    layout::{Constraint, Direction, Layout},
+    style::Modifier,
+    text::{Line, Span},
+    widgets::{Block, Borders, Cell, Clear, Paragraph, Row, Table, TableState},
+    Frame,
+};
+
+use crate::app::scheduler::TaskStatus;
+use crate::config::Settings;
+use crate::ui::layout::DialogLayout;
+use crate::ui::theme::Theme;
+use crate::utils::Formats;
+
+/// Outcome of a settings dialog key press
+pub enum PreferencesResult {
+    Close,
+    /// Change the prompt defaults while running
+    EditPromptDefaults,
+}
 
 pub struct PreferencesDialog {
-    // Will be implemented in Phase 7
+    /// Setting names and values, as in the config file
+    general: Vec<(&'static str, String)>,
+    schedule: Vec<TaskStatus>,
+    selected: usize,
+    formats: Formats,
 }
 
 impl PreferencesDialog {
-    pub fn new() -> Self {
-        Self {}
+    pub fn new(settings: &Settings, schedule: Vec<TaskStatus>) -> Self {
+        let general = vec![
+            ("database", settings.database_file()),
+            ("default_action", settings.default_action.to_string()),
+            ("default_duration", settings.default_duration.to_string()),
+            ("prompt_timeout", format!("{}s", settings.prompt_timeout)),
+            ("prompt_position", format!("{:?}", settings.prompt_position).to_lowercase()),
+            ("interception_mode", settings.interception_mode.label().to_lowercase()),
+            ("theme", settings.theme.clone()),
+            ("rule_hooks", settings.rule_hooks.len().to_string()),
+            ("update_check", if settings.update_check { "on" } else { "off" }.to_string()),
+        ];
+        Self {
+            general,
+            schedule,
+            selected: 0,
+            formats: Formats::default(),
+        }
+    }
+
+    pub fn with_formats(mut self, formats: Formats) -> Self {
+        self.formats = formats;
+        self
+    }
+
+    /// Handle key event, returns a result once the dialog should close
+    pub fn handle_key(&mut self, key: KeyEvent) -> Option<PreferencesResult> {
+        match key.code {
+            KeyCode::Down | KeyCode::Char('j') => {
+                self.selected = (self.selected + 1).min(self.schedule.len().saturating_sub(1));
+            }
+            KeyCode::Up | KeyCode::Char('k') => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Char('e') => return Some(PreferencesResult::EditPromptDefaults),
+            KeyCode::Esc | KeyCode::Enter | KeyCode::Char('q') => return Some(PreferencesResult::Close),
+            _ => {}
+        }
+        None
+    }
+
+    pub fn render(&self, frame: &mut Frame, theme: &Theme) {
+        let area = frame.area();
+        let dialog_area = DialogLayout::centered(area, 100, 22).dialog;
+
+        frame.render_widget(Clear, dialog_area);
+
+        let block = Block::default()
+            .title(" Settings ")
+            .borders(Borders::ALL)
+            .border_style(theme.border_focused())
+            .style(theme.normal());
+
+        let inner = block.inner(dialog_area);
+        frame.render_widget(block, dialog_area);
+
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .margin(1)
+            .constraints([
+                Constraint::Length(self.general.len() as u16 + 1), // General
+                Constraint::Length(1),                             // Tasks title
+                Constraint::Min(3),                                // Tasks
+                Constraint::Length(1),                             // Hints
+            ])
+            .split(inner);
+
+        let general: Vec<Line> = self
+            .general
+            .iter()
+            .map(|(name, value)| {
+                Line::from(vec![
+                    Span::styled(format!("{:18}", name), theme.dim()),
+                    Span::raw(self.formats.path(value).into_owned()),
+                ])
+            })
+            .collect();
+        frame.render_widget(Paragraph::new(general), chunks[0]);
+
+        frame.render_widget(
+            Paragraph::new("Scheduled tasks").style(theme.accent().add_modifier(Modifier::BOLD)),
+            chunks[1],
+        );
+        if self.schedule.is_empty() {
+            frame.render_widget(
+                Paragraph::new("No task is scheduled. Tasks are set up with scheduled_tasks in the config file.")
+                    .style(theme.dim()),
+                chunks[2],
+            );
+        } else {
+            let header = Row::new(["Name", "Task", "Schedule", "Last run", "Result", "Next run"])
+                .style(theme.accent().add_modifier(Modifier::BOLD));
+            let now = Utc::now();
+            let rows = self.schedule.iter().map(|task| {
+                let (last, result) = match &task.last_run {
+                    Some(run) => (
+                        Cell::from(self.formats.date_time(&run.time)),
+                        match &run.result {
+                            Ok(message) => Cell::from(self.formats.text(message).into_owned()).style(theme.success()),
+                            Err(e) => Cell::from(self.formats.text(e).into_owned()).style(theme.error()),
+                        },
+                    ),
+                    None => (Cell::from("never"), Cell::from("")),
+                };
+                let next = if task.next_run <= now {
+                    "due".to_string()
+                } else {
+                    self.formats.date_time(&task.next_run)
+                };
+                Row::new(vec![
+                    Cell::from(task.name.clone()),
+                    Cell::from(format!("{:?}", task.task).to_lowercase()),
+                    Cell::from(task.schedule.clone()),
+                    last,
+                    result,
+                    Cell::from(next),
+                ])
+            });
+            let table = Table::new(
+                rows,
+                [
+                    Constraint::Length(16),
+                    Constraint::Length(9),
+                    Constraint::Length(18),
+                    Constraint::Length(19),
+                    Constraint::Min(20),
+                    Constraint::Length(19),
+                ],
+            )
+            .header(header)
+            .row_highlight_style(theme.selected());
+            let mut state = TableState::default().with_selected(Some(self.selected));
+            frame.render_stateful_widget(table, chunks[2], &mut state);
+        }
+
+        frame.render_widget(Paragraph::new("↑↓=scroll  e=prompt defaults  Esc=close").style(theme.dim()), chunks[3]);
     }
 }
@@ -0,0 +1,157 @@
+//! Catalog of application profiles to apply or remove
+
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    layout::{Constraint, Direction, Layout},
+    style::Modifier,
+    widgets::{Block, Borders, Cell, Clear, Paragraph, Row, Table, TableState, Wrap},
+    Frame,
+};
+
+use crate::models::profile::catalog;
+use crate::models::{AppProfile, Rule};
+use crate::ui::layout::DialogLayout;
+use crate::ui::theme::Theme;
+
+/// Outcome of a key in the catalog
+pub enum ProfileCatalogResult {
+    /// Rules of the profile the node doesn't have yet
+    Apply(Vec<Rule>),
+    /// Names of the rules that came from the profile
+    Remove(Vec<String>),
+    Close,
+}
+
+pub struct ProfileCatalogDialog {
+    profiles: Vec<AppProfile>,
+    /// Rules of the node, to tell which profiles are applied
+    rules: Vec<Rule>,
+    selected: usize,
+}
+
+impl ProfileCatalogDialog {
+    pub fn new(rules: Vec<Rule>) -> Self {
+        Self {
+            profiles: catalog(),
+            rules,
+            selected: 0,
+        }
+    }
+
+    /// Rules of the node as they change
+    pub fn set_rules(&mut self, rules: Vec<Rule>) {
+        self.rules = rules;
+    }
+
+    /// Handle key event, returns a result for the tab to act on
+    pub fn handle_key(&mut self, key: KeyEvent) -> Option<ProfileCatalogResult> {
+        match key.code {
+            KeyCode::Down | KeyCode::Char('j') => {
+                self.selected = (self.selected + 1).min(self.profiles.len().saturating_sub(1));
+            }
+            KeyCode::Up | KeyCode::Char('k') => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Enter | KeyCode::Char('a') => {
+                let profile = self.profiles.get(self.selected)?;
+                let missing: Vec<Rule> = profile
+                    .rules()
+                    .into_iter()
+                    .filter(|rule| !self.rules.iter().any(|r| r.name == rule.name))
+                    .collect();
+                return (!missing.is_empty()).then_some(ProfileCatalogResult::Apply(missing));
+            }
+            KeyCode::Char('d') | KeyCode::Delete => {
+                let profile = self.profiles.get(self.selected)?;
+                let names: Vec<String> = profile.applied(&self.rules).iter().map(|r| r.name.clone()).collect();
+                return (!names.is_empty()).then_some(ProfileCatalogResult::Remove(names));
+            }
+            KeyCode::Esc | KeyCode::Char('q') => return Some(ProfileCatalogResult::Close),
+            _ => {}
+        }
+        None
+    }
+
+    pub fn render(&self, frame: &mut Frame, theme: &Theme) {
+        let area = frame.area();
+        let dialog_area = DialogLayout::centered(area, 90, 22).dialog;
+
+        frame.render_widget(Clear, dialog_area);
+
+        let block = Block::default()
+            .title(" Application Profiles ")
+            .borders(Borders::ALL)
+            .border_style(theme.border_focused())
+            .style(theme.normal());
+
+        let inner = block.inner(dialog_area);
+        frame.render_widget(block, dialog_area);
+
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .margin(1)
+            .constraints([
+                Constraint::Min(5),    // Profiles
+                Constraint::Length(5), // Rules of the selected one
+                Constraint::Length(1), // Hints
+            ])
+            .split(inner);
+
+        let header = Row::new(["Category", "Application", "Allows", "Applied"])
+            .style(theme.accent().add_modifier(Modifier::BOLD));
+        let rows = self.profiles.iter().map(|profile| {
+            let applied = profile.applied(&self.rules).len();
+            let status = match applied {
+                0 => Cell::from("-").style(theme.dim()),
+                n if n == profile.rules.len() => Cell::from("yes").style(theme.success()),
+                n => Cell::from(format!("{} of {}", n, profile.rules.len())).style(theme.warning()),
+            };
+            Row::new(vec![
+                Cell::from(profile.category),
+                Cell::from(profile.name),
+                Cell::from(profile.description),
+                status,
+            ])
+        });
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Length(17),
+                Constraint::Length(20),
+                Constraint::Min(30),
+                Constraint::Length(8),
+            ],
+        )
+        .header(header)
+        .row_highlight_style(theme.selected());
+        let mut state = TableState::default().with_selected(Some(self.selected));
+        frame.render_stateful_widget(table, chunks[0], &mut state);
+
+        if let Some(profile) = self.profiles.get(self.selected) {
+            let lines: Vec<String> = profile
+                .rules
+                .iter()
+                .map(|rule| {
+                    format!(
+                        "{}: {} to ports {}{}",
+                        rule.name,
+                        rule.process,
+                        rule.ports,
+                        rule.protocol.map(|p| format!(" ({})", p)).unwrap_or_default()
+                    )
+                })
+                .collect();
+            let rules_block = Block::default()
+                .title(" Rules ")
+                .borders(Borders::TOP)
+                .border_style(theme.border());
+            frame.render_widget(
+                Paragraph::new(lines.join("\n")).block(rules_block).style(theme.dim()).wrap(Wrap { trim: false }),
+                chunks[1],
+            );
+        }
+
+        frame.render_widget(
+            Paragraph::new("Enter/a=apply  d=remove its rules  ↑↓=select  Esc=close").style(theme.dim()),
+            chunks[2],
+        );
+    }
+}
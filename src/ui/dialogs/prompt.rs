@@ -1,10 +1,11 @@
 //! Connection prompt dialog
 
-use std::time::Instant;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::time::{Duration, Instant};
 
 use crossterm::event::{KeyCode, KeyEvent};
 use ratatui::{
-    layout::{Constraint, Direction, Layout},
+    layout::{Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
     text::{Line, Span},
     widgets::{Block, Borders, Clear, Gauge, Paragraph, Wrap},
@@ -12,15 +13,45 @@ use ratatui::{
 };
 use tokio::sync::oneshot;
 
+use crate::config::PromptPosition;
+use crate::models::merge::{extension, Extension};
+use crate::models::precedence::shadowing;
 use crate::models::{Connection, Operator, OperatorType, Rule, RuleAction, RuleDuration};
 use crate::ui::layout::DialogLayout;
 use crate::ui::theme::Theme;
+use crate::ui::widgets::clickable::{span_areas, ClickTargets};
+use crate::utils::{domain, text, Formats};
+
+/// Width of the full layout
+const PROMPT_WIDTH: u16 = 62;
+
+/// Width added to the full layout by the rule preview
+const PREVIEW_WIDTH: u16 = 48;
+
+/// Match options listed under "Apply to"
+const ADVANCED_OPTIONS: usize = 9;
+
+/// Identical prompts an answer can be given for ahead, cycled with `b`
+const BATCH_SIZES: [usize; 5] = [0, 5, 10, 25, 100];
+
+/// Durations an answer can be given for
+pub const PROMPT_DURATIONS: [RuleDuration; 7] = [
+    RuleDuration::Once,
+    RuleDuration::UntilRestart,
+    RuleDuration::Always,
+    RuleDuration::FiveMinutes,
+    RuleDuration::FifteenMinutes,
+    RuleDuration::ThirtyMinutes,
+    RuleDuration::OneHour,
+];
 
 /// Connection prompt dialog state
 pub struct PromptDialog {
     pub connection: Connection,
     pub node_addr: String,
     pub response_tx: Option<oneshot::Sender<Rule>>,
+    /// Row written ahead in the database
+    pub id: Option<i64>,
 
     // Selection state
     pub action: RuleAction,
@@ -35,10 +66,46 @@ pub struct PromptDialog {
     pub match_dest_port: bool,
     pub match_user: bool,
     pub match_checksum: bool,
+    /// The interface the connection goes out on, when known
+    pub match_iface: bool,
+    /// Generalized matches: any program in the same directory,
+    /// subdomains of the parent domain, the destination's network
+    pub match_path_dir: bool,
+    pub match_subdomains: bool,
+    pub match_network: bool,
+
+    // Rule preview
+    pub show_preview: bool,
+    /// Name given to the rule in place of the generated one
+    pub name: Option<String>,
+    /// Name being edited in the preview, and the cursor in it
+    name_input: Option<(String, usize)>,
+    /// First line of the rule shown by the preview
+    preview_scroll: u16,
 
     // Timeout tracking
     pub created_at: Instant,
     pub timeout_secs: u64,
+
+    /// Rules already on the node, to warn about dead rules
+    pub rules: Vec<Rule>,
+
+    /// Prompts waiting behind this one
+    pub queued: usize,
+    /// Identical prompts to come that get the same answer
+    pub batch: usize,
+    /// Rule sent back, None until answered or when skipped
+    answered: Option<Rule>,
+
+    /// Masking of hosts and paths
+    formats: Formats,
+
+    /// Centered or docked at the bottom
+    position: PromptPosition,
+    /// First line shown by the compact layout
+    scroll: u16,
+    /// Action choices on screen, answering when clicked
+    buttons: ClickTargets,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -58,6 +125,7 @@ impl PromptDialog {
             connection,
             node_addr,
             response_tx: Some(response_tx),
+            id: None,
             action: RuleAction::Allow,
             duration: RuleDuration::Once,
             focus: PromptFocus::Action,
@@ -68,11 +136,90 @@ impl PromptDialog {
             match_dest_port: false,
             match_user: false,
             match_checksum: false,
+            match_iface: false,
+            match_path_dir: false,
+            match_subdomains: false,
+            match_network: false,
+            show_preview: false,
+            name: None,
+            name_input: None,
+            preview_scroll: 0,
             created_at: Instant::now(),
             timeout_secs: 15,
+            rules: Vec::new(),
+            queued: 0,
+            batch: 0,
+            answered: None,
+            formats: Formats::default(),
+            position: PromptPosition::Center,
+            scroll: 0,
+            buttons: ClickTargets::default(),
         }
     }
 
+    pub fn with_rules(mut self, rules: Vec<Rule>) -> Self {
+        self.rules = rules;
+        self
+    }
+
+    pub fn with_formats(mut self, formats: Formats) -> Self {
+        self.formats = formats;
+        self
+    }
+
+    pub fn with_position(mut self, position: PromptPosition) -> Self {
+        self.position = position;
+        self
+    }
+
+    pub fn with_id(mut self, id: Option<i64>) -> Self {
+        self.id = id;
+        self
+    }
+
+    /// Answer picked when the prompt opens
+    pub fn with_defaults(mut self, action: RuleAction, duration: RuleDuration) -> Self {
+        self.action = action;
+        self.duration = duration;
+        self
+    }
+
+    /// Countdown of the configured prompt timeout
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout_secs = timeout.as_secs().max(1);
+        self
+    }
+
+    /// Shorten the countdown to end before the daemon stops waiting
+    pub fn with_deadline(mut self, deadline: Duration) -> Self {
+        self.timeout_secs = self.timeout_secs.min(deadline.as_secs()).max(1);
+        self
+    }
+
+    /// Warning when an existing rule would make the answer's rule ineffective
+    /// or redundant. Answers for this connection only are always effective.
+    pub fn shadow_warning(&self) -> Option<String> {
+        if self.duration == RuleDuration::Once {
+            return None;
+        }
+        let rule = self.create_rule();
+        shadowing(&self.rules, &rule, &self.connection).map(|shadow| shadow.message())
+    }
+
+    /// Existing rule the answer could be folded into instead of adding one.
+    /// Answers for this connection only never create a rule.
+    pub fn extension(&self) -> Option<Extension> {
+        if self.duration == RuleDuration::Once {
+            return None;
+        }
+        extension(&self.rules, &self.create_rule())
+    }
+
+    /// Rule the prompt was answered with, None while open or when skipped
+    pub fn answered(&self) -> Option<&Rule> {
+        self.answered.as_ref()
+    }
+
     /// Returns remaining seconds until timeout
     pub fn remaining_secs(&self) -> u64 {
         let elapsed = self.created_at.elapsed().as_secs();
@@ -86,6 +233,12 @@ impl PromptDialog {
     }
 
     pub fn handle_key(&mut self, key: KeyEvent) -> bool {
+        // Typing goes to the name until it's kept or dropped
+        if self.name_input.is_some() {
+            self.edit_name(key);
+            return false;
+        }
+
         match key.code {
             // Quick action keys
             KeyCode::Char('a') => {
@@ -100,6 +253,39 @@ impl PromptDialog {
                 self.action = RuleAction::Reject;
                 return self.confirm();
             }
+            // Answer with the existing rule extended, the daemon replaces it by name
+            KeyCode::Char('e') => {
+                if let (Some(ext), Some(tx)) = (self.extension(), self.response_tx.take()) {
+                    let _ = tx.send(ext.rule.clone());
+                    self.answered = Some(ext.rule);
+                    return true;
+                }
+            }
+
+            // Answer the next identical prompts the same way
+            KeyCode::Char('b') => {
+                let current = BATCH_SIZES.iter().position(|&n| n == self.batch).unwrap_or(0);
+                self.batch = BATCH_SIZES[(current + 1) % BATCH_SIZES.len()];
+            }
+            // Put this prompt back behind the others, unanswered
+            KeyCode::Char('n') if self.queued > 0 => return true,
+
+            // Rule the answer creates, and its name
+            KeyCode::Char('p') => {
+                self.show_preview = !self.show_preview;
+                self.preview_scroll = 0;
+            }
+            KeyCode::Char('N') => {
+                let name = self.rule_name();
+                self.show_preview = true;
+                self.name_input = Some((name.clone(), name.len()));
+            }
+            KeyCode::PageUp if self.show_preview => {
+                self.preview_scroll = self.preview_scroll.saturating_sub(5);
+            }
+            KeyCode::PageDown if self.show_preview => {
+                self.preview_scroll = self.preview_scroll.saturating_add(5);
+            }
 
             // Navigation
             KeyCode::Tab => {
@@ -144,15 +330,7 @@ impl PromptDialog {
                         };
                     }
                     PromptFocus::Duration => {
-                        let durations = [
-                            RuleDuration::Once,
-                            RuleDuration::UntilRestart,
-                            RuleDuration::Always,
-                            RuleDuration::FiveMinutes,
-                            RuleDuration::FifteenMinutes,
-                            RuleDuration::ThirtyMinutes,
-                            RuleDuration::OneHour,
-                        ];
+                        let durations = &PROMPT_DURATIONS;
                         let current = durations.iter().position(|d| d == &self.duration).unwrap_or(0);
                         let new_idx = if key.code == KeyCode::Left {
                             if current == 0 { durations.len() - 1 } else { current - 1 }
@@ -170,12 +348,15 @@ impl PromptDialog {
                 if self.advanced_focus > 0 {
                     self.advanced_focus -= 1;
                 } else {
-                    self.advanced_focus = 4; // 5 options (0-4)
+                    self.advanced_focus = ADVANCED_OPTIONS - 1;
                 }
             }
             KeyCode::Down if self.focus == PromptFocus::Advanced => {
-                self.advanced_focus = (self.advanced_focus + 1) % 5;
+                self.advanced_focus = (self.advanced_focus + 1) % ADVANCED_OPTIONS;
             }
+            // Scroll the compact layout, clamped when rendering
+            KeyCode::Up => self.scroll = self.scroll.saturating_sub(1),
+            KeyCode::Down => self.scroll = self.scroll.saturating_add(1),
 
             // Space to toggle advanced option or show advanced
             KeyCode::Char(' ') => {
@@ -187,6 +368,10 @@ impl PromptDialog {
                         2 => self.match_dest_port = !self.match_dest_port,
                         3 => self.match_user = !self.match_user,
                         4 => self.match_checksum = !self.match_checksum,
+                        5 => self.match_iface = !self.match_iface,
+                        6 => self.match_path_dir = !self.match_path_dir,
+                        7 => self.match_subdomains = !self.match_subdomains,
+                        8 => self.match_network = !self.match_network,
                         _ => {}
                     }
                 } else {
@@ -213,10 +398,46 @@ impl PromptDialog {
         false
     }
 
+    /// Edit the rule name: Enter keeps it, an empty one going back to the
+    /// generated name, and Esc drops the changes
+    fn edit_name(&mut self, key: KeyEvent) {
+        let Some((name, cursor)) = self.name_input.as_mut() else {
+            return;
+        };
+        match key.code {
+            KeyCode::Enter => {
+                let name = name.trim().to_string();
+                self.name = (!name.is_empty()).then_some(name);
+                self.name_input = None;
+            }
+            KeyCode::Esc => self.name_input = None,
+            KeyCode::Char(c) => text::insert(name, cursor, c),
+            KeyCode::Backspace => {
+                text::backspace(name, cursor);
+            }
+            KeyCode::Delete => {
+                text::delete(name, *cursor);
+            }
+            KeyCode::Left => *cursor = text::prev_boundary(name, *cursor),
+            KeyCode::Right => *cursor = text::next_boundary(name, *cursor),
+            KeyCode::Home => *cursor = 0,
+            KeyCode::End => *cursor = name.len(),
+            _ => {}
+        }
+    }
+
+    /// Answer without keys, e.g. from the command line
+    pub fn answer(&mut self, action: RuleAction, duration: RuleDuration) -> bool {
+        self.action = action;
+        self.duration = duration;
+        self.confirm()
+    }
+
     fn confirm(&mut self) -> bool {
         if let Some(tx) = self.response_tx.take() {
             let rule = self.create_rule();
-            let _ = tx.send(rule);
+            let _ = tx.send(rule.clone());
+            self.answered = Some(rule);
         }
         true
     }
@@ -227,36 +448,44 @@ impl PromptDialog {
             let mut rule = self.create_rule();
             rule.action = RuleAction::Allow;
             rule.duration = RuleDuration::Once;
-            let _ = tx.send(rule);
+            let _ = tx.send(rule.clone());
+            self.answered = Some(rule);
         }
         true
     }
 
-    fn create_rule(&self) -> Rule {
-        // Generate rule name based on process and destination
-        let name = format!(
-            "{}-{}",
-            self.connection.process_name(),
-            if !self.connection.dst_host.is_empty() {
-                self.connection.dst_host.split('.').next().unwrap_or("unknown")
-            } else {
-                &self.connection.dst_ip
-            }
-        );
+    /// Name given to the rule, or else one made of the process and destination
+    fn rule_name(&self) -> String {
+        self.name.clone().unwrap_or_else(|| self.connection.rule_name())
+    }
 
+    fn create_rule(&self) -> Rule {
         // Build operators based on selected options
         let mut operators = Vec::new();
 
-        // Always include process path as base
-        operators.push(Operator::simple("process.path", &self.connection.process_path));
+        // Always include process path as base, or its directory
+        match self.path_dir().filter(|_| self.match_path_dir) {
+            Some(dir) => operators.push(Operator::regexp("process.path", &format!("^{}/[^/]+$", regex::escape(dir)))),
+            None => operators.push(Operator::simple("process.path", &self.connection.process_path)),
+        }
 
-        // Add optional matchers
-        if self.match_dest_host && !self.connection.dst_host.is_empty() {
-            operators.push(Operator::simple("dest.host", &self.connection.dst_host));
+        // Add optional matchers, a generalized one in place of the exact one
+        match self.parent_domain().filter(|_| self.match_subdomains) {
+            Some(domain) => {
+                operators.push(Operator::regexp("dest.host", &format!(r"^(.*\.)?{}$", regex::escape(domain))));
+            }
+            None if self.match_dest_host && !self.connection.dst_host.is_empty() => {
+                operators.push(Operator::simple("dest.host", &self.connection.dst_host));
+            }
+            None => {}
         }
 
-        if self.match_dest_ip && !self.connection.dst_ip.is_empty() {
-            operators.push(Operator::simple("dest.ip", &self.connection.dst_ip));
+        match self.network().filter(|_| self.match_network) {
+            Some(cidr) => operators.push(Operator::network("dest.network", &cidr)),
+            None if self.match_dest_ip && !self.connection.dst_ip.is_empty() => {
+                operators.push(Operator::simple("dest.ip", &self.connection.dst_ip));
+            }
+            None => {}
         }
 
         if self.match_dest_port {
@@ -267,6 +496,10 @@ impl PromptDialog {
             operators.push(Operator::simple("user.id", &self.connection.user_id.to_string()));
         }
 
+        if self.match_iface && !self.connection.interface.is_empty() {
+            operators.push(Operator::simple("iface.out", &self.connection.interface));
+        }
+
         if self.match_checksum {
             if let Some(md5) = self.connection.process_checksums.get("md5") {
                 operators.push(Operator::simple("process.hash.md5", md5));
@@ -286,13 +519,222 @@ impl PromptDialog {
             }
         };
 
-        Rule::new(&name, self.action, self.duration.clone(), operator)
+        Rule::new(&self.rule_name(), self.action, self.duration.clone(), operator)
+    }
+
+    /// Rule the answer sends, with the name as typed so far. Values are
+    /// hidden along with the rest of the prompt's.
+    fn preview_rule(&self) -> Rule {
+        fn hide(operator: &mut Operator, formats: &Formats) {
+            operator.data = formats.text(&operator.data).into_owned();
+            for op in &mut operator.list {
+                hide(op, formats);
+            }
+        }
+        let mut rule = self.create_rule();
+        if let Some((name, _)) = &self.name_input {
+            rule.name = name.clone();
+        }
+        hide(&mut rule.operator, &self.formats);
+        rule
+    }
+
+    /// The previewed rule's answer and operator tree, then its JSON as sent
+    /// to the daemon
+    fn preview_lines(&self, theme: &Theme) -> Vec<Line<'static>> {
+        fn tree(operator: &Operator, prefix: &str, lines: &mut Vec<Line<'static>>, theme: &Theme) {
+            for (n, op) in operator.list.iter().enumerate() {
+                let last = n + 1 == operator.list.len();
+                let (branch, stem) = if last { ("└─", "   ") } else { ("├─", "│  ") };
+                lines.push(Line::from(vec![
+                    Span::raw(format!("{}{} ", prefix, branch)),
+                    Span::styled(format!("{} ", op.operand), theme.normal().add_modifier(Modifier::BOLD)),
+                    Span::styled(format!("{} ", op.op_type), theme.dim()),
+                    Span::raw(op.data.clone()),
+                ]));
+                tree(op, &format!("{}{}", prefix, stem), lines, theme);
+            }
+        }
+
+        let rule = self.preview_rule();
+        let action = rule.action.to_string();
+        let mut lines = vec![Line::from(vec![
+            Span::styled(Theme::action_label(&action), theme.action_style(&action)),
+            Span::raw(format!(" {}", rule.duration)),
+        ])];
+        let root = &rule.operator;
+        if root.list.is_empty() {
+            lines.push(Line::from(vec![
+                Span::styled(format!("{} ", root.operand), theme.normal().add_modifier(Modifier::BOLD)),
+                Span::styled(format!("{} ", root.op_type), theme.dim()),
+                Span::raw(root.data.clone()),
+            ]));
+        } else {
+            lines.push(Line::from(Span::styled(format!("{} of", root.op_type), theme.dim())));
+            tree(root, "", &mut lines, theme);
+        }
+        lines.push(Line::from(""));
+        let json = serde_json::to_string_pretty(&rule).unwrap_or_else(|e| format!("Failed to serialize rule: {}", e));
+        lines.extend(json.lines().map(|line| Line::from(Span::styled(line.to_string(), theme.dim()))));
+        lines
+    }
+
+    /// Rule name line of the preview, the edited name with its cursor
+    fn name_line(&self, theme: &Theme) -> Line<'static> {
+        let name = match &self.name_input {
+            Some((name, _)) => Span::styled(name.clone(), Style::default().add_modifier(Modifier::UNDERLINED)),
+            None => Span::styled(self.rule_name(), Style::default().fg(Color::Cyan)),
+        };
+        Line::from(vec![Span::styled("Name: ", theme.dim()), name])
+    }
+
+    fn full_width(&self) -> u16 {
+        if self.show_preview { PROMPT_WIDTH + PREVIEW_WIDTH } else { PROMPT_WIDTH }
+    }
+
+    fn full_height(&self) -> u16 {
+        if self.show_advanced { 32 } else { 22 }
+    }
+
+    /// Whether the full layout doesn't fit and the compact one is used
+    pub fn is_compact(&self, area: Rect) -> bool {
+        area.width < self.full_width() || area.height < self.full_height()
+    }
+
+    fn placement(&self, area: Rect, width: u16, height: u16) -> Rect {
+        match self.position {
+            PromptPosition::Center => DialogLayout::centered(area, width, height).dialog,
+            PromptPosition::Bottom => DialogLayout::docked(area, height).dialog,
+        }
+    }
+
+    /// A dead rule warning, or an existing rule the answer could extend
+    fn notice(&self) -> Option<Line<'static>> {
+        if let Some(warning) = self.shadow_warning() {
+            return Some(Line::from(Span::styled(
+                format!("  ⚠ {}", warning),
+                Style::default().fg(Color::Yellow),
+            )));
+        }
+        let ext = self.extension()?;
+        let value = match ext.operand.as_str() {
+            "dest.host" | "dest.ip" => self.formats.host(&ext.value),
+            _ => ext.value.as_str().into(),
+        };
+        Some(Line::from(Span::styled(
+            format!("  ↳ '{}' can cover {} as well, e=extend it instead", ext.rule.name, value),
+            Style::default().fg(Color::Cyan),
+        )))
+    }
+
+    /// Prompts waiting and the batch answer, once either is in use
+    fn queue_line(&self) -> Option<Line<'static>> {
+        if self.queued == 0 && self.batch == 0 {
+            return None;
+        }
+        let batch = match self.batch {
+            0 => "b=batch".to_string(),
+            n => format!("b=batch: next {} alike", n),
+        };
+        let mut text = format!("  {} queued  {}", self.queued, batch);
+        if self.queued > 0 {
+            text.push_str("  n=skip");
+        }
+        let style = if self.batch > 0 {
+            Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)
+        } else {
+            Style::default().fg(Color::Cyan)
+        };
+        Some(Line::from(Span::styled(text, style)))
+    }
+
+    fn destination(&self) -> String {
+        let host = if self.connection.dst_host.is_empty() {
+            &self.connection.dst_ip
+        } else {
+            &self.connection.dst_host
+        };
+        format!("{}:{}", self.formats.host(host), self.connection.dst_port)
+    }
+
+    /// Directory of the executable, for matching every program in it
+    fn path_dir(&self) -> Option<&str> {
+        let (dir, _) = self.connection.process_path.rsplit_once('/')?;
+        (!dir.is_empty()).then_some(dir)
+    }
+
+    /// Host without its first label, for matching it and its siblings.
+    /// A host right under a public suffix is its own parent, a public
+    /// suffix has none.
+    fn parent_domain(&self) -> Option<&str> {
+        let host = self.connection.dst_host.as_str();
+        if host.is_empty() || host.parse::<IpAddr>().is_ok() || domain::is_public_suffix(host) {
+            return None;
+        }
+        match host.split_once('.') {
+            Some((_, parent)) if !domain::is_public_suffix(parent) => Some(parent),
+            Some(_) => Some(host),
+            None => None,
+        }
+    }
+
+    /// The /24 (IPv4) or /64 (IPv6) network of the destination
+    fn network(&self) -> Option<String> {
+        match self.connection.dst_ip.parse::<IpAddr>().ok()? {
+            IpAddr::V4(ip) => {
+                let [a, b, c, _] = ip.octets();
+                Some(format!("{}/24", Ipv4Addr::new(a, b, c, 0)))
+            }
+            IpAddr::V6(ip) => {
+                let prefix = u128::from(ip) & !(u128::MAX >> 64);
+                Some(format!("{}/64", Ipv6Addr::from(prefix)))
+            }
+        }
+    }
+
+    fn advanced_options(&self) -> [(String, bool, bool); ADVANCED_OPTIONS] {
+        let path_dir = self.path_dir().map(|dir| self.formats.path(dir).into_owned());
+        let parent = self.parent_domain().map(|domain| self.formats.host(domain).into_owned());
+        let network = self.network();
+        let iface = self.connection.interface.as_str();
+        [
+            ("Destination host".to_string(), self.match_dest_host, !self.connection.dst_host.is_empty()),
+            ("Destination IP".to_string(), self.match_dest_ip, !self.connection.dst_ip.is_empty()),
+            ("Destination port".to_string(), self.match_dest_port, true),
+            ("This user".to_string(), self.match_user, true),
+            ("Executable checksum".to_string(), self.match_checksum, self.connection.process_checksums.contains_key("md5")),
+            (
+                format!("Out on {}", if iface.is_empty() { "its interface" } else { iface }),
+                self.match_iface,
+                !iface.is_empty(),
+            ),
+            (
+                format!("Any program in {}/", path_dir.as_deref().unwrap_or("its directory")),
+                self.match_path_dir,
+                path_dir.is_some(),
+            ),
+            (format!("*.{}", parent.as_deref().unwrap_or("domain")), self.match_subdomains, parent.is_some()),
+            (
+                format!("Network {}", network.as_deref().unwrap_or("of the IP")),
+                self.match_network,
+                network.is_some(),
+            ),
+        ]
+    }
+
+    /// Key of the action choice at a clicked position
+    pub fn click(&self, column: u16, row: u16) -> Option<KeyEvent> {
+        self.buttons.key_at(column, row)
     }
 
     pub fn render(&self, frame: &mut Frame, theme: &Theme) {
         let area = frame.area();
-        let height = if self.show_advanced { 28 } else { 22 };
-        let dialog_area = DialogLayout::centered(area, 62, height).dialog;
+        self.buttons.clear();
+        if self.is_compact(area) {
+            self.render_compact(frame, theme);
+            return;
+        }
+        let dialog_area = self.placement(area, self.full_width(), self.full_height());
 
         // Clear background
         frame.render_widget(Clear, dialog_area);
@@ -308,7 +750,16 @@ impl PromptDialog {
 
         frame.render_widget(block.clone(), dialog_area);
 
-        let inner = block.inner(dialog_area);
+        let mut inner = block.inner(dialog_area);
+
+        if self.show_preview {
+            let panes = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([Constraint::Length(PROMPT_WIDTH - 2), Constraint::Min(20)])
+                .split(inner);
+            inner = panes[0];
+            self.render_preview(frame, panes[1], theme);
+        }
 
         // Layout - dynamic based on advanced options
         let constraints = if self.show_advanced {
@@ -316,7 +767,7 @@ impl PromptDialog {
                 Constraint::Length(5), // Connection info
                 Constraint::Length(3), // Action
                 Constraint::Length(3), // Duration
-                Constraint::Length(7), // Advanced options
+                Constraint::Length(ADVANCED_OPTIONS as u16 + 2), // Advanced options
                 Constraint::Length(2), // Timeout bar
                 Constraint::Min(1),    // Hints
             ]
@@ -348,15 +799,12 @@ impl PromptDialog {
             Line::from(""),
             Line::from(vec![
                 Span::raw("  Destination: "),
-                Span::styled(
-                    self.connection.destination(),
-                    Style::default().fg(Color::Yellow),
-                ),
+                Span::styled(self.destination(), Style::default().fg(Color::Yellow)),
                 Span::raw(format!(" ({})", self.connection.protocol)),
             ]),
             Line::from(vec![
                 Span::raw("  Process: "),
-                Span::styled(&self.connection.process_path, theme.dim()),
+                Span::styled(self.formats.path(&self.connection.process_path), theme.dim()),
             ]),
             Line::from(vec![
                 Span::raw("  User: "),
@@ -399,6 +847,11 @@ impl PromptDialog {
             },
         ];
 
+        let areas = span_areas(action_block.inner(chunks[1]), &action_spans);
+        for (i, key) in [(1, 'a'), (3, 'd'), (5, 'r')] {
+            self.buttons.add(areas[i], KeyCode::Char(key));
+        }
+
         let action_para = Paragraph::new(Line::from(action_spans))
             .block(action_block);
         frame.render_widget(action_para, chunks[1]);
@@ -438,13 +891,7 @@ impl PromptDialog {
                     theme.border()
                 });
 
-            let options = [
-                ("Destination host", self.match_dest_host, !self.connection.dst_host.is_empty()),
-                ("Destination IP", self.match_dest_ip, !self.connection.dst_ip.is_empty()),
-                ("Destination port", self.match_dest_port, true),
-                ("This user", self.match_user, true),
-                ("Executable checksum", self.match_checksum, self.connection.process_checksums.contains_key("md5")),
-            ];
+            let options = self.advanced_options();
 
             let option_lines: Vec<Line> = options
                 .iter()
@@ -480,18 +927,149 @@ impl PromptDialog {
         let gauge = Gauge::default()
             .gauge_style(Style::default().fg(color))
             .ratio(ratio)
-            .label(format!("Timeout: {}s", remaining));
+            .label(format!("Timeout: {}s of {}s", remaining, self.timeout_secs));
         frame.render_widget(gauge, chunks[timeout_chunk_idx]);
 
         // Hints
-        let hint_text = if self.show_advanced {
-            "Enter=confirm  Esc=cancel  Tab=navigate  Space=toggle"
-        } else {
-            "Enter=confirm  Esc=cancel  Tab=navigate  Space=advanced"
+        let hint_text = match (self.show_advanced, self.show_preview) {
+            (true, true) => "Enter=confirm  Esc=cancel  Tab=navigate  Space=toggle  p=hide rule",
+            (true, false) => "Enter=confirm  Esc=cancel  Tab=navigate  Space=toggle  p=show rule",
+            (false, true) => "Enter=confirm  Esc=cancel  Tab=navigate  Space=advanced  p=hide rule",
+            (false, false) => "Enter=confirm  Esc=cancel  Tab=navigate  Space=advanced  p=show rule",
         };
-        let hints = Paragraph::new(format!("  {}", hint_text))
-            .style(theme.dim())
-            .wrap(Wrap { trim: true });
+        let mut hint_lines: Vec<Line> = self.notice().into_iter().chain(self.queue_line()).collect();
+        hint_lines.push(Line::from(Span::styled(format!("  {}", hint_text), theme.dim())));
+        let hints = Paragraph::new(hint_lines).wrap(Wrap { trim: true });
         frame.render_widget(hints, chunks[hints_chunk_idx]);
     }
+
+    /// The rule the answer creates, beside the options
+    fn render_preview(&self, frame: &mut Frame, area: Rect, theme: &Theme) {
+        let title = if self.name_input.is_some() {
+            " Rule (Enter=keep name  Esc=undo) "
+        } else {
+            " Rule (N=name  PgUp/PgDn=scroll) "
+        };
+        let block = Block::default()
+            .title(title)
+            .borders(Borders::LEFT)
+            .border_style(theme.border());
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .margin(1)
+            .constraints([Constraint::Length(2), Constraint::Min(1)])
+            .split(inner);
+        frame.render_widget(Paragraph::new(self.name_line(theme)), chunks[0]);
+        if let Some((name, cursor)) = &self.name_input {
+            let column = 6 + text::column(name, *cursor) as u16;
+            frame.set_cursor_position((chunks[0].x + column.min(chunks[0].width.saturating_sub(1)), chunks[0].y));
+        }
+
+        let lines = self.preview_lines(theme);
+        let max_scroll = lines.len().saturating_sub(chunks[1].height as usize) as u16;
+        let preview = Paragraph::new(lines).scroll((self.preview_scroll.min(max_scroll), 0));
+        frame.render_widget(preview, chunks[1]);
+    }
+
+    /// Single column for small terminals, scrolled to keep the focus visible
+    fn render_compact(&self, frame: &mut Frame, theme: &Theme) {
+        let selected = |focused: bool| if focused { "▶ " } else { "  " };
+        let choice = |label: &str, key: char, chosen: bool, color: Color| {
+            if chosen {
+                Span::styled(format!("[{}] {}", key, label.to_uppercase()), Style::default().fg(color).add_modifier(Modifier::BOLD))
+            } else {
+                Span::styled(format!(" {} {}", key, label), theme.dim())
+            }
+        };
+
+        let mut lines = vec![
+            Line::from(vec![
+                Span::styled(
+                    self.connection.process_name().to_string(),
+                    Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD),
+                ),
+                Span::raw(" → "),
+                Span::styled(self.destination(), Style::default().fg(Color::Yellow)),
+                Span::raw(format!(" ({})", self.connection.protocol)),
+            ]),
+            Line::from(Span::styled(self.formats.path(&self.connection.process_path).to_string(), theme.dim())),
+            Line::from(format!("UID {} | PID {}", self.connection.user_id, self.connection.process_id)),
+            Line::from(vec![
+                Span::raw(selected(self.focus == PromptFocus::Action)),
+                choice("allow", 'a', self.action == RuleAction::Allow, Color::Green),
+                Span::raw(" "),
+                choice("deny", 'd', self.action == RuleAction::Deny, Color::Red),
+                Span::raw(" "),
+                choice("reject", 'r', self.action == RuleAction::Reject, Color::Yellow),
+            ]),
+            Line::from(format!("{}◄ {} ►", selected(self.focus == PromptFocus::Duration), self.duration)),
+        ];
+        // Line to keep on screen
+        let mut focus_line = match self.focus {
+            PromptFocus::Action => 3,
+            _ => 4,
+        };
+        if self.show_advanced {
+            lines.push(Line::from("Apply to (Space=toggle)"));
+            let advanced_focused = self.focus == PromptFocus::Advanced;
+            for (i, (label, checked, available)) in self.advanced_options().into_iter().enumerate() {
+                let focused = advanced_focused && i == self.advanced_focus;
+                if focused {
+                    focus_line = lines.len();
+                }
+                let style = if available { theme.normal() } else { theme.dim() };
+                let checkbox = if checked { "[x]" } else { "[ ]" };
+                lines.push(Line::from(Span::styled(format!("{}{} {}", selected(focused), checkbox, label), style)));
+            }
+        }
+        if self.show_preview {
+            let title = if self.name_input.is_some() { "Rule (Enter=keep name Esc=undo)" } else { "Rule (N=name)" };
+            lines.push(Line::from(title));
+            if self.name_input.is_some() {
+                focus_line = lines.len();
+            }
+            lines.push(self.name_line(theme));
+            lines.extend(self.preview_lines(theme));
+        }
+        lines.extend(self.notice());
+        lines.extend(self.queue_line());
+        lines.push(Line::from(Span::styled(
+            "Enter=confirm Esc=cancel Tab=next Space=advanced p=rule ↑↓=scroll",
+            theme.dim(),
+        )));
+
+        let area = frame.area();
+        let width = area.width.min(PROMPT_WIDTH);
+        let dialog_area = self.placement(area, width, lines.len() as u16 + 2);
+        frame.render_widget(Clear, dialog_area);
+
+        let height = dialog_area.height.saturating_sub(2) as usize;
+        let max_scroll = lines.len().saturating_sub(height);
+        let mut scroll = (self.scroll as usize).min(max_scroll);
+        if focus_line < scroll {
+            scroll = focus_line;
+        } else if height > 0 && focus_line >= scroll + height {
+            scroll = focus_line + 1 - height;
+        }
+
+        let remaining = self.remaining_secs();
+        let more = match (scroll > 0, scroll < max_scroll) {
+            (true, true) => " ↕",
+            (true, false) => " ↑",
+            (false, true) => " ↓",
+            (false, false) => "",
+        };
+        let ratio = self.timeout_ratio();
+        let timeout_style = if ratio > 0.25 { theme.border_focused() } else { Style::default().fg(Color::Red) };
+        let block = Block::default()
+            .title(Span::styled(format!(" New Connection ({remaining}s){more} "), timeout_style))
+            .borders(Borders::ALL)
+            .border_style(theme.border_focused())
+            .style(theme.normal());
+        let paragraph = Paragraph::new(lines).block(block).scroll((scroll as u16, 0));
+        frame.render_widget(paragraph, dialog_area);
+    }
 }
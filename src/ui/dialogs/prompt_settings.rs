@@ -0,0 +1,167 @@
+//! Change how unanswered prompts are handled while running
+
+use std::time::Duration;
+
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    layout::{Constraint, Direction, Layout},
+    style::Modifier,
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph, Wrap},
+    Frame,
+};
+
+use crate::config::Settings;
+use crate::models::node::{ask_deadline, DEFAULT_ASK_TIMEOUT};
+use crate::models::{RuleAction, RuleDuration};
+use crate::ui::dialogs::prompt::PROMPT_DURATIONS;
+use crate::ui::layout::DialogLayout;
+use crate::ui::theme::Theme;
+
+/// Seconds the timeout changes by with each key press
+const TIMEOUT_STEP: u64 = 5;
+
+/// Longest prompt timeout offered, daemons stop waiting for an answer then
+const MAX_TIMEOUT: u64 = DEFAULT_ASK_TIMEOUT;
+
+const ACTIONS: [RuleAction; 3] = [RuleAction::Allow, RuleAction::Deny, RuleAction::Reject];
+
+/// Outcome of the dialog
+pub enum PromptSettingsResult {
+    /// Apply and keep in the config file
+    Save {
+        default_action: RuleAction,
+        default_duration: RuleDuration,
+        prompt_timeout: Duration,
+    },
+    Close,
+}
+
+pub struct PromptSettingsDialog {
+    pub default_action: RuleAction,
+    pub default_duration: RuleDuration,
+    /// Seconds
+    pub prompt_timeout: u64,
+    /// Field being changed: action, duration or timeout
+    selected: usize,
+}
+
+impl PromptSettingsDialog {
+    pub fn new(settings: &Settings) -> Self {
+        Self {
+            default_action: settings.default_action,
+            default_duration: settings.default_duration.clone(),
+            prompt_timeout: settings.prompt_timeout.clamp(1, MAX_TIMEOUT),
+            selected: 0,
+        }
+    }
+
+    fn change(&mut self, forward: bool) {
+        let step = |current: usize, len: usize| if forward { (current + 1) % len } else { (current + len - 1) % len };
+        match self.selected {
+            0 => {
+                let current = ACTIONS.iter().position(|a| *a == self.default_action).unwrap_or(0);
+                self.default_action = ACTIONS[step(current, ACTIONS.len())];
+            }
+            1 => {
+                let current = PROMPT_DURATIONS.iter().position(|d| *d == self.default_duration).unwrap_or(0);
+                self.default_duration = PROMPT_DURATIONS[step(current, PROMPT_DURATIONS.len())].clone();
+            }
+            _ => {
+                // Back onto the steps from an odd configured value
+                let timeout = if forward {
+                    (self.prompt_timeout / TIMEOUT_STEP + 1) * TIMEOUT_STEP
+                } else {
+                    (self.prompt_timeout - 1) / TIMEOUT_STEP * TIMEOUT_STEP
+                };
+                self.prompt_timeout = timeout.clamp(TIMEOUT_STEP, MAX_TIMEOUT);
+            }
+        }
+    }
+
+    /// Handle key event, returns a result once the dialog should close
+    pub fn handle_key(&mut self, key: KeyEvent) -> Option<PromptSettingsResult> {
+        match key.code {
+            KeyCode::Down | KeyCode::Char('j') | KeyCode::Tab => self.selected = (self.selected + 1).min(2),
+            KeyCode::Up | KeyCode::Char('k') | KeyCode::BackTab => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Left | KeyCode::Char('h') | KeyCode::Char('-') => self.change(false),
+            KeyCode::Right | KeyCode::Char('l') | KeyCode::Char('+') | KeyCode::Char(' ') => self.change(true),
+            KeyCode::Enter => {
+                return Some(PromptSettingsResult::Save {
+                    default_action: self.default_action,
+                    default_duration: self.default_duration.clone(),
+                    prompt_timeout: Duration::from_secs(self.prompt_timeout),
+                })
+            }
+            KeyCode::Esc | KeyCode::Char('q') => return Some(PromptSettingsResult::Close),
+            _ => {}
+        }
+        None
+    }
+
+    pub fn render(&self, frame: &mut Frame, theme: &Theme) {
+        let area = frame.area();
+        let dialog_area = DialogLayout::centered(area, 60, 14).dialog;
+
+        frame.render_widget(Clear, dialog_area);
+
+        let block = Block::default()
+            .title(" Prompt Defaults ")
+            .borders(Borders::ALL)
+            .border_style(theme.border_focused())
+            .style(theme.normal());
+
+        let inner = block.inner(dialog_area);
+        frame.render_widget(block, dialog_area);
+
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .margin(1)
+            .constraints([
+                Constraint::Length(4), // Intro
+                Constraint::Length(3), // Fields
+                Constraint::Min(0),
+                Constraint::Length(1), // Hints
+            ])
+            .split(inner);
+
+        frame.render_widget(
+            Paragraph::new(format!(
+                "The answer preselected in prompts, and given when none comes in time. Daemons stop \
+                 waiting after {}s, prompts close after {}s at most so the answer still arrives.",
+                MAX_TIMEOUT,
+                ask_deadline(MAX_TIMEOUT).as_secs()
+            ))
+                .style(theme.dim())
+                .wrap(Wrap { trim: true }),
+            chunks[0],
+        );
+
+        let fields = [
+            ("default_action", self.default_action.to_string()),
+            ("default_duration", self.default_duration.to_string()),
+            ("prompt_timeout", format!("{}s", self.prompt_timeout)),
+        ];
+        let lines: Vec<Line> = fields
+            .into_iter()
+            .enumerate()
+            .map(|(i, (name, value))| {
+                let style = if i == self.selected {
+                    theme.selected().add_modifier(Modifier::BOLD)
+                } else {
+                    theme.normal()
+                };
+                Line::from(vec![
+                    Span::styled(format!("{:18}", name), theme.dim()),
+                    Span::styled(format!("◄ {} ►", value), style),
+                ])
+            })
+            .collect();
+        frame.render_widget(Paragraph::new(lines), chunks[1]);
+
+        frame.render_widget(
+            Paragraph::new("↑↓=field  ←→=change  Enter=save  Esc=cancel").style(theme.dim()),
+            chunks[3],
+        );
+    }
+}
@@ -2,18 +2,23 @@
 
 use crossterm::event::{KeyCode, KeyEvent};
 use ratatui::{
-    layout::{Constraint, Direction, Layout},
+    layout::{Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
+    text::Line,
     widgets::{Block, Borders, Clear, Paragraph, Wrap},
     Frame,
 };
 
-use crate::models::{Operator, OperatorType, Rule, RuleAction, RuleDuration};
+use crate::models::precedence::conflicts;
+use crate::models::template::{TemplateVars, PLACEHOLDERS};
+use crate::models::{DaemonVersion, Event, Feature, Operator, OperatorType, Rule, RuleAction, RuleDuration};
+use crate::ui::dialogs::list_operator::ListOperatorEditor;
 use crate::ui::layout::DialogLayout;
 use crate::ui::theme::Theme;
+use crate::utils::{text, Formats};
 
 /// Available operand options for rules
-const OPERANDS: &[&str] = &[
+pub const OPERANDS: &[&str] = &[
     "process.path",
     "process.command",
     "process.id",
@@ -107,6 +112,8 @@ pub struct RuleEditorDialog {
     pub operator_type: OperatorType,
     pub operand_idx: usize,  // Index into OPERANDS
     pub data: String,
+    /// Entries of a list operator, each matched on its own
+    pub list: Vec<Operator>,
     pub enabled: bool,
     pub precedence: bool,
     pub nolog: bool,
@@ -116,6 +123,29 @@ pub struct RuleEditorDialog {
 
     // Cursor position for text editing
     cursor_pos: usize,
+
+    /// Entries of a list operator being edited
+    list_editor: Option<ListOperatorEditor>,
+
+    /// Show the JSON preview pane
+    pub show_preview: bool,
+
+    /// Version of the node the rule is sent to, for feature gating
+    daemon_version: Option<DaemonVersion>,
+
+    /// Rules already on the node, to warn about conflicts (None = not loaded yet)
+    rules: Option<Vec<Rule>>,
+    /// Conflicts found on the last save, saving again confirms
+    conflicts: Vec<String>,
+    /// Placeholder left without a value on the last save
+    template_error: Option<String>,
+    /// Recent connections, newest first, to show what the rule would match
+    recent: Vec<Event>,
+    /// Positions in `recent` of the connections the rule as edited matches
+    matching: Vec<usize>,
+    /// Node the rule is for, placeholders expand for it
+    node_addr: String,
+    formats: Formats,
 }
 
 impl RuleEditorDialog {
@@ -132,11 +162,22 @@ impl RuleEditorDialog {
             operator_type: OperatorType::Simple,
             operand_idx: 0, // process.path
             data: String::new(),
+            list: Vec::new(),
             enabled: true,
             precedence: false,
             nolog: false,
             original_name: None,
             cursor_pos: 0,
+            list_editor: None,
+            show_preview: true,
+            daemon_version: None,
+            rules: None,
+            conflicts: Vec::new(),
+            template_error: None,
+            recent: Vec::new(),
+            matching: Vec::new(),
+            node_addr: String::new(),
+            formats: Formats::default(),
         }
     }
 
@@ -158,11 +199,114 @@ impl RuleEditorDialog {
             operator_type: rule.operator.op_type.clone(),
             operand_idx,
             data: rule.operator.data.clone(),
+            list: rule.operator.list.clone(),
             enabled: rule.enabled,
             precedence: rule.precedence,
             nolog: rule.nolog,
             original_name: Some(rule.name.clone()),
             cursor_pos: rule.name.len(),
+            list_editor: None,
+            show_preview: true,
+            daemon_version: None,
+            rules: None,
+            conflicts: Vec::new(),
+            template_error: None,
+            recent: Vec::new(),
+            matching: Vec::new(),
+            node_addr: String::new(),
+            formats: Formats::default(),
+        }
+    }
+
+    /// Create editor for a new rule, starting from `rule`
+    pub fn create_from(rule: &Rule) -> Self {
+        Self {
+            mode: EditorMode::Create,
+            original_name: None,
+            ..Self::edit(rule)
+        }
+    }
+
+    /// Gate operands on the target node's daemon version
+    pub fn with_daemon_version(mut self, version: Option<DaemonVersion>) -> Self {
+        self.daemon_version = version;
+        self
+    }
+
+    /// Node the rule is saved to
+    pub fn with_node_addr(mut self, node_addr: &str) -> Self {
+        self.node_addr = node_addr.to_string();
+        self
+    }
+
+    /// Number and date formatting, masking the matched connections in private mode
+    pub fn with_formats(mut self, formats: Formats) -> Self {
+        self.formats = formats;
+        self
+    }
+
+    /// Recent connections, newest first, checked against the rule while it is edited
+    pub fn set_recent_events(&mut self, events: Vec<Event>) {
+        self.recent = events;
+        self.refresh_matches();
+    }
+
+    /// Recent connections the rule as currently edited matches
+    pub fn matching_events(&self) -> Vec<&Event> {
+        self.matching.iter().filter_map(|&i| self.recent.get(i)).collect()
+    }
+
+    fn refresh_matches(&mut self) {
+        self.matching.clear();
+        if !self.has_condition() || self.recent.is_empty() {
+            return;
+        }
+        // Placeholders as they will be saved, as typed when they can't be
+        let mut rule = self.build_rule();
+        let _ = rule.expand_placeholders(&self.template_vars(&rule));
+        self.matching = self
+            .recent
+            .iter()
+            .enumerate()
+            .filter(|(_, event)| rule.operator.matches(&event.connection))
+            .map(|(i, _)| i)
+            .collect();
+    }
+
+    /// Rules already on the node, checked for conflicts on save
+    pub fn set_rules(&mut self, rules: Vec<Rule>) {
+        self.rules = Some(rules);
+    }
+
+    pub fn wants_rules(&self) -> bool {
+        self.rules.is_none()
+    }
+
+    /// Why the target node can't honor the current rule, if it can't
+    pub fn compat_warning(&self) -> Option<String> {
+        let version = self.daemon_version?;
+        let operands: Vec<&str> = if self.is_list() {
+            self.list.iter().map(|e| e.operand.as_str()).collect()
+        } else {
+            vec![self.operand()]
+        };
+        operands
+            .into_iter()
+            .filter_map(Feature::for_operand)
+            .find(|f| !f.supported_by(Some(version)))
+            .map(|f| f.unsupported_message(version))
+    }
+
+    fn is_list(&self) -> bool {
+        self.operator_type == OperatorType::List
+    }
+
+    /// Whether there is something to match on: data, or list entries all with data
+    fn has_condition(&self) -> bool {
+        if self.is_list() {
+            !self.list.is_empty() && self.list.iter().all(|e| !e.data.is_empty())
+        } else {
+            !self.data.is_empty()
         }
     }
 
@@ -173,12 +317,16 @@ impl RuleEditorDialog {
 
     /// Build rule from current state
     pub fn build_rule(&self) -> Rule {
-        let operator = Operator {
-            op_type: self.operator_type.clone(),
-            operand: self.operand().to_string(),
-            data: self.data.clone(),
-            sensitive: false,
-            list: Vec::new(),
+        let operator = if self.is_list() {
+            Operator::list(self.list.clone())
+        } else {
+            Operator {
+                op_type: self.operator_type.clone(),
+                operand: self.operand().to_string(),
+                data: self.data.clone(),
+                sensitive: false,
+                list: Vec::new(),
+            }
         };
 
         let mut rule = Rule::new(&self.name, self.action, self.duration.clone(), operator);
@@ -189,27 +337,70 @@ impl RuleEditorDialog {
         rule
     }
 
+    /// Placeholder values on the rule's node, for the user of the newest
+    /// recent connection the rule matches as it is
+    fn template_vars(&self, rule: &Rule) -> TemplateVars {
+        let uid = self
+            .recent
+            .iter()
+            .find(|event| rule.operator.matches(&event.connection))
+            .map(|event| event.connection.user_id);
+        TemplateVars::for_node(rule, &self.node_addr, uid)
+    }
+
+    /// The rule to save, with its placeholders expanded for its node
+    fn expanded_rule(&mut self) -> Option<Rule> {
+        let mut rule = self.build_rule();
+        let vars = self.template_vars(&rule);
+        match rule.expand_placeholders(&vars) {
+            Ok(()) => Some(rule),
+            Err(e) => {
+                self.template_error = Some(e);
+                None
+            }
+        }
+    }
+
     /// Handle key event, returns true if dialog should close
     pub fn handle_key(&mut self, key: KeyEvent) -> Option<RuleEditorResult> {
+        let result = self.handle_field_key(key);
+        if result.is_none() {
+            self.refresh_matches();
+        }
+        result
+    }
+
+    fn handle_field_key(&mut self, key: KeyEvent) -> Option<RuleEditorResult> {
+        if let Some(editor) = &mut self.list_editor {
+            if let Some(entries) = editor.handle_key(key) {
+                self.list = entries;
+                self.list_editor = None;
+            }
+            return None;
+        }
+
+        let save = matches!(key.code, KeyCode::F(2) | KeyCode::Char('s'))
+            && key.modifiers.contains(crossterm::event::KeyModifiers::CONTROL);
+        // Saving again goes ahead despite the conflicts, anything else keeps editing
+        let confirmed = !self.conflicts.is_empty();
+        self.conflicts.clear();
+        self.template_error = None;
+        if confirmed && save {
+            return self.expanded_rule().map(RuleEditorResult::Save);
+        }
+
         if self.editing_text {
             return self.handle_text_input(key);
         }
 
         match key.code {
-            KeyCode::Tab => {
-                self.focus = self.focus.next();
-            }
-            KeyCode::BackTab => {
-                self.focus = self.focus.prev();
-            }
-            KeyCode::Up => {
-                self.focus = self.focus.prev();
-            }
-            KeyCode::Down => {
-                self.focus = self.focus.next();
-            }
+            KeyCode::Tab | KeyCode::Down => self.step_focus(true),
+            KeyCode::BackTab | KeyCode::Up => self.step_focus(false),
             KeyCode::Enter => {
                 match self.focus {
+                    EditorFocus::Data if self.is_list() => {
+                        self.list_editor = Some(ListOperatorEditor::new(self.list.clone()));
+                    }
                     EditorFocus::Name | EditorFocus::Description | EditorFocus::Data => {
                         self.editing_text = true;
                         self.cursor_pos = self.current_text().len();
@@ -238,13 +429,21 @@ impl RuleEditorDialog {
                     _ => self.cycle_option(true),
                 }
             }
+            KeyCode::Char('p') => {
+                self.show_preview = !self.show_preview;
+            }
             KeyCode::Esc => {
                 return Some(RuleEditorResult::Cancel);
             }
             KeyCode::F(2) | KeyCode::Char('s') if key.modifiers.contains(crossterm::event::KeyModifiers::CONTROL) => {
-                // Save
-                if !self.name.is_empty() && !self.data.is_empty() {
-                    return Some(RuleEditorResult::Save(self.build_rule()));
+                // Save, unless the node would silently ignore the operand
+                if !self.name.is_empty() && self.has_condition() && self.compat_warning().is_none() {
+                    let rule = self.expanded_rule()?;
+                    let existing = self.rules.as_deref().unwrap_or_default();
+                    self.conflicts = conflicts(existing, &rule).iter().map(|c| c.message()).collect();
+                    if self.conflicts.is_empty() {
+                        return Some(RuleEditorResult::Save(rule));
+                    }
                 }
             }
             _ => {}
@@ -258,38 +457,24 @@ impl RuleEditorDialog {
                 self.editing_text = false;
             }
             KeyCode::Char(c) => {
-                let cursor = self.cursor_pos;
-                let text = self.current_text_mut();
-                if cursor <= text.len() {
-                    text.insert(cursor, c);
-                    self.cursor_pos = cursor + 1;
-                }
+                let mut cursor = self.cursor_pos.min(self.current_text().len());
+                text::insert(self.current_text_mut(), &mut cursor, c);
+                self.cursor_pos = cursor;
             }
             KeyCode::Backspace => {
-                if self.cursor_pos > 0 {
-                    self.cursor_pos -= 1;
-                    let cursor = self.cursor_pos;
-                    let text = self.current_text_mut();
-                    text.remove(cursor);
-                }
+                let mut cursor = self.cursor_pos;
+                text::backspace(self.current_text_mut(), &mut cursor);
+                self.cursor_pos = cursor;
             }
             KeyCode::Delete => {
                 let cursor = self.cursor_pos;
-                let text = self.current_text_mut();
-                if cursor < text.len() {
-                    text.remove(cursor);
-                }
+                text::delete(self.current_text_mut(), cursor);
             }
             KeyCode::Left => {
-                if self.cursor_pos > 0 {
-                    self.cursor_pos -= 1;
-                }
+                self.cursor_pos = text::prev_boundary(self.current_text(), self.cursor_pos);
             }
             KeyCode::Right => {
-                let len = self.current_text().len();
-                if self.cursor_pos < len {
-                    self.cursor_pos += 1;
-                }
+                self.cursor_pos = text::next_boundary(self.current_text(), self.cursor_pos);
             }
             KeyCode::Home => {
                 self.cursor_pos = 0;
@@ -302,6 +487,14 @@ impl RuleEditorDialog {
         None
     }
 
+    /// Move the focus, past the operand of a list as its entries have their own
+    fn step_focus(&mut self, forward: bool) {
+        self.focus = if forward { self.focus.next() } else { self.focus.prev() };
+        if self.focus == EditorFocus::Operand && self.is_list() {
+            self.focus = if forward { self.focus.next() } else { self.focus.prev() };
+        }
+    }
+
     fn current_text(&self) -> &str {
         match self.focus {
             EditorFocus::Name => &self.name,
@@ -372,6 +565,7 @@ impl RuleEditorDialog {
                     OperatorType::Regexp,
                     OperatorType::Network,
                     OperatorType::Lists,
+                    OperatorType::List,
                 ];
                 let current = types.iter().position(|t| t == &self.operator_type).unwrap_or(0);
                 let new_idx = if forward {
@@ -379,15 +573,65 @@ impl RuleEditorDialog {
                 } else {
                     if current == 0 { types.len() - 1 } else { current - 1 }
                 };
+                // A new list starts with what was entered so far
+                if types[new_idx] == OperatorType::List && self.list.is_empty() && !self.data.is_empty() {
+                    self.list.push(Operator::new(self.operator_type.clone(), self.operand(), &self.data));
+                }
                 self.operator_type = types[new_idx].clone();
             }
             _ => {}
         }
     }
 
+    /// JSON for the current fields, in the format sent to the daemon and
+    /// written to its rules directory
+    fn preview_json(&self) -> String {
+        serde_json::to_string_pretty(&self.build_rule())
+            .unwrap_or_else(|e| format!("Failed to serialize rule: {}", e))
+    }
+
+    /// Recent connections the rule would match, to check its scope before saving
+    fn render_matches(&self, frame: &mut Frame, area: Rect, theme: &Theme) {
+        let matching = self.matching_events();
+        let title = if !self.has_condition() {
+            " Matches ".to_string()
+        } else {
+            format!(" Matches: {} of {} recent connections ", matching.len(), self.recent.len())
+        };
+        let block = Block::default()
+            .title(title)
+            .borders(Borders::LEFT | Borders::TOP)
+            .border_style(theme.border());
+
+        let lines: Vec<Line> = if self.recent.is_empty() {
+            vec![Line::styled("No recent connections to check", theme.dim())]
+        } else if !self.has_condition() {
+            vec![Line::styled("Enter data to see what the rule matches", theme.dim())]
+        } else if matching.is_empty() {
+            vec![Line::styled("Matches none of them", theme.dim())]
+        } else {
+            let f = &self.formats;
+            matching
+                .iter()
+                .map(|event| {
+                    let conn = &event.connection;
+                    let destination = if conn.dst_host.is_empty() { &conn.dst_ip } else { &conn.dst_host };
+                    Line::from(format!(
+                        "{} → {}:{}",
+                        text::elide_start(&f.path(&conn.process_path), 28),
+                        f.host(destination),
+                        conn.dst_port
+                    ))
+                })
+                .collect()
+        };
+        frame.render_widget(Paragraph::new(lines).block(block).style(theme.normal()), area);
+    }
+
     pub fn render(&self, frame: &mut Frame, theme: &Theme) {
         let area = frame.area();
-        let dialog_area = DialogLayout::centered(area, 70, 24).dialog;
+        let width = if self.show_preview { 120 } else { 70 };
+        let dialog_area = DialogLayout::centered(area, width, 24).dialog;
 
         // Clear background
         frame.render_widget(Clear, dialog_area);
@@ -406,7 +650,31 @@ impl RuleEditorDialog {
 
         frame.render_widget(block.clone(), dialog_area);
 
-        let inner = block.inner(dialog_area);
+        let mut inner = block.inner(dialog_area);
+
+        if self.show_preview {
+            let panes = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([Constraint::Length(68), Constraint::Min(20)])
+                .split(inner);
+            inner = panes[0];
+
+            let side = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Min(6), Constraint::Length(9)])
+                .split(panes[1]);
+
+            let preview = Paragraph::new(self.preview_json())
+                .block(
+                    Block::default()
+                        .title(" JSON Preview ")
+                        .borders(Borders::LEFT)
+                        .border_style(theme.border()),
+                )
+                .style(theme.dim());
+            frame.render_widget(preview, side[0]);
+            self.render_matches(frame, side[1], theme);
+        }
 
         // Layout
         let chunks = Layout::default()
@@ -474,10 +742,26 @@ impl RuleEditorDialog {
 
         render_field(frame, chunks[5], "Operator", &format!("◄ {} ►", self.operator_type),
             self.focus == EditorFocus::OperatorType, false);
-        render_field(frame, chunks[6], "Operand", &format!("◄ {} ►", self.operand()),
-            self.focus == EditorFocus::Operand, false);
-        render_field(frame, chunks[7], "Data", &self.data,
-            self.focus == EditorFocus::Data, self.editing_text && self.focus == EditorFocus::Data);
+        let compat_warning = self.compat_warning();
+        if self.is_list() {
+            let unsupported = if compat_warning.is_some() { " (unsupported by node)" } else { "" };
+            frame.render_widget(
+                Paragraph::new(format!("{:15} set per entry{}", "Operand:", unsupported)).style(theme.dim()),
+                chunks[6],
+            );
+            let operands: Vec<&str> = self.list.iter().map(|e| e.operand.as_str()).collect();
+            let entries = format!("{} entries: {}", self.list.len(), operands.join(", "));
+            render_field(frame, chunks[7], "Entries", &entries, self.focus == EditorFocus::Data, false);
+        } else {
+            let operand = match &compat_warning {
+                Some(_) => format!("◄ {} ► (unsupported by node)", self.operand()),
+                None => format!("◄ {} ►", self.operand()),
+            };
+            render_field(frame, chunks[6], "Operand", &operand,
+                self.focus == EditorFocus::Operand, false);
+            render_field(frame, chunks[7], "Data", &self.data,
+                self.focus == EditorFocus::Data, self.editing_text && self.focus == EditorFocus::Data);
+        }
 
         // Separator
         frame.render_widget(Paragraph::new("─".repeat(60)).style(theme.dim()), chunks[8]);
@@ -490,15 +774,41 @@ impl RuleEditorDialog {
         frame.render_widget(Paragraph::new("─".repeat(60)).style(theme.dim()), chunks[12]);
 
         // Hints
-        let hints = if self.editing_text {
-            "Enter/Esc=done editing  ←→=move cursor  Backspace=delete"
+        let hints = if self.focus == EditorFocus::Data && self.is_list() {
+            "Enter=edit entries, all must match  Ctrl+S=save".to_string()
+        } else if self.focus == EditorFocus::Data {
+            let names: Vec<&str> = PLACEHOLDERS.iter().map(|(placeholder, _)| *placeholder).collect();
+            let keys = if self.editing_text { "Enter/Esc=done editing" } else { "Enter=edit  Ctrl+S=save" };
+            format!("{}  Placeholders, expanded on save: {} (user of the rule or yours, dir of its process.path)", keys, names.join(" "))
+        } else if self.editing_text {
+            "Enter/Esc=done editing  ←→=move cursor  Backspace=delete".to_string()
         } else {
-            "Tab/↑↓=navigate  Enter=edit  ←→/Space=change  Ctrl+S=save  Esc=cancel"
+            "Tab/↑↓=navigate  Enter=edit  ←→/Space=change  p=preview  Ctrl+S=save  Esc=cancel".to_string()
         };
-        let hint_para = Paragraph::new(hints)
-            .style(theme.dim())
-            .wrap(Wrap { trim: true });
+        let hint_para = match compat_warning {
+            Some(warning) => Paragraph::new(format!("⚠ {} — pick another operand", warning))
+                .style(Style::default().fg(Color::Red)),
+            None if self.template_error.is_some() => {
+                let error = self.template_error.as_deref().unwrap_or_default();
+                Paragraph::new(format!("⚠ {} — it can't be saved on this host", error))
+                    .style(Style::default().fg(Color::Red))
+            }
+            None if !self.conflicts.is_empty() => {
+                let mut text = String::from("⚠ Conflicts with existing rules:\n");
+                for conflict in &self.conflicts {
+                    text.push_str(&format!("  • {}\n", conflict));
+                }
+                text.push_str("Ctrl+S=save anyway  any other key=keep editing");
+                Paragraph::new(text).style(Style::default().fg(Color::Yellow))
+            }
+            None => Paragraph::new(hints).style(theme.dim()),
+        }
+        .wrap(Wrap { trim: true });
         frame.render_widget(hint_para, chunks[13]);
+
+        if let Some(editor) = &self.list_editor {
+            editor.render(frame, theme);
+        }
     }
 }
 
@@ -0,0 +1,154 @@
+//! Changes made to a rule from the TUI, and the rule before each one
+
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    layout::{Constraint, Direction, Layout},
+    style::Modifier,
+    text::Line,
+    widgets::{Block, Borders, Cell, Clear, Paragraph, Row, Table, TableState, Wrap},
+    Frame,
+};
+
+use crate::models::report::rule_summary;
+use crate::models::RuleVersion;
+use crate::ui::layout::DialogLayout;
+use crate::ui::theme::Theme;
+use crate::utils::Formats;
+
+pub struct RuleHistoryDialog {
+    pub name: String,
+    /// Most recent first, None until loaded
+    versions: Option<Vec<RuleVersion>>,
+    selected: usize,
+    formats: Formats,
+}
+
+impl RuleHistoryDialog {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            versions: None,
+            selected: 0,
+            formats: Formats::default(),
+        }
+    }
+
+    pub fn with_formats(mut self, formats: Formats) -> Self {
+        self.formats = formats;
+        self
+    }
+
+    /// Whether the changes are still to be read from the database
+    pub fn wants_versions(&self) -> bool {
+        self.versions.is_none()
+    }
+
+    pub fn set_versions(&mut self, versions: Vec<RuleVersion>) {
+        self.selected = self.selected.min(versions.len().saturating_sub(1));
+        self.versions = Some(versions);
+    }
+
+    /// Handle key event, returns true if dialog should close
+    pub fn handle_key(&mut self, key: KeyEvent) -> bool {
+        let len = self.versions.as_ref().map_or(0, Vec::len);
+        match key.code {
+            KeyCode::Down | KeyCode::Char('j') => self.selected = (self.selected + 1).min(len.saturating_sub(1)),
+            KeyCode::Up | KeyCode::Char('k') => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Esc | KeyCode::Enter | KeyCode::Char('q') | KeyCode::Char('H') => return true,
+            _ => {}
+        }
+        false
+    }
+
+    pub fn render(&self, frame: &mut Frame, theme: &Theme) {
+        let area = frame.area();
+        let dialog_area = DialogLayout::centered(area, 96, 26).dialog;
+
+        frame.render_widget(Clear, dialog_area);
+
+        let block = Block::default()
+            .title(format!(" History of {} ", self.name))
+            .borders(Borders::ALL)
+            .border_style(theme.border_focused())
+            .style(theme.normal());
+
+        let inner = block.inner(dialog_area);
+        frame.render_widget(block, dialog_area);
+
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .margin(1)
+            .constraints([
+                Constraint::Min(5),         // Changes
+                Constraint::Percentage(40), // The rule before the selected one
+                Constraint::Length(1),      // Hints
+            ])
+            .split(inner);
+
+        let versions = self.versions.as_deref().unwrap_or_default();
+        if versions.is_empty() {
+            let message = if self.versions.is_none() {
+                "Reading…"
+            } else {
+                "Not changed from here yet."
+            };
+            frame.render_widget(Paragraph::new(message).style(theme.dim()), chunks[0]);
+        } else {
+            let header = Row::new(["Time", "Change", "Before"])
+                .style(theme.accent().add_modifier(Modifier::BOLD));
+            let rows = versions.iter().map(|version| {
+                let before = match &version.previous {
+                    Some(rule) => format!("{} {}", rule.action, if rule.enabled { "enabled" } else { "disabled" }),
+                    None => "no rule".to_string(),
+                };
+                let change = if version.undone {
+                    Cell::from(format!("{} (undone)", version.change)).style(theme.dim())
+                } else {
+                    Cell::from(version.change.to_string())
+                };
+                Row::new(vec![
+                    Cell::from(self.formats.date_time(&version.time)),
+                    change,
+                    Cell::from(before),
+                ])
+            });
+            let table = Table::new(
+                rows,
+                [Constraint::Length(20), Constraint::Length(18), Constraint::Min(16)],
+            )
+            .header(header)
+            .row_highlight_style(theme.selected());
+            let mut state = TableState::default().with_selected(Some(self.selected));
+            frame.render_stateful_widget(table, chunks[0], &mut state);
+        }
+
+        if let Some(version) = versions.get(self.selected) {
+            let lines: Vec<Line> = match &version.previous {
+                Some(rule) => vec![
+                    Line::raw(rule_summary(rule)),
+                    Line::styled(
+                        format!(
+                            "{} {} = {}",
+                            rule.operator.op_type, rule.operator.operand, rule.operator.data
+                        ),
+                        theme.dim(),
+                    ),
+                ],
+                None => vec![Line::styled("The rule didn't exist before this change.", theme.dim())],
+            };
+            let before_block = Block::default()
+                .title(" Before the change ")
+                .borders(Borders::TOP)
+                .border_style(theme.border());
+            frame.render_widget(
+                Paragraph::new(lines).block(before_block).wrap(Wrap { trim: false }),
+                chunks[1],
+            );
+        }
+
+        frame.render_widget(
+            Paragraph::new("↑↓=select  Esc=close  u on the rules undoes the latest change").style(theme.dim()),
+            chunks[2],
+        );
+    }
+}
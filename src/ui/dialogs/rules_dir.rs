@@ -0,0 +1,170 @@
+//! Import or export of rules through the daemon's rules directory
+
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    layout::{Constraint, Direction, Layout},
+    style::Modifier,
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph, Wrap},
+    Frame,
+};
+
+use crate::db::rules_io::{RulesDirReport, DEFAULT_RULES_DIR};
+use crate::ui::layout::DialogLayout;
+use crate::ui::theme::Theme;
+use crate::utils::text;
+
+/// Which way rules go
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RulesDirMode {
+    /// From the directory to the node
+    Import,
+    /// From the node to the directory
+    Export,
+}
+
+/// Requested outcome of a rules directory dialog key press
+pub enum RulesDirResult {
+    Close,
+    /// Copy the rules for this directory, the dialog stays open for the report
+    Run(RulesDirMode, String),
+}
+
+pub struct RulesDirDialog {
+    pub mode: RulesDirMode,
+    pub path: String,
+    cursor_pos: usize,
+    result: Option<Result<RulesDirReport, String>>,
+}
+
+impl RulesDirDialog {
+    pub fn new(mode: RulesDirMode) -> Self {
+        let path = DEFAULT_RULES_DIR.to_string();
+        Self {
+            mode,
+            cursor_pos: path.len(),
+            path,
+            result: None,
+        }
+    }
+
+    /// Show the outcome of the import or export
+    pub fn set_result(&mut self, result: Result<RulesDirReport, String>) {
+        self.result = Some(result);
+    }
+
+    /// Handle key event
+    pub fn handle_key(&mut self, key: KeyEvent) -> Option<RulesDirResult> {
+        // Once the copy has run, the dialog only shows the report
+        if self.result.is_some() {
+            return matches!(key.code, KeyCode::Esc | KeyCode::Enter | KeyCode::Char('q'))
+                .then_some(RulesDirResult::Close);
+        }
+
+        match key.code {
+            KeyCode::Esc => return Some(RulesDirResult::Close),
+            KeyCode::Enter => return Some(RulesDirResult::Run(self.mode, self.path.clone())),
+            KeyCode::Char(c) => text::insert(&mut self.path, &mut self.cursor_pos, c),
+            KeyCode::Backspace => {
+                text::backspace(&mut self.path, &mut self.cursor_pos);
+            }
+            KeyCode::Delete => {
+                text::delete(&mut self.path, self.cursor_pos);
+            }
+            KeyCode::Left => self.cursor_pos = text::prev_boundary(&self.path, self.cursor_pos),
+            KeyCode::Right => self.cursor_pos = text::next_boundary(&self.path, self.cursor_pos),
+            KeyCode::Home => self.cursor_pos = 0,
+            KeyCode::End => self.cursor_pos = self.path.len(),
+            _ => {}
+        }
+        None
+    }
+
+    pub fn render(&self, frame: &mut Frame, theme: &Theme) {
+        let area = frame.area();
+        let dialog_area = DialogLayout::centered(area, 76, 18).dialog;
+
+        frame.render_widget(Clear, dialog_area);
+
+        let (title, intro, verb) = match self.mode {
+            RulesDirMode::Import => (
+                " Import Rules ",
+                "Sends the rules found in an OpenSnitch rules directory to the node. Rules it already has are kept.",
+                "import",
+            ),
+            RulesDirMode::Export => (
+                " Export Rules ",
+                "Writes the node's permanent rules as one JSON file each, as opensnitchd and its GUI do.",
+                "export",
+            ),
+        };
+        let block = Block::default()
+            .title(title)
+            .borders(Borders::ALL)
+            .border_style(theme.border_focused())
+            .style(theme.normal());
+
+        let inner = block.inner(dialog_area);
+        frame.render_widget(block, dialog_area);
+
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .margin(1)
+            .constraints([
+                Constraint::Length(2), // Intro
+                Constraint::Length(3), // Path input
+                Constraint::Min(3),    // Report
+                Constraint::Length(1), // Hints
+            ])
+            .split(inner);
+
+        frame.render_widget(Paragraph::new(intro).style(theme.dim()).wrap(Wrap { trim: true }), chunks[0]);
+
+        let editing = self.result.is_none();
+        let path_block = Block::default()
+            .title(" Rules directory ")
+            .borders(Borders::ALL)
+            .border_style(if editing { theme.border_focused() } else { theme.border() });
+        frame.render_widget(
+            Paragraph::new(self.path.as_str()).block(path_block).style(theme.normal()),
+            chunks[1],
+        );
+
+        if editing {
+            frame.set_cursor_position((
+                chunks[1].x + 1 + text::column(&self.path, self.cursor_pos) as u16,
+                chunks[1].y + 1,
+            ));
+        }
+
+        let report_lines: Vec<Line> = match &self.result {
+            None => vec![],
+            Some(Err(e)) => vec![Line::from(Span::styled(format!("Rules {} failed: {}", verb, e), theme.error()))],
+            Some(Ok(report)) => {
+                let mut lines = vec![Line::from(Span::styled(
+                    report.summary(),
+                    theme.success().add_modifier(Modifier::BOLD),
+                ))];
+                if !report.errors.is_empty() {
+                    lines.push(Line::from(""));
+                    lines.push(Line::from(Span::styled("Errors:", theme.accent())));
+                    for error in &report.errors {
+                        lines.push(Line::from(format!("  {}", error)));
+                    }
+                }
+                lines
+            }
+        };
+        frame.render_widget(
+            Paragraph::new(report_lines).wrap(Wrap { trim: false }).style(theme.normal()),
+            chunks[2],
+        );
+
+        let hints = if editing {
+            format!("Enter={}  Esc=cancel", verb)
+        } else {
+            "Enter/Esc=close".to_string()
+        };
+        frame.render_widget(Paragraph::new(hints).style(theme.dim()), chunks[3]);
+    }
+}
@@ -0,0 +1,131 @@
+//! Log of the changes sent to daemons and their replies
+
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    layout::{Constraint, Direction, Layout},
+    style::Modifier,
+    widgets::{Block, Borders, Cell, Clear, Paragraph, Row, Table, TableState, Wrap},
+    Frame,
+};
+
+use crate::grpc::notifications::{Delivery, SentNotification};
+use crate::ui::layout::DialogLayout;
+use crate::ui::theme::Theme;
+use crate::utils::Formats;
+
+pub struct SentChangesDialog {
+    sent: Vec<SentNotification>,
+    selected: usize,
+    formats: Formats,
+}
+
+impl SentChangesDialog {
+    pub fn new(sent: Vec<SentNotification>) -> Self {
+        Self {
+            sent,
+            selected: 0,
+            formats: Formats::default(),
+        }
+    }
+
+    pub fn with_formats(mut self, formats: Formats) -> Self {
+        self.formats = formats;
+        self
+    }
+
+    /// Handle key event, returns true if dialog should close
+    pub fn handle_key(&mut self, key: KeyEvent) -> bool {
+        match key.code {
+            KeyCode::Down | KeyCode::Char('j') => {
+                self.selected = (self.selected + 1).min(self.sent.len().saturating_sub(1));
+            }
+            KeyCode::Up | KeyCode::Char('k') => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Esc | KeyCode::Enter | KeyCode::Char('q') => return true,
+            _ => {}
+        }
+        false
+    }
+
+    pub fn render(&self, frame: &mut Frame, theme: &Theme) {
+        let area = frame.area();
+        let dialog_area = DialogLayout::centered(area, 110, 30).dialog;
+
+        frame.render_widget(Clear, dialog_area);
+
+        let block = Block::default()
+            .title(" Sent Changes ")
+            .borders(Borders::ALL)
+            .border_style(theme.border_focused())
+            .style(theme.normal());
+
+        let inner = block.inner(dialog_area);
+        frame.render_widget(block, dialog_area);
+
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .margin(1)
+            .constraints([
+                Constraint::Min(5),         // Notifications
+                Constraint::Percentage(40), // Payload of the selected one
+                Constraint::Length(1),      // Hints
+            ])
+            .split(inner);
+
+        if self.sent.is_empty() {
+            frame.render_widget(
+                Paragraph::new("Nothing sent to a daemon yet.").style(theme.dim()),
+                chunks[0],
+            );
+        } else {
+            let header = Row::new(["Time", "Node", "Action", "Change", "Reply"])
+                .style(theme.accent().add_modifier(Modifier::BOLD));
+            let rows = self.sent.iter().map(|sent| {
+                let reply = match &sent.delivery {
+                    Delivery::NotSent => Cell::from("not sent").style(theme.error()),
+                    Delivery::Pending => Cell::from("waiting").style(theme.dim()),
+                    Delivery::Ok(_) => Cell::from("ok").style(theme.success()),
+                    Delivery::Error(e) => Cell::from(format!("error: {}", e)).style(theme.error()),
+                };
+                Row::new(vec![
+                    Cell::from(self.formats.time(&sent.time)),
+                    Cell::from(sent.node_addr.clone()),
+                    Cell::from(sent.action),
+                    Cell::from(self.formats.text(&sent.summary).into_owned()),
+                    reply,
+                ])
+            });
+            let table = Table::new(
+                rows,
+                [
+                    Constraint::Length(11),
+                    Constraint::Length(22),
+                    Constraint::Length(20),
+                    Constraint::Min(24),
+                    Constraint::Length(20),
+                ],
+            )
+            .header(header)
+            .row_highlight_style(theme.selected());
+            let mut state = TableState::default().with_selected(Some(self.selected));
+            frame.render_stateful_widget(table, chunks[0], &mut state);
+        }
+
+        if let Some(sent) = self.sent.get(self.selected) {
+            let payload = if sent.payload.is_empty() {
+                "(no payload)".to_string()
+            } else {
+                self.formats.text(&sent.payload).into_owned()
+            };
+            let payload_block = Block::default()
+                .title(format!(" Payload #{} ", sent.id))
+                .borders(Borders::TOP)
+                .border_style(theme.border());
+            frame.render_widget(
+                Paragraph::new(payload).block(payload_block).wrap(Wrap { trim: false }),
+                chunks[1],
+            );
+        }
+
+        frame.render_widget(Paragraph::new("↑↓=select  Esc=close").style(theme.dim()), chunks[2]);
+    }
+}
@@ -0,0 +1,103 @@
+//! Rule suggestion popup for repeatedly denied connections
+
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    layout::{Constraint, Direction, Layout},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph, Wrap},
+    Frame,
+};
+
+use crate::app::suggestions::RuleSuggestion;
+use crate::models::RuleAction;
+use crate::ui::layout::DialogLayout;
+use crate::ui::theme::Theme;
+use crate::utils::Formats;
+
+/// Outcome of the suggestion popup
+pub enum SuggestionResult {
+    /// Create a permanent rule with this action
+    Create(RuleAction),
+    Dismiss,
+}
+
+pub struct SuggestionDialog {
+    pub suggestion: RuleSuggestion,
+    formats: Formats,
+}
+
+impl SuggestionDialog {
+    pub fn new(suggestion: RuleSuggestion) -> Self {
+        Self {
+            suggestion,
+            formats: Formats::default(),
+        }
+    }
+
+    /// Masking of hosts and paths
+    pub fn with_formats(mut self, formats: Formats) -> Self {
+        self.formats = formats;
+        self
+    }
+
+    /// Handle key event, returns a result once the dialog should close
+    pub fn handle_key(&mut self, key: KeyEvent) -> Option<SuggestionResult> {
+        match key.code {
+            KeyCode::Char('d') => Some(SuggestionResult::Create(RuleAction::Deny)),
+            KeyCode::Char('a') => Some(SuggestionResult::Create(RuleAction::Allow)),
+            KeyCode::Esc | KeyCode::Char('n') => Some(SuggestionResult::Dismiss),
+            _ => None,
+        }
+    }
+
+    pub fn render(&self, frame: &mut Frame, theme: &Theme) {
+        let area = frame.area();
+        let dialog_area = DialogLayout::centered(area, 64, 11).dialog;
+
+        frame.render_widget(Clear, dialog_area);
+
+        let block = Block::default()
+            .title(" Rule Suggestion ")
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Yellow))
+            .style(theme.normal());
+
+        let inner = block.inner(dialog_area);
+        frame.render_widget(block, dialog_area);
+
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .margin(1)
+            .constraints([
+                Constraint::Min(2),    // Message
+                Constraint::Length(2), // Details
+                Constraint::Length(1), // Hints
+            ])
+            .split(inner);
+
+        let destination = &self.suggestion.destination;
+        let message = format!(
+            "{} — create a permanent deny or allow rule?",
+            self.suggestion.message().replace(destination.as_str(), &self.formats.host(destination))
+        );
+        frame.render_widget(
+            Paragraph::new(message)
+                .style(theme.normal().add_modifier(Modifier::BOLD))
+                .wrap(Wrap { trim: true }),
+            chunks[0],
+        );
+
+        let label = |text: &'static str| Span::styled(format!("{:9}", text), theme.dim());
+        let details = vec![
+            Line::from(vec![label("Process:"), Span::raw(self.formats.path(&self.suggestion.process_path))]),
+            Line::from(vec![label("Node:"), Span::raw(self.suggestion.node_addr.clone())]),
+        ];
+        frame.render_widget(Paragraph::new(details), chunks[1]);
+
+        frame.render_widget(
+            Paragraph::new("d=deny always  a=allow always  Esc=dismiss").style(theme.dim()),
+            chunks[2],
+        );
+    }
+}
@@ -0,0 +1,139 @@
+//! Theme picker with a live preview of every theme
+
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::Modifier,
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph},
+    Frame,
+};
+
+use crate::ui::layout::DialogLayout;
+use crate::ui::theme::Theme;
+
+/// Outcome of the theme picker
+pub enum ThemeDialogResult {
+    /// Keep the selected theme
+    Keep(&'static str),
+    /// Go back to the theme in use before the picker opened
+    Revert(&'static str),
+}
+
+pub struct ThemeDialog {
+    selected: usize,
+    original: usize,
+}
+
+impl ThemeDialog {
+    pub fn new(current: &str) -> Self {
+        let current = if current == "default" { "dark" } else { current };
+        let selected = Theme::NAMES.iter().position(|n| *n == current).unwrap_or(0);
+        Self {
+            selected,
+            original: selected,
+        }
+    }
+
+    /// Theme to apply while the picker is open
+    pub fn selected_name(&self) -> &'static str {
+        Theme::NAMES[self.selected]
+    }
+
+    /// Handle key event, returns a result once the dialog should close
+    pub fn handle_key(&mut self, key: KeyEvent) -> Option<ThemeDialogResult> {
+        let count = Theme::NAMES.len();
+        match key.code {
+            KeyCode::Right | KeyCode::Down | KeyCode::Tab => self.selected = (self.selected + 1) % count,
+            KeyCode::Left | KeyCode::Up | KeyCode::BackTab => self.selected = (self.selected + count - 1) % count,
+            KeyCode::Enter => return Some(ThemeDialogResult::Keep(self.selected_name())),
+            KeyCode::Esc => return Some(ThemeDialogResult::Revert(Theme::NAMES[self.original])),
+            _ => {}
+        }
+        None
+    }
+
+    pub fn render(&self, frame: &mut Frame, theme: &Theme) {
+        let area = frame.area();
+        let dialog_area = DialogLayout::centered(area, 72, 18).dialog;
+
+        frame.render_widget(Clear, dialog_area);
+
+        let block = Block::default()
+            .title(" Theme ")
+            .borders(Borders::ALL)
+            .border_style(theme.border_focused())
+            .style(theme.normal());
+
+        let inner = block.inner(dialog_area);
+        frame.render_widget(block, dialog_area);
+
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .margin(1)
+            .constraints([
+                Constraint::Min(12),   // Previews
+                Constraint::Length(1), // Hints
+            ])
+            .split(inner);
+
+        let columns = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints(vec![Constraint::Ratio(1, Theme::NAMES.len() as u32); Theme::NAMES.len()])
+            .split(chunks[0]);
+        for (i, (name, column)) in Theme::NAMES.iter().zip(columns.iter()).enumerate() {
+            if let Some(sample) = Theme::named(name) {
+                render_sample(frame, *column, name, &sample, i == self.selected);
+            }
+        }
+
+        frame.render_widget(
+            Paragraph::new("←→ = switch (applied live)  Enter = keep  Esc = revert").style(theme.dim()),
+            chunks[1],
+        );
+    }
+}
+
+/// Sample widgets drawn in one theme
+fn render_sample(frame: &mut Frame, area: Rect, name: &str, theme: &Theme, selected: bool) {
+    let (border, title) = if selected {
+        (theme.border_focused(), format!(" ▶ {} ", name))
+    } else {
+        (theme.border(), format!(" {} ", name))
+    };
+    let block = Block::default()
+        .title(Span::styled(title, if selected { theme.tab_active() } else { theme.tab_inactive() }))
+        .borders(Borders::ALL)
+        .border_style(border)
+        .style(theme.normal());
+
+    let badge = |text: &'static str, style| Span::styled(format!(" {} ", text), style);
+    let lines = vec![
+        Line::from(vec![
+            Span::styled("Tab", theme.tab_active()),
+            Span::raw(" │ "),
+            Span::styled("Other", theme.tab_inactive()),
+        ]),
+        Line::from(""),
+        Line::from(Span::styled("Process    Action", theme.accent().add_modifier(Modifier::BOLD))),
+        Line::from(vec![Span::raw("firefox    "), Span::styled("allow", theme.action_style("allow"))]),
+        Line::from(vec![Span::raw("▶ curl     "), Span::raw("deny")]).style(theme.selected()),
+        Line::from(vec![Span::raw("ssh        "), Span::styled("reject", theme.action_style("reject"))]),
+        Line::from(Span::styled("disabled rule", theme.dim())),
+        Line::from(""),
+        Line::from(vec![
+            badge("OK", theme.success()),
+            badge("WARN", theme.warning()),
+            badge("ERR", theme.error()),
+            badge("INFO", theme.info()),
+        ]),
+        Line::from(vec![
+            Span::styled("[ Yes ]", theme.accent().add_modifier(Modifier::BOLD)),
+            Span::raw("  "),
+            Span::styled("[ No ]", theme.dim()),
+            Span::raw("  "),
+            Span::styled("match", theme.highlight()),
+        ]),
+    ];
+    frame.render_widget(Paragraph::new(lines).block(block), area);
+}
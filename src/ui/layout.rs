@@ -5,6 +5,10 @@ use ratatui::layout::{Constraint, Direction, Layout, Rect};
 /// Standard application layout areas
 pub struct AppLayout {
     pub tabs: Rect,
+    /// Line above the content for warnings, empty unless asked for
+    pub banner: Rect,
+    /// Large counters above the content, empty unless asked for
+    pub counters: Rect,
     pub content: Rect,
     pub status: Rect,
 }
@@ -23,10 +27,34 @@ impl AppLayout {
 
         Self {
             tabs: chunks[0],
+            banner: Rect::new(chunks[1].x, chunks[1].y, chunks[1].width, 0),
+            counters: Rect::new(chunks[1].x, chunks[1].y, chunks[1].width, 0),
             content: chunks[1],
             status: chunks[2],
         }
     }
+
+    /// Take the first lines of the content for counters
+    pub fn with_counters(mut self, height: u16) -> Self {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(height), Constraint::Min(0)])
+            .split(self.content);
+        self.counters = chunks[0];
+        self.content = chunks[1];
+        self
+    }
+
+    /// Take the first line of the content for a banner
+    pub fn with_banner(mut self) -> Self {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(1), Constraint::Min(0)])
+            .split(self.content);
+        self.banner = chunks[0];
+        self.content = chunks[1];
+        self
+    }
 }
 
 /// Layout with filter bar
@@ -114,6 +142,14 @@ impl DialogLayout {
             dialog: Rect::new(x, y, width.min(area.width), height.min(area.height)),
         }
     }
+
+    /// Create full-width dialog docked at the bottom
+    pub fn docked(area: Rect, height: u16) -> Self {
+        let height = height.min(area.height);
+        Self {
+            dialog: Rect::new(area.x, area.bottom() - height, area.width, height),
+        }
+    }
 }
 
 /// Statistics dashboard layout
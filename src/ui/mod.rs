@@ -2,6 +2,7 @@ pub mod app;
 pub mod dialogs;
 pub mod layout;
 pub mod tabs;
+pub mod terminal;
 pub mod theme;
 pub mod widgets;
 
@@ -2,26 +2,48 @@
 
 use std::sync::Arc;
 
-use crossterm::event::{KeyCode, KeyEvent};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers, MouseEvent};
 use ratatui::{
     layout::{Constraint, Direction, Layout, Rect},
-    style::{Color, Modifier, Style},
+    style::Modifier,
     text::Span,
     widgets::{Block, Borders, Cell, Paragraph, Row, Table, TableState},
     Frame,
 };
 
-use crate::app::events::navigation_delta;
+use crate::app::events::{click_position, navigation_delta};
 use crate::app::state::AppState;
-use crate::models::{Alert, AlertPriority, AlertType};
+use crate::app::xref::AlertRef;
+use crate::models::{Alert, AlertData, AlertPriority, AlertType};
+use crate::ui::dialogs::alert_details::{AlertDetailsDialog, AlertDetailsResult};
+use crate::ui::tabs::{clicked_row, scroll_with_keys, step_index, Jump, Tab, TabCommand};
 use crate::ui::theme::Theme;
 use crate::ui::widgets::searchbar::SearchBar;
+use crate::ui::widgets::table::ColumnSort;
+use crate::utils::text::truncate;
+use crate::utils::Formats;
+
+const COLUMNS: [&str; 5] = ["Time", "Type", "Priority", "Source", "Message"];
 
 pub struct AlertsTab {
     table_state: TableState,
+    /// Where the table was last drawn, for clicks
+    table_area: Rect,
     search_bar: SearchBar,
     filter_active: bool,
     cached_alerts: Vec<Alert>,
+    /// Only show alerts of this priority (None = all)
+    severity: Option<AlertPriority>,
+    /// Keep acknowledged alerts in the list rather than hiding them
+    show_acknowledged: bool,
+    /// Newest first unless sorted
+    sort: ColumnSort,
+    /// Only show the alerts related to an item of another tab, and what it is
+    related: Option<(String, Vec<AlertRef>)>,
+    /// `g` pressed, waiting for where to jump
+    goto: bool,
+    details: Option<AlertDetailsDialog>,
+    formats: Formats,
 }
 
 impl AlertsTab {
@@ -30,18 +52,110 @@ impl AlertsTab {
         state.select(Some(0));
         Self {
             table_state: state,
+            table_area: Rect::default(),
             search_bar: SearchBar::new(),
             filter_active: false,
             cached_alerts: Vec::new(),
+            severity: None,
+            show_acknowledged: false,
+            sort: ColumnSort::default(),
+            related: None,
+            goto: false,
+            details: None,
+            formats: Formats::default(),
+        }
+    }
+
+    /// Number and date formatting
+    pub fn with_formats(mut self, formats: Formats) -> Self {
+        self.formats = formats;
+        self
+    }
+
+    /// Switch formatting while running, e.g. into private mode
+    pub fn set_formats(&mut self, formats: Formats) {
+        self.formats = formats;
+    }
+
+    /// Cycle the severity filter: All -> High -> Medium -> Low -> All
+    fn cycle_severity(&mut self) {
+        self.severity = match self.severity {
+            None => Some(AlertPriority::High),
+            Some(AlertPriority::High) => Some(AlertPriority::Medium),
+            Some(AlertPriority::Medium) => Some(AlertPriority::Low),
+            Some(AlertPriority::Low) => None,
+        };
+        self.table_state.select(Some(0));
+    }
+
+    /// Alerts matching the severity filter and search query, without the
+    /// acknowledged ones unless shown or related to another tab's item
+    pub fn filtered_alerts(&self) -> Vec<&Alert> {
+        let query = self.search_bar.query.to_lowercase();
+        let mut alerts: Vec<&Alert> = self
+            .cached_alerts
+            .iter()
+            .filter(|a| self.show_acknowledged || self.related.is_some() || !a.acknowledged)
+            .filter(|a| self.severity.is_none_or(|p| a.priority == p))
+            .filter(|a| {
+                self.related
+                    .as_ref()
+                    .is_none_or(|(_, refs)| refs.contains(&(a.id, a.timestamp)))
+            })
+            .filter(|a| {
+                query.is_empty()
+                    || a.text().to_lowercase().contains(&query)
+                    || a.node.to_lowercase().contains(&query)
+            })
+            .collect();
+        self.sort.sort(&mut alerts, |column, a, b| match column {
+            0 => a.timestamp.cmp(&b.timestamp),
+            1 => a.alert_type.to_string().cmp(&b.alert_type.to_string()),
+            2 => priority_rank(a.priority).cmp(&priority_rank(b.priority)),
+            3 => a.what.to_string().cmp(&b.what.to_string()),
+            _ => a.text().cmp(&b.text()),
+        });
+        alerts
+    }
+
+    fn set_sort(&mut self, sort: ColumnSort) {
+        self.sort = sort;
+        self.table_state.select(Some(0));
+    }
+
+    /// Only show these alerts, until Esc
+    pub fn show_related(&mut self, label: String, alerts: Vec<AlertRef>) {
+        self.related = Some((label, alerts));
+        self.table_state.select(Some(0));
+    }
+
+    /// Where the selected alert leads with `g` and a key
+    fn jump(&self, key: KeyCode) -> Option<Jump> {
+        let selected = self.table_state.selected().unwrap_or(0);
+        let alert = self.filtered_alerts().get(selected).copied()?;
+        match (key, alert.data.as_ref()?) {
+            (KeyCode::Char('c'), AlertData::Connection(conn)) => Some(Jump::ConnectionEvents(Box::new(conn.clone()))),
+            (KeyCode::Char('r'), AlertData::Connection(conn)) => Some(Jump::ConnectionRule(Box::new(conn.clone()))),
+            (KeyCode::Char('r'), AlertData::Rule(rule)) => Some(Jump::Rule(rule.name.clone())),
+            _ => None,
         }
     }
 
+    pub fn search_bar_mut(&mut self) -> &mut SearchBar {
+        &mut self.search_bar
+    }
+
+    pub fn set_alerts(&mut self, alerts: Vec<Alert>) {
+        self.cached_alerts = alerts;
+    }
+
     pub async fn update_cache(&mut self, state: &Arc<AppState>) {
         let alerts = state.alerts.read().await;
-        self.cached_alerts = alerts.iter().cloned().collect();
+        self.set_alerts(alerts.iter().cloned().collect());
     }
 
     pub fn render(&mut self, frame: &mut Frame, area: Rect, theme: &Theme) {
+        self.table_area = Rect::default();
         let chunks = Layout::default()
             .direction(Direction::Vertical)
             .constraints(if self.filter_active {
@@ -52,25 +166,18 @@ impl AlertsTab {
             .split(area);
 
         if self.filter_active {
+            let matched = self.filtered_alerts().len();
+            self.search_bar.set_matches(matched, self.cached_alerts.len());
             self.search_bar.render(frame, chunks[0], theme.normal(), theme.border_focused());
         }
 
-        let filtered_alerts: Vec<&Alert> = if self.search_bar.query.is_empty() {
-            self.cached_alerts.iter().collect()
-        } else {
-            let query = self.search_bar.query.to_lowercase();
-            self.cached_alerts
-                .iter()
-                .filter(|a| {
-                    a.text().to_lowercase().contains(&query)
-                        || a.node.to_lowercase().contains(&query)
-                })
-                .collect()
-        };
+        let filtered_alerts = self.filtered_alerts();
 
-        let header_cells = ["Time", "Type", "Priority", "Source", "Message"]
-            .iter()
-            .map(|h| Cell::from(*h).style(theme.accent().add_modifier(Modifier::BOLD)));
+        let header_cells = self
+            .sort
+            .header(&COLUMNS)
+            .into_iter()
+            .map(|h| Cell::from(h).style(theme.accent().add_modifier(Modifier::BOLD)));
         let header = Row::new(header_cells).height(1);
 
         let rows: Vec<Row> = if filtered_alerts.is_empty() {
@@ -86,40 +193,67 @@ impl AlertsTab {
             filtered_alerts
                 .iter()
                 .map(|alert| {
-                    let type_style = match alert.alert_type {
-                        AlertType::Error => Style::default().fg(Color::Red),
-                        AlertType::Warning => Style::default().fg(Color::Yellow),
-                        AlertType::Info => Style::default().fg(Color::Blue),
+                    // Markers repeat what the colors say
+                    let (type_marker, type_style) = match alert.alert_type {
+                        AlertType::Error => ("✗", theme.error()),
+                        AlertType::Warning => ("!", theme.warning()),
+                        AlertType::Info => ("i", theme.info()),
                     };
 
-                    let priority_style = match alert.priority {
-                        AlertPriority::High => Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
-                        AlertPriority::Medium => Style::default().fg(Color::Yellow),
-                        AlertPriority::Low => Style::default().fg(Color::DarkGray),
+                    let (priority_marker, priority_style) = match alert.priority {
+                        AlertPriority::High => ("!", theme.error().add_modifier(Modifier::BOLD)),
+                        AlertPriority::Medium => ("·", theme.warning()),
+                        AlertPriority::Low => (" ", theme.dim()),
                     };
 
-                    let time = alert.timestamp.format("%H:%M:%S").to_string();
+                    let mut time = self.formats.time(&alert.timestamp);
+                    if alert.restored {
+                        time = format!("↺ {}", time);
+                    }
+                    let row_style = if alert.acknowledged { theme.dim() } else { theme.normal() };
 
                     Row::new(vec![
                         Cell::from(time),
-                        Cell::from(format!("{}", alert.alert_type)).style(type_style),
-                        Cell::from(format!("{:?}", alert.priority)).style(priority_style),
+                        Cell::from(format!("{} {}", type_marker, alert.alert_type)).style(type_style),
+                        Cell::from(format!("{} {:?}", priority_marker, alert.priority)).style(priority_style),
                         Cell::from(format!("{}", alert.what)),
-                        Cell::from(truncate(&alert.text(), 40).to_string()),
+                        Cell::from(truncate(&self.formats.alert(alert), 40).to_string()),
                     ])
+                    .style(row_style)
                 })
                 .collect()
         };
 
         let widths = [
-            Constraint::Length(10),     // Time
+            Constraint::Length(12),     // Time
             Constraint::Length(10),     // Type
             Constraint::Length(10),     // Priority
             Constraint::Length(15),     // Source
             Constraint::Percentage(50), // Message
         ];
 
-        let title = format!(" Alerts ({}) ", filtered_alerts.len());
+        let severity = match self.severity {
+            None => "All".to_string(),
+            Some(p) => format!("{:?}", p),
+        };
+        let related = match &self.related {
+            Some((label, _)) => format!(" [{}, Esc = all]", label),
+            None => String::new(),
+        };
+        let restored = if filtered_alerts.iter().any(|a| a.restored) {
+            " [↺ = from a previous run]"
+        } else {
+            ""
+        };
+        let acknowledged = if self.show_acknowledged { "v=hide acked" } else { "v=show acked" };
+        let title = format!(
+            " Alerts ({}) [{}]{}{}  Enter=details  p=priority  s/S=sort  a=ack  A=ack all  {}  g c/g r=connection/rule ",
+            filtered_alerts.len(),
+            severity,
+            related,
+            restored,
+            acknowledged
+        );
 
         let table = Table::new(rows, widths)
             .header(header)
@@ -131,10 +265,50 @@ impl AlertsTab {
             .row_highlight_style(theme.selected())
             .highlight_symbol("▶ ");
 
+        self.table_area = chunks[1];
         frame.render_stateful_widget(table, chunks[1], &mut self.table_state);
+
+        if let Some(dialog) = &self.details {
+            dialog.render(frame, theme);
+        }
     }
+}
+
+impl Tab for AlertsTab {
+    fn showing_dialog(&self) -> bool {
+        self.details.is_some()
+    }
+
+    /// Clicking a row selects it
+    fn handle_mouse(&mut self, event: MouseEvent) -> Vec<TabCommand> {
+        let Some((column, row)) = click_position(&event).filter(|_| !self.showing_dialog()) else {
+            return scroll_with_keys(self, event);
+        };
+        if let Some(idx) = clicked_row(self.table_area, self.table_state.offset(), column, row) {
+            if idx < self.filtered_alerts().len() {
+                self.table_state.select(Some(idx));
+            }
+        }
+        Vec::new()
+    }
+
+    fn handle_key(&mut self, key: KeyEvent) -> Vec<TabCommand> {
+        if let Some(dialog) = &mut self.details {
+            let Some(result) = dialog.handle_key(key) else {
+                return Vec::new();
+            };
+            let alert = dialog.alert();
+            let commands = match result {
+                AlertDetailsResult::Acknowledge => vec![TabCommand::AcknowledgeAlert {
+                    id: alert.id,
+                    timestamp: alert.timestamp,
+                }],
+                AlertDetailsResult::Close => Vec::new(),
+            };
+            self.details = None;
+            return commands;
+        }
 
-    pub async fn handle_key(&mut self, key: KeyEvent, _state: &Arc<AppState>) {
         if self.filter_active {
             match key.code {
                 KeyCode::Esc | KeyCode::Enter => {
@@ -142,37 +316,88 @@ impl AlertsTab {
                     self.search_bar.deactivate();
                 }
                 KeyCode::Backspace => self.search_bar.backspace(),
+                KeyCode::Up => self.search_bar.history_prev(),
+                KeyCode::Down => self.search_bar.history_next(),
+                KeyCode::Char('r') if key.modifiers.contains(KeyModifiers::CONTROL) => self.search_bar.recall_last(),
                 KeyCode::Char(c) => self.search_bar.insert(c),
                 _ => {}
             }
-            return;
+            return Vec::new();
+        }
+
+        // Where to jump after `g`, `gg` still goes to the top
+        if std::mem::take(&mut self.goto) {
+            if let Some(jump) = self.jump(key.code) {
+                return vec![TabCommand::Jump(jump)];
+            }
+            if key.code != KeyCode::Char('g') {
+                return Vec::new();
+            }
+        } else if key.code == KeyCode::Char('g') {
+            self.goto = true;
+            return Vec::new();
         }
 
         match key.code {
+            KeyCode::Char('r') if key.modifiers.contains(KeyModifiers::CONTROL) => self.search_bar.recall_last(),
             KeyCode::Char('/') => {
                 self.filter_active = true;
                 self.search_bar.activate();
             }
+            KeyCode::Esc if self.related.is_some() => {
+                self.related = None;
+                self.table_state.select(Some(0));
+            }
             KeyCode::Esc => self.search_bar.clear(),
+            KeyCode::Char('p') => self.cycle_severity(),
+            KeyCode::Char('s') => {
+                let mut sort = self.sort;
+                sort.cycle(COLUMNS.len());
+                self.set_sort(sort);
+            }
+            KeyCode::Char('S') => {
+                let mut sort = self.sort;
+                sort.reverse();
+                self.set_sort(sort);
+            }
+            KeyCode::Char('v') => {
+                self.show_acknowledged = !self.show_acknowledged;
+                self.table_state.select(Some(0));
+            }
+            KeyCode::Enter => {
+                let selected = self.table_state.selected().unwrap_or(0);
+                if let Some(alert) = self.filtered_alerts().get(selected) {
+                    self.details = Some(AlertDetailsDialog::new((*alert).clone()).with_formats(self.formats.clone()));
+                }
+            }
+            KeyCode::Char('a') => {
+                let selected = self.table_state.selected().unwrap_or(0);
+                if let Some(alert) = self.filtered_alerts().get(selected) {
+                    return vec![TabCommand::AcknowledgeAlert {
+                        id: alert.id,
+                        timestamp: alert.timestamp,
+                    }];
+                }
+            }
+            KeyCode::Char('A') => return vec![TabCommand::AcknowledgeAllAlerts],
             _ => {
                 if let Some(delta) = navigation_delta(&key) {
-                    let len = self.cached_alerts.len();
-                    if len == 0 { return; }
-                    let current = self.table_state.selected().unwrap_or(0);
-                    let new_index = if delta == i32::MIN {
-                        0
-                    } else if delta == i32::MAX {
-                        len.saturating_sub(1)
-                    } else {
-                        (current as i32 + delta).clamp(0, len as i32 - 1) as usize
-                    };
-                    self.table_state.select(Some(new_index));
+                    let len = self.filtered_alerts().len();
+                    if let Some(idx) = step_index(self.table_state.selected(), len, delta) {
+                        self.table_state.select(Some(idx));
+                    }
                 }
             }
         }
+        Vec::new()
     }
 }
 
-fn truncate(s: &str, max: usize) -> &str {
-    if s.len() <= max { s } else { &s[..max] }
+/// Priorities in sorting order, the lowest first
+fn priority_rank(priority: AlertPriority) -> u8 {
+    match priority {
+        AlertPriority::Low => 0,
+        AlertPriority::Medium => 1,
+        AlertPriority::High => 2,
+    }
 }
@@ -1,24 +1,34 @@
 //! Connections tab implementation
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::sync::Arc;
 
-use crossterm::event::{KeyCode, KeyEvent};
+use chrono::{DateTime, Utc};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers, MouseEvent};
 use ratatui::{
     layout::{Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
-    text::Span,
-    widgets::{Block, Borders, Cell, Paragraph, Row, Table, TableState},
+    text::{Line, Span},
+    widgets::{Bar, BarChart, BarGroup, Block, Borders, Cell, Paragraph, Row, Table, TableState},
     Frame,
 };
-use tokio::sync::mpsc;
-
-use crate::app::events::navigation_delta;
+use crate::app::events::{click_position, navigation_delta};
+use crate::app::proxy::{ProxyChains, ProxyCorrelator};
+use crate::app::sampling::SamplingStatus;
 use crate::app::state::{AppMessage, AppState};
-use crate::models::Event;
-use crate::ui::dialogs::connection_details::ConnectionDetailsDialog;
+use crate::app::xref::ConnectionRef;
+use crate::config::KnownProxy;
+use crate::db::search::{SearchPage, SEARCH_PAGE};
+use crate::grpc::notifications::NotificationAction;
+use crate::models::{Connection, Event, RuleAction, RuleDuration};
+use crate::ui::dialogs::connection_details::{ConnectionDetailsDialog, DetailsResult};
+use crate::ui::tabs::{clicked_row, scroll_with_keys, step_index, Tab, TabCommand};
 use crate::ui::theme::Theme;
 use crate::ui::widgets::searchbar::SearchBar;
+use crate::ui::widgets::table::ColumnSort;
+use crate::utils::network::{port_anomaly, PortAnomaly};
+use crate::utils::text::truncate;
+use crate::utils::Formats;
 
 /// Aggregated connection entry
 #[derive(Clone)]
@@ -29,19 +39,118 @@ struct AggregatedConnection {
     count: u64,
     /// Unique key for this connection
     key: String,
+    /// Events per minute (unix minutes)
+    minutes: HashMap<i64, u64>,
+    /// Client a proxy made this connection for
+    origin: Option<String>,
+    /// Proxy this local connection was handed to
+    proxy: Option<String>,
+    /// Well-known protocol on a port it doesn't normally use
+    anomaly: Option<PortAnomaly>,
+    /// Processes and destinations seen in the group
+    processes: HashSet<String>,
+    destinations: HashSet<String>,
+}
+
+/// What makes connections one row of the table
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+enum Grouping {
+    /// Same process, protocol, destination and port
+    #[default]
+    ProcessDestination,
+    Process,
+    /// Same protocol, destination and port, from any process
+    Destination,
+    /// Every event a row of its own
+    Raw,
+}
+
+impl Grouping {
+    fn next(self) -> Self {
+        match self {
+            Self::ProcessDestination => Self::Process,
+            Self::Process => Self::Destination,
+            Self::Destination => Self::Raw,
+            Self::Raw => Self::ProcessDestination,
+        }
+    }
+
+    fn title(self) -> &'static str {
+        match self {
+            Self::ProcessDestination => "Unique Connections",
+            Self::Process => "Connections by Process",
+            Self::Destination => "Connections by Destination",
+            Self::Raw => "All Connections",
+        }
+    }
+}
+
+/// Page of stored connections shown in place of the live ones
+struct HistoryPage {
+    offset: usize,
+    total: usize,
+}
+
+const COLUMNS: [&str; 6] = ["Time", "Count", "Action", "Proto", "Destination", "Process"];
+
+/// Selectable timeline window lengths in minutes
+const TIMELINE_WINDOWS: [i64; 3] = [15, 30, 60];
+
+/// Minute (since the unix epoch) an event happened in
+fn event_minute(event: &Event) -> Option<i64> {
+    if event.unix_nano > 0 {
+        return Some(event.unix_nano / 60_000_000_000);
+    }
+    DateTime::parse_from_rfc3339(&event.time)
+        .ok()
+        .map(|t| t.timestamp().div_euclid(60))
+}
+
+fn is_denied(event: &Event) -> bool {
+    event.rule.as_ref().is_some_and(|r| r.action != RuleAction::Allow)
+}
+
+/// Where a connection goes, the hostname when known
+fn destination(conn: &Connection) -> &str {
+    if conn.dst_host.is_empty() { &conn.dst_ip } else { &conn.dst_host }
+}
+
+/// Action of an event, events from the database only carry its name
+fn action_name(event: &Event) -> String {
+    event
+        .rule
+        .as_ref()
+        .map(|rule| rule.action.to_string())
+        .or_else(|| event.connection.action.clone())
+        .unwrap_or_default()
 }
 
+
 impl AggregatedConnection {
-    fn new(event: Event) -> Self {
-        let key = Self::make_key(&event);
-        Self {
+    fn new(event: Event, chains: &ProxyChains) -> Self {
+        let origin = chains.origin(&event).map(str::to_string);
+        let proxy = chains.proxy(&event).map(str::to_string);
+        let key = Self::make_key(&event, origin.as_deref(), Grouping::default()).unwrap_or_default();
+        let conn = &event.connection;
+        let anomaly = port_anomaly(&conn.process_path, &conn.dst_ip, conn.dst_port);
+        let mut agg = Self {
             latest_event: event,
-            count: 1,
+            count: 0,
             key,
-        }
+            minutes: HashMap::new(),
+            origin,
+            proxy,
+            anomaly,
+            processes: HashSet::new(),
+            destinations: HashSet::new(),
+        };
+        agg.increment(agg.latest_event.clone());
+        agg
     }
 
-    fn make_key(event: &Event) -> String {
+    /// Grouping key, None for events shown on their own; proxied
+    /// connections are kept apart per client
+    fn make_key(event: &Event, origin: Option<&str>, grouping: Grouping) -> Option<String> {
         let conn = &event.connection;
         // Use process name (not full path) for more consistent grouping
         let process = conn.process_name();
@@ -52,23 +161,72 @@ impl AggregatedConnection {
         } else {
             &conn.dst_host
         };
-        format!("{}|{}|{}|{}", process, conn.protocol.to_lowercase(), dest, conn.dst_port)
+        let dest = format!("{}|{}|{}", conn.protocol.to_lowercase(), dest, conn.dst_port);
+        let key = match grouping {
+            Grouping::ProcessDestination => format!("{}|{}", process, dest),
+            Grouping::Process => process.to_string(),
+            Grouping::Destination => return Some(dest),
+            Grouping::Raw => return None,
+        };
+        Some(match origin {
+            Some(origin) => format!("{}|via:{}", key, origin),
+            None => key,
+        })
     }
 
     fn increment(&mut self, event: Event) {
+        // Coalesced events count as many as were merged into them
+        let count = event.count.max(1);
+        if let Some(minute) = event_minute(&event) {
+            *self.minutes.entry(minute).or_default() += count;
+        }
+        let conn = &event.connection;
+        self.processes.insert(conn.process_name().to_string());
+        self.destinations.insert(format!("{}:{}", destination(conn), conn.dst_port));
         self.latest_event = event;
-        self.count += 1;
+        self.count += count;
     }
 }
 
 pub struct ConnectionsTab {
     table_state: TableState,
+    /// Where the table was last drawn, for clicks
+    table_area: Rect,
     search_bar: SearchBar,
     filter_active: bool,
     /// Aggregated unique connections
     aggregated: Vec<AggregatedConnection>,
     details_dialog: Option<ConnectionDetailsDialog>,
     cached_node_addr: Option<String>,
+    proxies: ProxyCorrelator,
+
+    // Timeline view
+    show_timeline: bool,
+    /// Allowed/denied event counts per minute
+    timeline: BTreeMap<i64, (u64, u64)>,
+    window_idx: usize,
+    /// Last minute shown in the timeline
+    window_end: i64,
+    /// Bucket the table is filtered to
+    selected_minute: Option<i64>,
+    /// Only show connections flagged with a port anomaly
+    anomalies_only: bool,
+    /// Set while events are sampled under load
+    sampling: Option<SamplingStatus>,
+    /// Query of the stored connections
+    history_bar: SearchBar,
+    history_input: bool,
+    /// Results of the last query, shown instead of the live connections
+    history: Option<HistoryPage>,
+    history_error: Option<String>,
+    /// Only show the connections related to an item of another tab, and what it is
+    related: Option<(String, Vec<ConnectionRef>)>,
+    /// Most recent first unless sorted
+    sort: ColumnSort,
+    grouping: Grouping,
+    /// How long the details' sandbox action cuts a process off
+    sandbox_duration: RuleDuration,
+    formats: Formats,
 }
 
 impl ConnectionsTab {
@@ -77,57 +235,256 @@ impl ConnectionsTab {
         state.select(Some(0));
         Self {
             table_state: state,
+            table_area: Rect::default(),
             search_bar: SearchBar::new(),
             filter_active: false,
             aggregated: Vec::new(),
             details_dialog: None,
             cached_node_addr: None,
+            proxies: ProxyCorrelator::default(),
+            show_timeline: false,
+            timeline: BTreeMap::new(),
+            window_idx: 1,
+            window_end: 0,
+            selected_minute: None,
+            anomalies_only: false,
+            sampling: None,
+            history_bar: SearchBar::new()
+                .with_title(" Search history (e.g. firefox port:443 user:1000 action:deny since:2h, Enter to run) "),
+            history_input: false,
+            history: None,
+            history_error: None,
+            related: None,
+            sort: ColumnSort::default(),
+            grouping: Grouping::default(),
+            sandbox_duration: RuleDuration::ThirtyMinutes,
+            formats: Formats::default(),
         }
     }
 
-    pub fn showing_dialog(&self) -> bool {
-        self.details_dialog.is_some()
+    /// Number and date formatting
+    pub fn with_formats(mut self, formats: Formats) -> Self {
+        self.formats = formats;
+        self
     }
 
-    /// Update cached data from state (call before render)
-    pub async fn update_cache(&mut self, state: &Arc<AppState>) {
-        let connections = state.connections.read().await;
+    /// Switch formatting while running, e.g. into private mode
+    pub fn set_formats(&mut self, formats: Formats) {
+        if let Some(dialog) = self.details_dialog.take() {
+            self.details_dialog = Some(dialog.with_formats(formats.clone()));
+        }
+        self.formats = formats;
+    }
 
-        // Aggregate connections by process+destination
+    pub fn search_bar_mut(&mut self) -> &mut SearchBar {
+        &mut self.search_bar
+    }
+
+    /// How long the details' sandbox action cuts a process off
+    pub fn with_sandbox_duration(mut self, duration: RuleDuration) -> Self {
+        self.sandbox_duration = duration;
+        self
+    }
+
+    /// Local proxies to attribute outgoing connections through
+    pub fn with_proxies(mut self, proxies: Vec<KnownProxy>) -> Self {
+        self.proxies = ProxyCorrelator::new(proxies);
+        self
+    }
+
+    /// Aggregate events as grouped and cache the active node
+    pub fn set_events<'a>(&mut self, events: impl IntoIterator<Item = &'a Event>, node_addr: Option<String>) {
+        let events: Vec<&Event> = events.into_iter().collect();
+        let chains = self.proxies.correlate(events.iter().copied());
         let mut map: HashMap<String, AggregatedConnection> = HashMap::new();
+        let mut ungrouped = Vec::new();
+        self.timeline.clear();
+
+        for event in events {
+            if let Some(minute) = event_minute(event) {
+                let bucket = self.timeline.entry(minute).or_default();
+                if is_denied(event) {
+                    bucket.1 += event.count.max(1);
+                } else {
+                    bucket.0 += event.count.max(1);
+                }
+            }
 
-        for event in connections.iter() {
-            let key = AggregatedConnection::make_key(event);
+            let Some(key) = AggregatedConnection::make_key(event, chains.origin(event), self.grouping) else {
+                ungrouped.push(AggregatedConnection::new(event.clone(), &chains));
+                continue;
+            };
             if let Some(agg) = map.get_mut(&key) {
                 agg.increment(event.clone());
             } else {
-                map.insert(key.clone(), AggregatedConnection::new(event.clone()));
+                map.insert(key.clone(), AggregatedConnection::new(event.clone(), &chains));
             }
         }
 
         // Sort by most recent (latest timestamp first)
-        let mut aggregated: Vec<AggregatedConnection> = map.into_values().collect();
+        let mut aggregated: Vec<AggregatedConnection> = map.into_values().chain(ungrouped).collect();
         aggregated.sort_by(|a, b| b.latest_event.time.cmp(&a.latest_event.time));
         self.aggregated = aggregated;
+        self.cached_node_addr = node_addr;
+        self.window_end = self.timeline.keys().next_back().copied().unwrap_or(0);
+    }
+
+    /// Update cached data from state (call before render)
+    pub async fn update_cache(&mut self, state: &Arc<AppState>) {
+        let node_addr = {
+            let nodes = state.nodes.read().await;
+            nodes.active_addr().map(|s| s.to_string())
+        };
+        // Search results stay until left for the live connections
+        if self.history.is_some() {
+            self.cached_node_addr = node_addr;
+        } else {
+            let connections = state.connections.read().await;
+            self.set_events(connections.iter(), node_addr);
+        }
+        self.sampling = state.sampling_status();
+        // Keep the timeline anchored at the current minute while idle
+        self.window_end = self.window_end.max(Utc::now().timestamp().div_euclid(60));
+    }
+
+    /// Show a page of stored connections, one row each, or why the search failed
+    pub fn set_search_results(&mut self, result: Result<SearchPage, String>) {
+        match result {
+            Ok(page) => {
+                let chains = ProxyChains::default();
+                self.aggregated = page
+                    .events
+                    .into_iter()
+                    .map(|event| AggregatedConnection::new(event, &chains))
+                    .collect();
+                self.history = Some(HistoryPage {
+                    offset: page.offset,
+                    total: page.total,
+                });
+                self.history_error = None;
+                self.show_timeline = false;
+                self.selected_minute = None;
+                self.table_state.select(Some(0));
+            }
+            Err(e) => self.history_error = Some(e),
+        }
+    }
 
-        // Cache node address for rule creation
-        let nodes = state.nodes.read().await;
-        self.cached_node_addr = nodes.active_addr().map(|s| s.to_string());
+    /// Only show these connections of the live ones, until Esc
+    pub fn show_related(&mut self, label: String, connections: Vec<ConnectionRef>) {
+        self.related = Some((label, connections));
+        self.history = None;
+        self.history_error = None;
+        self.selected_minute = None;
+        self.table_state.select(Some(0));
+    }
+
+    /// Fetch another page of the current search
+    fn search(&self, offset: usize) -> Vec<TabCommand> {
+        vec![TabCommand::SearchConnections {
+            query: self.history_bar.query.clone(),
+            offset,
+        }]
+    }
+
+    fn window_start(&self) -> i64 {
+        self.window_end - TIMELINE_WINDOWS[self.window_idx] + 1
+    }
+
+    /// Move the timeline cursor, starting from the newest bucket
+    fn move_bucket(&mut self, delta: i64) {
+        let minute = match self.selected_minute {
+            Some(m) => m + delta,
+            None => self.window_end,
+        };
+        self.selected_minute = Some(minute.clamp(self.window_start(), self.window_end));
+        self.table_state.select(Some(0));
+    }
+
+    /// Aggregated connections matching the search query and timeline bucket
+    /// An `iface:<name>` term in the query matches the interface exactly
+    fn filtered(&self) -> Vec<&AggregatedConnection> {
+        let query = self.search_bar.query.to_lowercase();
+        let (interfaces, words): (Vec<&str>, Vec<&str>) =
+            query.split_whitespace().partition(|word| word.starts_with("iface:"));
+        let interface = interfaces.last().map(|term| &term["iface:".len()..]);
+        let query = words.join(" ");
+        let mut filtered: Vec<&AggregatedConnection> = self
+            .aggregated
+            .iter()
+            .filter(|agg| self.selected_minute.is_none_or(|m| agg.minutes.contains_key(&m)))
+            .filter(|agg| !self.anomalies_only || agg.anomaly.is_some())
+            .filter(|agg| {
+                self.related
+                    .as_ref()
+                    .is_none_or(|(_, refs)| refs.iter().any(|r| r.matches(&agg.latest_event.connection)))
+            })
+            .filter(|agg| interface.is_none_or(|i| agg.latest_event.connection.interface.eq_ignore_ascii_case(i)))
+            .filter(|agg| {
+                let conn = &agg.latest_event.connection;
+                query.is_empty()
+                    || conn.process_path.to_lowercase().contains(&query)
+                    || conn.dst_host.to_lowercase().contains(&query)
+                    || conn.dst_ip.to_lowercase().contains(&query)
+                    || conn.protocol.to_lowercase().contains(&query)
+                    || agg.origin.as_ref().is_some_and(|o| o.to_lowercase().contains(&query))
+            })
+            .collect();
+        self.sort.sort(&mut filtered, |column, a, b| {
+            let (ea, eb) = (&a.latest_event, &b.latest_event);
+            let (ca, cb) = (&ea.connection, &eb.connection);
+            match column {
+                0 => ea.time.cmp(&eb.time),
+                1 => self.shown_count(a).cmp(&self.shown_count(b)),
+                2 => action_name(ea).cmp(&action_name(eb)),
+                3 => ca.protocol.cmp(&cb.protocol),
+                // Grouped by process, by how many places it connects to
+                4 if self.grouping == Grouping::Process => a.destinations.len().cmp(&b.destinations.len()),
+                4 => destination(ca).cmp(destination(cb)).then_with(|| ca.dst_port.cmp(&cb.dst_port)),
+                // Grouped by destination, by how many processes connect to it
+                _ if self.grouping == Grouping::Destination => a.processes.len().cmp(&b.processes.len()),
+                _ => ca.process_name().cmp(cb.process_name()),
+            }
+        });
+        filtered
+    }
+
+    /// Events of a connection, only those of the selected minute in the timeline
+    fn shown_count(&self, agg: &AggregatedConnection) -> u64 {
+        match self.selected_minute {
+            Some(m) => agg.minutes.get(&m).copied().unwrap_or(0),
+            None => agg.count,
+        }
+    }
+
+    fn set_sort(&mut self, sort: ColumnSort) {
+        self.sort = sort;
+        self.table_state.select(Some(0));
+    }
+
+    /// Number of connections shown with the current filter
+    pub fn visible_len(&self) -> usize {
+        self.filtered().len()
     }
 
     pub fn render(&mut self, frame: &mut Frame, area: Rect, theme: &Theme) {
-        // Layout with optional filter bar
+        self.table_area = Rect::default();
+        // Layout with optional filter bar and timeline
         let chunks = Layout::default()
             .direction(Direction::Vertical)
-            .constraints(if self.filter_active {
-                vec![Constraint::Length(3), Constraint::Min(5)]
-            } else {
-                vec![Constraint::Length(0), Constraint::Min(5)]
-            })
+            .constraints([
+                Constraint::Length(if self.filter_active || self.history_input { 3 } else { 0 }),
+                Constraint::Length(if self.show_timeline { 10 } else { 0 }),
+                Constraint::Min(5),
+            ])
             .split(area);
 
         // Render filter bar if active
-        if self.filter_active {
+        if self.history_input {
+            self.history_bar.render(frame, chunks[0], theme.normal(), theme.border_focused());
+        } else if self.filter_active {
+            let matched = self.visible_len();
+            self.search_bar.set_matches(matched, self.aggregated.len());
             self.search_bar.render(
                 frame,
                 chunks[0],
@@ -136,27 +493,18 @@ impl ConnectionsTab {
             );
         }
 
-        // Filter aggregated connections
-        let filtered: Vec<&AggregatedConnection> = if self.search_bar.query.is_empty() {
-            self.aggregated.iter().collect()
-        } else {
-            let query = self.search_bar.query.to_lowercase();
-            self.aggregated
-                .iter()
-                .filter(|agg| {
-                    let conn = &agg.latest_event.connection;
-                    conn.process_path.to_lowercase().contains(&query)
-                        || conn.dst_host.to_lowercase().contains(&query)
-                        || conn.dst_ip.to_lowercase().contains(&query)
-                        || conn.protocol.to_lowercase().contains(&query)
-                })
-                .collect()
-        };
+        if self.show_timeline {
+            self.render_timeline(frame, chunks[1], theme);
+        }
+
+        let filtered = self.filtered();
 
         // Header
-        let header_cells = ["Time", "Count", "Proto", "Destination", "Process"]
-            .iter()
-            .map(|h| Cell::from(*h).style(theme.accent().add_modifier(Modifier::BOLD)));
+        let header_cells = self
+            .sort
+            .header(&COLUMNS)
+            .into_iter()
+            .map(|h| Cell::from(h).style(theme.accent().add_modifier(Modifier::BOLD)));
         let header = Row::new(header_cells).height(1);
 
         // Build rows
@@ -165,6 +513,7 @@ impl ConnectionsTab {
                 Cell::from(""),
                 Cell::from(""),
                 Cell::from(""),
+                Cell::from(""),
                 Cell::from("Waiting for connections..."),
                 Cell::from(""),
             ])
@@ -176,61 +525,120 @@ impl ConnectionsTab {
                     let event = &agg.latest_event;
                     let conn = &event.connection;
 
-                    let time = if event.time.len() > 8 {
+                    let time = match DateTime::parse_from_rfc3339(&event.time) {
+                        Ok(t) => self.formats.time(&t),
                         // Extract HH:MM:SS from ISO timestamp
-                        event.time.split('T').nth(1)
+                        Err(_) if event.time.len() > 8 => event.time.split('T').nth(1)
                             .and_then(|t| t.split('.').next())
-                            .unwrap_or(&event.time[..8.min(event.time.len())])
-                    } else {
-                        &event.time
+                            .unwrap_or(truncate(&event.time, 8))
+                            .to_string(),
+                        Err(_) => event.time.clone(),
                     };
 
-                    let dest = if conn.dst_host.is_empty() {
-                        format!("{}:{}", conn.dst_ip, conn.dst_port)
+                    let mut dest = if self.grouping == Grouping::Process && agg.destinations.len() > 1 {
+                        format!("{} destinations", self.formats.number(agg.destinations.len() as u64))
+                    } else if conn.dst_host.is_empty() {
+                        format!("{}:{}", self.formats.host(&conn.dst_ip), conn.dst_port)
                     } else {
-                        format!("{}:{}", truncate(&conn.dst_host, 30), conn.dst_port)
+                        format!("{}:{}", truncate(&self.formats.host(&conn.dst_host), 30), conn.dst_port)
                     };
+                    if let Some(proxy) = &agg.proxy {
+                        dest = format!("{} ({} proxy)", dest, proxy);
+                    }
 
-                    let process = truncate(conn.process_name(), 25);
+                    // Show the real client of proxied connections first
+                    let process = match &agg.origin {
+                        _ if self.grouping == Grouping::Destination && agg.processes.len() > 1 => {
+                            format!("{} processes", self.formats.number(agg.processes.len() as u64))
+                        }
+                        Some(origin) => format!("{} via {}", truncate(origin, 20), conn.process_name()),
+                        None => truncate(conn.process_name(), 25).to_string(),
+                    };
 
-                    let count_style = if agg.count > 100 {
-                        Style::default().fg(Color::Red)
-                    } else if agg.count > 10 {
-                        Style::default().fg(Color::Yellow)
+                    // Within a selected bucket, count only that minute
+                    let count = self.shown_count(agg);
+                    let count_style = if count > 100 {
+                        theme.error()
+                    } else if count > 10 {
+                        theme.warning()
                     } else {
                         theme.normal()
                     };
 
+                    let action = action_name(event);
+
+                    // Subtle marker for protocols on unusual ports
+                    let mut dest = vec![Span::raw(dest)];
+                    if let Some(anomaly) = &agg.anomaly {
+                        dest.push(Span::styled(
+                            format!(" ⚑ {}", anomaly.badge()),
+                            Style::default().fg(Color::Yellow),
+                        ));
+                    }
+
+                    // Stored by a previous run, nothing newer came in since
+                    let time = if event.restored {
+                        Cell::from(format!("↺ {}", time)).style(theme.dim())
+                    } else {
+                        Cell::from(time)
+                    };
+
                     Row::new(vec![
-                        Cell::from(time.to_string()),
-                        Cell::from(format!("{}", agg.count)).style(count_style),
+                        time,
+                        Cell::from(self.formats.number(count)).style(count_style),
+                        Cell::from(Theme::action_label(&action)).style(theme.action_style(&action)),
                         Cell::from(conn.protocol.clone()),
-                        Cell::from(dest),
-                        Cell::from(process.to_string()),
+                        Cell::from(Line::from(dest)),
+                        Cell::from(process),
                     ])
                 })
                 .collect()
         };
 
         let widths = [
-            Constraint::Length(10),     // Time
+            Constraint::Length(12),     // Time
             Constraint::Length(7),      // Count
+            Constraint::Length(8),      // Action
             Constraint::Length(6),      // Protocol
             Constraint::Percentage(40), // Destination
             Constraint::Percentage(30), // Process
         ];
 
         // Show count in title
-        let title = if self.search_bar.query.is_empty() {
-            format!(" Unique Connections ({}) ", filtered.len())
+        let mut title = if let Some(history) = &self.history {
+            format!(
+                " History {}-{} of {} [search: {}] ",
+                (history.offset + 1).min(history.total),
+                history.offset + self.aggregated.len(),
+                history.total,
+                self.history_bar.query
+            )
+        } else if self.search_bar.query.is_empty() {
+            format!(" {} ({}) ", self.grouping.title(), filtered.len())
         } else {
             format!(
-                " Unique Connections ({}/{}) [filter: {}] ",
+                " {} ({}/{}) [filter: {}] ",
+                self.grouping.title(),
                 filtered.len(),
                 self.aggregated.len(),
                 self.search_bar.query
             )
         };
+        if let Some((label, _)) = &self.related {
+            title.push_str(&format!("[{}, Esc = all] ", label));
+        }
+        if self.anomalies_only {
+            title.push_str("[anomalies only] ");
+        }
+        if self.history.is_none() && filtered.iter().any(|agg| agg.latest_event.restored) {
+            title.push_str("[↺ = from a previous run] ");
+        }
+        if let Some(e) = &self.history_error {
+            title.push_str(&format!("[search failed: {}] ", e));
+        }
+        if let Some(sampling) = self.sampling.as_ref().filter(|_| self.history.is_none()) {
+            title.push_str(&format!("[sampling 1 in {}, {} events seen] ", sampling.one_in, sampling.seen));
+        }
 
         let table = Table::new(rows, widths)
             .header(header)
@@ -242,17 +650,25 @@ impl ConnectionsTab {
             .row_highlight_style(theme.selected())
             .highlight_symbol("▶ ");
 
-        frame.render_stateful_widget(table, chunks[1], &mut self.table_state);
+        self.table_area = chunks[2];
+        frame.render_stateful_widget(table, chunks[2], &mut self.table_state);
 
         // Show help hint at bottom if space
-        if chunks[1].height > 10 && !self.filter_active {
+        if chunks[2].height > 10 && !self.filter_active && !self.history_input {
             let hint_area = Rect::new(
-                chunks[1].x,
-                chunks[1].y + chunks[1].height - 1,
-                chunks[1].width,
+                chunks[2].x,
+                chunks[2].y + chunks[2].height - 1,
+                chunks[2].width,
                 1,
             );
-            let hint = Paragraph::new(" / = filter  ↑↓ = navigate  Enter = details")
+            let hint = if self.history.is_some() {
+                " H = new search  n/p = next/previous page  / = filter  Enter = details  Esc = live connections"
+            } else if self.show_timeline {
+                " / = filter  ↑↓ = navigate  ←→ = minute  w = window  t = hide timeline  a = anomalies"
+            } else {
+                " / = filter  H = search history  ↑↓ = navigate  Enter = details  R = replay  t = timeline  a = anomalies  g = group  s/S = sort/reverse"
+            };
+            let hint = Paragraph::new(hint)
                 .style(theme.dim());
             frame.render_widget(hint, hint_area);
         }
@@ -263,13 +679,135 @@ impl ConnectionsTab {
         }
     }
 
-    pub async fn handle_key(&mut self, key: KeyEvent, _state: &Arc<AppState>, state_tx: &mpsc::Sender<AppMessage>) {
+    /// Per-minute histogram of allowed (green) and denied (red) events
+    fn render_timeline(&self, frame: &mut Frame, area: Rect, theme: &Theme) {
+        let window = TIMELINE_WINDOWS[self.window_idx];
+        let start = self.window_start();
+
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_style(theme.border())
+            .title(Span::styled(self.timeline_title(), theme.accent()));
+        let inner = block.inner(area);
+
+        // Two bars per minute plus a one column gap between minutes
+        let bar_width = ((inner.width as i64 / window - 1) / 2).max(1) as u16;
+
+        let mut chart = BarChart::default()
+            .block(block)
+            .bar_width(bar_width)
+            .bar_gap(0)
+            .group_gap(1);
+
+        for minute in start..=self.window_end {
+            let (allowed, denied) = self.timeline.get(&minute).copied().unwrap_or_default();
+            let selected = self.selected_minute == Some(minute);
+            let style = |color| {
+                let style = Style::default().fg(color);
+                if selected { style.add_modifier(Modifier::BOLD) } else { style }
+            };
+            let bars = [
+                Bar::default().value(allowed).text_value(String::new()).style(style(theme.allow)),
+                Bar::default().value(denied).text_value(String::new()).style(style(theme.deny)),
+            ];
+            chart = chart.data(BarGroup::default().bars(&bars));
+        }
+
+        frame.render_widget(chart, area);
+    }
+
+    fn format_minute(&self, minute: i64) -> String {
+        DateTime::<Utc>::from_timestamp(minute * 60, 0)
+            .map(|t| self.formats.minute(&t))
+            .unwrap_or_default()
+    }
+
+    fn timeline_title(&self) -> String {
+        let range = format!(
+            " Timeline {}-{} ({}m) ",
+            self.format_minute(self.window_start()),
+            self.format_minute(self.window_end),
+            TIMELINE_WINDOWS[self.window_idx]
+        );
+        match self.selected_minute {
+            Some(m) => {
+                let (allowed, denied) = self.timeline.get(&m).copied().unwrap_or_default();
+                format!(
+                    "{}[{}: {} allowed, {} denied] ",
+                    range,
+                    self.format_minute(m),
+                    self.formats.number(allowed),
+                    self.formats.number(denied)
+                )
+            }
+            // Which bar is which, without going by color
+            None => format!("{}[✓ allowed | ✗ denied] ", range),
+        }
+    }
+}
+
+impl Tab for ConnectionsTab {
+    /// Clicking a row selects it
+    fn handle_mouse(&mut self, event: MouseEvent) -> Vec<TabCommand> {
+        let Some((column, row)) = click_position(&event).filter(|_| !self.showing_dialog()) else {
+            return scroll_with_keys(self, event);
+        };
+        if let Some(idx) = clicked_row(self.table_area, self.table_state.offset(), column, row) {
+            if idx < self.visible_len() {
+                self.table_state.select(Some(idx));
+            }
+        }
+        Vec::new()
+    }
+
+    fn handle_key(&mut self, key: KeyEvent) -> Vec<TabCommand> {
         // Handle details dialog input
         if let Some(dialog) = &mut self.details_dialog {
-            if dialog.handle_key(key, state_tx, self.cached_node_addr.as_deref()) {
-                self.details_dialog = None;
+            let Some(result) = dialog.handle_key(key) else {
+                return Vec::new();
+            };
+            self.details_dialog = None;
+            if let (DetailsResult::CreateRule(rule), Some(addr)) = (result, &self.cached_node_addr) {
+                return vec![
+                    // Update local state
+                    TabCommand::send(AppMessage::RuleAdded {
+                        node_addr: addr.clone(),
+                        rule: rule.clone(),
+                    }),
+                    // Send to daemon
+                    TabCommand::send(AppMessage::SendNotification {
+                        node_addr: addr.clone(),
+                        action: NotificationAction::ChangeRule(rule),
+                    }),
+                ];
+            }
+            return Vec::new();
+        }
+
+        if self.history_input {
+            let bar = &mut self.history_bar;
+            match key.code {
+                KeyCode::Esc => {
+                    self.history_input = false;
+                    bar.deactivate();
+                }
+                KeyCode::Enter => {
+                    self.history_input = false;
+                    bar.deactivate();
+                    return self.search(0);
+                }
+                KeyCode::Backspace => bar.backspace(),
+                KeyCode::Delete => bar.delete(),
+                KeyCode::Left => bar.move_left(),
+                KeyCode::Right => bar.move_right(),
+                KeyCode::Home => bar.move_home(),
+                KeyCode::End => bar.move_end(),
+                KeyCode::Up => bar.history_prev(),
+                KeyCode::Down => bar.history_next(),
+                KeyCode::Char(c) => bar.insert(c),
+                _ => {}
             }
-            return;
+            return Vec::new();
         }
 
         // Handle filter input mode
@@ -301,59 +839,128 @@ impl ConnectionsTab {
                 KeyCode::End => {
                     self.search_bar.move_end();
                 }
+                KeyCode::Up => {
+                    self.search_bar.history_prev();
+                }
+                KeyCode::Down => {
+                    self.search_bar.history_next();
+                }
+                KeyCode::Char('r') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    self.search_bar.recall_last();
+                }
                 KeyCode::Char(c) => {
                     self.search_bar.insert(c);
                 }
                 _ => {}
             }
-            return;
+            return Vec::new();
         }
 
         // Normal mode
         match key.code {
+            KeyCode::Char('r') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.search_bar.recall_last();
+            }
             KeyCode::Char('/') => {
                 self.filter_active = true;
                 self.search_bar.activate();
             }
+            KeyCode::Char('H') => {
+                self.history_input = true;
+                self.history_bar.activate();
+            }
+            KeyCode::Char('n') => {
+                if let Some(history) = &self.history {
+                    let next = history.offset + SEARCH_PAGE;
+                    if next < history.total {
+                        return self.search(next);
+                    }
+                }
+            }
+            KeyCode::Char('p') => {
+                if let Some(history) = self.history.as_ref().filter(|h| h.offset > 0) {
+                    return self.search(history.offset.saturating_sub(SEARCH_PAGE));
+                }
+            }
+            KeyCode::Esc if self.history.is_some() => {
+                // Back to the live connections at the next refresh
+                self.history = None;
+                self.history_error = None;
+                self.aggregated.clear();
+                self.table_state.select(Some(0));
+            }
+            KeyCode::Esc if self.related.is_some() => {
+                self.related = None;
+                self.table_state.select(Some(0));
+            }
+            KeyCode::Esc if self.selected_minute.is_some() => {
+                self.selected_minute = None;
+            }
             KeyCode::Esc => {
                 self.search_bar.clear();
             }
+            KeyCode::Char('t') => {
+                self.show_timeline = !self.show_timeline;
+                self.selected_minute = None;
+                self.table_state.select(Some(0));
+            }
+            KeyCode::Char('a') => {
+                self.anomalies_only = !self.anomalies_only;
+                self.table_state.select(Some(0));
+            }
+            // Regrouped at the next refresh, search results are one row each anyway
+            KeyCode::Char('g') if self.history.is_none() => {
+                self.grouping = self.grouping.next();
+                self.table_state.select(Some(0));
+            }
+            KeyCode::Char('s') => {
+                let mut sort = self.sort;
+                sort.cycle(COLUMNS.len());
+                self.set_sort(sort);
+            }
+            KeyCode::Char('S') => {
+                let mut sort = self.sort;
+                sort.reverse();
+                self.set_sort(sort);
+            }
+            KeyCode::Char('w') if self.show_timeline => {
+                self.window_idx = (self.window_idx + 1) % TIMELINE_WINDOWS.len();
+                if let Some(m) = self.selected_minute {
+                    self.selected_minute = Some(m.max(self.window_start()));
+                }
+            }
+            KeyCode::Left if self.show_timeline => self.move_bucket(-1),
+            KeyCode::Right if self.show_timeline => self.move_bucket(1),
+            KeyCode::Char('R') => {
+                // Ask again with today's rules
+                let selected = self.table_state.selected().and_then(|idx| self.filtered().get(idx).copied());
+                if let Some(agg) = selected {
+                    return vec![TabCommand::ReplayEvent(Box::new(agg.latest_event.clone()))];
+                }
+            }
             KeyCode::Enter => {
                 // Open details dialog for selected connection
-                if let Some(idx) = self.table_state.selected() {
-                    if idx < self.aggregated.len() {
-                        let event = self.aggregated[idx].latest_event.clone();
-                        self.details_dialog = Some(ConnectionDetailsDialog::new(event));
-                    }
+                let selected = self.table_state.selected().and_then(|idx| self.filtered().get(idx).copied());
+                if let Some(agg) = selected {
+                    self.details_dialog = Some(
+                        ConnectionDetailsDialog::new(agg.latest_event.clone())
+                            .with_sandbox_duration(self.sandbox_duration.clone())
+                            .with_formats(self.formats.clone()),
+                    );
                 }
             }
             _ => {
                 if let Some(delta) = navigation_delta(&key) {
-                    let len = self.aggregated.len();
-                    if len == 0 {
-                        return;
+                    if let Some(idx) = step_index(self.table_state.selected(), self.visible_len(), delta) {
+                        self.table_state.select(Some(idx));
                     }
-
-                    let current = self.table_state.selected().unwrap_or(0);
-                    let new_index = if delta == i32::MIN {
-                        0
-                    } else if delta == i32::MAX {
-                        len.saturating_sub(1)
-                    } else {
-                        (current as i32 + delta).clamp(0, len as i32 - 1) as usize
-                    };
-
-                    self.table_state.select(Some(new_index));
                 }
             }
         }
+        Vec::new()
     }
-}
 
-fn truncate(s: &str, max: usize) -> &str {
-    if s.len() <= max {
-        s
-    } else {
-        &s[..max]
+    fn showing_dialog(&self) -> bool {
+        self.details_dialog.is_some() || self.history_input
     }
 }
@@ -0,0 +1,299 @@
+//! Daemon log tab: the daemon's log file as it grows, filtered by level
+
+use std::sync::Arc;
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers, MouseEvent};
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Modifier, Style},
+    text::Span,
+    widgets::{Block, Borders, Cell, Row, Table, TableState},
+    Frame,
+};
+
+use crate::app::events::{click_position, navigation_delta};
+use crate::app::state::AppState;
+use crate::models::Node;
+use crate::ui::tabs::{clicked_row, scroll_with_keys, step_index, Tab, TabCommand};
+use crate::ui::theme::Theme;
+use crate::ui::widgets::searchbar::SearchBar;
+use crate::utils::daemon_log::{LogEntry, LogLevel, LogTail, DEFAULT_LOG_FILE};
+use crate::utils::host::is_local_node;
+use crate::utils::Formats;
+
+/// Log file of the active node, or why it can't be read from here
+pub fn log_source(node: Option<&Node>) -> Result<String, String> {
+    let path = match node {
+        Some(node) if !is_local_node(&node.addr) => {
+            return Err(format!("{} runs on another host, its log file can't be read from here", node.addr))
+        }
+        Some(node) => node.log_file().unwrap_or_else(|| DEFAULT_LOG_FILE.to_string()),
+        None => DEFAULT_LOG_FILE.to_string(),
+    };
+    if path.starts_with("/dev/std") {
+        return Err(format!(
+            "The daemon logs to {}, see journalctl -u opensnitch for its output",
+            path
+        ));
+    }
+    Ok(path)
+}
+
+pub struct DaemonLogTab {
+    table_state: TableState,
+    /// Where the table was last drawn, for clicks
+    table_area: Rect,
+    search_bar: SearchBar,
+    filter_active: bool,
+    tail: Option<LogTail>,
+    /// Why there is nothing to show: no file to follow, or it can't be read
+    problem: Option<String>,
+    /// Entries below this level are hidden
+    min_level: LogLevel,
+    /// Keep the newest entry selected as entries come in
+    follow: bool,
+    formats: Formats,
+}
+
+impl DaemonLogTab {
+    pub fn new() -> Self {
+        Self {
+            table_state: TableState::default(),
+            table_area: Rect::default(),
+            search_bar: SearchBar::new(),
+            filter_active: false,
+            tail: None,
+            problem: None,
+            min_level: LogLevel::Debug,
+            follow: true,
+            formats: Formats::default(),
+        }
+    }
+
+    /// Number and date formatting
+    pub fn with_formats(mut self, formats: Formats) -> Self {
+        self.formats = formats;
+        self
+    }
+
+    pub fn set_formats(&mut self, formats: Formats) {
+        self.formats = formats;
+    }
+
+    pub fn search_bar_mut(&mut self) -> &mut SearchBar {
+        &mut self.search_bar
+    }
+
+    /// Follow a log file, or say why none can be; the entries read are kept
+    /// while the file stays the same
+    pub fn set_source(&mut self, source: Result<String, String>) {
+        match source {
+            Ok(path) => {
+                if self.tail.as_ref().is_none_or(|t| t.path().to_str() != Some(path.as_str())) {
+                    self.tail = Some(LogTail::new(path));
+                    self.follow = true;
+                }
+            }
+            Err(reason) => {
+                self.tail = None;
+                self.problem = Some(reason);
+            }
+        }
+    }
+
+    /// Read what the daemon logged since the last poll
+    pub fn poll(&mut self) {
+        let Some(tail) = &mut self.tail else {
+            return;
+        };
+        self.problem = match tail.poll() {
+            Ok(_) => None,
+            Err(e) => Some(format!("Can't read {}: {}", tail.path().display(), e)),
+        };
+        if self.follow {
+            self.select_last();
+        }
+    }
+
+    pub async fn update_cache(&mut self, state: &Arc<AppState>) {
+        let source = log_source(state.nodes.read().await.active_node());
+        self.set_source(source);
+        self.poll();
+    }
+
+    /// Entries at the minimum level or above matching the search query
+    pub fn shown_entries(&self) -> Vec<&LogEntry> {
+        let Some(tail) = &self.tail else {
+            return Vec::new();
+        };
+        let query = self.search_bar.query.to_lowercase();
+        tail.entries()
+            .iter()
+            .filter(|e| e.level.unwrap_or_default() >= self.min_level)
+            .filter(|e| query.is_empty() || e.message.to_lowercase().contains(&query))
+            .collect()
+    }
+
+    fn select_last(&mut self) {
+        let len = self.shown_entries().len();
+        self.table_state.select(len.checked_sub(1));
+    }
+
+    fn level_style(level: Option<LogLevel>, theme: &Theme) -> Style {
+        match level {
+            Some(LogLevel::Fatal | LogLevel::Error) => theme.error(),
+            Some(LogLevel::Warning) => theme.warning(),
+            Some(LogLevel::Important) => theme.info().add_modifier(Modifier::BOLD),
+            Some(LogLevel::Debug) => theme.dim(),
+            Some(LogLevel::Info) | None => theme.normal(),
+        }
+    }
+
+    pub fn render(&mut self, frame: &mut Frame, area: Rect, theme: &Theme) {
+        self.table_area = Rect::default();
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints(if self.filter_active {
+                vec![Constraint::Length(3), Constraint::Min(5)]
+            } else {
+                vec![Constraint::Length(0), Constraint::Min(5)]
+            })
+            .split(area);
+
+        if self.filter_active {
+            let matched = self.shown_entries().len();
+            let total = self.tail.as_ref().map_or(0, |t| t.entries().len());
+            self.search_bar.set_matches(matched, total);
+            self.search_bar.render(frame, chunks[0], theme.normal(), theme.border_focused());
+        }
+
+        let entries = self.shown_entries();
+
+        let header = Row::new(
+            ["Time", "Level", "Message"]
+                .iter()
+                .map(|h| Cell::from(*h).style(theme.accent().add_modifier(Modifier::BOLD))),
+        )
+        .height(1);
+
+        let rows: Vec<Row> = if let Some(problem) = &self.problem {
+            vec![Row::new(vec![Cell::from(""), Cell::from(""), Cell::from(problem.clone())]).style(theme.warning())]
+        } else if entries.is_empty() {
+            vec![Row::new(vec![Cell::from(""), Cell::from(""), Cell::from("Nothing logged at this level")]).style(theme.dim())]
+        } else {
+            entries
+                .iter()
+                .map(|entry| {
+                    let style = Self::level_style(entry.level, theme);
+                    Row::new(vec![
+                        Cell::from(entry.time.clone()).style(theme.dim()),
+                        Cell::from(entry.level.map_or("", |l| l.label())).style(style),
+                        Cell::from(self.formats.text(&entry.message).into_owned()),
+                    ])
+                    .style(style)
+                })
+                .collect()
+        };
+
+        let widths = [
+            Constraint::Length(20), // Time
+            Constraint::Length(10), // Level
+            Constraint::Min(20),    // Message
+        ];
+
+        let file = self.tail.as_ref().map(|t| t.path().display().to_string()).unwrap_or_default();
+        let title = format!(
+            " {} ({}) [{}+{}]  /=search  v=level  f=follow ",
+            file,
+            entries.len(),
+            self.min_level.label(),
+            if self.follow { ", following" } else { "" }
+        );
+        let table = Table::new(rows, widths)
+            .header(header)
+            .block(
+                Block::default()
+                    .borders(Borders::NONE)
+                    .title(Span::styled(title, theme.accent())),
+            )
+            .row_highlight_style(theme.selected())
+            .highlight_symbol("▶ ");
+
+        self.table_area = chunks[1];
+        frame.render_stateful_widget(table, chunks[1], &mut self.table_state);
+    }
+}
+
+impl Default for DaemonLogTab {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Tab for DaemonLogTab {
+    /// Clicking a row selects it and stops following
+    fn handle_mouse(&mut self, event: MouseEvent) -> Vec<TabCommand> {
+        let Some((column, row)) = click_position(&event) else {
+            return scroll_with_keys(self, event);
+        };
+        if let Some(idx) = clicked_row(self.table_area, self.table_state.offset(), column, row) {
+            if idx < self.shown_entries().len() {
+                self.table_state.select(Some(idx));
+                self.follow = false;
+            }
+        }
+        Vec::new()
+    }
+
+    fn handle_key(&mut self, key: KeyEvent) -> Vec<TabCommand> {
+        if self.filter_active {
+            match key.code {
+                KeyCode::Esc | KeyCode::Enter => {
+                    self.filter_active = false;
+                    self.search_bar.deactivate();
+                }
+                KeyCode::Backspace => self.search_bar.backspace(),
+                KeyCode::Up => self.search_bar.history_prev(),
+                KeyCode::Down => self.search_bar.history_next(),
+                KeyCode::Char('r') if key.modifiers.contains(KeyModifiers::CONTROL) => self.search_bar.recall_last(),
+                KeyCode::Char(c) => self.search_bar.insert(c),
+                _ => {}
+            }
+            self.select_last();
+            return Vec::new();
+        }
+
+        match key.code {
+            KeyCode::Char('r') if key.modifiers.contains(KeyModifiers::CONTROL) => self.search_bar.recall_last(),
+            KeyCode::Char('/') => {
+                self.filter_active = true;
+                self.search_bar.activate();
+            }
+            KeyCode::Esc => {
+                self.search_bar.clear();
+                self.select_last();
+            }
+            KeyCode::Char('v') => {
+                self.min_level = self.min_level.next();
+                self.select_last();
+            }
+            KeyCode::Char('f') => {
+                self.follow = !self.follow;
+                if self.follow {
+                    self.select_last();
+                }
+            }
+            _ => {
+                if let Some(delta) = navigation_delta(&key) {
+                    let len = self.shown_entries().len();
+                    if let Some(idx) = step_index(self.table_state.selected(), len, delta) {
+                        self.table_state.select(Some(idx));
+                        // Moving to the newest entry resumes following
+                        self.follow = idx + 1 == len;
+                    }
+                }
+            }
+        }
+        Vec::new()
+    }
+}
@@ -0,0 +1,247 @@
+//! DNS tab: hostnames and the addresses they resolved to
+
+use std::sync::Arc;
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers, MouseEvent};
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::Modifier,
+    text::Span,
+    widgets::{Block, Borders, Cell, Row, Table, TableState},
+    Frame,
+};
+
+use crate::app::events::{click_position, navigation_delta};
+use crate::app::state::AppState;
+use crate::models::{DnsEntry, Operator, Rule, RuleAction, RuleDuration};
+use crate::ui::tabs::{clicked_row, scroll_with_keys, step_index, Tab, TabCommand};
+use crate::ui::theme::Theme;
+use crate::ui::widgets::searchbar::SearchBar;
+use crate::utils::Formats;
+
+pub struct DnsTab {
+    table_state: TableState,
+    /// Where the table was last drawn, for clicks
+    table_area: Rect,
+    search_bar: SearchBar,
+    filter_active: bool,
+    cached_entries: Vec<DnsEntry>,
+    /// Node whose mappings are shown unless all are
+    cached_node_addr: Option<String>,
+    /// Show the mappings of every node
+    all_nodes: bool,
+    formats: Formats,
+}
+
+impl DnsTab {
+    pub fn new() -> Self {
+        let mut state = TableState::default();
+        state.select(Some(0));
+        Self {
+            table_state: state,
+            table_area: Rect::default(),
+            search_bar: SearchBar::new(),
+            filter_active: false,
+            cached_entries: Vec::new(),
+            cached_node_addr: None,
+            all_nodes: false,
+            formats: Formats::default(),
+        }
+    }
+
+    /// Number and date formatting
+    pub fn with_formats(mut self, formats: Formats) -> Self {
+        self.formats = formats;
+        self
+    }
+
+    pub fn set_formats(&mut self, formats: Formats) {
+        self.formats = formats;
+    }
+
+    pub fn search_bar_mut(&mut self) -> &mut SearchBar {
+        &mut self.search_bar
+    }
+
+    /// Mappings most recently seen first, and the node they are shown for
+    pub fn set_entries(&mut self, entries: Vec<DnsEntry>, node_addr: Option<String>) {
+        self.cached_entries = entries;
+        self.cached_node_addr = node_addr;
+    }
+
+    pub async fn update_cache(&mut self, state: &Arc<AppState>) {
+        let node_addr = state.nodes.read().await.active_node.clone();
+        let entries = state.dns.lock().unwrap().entries();
+        self.set_entries(entries, node_addr);
+    }
+
+    /// Mappings of the node shown matching the search query
+    pub fn filtered_entries(&self) -> Vec<&DnsEntry> {
+        let query = self.search_bar.query.to_lowercase();
+        self.cached_entries
+            .iter()
+            .filter(|e| self.all_nodes || self.cached_node_addr.as_ref().is_none_or(|addr| e.node == *addr))
+            .filter(|e| query.is_empty() || e.host.contains(&query) || e.ip.contains(&query))
+            .collect()
+    }
+
+    /// A rule for the selected hostname, to finish in the rule editor
+    fn selected_rule(&self) -> Option<TabCommand> {
+        let selected = self.table_state.selected().unwrap_or(0);
+        let entry = self.filtered_entries().get(selected).copied()?;
+        let rule = Rule::new(
+            &format!("allow-{}", entry.host),
+            RuleAction::Allow,
+            RuleDuration::Always,
+            Operator::simple("dest.host", &entry.host),
+        );
+        Some(TabCommand::NewRule {
+            node_addr: entry.node.clone(),
+            rule: Box::new(rule),
+        })
+    }
+
+    pub fn render(&mut self, frame: &mut Frame, area: Rect, theme: &Theme) {
+        self.table_area = Rect::default();
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints(if self.filter_active {
+                vec![Constraint::Length(3), Constraint::Min(5)]
+            } else {
+                vec![Constraint::Length(0), Constraint::Min(5)]
+            })
+            .split(area);
+
+        if self.filter_active {
+            let matched = self.filtered_entries().len();
+            self.search_bar.set_matches(matched, self.cached_entries.len());
+            self.search_bar.render(frame, chunks[0], theme.normal(), theme.border_focused());
+        }
+
+        let entries = self.filtered_entries();
+
+        let mut headers = vec!["Last seen", "Host", "Address", "Count", "First seen"];
+        if self.all_nodes {
+            headers.push("Node");
+        }
+        let header = Row::new(
+            headers
+                .iter()
+                .map(|h| Cell::from(*h).style(theme.accent().add_modifier(Modifier::BOLD))),
+        )
+        .height(1);
+
+        let rows: Vec<Row> = if entries.is_empty() {
+            vec![Row::new(vec![Cell::from(""), Cell::from("No DNS responses seen yet")]).style(theme.dim())]
+        } else {
+            entries
+                .iter()
+                .map(|entry| {
+                    let mut cells = vec![
+                        Cell::from(self.formats.time(&entry.last_seen)),
+                        Cell::from(self.formats.host(&entry.host).into_owned()),
+                        Cell::from(self.formats.host(&entry.ip).into_owned()),
+                        Cell::from(self.formats.number(entry.count)),
+                        Cell::from(self.formats.date_time(&entry.first_seen)).style(theme.dim()),
+                    ];
+                    if self.all_nodes {
+                        cells.push(Cell::from(entry.node.clone()));
+                    }
+                    Row::new(cells).style(theme.normal())
+                })
+                .collect()
+        };
+
+        let mut widths = vec![
+            Constraint::Length(12),     // Last seen
+            Constraint::Percentage(40), // Host
+            Constraint::Length(40),     // Address
+            Constraint::Length(8),      // Count
+            Constraint::Length(20),     // First seen
+        ];
+        if self.all_nodes {
+            widths.push(Constraint::Length(24));
+        }
+
+        let title = format!(
+            " DNS ({}) [{}]  /=filter  n=all nodes  r=rule for host ",
+            entries.len(),
+            if self.all_nodes { "all nodes" } else { "active node" }
+        );
+        let table = Table::new(rows, widths)
+            .header(header)
+            .block(
+                Block::default()
+                    .borders(Borders::NONE)
+                    .title(Span::styled(title, theme.accent())),
+            )
+            .row_highlight_style(theme.selected())
+            .highlight_symbol("▶ ");
+
+        self.table_area = chunks[1];
+        frame.render_stateful_widget(table, chunks[1], &mut self.table_state);
+    }
+}
+
+impl Default for DnsTab {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Tab for DnsTab {
+    /// Clicking a row selects it
+    fn handle_mouse(&mut self, event: MouseEvent) -> Vec<TabCommand> {
+        let Some((column, row)) = click_position(&event).filter(|_| !self.showing_dialog()) else {
+            return scroll_with_keys(self, event);
+        };
+        if let Some(idx) = clicked_row(self.table_area, self.table_state.offset(), column, row) {
+            if idx < self.filtered_entries().len() {
+                self.table_state.select(Some(idx));
+            }
+        }
+        Vec::new()
+    }
+
+    fn handle_key(&mut self, key: KeyEvent) -> Vec<TabCommand> {
+        if self.filter_active {
+            match key.code {
+                KeyCode::Esc | KeyCode::Enter => {
+                    self.filter_active = false;
+                    self.search_bar.deactivate();
+                }
+                KeyCode::Backspace => self.search_bar.backspace(),
+                KeyCode::Up => self.search_bar.history_prev(),
+                KeyCode::Down => self.search_bar.history_next(),
+                KeyCode::Char('r') if key.modifiers.contains(KeyModifiers::CONTROL) => self.search_bar.recall_last(),
+                KeyCode::Char(c) => self.search_bar.insert(c),
+                _ => {}
+            }
+            self.table_state.select(Some(0));
+            return Vec::new();
+        }
+
+        match key.code {
+            KeyCode::Char('r') if key.modifiers.contains(KeyModifiers::CONTROL) => self.search_bar.recall_last(),
+            KeyCode::Char('/') => {
+                self.filter_active = true;
+                self.search_bar.activate();
+            }
+            KeyCode::Esc => self.search_bar.clear(),
+            KeyCode::Char('n') => {
+                self.all_nodes = !self.all_nodes;
+                self.table_state.select(Some(0));
+            }
+            KeyCode::Char('r') | KeyCode::Enter => return self.selected_rule().into_iter().collect(),
+            _ => {
+                if let Some(delta) = navigation_delta(&key) {
+                    let len = self.filtered_entries().len();
+                    if let Some(idx) = step_index(self.table_state.selected(), len, delta) {
+                        self.table_state.select(Some(idx));
+                    }
+                }
+            }
+        }
+        Vec::new()
+    }
+}
@@ -10,15 +10,19 @@ use ratatui::{
     widgets::{Block, Borders, Cell, Clear, List, ListItem, ListState, Paragraph, Row, Table, TableState},
     Frame,
 };
-use tokio::sync::mpsc;
-
 use crate::app::events::navigation_delta;
 use crate::app::state::{AppMessage, AppState};
 use crate::grpc::notifications::NotificationAction;
-use crate::models::{FwChain, FwRule, SysFirewall};
+use crate::models::killswitch;
+use crate::models::{DaemonVersion, Feature, FwChain, FwRule, SysFirewall};
+use crate::ui::dialogs::fw_chain::{FwChainEditorDialog, FwChainEditorResult};
 use crate::ui::dialogs::fw_rule::{FwRuleEditorDialog, FwRuleEditorResult};
+use crate::ui::dialogs::killswitch::{KillSwitchDialog, KillSwitchResult};
 use crate::ui::layout::DialogLayout;
+use crate::ui::tabs::{step_index, Jump, Tab, TabCommand};
 use crate::ui::theme::Theme;
+use crate::utils::text::truncate;
+use crate::utils::Formats;
 
 const FIREWALL_CONFIG_PATH: &str = "/etc/opensnitchd/system-fw.json";
 
@@ -35,7 +39,12 @@ pub struct FirewallTab {
     rule_state: TableState,
     cached_firewall: Option<SysFirewall>,
     cached_chains: Vec<FwChain>,
+    cached_node_addr: Option<String>,
+    cached_daemon_version: Option<DaemonVersion>,
     selected_chain_idx: usize,
+    /// Show tables → chains → hooks as a tree instead of the split view
+    topology: bool,
+    topology_state: ListState,
 
     // Dialogs
     show_toggle_confirm: bool,
@@ -45,9 +54,20 @@ pub struct FirewallTab {
     show_editor: bool,
     editor: Option<FwRuleEditorDialog>,
 
+    /// Chain being created or having its policy changed
+    chain_editor: Option<FwChainEditorDialog>,
+
+    // VPN kill-switch assistant
+    killswitch: Option<KillSwitchDialog>,
+
     // Delete confirmation
     show_delete_confirm: bool,
     rule_to_delete: Option<String>,
+    chain_to_delete: Option<FwChain>,
+
+    /// `g` pressed, waiting for where to jump
+    goto: bool,
+    formats: Formats,
 }
 
 impl FirewallTab {
@@ -63,50 +83,96 @@ impl FirewallTab {
             rule_state,
             cached_firewall: None,
             cached_chains: Vec::new(),
+            cached_node_addr: None,
+            cached_daemon_version: None,
             selected_chain_idx: 0,
+            topology: false,
+            topology_state: ListState::default(),
             show_toggle_confirm: false,
             toggle_to_enable: false,
             show_editor: false,
             editor: None,
+            chain_editor: None,
+            killswitch: None,
             show_delete_confirm: false,
             rule_to_delete: None,
+            chain_to_delete: None,
+            goto: false,
+            formats: Formats::default(),
         }
     }
 
-    pub fn showing_dialog(&self) -> bool {
-        self.show_editor || self.show_toggle_confirm || self.show_delete_confirm
+    /// Masking of the addresses in rule descriptions
+    pub fn with_formats(mut self, formats: Formats) -> Self {
+        self.formats = formats;
+        self
+    }
+
+    /// Switch formatting while running, e.g. into private mode
+    pub fn set_formats(&mut self, formats: Formats) {
+        self.formats = formats;
     }
 
     /// Get currently selected rule
-    fn selected_rule(&self) -> Option<&FwRule> {
+    pub fn selected_rule(&self) -> Option<&FwRule> {
         let chain = self.selected_chain()?;
         let idx = self.rule_state.selected()?;
         chain.rules.get(idx)
     }
 
-    /// Save firewall config to disk
-    fn save_firewall_config(&self) -> Result<(), std::io::Error> {
-        if let Some(fw) = &self.cached_firewall {
-            let json = serde_json::to_string_pretty(fw)
-                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
-            std::fs::write(FIREWALL_CONFIG_PATH, json)?;
+    /// Copy the selected chain's rules back into the cached firewall
+    fn sync_selected_chain(&mut self) {
+        let (Some(fw), Some(chain)) = (&mut self.cached_firewall, self.cached_chains.get(self.selected_chain_idx)) else {
+            return;
+        };
+        for fc in &mut fw.system_rules {
+            if let Some(c) = fc.chains.iter_mut().find(|c| c.same_chain(chain)) {
+                c.rules = chain.rules.clone();
+            }
         }
-        Ok(())
+    }
+
+    /// Command to persist the cached firewall and reload it on the node
+    fn save_command(&self) -> Vec<TabCommand> {
+        self.cached_firewall
+            .iter()
+            .map(|fw| TabCommand::SaveFirewall {
+                node_addr: self.cached_node_addr.clone(),
+                firewall: fw.clone(),
+            })
+            .collect()
+    }
+
+    pub fn set_firewall(&mut self, firewall: Option<SysFirewall>, node_addr: Option<String>) {
+        self.cached_chains = firewall
+            .as_ref()
+            .map(|fw| fw.all_chains().cloned().collect())
+            .unwrap_or_default();
+        self.cached_firewall = firewall;
+        self.cached_node_addr = node_addr;
+    }
+
+    /// Version of the active node, used to gate firewall editing
+    pub fn set_daemon_version(&mut self, version: Option<DaemonVersion>) {
+        self.cached_daemon_version = version;
+    }
+
+    /// Whether the node can apply system firewall changes sent from here
+    fn can_edit(&self) -> bool {
+        Feature::SystemFirewall.supported_by(self.cached_daemon_version)
     }
 
     pub async fn update_cache(&mut self, state: &Arc<AppState>) {
         let nodes = state.nodes.read().await;
-        if let Some(node) = nodes.active_node() {
-            if let Some(fw) = &node.firewall {
-                self.cached_firewall = Some(fw.clone());
-                self.cached_chains = fw.all_chains().cloned().collect();
-            } else {
-                self.cached_firewall = None;
-                self.cached_chains.clear();
+        match nodes.active_node() {
+            Some(node) => {
+                self.set_firewall(node.firewall.clone(), Some(node.addr.clone()));
+                self.set_daemon_version(node.daemon_version());
+            }
+            None => {
+                self.set_firewall(None, None);
+                self.set_daemon_version(None);
             }
-        } else {
-            self.cached_firewall = None;
-            self.cached_chains.clear();
         }
     }
 
@@ -114,7 +180,7 @@ impl FirewallTab {
         self.cached_chains.get(self.selected_chain_idx)
     }
 
-    pub fn render(&mut self, frame: &mut Frame, area: Rect, _state: &Arc<AppState>, theme: &Theme) {
+    pub fn render(&mut self, frame: &mut Frame, area: Rect, theme: &Theme) {
         // Rule editor dialog
         if self.show_editor {
             if let Some(editor) = &self.editor {
@@ -123,6 +189,16 @@ impl FirewallTab {
             return;
         }
 
+        if let Some(dialog) = &self.chain_editor {
+            dialog.render(frame, theme);
+            return;
+        }
+
+        if let Some(dialog) = &self.killswitch {
+            dialog.render(frame, theme);
+            return;
+        }
+
         // Toggle confirmation dialog
         if self.show_toggle_confirm {
             self.render_toggle_confirm(frame, area, theme);
@@ -147,6 +223,11 @@ impl FirewallTab {
         // Render status bar
         self.render_status(frame, chunks[0], theme);
 
+        if self.topology {
+            self.render_topology(frame, chunks[1], theme);
+            return;
+        }
+
         // Split view: chains list | rules table
         let split = Layout::default()
             .direction(Direction::Horizontal)
@@ -169,32 +250,47 @@ impl FirewallTab {
         let output_policy = fw.map(|f| f.output_policy.as_str()).unwrap_or("N/A");
 
         let status_style = if running && enabled {
-            Style::default().fg(Color::Green)
+            theme.success()
         } else if running {
-            Style::default().fg(Color::Yellow)
+            theme.warning()
         } else {
-            Style::default().fg(Color::Red)
+            theme.error()
         };
 
         let status_text = if running && enabled {
-            "ENABLED"
+            "✓ ENABLED"
         } else if running {
-            "RUNNING (rules disabled)"
+            "! RUNNING (rules disabled)"
         } else {
-            "DISABLED"
+            "✗ DISABLED"
+        };
+
+        let hint = match self.cached_daemon_version.filter(|_| !self.can_edit()) {
+            Some(version) => Span::styled(
+                format!("⚠ {} — read-only", Feature::SystemFirewall.unsupported_message(version)),
+                theme.error(),
+            ),
+            None => Span::styled("F2=Toggle  F5=Reload  t=Topology  k=Kill-switch", theme.dim()),
+        };
+        let killswitch = match fw.and_then(killswitch::enabled) {
+            Some(true) => Span::styled("✓ on (K=off)", theme.success()),
+            Some(false) => Span::styled("! off (K=on)", theme.warning()),
+            None => Span::styled("none", theme.dim()),
         };
 
         let status_line = Line::from(vec![
             Span::raw(" Status: "),
             Span::styled(status_text, status_style.add_modifier(Modifier::BOLD)),
             Span::raw(" │ Input: "),
-            Span::styled(input_policy, policy_style(input_policy)),
+            Span::styled(Theme::action_label(input_policy), theme.action_style(input_policy)),
             Span::raw(" │ Output: "),
-            Span::styled(output_policy, policy_style(output_policy)),
+            Span::styled(Theme::action_label(output_policy), theme.action_style(output_policy)),
             Span::raw(" │ Chains: "),
             Span::raw(format!("{}", self.cached_chains.len())),
+            Span::raw(" │ Kill-switch: "),
+            killswitch,
             Span::raw(" │ "),
-            Span::styled("F2=Toggle  F5=Reload", theme.dim()),
+            hint,
         ]);
 
         let block = Block::default()
@@ -237,7 +333,8 @@ impl FirewallTab {
                 Block::default()
                     .borders(Borders::ALL)
                     .border_style(border_style)
-                    .title(" Chains "),
+                    .title(" Chains ")
+                    .title_bottom(Line::from(" N=new  P=policy  D=delete ").style(theme.dim())),
             )
             .highlight_style(theme.selected())
             .highlight_symbol("▶ ");
@@ -245,6 +342,79 @@ impl FirewallTab {
         frame.render_stateful_widget(list, area, &mut self.chain_state);
     }
 
+    /// Chains grouped by table, with each chain's hook below it. Returns the
+    /// tree lines and the line of every chain, in `cached_chains` order.
+    fn topology_lines(&self, theme: &Theme) -> (Vec<Line<'static>>, Vec<usize>) {
+        let mut tables: Vec<(&str, &str, Vec<usize>)> = Vec::new();
+        for (idx, chain) in self.cached_chains.iter().enumerate() {
+            match tables.iter_mut().find(|(f, t, _)| *f == chain.family && *t == chain.table) {
+                Some((_, _, chains)) => chains.push(idx),
+                None => tables.push((&chain.family, &chain.table, vec![idx])),
+            }
+        }
+
+        let mut lines = Vec::new();
+        let mut chain_lines = vec![0; self.cached_chains.len()];
+        for (family, table, chains) in &tables {
+            let rules: usize = chains.iter().map(|&i| self.cached_chains[i].rules.len()).sum();
+            lines.push(Line::from(vec![
+                Span::styled(format!("▣ table {} {}", family, table), theme.accent().add_modifier(Modifier::BOLD)),
+                Span::styled(format!("  {} chains, {} rules", chains.len(), rules), theme.dim()),
+            ]));
+
+            for (n, &idx) in chains.iter().enumerate() {
+                let chain = &self.cached_chains[idx];
+                let last = n + 1 == chains.len();
+                let (branch, stem) = if last { ("└─", "   ") } else { ("├─", "│  ") };
+                chain_lines[idx] = lines.len();
+                lines.push(Line::from(vec![
+                    Span::raw(format!("{} ", branch)),
+                    Span::styled(chain.name.clone(), theme.normal().add_modifier(Modifier::BOLD)),
+                    Span::styled(format!("  [{}]", chain.chain_type), theme.dim()),
+                    Span::raw("  policy "),
+                    Span::styled(Theme::action_label(&chain.policy), theme.action_style(&chain.policy)),
+                    Span::raw(format!("  {} rules", chain.rules.len())),
+                ]));
+                let hook = if chain.hook.is_empty() { "none (regular chain)" } else { chain.hook.as_str() };
+                lines.push(Line::from(vec![
+                    Span::raw(format!("{}└─ ", stem)),
+                    Span::styled(format!("hook {}", hook), theme.dim()),
+                    Span::styled(format!("  priority {}", chain.priority), theme.dim()),
+                ]));
+            }
+        }
+        (lines, chain_lines)
+    }
+
+    fn render_topology(&mut self, frame: &mut Frame, area: Rect, theme: &Theme) {
+        let (lines, chain_lines) = self.topology_lines(theme);
+        let items: Vec<ListItem> = if lines.is_empty() {
+            vec![ListItem::new("No chains configured").style(theme.dim())]
+        } else {
+            lines.into_iter().map(ListItem::new).collect()
+        };
+        self.topology_state.select(chain_lines.get(self.selected_chain_idx).copied());
+
+        let list = List::new(items)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_style(theme.border_focused())
+                    .title(" Topology: tables → chains → hooks ")
+                    .title_bottom(Line::from(" ↑↓ = chain  Enter = show rules  t/Esc = back ").style(theme.dim())),
+            )
+            .highlight_style(theme.selected())
+            .highlight_symbol("▶ ");
+
+        frame.render_stateful_widget(list, area, &mut self.topology_state);
+    }
+
+    fn select_chain(&mut self, idx: usize) {
+        self.chain_state.select(Some(idx));
+        self.selected_chain_idx = idx;
+        self.rule_state.select(Some(0)); // Reset rule selection
+    }
+
     fn render_rules(&mut self, frame: &mut Frame, area: Rect, theme: &Theme) {
         let focused = self.focus == FirewallFocus::Rules;
         let border_style = if focused {
@@ -275,24 +445,13 @@ impl FirewallTab {
                 .iter()
                 .enumerate()
                 .map(|(i, rule)| {
-                    let enabled_style = if rule.enabled {
-                        Style::default().fg(Color::Green)
-                    } else {
-                        Style::default().fg(Color::DarkGray)
-                    };
-
-                    let action_style = match rule.target.to_lowercase().as_str() {
-                        "accept" => Style::default().fg(Color::Green),
-                        "drop" => Style::default().fg(Color::Red),
-                        "reject" => Style::default().fg(Color::Magenta),
-                        _ => theme.normal(),
-                    };
+                    let enabled_style = if rule.enabled { theme.success() } else { theme.dim() };
 
                     Row::new(vec![
                         Cell::from(format!("{}", i + 1)),
                         Cell::from(if rule.enabled { "✓" } else { "✗" }).style(enabled_style),
-                        Cell::from(rule.target.clone()).style(action_style),
-                        Cell::from(truncate(&rule.description, 40).to_string()),
+                        Cell::from(Theme::action_label(&rule.target)).style(theme.action_style(&rule.target)),
+                        Cell::from(truncate(&self.formats.addresses(&rule.description), 40).to_string()),
                     ])
                 })
                 .collect()
@@ -327,7 +486,7 @@ impl FirewallTab {
                 area.width - 2,
                 1,
             );
-            let hint = Paragraph::new(" n=new  e/Enter=edit  d=delete  space=toggle")
+            let hint = Paragraph::new(" n=new  e/Enter=edit  d=delete  space=toggle  g e=alerts about it")
                 .style(theme.dim());
             frame.render_widget(hint, hint_area);
         }
@@ -373,7 +532,10 @@ impl FirewallTab {
         let dialog_area = DialogLayout::centered(area, 50, 8).dialog;
         frame.render_widget(Clear, dialog_area);
 
-        let rule_desc = self.rule_to_delete.as_deref().unwrap_or("unknown");
+        let question = match &self.chain_to_delete {
+            Some(chain) => format!("Delete chain '{}' and its {} rules?", truncate(&chain.name, 20), chain.rules.len()),
+            None => format!("Delete rule '{}'?", truncate(self.rule_to_delete.as_deref().unwrap_or("unknown"), 30)),
+        };
 
         let block = Block::default()
             .title(" Confirm Delete ")
@@ -392,7 +554,7 @@ impl FirewallTab {
             ])
             .split(inner);
 
-        let msg = Paragraph::new(format!("Delete rule '{}'?", truncate(rule_desc, 30)))
+        let msg = Paragraph::new(question)
             .style(theme.normal());
         frame.render_widget(msg, chunks[0]);
 
@@ -400,134 +562,178 @@ impl FirewallTab {
             .style(theme.dim());
         frame.render_widget(hint, chunks[1]);
     }
+}
 
-    pub async fn handle_key(&mut self, key: KeyEvent, state: &Arc<AppState>, state_tx: &mpsc::Sender<AppMessage>) {
+impl Tab for FirewallTab {
+    fn handle_key(&mut self, key: KeyEvent) -> Vec<TabCommand> {
         // Handle rule editor dialog
         if self.show_editor {
-            if let Some(editor) = &mut self.editor {
-                if let Some(result) = editor.handle_key(key) {
-                    match result {
-                        FwRuleEditorResult::Save(rule) => {
-                            // Add/update rule in cached firewall
-                            if let Some(fw) = &mut self.cached_firewall {
-                                if let Some(chain) = self.cached_chains.get_mut(self.selected_chain_idx) {
-                                    if editor.original_uuid.is_some() {
-                                        // Edit existing
-                                        if let Some(existing) = chain.rules.iter_mut().find(|r| r.uuid == rule.uuid) {
-                                            *existing = rule;
-                                        }
-                                    } else {
-                                        // Add new
-                                        chain.rules.push(rule);
-                                    }
-                                    // Update the main firewall struct
-                                    for fc in &mut fw.system_rules {
-                                        if let Some(c) = fc.chains.iter_mut().find(|c| c.name == chain.name) {
-                                            c.rules = chain.rules.clone();
-                                        }
-                                    }
-                                }
-                            }
-
-                            // Save to disk and reload
-                            if let Err(e) = self.save_firewall_config() {
-                                tracing::error!("Failed to save firewall config: {}", e);
-                            } else {
-                                // Send reload notification
-                                let node_addr = {
-                                    let nodes = state.nodes.read().await;
-                                    nodes.active_addr().map(|s| s.to_string())
-                                };
-                                if let Some(addr) = node_addr {
-                                    let _ = state_tx.send(AppMessage::SendNotification {
-                                        node_addr: addr,
-                                        action: NotificationAction::ReloadFwRules,
-                                    }).await;
-                                }
-                            }
-                        }
-                        FwRuleEditorResult::Cancel => {}
+            let Some(editor) = &mut self.editor else {
+                return Vec::new();
+            };
+            let Some(result) = editor.handle_key(key) else {
+                return Vec::new();
+            };
+            let is_edit = editor.original_uuid.is_some();
+            self.show_editor = false;
+            self.editor = None;
+
+            let FwRuleEditorResult::Save(rule) = result else {
+                return Vec::new();
+            };
+            // Add/update rule in cached firewall
+            if let Some(chain) = self.cached_chains.get_mut(self.selected_chain_idx) {
+                if is_edit {
+                    if let Some(existing) = chain.rules.iter_mut().find(|r| r.uuid == rule.uuid) {
+                        *existing = rule;
                     }
-                    self.show_editor = false;
-                    self.editor = None;
+                } else {
+                    chain.rules.push(rule);
                 }
             }
-            return;
+            self.sync_selected_chain();
+            return self.save_command();
+        }
+
+        if let Some(editor) = &mut self.chain_editor {
+            let Some(result) = editor.handle_key(key) else {
+                return Vec::new();
+            };
+            self.chain_editor = None;
+            let (FwChainEditorResult::Save(chain), Some(fw)) = (result, &mut self.cached_firewall) else {
+                return Vec::new();
+            };
+            let saved = chain.clone();
+            match fw.find_chain_mut(&chain.table, &chain.family, &chain.name) {
+                Some(existing) => existing.policy = chain.policy,
+                None => fw.add_chain(chain),
+            }
+            self.set_firewall(self.cached_firewall.clone(), self.cached_node_addr.clone());
+            if let Some(idx) = self.cached_chains.iter().position(|c| c.same_chain(&saved)) {
+                self.select_chain(idx);
+            }
+            return self.save_command();
+        }
+
+        if let Some(dialog) = &mut self.killswitch {
+            let result = dialog.handle_key(key);
+            let Some(KillSwitchResult::Install(settings)) = result else {
+                if result.is_some() {
+                    self.killswitch = None;
+                }
+                return Vec::new();
+            };
+            self.killswitch = None;
+            let Some(fw) = &mut self.cached_firewall else {
+                return Vec::new();
+            };
+            killswitch::install(fw, &settings);
+            self.set_firewall(self.cached_firewall.clone(), self.cached_node_addr.clone());
+            return self.save_command();
         }
 
         // Handle delete confirmation
         if self.show_delete_confirm {
             match key.code {
                 KeyCode::Char('y') | KeyCode::Char('Y') => {
+                    self.show_delete_confirm = false;
                     if let Some(uuid) = self.rule_to_delete.take() {
-                        // Remove rule from cached firewall
-                        if let Some(fw) = &mut self.cached_firewall {
-                            if let Some(chain) = self.cached_chains.get_mut(self.selected_chain_idx) {
-                                chain.rules.retain(|r| r.uuid != uuid);
-                                // Update the main firewall struct
-                                for fc in &mut fw.system_rules {
-                                    if let Some(c) = fc.chains.iter_mut().find(|c| c.name == chain.name) {
-                                        c.rules = chain.rules.clone();
-                                    }
-                                }
-                            }
+                        if let Some(chain) = self.cached_chains.get_mut(self.selected_chain_idx) {
+                            chain.rules.retain(|r| r.uuid != uuid);
                         }
-
-                        // Save to disk and reload
-                        if let Err(e) = self.save_firewall_config() {
-                            tracing::error!("Failed to save firewall config: {}", e);
-                        } else {
-                            let node_addr = {
-                                let nodes = state.nodes.read().await;
-                                nodes.active_addr().map(|s| s.to_string())
-                            };
-                            if let Some(addr) = node_addr {
-                                let _ = state_tx.send(AppMessage::SendNotification {
-                                    node_addr: addr,
-                                    action: NotificationAction::ReloadFwRules,
-                                }).await;
-                            }
+                        self.sync_selected_chain();
+                        return self.save_command();
+                    }
+                    if let (Some(chain), Some(fw)) = (self.chain_to_delete.take(), &mut self.cached_firewall) {
+                        if fw.remove_chain(&chain.table, &chain.family, &chain.name) {
+                            self.set_firewall(self.cached_firewall.clone(), self.cached_node_addr.clone());
+                            self.select_chain(self.selected_chain_idx.min(self.cached_chains.len().saturating_sub(1)));
+                            return self.save_command();
                         }
                     }
-                    self.show_delete_confirm = false;
                 }
                 KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => {
                     self.show_delete_confirm = false;
                     self.rule_to_delete = None;
+                    self.chain_to_delete = None;
                 }
                 _ => {}
             }
-            return;
+            return Vec::new();
         }
 
         // Handle toggle confirmation
         if self.show_toggle_confirm {
             match key.code {
                 KeyCode::Char('y') | KeyCode::Char('Y') => {
-                    let node_addr = {
-                        let nodes = state.nodes.read().await;
-                        nodes.active_addr().map(|s| s.to_string())
-                    };
-
-                    if let Some(addr) = node_addr {
+                    self.show_toggle_confirm = false;
+                    if let Some(addr) = &self.cached_node_addr {
                         let action = if self.toggle_to_enable {
                             NotificationAction::EnableFirewall
                         } else {
                             NotificationAction::DisableFirewall
                         };
-                        let _ = state_tx.send(AppMessage::SendNotification {
-                            node_addr: addr,
+                        return vec![TabCommand::send(AppMessage::SendNotification {
+                            node_addr: addr.clone(),
                             action,
-                        }).await;
+                        })];
                     }
-                    self.show_toggle_confirm = false;
                 }
                 KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => {
                     self.show_toggle_confirm = false;
                 }
                 _ => {}
             }
-            return;
+            return Vec::new();
+        }
+
+        if self.topology {
+            match key.code {
+                KeyCode::Char('t') | KeyCode::Esc => self.topology = false,
+                KeyCode::Enter => {
+                    self.topology = false;
+                    self.focus = FirewallFocus::Rules;
+                }
+                _ => {
+                    if let Some(delta) = navigation_delta(&key) {
+                        if let Some(idx) = step_index(Some(self.selected_chain_idx), self.cached_chains.len(), delta) {
+                            self.select_chain(idx);
+                        }
+                    }
+                }
+            }
+            return Vec::new();
+        }
+
+        // Where to jump after `g`, `gg` still goes to the top
+        if std::mem::take(&mut self.goto) {
+            if key.code == KeyCode::Char('e') && self.focus == FirewallFocus::Rules {
+                if let Some(rule) = self.selected_rule() {
+                    return vec![TabCommand::Jump(Jump::FirewallEvents {
+                        uuid: rule.uuid.clone(),
+                        description: rule.description.clone(),
+                    })];
+                }
+            }
+            if key.code != KeyCode::Char('g') {
+                return Vec::new();
+            }
+        } else if key.code == KeyCode::Char('g') {
+            self.goto = true;
+            return Vec::new();
+        }
+
+        // Older daemons would silently ignore firewall changes
+        if !self.can_edit()
+            && matches!(
+                key.code,
+                KeyCode::F(2)
+                    | KeyCode::Char('n' | 'e' | 'd' | ' ' | 'k' | 'K' | 'N' | 'P' | 'D')
+                    | KeyCode::Enter
+                    | KeyCode::Delete
+            )
+        {
+            return Vec::new();
         }
 
         match key.code {
@@ -537,6 +743,9 @@ impl FirewallTab {
                     FirewallFocus::Rules => FirewallFocus::Chains,
                 };
             }
+            KeyCode::Char('t') => {
+                self.topology = true;
+            }
             KeyCode::F(2) => {
                 // Toggle firewall
                 let currently_enabled = self.cached_firewall
@@ -548,22 +757,46 @@ impl FirewallTab {
             }
             KeyCode::F(5) => {
                 // Reload firewall rules
-                let node_addr = {
-                    let nodes = state.nodes.read().await;
-                    nodes.active_addr().map(|s| s.to_string())
-                };
-
-                if let Some(addr) = node_addr {
-                    let _ = state_tx.send(AppMessage::SendNotification {
-                        node_addr: addr,
+                if let Some(addr) = &self.cached_node_addr {
+                    return vec![TabCommand::send(AppMessage::SendNotification {
+                        node_addr: addr.clone(),
                         action: NotificationAction::ReloadFwRules,
-                    }).await;
+                    })];
+                }
+            }
+            KeyCode::Char('k') if self.cached_firewall.is_some() => {
+                self.killswitch = Some(KillSwitchDialog::new());
+            }
+            KeyCode::Char('K') => {
+                // Enable or disable the whole kill-switch at once
+                let Some(fw) = &mut self.cached_firewall else {
+                    return Vec::new();
+                };
+                let enable = killswitch::enabled(fw) == Some(false);
+                if killswitch::set_enabled(fw, enable) {
+                    self.set_firewall(self.cached_firewall.clone(), self.cached_node_addr.clone());
+                    return self.save_command();
+                }
+            }
+            // Chain actions, from either list
+            KeyCode::Char('N') if self.cached_firewall.is_some() => {
+                self.chain_editor = Some(FwChainEditorDialog::new(self.cached_chains.clone()));
+            }
+            KeyCode::Char('P') => {
+                if let Some(chain) = self.selected_chain() {
+                    self.chain_editor = Some(FwChainEditorDialog::edit(chain));
+                }
+            }
+            KeyCode::Char('D') => {
+                if let Some(chain) = self.selected_chain() {
+                    self.chain_to_delete = Some(chain.clone());
+                    self.show_delete_confirm = true;
                 }
             }
             KeyCode::Char('n') => {
                 // New rule (only in Rules focus)
                 if self.focus == FirewallFocus::Rules && !self.cached_chains.is_empty() {
-                    let mut editor = FwRuleEditorDialog::new();
+                    let mut editor = FwRuleEditorDialog::new().with_daemon_version(self.cached_daemon_version);
                     // Set position to end of list
                     if let Some(chain) = self.selected_chain() {
                         editor.position = chain.rules.len() as u64;
@@ -576,7 +809,7 @@ impl FirewallTab {
                 // Edit selected rule
                 if self.focus == FirewallFocus::Rules {
                     if let Some(rule) = self.selected_rule() {
-                        self.editor = Some(FwRuleEditorDialog::edit(rule));
+                        self.editor = Some(FwRuleEditorDialog::edit(rule).with_daemon_version(self.cached_daemon_version));
                         self.show_editor = true;
                     }
                 }
@@ -596,39 +829,13 @@ impl FirewallTab {
                     if let Some(rule) = self.selected_rule() {
                         let uuid = rule.uuid.clone();
                         let new_enabled = !rule.enabled;
-
-                        // Update in cached data
                         if let Some(chain) = self.cached_chains.get_mut(self.selected_chain_idx) {
                             if let Some(r) = chain.rules.iter_mut().find(|r| r.uuid == uuid) {
                                 r.enabled = new_enabled;
                             }
-                            // Update main firewall struct
-                            if let Some(fw) = &mut self.cached_firewall {
-                                for fc in &mut fw.system_rules {
-                                    if let Some(c) = fc.chains.iter_mut().find(|c| c.name == chain.name) {
-                                        if let Some(r) = c.rules.iter_mut().find(|r| r.uuid == uuid) {
-                                            r.enabled = new_enabled;
-                                        }
-                                    }
-                                }
-                            }
-                        }
-
-                        // Save and reload
-                        if let Err(e) = self.save_firewall_config() {
-                            tracing::error!("Failed to save firewall config: {}", e);
-                        } else {
-                            let node_addr = {
-                                let nodes = state.nodes.read().await;
-                                nodes.active_addr().map(|s| s.to_string())
-                            };
-                            if let Some(addr) = node_addr {
-                                let _ = state_tx.send(AppMessage::SendNotification {
-                                    node_addr: addr,
-                                    action: NotificationAction::ReloadFwRules,
-                                }).await;
-                            }
                         }
+                        self.sync_selected_chain();
+                        return self.save_command();
                     }
                 }
             }
@@ -636,55 +843,37 @@ impl FirewallTab {
                 if let Some(delta) = navigation_delta(&key) {
                     match self.focus {
                         FirewallFocus::Chains => {
-                            let len = self.cached_chains.len();
-                            if len == 0 {
-                                return;
+                            if let Some(idx) = step_index(self.chain_state.selected(), self.cached_chains.len(), delta) {
+                                self.select_chain(idx);
                             }
-                            let current = self.chain_state.selected().unwrap_or(0);
-                            let new_index = if delta == i32::MIN {
-                                0
-                            } else if delta == i32::MAX {
-                                len.saturating_sub(1)
-                            } else {
-                                (current as i32 + delta).clamp(0, len as i32 - 1) as usize
-                            };
-                            self.chain_state.select(Some(new_index));
-                            self.selected_chain_idx = new_index;
-                            self.rule_state.select(Some(0)); // Reset rule selection
                         }
                         FirewallFocus::Rules => {
                             let len = self.selected_chain()
                                 .map(|c| c.rules.len())
                                 .unwrap_or(0);
-                            if len == 0 {
-                                return;
+                            if let Some(idx) = step_index(self.rule_state.selected(), len, delta) {
+                                self.rule_state.select(Some(idx));
                             }
-                            let current = self.rule_state.selected().unwrap_or(0);
-                            let new_index = if delta == i32::MIN {
-                                0
-                            } else if delta == i32::MAX {
-                                len.saturating_sub(1)
-                            } else {
-                                (current as i32 + delta).clamp(0, len as i32 - 1) as usize
-                            };
-                            self.rule_state.select(Some(new_index));
                         }
                     }
                 }
             }
         }
+        Vec::new()
     }
-}
 
-fn policy_style(policy: &str) -> Style {
-    match policy.to_lowercase().as_str() {
-        "accept" => Style::default().fg(Color::Green),
-        "drop" => Style::default().fg(Color::Red),
-        "reject" => Style::default().fg(Color::Magenta),
-        _ => Style::default(),
+    fn showing_dialog(&self) -> bool {
+        self.show_editor
+            || self.chain_editor.is_some()
+            || self.killswitch.is_some()
+            || self.show_toggle_confirm
+            || self.show_delete_confirm
     }
 }
 
-fn truncate(s: &str, max: usize) -> &str {
-    if s.len() <= max { s } else { &s[..max] }
+/// Write the system firewall config to disk
+pub fn save_firewall_config(fw: &SysFirewall) -> Result<(), std::io::Error> {
+    let json = serde_json::to_string_pretty(fw)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+    std::fs::write(FIREWALL_CONFIG_PATH, json)
 }
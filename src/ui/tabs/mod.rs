@@ -1,20 +1,141 @@
 pub mod alerts;
 pub mod connections;
+pub mod daemon_log;
+pub mod dns;
 pub mod firewall;
 pub mod nodes;
 pub mod rules;
 pub mod statistics;
 
-use std::sync::Arc;
-use crossterm::event::KeyEvent;
-use ratatui::{layout::Rect, Frame};
+use chrono::{DateTime, Utc};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers, MouseEvent};
+use ratatui::layout::{Position, Rect};
 
-use crate::app::state::AppState;
-use crate::ui::theme::Theme;
+use crate::app::blocklists::KnownList;
+use crate::app::events::scroll_delta;
+use crate::app::state::AppMessage;
+use crate::models::report::ReportFormat;
+use crate::models::{Connection, Event, Policies, Rule, StatsFormat, SysFirewall};
 
-/// Trait for tab implementations
+/// Side effect requested by a tab, run by the app after key handling
+#[derive(Debug)]
+pub enum TabCommand {
+    /// Forward a message to the state manager
+    Send(Box<AppMessage>),
+    AcknowledgeAlert { id: u64, timestamp: DateTime<Utc> },
+    AcknowledgeAllAlerts,
+    SetActiveNode(String),
+    /// Drop an archived node and its stored snapshot
+    ForgetArchivedNode(String),
+    /// Import the official GUI database at this path
+    ImportGuiDatabase(String),
+    /// Write the system firewall config and ask the node to reload it
+    SaveFirewall { node_addr: Option<String>, firewall: SysFirewall },
+    /// Write a human-readable report of a node's rules
+    ExportRules { node_addr: String, format: ReportFormat },
+    /// Send the rules of a rules directory to a node
+    ImportRulesDir { node_addr: String, path: String },
+    /// Write a node's rules into a rules directory
+    ExportRulesDir { node_addr: String, path: String },
+    /// Download blocklists into a directory and deny the domains in it on a node
+    DownloadBlocklists { node_addr: String, dir: String, lists: Vec<KnownList> },
+    /// Fetch the Rules tab's window of the active node's rules around its selection
+    FetchRules,
+    /// Fetch a page of the stored connections matching a search query
+    SearchConnections { query: String, offset: usize },
+    /// Write the active node's statistics for spreadsheets or dashboards
+    ExportStats(StatsFormat),
+    /// Evaluate a past event against the active node's current rules
+    ReplayEvent(Box<Event>),
+    /// Show the items related to the selected one on another tab
+    Jump(Jump),
+    /// Open the rule editor for a new rule on a node, starting from this one
+    NewRule { node_addr: String, rule: Box<Rule> },
+    /// Write which rules belong to which policy
+    SavePolicies(Policies),
+    /// Put back the rule the latest change on a node replaced
+    UndoRuleChange { node_addr: String },
+    /// Send rules picked on one node to another and keep them under it
+    CopyRules { node_addr: String, rules: Vec<Rule> },
+    /// Delete stored events and alerts older than this and compact the database
+    PurgeDatabase { keep_days: u64 },
+    /// Put a node in quarantine, or lift it and restore the node's rules
+    SetQuarantine { node_addr: String, quarantine: bool },
+}
+
+/// Items on another tab related to the one selected, picked with `g` and a key
+#[derive(Debug, Clone)]
+pub enum Jump {
+    /// Connections the rule matched lately
+    RuleEvents(String),
+    /// Events of the connection
+    ConnectionEvents(Box<Connection>),
+    /// The rule of this name on the active node
+    Rule(String),
+    /// The rule that last matched the connection
+    ConnectionRule(Box<Connection>),
+    /// Alerts about a system firewall rule
+    FirewallEvents { uuid: String, description: String },
+}
+
+impl TabCommand {
+    pub fn send(msg: AppMessage) -> Self {
+        Self::Send(Box::new(msg))
+    }
+}
+
+/// Tab input handling as a pure state machine
+///
+/// Tabs only mutate their own state here; anything touching shared state
+/// or the outside world is returned as a command.
 pub trait Tab {
-    fn render(&self, frame: &mut Frame, area: Rect, state: &Arc<AppState>, theme: &Theme);
+    fn handle_key(&mut self, key: KeyEvent) -> Vec<TabCommand>;
+
+    /// Whether a dialog currently captures all input
+    fn showing_dialog(&self) -> bool {
+        false
+    }
+
+    /// Handle a mouse event, the scroll wheel moves like the arrow keys
+    fn handle_mouse(&mut self, event: MouseEvent) -> Vec<TabCommand> {
+        scroll_with_keys(self, event)
+    }
+}
+
+/// Turn the scroll wheel into arrow key presses
+pub fn scroll_with_keys<T: Tab + ?Sized>(tab: &mut T, event: MouseEvent) -> Vec<TabCommand> {
+    let Some(delta) = scroll_delta(&event) else {
+        return Vec::new();
+    };
+    let code = if delta < 0 { KeyCode::Up } else { KeyCode::Down };
+    (0..delta.unsigned_abs())
+        .flat_map(|_| tab.handle_key(KeyEvent::new(code, KeyModifiers::NONE)))
+        .collect()
+}
+
+/// Row of a tab's table under a click, the table drawn in `area` scrolled
+/// down by `offset`, with its title and header lines above the rows
+pub fn clicked_row(area: Rect, offset: usize, column: u16, row: u16) -> Option<usize> {
+    const ABOVE_ROWS: u16 = 2;
+    if !area.contains(Position::new(column, row)) || row < area.y + ABOVE_ROWS {
+        return None;
+    }
+    Some(offset + (row - area.y - ABOVE_ROWS) as usize)
+}
 
-    fn handle_key(&mut self, key: KeyEvent, state: &Arc<AppState>) -> impl std::future::Future<Output = ()> + Send;
+/// Move a selection by a navigation delta (i32::MIN/MAX jump to the ends)
+///
+/// Returns None when the list is empty.
+pub fn step_index(current: Option<usize>, len: usize, delta: i32) -> Option<usize> {
+    if len == 0 {
+        return None;
+    }
+    let current = current.unwrap_or(0);
+    Some(if delta == i32::MIN {
+        0
+    } else if delta == i32::MAX {
+        len - 1
+    } else {
+        (current as i64 + delta as i64).clamp(0, len as i64 - 1) as usize
+    })
 }
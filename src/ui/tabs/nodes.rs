@@ -1,27 +1,51 @@
 //! Nodes tab implementation
 
+use std::collections::HashSet;
 use std::sync::Arc;
+use std::time::Duration;
 
-use crossterm::event::{KeyCode, KeyEvent};
+use crossterm::event::{KeyCode, KeyEvent, MouseEvent};
 use ratatui::{
     layout::{Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
-    text::Span,
+    text::{Line, Span},
     widgets::{Block, Borders, Cell, Paragraph, Row, Table, TableState},
     Frame,
 };
-use tokio::sync::mpsc;
-
-use crate::app::events::navigation_delta;
-use crate::app::state::{AppMessage, AppState, UiUpdateSignal};
-use crate::models::{Node, node::NodeStatus};
+use crate::app::events::{click_position, navigation_delta};
+use crate::app::snapshots;
+use crate::app::state::AppState;
+use crate::db::import::ImportReport;
+use crate::models::{compat::unsupported_features, Node, node::{ask_deadline, AuthStatus, NodeStatus, RejectedPeer, DEFAULT_ASK_TIMEOUT}};
+use crate::ui::dialogs::confirm::ConfirmDialog;
+use crate::ui::dialogs::import::{ImportDialog, ImportDialogResult};
+use crate::ui::tabs::{clicked_row, scroll_with_keys, step_index, Tab, TabCommand};
 use crate::ui::theme::Theme;
-use crate::utils::format_duration;
+use crate::ui::widgets::table::ColumnSort;
+use crate::utils::duration::format_duration_ms;
+use crate::utils::text::truncate;
+use crate::utils::{format_duration, Formats};
+
+const COLUMNS: [&str; 9] = ["", "Address", "Name", "Version", "Status", "Auth", "Rules", "Ask", "Uptime"];
 
 pub struct NodesTab {
     table_state: TableState,
+    /// Where the table was last drawn, for clicks
+    table_area: Rect,
     cached_nodes: Vec<Node>,
     active_addr: Option<String>,
+    import_dialog: Option<ImportDialog>,
+    /// Quarantining or lifting it, for the node of this address
+    quarantine_confirm: Option<(String, bool, ConfirmDialog)>,
+    /// Addresses of the quarantined nodes
+    quarantined: HashSet<String>,
+    formats: Formats,
+    /// Addresses daemons can connect to
+    listeners: Vec<String>,
+    /// Requests that failed authentication, by IP
+    rejected: Vec<RejectedPeer>,
+    /// By address unless sorted
+    sort: ColumnSort,
 }
 
 impl NodesTab {
@@ -30,44 +54,125 @@ impl NodesTab {
         state.select(Some(0));
         Self {
             table_state: state,
+            table_area: Rect::default(),
             cached_nodes: Vec::new(),
             active_addr: None,
+            import_dialog: None,
+            quarantine_confirm: None,
+            quarantined: HashSet::new(),
+            formats: Formats::default(),
+            listeners: Vec::new(),
+            rejected: Vec::new(),
+            sort: ColumnSort::default(),
         }
     }
 
+    /// Number and date formatting
+    pub fn with_formats(mut self, formats: Formats) -> Self {
+        self.formats = formats;
+        self
+    }
+
+    /// Switch formatting while running, e.g. into private mode
+    pub fn set_formats(&mut self, formats: Formats) {
+        self.formats = formats;
+    }
+
+    pub fn set_nodes(&mut self, nodes: Vec<Node>, active_addr: Option<String>) {
+        self.cached_nodes = nodes;
+        self.active_addr = active_addr;
+        self.sort_nodes();
+    }
+
+    fn set_sort(&mut self, sort: ColumnSort) {
+        self.sort = sort;
+        self.sort_nodes();
+        self.table_state.select(Some(0));
+    }
+
+    fn sort_nodes(&mut self) {
+        self.cached_nodes.sort_by(|a, b| a.addr.cmp(&b.addr));
+        let active = self.active_addr.as_deref();
+        self.sort.sort(&mut self.cached_nodes, |column, a, b| match column {
+            0 => (active == Some(&a.addr)).cmp(&(active == Some(&b.addr))),
+            1 => a.addr.cmp(&b.addr),
+            2 => a.display_name().cmp(b.display_name()),
+            3 => a.version.cmp(&b.version),
+            4 => (a.archived, a.status.to_string()).cmp(&(b.archived, b.status.to_string())),
+            5 => a.auth.to_string().cmp(&b.auth.to_string()),
+            6 => a.rules.len().cmp(&b.rules.len()),
+            7 => a.ask_latency.last().cmp(&b.ask_latency.last()),
+            _ => {
+                let uptime = |node: &Node| node.statistics.as_ref().map(|s| s.uptime);
+                uptime(a).cmp(&uptime(b))
+            }
+        });
+    }
+
+    pub fn set_listeners(&mut self, listeners: Vec<String>) {
+        self.listeners = listeners;
+    }
+
+    pub fn set_rejected(&mut self, rejected: Vec<RejectedPeer>) {
+        self.rejected = rejected;
+    }
+
     pub async fn update_cache(&mut self, state: &Arc<AppState>) {
         let nodes = state.nodes.read().await;
-        self.cached_nodes = nodes.nodes.values().cloned().collect();
-        self.active_addr = nodes.active_addr().map(|s| s.to_string());
+        self.set_nodes(
+            nodes.nodes.values().cloned().collect(),
+            nodes.active_addr().map(|s| s.to_string()),
+        );
+        self.set_rejected(nodes.rejected.clone());
+        drop(nodes);
+        self.quarantined = self
+            .cached_nodes
+            .iter()
+            .filter(|node| snapshots::quarantined(state, &node.addr))
+            .map(|node| node.addr.clone())
+            .collect();
+        self.set_listeners(state.listeners.read().await.clone());
+    }
+
+    /// Show the outcome of a GUI database import in the open dialog
+    pub fn set_import_result(&mut self, result: Result<ImportReport, String>) {
+        if let Some(dialog) = &mut self.import_dialog {
+            dialog.set_result(result);
+        }
     }
 
     /// Get currently selected node
-    fn selected_node(&self) -> Option<&Node> {
+    pub fn selected_node(&self) -> Option<&Node> {
         let idx = self.table_state.selected()?;
         self.cached_nodes.get(idx)
     }
 
     pub fn render(&mut self, frame: &mut Frame, area: Rect, theme: &Theme) {
+        self.table_area = Rect::default();
         // Layout with hint bar at bottom
         let chunks = Layout::default()
             .direction(Direction::Vertical)
             .constraints([Constraint::Min(5), Constraint::Length(1)])
             .split(area);
 
-        let header_cells = ["", "Address", "Name", "Version", "Status", "Rules", "Uptime"]
-            .iter()
-            .map(|h| Cell::from(*h).style(theme.accent().add_modifier(Modifier::BOLD)));
+        let header_cells = self
+            .sort
+            .header(&COLUMNS)
+            .into_iter()
+            .map(|h| Cell::from(h).style(theme.accent().add_modifier(Modifier::BOLD)));
         let header = Row::new(header_cells).height(1);
 
         let rows: Vec<Row> = if self.cached_nodes.is_empty() {
             vec![Row::new(vec![
                 Cell::from(""),
-                Cell::from("unix:///tmp/osui.sock"),
+                Cell::from(self.listeners.first().map_or("not listening".into(), |l| self.formats.host(l).into_owned())),
                 Cell::from(""),
                 Cell::from(""),
                 Cell::from("Waiting for daemon..."),
                 Cell::from(""),
                 Cell::from(""),
+                Cell::from(""),
+                Cell::from(""),
             ])
             .style(theme.dim())]
         } else {
@@ -82,11 +187,23 @@ impl NodesTab {
                         theme.normal()
                     };
 
-                    let status_style = match node.status {
-                        NodeStatus::Connected => Style::default().fg(Color::Green),
-                        NodeStatus::Disconnected => Style::default().fg(Color::Red),
-                        NodeStatus::Connecting => Style::default().fg(Color::Yellow),
-                        NodeStatus::Error => Style::default().fg(Color::Red),
+                    let status = if node.archived {
+                        Cell::from("Archived").style(Style::default().fg(Color::Magenta))
+                    } else if self.quarantined.contains(&node.addr) {
+                        Cell::from("Quarantined").style(Style::default().fg(Color::Red).add_modifier(Modifier::BOLD))
+                    } else {
+                        let status_style = match node.status {
+                            NodeStatus::Connected => Style::default().fg(Color::Green),
+                            NodeStatus::Disconnected => Style::default().fg(Color::Red),
+                            NodeStatus::Connecting => Style::default().fg(Color::Yellow),
+                            NodeStatus::Error => Style::default().fg(Color::Red),
+                        };
+                        Cell::from(format!("{}", node.status)).style(status_style)
+                    };
+
+                    let auth_style = match node.auth {
+                        AuthStatus::None => theme.dim(),
+                        AuthStatus::Verified => Style::default().fg(Color::Green),
                     };
 
                     let uptime = node
@@ -95,13 +212,35 @@ impl NodesTab {
                         .map(|s| format_duration(s.uptime))
                         .unwrap_or_else(|| "N/A".to_string());
 
+                    // Older daemons can't honor every feature the UI offers
+                    let version = if unsupported_features(node.daemon_version()).is_empty() {
+                        Cell::from(node.version.clone())
+                    } else {
+                        Cell::from(format!("{} ⚠", node.version)).style(Style::default().fg(Color::Yellow))
+                    };
+
+                    // Time the last ask was answered in, flagged once any came too late
+                    let ask = match node.ask_latency.last() {
+                        None => Cell::from("-").style(theme.dim()),
+                        Some(last) if node.ask_latency.slow > 0 => {
+                            Cell::from(format!("{} ⚠", millis(last)))
+                                .style(Style::default().fg(Color::Yellow))
+                        }
+                        Some(last) => Cell::from(millis(last)),
+                    };
+
                     Row::new(vec![
                         Cell::from(active_marker).style(active_style),
-                        Cell::from(truncate(&node.addr, 28).to_string()),
-                        Cell::from(node.display_name().to_string()),
-                        Cell::from(node.version.clone()),
-                        Cell::from(format!("{}", node.status)).style(status_style),
-                        Cell::from(format!("{}", node.rules.len())),
+                        Cell::from(truncate(&self.formats.host(&node.addr), 28).to_string()),
+                        Cell::from(match &node.listener {
+                            Some(label) => format!("{} [{}]", self.formats.host(node.display_name()), label),
+                            None => self.formats.host(node.display_name()).into_owned(),
+                        }),
+                        version,
+                        status,
+                        Cell::from(format!("{}", node.auth)).style(auth_style),
+                        Cell::from(self.formats.number(node.rules.len() as u64)),
+                        ask,
                         Cell::from(uptime),
                     ])
                 })
@@ -110,66 +249,203 @@ impl NodesTab {
 
         let widths = [
             Constraint::Length(2),      // Active marker
-            Constraint::Percentage(28), // Address
+            Constraint::Percentage(24), // Address
             Constraint::Percentage(15), // Name
             Constraint::Length(12),     // Version
             Constraint::Length(12),     // Status
+            Constraint::Length(10),     // Auth
             Constraint::Length(8),      // Rules
+            Constraint::Length(8),      // Ask latency
             Constraint::Length(12),     // Uptime
         ];
 
-        let title = format!(" Nodes ({}) ", self.cached_nodes.len());
+        // Daemons subscribing on any of these show up here
+        let title = if self.listeners.is_empty() {
+            format!(" Nodes ({}) ", self.cached_nodes.len())
+        } else {
+            let listeners: Vec<_> = self.listeners.iter().map(|l| self.formats.host(l)).collect();
+            format!(" Nodes ({}) · listening on {} ", self.cached_nodes.len(), listeners.join(", "))
+        };
+
+        let mut title = vec![Span::styled(title, theme.accent())];
+        // Latest first, requests without the token don't make nodes
+        if let Some(latest) = self.rejected.iter().max_by_key(|peer| peer.last_seen) {
+            let requests: u64 = self.rejected.iter().map(|peer| peer.requests).sum();
+            let others = match self.rejected.len() - 1 {
+                0 => String::new(),
+                n => format!(" and {} more", n),
+            };
+            title.push(Span::styled(
+                format!(
+                    "⚠ {} unauthenticated requests rejected from {}{} ",
+                    self.formats.number(requests),
+                    self.formats.host(&latest.ip),
+                    others
+                ),
+                Style::default().fg(Color::Red),
+            ));
+        }
 
         let table = Table::new(rows, widths)
             .header(header)
             .block(
                 Block::default()
                     .borders(Borders::NONE)
-                    .title(Span::styled(title, theme.accent())),
+                    .title(Line::from(title)),
             )
             .row_highlight_style(theme.selected())
             .highlight_symbol("▶ ");
 
+        self.table_area = chunks[0];
         frame.render_stateful_widget(table, chunks[0], &mut self.table_state);
 
-        // Hint bar
-        let hint = Paragraph::new(" ↑↓ = navigate  Enter = set active node  ★ = active")
-            .style(theme.dim());
+        // Hint bar, or what the selected node's daemon can't do
+        let selected = self.selected_node();
+        let missing = selected
+            .map(|n| unsupported_features(n.daemon_version()))
+            .unwrap_or_default();
+        let hint = if let Some(node) = selected.filter(|n| n.archived) {
+            Paragraph::new(format!(
+                " Archived, last seen {} (read-only)  Enter = browse  x = forget",
+                self.formats.date_time(&node.last_seen)
+            ))
+            .style(Style::default().fg(Color::Magenta))
+        } else if let Some(node) = selected.filter(|n| n.ask_latency.slow > 0) {
+            let latency = &node.ask_latency;
+            Paragraph::new(format!(
+                " ⚠ {} of {} asks answered after {}, the daemon stops waiting at {}s  (avg {}, max {})",
+                self.formats.number(latency.slow),
+                self.formats.number(latency.answered),
+                millis(ask_deadline(DEFAULT_ASK_TIMEOUT)),
+                DEFAULT_ASK_TIMEOUT,
+                millis(latency.average().unwrap_or_default()),
+                millis(latency.max().unwrap_or_default()),
+            ))
+            .style(Style::default().fg(Color::Yellow))
+        } else if missing.is_empty() {
+            Paragraph::new(" ↑↓ = navigate  Enter = set active node  Q = quarantine  i = import GUI database  s/S = sort/reverse  ★ = active")
+                .style(theme.dim())
+        } else {
+            let labels: Vec<&str> = missing.iter().map(|f| f.label()).collect();
+            Paragraph::new(format!(" ⚠ Unsupported by this daemon: {}", labels.join(", ")))
+                .style(Style::default().fg(Color::Yellow))
+        };
         frame.render_widget(hint, chunks[1]);
+
+        if let Some(dialog) = &self.import_dialog {
+            dialog.render(frame, theme);
+        }
+        if let Some((_, _, dialog)) = &self.quarantine_confirm {
+            dialog.render(frame, theme);
+        }
+    }
+
+    /// Ask before quarantining the selected node, or lifting its quarantine
+    fn confirm_quarantine(&mut self) {
+        let Some(node) = self.selected_node().filter(|n| !n.archived) else {
+            return;
+        };
+        let quarantine = !self.quarantined.contains(&node.addr);
+        let dialog = if quarantine {
+            ConfirmDialog::new(
+                "Quarantine Node",
+                &format!(
+                    "Disable the {} rules of {} and deny everything but DNS, DHCP and the hosts of quarantine_allowlist? \
+                     The rules are saved and come back when the quarantine is lifted.",
+                    node.rules.len(),
+                    self.formats.host(node.display_name())
+                ),
+            )
+            .with_labels("Quarantine", "Cancel")
+        } else {
+            ConfirmDialog::new(
+                "Lift Quarantine",
+                &format!("Restore the rules {} had before its quarantine?", self.formats.host(node.display_name())),
+            )
+            .with_labels("Lift", "Cancel")
+        };
+        self.quarantine_confirm = Some((node.addr.clone(), quarantine, dialog));
+    }
+}
+
+impl Tab for NodesTab {
+    /// Clicking a row selects it
+    fn handle_mouse(&mut self, event: MouseEvent) -> Vec<TabCommand> {
+        let Some((column, row)) = click_position(&event).filter(|_| !self.showing_dialog()) else {
+            return scroll_with_keys(self, event);
+        };
+        if let Some(idx) = clicked_row(self.table_area, self.table_state.offset(), column, row) {
+            if idx < self.cached_nodes.len() {
+                self.table_state.select(Some(idx));
+            }
+        }
+        Vec::new()
     }
 
-    pub async fn handle_key(&mut self, key: KeyEvent, state: &Arc<AppState>, _state_tx: &mpsc::Sender<AppMessage>) {
+    fn handle_key(&mut self, key: KeyEvent) -> Vec<TabCommand> {
+        if let Some(dialog) = &mut self.import_dialog {
+            match dialog.handle_key(key) {
+                Some(ImportDialogResult::Close) => self.import_dialog = None,
+                Some(ImportDialogResult::Import(path)) => return vec![TabCommand::ImportGuiDatabase(path)],
+                None => {}
+            }
+            return Vec::new();
+        }
+
+        if let Some((_, _, dialog)) = &mut self.quarantine_confirm {
+            if !dialog.handle_key(key) {
+                return Vec::new();
+            }
+            let confirmed = dialog.result == Some(true);
+            let (node_addr, quarantine, _) = self.quarantine_confirm.take().unwrap();
+            if confirmed {
+                return vec![TabCommand::SetQuarantine { node_addr, quarantine }];
+            }
+            return Vec::new();
+        }
+
         match key.code {
+            KeyCode::Char('Q') => self.confirm_quarantine(),
+            KeyCode::Char('i') => {
+                self.import_dialog = Some(ImportDialog::new());
+            }
+            KeyCode::Char('s') => {
+                let mut sort = self.sort;
+                sort.cycle(COLUMNS.len());
+                self.set_sort(sort);
+            }
+            KeyCode::Char('S') => {
+                let mut sort = self.sort;
+                sort.reverse();
+                self.set_sort(sort);
+            }
+            KeyCode::Char('x') => {
+                if let Some(node) = self.selected_node().filter(|n| n.archived) {
+                    return vec![TabCommand::ForgetArchivedNode(node.addr.clone())];
+                }
+            }
             KeyCode::Enter | KeyCode::Char(' ') => {
                 // Switch to selected node
                 if let Some(node) = self.selected_node() {
-                    let addr = node.addr.clone();
-                    let mut nodes = state.nodes.write().await;
-                    if nodes.set_active(&addr) {
-                        self.active_addr = Some(addr);
-                        state.notify_ui(UiUpdateSignal::NodeChanged);
-                    }
+                    return vec![TabCommand::SetActiveNode(node.addr.clone())];
                 }
             }
             _ => {
                 if let Some(delta) = navigation_delta(&key) {
-                    let len = self.cached_nodes.len();
-                    if len == 0 { return; }
-                    let current = self.table_state.selected().unwrap_or(0);
-                    let new_index = if delta == i32::MIN {
-                        0
-                    } else if delta == i32::MAX {
-                        len.saturating_sub(1)
-                    } else {
-                        (current as i32 + delta).clamp(0, len as i32 - 1) as usize
-                    };
-                    self.table_state.select(Some(new_index));
+                    if let Some(idx) = step_index(self.table_state.selected(), self.cached_nodes.len(), delta) {
+                        self.table_state.select(Some(idx));
+                    }
                 }
             }
         }
+        Vec::new()
+    }
+
+    fn showing_dialog(&self) -> bool {
+        self.import_dialog.is_some() || self.quarantine_confirm.is_some()
     }
 }
 
-fn truncate(s: &str, max: usize) -> &str {
-    if s.len() <= max { s } else { &s[..max] }
+fn millis(d: Duration) -> String {
+    format_duration_ms(d.as_millis() as u64)
 }
@@ -1,38 +1,137 @@
 //! Rules tab implementation
 
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::sync::Arc;
 
-use crossterm::event::{KeyCode, KeyEvent};
+use chrono::Utc;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers, MouseEvent};
 use ratatui::{
     layout::{Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
-    text::Span,
+    text::{Line, Span},
     widgets::{Block, Borders, Cell, Paragraph, Row, Table, TableState},
     Frame,
 };
-use tokio::sync::mpsc;
-
-use crate::app::events::navigation_delta;
+use crate::app::blocklists::{list_dirs, BlocklistReport};
+use crate::app::events::{click_position, navigation_delta};
 use crate::app::state::{AppMessage, AppState};
+use crate::db::rules_io::RulesDirReport;
 use crate::grpc::notifications::NotificationAction;
-use crate::models::Rule;
+use crate::models::report::{rule_summary, ReportFormat};
+use crate::models::{DaemonVersion, Event, Policies, Rule, RuleDuration, RuleHits, RuleIndex, RulePage};
+use crate::ui::dialogs::blocklists::{BlocklistsDialog, BlocklistsResult};
+use crate::ui::dialogs::copy_rules::{CopyRulesDialog, CopyRulesResult};
+use crate::ui::dialogs::profiles::{ProfileCatalogDialog, ProfileCatalogResult};
 use crate::ui::dialogs::rule_editor::{RuleEditorDialog, RuleEditorResult};
+use crate::ui::dialogs::rule_history::RuleHistoryDialog;
+use crate::ui::dialogs::rules_dir::{RulesDirDialog, RulesDirMode, RulesDirResult};
+use crate::ui::tabs::{clicked_row, scroll_with_keys, step_index, Jump, Tab, TabCommand};
 use crate::ui::theme::Theme;
+use crate::ui::widgets::form::TextInput;
 use crate::ui::widgets::searchbar::SearchBar;
+use crate::ui::widgets::table::ColumnSort;
+use crate::utils::duration::format_duration_compact;
+use crate::utils::text::truncate;
+use crate::utils::Formats;
+
+/// Rules copied around the selection, more than any screen shows
+const RULE_WINDOW: usize = 200;
+
+/// Recent connections the rule editor checks the rule against
+const MATCH_SAMPLE: usize = 500;
+
+/// Changes of a rule shown in its history
+const HISTORY_LIMIT: i64 = 100;
+
+const COLUMNS: [&str; 9] = ["Name", "Enabled", "Action", "Duration", "Updated", "Hits", "Last Hit", "Operand", "Data"];
+
+/// A line of the policies view
+#[derive(Debug, Clone)]
+pub enum PolicyRow {
+    /// Heading of a policy, None for the rules in no policy
+    Policy { name: Option<String>, rules: usize, enabled: usize, collapsed: bool },
+    Rule(Rule),
+}
 
 pub struct RulesTab {
+    /// Selection within the window
     table_state: TableState,
+    /// Where the table was last drawn, for clicks
+    table_area: Rect,
+    /// Selection among all matching rules
+    selected: usize,
+    /// First matching rule drawn, only the rows on screen are built
+    first_row: usize,
     search_bar: SearchBar,
     filter_active: bool,
-    cached_rules: Vec<Rule>,
+    /// Window of the matching rules around the selection
+    page: RulePage,
+    /// The selection moved past the node's rules fetched so far
+    window_left: bool,
+    /// Full rule list handed over directly rather than fetched from the node
+    local_rules: Option<Vec<Rule>>,
+    local_index: RuleIndex,
+    cached_node_addr: Option<String>,
+    cached_daemon_version: Option<DaemonVersion>,
+    /// Rules changed and sent, waiting for the daemon to confirm
+    unconfirmed: HashSet<String>,
+    /// Rules picked with `m`, to copy to another node
+    marked: BTreeMap<String, Rule>,
+    /// Other connected nodes, address and name, rules can be copied to
+    copy_targets: Vec<(String, String)>,
+    copy: Option<CopyRulesDialog>,
 
     // Editor dialog state
     show_editor: bool,
     editor: Option<RuleEditorDialog>,
 
+    /// Application profiles to apply to the node
+    catalog: Option<ProfileCatalogDialog>,
+
+    /// Import or export through a rules directory
+    rules_dir: Option<RulesDirDialog>,
+
+    /// Blocklists downloaded for a lists.domains deny rule
+    blocklists: Option<BlocklistsDialog>,
+
+    /// Changes made to the selected rule
+    history: Option<RuleHistoryDialog>,
+
     // Confirmation dialog state
     show_delete_confirm: bool,
     rule_to_delete: Option<String>,
+
+    /// Where the last report was written, or why it failed
+    export_result: Option<Result<String, String>>,
+    /// What the last undo put back, or why nothing was
+    undo_result: Option<Result<String, String>>,
+
+    /// Rules ranked by the events they matched, instead of the rule list
+    noisy: bool,
+    noisy_state: TableState,
+    /// Recent events per rule name
+    rule_hits: HashMap<String, u64>,
+    noisy_rules: Vec<(Rule, u64)>,
+    /// Events each rule matched over time, by rule name
+    hits: HashMap<String, RuleHits>,
+    /// Rules are listed as the node has them unless sorted
+    sort: ColumnSort,
+    /// Rules under the policy they belong to, instead of the rule list
+    grouped: bool,
+    policy_state: TableState,
+    policies: Policies,
+    /// All rules of the node while the policies view shows them
+    node_rules: Vec<Rule>,
+    /// Policies showing only their heading, "" for the rules in none
+    collapsed: BTreeSet<String>,
+    /// Rule being put into a policy, and the policy name typed
+    assigning: Option<(String, TextInput)>,
+    /// Path of a process being cut off from the network
+    sandboxing: Option<TextInput>,
+    sandbox_duration: RuleDuration,
+    /// `g` pressed, waiting for where to jump
+    goto: bool,
+    formats: Formats,
 }
 
 impl RulesTab {
@@ -41,51 +140,310 @@ impl RulesTab {
         state.select(Some(0));
         Self {
             table_state: state,
+            table_area: Rect::default(),
+            selected: 0,
+            first_row: 0,
             search_bar: SearchBar::new(),
             filter_active: false,
-            cached_rules: Vec::new(),
+            page: RulePage::default(),
+            window_left: false,
+            local_rules: None,
+            local_index: RuleIndex::default(),
+            cached_node_addr: None,
+            cached_daemon_version: None,
+            unconfirmed: HashSet::new(),
+            marked: BTreeMap::new(),
+            copy_targets: Vec::new(),
+            copy: None,
             show_editor: false,
             editor: None,
+            catalog: None,
+            rules_dir: None,
+            blocklists: None,
+            history: None,
             show_delete_confirm: false,
             rule_to_delete: None,
+            export_result: None,
+            undo_result: None,
+            noisy: false,
+            noisy_state: TableState::default().with_selected(Some(0)),
+            rule_hits: HashMap::new(),
+            noisy_rules: Vec::new(),
+            hits: HashMap::new(),
+            sort: ColumnSort::default(),
+            grouped: false,
+            policy_state: TableState::default().with_selected(Some(0)),
+            policies: Policies::default(),
+            node_rules: Vec::new(),
+            collapsed: BTreeSet::new(),
+            assigning: None,
+            sandboxing: None,
+            sandbox_duration: RuleDuration::ThirtyMinutes,
+            goto: false,
+            formats: Formats::default(),
+        }
+    }
+
+    /// Number and date formatting
+    pub fn with_formats(mut self, formats: Formats) -> Self {
+        self.formats = formats;
+        self
+    }
+
+    /// Which rules belong to which policy, kept in a local file
+    pub fn with_policies(mut self, policies: Policies) -> Self {
+        self.policies = policies;
+        self
+    }
+
+    /// How long a sandboxed process stays cut off from the network
+    pub fn with_sandbox_duration(mut self, duration: RuleDuration) -> Self {
+        self.sandbox_duration = duration;
+        self
+    }
+
+    /// Switch formatting while running, e.g. into private mode
+    pub fn set_formats(&mut self, formats: Formats) {
+        self.formats = formats;
+    }
+
+    pub fn search_bar_mut(&mut self) -> &mut SearchBar {
+        &mut self.search_bar
+    }
+
+    pub fn set_rules(&mut self, rules: Vec<Rule>, node_addr: Option<String>) {
+        if node_addr != self.cached_node_addr {
+            self.marked.clear();
+        }
+        self.noisy_rules = rank_noisy(&rules, &self.rule_hits);
+        self.node_rules = rules.clone();
+        self.local_rules = Some(rules);
+        self.cached_node_addr = node_addr;
+        self.refresh_local();
+    }
+
+    /// Show a window fetched from the node's rules
+    fn set_page(&mut self, page: RulePage, node_addr: Option<String>) {
+        if node_addr != self.cached_node_addr {
+            self.marked.clear();
+        }
+        self.window_left = false;
+        self.selected = self.selected.min(page.matched.saturating_sub(1));
+        self.page = page;
+        self.local_rules = None;
+        self.cached_node_addr = node_addr;
+    }
+
+    /// Where the window around the selection starts
+    fn window_start(&self) -> usize {
+        self.selected.saturating_sub(RULE_WINDOW / 2)
+    }
+
+    /// Cut the window again from rules set directly, after the selection or filter changed.
+    /// A node's rules are fetched again instead once the selection leaves the window.
+    fn refresh_local(&mut self) {
+        if let Some(rules) = &self.local_rules {
+            self.local_index.update(rules);
+            let mut positions = self.local_index.filter(&self.search_bar.query).to_vec();
+            sort_positions(rules, &mut positions, &self.hits, self.sort);
+            let page = RulePage::window(rules, &positions, self.window_start(), RULE_WINDOW);
+            self.selected = self.selected.min(page.matched.saturating_sub(1));
+            self.page = page;
+        } else if self.selected_rule().is_none() && self.selected < self.page.matched {
+            self.window_left = true;
+        }
+    }
+
+    /// Ask for the window around the selection when it moved out of the one at hand
+    fn fetch_window(&mut self) -> Vec<TabCommand> {
+        if std::mem::take(&mut self.window_left) {
+            vec![TabCommand::FetchRules]
+        } else {
+            Vec::new()
         }
     }
 
-    pub fn showing_dialog(&self) -> bool {
-        self.show_editor || self.show_delete_confirm
+    /// Recent events per rule name, ranking the noisy rules view
+    pub fn set_rule_hits(&mut self, hits: HashMap<String, u64>) {
+        self.rule_hits = hits;
+        if let Some(rules) = &self.local_rules {
+            self.noisy_rules = rank_noisy(rules, &self.rule_hits);
+        }
+    }
+
+    /// Events each rule of the node matched, for the Hits columns
+    pub fn set_hits(&mut self, hits: HashMap<String, RuleHits>) {
+        self.hits = hits;
+        self.refresh_local();
+    }
+
+    /// Sort the rules differently, e.g. by hits to find dead ones
+    fn set_sort(&mut self, sort: ColumnSort) {
+        self.sort = sort;
+        self.selected = 0;
+        self.refresh_local();
+    }
+
+    /// Rules that matched recent events, most events first
+    pub fn noisy_rules(&self) -> &[(Rule, u64)] {
+        &self.noisy_rules
+    }
+
+    /// Show the rule editor, with the rules it checks for conflicts when already at hand
+    fn open_editor(&mut self, mut editor: RuleEditorDialog) {
+        if let Some(rules) = &self.local_rules {
+            editor.set_rules(rules.clone());
+        }
+        self.editor = Some(
+            editor
+                .with_daemon_version(self.cached_daemon_version)
+                .with_node_addr(self.cached_node_addr.as_deref().unwrap_or_default())
+                .with_formats(self.formats.clone()),
+        );
+        self.show_editor = true;
+    }
+
+    /// Start a new rule from one made up on another tab
+    pub fn new_rule(&mut self, rule: &Rule) {
+        self.open_editor(RuleEditorDialog::create_from(rule));
+    }
+
+    /// Version of the active node, used to gate editor features
+    pub fn set_daemon_version(&mut self, version: Option<DaemonVersion>) {
+        self.cached_daemon_version = version;
     }
 
     pub async fn update_cache(&mut self, state: &Arc<AppState>) {
+        // Only the noisy rules view needs the whole rule list and the events
+        if self.noisy {
+            self.set_rule_hits(rule_hits(state.connections.read().await.iter()));
+        }
+
         let nodes = state.nodes.read().await;
-        if let Some(node) = nodes.active_node() {
-            self.cached_rules = node.rules.clone();
-        } else {
-            self.cached_rules.clear();
+        match nodes.active_node() {
+            Some(node) => {
+                let hits = state.rule_hits.lock().unwrap().node_hits(&node.addr);
+                self.set_hits(hits);
+                let page = if self.sort.is_sorted() {
+                    let mut positions = nodes.matching_rules(&node.addr, &self.search_bar.query);
+                    sort_positions(&node.rules, &mut positions, &self.hits, self.sort);
+                    RulePage::window(&node.rules, &positions, self.window_start(), RULE_WINDOW)
+                } else {
+                    nodes.rule_page(&node.addr, &self.search_bar.query, self.window_start(), RULE_WINDOW)
+                };
+                self.set_page(page, Some(node.addr.clone()));
+                self.set_daemon_version(node.daemon_version());
+                self.unconfirmed = state.unconfirmed_rules(&node.addr).await;
+                let mut targets: Vec<_> = nodes
+                    .connected_nodes()
+                    .filter(|n| n.addr != node.addr)
+                    .map(|n| (n.addr.clone(), n.display_name().to_string()))
+                    .collect();
+                targets.sort();
+                self.set_copy_targets(targets);
+                if self.noisy {
+                    self.noisy_rules = rank_noisy(&node.rules, &self.rule_hits);
+                }
+                if self.grouped {
+                    self.node_rules = node.rules.clone();
+                }
+                if let Some(editor) = self.editor.as_mut().filter(|e| e.wants_rules()) {
+                    editor.set_rules(node.rules.clone());
+                }
+                if let Some(editor) = &mut self.editor {
+                    let recent = state.connections.read().await.iter().take(MATCH_SAMPLE).cloned().collect();
+                    editor.set_recent_events(recent);
+                }
+                if let Some(catalog) = &mut self.catalog {
+                    catalog.set_rules(node.rules.clone());
+                }
+                if let Some(dialog) = &mut self.blocklists {
+                    dialog.set_dirs(list_dirs(&node.rules));
+                }
+                if let Some(dialog) = self.history.as_mut().filter(|d| d.wants_versions()) {
+                    match state.db.select_rule_versions(&node.addr, &dialog.name, HISTORY_LIMIT) {
+                        Ok(versions) => dialog.set_versions(versions),
+                        Err(e) => {
+                            tracing::error!("Failed to read the history of rule {}: {}", dialog.name, e);
+                            dialog.set_versions(Vec::new());
+                        }
+                    }
+                }
+            }
+            None => {
+                self.set_rules(Vec::new(), None);
+                self.set_daemon_version(None);
+            }
         }
     }
 
-    /// Get currently selected rule
-    fn selected_rule(&self) -> Option<&Rule> {
-        let idx = self.table_state.selected()?;
-        // Get filtered rules to match what's displayed
-        let filtered: Vec<&Rule> = if self.search_bar.query.is_empty() {
-            self.cached_rules.iter().collect()
-        } else {
-            let query = self.search_bar.query.to_lowercase();
-            self.cached_rules
-                .iter()
-                .filter(|r| {
-                    r.name.to_lowercase().contains(&query)
-                        || r.description.to_lowercase().contains(&query)
-                        || r.operator.operand.to_lowercase().contains(&query)
-                        || r.operator.data.to_lowercase().contains(&query)
-                })
-                .collect()
+    /// Connected nodes other than the active one, address and name
+    pub fn set_copy_targets(&mut self, nodes: Vec<(String, String)>) {
+        self.copy_targets = nodes;
+    }
+
+    /// Mark or unmark the selected rule for copying, and move on to the next
+    fn toggle_mark(&mut self) {
+        let Some(rule) = self.selected_rule().cloned() else {
+            return;
         };
-        filtered.get(idx).copied()
+        if self.marked.remove(&rule.name).is_none() {
+            self.marked.insert(rule.name.clone(), rule);
+        }
+        if let Some(idx) = step_index(Some(self.selected), self.page.matched, 1) {
+            self.selected = idx;
+            self.refresh_local();
+        }
+    }
+
+    /// Show where a rules report was written, until the next key press
+    pub fn set_export_result(&mut self, result: Result<String, String>) {
+        self.export_result = Some(result);
+    }
+
+    /// Show what an undo put back, until the next key press
+    pub fn set_undo_result(&mut self, result: Result<String, String>) {
+        self.undo_result = Some(result);
+    }
+
+    /// Show the outcome of a rules directory import or export in the open dialog
+    pub fn set_rules_dir_result(&mut self, result: Result<RulesDirReport, String>) {
+        if let Some(dialog) = &mut self.rules_dir {
+            dialog.set_result(result);
+        }
+    }
+
+    /// Show the outcome of a blocklists download in the open dialog
+    pub fn set_blocklists_result(&mut self, result: Result<BlocklistReport, String>) {
+        if let Some(dialog) = &mut self.blocklists {
+            dialog.set_result(result);
+        }
+    }
+
+    /// Matching rules in the current window, in display order
+    pub fn filtered_rules(&self) -> &[Rule] {
+        &self.page.rules
+    }
+
+    /// Select a rule by its position among all the node's rules, back in
+    /// that order: filter, sort and other views are cleared
+    pub fn select_rule(&mut self, index: usize) {
+        self.search_bar.clear();
+        self.noisy = false;
+        self.grouped = false;
+        self.sort = ColumnSort::default();
+        self.selected = index;
+        self.refresh_local();
+    }
+
+    /// Get currently selected rule
+    pub fn selected_rule(&self) -> Option<&Rule> {
+        let idx = self.selected.checked_sub(self.page.offset)?;
+        self.page.rules.get(idx)
     }
 
     pub fn render(&mut self, frame: &mut Frame, area: Rect, theme: &Theme) {
+        self.table_area = Rect::default();
         // If editor is showing, render it on top
         if self.show_editor {
             if let Some(editor) = &self.editor {
@@ -94,12 +452,47 @@ impl RulesTab {
             return;
         }
 
+        if let Some(catalog) = &self.catalog {
+            catalog.render(frame, theme);
+            return;
+        }
+
+        if let Some(dialog) = &self.rules_dir {
+            dialog.render(frame, theme);
+            return;
+        }
+
+        if let Some(dialog) = &self.blocklists {
+            dialog.render(frame, theme);
+            return;
+        }
+
+        if let Some(dialog) = &self.history {
+            dialog.render(frame, theme);
+            return;
+        }
+
+        if let Some(dialog) = &self.copy {
+            dialog.render(frame, theme);
+            return;
+        }
+
         // If delete confirmation is showing, render it
         if self.show_delete_confirm {
             self.render_delete_confirm(frame, area, theme);
             return;
         }
 
+        if self.noisy {
+            self.render_noisy(frame, area, theme);
+            return;
+        }
+
+        if self.grouped {
+            self.render_policies(frame, area, theme);
+            return;
+        }
+
         let chunks = Layout::default()
             .direction(Direction::Vertical)
             .constraints(if self.filter_active {
@@ -110,28 +503,29 @@ impl RulesTab {
             .split(area);
 
         if self.filter_active {
+            self.search_bar.set_matches(self.page.matched, self.page.total);
             self.search_bar.render(frame, chunks[0], theme.normal(), theme.border_focused());
         }
 
-        // Filter rules
-        let filtered_rules: Vec<&Rule> = if self.search_bar.query.is_empty() {
-            self.cached_rules.iter().collect()
-        } else {
-            let query = self.search_bar.query.to_lowercase();
-            self.cached_rules
-                .iter()
-                .filter(|r| {
-                    r.name.to_lowercase().contains(&query)
-                        || r.description.to_lowercase().contains(&query)
-                        || r.operator.operand.to_lowercase().contains(&query)
-                        || r.operator.data.to_lowercase().contains(&query)
-                })
-                .collect()
-        };
+        // Only the rows on screen are built, scrolled to keep the selection in
+        // view: below the title and header, above the hint line
+        let show_hint = chunks[1].height > 10 && !self.filter_active;
+        let visible = (chunks[1].height as usize).saturating_sub(if show_hint { 3 } else { 2 }).max(1);
+        if self.selected < self.first_row {
+            self.first_row = self.selected;
+        } else if self.selected >= self.first_row + visible {
+            self.first_row = self.selected + 1 - visible;
+        }
+        self.first_row = self.first_row.clamp(self.page.offset, self.page.offset + self.page.rules.len().saturating_sub(1));
+        let start = self.first_row - self.page.offset;
+        let filtered_rules = self.filtered_rules();
+        let filtered_rules = &filtered_rules[start..(start + visible).min(filtered_rules.len())];
 
-        let header_cells = ["Name", "Enabled", "Action", "Duration", "Operand", "Data"]
-            .iter()
-            .map(|h| Cell::from(*h).style(theme.accent().add_modifier(Modifier::BOLD)));
+        let header_cells = self
+            .sort
+            .header(&COLUMNS)
+            .into_iter()
+            .map(|h| Cell::from(h).style(theme.accent().add_modifier(Modifier::BOLD)));
         let header = Row::new(header_cells).height(1);
 
         let rows: Vec<Row> = if filtered_rules.is_empty() {
@@ -142,32 +536,61 @@ impl RulesTab {
                 Cell::from(""),
                 Cell::from(""),
                 Cell::from(""),
+                Cell::from(""),
+                Cell::from(""),
+                Cell::from(""),
             ])
             .style(theme.dim())]
         } else {
+            let now = Utc::now();
             filtered_rules
                 .iter()
                 .map(|rule| {
-                    let enabled_style = if rule.enabled {
-                        Style::default().fg(Color::Green)
+                    let enabled_style = if rule.enabled { theme.success() } else { theme.dim() };
+                    let action = rule.action.to_string();
+                    // Temporary rules count down to when the daemon deletes them
+                    let duration = match rule.remaining(now) {
+                        Some(left) => Cell::from(format!(
+                            "{}{} left",
+                            if rule.expiry_estimated() { "~" } else { "" },
+                            format_duration_compact(left.as_secs())
+                        ))
+                        .style(theme.warning()),
+                        None => Cell::from(rule.duration.to_string()),
+                    };
+                    let updated = rule.updated.unwrap_or(rule.created);
+                    let age = now.signed_duration_since(updated).num_seconds().max(0) as u64;
+
+                    // Waiting for the daemon, undone if it rejects the change
+                    let mut name = if self.unconfirmed.contains(&rule.name) {
+                        Cell::from(format!("⧗ {}", truncate(&rule.name, 23))).style(theme.warning())
                     } else {
-                        Style::default().fg(Color::DarkGray)
+                        Cell::from(truncate(&rule.name, 25).to_string())
                     };
+                    if self.marked.contains_key(&rule.name) {
+                        name = Cell::from(format!("● {}", truncate(&rule.name, 23))).style(theme.accent());
+                    }
 
-                    let action_style = match rule.action.to_string().as_str() {
-                        "allow" => Style::default().fg(Color::Green),
-                        "deny" => Style::default().fg(Color::Red),
-                        "reject" => Style::default().fg(Color::Magenta),
-                        _ => theme.normal(),
+                    let hits = self.hits.get(&rule.name).copied().unwrap_or_default();
+                    let count = Cell::from(self.formats.number(hits.count));
+                    let last_hit = match hits.last_hit {
+                        Some(at) => {
+                            let ago = now.signed_duration_since(at).num_seconds().max(0) as u64;
+                            Cell::from(format!("{} ago", format_duration_compact(ago)))
+                        }
+                        None => Cell::from("never").style(theme.dim()),
                     };
 
                     Row::new(vec![
-                        Cell::from(truncate(&rule.name, 25).to_string()),
+                        name,
                         Cell::from(if rule.enabled { "✓" } else { "✗" }).style(enabled_style),
-                        Cell::from(rule.action.to_string()).style(action_style),
-                        Cell::from(rule.duration.to_string()),
+                        Cell::from(Theme::action_label(&action)).style(theme.action_style(&action)),
+                        duration,
+                        Cell::from(format!("{} ago", format_duration_compact(age))).style(theme.dim()),
+                        if hits.count == 0 { count.style(theme.dim()) } else { count },
+                        last_hit,
                         Cell::from(truncate(&rule.operator.operand, 18).to_string()),
-                        Cell::from(truncate(&rule.operator.data, 25).to_string()),
+                        Cell::from(truncate(&self.formats.rule(rule).operator.data, 25).to_string()),
                     ])
                 })
                 .collect()
@@ -176,22 +599,30 @@ impl RulesTab {
         let widths = [
             Constraint::Percentage(20), // Name
             Constraint::Length(8),      // Enabled
-            Constraint::Length(8),      // Action
+            Constraint::Length(9),      // Action
             Constraint::Length(14),     // Duration
-            Constraint::Percentage(18), // Operand
-            Constraint::Percentage(25), // Data
+            Constraint::Length(10),     // Updated
+            Constraint::Length(7),      // Hits
+            Constraint::Length(10),     // Last Hit
+            Constraint::Percentage(15), // Operand
+            Constraint::Percentage(20), // Data
         ];
 
         let title = if self.search_bar.query.is_empty() {
-            format!(" Rules ({}) ", filtered_rules.len())
+            format!(" Rules ({}) ", self.page.matched)
         } else {
             format!(
                 " Rules ({}/{}) [filter: {}] ",
-                filtered_rules.len(),
-                self.cached_rules.len(),
+                self.page.matched,
+                self.page.total,
                 self.search_bar.query
             )
         };
+        let title = if self.marked.is_empty() {
+            title
+        } else {
+            format!("{}[{} marked, c = copy to node, M = unmark] ", title, self.marked.len())
+        };
 
         let table = Table::new(rows, widths)
             .header(header)
@@ -203,19 +634,394 @@ impl RulesTab {
             .row_highlight_style(theme.selected())
             .highlight_symbol("▶ ");
 
+        self.table_state.select(self.selected.checked_sub(self.first_row));
+        *self.table_state.offset_mut() = 0;
+        self.table_area = chunks[1];
         frame.render_stateful_widget(table, chunks[1], &mut self.table_state);
 
-        if chunks[1].height > 10 && !self.filter_active {
+        if show_hint {
             let hint_area = Rect::new(
                 chunks[1].x,
                 chunks[1].y + chunks[1].height - 1,
                 chunks[1].width,
                 1,
             );
-            let hint = Paragraph::new(" / = filter  e = edit  n = new  d = delete  space = toggle")
-                .style(theme.dim());
+            let hint = match (&self.export_result, &self.undo_result) {
+                (Some(Ok(path)), _) => Paragraph::new(format!(" Report written to {}", path))
+                    .style(theme.success()),
+                (Some(Err(e)), _) => Paragraph::new(format!(" ✗ Report export failed: {}", e))
+                    .style(theme.error()),
+                (None, Some(Ok(message))) => Paragraph::new(format!(" Undone: {}", message))
+                    .style(theme.success()),
+                (None, Some(Err(e))) => Paragraph::new(format!(" ✗ Nothing undone: {}", e))
+                    .style(theme.error()),
+                (None, None) => Paragraph::new(" / = filter  e = edit  n = new  p = profiles  d = delete  space = toggle  u/H = undo/history  b = no network for a while  s/S = sort/reverse  m/c = mark/copy to node  N = noisy  P = policies  x/X = export report  i/o = import/export rules dir  L = blocklists  g e = recent events")
+                    .style(theme.dim()),
+            };
             frame.render_widget(hint, hint_area);
         }
+
+        if let Some(input) = &self.sandboxing {
+            self.render_sandbox(frame, area, input, theme);
+        }
+    }
+
+    /// Ask for the process to cut off from the network
+    fn render_sandbox(&self, frame: &mut Frame, area: Rect, input: &TextInput, theme: &Theme) {
+        use ratatui::widgets::Clear;
+        use crate::ui::layout::DialogLayout;
+
+        let dialog_area = DialogLayout::centered(area, 60, 7).dialog;
+        frame.render_widget(Clear, dialog_area);
+        let block = Block::default()
+            .title(format!(" No network for {} ", self.sandbox_duration))
+            .title_bottom(Line::from(" Enter = deny the process  Esc = cancel ").style(theme.dim()))
+            .borders(Borders::ALL)
+            .border_style(theme.border_focused());
+        let inner = block.inner(dialog_area);
+        frame.render_widget(block, dialog_area);
+        let input_area = Rect::new(inner.x + 1, inner.y + 1, inner.width.saturating_sub(2), 3);
+        input.render(frame, input_area, theme.normal(), theme.border_focused());
+    }
+
+    /// Keys while the process to sandbox is typed
+    fn handle_sandbox_key(&mut self, key: KeyEvent) -> Vec<TabCommand> {
+        let Some(input) = &mut self.sandboxing else {
+            return Vec::new();
+        };
+        match key.code {
+            KeyCode::Esc => self.sandboxing = None,
+            KeyCode::Enter => {
+                let path = input.value.trim().to_string();
+                let Some(addr) = self.cached_node_addr.clone().filter(|_| !path.is_empty()) else {
+                    return Vec::new();
+                };
+                self.sandboxing = None;
+                let rule = Rule::sandbox(&path, self.sandbox_duration.clone());
+                return vec![
+                    TabCommand::send(AppMessage::RuleAdded { node_addr: addr.clone(), rule: rule.clone() }),
+                    TabCommand::send(AppMessage::SendNotification {
+                        node_addr: addr,
+                        action: NotificationAction::ChangeRule(rule),
+                    }),
+                ];
+            }
+            KeyCode::Backspace => input.backspace(),
+            KeyCode::Char(c) => input.insert(c),
+            _ => {}
+        }
+        Vec::new()
+    }
+
+    fn render_noisy(&mut self, frame: &mut Frame, area: Rect, theme: &Theme) {
+        let header = Row::new(
+            ["Events", "Name", "Logged", "Rule"]
+                .iter()
+                .map(|h| Cell::from(*h).style(theme.accent().add_modifier(Modifier::BOLD))),
+        );
+
+        let rows: Vec<Row> = if self.noisy_rules().is_empty() {
+            vec![Row::new(vec![Cell::from(""), Cell::from("No rule matched recent events")]).style(theme.dim())]
+        } else {
+            self.noisy_rules()
+                .iter()
+                .map(|(rule, hits)| {
+                    let logged = if rule.nolog {
+                        Cell::from("no").style(theme.dim())
+                    } else {
+                        Cell::from("yes").style(Style::default().fg(Color::Yellow))
+                    };
+                    Row::new(vec![
+                        Cell::from(self.formats.number(*hits)),
+                        Cell::from(truncate(&rule.name, 25).to_string()),
+                        logged,
+                        Cell::from(rule_summary(&self.formats.rule(rule))),
+                    ])
+                })
+                .collect()
+        };
+
+        let widths = [
+            Constraint::Length(8),
+            Constraint::Percentage(25),
+            Constraint::Length(7),
+            Constraint::Min(20),
+        ];
+        let table = Table::new(rows, widths)
+            .header(header)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_style(theme.border_focused())
+                    .title(" Noisy rules: most events first ")
+                    .title_bottom(Line::from(" ↑↓ = select  l = toggle logging  N/Esc = back ").style(theme.dim())),
+            )
+            .row_highlight_style(theme.selected())
+            .highlight_symbol("▶ ");
+
+        frame.render_stateful_widget(table, area, &mut self.noisy_state);
+    }
+
+    /// Keys of the noisy rules view
+    fn handle_noisy_key(&mut self, key: KeyEvent) -> Vec<TabCommand> {
+        match key.code {
+            KeyCode::Char('N') | KeyCode::Esc => self.noisy = false,
+            KeyCode::Char('l') => {
+                let selected = self.noisy_state.selected().and_then(|i| self.noisy_rules.get_mut(i));
+                if let (Some((rule, _)), Some(addr)) = (selected, &self.cached_node_addr) {
+                    rule.nolog = !rule.nolog;
+                    let rule = rule.clone();
+                    return vec![
+                        TabCommand::send(AppMessage::RuleModified {
+                            node_addr: addr.clone(),
+                            rule: rule.clone(),
+                        }),
+                        TabCommand::send(AppMessage::SendNotification {
+                            node_addr: addr.clone(),
+                            action: NotificationAction::ChangeRule(rule),
+                        }),
+                    ];
+                }
+            }
+            _ => {
+                if let Some(delta) = navigation_delta(&key) {
+                    if let Some(idx) = step_index(self.noisy_state.selected(), self.noisy_rules.len(), delta) {
+                        self.noisy_state.select(Some(idx));
+                    }
+                }
+            }
+        }
+        Vec::new()
+    }
+
+    /// Policies of the node with their rules under them, sorted by name,
+    /// then the rules in no policy
+    pub fn policy_rows(&self) -> Vec<PolicyRow> {
+        let node = self.cached_node_addr.as_deref().unwrap_or_default();
+        let (policies, unassigned) = self.policies.group(node, &self.node_rules);
+        let mut sections: Vec<(Option<String>, Vec<&Rule>)> =
+            policies.into_iter().map(|(name, rules)| (Some(name), rules)).collect();
+        if !unassigned.is_empty() {
+            sections.push((None, unassigned));
+        }
+
+        let mut rows = Vec::new();
+        for (name, rules) in sections {
+            let collapsed = self.collapsed.contains(name.as_deref().unwrap_or_default());
+            rows.push(PolicyRow::Policy {
+                name,
+                rules: rules.len(),
+                enabled: rules.iter().filter(|r| r.enabled).count(),
+                collapsed,
+            });
+            if !collapsed {
+                rows.extend(rules.into_iter().map(|rule| PolicyRow::Rule(rule.clone())));
+            }
+        }
+        rows
+    }
+
+    fn selected_policy_row(&self) -> Option<PolicyRow> {
+        self.policy_rows().into_iter().nth(self.policy_state.selected()?)
+    }
+
+    /// Switch a whole policy: off when any of its rules is on, on otherwise
+    ///
+    /// The rules are updated one by one locally but sent to the node in a
+    /// single notification.
+    fn toggle_policy(&self, policy: &str) -> Vec<TabCommand> {
+        let Some(addr) = &self.cached_node_addr else {
+            return Vec::new();
+        };
+        let rules: Vec<&Rule> = self
+            .node_rules
+            .iter()
+            .filter(|r| self.policies.policy_of(addr, &r.name) == Some(policy))
+            .collect();
+        let enable = !rules.iter().any(|r| r.enabled);
+        let changed: Vec<Rule> = rules
+            .into_iter()
+            .filter(|r| r.enabled != enable)
+            .map(|r| Rule { enabled: enable, ..r.clone() })
+            .collect();
+        if changed.is_empty() {
+            return Vec::new();
+        }
+
+        let mut commands: Vec<TabCommand> = changed
+            .iter()
+            .map(|rule| {
+                TabCommand::send(AppMessage::RuleToggled {
+                    node_addr: addr.clone(),
+                    name: rule.name.clone(),
+                    enabled: enable,
+                })
+            })
+            .collect();
+        commands.push(TabCommand::send(AppMessage::SendNotification {
+            node_addr: addr.clone(),
+            action: if enable {
+                NotificationAction::EnableRules(changed)
+            } else {
+                NotificationAction::DisableRules(changed)
+            },
+        }));
+        commands
+    }
+
+    fn render_policies(&mut self, frame: &mut Frame, area: Rect, theme: &Theme) {
+        let rows: Vec<Row> = match self.policy_rows() {
+            rows if rows.is_empty() => vec![Row::new(vec![Cell::from("No rules loaded")]).style(theme.dim())],
+            rows => rows
+                .into_iter()
+                .map(|row| match row {
+                    PolicyRow::Policy { name, rules, enabled, collapsed } => {
+                        let marker = if collapsed { "▶" } else { "▼" };
+                        let state = match enabled {
+                            0 => Span::styled("off", theme.dim()),
+                            n if n == rules => Span::styled("on", theme.success()),
+                            n => Span::styled(format!("{}/{} on", n, rules), theme.warning()),
+                        };
+                        let heading = match &name {
+                            Some(name) => Span::styled(
+                                format!("{} {}", marker, name),
+                                theme.accent().add_modifier(Modifier::BOLD),
+                            ),
+                            None => Span::styled(format!("{} No policy", marker), theme.dim().add_modifier(Modifier::BOLD)),
+                        };
+                        Row::new(vec![
+                            Cell::from(heading),
+                            Cell::from(if name.is_some() { state } else { Span::raw("") }),
+                            Cell::from(format!("{} rules", rules)).style(theme.dim()),
+                        ])
+                    }
+                    PolicyRow::Rule(rule) => {
+                        let enabled_style = if rule.enabled { theme.success() } else { theme.dim() };
+                        Row::new(vec![
+                            Cell::from(format!("    {}", truncate(&rule.name, 30))),
+                            Cell::from(if rule.enabled { "✓" } else { "✗" }).style(enabled_style),
+                            Cell::from(rule_summary(&self.formats.rule(&rule))),
+                        ])
+                    }
+                })
+                .collect(),
+        };
+
+        let widths = [Constraint::Percentage(35), Constraint::Length(10), Constraint::Min(20)];
+        let table = Table::new(rows, widths)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_style(theme.border_focused())
+                    .title(" Policies ")
+                    .title_bottom(
+                        Line::from(" ↑↓ = select  Enter/←→ = fold  space = toggle  a = assign policy  P/Esc = back ")
+                            .style(theme.dim()),
+                    ),
+            )
+            .row_highlight_style(theme.selected())
+            .highlight_symbol("▶ ");
+        frame.render_stateful_widget(table, area, &mut self.policy_state);
+
+        if let Some((rule, input)) = &self.assigning {
+            use ratatui::widgets::Clear;
+            use crate::ui::layout::DialogLayout;
+
+            let dialog_area = DialogLayout::centered(area, 50, 7).dialog;
+            frame.render_widget(Clear, dialog_area);
+            let block = Block::default()
+                .title(format!(" Policy of {} ", truncate(rule, 30)))
+                .title_bottom(Line::from(" Enter = save, blank = none  Esc = cancel ").style(theme.dim()))
+                .borders(Borders::ALL)
+                .border_style(theme.border_focused());
+            let inner = block.inner(dialog_area);
+            frame.render_widget(block, dialog_area);
+            let input_area = Rect::new(inner.x + 1, inner.y + 1, inner.width.saturating_sub(2), 3);
+            input.render(frame, input_area, theme.normal(), theme.border_focused());
+        }
+    }
+
+    /// Keys of the policies view
+    fn handle_policy_key(&mut self, key: KeyEvent) -> Vec<TabCommand> {
+        if let Some((rule, input)) = &mut self.assigning {
+            match key.code {
+                KeyCode::Esc => self.assigning = None,
+                KeyCode::Enter => {
+                    let (rule, policy) = (rule.clone(), input.value.clone());
+                    self.assigning = None;
+                    if let Some(addr) = &self.cached_node_addr {
+                        self.policies.assign(addr, &rule, &policy);
+                        return vec![TabCommand::SavePolicies(self.policies.clone())];
+                    }
+                }
+                KeyCode::Backspace => input.backspace(),
+                KeyCode::Char(c) => input.insert(c),
+                _ => {}
+            }
+            return Vec::new();
+        }
+
+        let selected = self.selected_policy_row();
+        match (key.code, selected) {
+            (KeyCode::Char('P') | KeyCode::Esc, _) => self.grouped = false,
+            (KeyCode::Enter | KeyCode::Left | KeyCode::Right, Some(PolicyRow::Policy { name, .. })) => {
+                let name = name.unwrap_or_default();
+                let fold = match key.code {
+                    KeyCode::Left => true,
+                    KeyCode::Right => false,
+                    _ => !self.collapsed.contains(&name),
+                };
+                if fold {
+                    self.collapsed.insert(name);
+                } else {
+                    self.collapsed.remove(&name);
+                }
+            }
+            (KeyCode::Enter, Some(PolicyRow::Rule(rule))) => self.open_editor(RuleEditorDialog::edit(&rule)),
+            (KeyCode::Char(' '), Some(PolicyRow::Policy { name: Some(name), .. })) => return self.toggle_policy(&name),
+            (KeyCode::Char(' '), Some(PolicyRow::Rule(rule))) => return self.toggle_rule(&rule),
+            (KeyCode::Char('a'), Some(PolicyRow::Rule(rule))) => {
+                let node = self.cached_node_addr.as_deref().unwrap_or_default();
+                let current = self.policies.policy_of(node, &rule.name).unwrap_or_default();
+                let mut input = TextInput::new("Policy").with_value(current);
+                input.focused = true;
+                self.assigning = Some((rule.name, input));
+            }
+            _ => {
+                if let Some(delta) = navigation_delta(&key) {
+                    let len = self.policy_rows().len();
+                    if let Some(idx) = step_index(self.policy_state.selected(), len, delta) {
+                        self.policy_state.select(Some(idx));
+                    }
+                }
+            }
+        }
+        Vec::new()
+    }
+
+    /// Enable a disabled rule or disable an enabled one
+    fn toggle_rule(&self, rule: &Rule) -> Vec<TabCommand> {
+        let Some(addr) = &self.cached_node_addr else {
+            return Vec::new();
+        };
+        let new_enabled = !rule.enabled;
+        // Send notification to daemon
+        let action = if new_enabled {
+            NotificationAction::EnableRule(rule.name.clone())
+        } else {
+            NotificationAction::DisableRule(rule.name.clone())
+        };
+        vec![
+            TabCommand::send(AppMessage::RuleToggled {
+                node_addr: addr.clone(),
+                name: rule.name.clone(),
+                enabled: new_enabled,
+            }),
+            TabCommand::send(AppMessage::SendNotification {
+                node_addr: addr.clone(),
+                action,
+            }),
+        ]
     }
 
     fn render_delete_confirm(&self, frame: &mut Frame, area: Rect, theme: &Theme) {
@@ -251,76 +1057,171 @@ impl RulesTab {
             .style(theme.dim());
         frame.render_widget(hint, chunks[1]);
     }
+}
 
-    pub async fn handle_key(&mut self, key: KeyEvent, state: &Arc<AppState>, state_tx: &mpsc::Sender<AppMessage>) {
+impl Tab for RulesTab {
+    /// Clicking a row selects it
+    fn handle_mouse(&mut self, event: MouseEvent) -> Vec<TabCommand> {
+        let Some((column, row)) = click_position(&event).filter(|_| !self.showing_dialog()) else {
+            return scroll_with_keys(self, event);
+        };
+        if let Some(idx) = clicked_row(self.table_area, self.first_row, column, row) {
+            if idx < self.page.offset + self.page.rules.len() {
+                self.selected = idx;
+                self.refresh_local();
+            }
+        }
+        Vec::new()
+    }
+
+    fn handle_key(&mut self, key: KeyEvent) -> Vec<TabCommand> {
+        let mut commands = self.handle_list_key(key);
+        commands.extend(self.fetch_window());
+        commands
+    }
+
+    fn showing_dialog(&self) -> bool {
+        self.show_editor
+            || self.show_delete_confirm
+            || self.catalog.is_some()
+            || self.rules_dir.is_some()
+            || self.blocklists.is_some()
+            || self.history.is_some()
+            || self.copy.is_some()
+            || self.assigning.is_some()
+            || self.sandboxing.is_some()
+    }
+}
+
+impl RulesTab {
+    fn handle_list_key(&mut self, key: KeyEvent) -> Vec<TabCommand> {
         // Handle editor dialog
         if self.show_editor {
+            let mut commands = Vec::new();
             if let Some(editor) = &mut self.editor {
                 if let Some(result) = editor.handle_key(key) {
-                    match result {
-                        RuleEditorResult::Save(rule) => {
-                            // Determine if this is add or modify
-                            let is_new = editor.original_name.is_none();
-
-                            // Get active node address
-                            let node_addr = {
-                                let nodes = state.nodes.read().await;
-                                nodes.active_addr().map(|s| s.to_string())
-                            };
-
-                            if let Some(addr) = node_addr {
-                                if is_new {
-                                    // Send add rule notification
-                                    let _ = state_tx.send(AppMessage::RuleAdded {
-                                        node_addr: addr.clone(),
-                                        rule: rule.clone(),
-                                    }).await;
-                                    let _ = state_tx.send(AppMessage::SendNotification {
-                                        node_addr: addr,
-                                        action: NotificationAction::ChangeRule(rule),
-                                    }).await;
-                                } else {
-                                    // Send modify rule notification
-                                    let _ = state_tx.send(AppMessage::RuleModified {
-                                        node_addr: addr.clone(),
-                                        rule: rule.clone(),
-                                    }).await;
-                                    let _ = state_tx.send(AppMessage::SendNotification {
-                                        node_addr: addr,
-                                        action: NotificationAction::ChangeRule(rule),
-                                    }).await;
-                                }
-                            }
-                        }
-                        RuleEditorResult::Cancel => {}
+                    if let (RuleEditorResult::Save(rule), Some(addr)) = (result, &self.cached_node_addr) {
+                        // Determine if this is add or modify
+                        let msg = if editor.original_name.is_none() {
+                            AppMessage::RuleAdded { node_addr: addr.clone(), rule: rule.clone() }
+                        } else {
+                            AppMessage::RuleModified { node_addr: addr.clone(), rule: rule.clone() }
+                        };
+                        commands.push(TabCommand::send(msg));
+                        commands.push(TabCommand::send(AppMessage::SendNotification {
+                            node_addr: addr.clone(),
+                            action: NotificationAction::ChangeRule(rule),
+                        }));
                     }
                     self.show_editor = false;
                     self.editor = None;
                 }
             }
-            return;
+            return commands;
+        }
+
+        if let Some(catalog) = &mut self.catalog {
+            let Some(result) = catalog.handle_key(key) else {
+                return Vec::new();
+            };
+            self.catalog = None;
+            let Some(addr) = &self.cached_node_addr else {
+                return Vec::new();
+            };
+            return match result {
+                ProfileCatalogResult::Apply(rules) => rules
+                    .into_iter()
+                    .flat_map(|rule| {
+                        [
+                            TabCommand::send(AppMessage::RuleAdded { node_addr: addr.clone(), rule: rule.clone() }),
+                            TabCommand::send(AppMessage::SendNotification {
+                                node_addr: addr.clone(),
+                                action: NotificationAction::ChangeRule(rule),
+                            }),
+                        ]
+                    })
+                    .collect(),
+                ProfileCatalogResult::Remove(names) => names
+                    .into_iter()
+                    .flat_map(|name| {
+                        [
+                            TabCommand::send(AppMessage::RuleDeleted { node_addr: addr.clone(), name: name.clone() }),
+                            TabCommand::send(AppMessage::SendNotification {
+                                node_addr: addr.clone(),
+                                action: NotificationAction::DeleteRule(name),
+                            }),
+                        ]
+                    })
+                    .collect(),
+                ProfileCatalogResult::Close => Vec::new(),
+            };
+        }
+
+        if let Some(dialog) = &mut self.copy {
+            let Some(result) = dialog.handle_key(key) else {
+                return Vec::new();
+            };
+            self.copy = None;
+            return match result {
+                CopyRulesResult::Copy { node_addr, rules } => {
+                    self.marked.clear();
+                    vec![TabCommand::CopyRules { node_addr, rules }]
+                }
+                CopyRulesResult::Close => Vec::new(),
+            };
+        }
+
+        if let Some(dialog) = &mut self.rules_dir {
+            match dialog.handle_key(key) {
+                Some(RulesDirResult::Close) => self.rules_dir = None,
+                Some(RulesDirResult::Run(mode, path)) => {
+                    if let Some(addr) = &self.cached_node_addr {
+                        let node_addr = addr.clone();
+                        return vec![match mode {
+                            RulesDirMode::Import => TabCommand::ImportRulesDir { node_addr, path },
+                            RulesDirMode::Export => TabCommand::ExportRulesDir { node_addr, path },
+                        }];
+                    }
+                }
+                None => {}
+            }
+            return Vec::new();
+        }
+
+        if let Some(dialog) = &mut self.blocklists {
+            match dialog.handle_key(key) {
+                Some(BlocklistsResult::Close) => self.blocklists = None,
+                Some(BlocklistsResult::Download { dir, lists }) => {
+                    if let Some(addr) = &self.cached_node_addr {
+                        return vec![TabCommand::DownloadBlocklists { node_addr: addr.clone(), dir, lists }];
+                    }
+                }
+                None => {}
+            }
+            return Vec::new();
+        }
+
+        if let Some(dialog) = &mut self.history {
+            if dialog.handle_key(key) {
+                self.history = None;
+            }
+            return Vec::new();
         }
 
         // Handle delete confirmation
         if self.show_delete_confirm {
+            let mut commands = Vec::new();
             match key.code {
                 KeyCode::Char('y') | KeyCode::Char('Y') => {
-                    if let Some(name) = self.rule_to_delete.take() {
-                        let node_addr = {
-                            let nodes = state.nodes.read().await;
-                            nodes.active_addr().map(|s| s.to_string())
-                        };
-
-                        if let Some(addr) = node_addr {
-                            let _ = state_tx.send(AppMessage::RuleDeleted {
-                                node_addr: addr.clone(),
-                                name: name.clone(),
-                            }).await;
-                            let _ = state_tx.send(AppMessage::SendNotification {
-                                node_addr: addr,
-                                action: NotificationAction::DeleteRule(name),
-                            }).await;
-                        }
+                    if let (Some(name), Some(addr)) = (self.rule_to_delete.take(), &self.cached_node_addr) {
+                        commands.push(TabCommand::send(AppMessage::RuleDeleted {
+                            node_addr: addr.clone(),
+                            name: name.clone(),
+                        }));
+                        commands.push(TabCommand::send(AppMessage::SendNotification {
+                            node_addr: addr.clone(),
+                            action: NotificationAction::DeleteRule(name),
+                        }));
                     }
                     self.show_delete_confirm = false;
                 }
@@ -330,7 +1231,7 @@ impl RulesTab {
                 }
                 _ => {}
             }
-            return;
+            return commands;
         }
 
         if self.filter_active {
@@ -343,28 +1244,108 @@ impl RulesTab {
                 KeyCode::Delete => self.search_bar.delete(),
                 KeyCode::Left => self.search_bar.move_left(),
                 KeyCode::Right => self.search_bar.move_right(),
+                KeyCode::Up => self.search_bar.history_prev(),
+                KeyCode::Down => self.search_bar.history_next(),
+                KeyCode::Char('r') if key.modifiers.contains(KeyModifiers::CONTROL) => self.search_bar.recall_last(),
                 KeyCode::Char(c) => self.search_bar.insert(c),
                 _ => {}
             }
-            return;
+            self.refresh_local();
+            return Vec::new();
+        }
+
+        if self.sandboxing.is_some() {
+            return self.handle_sandbox_key(key);
+        }
+
+        if self.noisy {
+            return self.handle_noisy_key(key);
+        }
+
+        if self.grouped {
+            return self.handle_policy_key(key);
+        }
+
+        // Where to jump after `g`, `gg` still goes to the top
+        if std::mem::take(&mut self.goto) {
+            if key.code == KeyCode::Char('e') {
+                if let Some(rule) = self.selected_rule() {
+                    return vec![TabCommand::Jump(Jump::RuleEvents(rule.name.clone()))];
+                }
+            }
+            if key.code != KeyCode::Char('g') {
+                return Vec::new();
+            }
+        } else if key.code == KeyCode::Char('g') {
+            self.goto = true;
+            return Vec::new();
         }
 
+        self.export_result = None;
+        self.undo_result = None;
         match key.code {
+            KeyCode::Char('r') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.search_bar.recall_last();
+                self.refresh_local();
+            }
+            KeyCode::Char('N') => {
+                self.noisy = true;
+                self.noisy_state.select(Some(0));
+            }
+            KeyCode::Char('x' | 'X') => {
+                // All rules of the node, whatever the filter shows
+                if let Some(addr) = &self.cached_node_addr {
+                    let format = if key.code == KeyCode::Char('x') {
+                        ReportFormat::Markdown
+                    } else {
+                        ReportFormat::Text
+                    };
+                    return vec![TabCommand::ExportRules {
+                        node_addr: addr.clone(),
+                        format,
+                    }];
+                }
+            }
             KeyCode::Char('/') => {
                 self.filter_active = true;
                 self.search_bar.activate();
             }
-            KeyCode::Esc => self.search_bar.clear(),
+            KeyCode::Esc => {
+                self.search_bar.clear();
+                self.refresh_local();
+            }
             KeyCode::Char('n') => {
                 // New rule
-                self.editor = Some(RuleEditorDialog::new());
-                self.show_editor = true;
+                self.open_editor(RuleEditorDialog::new());
+            }
+            KeyCode::Char('p') if self.cached_node_addr.is_some() => {
+                let rules = self.local_rules.clone().unwrap_or_default();
+                self.catalog = Some(ProfileCatalogDialog::new(rules));
+            }
+            KeyCode::Char('i') if self.cached_node_addr.is_some() => {
+                self.rules_dir = Some(RulesDirDialog::new(RulesDirMode::Import));
+            }
+            KeyCode::Char('o') if self.cached_node_addr.is_some() => {
+                self.rules_dir = Some(RulesDirDialog::new(RulesDirMode::Export));
+            }
+            KeyCode::Char('L') if self.cached_node_addr.is_some() => {
+                self.blocklists = Some(BlocklistsDialog::new());
+            }
+            KeyCode::Char('u') => {
+                if let Some(addr) = &self.cached_node_addr {
+                    return vec![TabCommand::UndoRuleChange { node_addr: addr.clone() }];
+                }
+            }
+            KeyCode::Char('H') => {
+                if let Some(rule) = self.selected_rule() {
+                    self.history = Some(RuleHistoryDialog::new(&rule.name).with_formats(self.formats.clone()));
+                }
             }
             KeyCode::Char('e') | KeyCode::Enter => {
                 // Edit selected rule
                 if let Some(rule) = self.selected_rule() {
-                    self.editor = Some(RuleEditorDialog::edit(rule));
-                    self.show_editor = true;
+                    let editor = RuleEditorDialog::edit(rule);
+                    self.open_editor(editor);
                 }
             }
             KeyCode::Char('d') | KeyCode::Delete => {
@@ -377,68 +1358,99 @@ impl RulesTab {
             KeyCode::Char(' ') => {
                 // Toggle enable/disable
                 if let Some(rule) = self.selected_rule() {
-                    let node_addr = {
-                        let nodes = state.nodes.read().await;
-                        nodes.active_addr().map(|s| s.to_string())
-                    };
-
-                    if let Some(addr) = node_addr {
-                        let new_enabled = !rule.enabled;
-                        let _ = state_tx.send(AppMessage::RuleToggled {
-                            node_addr: addr.clone(),
-                            name: rule.name.clone(),
-                            enabled: new_enabled,
-                        }).await;
-
-                        // Send notification to daemon
-                        let action = if new_enabled {
-                            NotificationAction::EnableRule(rule.name.clone())
-                        } else {
-                            NotificationAction::DisableRule(rule.name.clone())
-                        };
-                        let _ = state_tx.send(AppMessage::SendNotification {
-                            node_addr: addr,
-                            action,
-                        }).await;
-                    }
+                    return self.toggle_rule(rule);
                 }
             }
+            KeyCode::Char('s') => {
+                let mut sort = self.sort;
+                sort.cycle(COLUMNS.len());
+                self.set_sort(sort);
+            }
+            KeyCode::Char('S') => {
+                let mut sort = self.sort;
+                sort.reverse();
+                self.set_sort(sort);
+            }
+            KeyCode::Char('b') if self.cached_node_addr.is_some() => {
+                // The process of the selected rule, when it is about one
+                let path = self
+                    .selected_rule()
+                    .filter(|rule| rule.operator.operand == "process.path")
+                    .map(|rule| rule.operator.data.clone())
+                    .unwrap_or_default();
+                let mut input = TextInput::new("Process path").with_value(&path);
+                input.focused = true;
+                self.sandboxing = Some(input);
+            }
+            KeyCode::Char('m') => self.toggle_mark(),
+            KeyCode::Char('M') => self.marked.clear(),
+            KeyCode::Char('c') => {
+                // The marked rules, or the selected one when none is
+                let rules: Vec<Rule> = if self.marked.is_empty() {
+                    self.selected_rule().cloned().into_iter().collect()
+                } else {
+                    self.marked.values().cloned().collect()
+                };
+                if !rules.is_empty() {
+                    self.copy = Some(CopyRulesDialog::new(rules, self.copy_targets.clone()));
+                }
+            }
+            KeyCode::Char('P') => {
+                self.grouped = true;
+                self.policy_state.select(Some(0));
+                self.refresh_local();
+            }
             _ => {
                 if let Some(delta) = navigation_delta(&key) {
-                    // Get filtered rules length
-                    let filtered_len = if self.search_bar.query.is_empty() {
-                        self.cached_rules.len()
-                    } else {
-                        let query = self.search_bar.query.to_lowercase();
-                        self.cached_rules
-                            .iter()
-                            .filter(|r| {
-                                r.name.to_lowercase().contains(&query)
-                                    || r.description.to_lowercase().contains(&query)
-                                    || r.operator.operand.to_lowercase().contains(&query)
-                                    || r.operator.data.to_lowercase().contains(&query)
-                            })
-                            .count()
-                    };
-
-                    if filtered_len == 0 {
-                        return;
+                    if let Some(idx) = step_index(Some(self.selected), self.page.matched, delta) {
+                        self.selected = idx;
+                        self.refresh_local();
                     }
-                    let current = self.table_state.selected().unwrap_or(0);
-                    let new_index = if delta == i32::MIN {
-                        0
-                    } else if delta == i32::MAX {
-                        filtered_len.saturating_sub(1)
-                    } else {
-                        (current as i32 + delta).clamp(0, filtered_len as i32 - 1) as usize
-                    };
-                    self.table_state.select(Some(new_index));
                 }
             }
         }
+        Vec::new()
+    }
+}
+
+/// Count events per matching rule
+fn rule_hits<'a>(events: impl Iterator<Item = &'a Event>) -> HashMap<String, u64> {
+    let mut hits = HashMap::new();
+    for rule in events.filter_map(|e| e.rule.as_ref()) {
+        *hits.entry(rule.name.clone()).or_insert(0) += 1;
     }
+    hits
+}
+
+/// Order positions of rules by a column, the rules themselves stay put
+fn sort_positions(rules: &[Rule], positions: &mut [usize], hits: &HashMap<String, RuleHits>, sort: ColumnSort) {
+    let hits_of = |rule: &Rule| hits.get(&rule.name).copied().unwrap_or_default();
+    sort.sort(positions, |column, &a, &b| {
+        let (a, b) = (&rules[a], &rules[b]);
+        match column {
+            0 => a.name.cmp(&b.name),
+            1 => a.enabled.cmp(&b.enabled),
+            2 => a.action.to_string().cmp(&b.action.to_string()),
+            3 => a.duration.to_string().cmp(&b.duration.to_string()),
+            4 => a.updated.unwrap_or(a.created).cmp(&b.updated.unwrap_or(b.created)),
+            // Never matched first, then the longest unmatched
+            5 => {
+                let (ha, hb) = (hits_of(a), hits_of(b));
+                ha.count.cmp(&hb.count).then_with(|| ha.last_hit.cmp(&hb.last_hit))
+            }
+            6 => hits_of(a).last_hit.cmp(&hits_of(b).last_hit),
+            7 => a.operator.operand.cmp(&b.operator.operand),
+            _ => a.operator.data.cmp(&b.operator.data),
+        }
+    });
 }
 
-fn truncate(s: &str, max: usize) -> &str {
-    if s.len() <= max { s } else { &s[..max] }
+/// Rules with events, most first
+fn rank_noisy(rules: &[Rule], hits: &HashMap<String, u64>) -> Vec<(Rule, u64)> {
+    let mut noisy: Vec<(Rule, u64)> = rules
+        .iter()
+        .filter_map(|rule| hits.get(&rule.name).map(|&n| (rule.clone(), n)))
+        .collect();
+    noisy.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.name.cmp(&b.0.name)));
+    noisy
 }
@@ -1,21 +1,35 @@
 //! Statistics tab implementation
 
+use std::borrow::Cow;
+use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
-use crossterm::event::{KeyCode, KeyEvent};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 use ratatui::{
     layout::{Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
     text::{Line, Span},
-    widgets::{BarChart, Block, Borders, Gauge, List, ListItem, Paragraph},
+    widgets::{BarChart, Block, Borders, Gauge, List, ListItem, ListState, Paragraph, Sparkline},
     Frame,
 };
+use tokio::task::JoinHandle;
 
 use crate::app::events::navigation_delta;
 use crate::app::state::AppState;
-use crate::models::Statistics;
+use crate::config::{ScheduledTask, Settings, StatsLimits, TaskKind};
+use crate::db::DbSize;
+use crate::models::statistics::RATE_WINDOW;
+use crate::models::{RateSample, Statistics, StatsFormat};
+use crate::ui::dialogs::confirm::ConfirmDialog;
+use crate::ui::tabs::{step_index, Tab, TabCommand};
 use crate::ui::theme::Theme;
-use crate::utils::format_duration;
+use crate::ui::widgets::searchbar::SearchBar;
+use crate::utils::{format_duration, text, Formats};
+
+/// How often the database size is read, counting the rows of a big
+/// database takes a while
+const DB_SIZE_INTERVAL: Duration = Duration::from_secs(30);
 
 /// Focus area for statistics tab
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -26,6 +40,7 @@ pub enum StatsFocus {
     ByPort,
     ByUser,
     ByExecutable,
+    ByInterface,
 }
 
 impl StatsFocus {
@@ -36,68 +51,356 @@ impl StatsFocus {
             Self::ByHost => Self::ByPort,
             Self::ByPort => Self::ByUser,
             Self::ByUser => Self::ByExecutable,
-            Self::ByExecutable => Self::Summary,
+            Self::ByExecutable => Self::ByInterface,
+            Self::ByInterface => Self::Summary,
         }
     }
 
     fn prev(self) -> Self {
         match self {
-            Self::Summary => Self::ByExecutable,
+            Self::Summary => Self::ByInterface,
             Self::ByProtocol => Self::Summary,
             Self::ByHost => Self::ByProtocol,
             Self::ByPort => Self::ByHost,
             Self::ByUser => Self::ByPort,
             Self::ByExecutable => Self::ByUser,
+            Self::ByInterface => Self::ByExecutable,
+        }
+    }
+
+    /// Index of the breakdown panel, None for the summary
+    fn panel_index(self) -> Option<usize> {
+        match self {
+            Self::Summary => None,
+            Self::ByProtocol => Some(0),
+            Self::ByHost => Some(1),
+            Self::ByPort => Some(2),
+            Self::ByUser => Some(3),
+            Self::ByExecutable => Some(4),
+            Self::ByInterface => Some(5),
+        }
+    }
+
+    fn title(self) -> &'static str {
+        match self {
+            Self::Summary => "Summary",
+            Self::ByProtocol => "By Protocol",
+            Self::ByHost => "By Host",
+            Self::ByPort => "By Port",
+            Self::ByUser => "By User",
+            Self::ByExecutable => "By Executable",
+            Self::ByInterface => "By Interface",
         }
     }
 }
 
+/// Breakdown panels in grid order
+const PANELS: [StatsFocus; 6] = [
+    StatsFocus::ByProtocol,
+    StatsFocus::ByHost,
+    StatsFocus::ByPort,
+    StatsFocus::ByUser,
+    StatsFocus::ByExecutable,
+    StatsFocus::ByInterface,
+];
+
+/// Minutes of traffic rates shown
+const RATE_MINUTES: i64 = RATE_WINDOW.num_minutes();
+
+/// Step used when adjusting a panel limit with +/-
+const LIMIT_STEP: usize = 5;
+
+/// Bounds for the refresh interval in seconds
+const MIN_REFRESH_SECS: u64 = 1;
+const MAX_REFRESH_SECS: u64 = 60;
+
 pub struct StatisticsTab {
     focus: StatsFocus,
     cached_stats: Option<Statistics>,
     connections_count: usize,
     rules_count: usize,
     alerts_count: usize,
+    limits: StatsLimits,
+    refresh_interval: Duration,
+    last_refresh: Option<Instant>,
+    /// Events per local interface, counted by the TUI
+    by_interface: HashMap<String, u64>,
+    /// Recent connections and drops per second, oldest first
+    rates: Vec<RateSample>,
+    show_traffic: bool,
+    list_states: [ListState; 6],
+    /// Focused panel shown full-screen
+    expanded: bool,
+    expanded_state: ListState,
+    search_bar: SearchBar,
+    filter_active: bool,
+    /// Path of the last export, or why it failed
+    export_result: Option<Result<String, String>>,
+    formats: Formats,
+    /// Size of the local database, read in the background now and then
+    db_size: Option<DbSize>,
+    db_size_read: Option<Instant>,
+    db_size_task: Option<JoinHandle<Option<DbSize>>>,
+    /// Days kept by a manual purge, those of the scheduled purge if any
+    keep_days: u64,
+    purge_confirm: Option<ConfirmDialog>,
 }
 
 impl StatisticsTab {
-    pub fn new() -> Self {
+    pub fn new(settings: &Settings) -> Self {
         Self {
             focus: StatsFocus::Summary,
             cached_stats: None,
             connections_count: 0,
             rules_count: 0,
             alerts_count: 0,
+            limits: settings.stats_limits.clone(),
+            refresh_interval: Duration::from_secs(
+                settings.stats_refresh_secs.clamp(MIN_REFRESH_SECS, MAX_REFRESH_SECS),
+            ),
+            last_refresh: None,
+            by_interface: HashMap::new(),
+            rates: Vec::new(),
+            show_traffic: true,
+            list_states: std::array::from_fn(|_| ListState::default().with_selected(Some(0))),
+            expanded: false,
+            expanded_state: ListState::default().with_selected(Some(0)),
+            search_bar: SearchBar::new(),
+            filter_active: false,
+            export_result: None,
+            formats: settings.formats(),
+            db_size: None,
+            db_size_read: None,
+            db_size_task: None,
+            keep_days: settings
+                .scheduled_tasks
+                .iter()
+                .find(|task| task.task == TaskKind::Purge)
+                .map_or(ScheduledTask::default().keep_days, |task| task.keep_days),
+            purge_confirm: None,
         }
     }
 
+    /// Read the database size again on the next refresh, e.g. after a purge
+    pub fn reread_db_size(&mut self) {
+        self.db_size_read = None;
+    }
+
+    pub fn search_bar_mut(&mut self) -> &mut SearchBar {
+        &mut self.search_bar
+    }
+
+    pub fn set_export_result(&mut self, result: Result<String, String>) {
+        self.export_result = Some(result);
+    }
+
+    pub fn set_stats(&mut self, stats: Option<Statistics>) {
+        self.cached_stats = stats;
+    }
+
+    /// Recent traffic rates of the node shown, oldest first
+    pub fn set_rates(&mut self, rates: Vec<RateSample>) {
+        self.rates = rates;
+    }
+
     pub async fn update_cache(&mut self, state: &Arc<AppState>) {
+        let due = self
+            .last_refresh
+            .map(|t| t.elapsed() >= self.refresh_interval)
+            .unwrap_or(true);
+
         let nodes = state.nodes.read().await;
         if let Some(node) = nodes.active_node() {
-            self.cached_stats = node.statistics.clone();
+            if due {
+                self.set_stats(node.statistics.clone());
+                self.by_interface = node.by_interface.clone();
+                self.set_rates(state.traffic.lock().unwrap().samples(&node.addr));
+            }
             self.rules_count = node.rules.len();
         } else {
-            self.cached_stats = None;
+            self.set_stats(None);
+            self.set_rates(Vec::new());
+            self.by_interface.clear();
             self.rules_count = 0;
         }
         drop(nodes);
 
+        if due {
+            self.last_refresh = Some(Instant::now());
+        }
+
+        if self.db_size_task.as_ref().is_some_and(|task| task.is_finished()) {
+            if let Some(task) = self.db_size_task.take() {
+                self.db_size = task.await.ok().flatten().or(self.db_size);
+            }
+        }
+        let size_due = self.db_size_read.is_none_or(|t| t.elapsed() >= DB_SIZE_INTERVAL);
+        if size_due && self.db_size_task.is_none() {
+            self.db_size_read = Some(Instant::now());
+            let state = state.clone();
+            self.db_size_task = Some(tokio::task::spawn_blocking(move || state.db.size().ok()));
+        }
+
         self.connections_count = state.connections.read().await.len();
         self.alerts_count = state.alerts.read().await.len();
     }
 
-    pub fn render(&self, frame: &mut Frame, area: Rect, _state: &Arc<AppState>, theme: &Theme) {
-        // Main layout: top cards + bottom breakdown
+    pub fn render(&mut self, frame: &mut Frame, area: Rect, theme: &Theme) {
+        if self.expanded {
+            self.render_expanded(frame, area, theme);
+            return;
+        }
+        self.render_panels(frame, area, theme);
+        if let Some(dialog) = &self.purge_confirm {
+            dialog.render(frame, theme);
+        }
+    }
+
+    fn render_panels(&mut self, frame: &mut Frame, area: Rect, theme: &Theme) {
+        // Main layout: top cards + traffic + bottom breakdown
         let chunks = Layout::default()
             .direction(Direction::Vertical)
             .constraints([
                 Constraint::Length(5),  // Summary cards
+                Constraint::Length(if self.show_traffic { 6 } else { 0 }), // Traffic
                 Constraint::Min(10),    // Breakdown panels
+                Constraint::Length(3),  // Hints
             ])
             .split(area);
 
         self.render_summary_cards(frame, chunks[0], theme);
-        self.render_breakdowns(frame, chunks[1], theme);
+        if self.show_traffic {
+            self.render_traffic(frame, chunks[1], theme);
+        }
+        self.render_breakdowns(frame, chunks[2], theme);
+        self.render_hints(frame, chunks[3], theme);
+    }
+
+    /// Connections and drops per second over the last minutes
+    fn render_traffic(&self, frame: &mut Frame, area: Rect, theme: &Theme) {
+        let cols = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+            .split(area);
+
+        let connections: Vec<f64> = self.rates.iter().map(|s| s.connections).collect();
+        let dropped: Vec<f64> = self.rates.iter().map(|s| s.dropped).collect();
+        self.render_rate(frame, cols[0], "Connections/s", &connections, Color::Blue, theme);
+        self.render_rate(frame, cols[1], "Dropped/s", &dropped, Color::Red, theme);
+    }
+
+    fn render_rate(&self, frame: &mut Frame, area: Rect, title: &str, rates: &[f64], color: Color, theme: &Theme) {
+        let now = rates.last().copied().unwrap_or(0.0);
+        let peak = rates.iter().copied().fold(0.0, f64::max);
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_style(theme.border())
+            .title(format!(" {} {:.1} ", title, now))
+            .title_bottom(Line::from(format!(" peak {:.1}, last {} min ", peak, RATE_MINUTES)).style(theme.dim()));
+
+        // The newest samples that fit, in hundredths so low rates still show
+        let width = block.inner(area).width as usize;
+        let data: Vec<u64> = rates[rates.len().saturating_sub(width)..]
+            .iter()
+            .map(|rate| (rate * 100.0).round() as u64)
+            .collect();
+        frame.render_widget(
+            Sparkline::default().block(block).data(&data).style(Style::default().fg(color)),
+            area,
+        );
+    }
+
+    fn panel_limit(&self, panel: StatsFocus) -> usize {
+        match panel {
+            StatsFocus::Summary => 0,
+            StatsFocus::ByProtocol => self.limits.by_protocol,
+            StatsFocus::ByHost => self.limits.by_host,
+            StatsFocus::ByPort => self.limits.by_port,
+            StatsFocus::ByUser => self.limits.by_user,
+            StatsFocus::ByExecutable => self.limits.by_executable,
+            StatsFocus::ByInterface => self.limits.by_interface,
+        }
+    }
+
+    /// Switch formatting while running, e.g. into private mode
+    pub fn set_formats(&mut self, formats: Formats) {
+        self.formats = formats;
+    }
+
+    /// How an entry of a panel is shown, masking hosts and paths in private mode
+    fn label<'a>(&self, panel: StatsFocus, key: &'a str) -> Cow<'a, str> {
+        match panel {
+            StatsFocus::ByHost => self.formats.host(key),
+            StatsFocus::ByExecutable => self.formats.path(key),
+            _ => Cow::Borrowed(key),
+        }
+    }
+
+    /// Entries of a breakdown panel sorted by count, capped at the panel limit
+    fn panel_entries(&self, panel: StatsFocus) -> Vec<(String, u64)> {
+        let data = match (panel, self.cached_stats.as_ref()) {
+            (StatsFocus::Summary, _) => return Vec::new(),
+            // Counted here, known without daemon statistics
+            (StatsFocus::ByInterface, _) => &self.by_interface,
+            (_, None) => return Vec::new(),
+            (StatsFocus::ByProtocol, Some(stats)) => &stats.by_proto,
+            (StatsFocus::ByHost, Some(stats)) => &stats.by_host,
+            (StatsFocus::ByPort, Some(stats)) => &stats.by_port,
+            (StatsFocus::ByUser, Some(stats)) => &stats.by_uid,
+            (StatsFocus::ByExecutable, Some(stats)) => &stats.by_executable,
+        };
+
+        let mut sorted: Vec<(String, u64)> = data.iter().map(|(k, v)| (k.clone(), *v)).collect();
+        sorted.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+
+        let limit = self.panel_limit(panel);
+        if limit > 0 {
+            sorted.truncate(limit);
+        }
+        sorted
+    }
+
+    /// Entries of the expanded panel matching the search query
+    pub fn expanded_entries(&self) -> Vec<(String, u64)> {
+        let entries = self.panel_entries(self.focus);
+        if self.search_bar.query.is_empty() {
+            return entries;
+        }
+        let query = self.search_bar.query.to_lowercase();
+        entries
+            .into_iter()
+            .filter(|(key, _)| key.to_lowercase().contains(&query))
+            .collect()
+    }
+
+    /// Entry limit of the focused breakdown panel, if a panel is focused
+    fn focused_limit_mut(&mut self) -> Option<&mut usize> {
+        match self.focus {
+            StatsFocus::Summary => None,
+            StatsFocus::ByProtocol => Some(&mut self.limits.by_protocol),
+            StatsFocus::ByHost => Some(&mut self.limits.by_host),
+            StatsFocus::ByPort => Some(&mut self.limits.by_port),
+            StatsFocus::ByUser => Some(&mut self.limits.by_user),
+            StatsFocus::ByExecutable => Some(&mut self.limits.by_executable),
+            StatsFocus::ByInterface => Some(&mut self.limits.by_interface),
+        }
+    }
+
+    /// Grow (+) or shrink (-) the focused panel limit, or the refresh
+    /// interval when the summary is focused
+    fn adjust(&mut self, increase: bool) {
+        if let Some(limit) = self.focused_limit_mut() {
+            *limit = if increase {
+                *limit + LIMIT_STEP
+            } else {
+                limit.saturating_sub(LIMIT_STEP)
+            };
+            return;
+        }
+
+        let secs = self.refresh_interval.as_secs();
+        let secs = if increase { secs + 1 } else { secs.saturating_sub(1) };
+        self.refresh_interval = Duration::from_secs(secs.clamp(MIN_REFRESH_SECS, MAX_REFRESH_SECS));
     }
 
     fn render_summary_cards(&self, frame: &mut Frame, area: Rect, theme: &Theme) {
@@ -133,7 +436,7 @@ impl StatisticsTab {
             frame,
             cards[1],
             "Connections",
-            &format!("{}", self.connections_count),
+            &self.formats.number(self.connections_count as u64),
             Color::Blue,
             theme,
         );
@@ -143,7 +446,7 @@ impl StatisticsTab {
             frame,
             cards[2],
             "Rules",
-            &format!("{}", self.rules_count),
+            &self.formats.number(self.rules_count as u64),
             Color::Green,
             theme,
         );
@@ -153,13 +456,13 @@ impl StatisticsTab {
             frame,
             cards[3],
             "Alerts",
-            &format!("{}", self.alerts_count),
+            &self.formats.number(self.alerts_count as u64),
             if self.alerts_count > 0 { Color::Yellow } else { Color::Gray },
             theme,
         );
 
         // Accepted/Dropped ratio
-        let ratio_text = format!("{}/{}", accepted, dropped);
+        let ratio_text = format!("{}/{}", self.formats.number(accepted), self.formats.number(dropped));
         self.render_card(
             frame,
             cards[4],
@@ -193,7 +496,7 @@ impl StatisticsTab {
         frame.render_widget(value_para, centered_area);
     }
 
-    fn render_breakdowns(&self, frame: &mut Frame, area: Rect, theme: &Theme) {
+    fn render_breakdowns(&mut self, frame: &mut Frame, area: Rect, theme: &Theme) {
         // 2x3 grid layout
         let rows = Layout::default()
             .direction(Direction::Vertical)
@@ -221,76 +524,17 @@ impl StatisticsTab {
             ])
             .split(rows[1]);
 
-        let stats = self.cached_stats.as_ref();
-
-        // By Protocol
-        let by_proto = stats.map(|s| &s.by_proto).cloned().unwrap_or_default();
-        self.render_breakdown_list(
-            frame,
-            top_cols[0],
-            "By Protocol",
-            &by_proto,
-            self.focus == StatsFocus::ByProtocol,
-            theme,
-        );
-
-        // By Host
-        let by_host = stats.map(|s| &s.by_host).cloned().unwrap_or_default();
-        self.render_breakdown_list(
-            frame,
-            top_cols[1],
-            "By Host",
-            &by_host,
-            self.focus == StatsFocus::ByHost,
-            theme,
-        );
-
-        // By Port
-        let by_port = stats.map(|s| &s.by_port).cloned().unwrap_or_default();
-        self.render_breakdown_list(
-            frame,
-            top_cols[2],
-            "By Port",
-            &by_port,
-            self.focus == StatsFocus::ByPort,
-            theme,
-        );
-
-        // By User
-        let by_user = stats.map(|s| &s.by_uid).cloned().unwrap_or_default();
-        self.render_breakdown_list(
-            frame,
-            bottom_cols[0],
-            "By User",
-            &by_user,
-            self.focus == StatsFocus::ByUser,
-            theme,
-        );
-
-        // By Executable
-        let by_exe = stats.map(|s| &s.by_executable).cloned().unwrap_or_default();
-        self.render_breakdown_list(
-            frame,
-            bottom_cols[1],
-            "By Executable",
-            &by_exe,
-            self.focus == StatsFocus::ByExecutable,
-            theme,
-        );
-
-        // Hints panel
-        self.render_hints(frame, bottom_cols[2], theme);
+        let areas = [top_cols[0], top_cols[1], top_cols[2], bottom_cols[0], bottom_cols[1], bottom_cols[2]];
+        for (panel, panel_area) in PANELS.into_iter().zip(areas) {
+            self.render_breakdown_list(frame, panel_area, panel, theme);
+        }
     }
 
-    fn render_breakdown_list(
-        &self,
-        frame: &mut Frame,
-        area: Rect,
-        title: &str,
-        data: &std::collections::HashMap<String, u64>,
-        focused: bool,
-        theme: &Theme,
-    ) {
+    fn render_breakdown_list(&mut self, frame: &mut Frame, area: Rect, panel: StatsFocus, theme: &Theme) {
+        let focused = self.focus == panel;
+        let limit = self.panel_limit(panel);
+        let entries = self.panel_entries(panel);
+
         let border_style = if focused {
             theme.border_focused()
         } else {
@@ -300,74 +544,257 @@ impl StatisticsTab {
         let block = Block::default()
             .borders(Borders::ALL)
             .border_style(border_style)
-            .title(format!(" {} ", title));
+            .title(if limit > 0 {
+                format!(" {} (top {}) ", panel.title(), limit)
+            } else {
+                format!(" {} ", panel.title())
+            });
 
         let inner = block.inner(area);
         frame.render_widget(block, area);
 
-        if data.is_empty() {
+        if entries.is_empty() {
             let msg = Paragraph::new("No data").style(theme.dim());
             frame.render_widget(msg, inner);
             return;
         }
 
-        // Sort by count descending, take top entries that fit
-        let mut sorted: Vec<_> = data.iter().collect();
-        sorted.sort_by(|a, b| b.1.cmp(a.1));
-
-        let max_items = (inner.height as usize).saturating_sub(1);
-        let items: Vec<ListItem> = sorted
+        let items: Vec<ListItem> = entries
             .iter()
-            .take(max_items)
             .map(|(key, count)| {
-                let truncated = if key.len() > 20 {
-                    format!("{}...", &key[..17])
-                } else {
-                    key.to_string()
-                };
-                ListItem::new(format!("{:20} {:>6}", truncated, count))
+                let key = self.label(panel, key);
+                let truncated = text::pad(&text::elide(&key, 20), 20);
+                ListItem::new(format!("{} {:>6}", truncated, self.formats.number(*count)))
             })
             .collect();
 
-        let list = List::new(items).style(theme.normal());
-        frame.render_widget(list, inner);
+        let mut list = List::new(items)
+            .style(theme.normal())
+            .highlight_symbol(if focused { "▶ " } else { "  " });
+        if focused {
+            list = list.highlight_style(theme.selected());
+        }
+
+        let Some(idx) = panel.panel_index() else { return };
+        let state = &mut self.list_states[idx];
+        clamp_selection(state, entries.len());
+        frame.render_stateful_widget(list, inner, state);
     }
 
-    fn render_hints(&self, frame: &mut Frame, area: Rect, theme: &Theme) {
+    fn render_expanded(&mut self, frame: &mut Frame, area: Rect, theme: &Theme) {
+        let show_search = self.filter_active || !self.search_bar.query.is_empty();
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(if show_search { 3 } else { 0 }),
+                Constraint::Min(5),
+                Constraint::Length(1),
+            ])
+            .split(area);
+
+        let entries = self.expanded_entries();
+        if show_search {
+            let total = self.panel_entries(self.focus).len();
+            self.search_bar.set_matches(entries.len(), total);
+            self.search_bar.render(frame, chunks[0], theme.normal(), theme.border_focused());
+        }
         let block = Block::default()
             .borders(Borders::ALL)
-            .border_style(theme.border())
-            .title(" Navigation ");
+            .border_style(theme.border_focused())
+            .title(Span::styled(
+                format!(" {} ({}) ", self.focus.title(), entries.len()),
+                theme.accent(),
+            ));
+
+        if entries.is_empty() {
+            let msg = Paragraph::new("No matching entries").style(theme.dim()).block(block);
+            frame.render_widget(msg, chunks[1]);
+        } else {
+            let items: Vec<ListItem> = entries
+                .iter()
+                .map(|(key, count)| {
+                    ListItem::new(format!("{:>8}  {}", self.formats.number(*count), self.label(self.focus, key)))
+                })
+                .collect();
+
+            let list = List::new(items)
+                .block(block)
+                .style(theme.normal())
+                .highlight_style(theme.selected())
+                .highlight_symbol("▶ ");
+
+            clamp_selection(&mut self.expanded_state, entries.len());
+            frame.render_stateful_widget(list, chunks[1], &mut self.expanded_state);
+        }
 
-        frame.render_widget(block.clone(), area);
+        let hint = Paragraph::new(" ↑↓ = scroll  Home/End = jump  / = search  Esc = back")
+            .style(theme.dim());
+        frame.render_widget(hint, chunks[2]);
+    }
 
-        let inner = block.inner(area);
-        let current_focus = match self.focus {
-            StatsFocus::Summary => "Summary",
-            StatsFocus::ByProtocol => "By Protocol",
-            StatsFocus::ByHost => "By Host",
-            StatsFocus::ByPort => "By Port",
-            StatsFocus::ByUser => "By User",
-            StatsFocus::ByExecutable => "By Executable",
+    fn render_hints(&self, frame: &mut Frame, area: Rect, theme: &Theme) {
+        let adjusting = if self.focus == StatsFocus::Summary {
+            "refresh interval"
+        } else {
+            "panel limit"
         };
 
-        let hint_text = format!(
-            "\n  Tab    = Next panel\n  S-Tab  = Previous panel\n  ↑/↓    = Scroll list\n  r      = Refresh stats\n\n  Current:\n    {}",
-            current_focus
+        let keys = format!(
+            " Tab/S-Tab = panel  ↑↓ = scroll  Enter = expand  +/- = {}  r = refresh  t = traffic  x/X = export JSON/CSV  P = purge",
+            adjusting
         );
-        let para = Paragraph::new(hint_text).style(theme.dim());
-        frame.render_widget(para, inner);
+        let status = match &self.export_result {
+            Some(Ok(path)) => Span::styled(format!(" Exported to {}", path), Style::default().fg(Color::Green)),
+            Some(Err(e)) => Span::styled(format!(" Export failed: {}", e), Style::default().fg(Color::Red)),
+            None => Span::styled(
+                format!(" {}, refresh every {}s", self.focus.title(), self.refresh_interval.as_secs()),
+                theme.dim(),
+            ),
+        };
+        let hints = vec![Line::styled(keys, theme.dim()), Line::from(status), self.db_line(theme)];
+        frame.render_widget(Paragraph::new(hints), area);
+    }
+
+    /// Database size and rows kept
+    fn db_line(&self, theme: &Theme) -> Line<'static> {
+        let Some(size) = self.db_size else {
+            return Line::styled(" Database size unknown", theme.dim());
+        };
+        let f = &self.formats;
+        let mut text = format!(" Database {}", f.bytes(size.bytes));
+        if size.free_bytes > 0 {
+            text.push_str(&format!(" ({} free)", f.bytes(size.free_bytes)));
+        }
+        text.push_str(&format!(
+            ": {} events, {} alerts, {} rules, {} DNS entries",
+            f.number(size.connections),
+            f.number(size.alerts),
+            f.number(size.rules),
+            f.number(size.dns)
+        ));
+        Line::styled(text, theme.dim())
     }
 
-    pub async fn handle_key(&mut self, key: KeyEvent, _state: &Arc<AppState>) {
+    fn handle_panel_key(&mut self, key: KeyEvent) -> Vec<TabCommand> {
+        self.export_result = None;
         match key.code {
+            KeyCode::Char('t') => self.show_traffic = !self.show_traffic,
+            KeyCode::Char('x') => return vec![TabCommand::ExportStats(StatsFormat::Json)],
+            KeyCode::Char('X') => return vec![TabCommand::ExportStats(StatsFormat::Csv)],
+            KeyCode::Char('P') => {
+                let message = format!(
+                    "Delete events and alerts older than {} days and compact the database?",
+                    self.keep_days
+                );
+                self.purge_confirm = Some(ConfirmDialog::new("Purge Database", &message).with_labels("Purge", "Cancel"));
+            }
             KeyCode::Tab => {
                 self.focus = self.focus.next();
             }
             KeyCode::BackTab => {
                 self.focus = self.focus.prev();
             }
-            _ => {}
+            KeyCode::Char('+') | KeyCode::Char('=') => {
+                self.adjust(true);
+            }
+            KeyCode::Char('-') => {
+                self.adjust(false);
+            }
+            KeyCode::Char('r') => {
+                self.last_refresh = None;
+            }
+            KeyCode::Enter if self.focus != StatsFocus::Summary => {
+                self.expanded = true;
+                self.search_bar.clear();
+                self.expanded_state.select(Some(0));
+            }
+            _ => {
+                if let Some(delta) = navigation_delta(&key) {
+                    if let Some(idx) = self.focus.panel_index() {
+                        let len = self.panel_entries(self.focus).len();
+                        move_selection(&mut self.list_states[idx], len, delta);
+                    }
+                }
+            }
+        }
+        Vec::new()
+    }
+
+    fn handle_expanded_key(&mut self, key: KeyEvent) {
+        if self.filter_active {
+            match key.code {
+                KeyCode::Esc | KeyCode::Enter => {
+                    self.filter_active = false;
+                    self.search_bar.deactivate();
+                }
+                KeyCode::Backspace => self.search_bar.backspace(),
+                KeyCode::Up => self.search_bar.history_prev(),
+                KeyCode::Down => self.search_bar.history_next(),
+                KeyCode::Char('r') if key.modifiers.contains(KeyModifiers::CONTROL) => self.search_bar.recall_last(),
+                KeyCode::Char(c) => self.search_bar.insert(c),
+                _ => {}
+            }
+            self.expanded_state.select(Some(0));
+            return;
         }
+
+        match key.code {
+            KeyCode::Char('r') if key.modifiers.contains(KeyModifiers::CONTROL) => self.search_bar.recall_last(),
+            KeyCode::Char('/') => {
+                self.filter_active = true;
+                self.search_bar.activate();
+            }
+            KeyCode::Esc if !self.search_bar.query.is_empty() => self.search_bar.clear(),
+            KeyCode::Esc | KeyCode::Enter => self.expanded = false,
+            _ => {
+                if let Some(delta) = navigation_delta(&key) {
+                    let len = self.expanded_entries().len();
+                    move_selection(&mut self.expanded_state, len, delta);
+                }
+            }
+        }
+    }
+}
+
+impl Tab for StatisticsTab {
+    fn handle_key(&mut self, key: KeyEvent) -> Vec<TabCommand> {
+        if let Some(dialog) = &mut self.purge_confirm {
+            if !dialog.handle_key(key) {
+                return Vec::new();
+            }
+            let confirmed = dialog.result == Some(true);
+            self.purge_confirm = None;
+            if confirmed {
+                // Show the new size right after
+                self.last_refresh = None;
+                return vec![TabCommand::PurgeDatabase { keep_days: self.keep_days }];
+            }
+            return Vec::new();
+        }
+        if self.expanded {
+            self.handle_expanded_key(key);
+            Vec::new()
+        } else {
+            self.handle_panel_key(key)
+        }
+    }
+
+    fn showing_dialog(&self) -> bool {
+        self.expanded || self.purge_confirm.is_some()
+    }
+}
+
+/// Keep the selection within a list of `len` entries
+fn clamp_selection(state: &mut ListState, len: usize) {
+    if let Some(selected) = state.selected() {
+        if selected >= len {
+            state.select(Some(len.saturating_sub(1)));
+        }
+    }
+}
+
+fn move_selection(state: &mut ListState, len: usize, delta: i32) {
+    if let Some(idx) = step_index(state.selected(), len, delta) {
+        state.select(Some(idx));
     }
 }
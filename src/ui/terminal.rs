@@ -0,0 +1,159 @@
+//! Fallbacks for terminals without UTF-8 or a rich palette
+//!
+//! Serial lines and plain consoles often only render ASCII and the 8 basic
+//! colors. Rather than every widget knowing about that, the finished frame
+//! is rewritten: symbols become their closest ASCII character and colors
+//! the closest basic one.
+
+use ratatui::buffer::Buffer;
+use ratatui::style::{Color, Modifier};
+
+use crate::config::TerminalMode;
+
+/// Terminals known to lack more than the basic colors
+const BASIC_TERMS: &[&str] = &["dumb", "linux", "ansi", "cons25", "sun", "vt52", "vt100", "vt102", "vt220", "vt320"];
+
+/// The basic colors in ANSI order
+const BASIC_COLORS: [Color; 8] = [
+    Color::Black,
+    Color::Red,
+    Color::Green,
+    Color::Yellow,
+    Color::Blue,
+    Color::Magenta,
+    Color::Cyan,
+    Color::Gray,
+];
+
+/// What the terminal can render
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Capabilities {
+    /// Symbols outside ASCII
+    pub unicode: bool,
+    /// Colors beyond the basic 8
+    pub rich_colors: bool,
+}
+
+impl Capabilities {
+    pub fn full() -> Self {
+        Self {
+            unicode: true,
+            rich_colors: true,
+        }
+    }
+
+    pub fn basic() -> Self {
+        Self {
+            unicode: false,
+            rich_colors: false,
+        }
+    }
+
+    /// Capabilities for a mode, detected from the environment in auto mode
+    pub fn detect(mode: TerminalMode) -> Self {
+        match mode {
+            TerminalMode::Full => Self::full(),
+            TerminalMode::Basic => Self::basic(),
+            TerminalMode::Auto => {
+                let var = |name| std::env::var(name).unwrap_or_default();
+                let locale = ["LC_ALL", "LC_CTYPE", "LANG"]
+                    .into_iter()
+                    .map(var)
+                    .find(|value| !value.is_empty())
+                    .unwrap_or_default();
+                Self::from_env(&var("TERM"), &var("COLORTERM"), &locale)
+            }
+        }
+    }
+
+    /// Capabilities from TERM, COLORTERM and the character locale
+    pub fn from_env(term: &str, colorterm: &str, locale: &str) -> Self {
+        let locale = locale.to_lowercase();
+        Self {
+            unicode: locale.contains("utf-8") || locale.contains("utf8"),
+            rich_colors: !colorterm.is_empty() || !(term.is_empty() || BASIC_TERMS.contains(&term)),
+        }
+    }
+
+    /// Rewrite a drawn frame to what the terminal can render
+    pub fn downgrade(&self, buffer: &mut Buffer) {
+        if self.unicode && self.rich_colors {
+            return;
+        }
+        for cell in buffer.content.iter_mut() {
+            if !self.unicode && !cell.symbol().is_ascii() {
+                let ascii = ascii_symbol(cell.symbol());
+                cell.set_symbol(ascii);
+            }
+            if !self.rich_colors {
+                // Dim text keeps standing out once dark gray is gone
+                if cell.fg == Color::DarkGray {
+                    cell.modifier.insert(Modifier::DIM);
+                }
+                cell.fg = basic_color(cell.fg, Color::Gray);
+                cell.bg = basic_color(cell.bg, Color::Black);
+            }
+        }
+    }
+}
+
+/// Closest ASCII character of a symbol
+pub fn ascii_symbol(symbol: &str) -> &'static str {
+    let Some(c) = symbol.chars().next() else {
+        return " ";
+    };
+    match c {
+        '─' | '━' | '═' | '╌' | '┄' | '—' | '–' | '↔' => "-",
+        '│' | '┃' | '║' | '╎' | '┆' => "|",
+        '\u{2500}'..='\u{257f}' => "+",
+        '▶' | '►' | '▸' | '→' | '⇒' => ">",
+        '◀' | '◄' | '◂' | '←' => "<",
+        '▲' | '↑' => "^",
+        '▼' | '↓' => "v",
+        '✓' | '✔' => "+",
+        '✗' | '✘' => "x",
+        '●' | '•' | '★' | '·' => "*",
+        '○' => "o",
+        '▣' => "#",
+        '⚠' | '⚑' => "!",
+        '…' => ".",
+        // Bar charts, gauges and scrollbars
+        '\u{2580}'..='\u{259f}' | '■' => "#",
+        '\u{2800}'..='\u{28ff}' => ".",
+        '\u{a0}' => " ",
+        _ => "?",
+    }
+}
+
+/// Closest basic color, with what dark gray becomes
+fn basic_color(color: Color, dark_gray: Color) -> Color {
+    match color {
+        Color::Reset => Color::Reset,
+        Color::White => Color::Gray,
+        Color::DarkGray => dark_gray,
+        Color::LightRed => Color::Red,
+        Color::LightGreen => Color::Green,
+        Color::LightYellow => Color::Yellow,
+        Color::LightBlue => Color::Blue,
+        Color::LightMagenta => Color::Magenta,
+        Color::LightCyan => Color::Cyan,
+        Color::Indexed(i) if i < 8 => BASIC_COLORS[i as usize],
+        Color::Indexed(i) if i < 16 => BASIC_COLORS[i as usize - 8],
+        Color::Indexed(i) if i < 232 => {
+            // 6x6x6 color cube
+            let level = |l: u8| if l >= 3 { 255 } else { 0 };
+            let i = i - 16;
+            rgb_color(level(i / 36), level(i / 6 % 6), level(i % 6))
+        }
+        Color::Indexed(i) => {
+            if i - 232 >= 12 { Color::Gray } else { Color::Black }
+        }
+        Color::Rgb(r, g, b) => rgb_color(r, g, b),
+        basic => basic,
+    }
+}
+
+fn rgb_color(r: u8, g: u8, b: u8) -> Color {
+    let bit = |channel: u8, value: usize| if channel > 127 { value } else { 0 };
+    BASIC_COLORS[bit(r, 1) | bit(g, 2) | bit(b, 4)]
+}
@@ -75,6 +75,19 @@ impl Default for Theme {
 }
 
 impl Theme {
+    /// Names `named` knows, in the order the preview cycles through them
+    pub const NAMES: &'static [&'static str] = &["dark", "light", "colorblind"];
+
+    /// Theme by its settings name, "default" being the dark one
+    pub fn named(name: &str) -> Option<Self> {
+        match name {
+            "default" | "dark" => Some(Self::dark()),
+            "light" => Some(Self::light()),
+            "colorblind" => Some(Self::colorblind()),
+            _ => None,
+        }
+    }
+
     /// Dark theme variant
     pub fn dark() -> Self {
         Self::default()
@@ -105,6 +118,21 @@ impl Theme {
         }
     }
 
+    /// Dark theme with the Okabe-Ito palette, telling allow and deny apart
+    /// without red and green
+    pub fn colorblind() -> Self {
+        Self {
+            success: Color::Rgb(0, 114, 178),
+            warning: Color::Rgb(240, 228, 66),
+            error: Color::Rgb(213, 94, 0),
+            info: Color::Rgb(86, 180, 233),
+            allow: Color::Rgb(0, 114, 178),
+            deny: Color::Rgb(230, 159, 0),
+            reject: Color::Rgb(204, 121, 167),
+            ..Self::dark()
+        }
+    }
+
     // Style helpers
     pub fn normal(&self) -> Style {
         Style::default().fg(self.fg).bg(self.bg)
@@ -170,4 +198,21 @@ impl Theme {
             _ => self.normal(),
         }
     }
+
+    /// Shape shown with an action, so it doesn't rest on colors alone
+    pub fn action_marker(action: &str) -> &'static str {
+        match action.to_lowercase().as_str() {
+            "allow" | "accept" => "✓",
+            "deny" | "drop" | "reject" => "✗",
+            _ => "",
+        }
+    }
+
+    /// Action as its marker and word, e.g. "✓ ALLOW", unknown ones as they are
+    pub fn action_label(action: &str) -> String {
+        match Self::action_marker(action) {
+            "" => action.to_string(),
+            marker => format!("{} {}", marker, action.to_uppercase()),
+        }
+    }
 }
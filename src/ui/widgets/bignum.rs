@@ -0,0 +1,59 @@
+//! Numbers three lines tall, readable across a room
+
+/// Rows of a digit, drawn with box characters
+fn box_glyph(digit: char) -> Option<[&'static str; 3]> {
+    Some(match digit {
+        '0' => ["┌─┐", "│ │", "└─┘"],
+        '1' => [" ┐ ", " │ ", " ┴ "],
+        '2' => ["╶─┐", "┌─┘", "└─╴"],
+        '3' => ["╶─┐", " ─┤", "╶─┘"],
+        '4' => ["╷ ╷", "└─┤", "  ╵"],
+        '5' => ["┌─╴", "└─┐", "╶─┘"],
+        '6' => ["┌─╴", "├─┐", "└─┘"],
+        '7' => ["╶─┐", "  │", "  ╵"],
+        '8' => ["┌─┐", "├─┤", "└─┘"],
+        '9' => ["┌─┐", "└─┤", "╶─┘"],
+        _ => return None,
+    })
+}
+
+/// Rows of a digit in ASCII, seven segments made of `_` and `|`
+fn ascii_glyph(digit: char) -> Option<[&'static str; 3]> {
+    Some(match digit {
+        '0' => [" _ ", "| |", "|_|"],
+        '1' => ["   ", "  |", "  |"],
+        '2' => [" _ ", " _|", "|_ "],
+        '3' => [" _ ", " _|", " _|"],
+        '4' => ["   ", "|_|", "  |"],
+        '5' => [" _ ", "|_ ", " _|"],
+        '6' => [" _ ", "|_ ", "|_|"],
+        '7' => [" _ ", "  |", "  |"],
+        '8' => [" _ ", "|_|", "|_|"],
+        '9' => [" _ ", "|_|", " _|"],
+        _ => return None,
+    })
+}
+
+/// Three rows spelling out `text`; anything but digits, like separators
+/// and units, stays one character wide on the bottom row. Without `unicode`
+/// the digits are drawn in ASCII, box characters would turn into `+`.
+pub fn big_lines(text: &str, unicode: bool) -> [String; 3] {
+    let glyph = if unicode { box_glyph } else { ascii_glyph };
+    let mut rows: [String; 3] = Default::default();
+    for c in text.chars() {
+        match glyph(c) {
+            Some(glyph) => {
+                for (row, part) in rows.iter_mut().zip(glyph) {
+                    row.push_str(part);
+                    row.push(' ');
+                }
+            }
+            None => {
+                rows[0].push(' ');
+                rows[1].push(' ');
+                rows[2].push(c);
+            }
+        }
+    }
+    rows
+}
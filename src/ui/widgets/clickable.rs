@@ -0,0 +1,47 @@
+//! Screen areas acting like a key press when clicked
+//!
+//! Dialogs note where they drew their buttons while rendering, a click
+//! there is then handled as the key the button stands for.
+
+use std::cell::RefCell;
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::{layout::Rect, text::Span};
+
+#[derive(Debug, Default)]
+pub struct ClickTargets(RefCell<Vec<(Rect, KeyCode)>>);
+
+impl ClickTargets {
+    /// Forget the targets of the last render
+    pub fn clear(&self) {
+        self.0.borrow_mut().clear();
+    }
+
+    pub fn add(&self, area: Rect, key: KeyCode) {
+        self.0.borrow_mut().push((area, key));
+    }
+
+    /// Key of the target at a screen position
+    pub fn key_at(&self, column: u16, row: u16) -> Option<KeyEvent> {
+        let position = ratatui::layout::Position::new(column, row);
+        self.0
+            .borrow()
+            .iter()
+            .find(|(area, _)| area.contains(position))
+            .map(|(_, code)| KeyEvent::new(*code, KeyModifiers::NONE))
+    }
+}
+
+/// Where each span of a line drawn from the left of `area` ends up
+pub fn span_areas(area: Rect, spans: &[Span]) -> Vec<Rect> {
+    let mut x = area.x;
+    spans
+        .iter()
+        .map(|span| {
+            let width = (span.width() as u16).min(area.right().saturating_sub(x));
+            let rect = Rect::new(x, area.y, width, 1.min(area.height));
+            x += width;
+            rect
+        })
+        .collect()
+}
@@ -7,6 +7,8 @@ use ratatui::{
     Frame,
 };
 
+use crate::utils::text;
+
 /// Text input field
 pub struct TextInput {
     pub label: String,
@@ -32,15 +34,11 @@ impl TextInput {
     }
 
     pub fn insert(&mut self, c: char) {
-        self.value.insert(self.cursor_pos, c);
-        self.cursor_pos += 1;
+        text::insert(&mut self.value, &mut self.cursor_pos, c);
     }
 
     pub fn backspace(&mut self) {
-        if self.cursor_pos > 0 {
-            self.cursor_pos -= 1;
-            self.value.remove(self.cursor_pos);
-        }
+        text::backspace(&mut self.value, &mut self.cursor_pos);
     }
 
     pub fn render(&self, frame: &mut Frame, area: Rect, style: Style, focused_style: Style) {
@@ -59,7 +57,7 @@ impl TextInput {
 
         if self.focused {
             frame.set_cursor_position((
-                area.x + 1 + self.cursor_pos as u16,
+                area.x + 1 + text::column(&self.value, self.cursor_pos) as u16,
                 area.y + 1,
             ));
         }
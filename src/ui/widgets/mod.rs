@@ -1,6 +1,9 @@
+pub mod bignum;
+pub mod clickable;
 pub mod form;
 pub mod popup;
 pub mod searchbar;
 pub mod statusbar;
 pub mod table;
+pub mod textarea;
 pub mod tree;
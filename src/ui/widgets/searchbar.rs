@@ -2,16 +2,31 @@
 
 use ratatui::{
     layout::Rect,
-    style::Style,
+    style::{Color, Style},
+    text::Line,
     widgets::{Block, Borders, Paragraph},
     Frame,
 };
 
+use crate::utils::text;
+
+/// Maximum number of remembered queries
+const MAX_HISTORY: usize = 50;
+
 /// Search bar state
 pub struct SearchBar {
     pub query: String,
     pub active: bool,
     pub cursor_pos: usize,
+    /// Past queries, oldest first
+    history: Vec<String>,
+    /// Position while browsing history
+    history_idx: Option<usize>,
+    /// Query being typed before browsing started
+    draft: String,
+    /// Entries matching the query and entries overall, as last counted
+    matches: Option<(usize, usize)>,
+    title: &'static str,
 }
 
 impl SearchBar {
@@ -20,6 +35,88 @@ impl SearchBar {
             query: String::new(),
             active: false,
             cursor_pos: 0,
+            history: Vec::new(),
+            history_idx: None,
+            draft: String::new(),
+            matches: None,
+            title: " Filter (/ to edit, ↑↓ history, Ctrl+R last, Esc to clear) ",
+        }
+    }
+
+    /// Title of the bar, describing what the query does
+    pub fn with_title(mut self, title: &'static str) -> Self {
+        self.title = title;
+        self
+    }
+
+    /// Record how many of `total` entries the current query matches
+    pub fn set_matches(&mut self, matched: usize, total: usize) {
+        self.matches = Some((matched, total));
+    }
+
+    pub fn history(&self) -> &[String] {
+        &self.history
+    }
+
+    pub fn set_history(&mut self, history: Vec<String>) {
+        self.history = history;
+        self.history_idx = None;
+    }
+
+    /// Remember the current query, moving repeats to the end
+    fn push_history(&mut self) {
+        self.history_idx = None;
+        if self.query.is_empty() {
+            return;
+        }
+        self.history.retain(|q| q != &self.query);
+        self.history.push(self.query.clone());
+        if self.history.len() > MAX_HISTORY {
+            self.history.remove(0);
+        }
+    }
+
+    fn set_query(&mut self, query: String) {
+        self.query = query;
+        self.cursor_pos = self.query.len();
+    }
+
+    /// Step back to an older query
+    pub fn history_prev(&mut self) {
+        if self.history.is_empty() {
+            return;
+        }
+        let idx = match self.history_idx {
+            None => {
+                self.draft = self.query.clone();
+                self.history.len() - 1
+            }
+            Some(i) => i.saturating_sub(1),
+        };
+        self.history_idx = Some(idx);
+        self.set_query(self.history[idx].clone());
+    }
+
+    /// Step forward to a newer query, ending at the draft
+    pub fn history_next(&mut self) {
+        let Some(i) = self.history_idx else {
+            return;
+        };
+        if i + 1 < self.history.len() {
+            self.history_idx = Some(i + 1);
+            self.set_query(self.history[i + 1].clone());
+        } else {
+            self.history_idx = None;
+            let draft = std::mem::take(&mut self.draft);
+            self.set_query(draft);
+        }
+    }
+
+    /// Reuse the most recent query
+    pub fn recall_last(&mut self) {
+        if let Some(last) = self.history.last() {
+            self.set_query(last.clone());
+            self.history_idx = None;
         }
     }
 
@@ -30,6 +127,7 @@ impl SearchBar {
 
     pub fn deactivate(&mut self) {
         self.active = false;
+        self.push_history();
     }
 
     pub fn clear(&mut self) {
@@ -38,33 +136,23 @@ impl SearchBar {
     }
 
     pub fn insert(&mut self, c: char) {
-        self.query.insert(self.cursor_pos, c);
-        self.cursor_pos += 1;
+        text::insert(&mut self.query, &mut self.cursor_pos, c);
     }
 
     pub fn backspace(&mut self) {
-        if self.cursor_pos > 0 {
-            self.cursor_pos -= 1;
-            self.query.remove(self.cursor_pos);
-        }
+        text::backspace(&mut self.query, &mut self.cursor_pos);
     }
 
     pub fn delete(&mut self) {
-        if self.cursor_pos < self.query.len() {
-            self.query.remove(self.cursor_pos);
-        }
+        text::delete(&mut self.query, self.cursor_pos);
     }
 
     pub fn move_left(&mut self) {
-        if self.cursor_pos > 0 {
-            self.cursor_pos -= 1;
-        }
+        self.cursor_pos = text::prev_boundary(&self.query, self.cursor_pos);
     }
 
     pub fn move_right(&mut self) {
-        if self.cursor_pos < self.query.len() {
-            self.cursor_pos += 1;
-        }
+        self.cursor_pos = text::next_boundary(&self.query, self.cursor_pos);
     }
 
     pub fn move_home(&mut self) {
@@ -78,10 +166,18 @@ impl SearchBar {
     pub fn render(&self, frame: &mut Frame, area: Rect, style: Style, focused_style: Style) {
         let border_style = if self.active { focused_style } else { style };
 
-        let block = Block::default()
+        let mut block = Block::default()
             .borders(Borders::ALL)
             .border_style(border_style)
-            .title(" Filter (/ to edit, Esc to clear) ");
+            .title(self.title);
+
+        // Live count, so an ineffective filter shows while typing
+        if let Some((matched, total)) = self.matches.filter(|_| !self.query.is_empty()) {
+            let count_style = if matched == 0 { Style::default().fg(Color::Yellow) } else { style };
+            block = block.title(
+                Line::styled(format!(" matches: {} of {} ", matched, total), count_style).right_aligned(),
+            );
+        }
 
         let display_text = if self.query.is_empty() && !self.active {
             "Type to filter...".to_string()
@@ -98,7 +194,7 @@ impl SearchBar {
         // Show cursor if active
         if self.active {
             frame.set_cursor_position((
-                area.x + 1 + self.cursor_pos as u16,
+                area.x + 1 + text::column(&self.query, self.cursor_pos) as u16,
                 area.y + 1,
             ));
         }
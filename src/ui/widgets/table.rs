@@ -1,5 +1,8 @@
 //! Sortable and filterable table widget
 
+use std::borrow::Cow;
+use std::cmp::Ordering;
+
 use ratatui::widgets::TableState;
 
 /// Extended table state with sorting and filtering
@@ -54,3 +57,56 @@ impl Default for SortableTableState {
         Self::new()
     }
 }
+
+/// Column a table is sorted by, cycled with `s` and reversed with `S`.
+/// Without a column rows keep the order they come in.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ColumnSort {
+    pub column: Option<usize>,
+    pub descending: bool,
+}
+
+impl ColumnSort {
+    /// Sort by the next of `columns`, back to no column after the last
+    pub fn cycle(&mut self, columns: usize) {
+        self.column = match self.column {
+            None if columns > 0 => Some(0),
+            Some(column) if column + 1 < columns => Some(column + 1),
+            _ => None,
+        };
+        self.descending = false;
+    }
+
+    pub fn reverse(&mut self) {
+        self.descending = !self.descending;
+    }
+
+    /// Whether rows come in another order than given
+    pub fn is_sorted(&self) -> bool {
+        self.column.is_some() || self.descending
+    }
+
+    /// Header labels, the sorted column marked with ▲ or ▼
+    pub fn header<'a>(&self, labels: &[&'a str]) -> Vec<Cow<'a, str>> {
+        let arrow = if self.descending { "▼" } else { "▲" };
+        labels
+            .iter()
+            .enumerate()
+            .map(|(i, label)| match self.column {
+                Some(column) if column == i => format!("{} {}", label, arrow).trim_start().to_string().into(),
+                _ => (*label).into(),
+            })
+            .collect()
+    }
+
+    /// Sort `rows` by the column, `compare` telling how two rows compare in
+    /// a column. Equal rows keep their order.
+    pub fn sort<T>(&self, rows: &mut [T], compare: impl Fn(usize, &T, &T) -> Ordering) {
+        match self.column {
+            Some(column) if self.descending => rows.sort_by(|a, b| compare(column, b, a)),
+            Some(column) => rows.sort_by(|a, b| compare(column, a, b)),
+            None if self.descending => rows.reverse(),
+            None => {}
+        }
+    }
+}
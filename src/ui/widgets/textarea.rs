@@ -0,0 +1,148 @@
+//! Multi-line text editor widget
+
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    layout::Rect,
+    style::Style,
+    text::Line,
+    widgets::{Block, Borders, Paragraph},
+    Frame,
+};
+
+use crate::utils::text;
+
+/// Multi-line text editor state (cursor column is a byte offset on a
+/// grapheme boundary)
+pub struct TextArea {
+    lines: Vec<String>,
+    row: usize,
+    col: usize,
+}
+
+impl TextArea {
+    pub fn new(text: &str) -> Self {
+        let mut lines: Vec<String> = text.lines().map(str::to_string).collect();
+        if lines.is_empty() {
+            lines.push(String::new());
+        }
+        Self { lines, row: 0, col: 0 }
+    }
+
+    pub fn text(&self) -> String {
+        self.lines.join("\n")
+    }
+
+    fn line_len(&self) -> usize {
+        self.lines[self.row].len()
+    }
+
+    /// Move to another row, keeping the cursor in the same screen column
+    fn move_to_row(&mut self, row: usize) {
+        let column = text::column(&self.lines[self.row], self.col);
+        self.row = row;
+        self.col = text::truncate(&self.lines[self.row], column).len();
+    }
+
+    /// Handle an editing key, returns true if the text changed
+    pub fn handle_key(&mut self, key: KeyEvent) -> bool {
+        match key.code {
+            KeyCode::Char(c) => {
+                text::insert(&mut self.lines[self.row], &mut self.col, c);
+                true
+            }
+            KeyCode::Tab => {
+                self.lines[self.row].insert_str(self.col, "  ");
+                self.col += 2;
+                true
+            }
+            KeyCode::Enter => {
+                let rest = self.lines[self.row].split_off(self.col);
+                self.row += 1;
+                self.lines.insert(self.row, rest);
+                self.col = 0;
+                true
+            }
+            KeyCode::Backspace if self.col > 0 => text::backspace(&mut self.lines[self.row], &mut self.col),
+            KeyCode::Backspace if self.row > 0 => {
+                let line = self.lines.remove(self.row);
+                self.row -= 1;
+                self.col = self.line_len();
+                self.lines[self.row].push_str(&line);
+                true
+            }
+            KeyCode::Delete if self.col < self.line_len() => text::delete(&mut self.lines[self.row], self.col),
+            KeyCode::Delete if self.row + 1 < self.lines.len() => {
+                let next = self.lines.remove(self.row + 1);
+                self.lines[self.row].push_str(&next);
+                true
+            }
+            KeyCode::Left => {
+                if self.col > 0 {
+                    self.col = text::prev_boundary(&self.lines[self.row], self.col);
+                } else if self.row > 0 {
+                    self.row -= 1;
+                    self.col = self.line_len();
+                }
+                false
+            }
+            KeyCode::Right => {
+                if self.col < self.line_len() {
+                    self.col = text::next_boundary(&self.lines[self.row], self.col);
+                } else if self.row + 1 < self.lines.len() {
+                    self.row += 1;
+                    self.col = 0;
+                }
+                false
+            }
+            KeyCode::Up => {
+                self.move_to_row(self.row.saturating_sub(1));
+                false
+            }
+            KeyCode::Down => {
+                self.move_to_row((self.row + 1).min(self.lines.len() - 1));
+                false
+            }
+            KeyCode::Home => {
+                self.col = 0;
+                false
+            }
+            KeyCode::End => {
+                self.col = self.line_len();
+                false
+            }
+            _ => false,
+        }
+    }
+
+    pub fn render(&self, frame: &mut Frame, area: Rect, title: &str, style: Style, border_style: Style) {
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_style(border_style)
+            .title(title.to_string());
+        let inner = block.inner(area);
+
+        // Scroll just enough to keep the cursor visible
+        let height = inner.height.max(1) as usize;
+        let width = inner.width.max(1) as usize;
+        let top = (self.row + 1).saturating_sub(height);
+        let line = &self.lines[self.row];
+        let column = text::column(line, self.col);
+        let left = (column + 1).saturating_sub(width);
+        // A wide character cut at the edge is skipped whole
+        let skipped = text::width(line) - text::width(text::skip_width(line, left));
+
+        let lines: Vec<Line> = self
+            .lines
+            .iter()
+            .skip(top)
+            .take(height)
+            .map(|l| Line::from(text::skip_width(l, left)))
+            .collect();
+
+        frame.render_widget(Paragraph::new(lines).block(block).style(style), area);
+        frame.set_cursor_position((
+            inner.x + column.saturating_sub(skipped) as u16,
+            inner.y + (self.row - top) as u16,
+        ));
+    }
+}
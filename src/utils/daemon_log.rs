@@ -0,0 +1,202 @@
+//! The daemon's log file, followed as it grows
+//!
+//! opensnitchd writes lines like `[2024-03-01 10:00:00]  INF  message`,
+//! colored with terminal escapes unless configured otherwise. Lines without
+//! a level, e.g. of a stack trace, belong to the entry above them.
+
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+/// Where the daemon logs when its configuration doesn't say
+pub const DEFAULT_LOG_FILE: &str = "/var/log/opensnitchd.log";
+
+/// Entries kept, the oldest are dropped beyond
+pub const MAX_LOG_ENTRIES: usize = 5000;
+
+/// How much of the end of the file is read when starting to follow it
+const TAIL_BYTES: u64 = 256 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub enum LogLevel {
+    #[default]
+    Debug,
+    Info,
+    Important,
+    Warning,
+    Error,
+    Fatal,
+}
+
+impl LogLevel {
+    /// Level of the daemon's label, e.g. `WAR`
+    fn from_label(label: &str) -> Option<Self> {
+        match label {
+            "DBG" => Some(Self::Debug),
+            "INF" => Some(Self::Info),
+            "IMP" => Some(Self::Important),
+            "WAR" => Some(Self::Warning),
+            "ERR" => Some(Self::Error),
+            "!!!" => Some(Self::Fatal),
+            _ => None,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Debug => "debug",
+            Self::Info => "info",
+            Self::Important => "important",
+            Self::Warning => "warning",
+            Self::Error => "error",
+            Self::Fatal => "fatal",
+        }
+    }
+
+    /// The next level up, back to debug after fatal
+    pub fn next(self) -> Self {
+        match self {
+            Self::Debug => Self::Info,
+            Self::Info => Self::Important,
+            Self::Important => Self::Warning,
+            Self::Warning => Self::Error,
+            Self::Error => Self::Fatal,
+            Self::Fatal => Self::Debug,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogEntry {
+    /// As the daemon wrote it, empty for lines without one
+    pub time: String,
+    /// None for lines without a level
+    pub level: Option<LogLevel>,
+    pub message: String,
+}
+
+impl LogEntry {
+    pub fn parse(line: &str) -> Self {
+        let line = strip_escapes(line);
+        let mut rest = line.trim_end();
+        let mut time = "";
+        if let Some(stamped) = rest.strip_prefix('[') {
+            if let Some(end) = stamped.find(']') {
+                time = &stamped[..end];
+                rest = &stamped[end + 1..];
+            }
+        }
+        let trimmed = rest.trim_start();
+        let label = trimmed.split_whitespace().next().unwrap_or_default();
+        match LogLevel::from_label(label) {
+            Some(level) => Self {
+                time: time.to_string(),
+                level: Some(level),
+                message: trimmed[label.len()..].trim().to_string(),
+            },
+            None => Self {
+                time: time.to_string(),
+                level: None,
+                message: if time.is_empty() { rest.to_string() } else { trimmed.to_string() },
+            },
+        }
+    }
+}
+
+/// Drop terminal escapes such as colors
+fn strip_escapes(line: &str) -> String {
+    let mut out = String::with_capacity(line.len());
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        if c != '\u{1b}' {
+            out.push(c);
+            continue;
+        }
+        if chars.next() == Some('[') {
+            // Parameters up to the final letter
+            for c in chars.by_ref() {
+                if c.is_ascii_alphabetic() {
+                    break;
+                }
+            }
+        }
+    }
+    out
+}
+
+/// Follows a log file, reading what was appended on each poll
+#[derive(Debug)]
+pub struct LogTail {
+    path: PathBuf,
+    /// Bytes read so far, None before the first poll
+    offset: Option<u64>,
+    /// Start of a line whose end wasn't written yet
+    partial: String,
+    entries: VecDeque<LogEntry>,
+}
+
+impl LogTail {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            offset: None,
+            partial: String::new(),
+            entries: VecDeque::new(),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn entries(&self) -> &VecDeque<LogEntry> {
+        &self.entries
+    }
+
+    /// Read the lines appended since the last poll, the end of the file the
+    /// first time. A file shorter than read so far was rotated and is read
+    /// again from the start. Returns whether new entries came in.
+    pub fn poll(&mut self) -> io::Result<bool> {
+        let mut file = File::open(&self.path)?;
+        let len = file.metadata()?.len();
+        let first = self.offset.is_none();
+        let start = match self.offset {
+            Some(offset) if offset == len => return Ok(false),
+            Some(offset) if offset < len => offset,
+            Some(_) => {
+                self.partial.clear();
+                0
+            }
+            None => len.saturating_sub(TAIL_BYTES),
+        };
+        file.seek(SeekFrom::Start(start))?;
+        let mut bytes = Vec::new();
+        file.take(len - start).read_to_end(&mut bytes)?;
+        self.offset = Some(start + bytes.len() as u64);
+
+        let mut text = std::mem::take(&mut self.partial);
+        text.push_str(&String::from_utf8_lossy(&bytes));
+        let mut lines: Vec<&str> = text.split('\n').collect();
+        // What follows the last newline is an unfinished line
+        self.partial = lines.pop().unwrap_or_default().to_string();
+        // Reading from the middle of the file, the first line is cut
+        if first && start > 0 && !lines.is_empty() {
+            lines.remove(0);
+        }
+
+        let before = self.entries.len();
+        for line in lines.into_iter().filter(|l| !l.trim().is_empty()) {
+            let mut entry = LogEntry::parse(line);
+            if entry.level.is_none() {
+                entry.level = self.entries.back().and_then(|e| e.level);
+            }
+            self.entries.push_back(entry);
+        }
+        let added = self.entries.len() > before;
+        while self.entries.len() > MAX_LOG_ENTRIES {
+            self.entries.pop_front();
+        }
+        Ok(added)
+    }
+}
@@ -0,0 +1,48 @@
+//! Public suffixes, the domains anyone may register names under
+//!
+//! Rules must never match every subdomain of one, `*.co.uk` would let a
+//! program reach whole countries. The list of the publicsuffix package is
+//! used when it is installed, a few common suffixes stand in otherwise.
+
+use std::sync::OnceLock;
+
+use publicsuffix::{List, Psl};
+
+/// Where distributions install the list
+const SYSTEM_LIST: &str = "/usr/share/publicsuffix/public_suffix_list.dat";
+
+/// Second levels countries commonly open for registration, as in `co.uk`
+const COMMON_SECOND_LEVELS: [&str; 10] = ["ac", "co", "com", "edu", "go", "gov", "ne", "net", "or", "org"];
+
+static LIST: OnceLock<Option<List>> = OnceLock::new();
+
+fn system_list() -> Option<&'static List> {
+    LIST.get_or_init(|| {
+        let text = std::fs::read_to_string(SYSTEM_LIST).ok()?;
+        match text.parse() {
+            Ok(list) => Some(list),
+            Err(e) => {
+                tracing::warn!("Can't read the public suffix list {}: {}", SYSTEM_LIST, e);
+                None
+            }
+        }
+    })
+    .as_ref()
+}
+
+/// Whether `domain` is a public suffix, like `com` or `co.uk`
+pub fn is_public_suffix(domain: &str) -> bool {
+    let domain = domain.trim_end_matches('.').to_ascii_lowercase();
+    match system_list() {
+        Some(list) => list.suffix(domain.as_bytes()).is_none_or(|suffix| suffix.as_bytes().len() == domain.len()),
+        None => is_common_suffix(&domain),
+    }
+}
+
+/// A top level domain, or a common second level of a country's
+fn is_common_suffix(domain: &str) -> bool {
+    match domain.split_once('.') {
+        None => true,
+        Some((second, tld)) => tld.len() == 2 && COMMON_SECOND_LEVELS.contains(&second),
+    }
+}
@@ -0,0 +1,272 @@
+//! Number, byte size, date and private data formatting
+//!
+//! Counts are grouped and dates ordered the way the user's locale does,
+//! or kept in plain ISO style. The locale comes from the settings, else
+//! from LC_ALL, LC_NUMERIC or LANG. In private mode hostnames, addresses
+//! and paths are masked on screen, for sharing it.
+
+use std::borrow::Cow;
+use std::net::IpAddr;
+
+use chrono::{DateTime, Local, TimeZone};
+use serde::{Deserialize, Serialize};
+
+use crate::models::{Alert, AlertData, Operator, Rule};
+
+/// How dates are written
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DateStyle {
+    /// 2024-05-01 13:45:00
+    #[default]
+    Iso,
+    /// In the locale's order, e.g. 01.05.2024 13:45:00 or 05/01/2024 01:45:00 PM
+    Locale,
+}
+
+/// Separators and date order of a group of locales
+struct LocaleFormat {
+    /// Matching `language_TERRITORY` names or bare languages
+    names: &'static [&'static str],
+    thousands: char,
+    decimal: char,
+    date: &'static str,
+    time: &'static str,
+}
+
+/// Specific territories come before their language
+const LOCALES: &[LocaleFormat] = &[
+    LocaleFormat { names: &["en_US"], thousands: ',', decimal: '.', date: "%m/%d/%Y", time: "%I:%M:%S %p" },
+    LocaleFormat { names: &["de_CH"], thousands: '\'', decimal: '.', date: "%d.%m.%Y", time: "%H:%M:%S" },
+    LocaleFormat { names: &["en"], thousands: ',', decimal: '.', date: "%d/%m/%Y", time: "%H:%M:%S" },
+    LocaleFormat { names: &["ja", "zh", "ko"], thousands: ',', decimal: '.', date: "%Y/%m/%d", time: "%H:%M:%S" },
+    LocaleFormat { names: &["de", "da", "nb", "nn", "tr"], thousands: '.', decimal: ',', date: "%d.%m.%Y", time: "%H:%M:%S" },
+    LocaleFormat { names: &["it", "es", "pt", "el", "id"], thousands: '.', decimal: ',', date: "%d/%m/%Y", time: "%H:%M:%S" },
+    LocaleFormat { names: &["nl"], thousands: '.', decimal: ',', date: "%d-%m-%Y", time: "%H:%M:%S" },
+    LocaleFormat { names: &["fr"], thousands: '\u{a0}', decimal: ',', date: "%d/%m/%Y", time: "%H:%M:%S" },
+    LocaleFormat { names: &["sv"], thousands: '\u{a0}', decimal: ',', date: "%Y-%m-%d", time: "%H:%M:%S" },
+    LocaleFormat { names: &["ru", "uk", "pl", "cs", "fi"], thousands: '\u{a0}', decimal: ',', date: "%d.%m.%Y", time: "%H:%M:%S" },
+];
+
+const ISO_DATE: &str = "%Y-%m-%d";
+const ISO_TIME: &str = "%H:%M:%S";
+
+/// Formatting shared by every tab and dialog
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Formats {
+    thousands: Option<char>,
+    decimal: char,
+    date: &'static str,
+    time: &'static str,
+    /// Mask hostnames, addresses and paths
+    private: bool,
+}
+
+impl Default for Formats {
+    /// Plain numbers and ISO dates
+    fn default() -> Self {
+        Self {
+            thousands: None,
+            decimal: '.',
+            date: ISO_DATE,
+            time: ISO_TIME,
+            private: false,
+        }
+    }
+}
+
+impl Formats {
+    /// Formatting of `locale` (e.g. "de_DE.UTF-8"); unknown locales keep the defaults
+    pub fn new(locale: &str, group_numbers: bool, dates: DateStyle) -> Self {
+        let name = locale.split(['.', '@']).next().unwrap_or_default();
+        let language = name.split('_').next().unwrap_or_default();
+        let Some(known) = LOCALES
+            .iter()
+            .find(|l| l.names.contains(&name) || l.names.contains(&language))
+        else {
+            return Self::default();
+        };
+
+        let mut formats = Self {
+            thousands: group_numbers.then_some(known.thousands),
+            decimal: known.decimal,
+            ..Self::default()
+        };
+        if dates == DateStyle::Locale {
+            formats.date = known.date;
+            formats.time = known.time;
+        }
+        formats
+    }
+
+    /// Formatting of `locale`, or of the environment's when empty
+    pub fn detect(locale: &str, group_numbers: bool, dates: DateStyle) -> Self {
+        if !locale.is_empty() {
+            return Self::new(locale, group_numbers, dates);
+        }
+        let env = ["LC_ALL", "LC_NUMERIC", "LANG"]
+            .iter()
+            .filter_map(|var| std::env::var(var).ok())
+            .find(|value| !value.is_empty())
+            .unwrap_or_default();
+        Self::new(&env, group_numbers, dates)
+    }
+
+    /// Mask hostnames, addresses and paths, for screen sharing
+    pub fn with_private(mut self, private: bool) -> Self {
+        self.private = private;
+        self
+    }
+
+    pub fn is_private(&self) -> bool {
+        self.private
+    }
+
+    /// A count, e.g. 1,234,567
+    pub fn number(&self, n: u64) -> String {
+        let digits = n.to_string();
+        let Some(separator) = self.thousands else {
+            return digits;
+        };
+        let mut out = String::with_capacity(digits.len() + digits.len() / 3);
+        for (i, digit) in digits.chars().enumerate() {
+            if i > 0 && (digits.len() - i).is_multiple_of(3) {
+                out.push(separator);
+            }
+            out.push(digit);
+        }
+        out
+    }
+
+    /// A size in binary units, e.g. 1.5 KiB
+    pub fn bytes(&self, n: u64) -> String {
+        const UNITS: [&str; 5] = ["KiB", "MiB", "GiB", "TiB", "PiB"];
+        if n < 1024 {
+            return format!("{} B", n);
+        }
+        let mut value = n as f64 / 1024.0;
+        let mut unit = 0;
+        while value >= 1024.0 && unit < UNITS.len() - 1 {
+            value /= 1024.0;
+            unit += 1;
+        }
+        let value = format!("{:.1}", value).replace('.', &self.decimal.to_string());
+        format!("{} {}", value, UNITS[unit])
+    }
+
+    /// Date and time in local time
+    pub fn date_time<Tz: TimeZone>(&self, t: &DateTime<Tz>) -> String {
+        t.with_timezone(&Local)
+            .format(&format!("{} {}", self.date, self.time))
+            .to_string()
+    }
+
+    /// Time of day in local time
+    pub fn time<Tz: TimeZone>(&self, t: &DateTime<Tz>) -> String {
+        t.with_timezone(&Local).format(self.time).to_string()
+    }
+
+    /// A hostname or address; in private mode a short hash that still
+    /// tells hosts apart, keeping the top-level domain, e.g. #3fa2c1.org
+    pub fn host<'a>(&self, host: &'a str) -> Cow<'a, str> {
+        if !self.private || host.is_empty() {
+            return Cow::Borrowed(host);
+        }
+        let hash = short_hash(host);
+        match host.rsplit_once('.') {
+            Some((_, tld)) if host.parse::<IpAddr>().is_err() && tld.chars().all(|c| c.is_ascii_alphabetic()) => {
+                Cow::Owned(format!("#{}.{}", hash, tld))
+            }
+            _ => Cow::Owned(format!("#{}", hash)),
+        }
+    }
+
+    /// A path; in private mode only its file name, e.g. .../curl
+    pub fn path<'a>(&self, path: &'a str) -> Cow<'a, str> {
+        match path.rsplit_once('/') {
+            Some((_, name)) if self.private => Cow::Owned(format!(".../{}", name)),
+            _ => Cow::Borrowed(path),
+        }
+    }
+
+    /// Free text that may hold anything, e.g. command line arguments
+    pub fn text<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        if self.private && !text.is_empty() {
+            Cow::Borrowed("(hidden)")
+        } else {
+            Cow::Borrowed(text)
+        }
+    }
+
+    /// Text naming addresses or networks among other words, e.g. a firewall
+    /// rule's description; in private mode those are masked
+    pub fn addresses<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        if !self.private {
+            return Cow::Borrowed(text);
+        }
+        let masked: Vec<Cow<str>> = text
+            .split(' ')
+            .map(|word| {
+                let bare = word.trim_matches(|c: char| matches!(c, ',' | ';' | '(' | ')'));
+                let ip = bare.split_once('/').map_or(bare, |(ip, _)| ip);
+                if ip.parse::<IpAddr>().is_ok() {
+                    Cow::Owned(word.replace(bare, &self.host(bare)))
+                } else {
+                    Cow::Borrowed(word)
+                }
+            })
+            .collect();
+        Cow::Owned(masked.join(" "))
+    }
+
+    /// One line about an alert, with what it names masked in private mode
+    pub fn alert(&self, alert: &Alert) -> String {
+        match &alert.data {
+            Some(AlertData::Text(text)) => self.text(text).into_owned(),
+            Some(AlertData::Connection(c)) => {
+                let dest = if c.dst_host.is_empty() { &c.dst_ip } else { &c.dst_host };
+                format!("{} -> {}:{}", c.process_name(), self.host(dest), c.dst_port)
+            }
+            Some(AlertData::FirewallRule(r)) => format!("FW Rule: {}", self.addresses(&r.description)),
+            _ => alert.text(),
+        }
+    }
+
+    /// A rule with the hosts, addresses and paths it matches masked
+    pub fn rule<'a>(&self, rule: &'a Rule) -> Cow<'a, Rule> {
+        if !self.private {
+            return Cow::Borrowed(rule);
+        }
+        let mut masked = rule.clone();
+        self.mask_operator(&mut masked.operator);
+        Cow::Owned(masked)
+    }
+
+    fn mask_operator(&self, operator: &mut Operator) {
+        operator.list.iter_mut().for_each(|op| self.mask_operator(op));
+        let operand = operator.operand.as_str();
+        operator.data = match operand {
+            "process.path" | "process.parent.path" => self.path(&operator.data).into_owned(),
+            "dest.host" | "dest.ip" | "dest.network" | "source.ip" | "source.network" => {
+                self.host(&operator.data).into_owned()
+            }
+            _ if operand == "process.command" || operand.starts_with("process.env.") => {
+                self.text(&operator.data).into_owned()
+            }
+            _ => return,
+        };
+    }
+
+    /// Time of day without seconds, for compact labels
+    pub fn minute<Tz: TimeZone>(&self, t: &DateTime<Tz>) -> String {
+        t.with_timezone(&Local).format(&self.time.replace(":%S", "")).to_string()
+    }
+}
+
+/// Six hex digits of the FNV-1a hash of a value, stable across runs
+pub fn short_hash(value: &str) -> String {
+    let hash = value
+        .bytes()
+        .fold(0x811c_9dc5_u32, |hash, byte| (hash ^ byte as u32).wrapping_mul(0x0100_0193));
+    format!("{:06x}", hash >> 8)
+}
@@ -0,0 +1,96 @@
+//! What the TUI may manage on the host it runs on
+//!
+//! Next to the daemon the TUI points its config at the gRPC socket,
+//! restarts the service and writes the firewall config. In a container or
+//! appliance only the gRPC port is reachable, so it runs as a remote
+//! console for daemons elsewhere and leaves the host alone.
+
+use std::path::Path;
+
+use crate::config::HostMode;
+
+/// Where the daemon keeps its configuration
+pub const DAEMON_CONFIG_DIR: &str = "/etc/opensnitchd";
+
+/// Files container runtimes leave in the root filesystem
+const CONTAINER_MARKERS: &[&str] = &["/.dockerenv", "/run/.containerenv"];
+
+/// Control groups of processes started by a container runtime
+const CONTAINER_CGROUPS: &[&str] = &["docker", "kubepods", "containerd", "libpod", "lxc"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Host {
+    /// The daemon runs here: root, its config, systemd and the network are ours to use
+    pub manage_daemon: bool,
+    /// Running inside a container
+    pub container: bool,
+}
+
+impl Host {
+    /// Next to the daemon
+    pub fn local() -> Self {
+        Self {
+            manage_daemon: true,
+            container: false,
+        }
+    }
+
+    /// Remote console only
+    pub fn console() -> Self {
+        Self {
+            manage_daemon: false,
+            container: false,
+        }
+    }
+
+    /// Capabilities for a mode, detected from the host in auto mode
+    pub fn detect(mode: HostMode) -> Self {
+        let container = in_container();
+        let host = match mode {
+            HostMode::Local => Self::local(),
+            HostMode::Console => Self::console(),
+            HostMode::Auto => Self::from_probe(container, daemon_installed()),
+        };
+        Self { container, ..host }
+    }
+
+    /// Capabilities from what was found on the host
+    pub fn from_probe(container: bool, daemon_installed: bool) -> Self {
+        Self {
+            manage_daemon: !container && daemon_installed,
+            container,
+        }
+    }
+}
+
+fn in_container() -> bool {
+    if std::env::var_os("container").is_some() || CONTAINER_MARKERS.iter().any(|m| Path::new(m).exists()) {
+        return true;
+    }
+    std::fs::read_to_string("/proc/1/cgroup")
+        .map(|cgroup| is_container_cgroup(&cgroup))
+        .unwrap_or(false)
+}
+
+/// Whether a /proc/<pid>/cgroup belongs to a container
+pub fn is_container_cgroup(cgroup: &str) -> bool {
+    cgroup
+        .lines()
+        .any(|line| CONTAINER_CGROUPS.iter().any(|name| line.contains(name)))
+}
+
+/// The daemon is installed and systemd could restart it
+fn daemon_installed() -> bool {
+    let systemctl = std::env::var_os("PATH")
+        .map(|path| std::env::split_paths(&path).any(|dir| dir.join("systemctl").is_file()))
+        .unwrap_or(false);
+    systemctl && Path::new(DAEMON_CONFIG_DIR).is_dir()
+}
+
+/// Whether a node address is a daemon on this host: a Unix socket or loopback
+pub fn is_local_node(addr: &str) -> bool {
+    addr.starts_with("unix:")
+        || addr
+            .parse::<std::net::SocketAddr>()
+            .is_ok_and(|addr| addr.ip().is_loopback())
+}
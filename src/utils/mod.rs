@@ -1,6 +1,13 @@
+pub mod daemon_log;
+pub mod domain;
 pub mod duration;
+pub mod format;
+pub mod host;
 pub mod network;
 pub mod process;
+pub mod text;
 
 pub use duration::format_duration;
-pub use network::format_address;
+pub use format::{DateStyle, Formats};
+pub use host::Host;
+pub use network::{format_address, NetworkState, RoutingTable};
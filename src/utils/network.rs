@@ -1,4 +1,8 @@
-//! Network formatting utilities
+//! Network formatting and detection utilities
+
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+use super::text;
 
 /// Format an address:port combination
 pub fn format_address(host: &str, ip: &str, port: u32) -> String {
@@ -8,11 +12,7 @@ pub fn format_address(host: &str, ip: &str, port: u32) -> String {
 
 /// Truncate hostname to fit display
 pub fn truncate_host(host: &str, max_len: usize) -> String {
-    if host.len() <= max_len {
-        host.to_string()
-    } else {
-        format!("{}...", &host[..max_len.saturating_sub(3)])
-    }
+    text::elide(host, max_len)
 }
 
 /// Get protocol display name
@@ -35,8 +35,251 @@ pub fn is_ipv6(ip: &str) -> bool {
 pub fn format_ip(ip: &str) -> String {
     if is_ipv6(ip) && ip.len() > 20 {
         // Truncate long IPv6 addresses
-        format!("{}...", &ip[..17])
+        text::elide(ip, 20)
     } else {
         ip.to_string()
     }
 }
+
+/// Well-known protocol, the programs speaking it and the ports it runs on
+struct ServicePorts {
+    service: &'static str,
+    processes: &'static [&'static str],
+    ports: &'static [u32],
+}
+
+/// Heuristics for spotting protocols on unusual ports, by client program
+const SERVICE_PORTS: &[ServicePorts] = &[
+    ServicePorts { service: "SSH", processes: &["ssh", "scp", "sftp", "autossh"], ports: &[22] },
+    ServicePorts {
+        service: "DNS",
+        processes: &["systemd-resolved", "dnsmasq", "unbound", "named", "dig", "nslookup", "host", "kdig", "drill"],
+        // Plain DNS and DNS over TLS
+        ports: &[53, 853],
+    },
+    ServicePorts {
+        service: "NTP",
+        processes: &["ntpd", "chronyd", "systemd-timesyncd", "ntpdate", "sntp"],
+        // NTP and NTS key exchange
+        ports: &[123, 4460],
+    },
+    ServicePorts { service: "SMTP", processes: &["smtp", "exim4", "msmtp", "ssmtp"], ports: &[25, 465, 587] },
+    ServicePorts { service: "Mail", processes: &["fetchmail", "getmail", "mbsync"], ports: &[110, 143, 993, 995] },
+    ServicePorts { service: "rsync", processes: &["rsync"], ports: &[873] },
+];
+
+/// A well-known protocol spoken on a port it doesn't normally use
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PortAnomaly {
+    pub service: &'static str,
+    pub port: u32,
+}
+
+impl PortAnomaly {
+    /// Short badge text, e.g. "SSH:443"
+    pub fn badge(&self) -> String {
+        format!("{}:{}", self.service, self.port)
+    }
+}
+
+/// Check whether a program connects to a remote port unusual for its protocol
+///
+/// Only programs in the heuristic table are judged; local destinations are
+/// left alone since stub resolvers and tunnels often listen on odd ports.
+pub fn port_anomaly(process_path: &str, dst_ip: &str, dst_port: u32) -> Option<PortAnomaly> {
+    if dst_port == 0 || dst_ip.parse::<std::net::IpAddr>().is_ok_and(|ip| ip.is_loopback()) {
+        return None;
+    }
+    let process = process_path.rsplit('/').next().unwrap_or(process_path);
+    let known = SERVICE_PORTS.iter().find(|s| s.processes.contains(&process))?;
+    (!known.ports.contains(&dst_port)).then_some(PortAnomaly { service: known.service, port: dst_port })
+}
+
+/// Network the host is currently on
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NetworkState {
+    /// Interface of the default route
+    pub interface: String,
+    /// Default gateway address
+    pub gateway: String,
+    /// Wi-Fi network name, empty on wired or unknown networks
+    pub ssid: String,
+}
+
+impl NetworkState {
+    /// Read the current default route and SSID
+    pub fn detect() -> Self {
+        let (interface, gateway) = std::fs::read_to_string("/proc/net/route")
+            .ok()
+            .and_then(|route| parse_default_route(&route))
+            .unwrap_or_default();
+        let ssid = if interface.is_empty() {
+            String::new()
+        } else {
+            detect_ssid(&interface)
+        };
+        Self { interface, gateway, ssid }
+    }
+
+    /// Short label for the status bar
+    pub fn label(&self) -> String {
+        match (self.ssid.is_empty(), self.interface.is_empty()) {
+            (false, _) => self.ssid.clone(),
+            (true, false) => self.interface.clone(),
+            (true, true) => "offline".to_string(),
+        }
+    }
+}
+
+/// Interface and gateway of the first default route in /proc/net/route
+pub fn parse_default_route(route: &str) -> Option<(String, String)> {
+    route.lines().skip(1).find_map(|line| {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let (iface, dest, gateway, mask) = (fields.first()?, fields.get(1)?, fields.get(2)?, fields.get(7)?);
+        if *dest != "00000000" || *mask != "00000000" {
+            return None;
+        }
+        // Gateway is a little-endian hex IPv4 address
+        let raw = u32::from_str_radix(gateway, 16).ok()?;
+        let gateway = std::net::Ipv4Addr::from(raw.swap_bytes());
+        Some((iface.to_string(), gateway.to_string()))
+    })
+}
+
+/// Routes of the host, to tell which interface a local address is on
+#[derive(Debug, Clone, Default)]
+pub struct RoutingTable {
+    routes: Vec<Route>,
+}
+
+#[derive(Debug, Clone)]
+struct Route {
+    network: IpAddr,
+    prefix: u32,
+    interface: String,
+}
+
+impl RoutingTable {
+    /// Read the IPv4 and IPv6 routes from /proc
+    pub fn read() -> Self {
+        let read = |path| std::fs::read_to_string(path).unwrap_or_default();
+        Self::parse(&read("/proc/net/route"), &read("/proc/net/ipv6_route"))
+    }
+
+    /// Routes from /proc/net/route and /proc/net/ipv6_route
+    pub fn parse(ipv4: &str, ipv6: &str) -> Self {
+        let v4 = ipv4.lines().skip(1).filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let (iface, dest, mask) = (fields.first()?, fields.get(1)?, fields.get(7)?);
+            // Little-endian hex, like the gateway
+            let dest = u32::from_str_radix(dest, 16).ok()?.swap_bytes();
+            let mask = u32::from_str_radix(mask, 16).ok()?.swap_bytes();
+            Some(Route {
+                network: IpAddr::V4(Ipv4Addr::from(dest)),
+                prefix: mask.count_ones(),
+                interface: iface.to_string(),
+            })
+        });
+        let v6 = ipv6.lines().filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let (dest, prefix, iface) = (fields.first()?, fields.get(1)?, fields.get(9)?);
+            Some(Route {
+                network: IpAddr::V6(Ipv6Addr::from(u128::from_str_radix(dest, 16).ok()?)),
+                prefix: u32::from_str_radix(prefix, 16).ok()?,
+                interface: iface.to_string(),
+            })
+        });
+        // The host's own addresses are routed through lo in the local table
+        let routes = v4.chain(v6).filter(|route| route.interface != "lo").collect();
+        Self { routes }
+    }
+
+    /// Interface of the most specific route covering an address
+    pub fn interface(&self, ip: &str) -> Option<&str> {
+        let ip: IpAddr = ip.parse().ok()?;
+        if ip.is_loopback() {
+            return Some("lo");
+        }
+        self.route(ip).map(|route| route.interface.as_str())
+    }
+
+    /// Network of the most specific route covering an address, as
+    /// "192.168.1.0/24"; None for addresses only the default route covers
+    pub fn network(&self, ip: &str) -> Option<String> {
+        let ip: IpAddr = ip.parse().ok()?;
+        if ip.is_loopback() {
+            return None;
+        }
+        self.route(ip)
+            .filter(|route| route.prefix > 0)
+            .map(|route| format!("{}/{}", route.network, route.prefix))
+    }
+
+    fn route(&self, ip: IpAddr) -> Option<&Route> {
+        self.routes
+            .iter()
+            .filter(|route| covers(route, ip))
+            .max_by_key(|route| route.prefix)
+    }
+}
+
+fn covers(route: &Route, ip: IpAddr) -> bool {
+    match (route.network, ip) {
+        (IpAddr::V4(network), IpAddr::V4(ip)) => {
+            let mask = u32::MAX.checked_shl(32_u32.saturating_sub(route.prefix)).unwrap_or(0);
+            u32::from(network) & mask == u32::from(ip) & mask
+        }
+        (IpAddr::V6(network), IpAddr::V6(ip)) => {
+            let mask = u128::MAX.checked_shl(128_u32.saturating_sub(route.prefix)).unwrap_or(0);
+            u128::from(network) & mask == u128::from(ip) & mask
+        }
+        _ => false,
+    }
+}
+
+/// SSID of the active connection from `nmcli -t -f active,ssid dev wifi`
+pub fn parse_nmcli_ssid(output: &str) -> Option<String> {
+    output
+        .lines()
+        .find_map(|line| line.strip_prefix("yes:"))
+        .filter(|ssid| !ssid.is_empty())
+        .map(|ssid| ssid.replace("\\:", ":"))
+}
+
+fn detect_ssid(interface: &str) -> String {
+    let run = |cmd: &str, args: &[&str]| {
+        std::process::Command::new(cmd)
+            .args(args)
+            .output()
+            .ok()
+            .filter(|o| o.status.success())
+            .map(|o| String::from_utf8_lossy(&o.stdout).to_string())
+    };
+    run("iwgetid", &["-r", interface])
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .or_else(|| run("nmcli", &["-t", "-f", "active,ssid", "dev", "wifi"]).and_then(|o| parse_nmcli_ssid(&o)))
+        .unwrap_or_default()
+}
+
+/// Re-detect the network every `interval`, publishing only changes
+pub fn spawn_watcher(interval: std::time::Duration) -> tokio::sync::watch::Receiver<NetworkState> {
+    let (tx, rx) = tokio::sync::watch::channel(NetworkState::default());
+    tokio::spawn(async move {
+        loop {
+            let Ok(network) = tokio::task::spawn_blocking(NetworkState::detect).await else {
+                break;
+            };
+            tx.send_if_modified(|current| {
+                let changed = *current != network;
+                *current = network;
+                changed
+            });
+            if tx.is_closed() {
+                break;
+            }
+            tokio::time::sleep(interval).await;
+        }
+    });
+    rx
+}
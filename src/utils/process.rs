@@ -1,5 +1,17 @@
 //! Process information utilities
 
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use super::text;
+use crate::models::Connection;
+
+/// Process fields filled from /proc rather than reported by the daemon
+pub const ENRICHED_PATH: &str = "path";
+pub const ENRICHED_ARGS: &str = "args";
+pub const ENRICHED_CWD: &str = "cwd";
+
 /// Get the basename of a path
 pub fn basename(path: &str) -> &str {
     path.rsplit('/').next().unwrap_or(path)
@@ -7,18 +19,20 @@ pub fn basename(path: &str) -> &str {
 
 /// Truncate a path to fit display, keeping the basename
 pub fn truncate_path(path: &str, max_len: usize) -> String {
-    if path.len() <= max_len {
+    if text::width(path) <= max_len {
         return path.to_string();
     }
 
     let base = basename(path);
-    if base.len() >= max_len {
-        return format!("...{}", &base[base.len().saturating_sub(max_len - 3)..]);
+    let base_width = text::width(base);
+    if base_width >= max_len {
+        return text::elide_start(base, max_len);
     }
 
-    let remaining = max_len - base.len() - 4; // -4 for ".../
-    if remaining > 0 && path.len() > base.len() + 1 {
-        let prefix = &path[..remaining.min(path.len() - base.len() - 1)];
+    let remaining = max_len - base_width - 4; // -4 for ".../
+    let dir = &path[..path.len() - base.len()];
+    if remaining > 0 && !dir.is_empty() {
+        let prefix = text::truncate(dir.trim_end_matches('/'), remaining);
         format!("{}.../{}", prefix, base)
     } else {
         format!(".../{}", base)
@@ -42,3 +56,130 @@ pub fn uid_to_name(uid: u32) -> String {
         _ => uid.to_string(),
     }
 }
+
+/// What /proc tells about a running process
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProcInfo {
+    pub exe: Option<String>,
+    pub args: Vec<String>,
+    pub cwd: Option<String>,
+}
+
+/// Read a process from a proc filesystem mounted at `root`, None once it is gone
+pub fn read_proc(root: &Path, pid: u32) -> Option<ProcInfo> {
+    let dir = root.join(pid.to_string());
+    let link = |name: &str| {
+        std::fs::read_link(dir.join(name))
+            .ok()
+            .map(|target| target.to_string_lossy().trim_end_matches(" (deleted)").to_string())
+    };
+    let cmdline = std::fs::read(dir.join("cmdline")).ok()?;
+    Some(ProcInfo {
+        exe: link("exe"),
+        args: cmdline
+            .split(|&b| b == 0)
+            .filter(|arg| !arg.is_empty())
+            .map(|arg| String::from_utf8_lossy(arg).into_owned())
+            .collect(),
+        cwd: link("cwd"),
+    })
+}
+
+/// When a process started, in clock ticks since boot, None once it is gone.
+/// Tells a process apart from a later one given the same PID
+pub fn start_time(root: &Path, pid: u32) -> Option<u64> {
+    let stat = std::fs::read_to_string(root.join(pid.to_string()).join("stat")).ok()?;
+    // The name in parentheses may hold spaces and parentheses itself,
+    // fields after it start with the state, the third one
+    let (_, fields) = stat.rsplit_once(')')?;
+    fields.split_whitespace().nth(19)?.parse().ok()
+}
+
+/// Process details read from /proc, shared by everything filling in events
+///
+/// A busy process connects many times: its details are read once per
+/// process start rather than per event. Entries are checked against the
+/// start time so a reused PID never gets another process' details, and
+/// dropped once the process is gone or after `ttl`.
+pub struct ProcCache {
+    root: PathBuf,
+    ttl: Duration,
+    entries: HashMap<(u32, u64), (ProcInfo, Instant)>,
+    last_sweep: Instant,
+}
+
+impl ProcCache {
+    pub fn new(root: &Path, ttl: Duration) -> Self {
+        Self {
+            root: root.to_path_buf(),
+            ttl,
+            entries: HashMap::new(),
+            last_sweep: Instant::now(),
+        }
+    }
+
+    /// Details of a running process, None once it is gone
+    pub fn get(&mut self, pid: u32, now: Instant) -> Option<ProcInfo> {
+        if now.duration_since(self.last_sweep) >= self.ttl {
+            let ttl = self.ttl;
+            self.entries.retain(|_, (_, read)| now.duration_since(*read) < ttl);
+            self.last_sweep = now;
+        }
+        let Some(start) = start_time(&self.root, pid) else {
+            self.entries.retain(|(cached, _), _| *cached != pid);
+            return None;
+        };
+        if let Some((info, read)) = self.entries.get(&(pid, start)) {
+            if now.duration_since(*read) < self.ttl {
+                return Some(info.clone());
+            }
+        }
+        // Whatever ran under this PID before has exited
+        self.entries.retain(|(cached, _), _| *cached != pid);
+        let info = read_proc(&self.root, pid)?;
+        self.entries.insert((pid, start), (info.clone(), now));
+        Some(info)
+    }
+
+    /// Fill the process fields the daemon left empty
+    ///
+    /// The process may have exited or its PID been reused since the daemon
+    /// saw it: nothing is filled when the executable differs from the reported
+    /// one. Filled fields are listed in `enriched`.
+    pub fn enrich(&mut self, connection: &mut Connection, now: Instant) {
+        let missing = connection.process_path.is_empty()
+            || connection.process_args.is_empty()
+            || connection.process_cwd.is_empty();
+        if connection.process_id == 0 || !missing {
+            return;
+        }
+        if let Some(info) = self.get(connection.process_id, now) {
+            fill_from_proc(info, connection);
+        }
+    }
+}
+
+fn fill_from_proc(info: ProcInfo, connection: &mut Connection) {
+    if let Some(exe) = &info.exe {
+        if !connection.process_path.is_empty() && *exe != connection.process_path {
+            return;
+        }
+    }
+
+    if connection.process_path.is_empty() {
+        if let Some(exe) = info.exe {
+            connection.process_path = exe;
+            connection.enriched.push(ENRICHED_PATH.to_string());
+        }
+    }
+    if connection.process_args.is_empty() && !info.args.is_empty() {
+        connection.process_args = info.args;
+        connection.enriched.push(ENRICHED_ARGS.to_string());
+    }
+    if connection.process_cwd.is_empty() {
+        if let Some(cwd) = info.cwd {
+            connection.process_cwd = cwd;
+            connection.enriched.push(ENRICHED_CWD.to_string());
+        }
+    }
+}
@@ -0,0 +1,121 @@
+//! Text handling for the terminal
+//!
+//! Hostnames, paths and rule names may hold any UTF-8. Byte offsets can't
+//! cut them anywhere: truncation counts display columns and stops between
+//! grapheme clusters, and editing cursors are byte offsets that only ever
+//! sit between graphemes.
+
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+/// Columns the text takes on screen
+pub fn width(text: &str) -> usize {
+    text.width()
+}
+
+/// Longest start of `text` fitting in `max_width` columns
+pub fn truncate(text: &str, max_width: usize) -> &str {
+    let mut used = 0;
+    for (i, grapheme) in text.grapheme_indices(true) {
+        used += grapheme.width();
+        if used > max_width {
+            return &text[..i];
+        }
+    }
+    text
+}
+
+/// Shortest end of `text` that leaves out at least `columns` columns
+pub fn skip_width(text: &str, columns: usize) -> &str {
+    let mut skipped = 0;
+    for (i, grapheme) in text.grapheme_indices(true) {
+        if skipped >= columns {
+            return &text[i..];
+        }
+        skipped += grapheme.width();
+    }
+    ""
+}
+
+/// Truncated to `max_width` columns, ending in "..." when cut
+pub fn elide(text: &str, max_width: usize) -> String {
+    if width(text) <= max_width {
+        return text.to_string();
+    }
+    format!("{}...", truncate(text, max_width.saturating_sub(3)))
+}
+
+/// Truncated to `max_width` columns from the start, beginning with "..."
+/// when cut, for paths whose end says most
+pub fn elide_start(text: &str, max_width: usize) -> String {
+    if width(text) <= max_width {
+        return text.to_string();
+    }
+    let keep = max_width.saturating_sub(3);
+    let mut start = text.len();
+    let mut used = 0;
+    for (i, grapheme) in text.grapheme_indices(true).rev() {
+        used += grapheme.width();
+        if used > keep {
+            break;
+        }
+        start = i;
+    }
+    format!("...{}", &text[start..])
+}
+
+/// Cursor moved back one grapheme
+pub fn prev_boundary(text: &str, cursor: usize) -> usize {
+    text[..cursor.min(text.len())]
+        .grapheme_indices(true)
+        .next_back()
+        .map(|(i, _)| i)
+        .unwrap_or(0)
+}
+
+/// Cursor moved forward one grapheme
+pub fn next_boundary(text: &str, cursor: usize) -> usize {
+    let cursor = cursor.min(text.len());
+    text[cursor..]
+        .graphemes(true)
+        .next()
+        .map(|grapheme| cursor + grapheme.len())
+        .unwrap_or(text.len())
+}
+
+/// Screen column of the cursor, from the start of the text
+pub fn column(text: &str, cursor: usize) -> usize {
+    width(&text[..cursor.min(text.len())])
+}
+
+/// Insert at the cursor and move past what was typed
+pub fn insert(text: &mut String, cursor: &mut usize, c: char) {
+    text.insert(*cursor, c);
+    *cursor += c.len_utf8();
+}
+
+/// Remove the grapheme before the cursor, returns whether there was one
+pub fn backspace(text: &mut String, cursor: &mut usize) -> bool {
+    if *cursor == 0 {
+        return false;
+    }
+    let start = prev_boundary(text, *cursor);
+    text.replace_range(start..*cursor, "");
+    *cursor = start;
+    true
+}
+
+/// Remove the grapheme after the cursor, returns whether there was one
+pub fn delete(text: &mut String, cursor: usize) -> bool {
+    if cursor >= text.len() {
+        return false;
+    }
+    let end = next_boundary(text, cursor);
+    text.replace_range(cursor..end, "");
+    true
+}
+
+/// Padded with spaces to `columns` columns, for aligned lists
+pub fn pad(text: &str, columns: usize) -> String {
+    format!("{}{}", text, " ".repeat(columns.saturating_sub(width(text))))
+}
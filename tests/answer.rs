@@ -0,0 +1,89 @@
+//! One-shot answers from the command line over the control socket
+
+mod common;
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::sync::{broadcast, mpsc, oneshot};
+
+use opensnitch_tui::app::answer::{self, AnswerRequest, ControlRequest};
+use opensnitch_tui::app::mirror;
+use opensnitch_tui::app::state::{AppMessage, PendingPrompt};
+use opensnitch_tui::app::AppState;
+use opensnitch_tui::db::Database;
+use opensnitch_tui::models::node::ClientConfig;
+use opensnitch_tui::models::{Connection, RuleAction, RuleDuration};
+
+use common::connection;
+
+fn request(pid: Option<u32>, process: Option<&str>, duration: RuleDuration) -> ControlRequest {
+    ControlRequest::Answer(AnswerRequest {
+        pid,
+        process: process.map(str::to_string),
+        action: RuleAction::Deny,
+        duration,
+    })
+}
+
+#[test]
+fn command_line_values_are_parsed_strictly() {
+    assert_eq!(answer::parse_action("Deny"), Ok(RuleAction::Deny));
+    assert!(answer::parse_action("dney").is_err());
+    assert_eq!(answer::parse_duration("1h"), Ok(RuleDuration::OneHour));
+    assert_eq!(answer::parse_duration("until restart"), Ok(RuleDuration::UntilRestart));
+    assert!(answer::parse_duration("2h").is_err());
+}
+
+#[tokio::test]
+async fn answers_the_pending_prompt_or_adds_a_rule() {
+    let path = std::env::temp_dir().join(format!("opensnitch-tui-answer-{}.sock", std::process::id()));
+    let path = path.to_string_lossy().to_string();
+
+    let (ui_update_tx, _) = broadcast::channel(100);
+    let state = Arc::new(AppState::new(Database::open(":memory:").unwrap(), ui_update_tx));
+    state.nodes.write().await.add_node("node-a", ClientConfig::default());
+    let (response_tx, response_rx) = oneshot::channel();
+    state.pending_prompts.write().await.push_back(PendingPrompt {
+        connection: Connection { process_id: 1234, ..connection("/usr/bin/curl", "example.org", 443) },
+        node_addr: "node-a".to_string(),
+        id: None,
+        response_tx,
+    });
+
+    let (state_tx, mut state_rx) = mpsc::channel(10);
+    let server = tokio::spawn(mirror::serve(path.clone(), state.clone(), state_tx));
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    let reply = answer::send(&path, request(Some(1234), None, RuleDuration::OneHour)).await.unwrap();
+    assert!(reply.ok, "{}", reply.message);
+    let rule = response_rx.await.unwrap();
+    assert_eq!((rule.action, rule.duration), (RuleAction::Deny, RuleDuration::OneHour));
+    assert_eq!(rule.name, "curl-example");
+    let operands: Vec<&str> = rule.operator.list.iter().map(|op| op.operand.as_str()).collect();
+    assert_eq!(operands, ["process.path", "dest.host"]);
+    assert!(state.pending_prompts.read().await.is_empty());
+
+    // Nothing left to answer, a rule for the process is added instead
+    let reply = answer::send(&path, request(None, Some("/usr/bin/curl"), RuleDuration::Always)).await.unwrap();
+    assert!(reply.ok, "{}", reply.message);
+    match state_rx.recv().await {
+        Some(AppMessage::RuleAdded { node_addr, rule }) => {
+            assert_eq!(node_addr, "node-a");
+            assert_eq!((rule.name.as_str(), rule.action), ("deny-curl", RuleAction::Deny));
+        }
+        other => panic!("expected a new rule, got {:?}", other.is_some()),
+    }
+
+    // The executable of a pid is only known for processes of this host
+    let reply = answer::send(&path, request(Some(std::process::id()), None, RuleDuration::Always)).await.unwrap();
+    assert!(!reply.ok);
+    assert!(reply.message.contains("another host"), "{}", reply.message);
+
+    // A rule can't last for one connection
+    let reply = answer::send(&path, request(None, Some("/usr/bin/curl"), RuleDuration::Once)).await.unwrap();
+    assert!(!reply.ok);
+
+    server.abort();
+    let _ = std::fs::remove_file(&path);
+}
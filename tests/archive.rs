@@ -0,0 +1,98 @@
+//! Archived snapshots of nodes that went offline
+
+use std::sync::Arc;
+
+use tokio::sync::{broadcast, mpsc};
+
+use opensnitch_tui::app::state::{run_state_manager, AppMessage};
+use opensnitch_tui::app::suggestions::DenialTracker;
+use opensnitch_tui::app::AppState;
+use opensnitch_tui::db::Database;
+use opensnitch_tui::models::node::{ClientConfig, NodeStatus};
+use opensnitch_tui::models::{NodeManager, Operator, Rule, RuleAction, RuleDuration};
+
+fn config(name: &str) -> ClientConfig {
+    ClientConfig {
+        name: name.to_string(),
+        version: "1.6.6".to_string(),
+        rules: vec![Rule::new(
+            "allow-curl",
+            RuleAction::Allow,
+            RuleDuration::Always,
+            Operator::simple("process.path", "/usr/bin/curl"),
+        )],
+        ..Default::default()
+    }
+}
+
+#[test]
+fn disconnected_nodes_stay_browsable() {
+    let mut nodes = NodeManager::new();
+    nodes.add_node("node-a", config("a"));
+    nodes.remove_node("node-a");
+
+    // Nothing else is live, so the archive stays in view
+    assert_eq!(nodes.active_addr(), Some("node-a"));
+    assert!(nodes.active_archived());
+    let node = nodes.get_node("node-a").unwrap();
+    assert_eq!(node.status, NodeStatus::Disconnected);
+    assert_eq!(node.rules.len(), 1);
+
+    // Reconnecting makes it live again
+    nodes.add_node("node-a", config("a"));
+    assert!(!nodes.active_archived());
+
+    nodes.add_node("node-b", config("b"));
+    nodes.remove_node("node-a");
+    assert_eq!(nodes.active_addr(), Some("node-b"));
+
+    assert!(!nodes.forget_archived("node-b"));
+    assert!(nodes.forget_archived("node-a"));
+    assert_eq!(nodes.node_count(), 1);
+}
+
+#[tokio::test]
+async fn archives_survive_a_restart() {
+    let path = std::env::temp_dir().join(format!("opensnitch-tui-archive-{}.db", std::process::id()));
+    let path = path.to_string_lossy().to_string();
+
+    {
+        let (ui_update_tx, _) = broadcast::channel(100);
+        let state = Arc::new(AppState::new(Database::open(&path).unwrap(), ui_update_tx.clone()));
+        let (tx, rx) = mpsc::channel(10);
+        let manager = tokio::spawn(run_state_manager(
+            state.clone(),
+            rx,
+            ui_update_tx,
+            DenialTracker::new(0, std::time::Duration::from_secs(60)),
+        ));
+        tx.send(AppMessage::NodeConnected {
+            addr: "node-a".to_string(),
+            config: config("a"),
+            auth: Default::default(),
+            listener: None,
+        })
+        .await
+        .unwrap();
+        tx.send(AppMessage::NodeDisconnected { addr: "node-a".to_string() }).await.unwrap();
+        drop(tx);
+        manager.await.unwrap();
+    }
+
+    let (ui_update_tx, _) = broadcast::channel(100);
+    let state = AppState::new(Database::open(&path).unwrap(), ui_update_tx);
+    state.restore_archived_nodes().await;
+    let nodes = state.nodes.read().await;
+    let node = nodes.get_node("node-a").unwrap();
+    assert!(node.archived);
+    assert_eq!(node.name, "a");
+    assert_eq!(node.rules[0].name, "allow-curl");
+    drop(nodes);
+
+    state.forget_archived_node("node-a").await;
+    assert!(state.db.select_archived_nodes().unwrap().is_empty());
+
+    for suffix in ["", "-wal", "-shm"] {
+        let _ = std::fs::remove_file(format!("{}{}", path, suffix));
+    }
+}
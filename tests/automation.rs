@@ -0,0 +1,114 @@
+//! Automations acting on alerts as they arrive
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use tokio::sync::{broadcast, mpsc};
+
+use opensnitch_tui::app::state::{run_state_manager, AppMessage};
+use opensnitch_tui::app::suggestions::DenialTracker;
+use opensnitch_tui::app::AppState;
+use opensnitch_tui::db::Database;
+use opensnitch_tui::models::automation::deny_rule;
+use opensnitch_tui::models::connection::Process;
+use opensnitch_tui::models::node::ClientConfig;
+use opensnitch_tui::models::{
+    Alert, AlertAutomation, AlertData, AlertPriority, AlertType, AlertWhat, Automations, RuleAction,
+};
+use opensnitch_tui::ui::dialogs::automations::{AutomationsDialog, AutomationsResult};
+
+fn kernel_alert(path: &str) -> Alert {
+    let process = Process { path: path.to_string(), comm: "miner".to_string(), pid: 42, ..Default::default() };
+    let mut alert = Alert::new(1, AlertType::Warning, AlertPriority::High, AlertWhat::KernelEvent, Some(AlertData::Process(process)));
+    alert.node = "node-a".to_string();
+    alert
+}
+
+#[tokio::test]
+async fn matching_alerts_deny_their_binary_and_are_acknowledged() {
+    let (ui_update_tx, _) = broadcast::channel(100);
+    let automations = Automations {
+        automations: vec![AlertAutomation {
+            name: "miners".to_string(),
+            what: Some(AlertWhat::KernelEvent),
+            contains: "/tmp/".to_string(),
+            deny_binary: true,
+            ..Default::default()
+        }],
+    };
+    let state = Arc::new(
+        AppState::new(Database::open(":memory:").unwrap(), ui_update_tx.clone()).with_automations(automations),
+    );
+    state.nodes.write().await.add_node("node-a", ClientConfig::default());
+    let (tx, rx) = mpsc::channel(10);
+    let manager = tokio::spawn(run_state_manager(
+        state.clone(),
+        rx,
+        ui_update_tx,
+        DenialTracker::new(0, Duration::from_secs(60)),
+    ));
+    let (daemon_tx, mut daemon_rx) = mpsc::channel(10);
+    tx.send(AppMessage::NotificationChannelOpened { node_addr: "node-a".to_string(), tx: daemon_tx }).await.unwrap();
+
+    // The same binary twice gets one rule, other binaries are left alone
+    for path in ["/tmp/.x/miner", "/usr/bin/update", "/tmp/.x/miner"] {
+        tx.send(AppMessage::AlertReceived { alert: kernel_alert(path) }).await.unwrap();
+    }
+    let name = deny_rule("/tmp/.x/miner").name;
+    let sent = daemon_rx.recv().await.unwrap();
+    assert_eq!(sent.rules[0].name, name);
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert!(daemon_rx.try_recv().is_err());
+
+    let nodes = state.nodes.read().await;
+    let rules = &nodes.get_node("node-a").unwrap().rules;
+    assert_eq!(rules.len(), 1);
+    assert_eq!((rules[0].action, rules[0].operator.data.as_str()), (RuleAction::Deny, "/tmp/.x/miner"));
+    drop(nodes);
+
+    let acknowledged: Vec<bool> = state.alerts.read().await.iter().map(|a| a.acknowledged).collect();
+    assert_eq!(acknowledged, [true, false, true]);
+
+    let log = state.automation_log.read().await;
+    let actions: Vec<_> = log.iter().map(|run| run.actions.join(", ")).collect();
+    assert_eq!(
+        actions,
+        [format!("rule {} already there, acknowledged", name), format!("added rule {}, acknowledged", name)]
+    );
+    drop(log);
+    manager.abort();
+}
+
+#[test]
+fn binaries_of_the_same_name_get_their_own_deny_rule() {
+    let name = deny_rule("/tmp/.x/miner").name;
+    assert!(name.starts_with("auto-deny-miner-"), "{}", name);
+    assert_ne!(name, deny_rule("/tmp/.y/miner").name);
+    assert_eq!(name, deny_rule("/tmp/.x/miner").name);
+}
+
+#[test]
+fn automations_are_set_up_in_a_dialog() {
+    let key = |code| KeyEvent::new(code, KeyModifiers::NONE);
+    let mut dialog = AutomationsDialog::new(Automations::default(), Vec::new());
+    dialog.handle_key(key(KeyCode::Char('n')));
+    for c in "kernel".chars() {
+        dialog.handle_key(key(KeyCode::Char(c)));
+    }
+    // Kind: back from Any wraps to the last one
+    dialog.handle_key(key(KeyCode::Tab));
+    dialog.handle_key(key(KeyCode::Left));
+    for code in [KeyCode::Tab, KeyCode::Tab, KeyCode::Char(' '), KeyCode::Enter, KeyCode::Esc] {
+        if let Some(result) = dialog.handle_key(key(code)) {
+            let AutomationsResult::Save(automations) = result else {
+                panic!("expected the automation to be saved");
+            };
+            let automation = &automations.automations[0];
+            assert_eq!(automation.name, "kernel");
+            assert_eq!(automation.summary(), "Kernel Event alerts → deny the binary, acknowledge");
+            return;
+        }
+    }
+    panic!("expected the dialog to close");
+}
@@ -0,0 +1,108 @@
+//! Blocklists downloaded for a lists.domains deny rule
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+use opensnitch_tui::app::blocklists::{self, blocklist_rule, list_dirs, ListFile, KNOWN_LISTS, RULE_NAME};
+use opensnitch_tui::models::{Operator, OperatorType, Rule, RuleAction, RuleDuration};
+use opensnitch_tui::ui::dialogs::blocklists::{BlocklistsDialog, BlocklistsResult};
+
+fn lists_rule(name: &str, dir: &str) -> Rule {
+    Rule::new(
+        name,
+        RuleAction::Deny,
+        RuleDuration::Always,
+        Operator::new(OperatorType::Lists, "lists.domains", dir),
+    )
+}
+
+#[test]
+fn list_dirs_come_from_lists_rules() {
+    let nested = Rule::new(
+        "nested",
+        RuleAction::Deny,
+        RuleDuration::Always,
+        Operator::list(vec![
+            Operator::simple("process.path", "/usr/bin/firefox"),
+            Operator::new(OperatorType::Lists, "lists.domains", "/srv/lists/ads"),
+        ]),
+    );
+    let rules = vec![
+        lists_rule("ads", "/srv/lists/ads"),
+        Rule::new("ips", RuleAction::Deny, RuleDuration::Always, Operator::new(OperatorType::Lists, "lists.ips", "/srv/ips")),
+        nested,
+        lists_rule("malware", "/srv/lists/malware"),
+    ];
+    assert_eq!(list_dirs(&rules), ["/srv/lists/ads", "/srv/lists/malware"]);
+}
+
+#[test]
+fn the_deny_rule_is_created_or_pointed_at_the_directory() {
+    let rule = blocklist_rule("/srv/lists", None).unwrap();
+    assert_eq!(rule.name, RULE_NAME);
+    assert_eq!(rule.action, RuleAction::Deny);
+    assert_eq!(rule.operator.op_type, OperatorType::Lists);
+    assert_eq!(rule.operator.operand, "lists.domains");
+    assert_eq!(rule.operator.data, "/srv/lists");
+
+    // Already in place
+    assert!(blocklist_rule("/srv/lists", Some(&rule)).is_none());
+
+    let mut disabled = rule.clone().with_description("mine");
+    disabled.enabled = false;
+    let updated = blocklist_rule("/srv/other", Some(&disabled)).unwrap();
+    assert!(updated.enabled);
+    assert_eq!(updated.operator.data, "/srv/other");
+    assert_eq!(updated.description, "mine");
+}
+
+#[tokio::test]
+async fn lists_are_written_and_failures_keep_the_old_file() {
+    let dir = std::env::temp_dir().join(format!("opensnitch-tui-blocklists-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    let dir_str = dir.to_string_lossy().into_owned();
+
+    let mut good = KNOWN_LISTS[0].task(&dir_str);
+    good.command = "printf '# ads\\n0.0.0.0 ads.example\\n0.0.0.0 track.example\\n'".to_string();
+    let mut bad = KNOWN_LISTS[1].task(&dir_str);
+    bad.command = "exit 3".to_string();
+    assert!(good.path.ends_with(KNOWN_LISTS[0].file));
+
+    let report = blocklists::download(vec![good, bad]).await;
+    assert!(report.written());
+    assert!(report.lists[0].1.as_ref().unwrap().starts_with("2 entries"));
+    assert!(report.lists[1].1.is_err());
+    let file = ListFile::read(&KNOWN_LISTS[0].path(&dir_str)).unwrap();
+    assert_eq!(file.entries, 2);
+    assert!(ListFile::read(&KNOWN_LISTS[1].path(&dir_str)).is_none());
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn dialog_picks_the_configured_directory_and_downloaded_lists() {
+    let dir = std::env::temp_dir().join(format!("opensnitch-tui-blocklists-dialog-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let dir_str = dir.to_string_lossy().into_owned();
+    std::fs::write(KNOWN_LISTS[1].path(&dir_str), "0.0.0.0 ads.example\n").unwrap();
+
+    let key = |code| KeyEvent::new(code, KeyModifiers::NONE);
+    let mut dialog = BlocklistsDialog::new();
+    dialog.set_dirs(vec![dir_str.clone()]);
+    assert_eq!(dialog.dir, dir_str);
+
+    // The list already there is picked, the first one is added
+    dialog.handle_key(key(KeyCode::Char(' ')));
+    let Some(BlocklistsResult::Download { dir: target, lists }) = dialog.handle_key(key(KeyCode::Enter)) else {
+        panic!("no download");
+    };
+    assert_eq!(target, dir_str);
+    let names: Vec<_> = lists.iter().map(|list| list.name).collect();
+    assert_eq!(names, [KNOWN_LISTS[0].name, KNOWN_LISTS[1].name]);
+
+    // Waiting for the download, only closing works
+    assert!(dialog.handle_key(key(KeyCode::Enter)).is_none());
+    dialog.set_result(Err("remote".to_string()));
+    assert!(matches!(dialog.handle_key(key(KeyCode::Esc)), Some(BlocklistsResult::Close)));
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
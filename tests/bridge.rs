@@ -0,0 +1,103 @@
+//! Event stream for web dashboards
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::sync::broadcast;
+
+use opensnitch_tui::app::{bridge, AppState};
+use opensnitch_tui::db::Database;
+use opensnitch_tui::models::{Alert, AlertData, AlertPriority, AlertType, AlertWhat, Connection, Event};
+
+const TOKEN: &str = "dashboard-token";
+
+async fn connect(port: u16, path: &str, origin: Option<&str>) -> (TcpStream, String) {
+    let mut stream = TcpStream::connect(("127.0.0.1", port)).await.unwrap();
+    let origin = origin.map(|origin| format!("Origin: {}\r\n", origin)).unwrap_or_default();
+    let request = format!(
+        "GET {} HTTP/1.1\r\nHost: localhost\r\nUpgrade: websocket\r\nConnection: Upgrade\r\n\
+         Sec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\nSec-WebSocket-Version: 13\r\n{}\r\n",
+        path, origin
+    );
+    stream.write_all(request.as_bytes()).await.unwrap();
+
+    let mut response = Vec::new();
+    let mut byte = [0u8; 1];
+    while !response.ends_with(b"\r\n\r\n") {
+        stream.read_exact(&mut byte).await.unwrap();
+        response.push(byte[0]);
+    }
+    (stream, String::from_utf8(response).unwrap())
+}
+
+/// Opcode and payload of the next unmasked frame
+async fn frame(stream: &mut TcpStream) -> (u8, String) {
+    let mut head = [0u8; 2];
+    stream.read_exact(&mut head).await.unwrap();
+    let len = match head[1] {
+        126 => stream.read_u16().await.unwrap() as usize,
+        len => len as usize,
+    };
+    let mut payload = vec![0u8; len];
+    stream.read_exact(&mut payload).await.unwrap();
+    (head[0] & 0x0F, String::from_utf8(payload).unwrap())
+}
+
+#[tokio::test]
+async fn sanitized_events_are_streamed_to_websocket_clients() {
+    let (ui_update_tx, _) = broadcast::channel(100);
+    let state = Arc::new(AppState::new(Database::open(":memory:").unwrap(), ui_update_tx));
+    assert!(bridge::bind("0.0.0.0:0").await.is_err());
+    let listener = bridge::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    tokio::spawn(bridge::serve(listener, state.clone(), vec!["https://dash.example".to_string()], TOKEN.to_string()));
+    let path = format!("/events?token={}", TOKEN);
+
+    // Pages from elsewhere can't listen in
+    let (_, response) = connect(port, &path, Some("https://evil.example")).await;
+    assert!(response.starts_with("HTTP/1.1 403"));
+    let (_, response) = connect(port, &path, Some("https://dash.example")).await;
+    assert!(response.starts_with("HTTP/1.1 101"));
+
+    // Nor can other local users without the token
+    let (_, response) = connect(port, "/events", None).await;
+    assert!(response.starts_with("HTTP/1.1 401"));
+    let (_, response) = connect(port, "/events?token=dashboard", None).await;
+    assert!(response.starts_with("HTTP/1.1 401"));
+
+    let (mut stream, response) = connect(port, &path, Some("http://localhost:8123")).await;
+    assert!(response.starts_with("HTTP/1.1 101"));
+    // Accept key of the RFC 6455 example
+    assert!(response.contains("Sec-WebSocket-Accept: s3pPLMBiTxaQ9kYGzzhZRbK+xOo=\r\n"));
+
+    let connection = Connection {
+        protocol: "tcp".to_string(),
+        dst_host: "example.org".to_string(),
+        dst_port: 443,
+        process_path: "/usr/bin/curl".to_string(),
+        process_args: vec!["curl".to_string(), "-u".to_string(), "me:secret".to_string()],
+        process_env: HashMap::from([("TOKEN".to_string(), "secret".to_string())]),
+        ..Default::default()
+    };
+    state.add_connection(Event::new(connection, None)).await;
+    let alert = Alert::new(7, AlertType::Warning, AlertPriority::High, AlertWhat::Generic, Some(AlertData::Text("disk full".into())));
+    state.add_alert(alert).await;
+
+    let (opcode, text) = frame(&mut stream).await;
+    assert_eq!(opcode, 0x1);
+    assert!(!text.contains("secret"));
+    let message: bridge::StreamEvent = serde_json::from_str(&text).unwrap();
+    let bridge::StreamEvent::Connection(event) = message else { panic!("not a connection: {}", text) };
+    assert_eq!((event.process_path.as_str(), event.dst_port), ("/usr/bin/curl", 443));
+
+    let (_, text) = frame(&mut stream).await;
+    assert!(text.starts_with(r#"{"type":"alert","id":7"#), "{}", text);
+
+    // Masked ping, then close
+    stream.write_all(&[0x89, 0x82, 1, 2, 3, 4, b'h' ^ 1, b'i' ^ 2]).await.unwrap();
+    assert_eq!(frame(&mut stream).await, (0xA, "hi".to_string()));
+    stream.write_all(&[0x88, 0x80, 1, 2, 3, 4]).await.unwrap();
+    assert_eq!(frame(&mut stream).await.0, 0x8);
+}
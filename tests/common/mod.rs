@@ -0,0 +1,33 @@
+//! Helpers shared by the integration tests
+//!
+//! Each test file only uses some of them.
+#![allow(dead_code)]
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+use opensnitch_tui::models::{Connection, Event};
+
+pub fn key(code: KeyCode) -> KeyEvent {
+    KeyEvent::new(code, KeyModifiers::NONE)
+}
+
+pub fn ctrl(c: char) -> KeyEvent {
+    KeyEvent::new(KeyCode::Char(c), KeyModifiers::CONTROL)
+}
+
+/// A TCP connection of `process` to `host`
+pub fn connection(process: &str, host: &str, port: u32) -> Connection {
+    Connection {
+        protocol: "tcp".to_string(),
+        dst_ip: "93.184.216.34".to_string(),
+        dst_host: host.to_string(),
+        dst_port: port,
+        process_path: process.to_string(),
+        ..Default::default()
+    }
+}
+
+/// An event of [`connection`] no rule matched
+pub fn event(process: &str, host: &str, port: u32) -> Event {
+    Event::new(connection(process, host, port), None)
+}
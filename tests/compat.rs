@@ -0,0 +1,118 @@
+//! Daemon version feature matrix and the UI gating built on it
+
+mod common;
+
+use crossterm::event::KeyCode;
+
+use opensnitch_tui::models::compat::unsupported_features;
+use opensnitch_tui::models::{
+    DaemonVersion, Expression, Feature, FwChain, FwChains, FwRule, Node, Statement, SysFirewall,
+};
+use opensnitch_tui::ui::dialogs::fw_rule::{FwRuleEditorDialog, FwRuleEditorResult};
+use opensnitch_tui::ui::dialogs::rule_editor::{RuleEditorDialog, RuleEditorResult};
+use opensnitch_tui::ui::tabs::{firewall::FirewallTab, Tab};
+
+use common::{ctrl, key};
+
+#[test]
+fn parses_daemon_versions() {
+    assert_eq!(DaemonVersion::parse("1.6.5"), Some(DaemonVersion(1, 6, 5)));
+    assert_eq!(DaemonVersion::parse("v1.6.0-rc1"), Some(DaemonVersion(1, 6, 0)));
+    assert_eq!(DaemonVersion::parse("1.7.0.1"), Some(DaemonVersion(1, 7, 0)));
+    assert_eq!(DaemonVersion::parse("1.5"), Some(DaemonVersion(1, 5, 0)));
+    assert_eq!(DaemonVersion::parse(""), None);
+    assert_eq!(DaemonVersion::parse("unknown"), None);
+
+    let mut node = Node::new("unix:///tmp/osui.sock");
+    assert_eq!(node.daemon_version(), None);
+    node.version = "1.6.6".to_string();
+    assert_eq!(node.daemon_version(), Some(DaemonVersion(1, 6, 6)));
+}
+
+#[test]
+fn features_gate_on_minimum_version() {
+    let old = DaemonVersion::parse("1.5.2");
+    assert!(!Feature::ChecksumOperands.supported_by(old));
+    assert!(Feature::SystemFirewall.supported_by(old));
+    assert!(Feature::FwRateStatements.supported_by(DaemonVersion::parse("1.6.6")));
+    assert!(!Feature::FwRateStatements.supported_by(DaemonVersion::parse("1.6.5")));
+
+    // Unknown versions don't block anything
+    assert!(unsupported_features(None).is_empty());
+    assert_eq!(unsupported_features(DaemonVersion::parse("1.4.0")).len(), Feature::ALL.len());
+
+    assert_eq!(Feature::for_operand("process.hash.sha1"), Some(Feature::ChecksumOperands));
+    assert_eq!(Feature::for_operand("dest.host"), None);
+    assert_eq!(
+        Feature::ChecksumOperands.unsupported_message(DaemonVersion(1, 5, 2)),
+        "checksum operands: needs daemon ≥1.6.0 (node runs 1.5.2)"
+    );
+}
+
+fn checksum_rule_editor(version: &str) -> RuleEditorDialog {
+    let mut editor = RuleEditorDialog::new().with_daemon_version(DaemonVersion::parse(version));
+    editor.name = "curl-md5".to_string();
+    editor.data = "d41d8cd98f00b204e9800998ecf8427e".to_string();
+    // process.path -> process.command -> process.id -> process.hash.md5
+    for _ in 0..5 {
+        editor.handle_key(key(KeyCode::Down));
+    }
+    for _ in 0..3 {
+        editor.handle_key(key(KeyCode::Right));
+    }
+    editor
+}
+
+#[test]
+fn rule_editor_refuses_operands_the_node_cannot_honor() {
+    let mut editor = checksum_rule_editor("1.5.2");
+    assert!(editor.compat_warning().is_some());
+    assert!(editor.handle_key(ctrl('s')).is_none());
+
+    let mut editor = checksum_rule_editor("1.6.0");
+    assert!(editor.compat_warning().is_none());
+    match editor.handle_key(ctrl('s')) {
+        Some(RuleEditorResult::Save(rule)) => assert_eq!(rule.operator.operand, "process.hash.md5"),
+        _ => panic!("expected the rule to be saved"),
+    }
+}
+
+#[test]
+fn fw_rule_editor_refuses_rate_statements_on_old_daemons() {
+    let rule = FwRule::new("throttle", "accept").with_expressions(vec![Expression {
+        statement: Statement {
+            op: String::new(),
+            name: "limit".to_string(),
+            values: Vec::new(),
+        },
+    }]);
+
+    let mut editor = FwRuleEditorDialog::edit(&rule).with_daemon_version(DaemonVersion::parse("1.6.5"));
+    assert!(editor.handle_key(key(KeyCode::F(2))).is_none());
+
+    let mut editor = FwRuleEditorDialog::edit(&rule).with_daemon_version(DaemonVersion::parse("1.6.6"));
+    assert!(matches!(editor.handle_key(key(KeyCode::F(2))), Some(FwRuleEditorResult::Save(_))));
+}
+
+#[test]
+fn firewall_tab_is_read_only_on_old_daemons() {
+    let mut fw = SysFirewall::new();
+    fw.system_rules.push(FwChains {
+        rule: None,
+        chains: vec![FwChain::new("input", "filter", "input").with_rules(vec![FwRule::new("ssh", "accept")])],
+    });
+
+    let mut tab = FirewallTab::new();
+    tab.set_firewall(Some(fw), Some("node".to_string()));
+    tab.set_daemon_version(DaemonVersion::parse("1.4.0"));
+
+    assert!(tab.handle_key(key(KeyCode::F(2))).is_empty());
+    assert!(!tab.showing_dialog());
+    tab.handle_key(key(KeyCode::Tab));
+    assert!(tab.handle_key(key(KeyCode::Char(' '))).is_empty());
+    tab.handle_key(key(KeyCode::Char('e')));
+    assert!(!tab.showing_dialog());
+
+    tab.set_daemon_version(DaemonVersion::parse("1.6.0"));
+    assert_eq!(tab.handle_key(key(KeyCode::Char(' '))).len(), 1);
+}
@@ -0,0 +1,141 @@
+//! Following the daemon's log file
+
+use std::io::Write;
+use std::path::PathBuf;
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+use opensnitch_tui::models::Node;
+use opensnitch_tui::ui::tabs::daemon_log::{log_source, DaemonLogTab};
+use opensnitch_tui::ui::tabs::Tab;
+use opensnitch_tui::utils::daemon_log::{LogEntry, LogLevel, LogTail, DEFAULT_LOG_FILE};
+
+fn log_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("opensnitch-tui-log-{}-{}.log", name, std::process::id()))
+}
+
+fn append(path: &PathBuf, text: &str) {
+    let mut file = std::fs::OpenOptions::new().create(true).append(true).open(path).unwrap();
+    file.write_all(text.as_bytes()).unwrap();
+}
+
+fn messages(tail: &LogTail) -> Vec<&str> {
+    tail.entries().iter().map(|e| e.message.as_str()).collect()
+}
+
+#[test]
+fn lines_are_parsed_with_or_without_colors() {
+    let entry = LogEntry::parse("[2024-03-01 10:00:00]  WAR  Rule not loaded: bad regexp");
+    assert_eq!(entry.time, "2024-03-01 10:00:00");
+    assert_eq!(entry.level, Some(LogLevel::Warning));
+    assert_eq!(entry.message, "Rule not loaded: bad regexp");
+
+    let colored = LogEntry::parse("\u{1b}[2m[2024-03-01 10:00:01]\u{1b}[0m  \u{1b}[31m!!!\u{1b}[0m  netfilter queue gone\n");
+    assert_eq!(colored.time, "2024-03-01 10:00:01");
+    assert_eq!(colored.level, Some(LogLevel::Fatal));
+    assert_eq!(colored.message, "netfilter queue gone");
+
+    let bare = LogEntry::parse("\tgoroutine 1 [running]:");
+    assert_eq!(bare.time, "");
+    assert_eq!(bare.level, None);
+    assert_eq!(bare.message, "\tgoroutine 1 [running]:");
+
+    assert!(LogLevel::Debug < LogLevel::Info && LogLevel::Error < LogLevel::Fatal);
+    assert_eq!(LogLevel::Fatal.next(), LogLevel::Debug);
+}
+
+#[test]
+fn appended_lines_are_read_and_rotation_starts_over() {
+    let path = log_path("tail");
+    let _ = std::fs::remove_file(&path);
+    let mut tail = LogTail::new(&path);
+    assert!(tail.poll().is_err());
+
+    append(&path, "[t1]  INF  started\n[t2]  ERR  boom\npanic: oops\n[t3]  DBG  half");
+    assert!(tail.poll().unwrap());
+    assert_eq!(messages(&tail), ["started", "boom", "panic: oops"]);
+    // A line without a level belongs to the entry above
+    assert_eq!(tail.entries()[2].level, Some(LogLevel::Error));
+    assert!(!tail.poll().unwrap());
+
+    append(&path, " a line\n");
+    assert!(tail.poll().unwrap());
+    assert_eq!(messages(&tail).last(), Some(&"half a line"));
+
+    // Rotated: the new file is shorter than what was read
+    std::fs::write(&path, "[t4]  IMP  restarted\n").unwrap();
+    assert!(tail.poll().unwrap());
+    assert_eq!(messages(&tail).last(), Some(&"restarted"));
+    assert_eq!(tail.entries().len(), 5);
+
+    let _ = std::fs::remove_file(&path);
+}
+
+#[test]
+fn a_large_file_is_read_from_its_end() {
+    let path = log_path("large");
+    let line = format!("[t]  DBG  {}\n", "x".repeat(90));
+    std::fs::write(&path, line.repeat(4000) + "[t]  INF  last\n").unwrap();
+
+    let mut tail = LogTail::new(&path);
+    tail.poll().unwrap();
+    let entries = tail.entries();
+    assert!(entries.len() < 4000);
+    // No line cut in half where reading started
+    assert!(entries.iter().all(|e| e.level.is_some() && e.time == "t"));
+    assert_eq!(entries.back().map(|e| e.message.as_str()), Some("last"));
+
+    let _ = std::fs::remove_file(&path);
+}
+
+#[test]
+fn log_file_comes_from_the_local_node_config() {
+    assert_eq!(log_source(None), Ok(DEFAULT_LOG_FILE.to_string()));
+
+    let mut node = Node::new("unix:///tmp/osui.sock");
+    assert_eq!(log_source(Some(&node)), Ok(DEFAULT_LOG_FILE.to_string()));
+    node.config = r#"{"Server": {"Address": "unix:///tmp/osui.sock", "LogFile": "/var/log/snitch.log"}}"#.to_string();
+    assert_eq!(log_source(Some(&node)), Ok("/var/log/snitch.log".to_string()));
+    node.config = r#"{"Server": {"LogFile": "/dev/stdout"}}"#.to_string();
+    assert!(log_source(Some(&node)).unwrap_err().contains("journalctl"));
+
+    let remote = Node::new("192.0.2.7:50051");
+    assert!(log_source(Some(&remote)).unwrap_err().contains("another host"));
+}
+
+#[test]
+fn tab_filters_by_level_and_search() {
+    let path = log_path("tab");
+    std::fs::write(&path, "[t1]  DBG  checking rules\n[t2]  WAR  slow dns\n[t3]  ERR  rule load failed\n").unwrap();
+
+    let mut tab = DaemonLogTab::new();
+    tab.set_source(Ok(path.to_string_lossy().into_owned()));
+    tab.poll();
+    assert_eq!(tab.shown_entries().len(), 3);
+
+    let key = |c| KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE);
+    // Info, important, warning and up
+    for _ in 0..3 {
+        tab.handle_key(key('v'));
+    }
+    let shown: Vec<_> = tab.shown_entries().iter().map(|e| e.message.clone()).collect();
+    assert_eq!(shown, ["slow dns", "rule load failed"]);
+
+    tab.handle_key(key('/'));
+    for c in "RULE".chars() {
+        tab.handle_key(key(c));
+    }
+    tab.handle_key(KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE));
+    let shown: Vec<_> = tab.shown_entries().iter().map(|e| e.message.clone()).collect();
+    assert_eq!(shown, ["rule load failed"]);
+
+    tab.handle_key(KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE));
+    append(&path, "[t4]  ERR  queue closed\n");
+    tab.poll();
+    assert_eq!(tab.shown_entries().len(), 3);
+
+    tab.set_source(Err("remote".to_string()));
+    assert!(tab.shown_entries().is_empty());
+
+    let _ = std::fs::remove_file(&path);
+}
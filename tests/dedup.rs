@@ -0,0 +1,101 @@
+//! Bursts of identical events merged before they are stored
+
+mod common;
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use tokio::sync::{broadcast, mpsc};
+
+use opensnitch_tui::app::dedup::EventCoalescer;
+use opensnitch_tui::app::state::{run_state_manager, AppMessage};
+use opensnitch_tui::app::suggestions::DenialTracker;
+use opensnitch_tui::app::AppState;
+use opensnitch_tui::db::Database;
+use opensnitch_tui::models::{Connection, Event};
+
+use common::connection;
+
+fn event(process: &str, src_port: u32) -> Event {
+    Event::new(Connection { src_port, ..connection(process, "", 443) }, None)
+}
+
+#[test]
+fn identical_events_within_the_window_are_merged() {
+    let mut coalescer = EventCoalescer::new(Duration::from_millis(100));
+    let start = Instant::now();
+
+    for i in 0..5 {
+        assert!(coalescer.push("node-a", event("/usr/bin/app", 40000 + i), start).is_none());
+    }
+    coalescer.push("node-a", event("/usr/bin/other", 40000), start + Duration::from_millis(10));
+    // Same connection from another node
+    coalescer.push("node-b", event("/usr/bin/app", 40000), start + Duration::from_millis(20));
+
+    assert!(coalescer.flush(start + Duration::from_millis(99)).is_empty());
+    let flushed = coalescer.flush(start + Duration::from_millis(100));
+    assert_eq!(flushed.len(), 1);
+    assert_eq!(flushed[0].count, 5);
+    assert_eq!(flushed[0].connection.src_port, 40000);
+
+    let flushed = coalescer.flush(start + Duration::from_millis(120));
+    let counts: Vec<_> = flushed.iter().map(|e| (e.connection.process_path.as_str(), e.count)).collect();
+    assert_eq!(counts, [("/usr/bin/other", 1), ("/usr/bin/app", 1)]);
+
+    // Disabled, every event passes straight through
+    let mut coalescer = EventCoalescer::new(Duration::ZERO);
+    assert_eq!(coalescer.push("node-a", event("/usr/bin/app", 1), start).map(|e| e.count), Some(1));
+}
+
+#[tokio::test]
+async fn the_state_manager_stores_one_event_per_burst() {
+    let (ui_update_tx, _) = broadcast::channel(100);
+    let db = Database::open(":memory:").unwrap();
+    let state = Arc::new(AppState::new(db, ui_update_tx.clone()).with_dedup(Duration::from_millis(50)));
+    let (tx, rx) = mpsc::channel(100);
+    let manager = tokio::spawn(run_state_manager(
+        state.clone(),
+        rx,
+        ui_update_tx,
+        DenialTracker::new(0, Duration::from_secs(60)),
+    ));
+
+    for i in 0..20 {
+        let event = event("/usr/bin/app", 40000 + i);
+        tx.send(AppMessage::ConnectionEvent { node_addr: "node-a".to_string(), event: Box::new(event) }).await.unwrap();
+    }
+    tokio::time::sleep(Duration::from_millis(150)).await;
+
+    let connections = state.connections.read().await;
+    assert_eq!(connections.len(), 1);
+    assert_eq!(connections[0].count, 20);
+    assert_eq!(state.db.connection_count().unwrap(), 1);
+    assert_eq!(state.db.select_connections(10).unwrap()[0].count, 20);
+    assert_eq!(state.db.select_stats_by_proc(10).unwrap()["/usr/bin/app"], 20);
+    manager.abort();
+}
+
+#[tokio::test]
+async fn bursts_held_back_are_stored_when_the_state_manager_stops() {
+    let (ui_update_tx, _) = broadcast::channel(100);
+    let db = Database::open(":memory:").unwrap();
+    let state = Arc::new(AppState::new(db, ui_update_tx.clone()).with_dedup(Duration::from_secs(60)));
+    let (tx, rx) = mpsc::channel(100);
+    let manager = tokio::spawn(run_state_manager(
+        state.clone(),
+        rx,
+        ui_update_tx,
+        DenialTracker::new(0, Duration::from_secs(60)),
+    ));
+
+    for i in 0..3 {
+        let event = event("/usr/bin/app", 40000 + i);
+        tx.send(AppMessage::ConnectionEvent { node_addr: "node-a".to_string(), event: Box::new(event) }).await.unwrap();
+    }
+    drop(tx);
+    manager.await.unwrap();
+
+    let stored = state.db.select_connections(10).unwrap();
+    assert_eq!(stored.len(), 1);
+    assert_eq!(stored[0].count, 3);
+}
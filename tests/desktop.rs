@@ -0,0 +1,43 @@
+//! Desktop notifications for prompts and alerts
+
+mod common;
+
+use opensnitch_tui::app::desktop::{alert_message, prompt_message, send};
+use opensnitch_tui::models::{Alert, AlertData, AlertPriority, AlertType, AlertWhat};
+use opensnitch_tui::utils::Formats;
+
+use common::connection;
+
+#[test]
+fn notifications_tell_process_and_destination() {
+    let (summary, body) = prompt_message(&connection("/usr/bin/curl", "example.org", 443), &Formats::default());
+    assert_eq!(summary, "curl wants to connect");
+    assert_eq!(body, "to example.org:443 (tcp)\n/usr/bin/curl");
+
+    // Privacy mode applies to the desktop as well
+    let private = Formats::default().with_private(true);
+    let (_, body) = prompt_message(&connection("/usr/bin/curl", "example.org", 443), &private);
+    assert!(!body.contains("example") && !body.contains("/usr/bin"), "{}", body);
+
+    let mut alert = Alert::new(
+        1,
+        AlertType::Warning,
+        AlertPriority::High,
+        AlertWhat::Connection,
+        Some(AlertData::Connection(connection("/usr/bin/curl", "example.org", 443))),
+    );
+    alert.node = "unix:///tmp/osui.sock".to_string();
+    let (_, body) = alert_message(&alert, &Formats::default());
+    assert_eq!(body, "curl -> example.org:443\non unix:///tmp/osui.sock");
+}
+
+#[tokio::test]
+async fn the_command_gets_summary_and_body() {
+    let path = std::env::temp_dir().join(format!("opensnitch-tui-notify-{}.txt", std::process::id()));
+    let command = format!("printf '%s|' > {}", path.display());
+    send(&command, "curl wants to connect", "to example.org:443").await.unwrap();
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "curl wants to connect|to example.org:443|");
+    let _ = std::fs::remove_file(&path);
+
+    assert!(send("exit 3", "summary", "body").await.unwrap_err().contains("3"));
+}
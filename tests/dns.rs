@@ -0,0 +1,62 @@
+//! Hostnames resolved on the nodes
+
+mod common;
+
+use chrono::{Duration, TimeZone, Utc};
+use tokio::sync::broadcast;
+
+use opensnitch_tui::app::AppState;
+use opensnitch_tui::db::Database;
+use opensnitch_tui::models::{Connection, DnsEntry, DnsLog, Event};
+
+use common::connection;
+
+fn event(host: &str, ip: &str, seconds: i64) -> Event {
+    let connection = Connection { dst_ip: ip.to_string(), ..connection("/usr/bin/curl", host, 443) };
+    let mut event = Event::new(connection, None);
+    let start = Utc.with_ymd_and_hms(2024, 3, 1, 10, 0, 0).unwrap();
+    event.time = (start + Duration::seconds(seconds)).to_rfc3339();
+    event
+}
+
+#[test]
+fn mappings_are_counted_and_stored_now_and_then() {
+    let now = Utc::now();
+    assert_eq!(DnsEntry::of("node", &event("", "192.0.2.1", 0), now), None);
+    assert_eq!(DnsEntry::of("node", &event("192.0.2.1", "192.0.2.1", 0), now), None);
+    let entry = DnsEntry::of("node", &event("Example.ORG.", "192.0.2.1", 0), now).unwrap();
+    assert_eq!(entry.host, "example.org");
+
+    let mut log = DnsLog::default();
+    assert!(log.record(entry).is_some());
+    // Seen again soon: counted, not stored again
+    let again = |seconds| DnsEntry::of("node", &event("example.org", "192.0.2.1", seconds), now).unwrap();
+    assert_eq!(log.record(again(30)), None);
+    let stored = log.record(again(90)).unwrap();
+    assert_eq!((stored.count, stored.last_seen - stored.first_seen), (3, Duration::seconds(90)));
+
+    log.record(DnsEntry::of("node", &event("example.net", "192.0.2.2", 100), now).unwrap());
+    let hosts: Vec<_> = log.entries().into_iter().map(|e| e.host).collect();
+    assert_eq!(hosts, ["example.net", "example.org"]);
+}
+
+#[tokio::test]
+async fn mappings_survive_a_restart() {
+    let path = std::env::temp_dir().join(format!("opensnitch-tui-dns-{}.db", std::process::id()));
+    let path = path.to_string_lossy().to_string();
+    let _ = std::fs::remove_file(&path);
+
+    let (ui_update_tx, _) = broadcast::channel(100);
+    let state = AppState::new(Database::open(&path).unwrap(), ui_update_tx.clone());
+    state.record_dns("unix:/local", &event("example.org", "192.0.2.1", 0));
+    state.record_dns("unix:/local", &event("example.org", "2001:db8::1", 5));
+    state.record_dns("unix:/local", &event("example.org", "192.0.2.1", 120));
+    drop(state);
+
+    let state = AppState::new(Database::open(&path).unwrap(), ui_update_tx);
+    state.restore_dns();
+    let entries = state.dns.lock().unwrap().entries();
+    assert_eq!(entries.len(), 2);
+    assert_eq!((entries[0].ip.as_str(), entries[0].count), ("192.0.2.1", 2));
+    std::fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,109 @@
+//! Rules the daemon expires on its own
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::Utc;
+use tokio::sync::{broadcast, mpsc};
+
+use opensnitch_tui::app::state::{run_state_manager, AppMessage};
+use opensnitch_tui::app::suggestions::DenialTracker;
+use opensnitch_tui::app::AppState;
+use opensnitch_tui::db::Database;
+use opensnitch_tui::grpc::proto;
+use opensnitch_tui::models::node::ClientConfig;
+use opensnitch_tui::models::{
+    Alert, AlertData, AlertPriority, AlertType, AlertWhat, Operator, Rule, RuleAction, RuleDuration,
+};
+
+fn rule(name: &str, expires_in: Option<chrono::Duration>) -> Rule {
+    let mut rule = Rule::new(name, RuleAction::Deny, RuleDuration::OneHour, Operator::simple("dest.host", name));
+    rule.expires = expires_in.map(|left| Utc::now() + left);
+    rule
+}
+
+#[test]
+fn timestamps_come_from_newer_daemons_only() {
+    let mut message: proto::Rule = rule("a", None).into();
+    assert_eq!((message.updated, message.expires), (0, 0));
+    let old: Rule = message.clone().into();
+    assert_eq!((old.updated, old.expires), (None, None));
+
+    message.updated = 1_700_000_000;
+    message.expires = 1_700_003_600;
+    let new: Rule = message.into();
+    assert_eq!(new.updated.map(|t| t.timestamp()), Some(1_700_000_000));
+    assert_eq!(new.expires.map(|t| t.timestamp()), Some(1_700_003_600));
+    assert!(new.is_expired(Utc::now()));
+    assert_eq!(new.remaining(Utc::now()), Some(Duration::ZERO));
+
+    let left = rule("b", Some(chrono::Duration::minutes(30))).remaining(Utc::now()).unwrap();
+    assert!(left > Duration::from_secs(29 * 60) && left <= Duration::from_secs(30 * 60));
+}
+
+#[test]
+fn older_daemons_expire_by_duration() {
+    let mut rule = Rule::new("a", RuleAction::Allow, RuleDuration::FiveMinutes, Operator::simple("dest.host", "a"));
+    rule.created = Utc::now() - chrono::Duration::minutes(2);
+    assert!(rule.expiry_estimated());
+    let left = rule.remaining(Utc::now()).unwrap();
+    assert!(left > Duration::from_secs(2 * 60) && left <= Duration::from_secs(3 * 60));
+
+    // Changing the rule starts its time again
+    rule.updated = Some(Utc::now() - chrono::Duration::minutes(6));
+    assert!(rule.is_expired(Utc::now()));
+
+    // Without a creation time nothing is known
+    let mut message: proto::Rule = rule.into();
+    (message.created, message.updated) = (0, 0);
+    let unknown: Rule = message.into();
+    assert_eq!(unknown.remaining(Utc::now()), None);
+
+    let always = Rule::new("b", RuleAction::Allow, RuleDuration::Always, Operator::simple("dest.host", "b"));
+    assert!(!always.is_expired(Utc::now() + chrono::Duration::days(365)));
+}
+
+#[tokio::test]
+async fn expired_rules_are_dropped() {
+    let (ui_update_tx, _) = broadcast::channel(100);
+    let state = Arc::new(AppState::new(Database::open(":memory:").unwrap(), ui_update_tx.clone()));
+    let config = ClientConfig {
+        rules: vec![
+            rule("kept", None),
+            rule("later", Some(chrono::Duration::hours(1))),
+            rule("past", Some(chrono::Duration::seconds(-1))),
+            rule("pushed", None),
+            Rule {
+                created: Utc::now() - chrono::Duration::minutes(20),
+                ..Rule::new("stale", RuleAction::Allow, RuleDuration::FifteenMinutes, Operator::simple("dest.host", "stale"))
+            },
+        ],
+        ..Default::default()
+    };
+    state.nodes.write().await.add_node("node-a", config);
+    let (tx, rx) = mpsc::channel(10);
+    let manager = tokio::spawn(run_state_manager(
+        state.clone(),
+        rx,
+        ui_update_tx,
+        DenialTracker::new(0, Duration::from_secs(60)),
+    ));
+
+    // The daemon reports a deletion the rule list knew nothing about
+    let mut alert = Alert::new(
+        1,
+        AlertType::Info,
+        AlertPriority::Low,
+        AlertWhat::Rule,
+        Some(AlertData::Rule(rule("pushed", Some(chrono::Duration::seconds(-5))))),
+    );
+    alert.node = "node-a".to_string();
+    tx.send(AppMessage::AlertReceived { alert }).await.unwrap();
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    let nodes = state.nodes.read().await;
+    let names: Vec<_> = nodes.get_node("node-a").unwrap().rules.iter().map(|r| r.name.as_str()).collect();
+    assert_eq!(names, ["kept", "later"]);
+    drop(nodes);
+    manager.abort();
+}
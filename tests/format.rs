@@ -0,0 +1,101 @@
+//! Locale-aware number and date formatting
+
+mod common;
+
+use chrono::{Local, TimeZone};
+
+use opensnitch_tui::models::{
+    Alert, AlertData, AlertPriority, AlertType, AlertWhat, FwRule, Operator, Rule, RuleAction, RuleDuration,
+};
+use opensnitch_tui::utils::{DateStyle, Formats};
+
+use common::connection;
+
+#[test]
+fn counts_are_grouped_like_the_locale() {
+    assert_eq!(Formats::new("en_US.UTF-8", true, DateStyle::Iso).number(1_234_567), "1,234,567");
+    assert_eq!(Formats::new("de_DE.UTF-8", true, DateStyle::Iso).number(1_234_567), "1.234.567");
+    assert_eq!(Formats::new("de_CH", true, DateStyle::Iso).number(12_345), "12'345");
+    assert_eq!(Formats::new("fr_FR@euro", true, DateStyle::Iso).number(1_000), "1\u{a0}000");
+    assert_eq!(Formats::new("en_US", true, DateStyle::Iso).number(999), "999");
+
+    // Grouping off, or a locale without conventions
+    assert_eq!(Formats::new("en_US", false, DateStyle::Iso).number(1_234_567), "1234567");
+    assert_eq!(Formats::new("C", true, DateStyle::Iso).number(1_234_567), "1234567");
+    assert_eq!(Formats::default().number(1_234_567), "1234567");
+}
+
+#[test]
+fn sizes_use_binary_units_and_the_locale_decimal() {
+    let en = Formats::new("en_GB", true, DateStyle::Iso);
+    assert_eq!(en.bytes(512), "512 B");
+    assert_eq!(en.bytes(1536), "1.5 KiB");
+    assert_eq!(en.bytes(5 * 1024 * 1024), "5.0 MiB");
+    assert_eq!(Formats::new("de_DE", true, DateStyle::Iso).bytes(1536), "1,5 KiB");
+}
+
+#[test]
+fn dates_are_iso_unless_the_locale_order_is_asked_for() {
+    let t = Local.with_ymd_and_hms(2024, 5, 1, 13, 45, 7).unwrap();
+
+    let iso = Formats::new("en_US", true, DateStyle::Iso);
+    assert_eq!(iso.date_time(&t), "2024-05-01 13:45:07");
+    assert_eq!(iso.time(&t), "13:45:07");
+    assert_eq!(iso.minute(&t), "13:45");
+
+    let us = Formats::new("en_US", true, DateStyle::Locale);
+    assert_eq!(us.date_time(&t), "05/01/2024 01:45:07 PM");
+    assert_eq!(us.minute(&t), "01:45 PM");
+    assert_eq!(Formats::new("de_DE", true, DateStyle::Locale).date_time(&t), "01.05.2024 13:45:07");
+
+    // A configured locale beats the environment
+    assert_eq!(Formats::detect("de_AT", true, DateStyle::Locale).time(&t), "13:45:07");
+    assert_eq!(Formats::detect("nl_NL", true, DateStyle::Locale).date_time(&t), "01-05-2024 13:45:07");
+}
+
+#[test]
+fn private_mode_masks_hosts_and_paths() {
+    let private = Formats::default().with_private(true);
+    let host = private.host("tracker.example.org");
+    assert!(host.starts_with('#') && host.ends_with(".org"), "{}", host);
+    assert!(!host.contains("example"));
+    // Stable, so hosts can still be told apart
+    assert_eq!(private.host("tracker.example.org"), host);
+    assert_ne!(private.host("cdn.example.org"), host);
+    assert!(!private.host("198.51.100.7").contains("198"));
+    assert_eq!(private.path("/home/alice/bin/sync"), ".../sync");
+    assert_eq!(private.text("--token=secret"), "(hidden)");
+
+    let rule = Rule::new("alice-sync", RuleAction::Allow, RuleDuration::Always, Operator::list(vec![
+        Operator::simple("process.path", "/home/alice/bin/sync"),
+        Operator::simple("dest.port", "443"),
+    ]));
+    let masked = private.rule(&rule);
+    assert_eq!(masked.operator.list[0].data, ".../sync");
+    assert_eq!(masked.operator.list[1].data, "443");
+
+    // Off by default
+    let plain = Formats::default();
+    assert_eq!(plain.host("tracker.example.org"), "tracker.example.org");
+    assert_eq!(plain.path("/home/alice/bin/sync"), "/home/alice/bin/sync");
+    assert!(matches!(plain.rule(&rule), std::borrow::Cow::Borrowed(_)));
+}
+
+#[test]
+fn private_mode_masks_what_alerts_and_descriptions_name() {
+    let private = Formats::default().with_private(true);
+    let masked = private.addresses("Kill-switch: LAN 192.168.1.0/24, via 10.0.0.1");
+    assert!(masked.starts_with("Kill-switch: LAN #"), "{}", masked);
+    assert!(!masked.contains("192.168") && !masked.contains("10.0.0.1"), "{}", masked);
+    assert!(masked.contains(", via #"), "{}", masked);
+
+    let connection = connection("/usr/bin/curl", "tracker.example.org", 443);
+    let alert = Alert::new(1, AlertType::Warning, AlertPriority::High, AlertWhat::Connection, Some(AlertData::Connection(connection)));
+    let text = private.alert(&alert);
+    assert!(text.starts_with("curl -> #") && text.ends_with(".org:443"), "{}", text);
+    assert_eq!(Formats::default().alert(&alert), alert.text());
+
+    let fw = FwRule::new("Kill-switch: VPN endpoint 198.51.100.7", "accept");
+    let alert = Alert::new(2, AlertType::Warning, AlertPriority::High, AlertWhat::Firewall, Some(AlertData::FirewallRule(fw)));
+    assert!(!private.alert(&alert).contains("198.51"));
+}
@@ -0,0 +1,81 @@
+mod common;
+
+use crossterm::event::KeyCode;
+
+use opensnitch_tui::models::{Expression, FwRule, Statement, StatementValue};
+use opensnitch_tui::ui::dialogs::fw_rule::{FwRuleEditorDialog, FwRuleEditorResult};
+
+use common::key;
+
+fn type_text(editor: &mut FwRuleEditorDialog, text: &str) {
+    for c in text.chars() {
+        editor.handle_key(key(KeyCode::Char(c)));
+    }
+}
+
+fn values(statement: &Statement) -> Vec<(&str, &str)> {
+    statement.values.iter().map(|v| (v.key.as_str(), v.value.as_str())).collect()
+}
+
+#[test]
+fn parses_statements_the_way_nft_writes_them() {
+    let ct = Statement::parse("ct state {established, related}").unwrap();
+    assert_eq!((ct.name.as_str(), ct.op.as_str()), ("ct", "=="));
+    assert_eq!(values(&ct), [("state", "established"), ("state", "related")]);
+
+    let iface = Statement::parse("iifname != lo").unwrap();
+    assert_eq!((iface.name.as_str(), iface.op.as_str()), ("iifname", "!="));
+    assert_eq!(values(&iface), [("value", "lo")]);
+
+    let ports = Statement::parse("tcp dport 1000-2000").unwrap();
+    assert_eq!(values(&ports), [("dport", "1000-2000")]);
+
+    let log = Statement::parse("log prefix=ssh level=warn").unwrap();
+    assert_eq!(values(&log), [("prefix", "ssh"), ("level", "warn")]);
+
+    assert!(Statement::parse("counter").unwrap().values.is_empty());
+    assert!(Statement::parse("").is_err());
+    assert!(Statement::parse("tcp dport !=").is_err());
+    assert!(Statement::parse("tcp dport 2000-1000").is_err());
+
+    for text in ["ct state == {established, related}", "iifname != lo", "icmp type == echo-request", "log == prefix=ssh level=warn"] {
+        assert_eq!(Statement::parse(text).unwrap().to_string(), text);
+    }
+}
+
+#[test]
+fn editor_adds_edits_and_keeps_expressions_it_has_no_field_for() {
+    let rule = FwRule::new("ssh", "accept").with_expressions(vec![Expression {
+        statement: Statement {
+            op: "!=".to_string(),
+            name: "dport".to_string(),
+            values: vec![StatementValue { key: "value".to_string(), value: "22".to_string() }],
+        },
+    }]);
+    let mut editor = FwRuleEditorDialog::edit(&rule);
+    assert!(editor.dest_port.is_empty());
+    assert_eq!(editor.expressions.len(), 1);
+
+    editor.handle_key(key(KeyCode::Char('a')));
+    type_text(&mut editor, "ct state 9-1");
+    editor.handle_key(key(KeyCode::Enter));
+    assert_eq!(editor.expressions.len(), 1, "invalid input stays in the editor");
+    for _ in 0.."9-1".len() {
+        editor.handle_key(key(KeyCode::Backspace));
+    }
+    type_text(&mut editor, "{established, related}");
+    editor.handle_key(key(KeyCode::Enter));
+
+    // Edit the first row in place
+    editor.handle_key(key(KeyCode::Up));
+    editor.handle_key(key(KeyCode::Enter));
+    editor.handle_key(key(KeyCode::End));
+    type_text(&mut editor, " 2222");
+    editor.handle_key(key(KeyCode::Enter));
+
+    let Some(FwRuleEditorResult::Save(saved)) = editor.handle_key(key(KeyCode::F(2))) else {
+        panic!("rule not saved");
+    };
+    let texts: Vec<_> = saved.expressions.iter().map(|e| e.statement.to_string()).collect();
+    assert_eq!(texts, ["dport != {22, 2222}", "ct state == {established, related}"]);
+}
@@ -0,0 +1,84 @@
+//! Commands and webhooks run for events of a rule
+
+mod common;
+
+use std::time::{Duration, Instant};
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+use opensnitch_tui::app::hooks::{RuleHooks, Sink};
+use opensnitch_tui::config::RuleHook;
+use opensnitch_tui::models::{Event, Operator, Rule, RuleAction, RuleDuration};
+
+use common::connection;
+
+fn event(rule: &str) -> Event {
+    let connection = connection("/usr/bin/app", "telemetry.example.org", 443);
+    let rule = Rule::new(rule, RuleAction::Deny, RuleDuration::Always, Operator::simple("dest.host", "x"));
+    Event::new(connection, Some(rule))
+}
+
+fn hook(rule: &str, command: &str, webhook: &str) -> RuleHook {
+    RuleHook {
+        rule: rule.to_string(),
+        command: command.to_string(),
+        webhook: webhook.to_string(),
+        min_interval_secs: 60,
+    }
+}
+
+#[test]
+fn hooks_are_rate_limited_per_rule() {
+    let mut hooks = RuleHooks::new(vec![
+        hook("block-telemetry", "true", ""),
+        hook("other", "true", ""),
+        // Nothing to run
+        hook("unused", "", ""),
+    ]);
+    let start = Instant::now();
+
+    let due = hooks.due("node-a", &event("block-telemetry"), start);
+    assert_eq!(due.len(), 1);
+    assert_eq!(due[0].sinks, vec![Sink::Command("true".to_string())]);
+    assert!(due[0].payload.contains("\"dst_host\":\"telemetry.example.org\""));
+    assert!(hooks.due("node-a", &event("unhooked"), start).is_empty());
+
+    // Held back within the interval, then reported with the next run
+    for _ in 0..3 {
+        assert!(hooks.due("node-a", &event("block-telemetry"), start + Duration::from_secs(10)).is_empty());
+    }
+    let due = hooks.due("node-a", &event("block-telemetry"), start + Duration::from_secs(61));
+    assert_eq!(due[0].suppressed, 3);
+    assert!(due[0].payload.contains("\"suppressed\":3"));
+}
+
+#[tokio::test]
+async fn hooks_deliver_to_commands_and_webhooks() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/events", listener.local_addr().unwrap());
+    let server = tokio::spawn(async move {
+        let (mut stream, _) = listener.accept().await.unwrap();
+        let mut request = vec![0; 4096];
+        let n = stream.read(&mut request).await.unwrap();
+        stream.write_all(b"HTTP/1.1 204 No Content\r\n\r\n").await.unwrap();
+        String::from_utf8_lossy(&request[..n]).to_string()
+    });
+
+    let mut hooks = RuleHooks::new(vec![hook("block-telemetry", "grep -q telemetry.example.org", &url)]);
+    let trigger = hooks.due("node-a", &event("block-telemetry"), Instant::now()).remove(0);
+    let runs = trigger.run().await;
+
+    assert_eq!(runs.len(), 2);
+    assert!(runs.iter().all(|run| run.result.is_ok()), "{:?}", runs);
+    assert_eq!(runs[1].result, Ok("HTTP 204".to_string()));
+    let request = server.await.unwrap();
+    assert!(request.starts_with("POST /events HTTP/1.1"));
+    assert!(request.contains("\"rule\":\"block-telemetry\""));
+
+    // Failures are logged with the reason
+    let failed = Sink::Command("echo oops >&2; exit 3".to_string()).deliver("{}").await;
+    assert!(failed.unwrap_err().contains("oops"));
+    let https = Sink::Webhook("https://example.org/".to_string()).deliver("{}").await;
+    assert!(https.is_err());
+}
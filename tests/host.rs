@@ -0,0 +1,42 @@
+//! Telling a host running the daemon from a container serving as remote console
+
+use opensnitch_tui::config::{AuthType, HostMode, Settings};
+use opensnitch_tui::utils::host::{is_container_cgroup, Host};
+
+#[test]
+fn manages_the_daemon_only_next_to_it() {
+    assert!(Host::from_probe(false, true).manage_daemon);
+    // Containers can't reach the host's systemd, even with the config mounted
+    assert!(!Host::from_probe(true, true).manage_daemon);
+    assert!(!Host::from_probe(false, false).manage_daemon);
+
+    // The setting wins over detection
+    assert!(Host::detect(HostMode::Local).manage_daemon);
+    assert!(!Host::detect(HostMode::Console).manage_daemon);
+    assert_eq!(Settings::default().host_mode, HostMode::Auto);
+}
+
+#[test]
+fn recognises_container_cgroups() {
+    assert!(is_container_cgroup("0::/system.slice/docker-4f1c2e.scope\n"));
+    assert!(is_container_cgroup("12:pids:/kubepods/besteffort/pod1234\n1:name=systemd:/\n"));
+    assert!(!is_container_cgroup("0::/init.scope\n"));
+    assert!(!is_container_cgroup("0::/user.slice/user-1000.slice/session-2.scope\n"));
+}
+
+#[test]
+fn a_console_only_listens_beyond_loopback_with_authentication() {
+    let mut settings = Settings::default();
+    assert_eq!(settings.console_address, "127.0.0.1:50051");
+    assert!(settings.check_console_address().is_ok());
+
+    settings.console_address = "0.0.0.0:50051".to_string();
+    assert!(settings.check_console_address().is_err());
+    settings.auth_type = AuthType::Token;
+    settings.auth_token = "secret".to_string();
+    assert!(settings.check_console_address().is_ok());
+
+    settings.auth_type = AuthType::Simple;
+    settings.tls_cert = "/etc/opensnitch-tui/cert.pem".to_string();
+    assert!(settings.check_console_address().is_ok());
+}
@@ -0,0 +1,119 @@
+//! Asks answered by a prompt or by the interception mode
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::sync::{broadcast, mpsc};
+use tonic::Request;
+
+use opensnitch_tui::app::state::AppMessage;
+use opensnitch_tui::app::AppState;
+use opensnitch_tui::config::{InterceptionMode, PromptPolicy, Settings};
+use opensnitch_tui::db::Database;
+use opensnitch_tui::grpc::proto::ui_server::Ui;
+use opensnitch_tui::grpc::{proto, UiService};
+use opensnitch_tui::models::{Operator, Rule, RuleAction, RuleDuration};
+
+fn connection() -> proto::Connection {
+    proto::Connection {
+        protocol: "tcp".to_string(),
+        dst_host: "example.org".to_string(),
+        dst_port: 443,
+        process_path: "/usr/bin/curl".to_string(),
+        ..Default::default()
+    }
+}
+
+fn service(mode: InterceptionMode, timeout: Duration) -> (Arc<AppState>, UiService, mpsc::Receiver<AppMessage>) {
+    let (ui_update_tx, _) = broadcast::channel(100);
+    let state = Arc::new(
+        AppState::new(Database::open(":memory:").unwrap(), ui_update_tx)
+            .with_interception(mode)
+            .with_prompt_timeout(timeout),
+    );
+    let (tx, rx) = mpsc::channel(10);
+    let service = UiService::new(state.clone(), tx);
+    (state, service, rx)
+}
+
+#[test]
+fn mode_is_saved_by_name() {
+    let settings: Settings = serde_json::from_str(r#"{"interception_mode": "deny-all"}"#).unwrap();
+    assert_eq!(settings.interception_mode, InterceptionMode::DenyAll);
+    assert_eq!(Settings::default().interception_mode, InterceptionMode::Ask);
+    assert_eq!(InterceptionMode::DenyAll.next(), InterceptionMode::Ask);
+}
+
+#[tokio::test]
+async fn monitoring_modes_answer_without_asking() {
+    let (state, service, mut rx) = service(InterceptionMode::AllowAll, Duration::from_secs(5));
+    let rule = service.ask_rule(Request::new(connection())).await.unwrap().into_inner();
+    assert_eq!((rule.action.as_str(), rule.duration.as_str()), ("allow", "once"));
+
+    state.set_interception(InterceptionMode::DenyAll);
+    let rule = service.ask_rule(Request::new(connection())).await.unwrap().into_inner();
+    assert_eq!(rule.action, "deny");
+
+    rx.close();
+    while let Some(message) = rx.recv().await {
+        assert!(!matches!(message, AppMessage::ConnectionPrompt { .. }));
+    }
+}
+
+#[tokio::test]
+async fn asks_wait_for_the_prompt() {
+    let (_state, service, mut rx) = service(InterceptionMode::Ask, Duration::from_secs(5));
+    let answering = tokio::spawn(async move {
+        while let Some(message) = rx.recv().await {
+            if let AppMessage::ConnectionPrompt { response_tx, .. } = message {
+                let rule = Rule::new(
+                    "curl",
+                    RuleAction::Reject,
+                    RuleDuration::Always,
+                    Operator::simple("process.path", "/usr/bin/curl"),
+                );
+                response_tx.send(rule).unwrap();
+            }
+        }
+    });
+    let rule = service.ask_rule(Request::new(connection())).await.unwrap().into_inner();
+    assert_eq!((rule.name.as_str(), rule.action.as_str()), ("curl", "reject"));
+    answering.abort();
+}
+
+#[tokio::test]
+async fn unanswered_prompts_fall_back_to_the_default() {
+    let (state, service, mut rx) = service(InterceptionMode::Ask, Duration::from_millis(50));
+    state.prompt_policy.write().await.default_action = RuleAction::Deny;
+
+    let rule = service.ask_rule(Request::new(connection())).await.unwrap().into_inner();
+    assert_eq!(rule.action, "deny");
+    let mut prompted = false;
+    while let Ok(message) = rx.try_recv() {
+        prompted |= matches!(message, AppMessage::ConnectionPrompt { .. });
+    }
+    assert!(prompted);
+}
+
+#[tokio::test]
+async fn prompt_defaults_change_while_running() {
+    let (state, service, _rx) = service(InterceptionMode::Ask, Duration::from_secs(60));
+    let policy = PromptPolicy { profile: None, default_action: RuleAction::Reject, default_duration: RuleDuration::FiveMinutes };
+    state.set_prompt_defaults(policy, Duration::from_millis(50)).await;
+    assert_eq!(state.prompt_timeout(), Duration::from_millis(50));
+
+    // Answered by the new defaults well before the old timeout
+    let rule = tokio::time::timeout(Duration::from_secs(5), service.ask_rule(Request::new(connection())))
+        .await
+        .expect("the new timeout applies")
+        .unwrap()
+        .into_inner();
+    assert_eq!((rule.action.as_str(), rule.duration.as_str()), ("reject", "5m"));
+
+    // A network profile in effect keeps its own answer
+    state.prompt_policy.write().await.profile = Some("office".to_string());
+    let policy = PromptPolicy { profile: None, default_action: RuleAction::Allow, default_duration: RuleDuration::Once };
+    state.set_prompt_defaults(policy, Duration::from_secs(20)).await;
+    assert_eq!(state.prompt_policy.read().await.default_action, RuleAction::Reject);
+    assert_eq!(state.prompt_timeout(), Duration::from_secs(20));
+}
@@ -0,0 +1,35 @@
+//! Interface and source network of connections
+
+use opensnitch_tui::db::Database;
+use opensnitch_tui::models::{Connection, Event, Operator};
+
+fn vpn_connection() -> Connection {
+    Connection {
+        protocol: "tcp".to_string(),
+        src_ip: "10.8.0.2".to_string(),
+        dst_ip: "192.0.2.1".to_string(),
+        dst_port: 443,
+        process_path: "/usr/bin/curl".to_string(),
+        interface: "wg0".to_string(),
+        src_network: "10.8.0.0/24".to_string(),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn interface_rules_never_match_in_the_tui() {
+    // The daemon does not say which interfaces a connection goes through
+    let conn = vpn_connection();
+    assert!(!Operator::simple("iface.out", "wg0").matches(&conn));
+    assert!(!Operator::simple("iface.in", "wg0").matches(&conn));
+}
+
+#[test]
+fn interfaces_are_stored_with_connections() {
+    let db = Database::open(":memory:").unwrap();
+    db.insert_connection(&Event::new(vpn_connection(), None)).unwrap();
+
+    let stored = &db.select_connections(10).unwrap()[0].connection;
+    assert_eq!(stored.interface, "wg0");
+    assert_eq!(stored.src_network, "10.8.0.0/24");
+}
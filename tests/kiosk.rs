@@ -0,0 +1,56 @@
+//! Wall display mode: the keys it lets through, the tabs it cycles and its large digits
+
+mod common;
+
+use std::time::{Duration, Instant};
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+use opensnitch_tui::ui::app::{Kiosk, TabId};
+use opensnitch_tui::ui::widgets::bignum::big_lines;
+
+use common::key;
+
+#[test]
+fn only_quitting_gets_through_a_kiosk() {
+    let kiosk = Kiosk::new(Instant::now());
+    assert!(kiosk.quits(&key(KeyCode::Char('q'))));
+    assert!(kiosk.quits(&KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL)));
+
+    // Switching tabs, answering or deleting does nothing
+    for code in [KeyCode::Tab, KeyCode::Char('2'), KeyCode::Char('d'), KeyCode::Enter, KeyCode::Esc] {
+        assert!(!kiosk.quits(&key(code)), "{:?}", code);
+    }
+    assert!(!kiosk.quits(&KeyEvent::new(KeyCode::Char('q'), KeyModifiers::CONTROL)));
+}
+
+#[test]
+fn a_kiosk_cycles_through_the_tabs_worth_watching() {
+    let start = Instant::now();
+    let mut kiosk = Kiosk::new(start);
+    assert_eq!(kiosk.tab(), TabId::Connections);
+
+    assert_eq!(kiosk.cycle(start + Duration::from_secs(19)), None);
+    assert_eq!(kiosk.cycle(start + Duration::from_secs(20)), Some(TabId::Statistics));
+    // Each tab gets its full time from when it came up
+    assert_eq!(kiosk.cycle(start + Duration::from_secs(30)), None);
+    assert_eq!(kiosk.cycle(start + Duration::from_secs(40)), Some(TabId::Alerts));
+    assert_eq!(kiosk.cycle(start + Duration::from_secs(60)), Some(TabId::Connections));
+}
+
+#[test]
+fn big_numbers_keep_separators_on_the_baseline() {
+    let [top, middle, bottom] = big_lines("1,024", true);
+    assert_eq!(top, " ┐   ┌─┐ ╶─┐ ╷ ╷ ");
+    assert_eq!(middle, " │   │ │ ┌─┘ └─┤ ");
+    assert_eq!(bottom, " ┴  ,└─┘ └─╴   ╵ ");
+}
+
+#[test]
+fn big_numbers_fall_back_to_ascii() {
+    let [top, middle, bottom] = big_lines("1,024", false);
+    assert_eq!(top, "      _   _      ");
+    assert_eq!(middle, "  |  | |  _| |_| ");
+    assert_eq!(bottom, "  | ,|_| |_    | ");
+    assert!([top, middle, bottom].iter().all(|row| row.is_ascii()));
+}
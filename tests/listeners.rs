@@ -0,0 +1,70 @@
+//! Further listeners, e.g. for the daemon of a network namespace
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::sync::{broadcast, mpsc};
+use tonic::Request;
+
+use opensnitch_tui::app::state::AppMessage;
+use opensnitch_tui::app::AppState;
+use opensnitch_tui::config::{ListenAddress, Settings};
+use opensnitch_tui::db::Database;
+use opensnitch_tui::grpc::proto::ui_server::Ui;
+use opensnitch_tui::grpc::{proto, UiService};
+use opensnitch_tui::models::{RuleAction, RuleDuration};
+
+#[test]
+fn listeners_are_bare_addresses_or_labeled() {
+    let settings: Settings = serde_json::from_str(
+        r#"{"listen_addresses": [
+            "127.0.0.1:50052",
+            {"address": "unix:///run/netns-vpn.sock", "label": "vpn", "default_action": "deny"}
+        ]}"#,
+    )
+    .unwrap();
+    let [host, vpn] = settings.listen_addresses.as_slice() else {
+        panic!("expected two listeners");
+    };
+    assert_eq!(host, &ListenAddress::new("127.0.0.1:50052"));
+    assert_eq!((host.tag(), host.prompt_policy()), (None, None));
+
+    assert_eq!(vpn.tag(), Some("vpn"));
+    let policy = vpn.prompt_policy().unwrap();
+    assert_eq!(policy.profile.as_deref(), Some("vpn"));
+    assert_eq!((policy.default_action, policy.default_duration), (RuleAction::Deny, RuleDuration::Once));
+
+    // Written back the same way
+    let saved = serde_json::to_string(&settings).unwrap();
+    let again: Settings = serde_json::from_str(&saved).unwrap();
+    assert_eq!(again.listen_addresses, settings.listen_addresses);
+}
+
+#[tokio::test]
+async fn nodes_are_tagged_and_answered_per_listener() {
+    let (ui_update_tx, _) = broadcast::channel(100);
+    let state = Arc::new(
+        AppState::new(Database::open(":memory:").unwrap(), ui_update_tx).with_prompt_timeout(Duration::from_millis(10)),
+    );
+    let (tx, mut rx) = mpsc::channel(10);
+    let mut vpn = ListenAddress::new("unix:///run/netns-vpn.sock");
+    vpn.label = "vpn".to_string();
+    vpn.default_action = Some(RuleAction::Reject);
+    let service = UiService::new(state.clone(), tx).with_listener(vpn);
+
+    // Unix socket peers have no address, the listener's tells them apart
+    let config = proto::ClientConfig { name: "vpn-host".to_string(), ..Default::default() };
+    service.subscribe(Request::new(config)).await.unwrap();
+    match rx.recv().await {
+        Some(AppMessage::NodeConnected { addr, listener, .. }) => {
+            assert_eq!(addr, "unix:///run/netns-vpn.sock");
+            assert_eq!(listener.as_deref(), Some("vpn"));
+        }
+        _ => panic!("expected the node to connect"),
+    }
+
+    // Nobody answers, the listener's default does rather than the network's
+    let connection = proto::Connection { dst_port: 443, process_path: "/usr/bin/curl".to_string(), ..Default::default() };
+    let rule = service.ask_rule(Request::new(connection)).await.unwrap().into_inner();
+    assert_eq!(rule.action, "reject");
+}
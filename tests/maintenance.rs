@@ -0,0 +1,55 @@
+//! Maintenance mode pausing event ingestion
+
+mod common;
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::sync::{broadcast, mpsc};
+
+use opensnitch_tui::app::state::{run_state_manager, AppMessage};
+use opensnitch_tui::app::suggestions::DenialTracker;
+use opensnitch_tui::app::AppState;
+use opensnitch_tui::db::Database;
+use opensnitch_tui::models::Statistics;
+
+use common::event;
+
+#[tokio::test]
+async fn events_are_dropped_but_counted_while_in_maintenance() {
+    let (ui_update_tx, _) = broadcast::channel(100);
+    let state = Arc::new(AppState::new(Database::open(":memory:").unwrap(), ui_update_tx.clone()));
+    let (tx, rx) = mpsc::channel(10);
+    let manager = tokio::spawn(run_state_manager(
+        state.clone(),
+        rx,
+        ui_update_tx,
+        DenialTracker::new(0, Duration::from_secs(60)),
+    ));
+
+    state.set_maintenance(true);
+    let stats = Statistics {
+        events: vec![event("/usr/bin/rsync", "example.org", 443), event("/usr/bin/rsync", "example.org", 443)],
+        ..Default::default()
+    };
+    let node_addr = "node-a".to_string();
+    tx.send(AppMessage::StatsUpdate { node_addr: node_addr.clone(), stats }).await.unwrap();
+    tx.send(AppMessage::ConnectionEvent { node_addr: node_addr.clone(), event: Box::new(event("/usr/bin/rsync", "example.org", 443)) })
+        .await
+        .unwrap();
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    assert!(state.connections.read().await.is_empty());
+    assert_eq!(state.db.connection_count().unwrap(), 0);
+    assert_eq!(state.maintenance_skipped(), 3);
+
+    // Back to normal, the counter starts over
+    state.set_maintenance(false);
+    assert_eq!(state.maintenance_skipped(), 0);
+    tx.send(AppMessage::ConnectionEvent { node_addr, event: Box::new(event("/usr/bin/curl", "example.org", 443)) }).await.unwrap();
+    drop(tx);
+    manager.await.unwrap();
+
+    assert_eq!(state.connections.read().await.len(), 1);
+    assert_eq!(state.db.connection_count().unwrap(), 1);
+}
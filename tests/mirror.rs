@@ -0,0 +1,95 @@
+//! Read-only mirrors fed through the control socket
+
+use std::os::unix::fs::PermissionsExt;
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::sync::{broadcast, mpsc};
+
+use opensnitch_tui::app::mirror::{self, MirrorSnapshot};
+use opensnitch_tui::app::state::UiUpdateSignal;
+use opensnitch_tui::app::AppState;
+use opensnitch_tui::db::Database;
+use opensnitch_tui::models::node::ClientConfig;
+use opensnitch_tui::models::{Alert, AlertData, AlertPriority, AlertType, AlertWhat};
+
+fn state() -> Arc<AppState> {
+    let (ui_update_tx, _) = broadcast::channel(100);
+    Arc::new(AppState::new(Database::open(":memory:").unwrap(), ui_update_tx))
+}
+
+async fn add_node(state: &AppState, addr: &str) {
+    let config = ClientConfig {
+        name: addr.to_string(),
+        version: "1.6.6".to_string(),
+        ..Default::default()
+    };
+    state.nodes.write().await.add_node(addr, config);
+}
+
+#[tokio::test]
+async fn snapshots_keep_the_locally_chosen_node() {
+    let primary = state();
+    add_node(&primary, "node-a").await;
+    add_node(&primary, "node-b").await;
+
+    let mirror = state();
+    MirrorSnapshot::capture(&primary).await.apply(&mirror).await;
+    assert_eq!(mirror.nodes.read().await.active_addr(), Some("node-a"));
+
+    mirror.nodes.write().await.set_active("node-b");
+    MirrorSnapshot::capture(&primary).await.apply(&mirror).await;
+    assert_eq!(mirror.nodes.read().await.active_addr(), Some("node-b"));
+    assert_eq!(mirror.nodes.read().await.nodes.len(), 2);
+}
+
+#[tokio::test]
+async fn mirror_follows_primary_over_the_socket() {
+    let path = std::env::temp_dir().join(format!("opensnitch-tui-mirror-{}.sock", std::process::id()));
+    let path = path.to_string_lossy().to_string();
+
+    let primary = state();
+    add_node(&primary, "node-a").await;
+    let (state_tx, _state_rx) = mpsc::channel(10);
+    let server = tokio::spawn(mirror::serve(path.clone(), primary.clone(), state_tx));
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    // Created closed to other users, not opened up then tightened
+    assert_eq!(std::fs::metadata(&path).unwrap().permissions().mode() & 0o077, 0);
+
+    let replica = state();
+    let client = tokio::spawn({
+        let (path, replica) = (path.clone(), replica.clone());
+        async move { mirror::attach(&path, replica).await }
+    });
+
+    // Initial snapshot arrives on attach
+    for _ in 0..50 {
+        if replica.nodes.read().await.active_addr().is_some() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    assert_eq!(replica.nodes.read().await.active_addr(), Some("node-a"));
+
+    // Later changes follow once the primary signals an update
+    let alert = Alert::new(
+        1,
+        AlertType::Warning,
+        AlertPriority::High,
+        AlertWhat::Generic,
+        Some(AlertData::Text("disk full".to_string())),
+    );
+    primary.add_alert(alert).await;
+    primary.notify_ui(UiUpdateSignal::AlertsUpdated);
+    for _ in 0..100 {
+        if !replica.alerts.read().await.is_empty() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    assert_eq!(replica.alerts.read().await.len(), 1);
+
+    client.abort();
+    server.abort();
+    let _ = std::fs::remove_file(&path);
+}
@@ -0,0 +1,121 @@
+//! Network detection and the prompt policy it selects
+
+use opensnitch_tui::config::settings::NetworkProfile;
+use opensnitch_tui::config::Settings;
+use opensnitch_tui::models::{RuleAction, RuleDuration};
+use opensnitch_tui::utils::network::{parse_default_route, parse_nmcli_ssid, port_anomaly};
+use opensnitch_tui::utils::{NetworkState, RoutingTable};
+
+const ROUTE: &str = "\
+Iface\tDestination\tGateway \tFlags\tRefCnt\tUse\tMetric\tMask\t\tMTU\tWindow\tIRTT
+wlan0\t0000A8C0\t00000000\t0001\t0\t0\t600\t00FFFFFF\t0\t0\t0
+wlan0\t00000000\t0100A8C0\t0003\t0\t0\t600\t00000000\t0\t0\t0
+";
+
+#[test]
+fn parses_default_route_and_ssid() {
+    assert_eq!(
+        parse_default_route(ROUTE),
+        Some(("wlan0".to_string(), "192.168.0.1".to_string()))
+    );
+    assert_eq!(parse_default_route("Iface\tDestination\n"), None);
+
+    assert_eq!(parse_nmcli_ssid("no:Neighbours\nyes:Cafe\\:Free\n"), Some("Cafe:Free".to_string()));
+    assert_eq!(parse_nmcli_ssid("no:Neighbours\n"), None);
+}
+
+fn network(ssid: &str, gateway: &str) -> NetworkState {
+    NetworkState {
+        interface: "wlan0".to_string(),
+        gateway: gateway.to_string(),
+        ssid: ssid.to_string(),
+    }
+}
+
+#[test]
+fn first_matching_profile_sets_the_policy() {
+    let settings = Settings {
+        network_profiles: vec![
+            NetworkProfile {
+                name: "home".to_string(),
+                ssid: "Home".to_string(),
+                gateway: "192.168.0.1".to_string(),
+                default_action: RuleAction::Allow,
+                default_duration: RuleDuration::Always,
+                ..Default::default()
+            },
+            NetworkProfile {
+                name: "public".to_string(),
+                interface: "wlan0".to_string(),
+                ..Default::default()
+            },
+        ],
+        ..Default::default()
+    };
+
+    let home = settings.prompt_policy(&network("Home", "192.168.0.1"));
+    assert_eq!(home.profile.as_deref(), Some("home"));
+    assert_eq!(home.default_duration, RuleDuration::Always);
+
+    // Same SSID behind another gateway is not home
+    let spoofed = settings.prompt_policy(&network("Home", "10.0.0.1"));
+    assert_eq!(spoofed.profile.as_deref(), Some("public"));
+    assert_eq!(spoofed.default_action, RuleAction::Deny);
+
+    // Nothing matches a wired network, the global defaults apply
+    let wired = NetworkState {
+        interface: "eth0".to_string(),
+        ..Default::default()
+    };
+    let policy = settings.prompt_policy(&wired);
+    assert_eq!(policy.profile, None);
+    assert_eq!(policy.default_action, settings.default_action);
+    assert_eq!(wired.label(), "eth0");
+}
+
+#[test]
+fn flags_protocols_on_unusual_ports() {
+    let ssh = port_anomaly("/usr/bin/ssh", "140.82.121.4", 443).unwrap();
+    assert_eq!(ssh.badge(), "SSH:443");
+    assert_eq!(port_anomaly("/usr/bin/ssh", "140.82.121.4", 22), None);
+
+    assert_eq!(port_anomaly("/usr/sbin/dnsmasq", "9.9.9.9", 5353).unwrap().service, "DNS");
+    assert_eq!(port_anomaly("/usr/sbin/dnsmasq", "9.9.9.9", 853), None);
+    // Local forwarders listen wherever they like
+    assert_eq!(port_anomaly("/usr/sbin/dnsmasq", "127.0.0.1", 5353), None);
+
+    // TLS clients are free to use alternate ports
+    assert_eq!(port_anomaly("/usr/bin/curl", "198.51.100.7", 8443), None);
+}
+
+#[test]
+fn finds_the_interface_of_source_addresses() {
+    let route = format!("{}tun0\t0000080A\t00000000\t0001\t0\t0\t50\t0000FFFF\t0\t0\t0\n", ROUTE);
+    let ipv6 = "\
+fd000000000000000000000000000000 40 00000000000000000000000000000000 00 00000000000000000000000000000000 00000100 00000001 00000000 00000001     tun0
+fd000000000000000000000000000001 80 00000000000000000000000000000000 00 00000000000000000000000000000000 00000000 00000002 00000000 80200001       lo
+";
+    let routes = RoutingTable::parse(&route, ipv6);
+
+    assert_eq!(routes.interface("192.168.0.23"), Some("wlan0"));
+    // The VPN's own subnet beats the default route
+    assert_eq!(routes.interface("10.8.3.4"), Some("tun0"));
+    assert_eq!(routes.interface("100.64.0.1"), Some("wlan0"));
+    // Local table routes through lo say nothing about the interface
+    assert_eq!(routes.interface("fd00::1"), Some("tun0"));
+    assert_eq!(routes.interface("127.0.0.1"), Some("lo"));
+    assert_eq!(routes.interface("2001:db8::1"), None);
+    assert_eq!(routes.interface("not an address"), None);
+}
+
+#[test]
+fn finds_the_network_of_source_addresses() {
+    let route = format!("{}tun0\t0000080A\t00000000\t0001\t0\t0\t50\t0000FFFF\t0\t0\t0\n", ROUTE);
+    let routes = RoutingTable::parse(&route, "");
+
+    assert_eq!(routes.network("192.168.0.23").as_deref(), Some("192.168.0.0/24"));
+    assert_eq!(routes.network("10.8.3.4").as_deref(), Some("10.8.0.0/16"));
+    // Past the default route, or on the host itself, there's no telling
+    assert_eq!(routes.network("100.64.0.1"), None);
+    assert_eq!(routes.network("127.0.0.1"), None);
+}
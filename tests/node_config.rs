@@ -0,0 +1,69 @@
+//! Reading and changing fields of a node's daemon configuration
+
+use std::time::Duration;
+
+use opensnitch_tui::models::node::{ask_deadline, AskLatency, DEFAULT_ASK_TIMEOUT};
+use opensnitch_tui::models::{Connection, Node, RuleAction};
+use opensnitch_tui::ui::dialogs::prompt::PromptDialog;
+use tokio::sync::oneshot;
+
+fn with_config(config: &str) -> Node {
+    let mut node = Node::new("unix:///tmp/osui.sock");
+    node.config = config.to_string();
+    node
+}
+
+#[test]
+fn reads_default_action_and_interception() {
+    let node = with_config(r#"{"DefaultAction": "deny", "InterceptUnknown": true, "LogLevel": 1}"#);
+    assert_eq!(node.default_action(), Some(RuleAction::Deny));
+    assert_eq!(node.intercept_unknown(), Some(true));
+
+    let empty = with_config("");
+    assert_eq!(empty.default_action(), None);
+    assert_eq!(empty.intercept_unknown(), None);
+}
+
+#[test]
+fn config_with_replaces_one_field_only() {
+    let node = with_config(r#"{"DefaultAction": "allow", "InterceptUnknown": false, "LogLevel": 1}"#);
+    let config = node
+        .config_with("DefaultAction", serde_json::Value::String("deny".to_string()))
+        .unwrap();
+
+    let updated = with_config(&config);
+    assert_eq!(updated.default_action(), Some(RuleAction::Deny));
+    assert_eq!(updated.intercept_unknown(), Some(false));
+    assert_eq!(updated.config_value("LogLevel"), Some(serde_json::json!(1)));
+
+    assert_eq!(with_config("not json").config_with("InterceptUnknown", true.into()), None);
+}
+
+#[test]
+fn ask_deadline_leaves_a_margin_of_the_daemon_timeout() {
+    assert_eq!(ask_deadline(30), Duration::from_secs(24));
+    let deadline = ask_deadline(DEFAULT_ASK_TIMEOUT);
+    assert_eq!(deadline, Duration::from_secs(12));
+
+    let mut latency = AskLatency::default();
+    assert_eq!(latency.average(), None);
+    assert!(!latency.record(Duration::from_millis(10), deadline));
+    assert!(latency.record(Duration::from_secs(25), deadline));
+    assert_eq!((latency.answered, latency.slow), (2, 1));
+    assert_eq!(latency.last(), Some(Duration::from_secs(25)));
+    assert_eq!(latency.max(), Some(Duration::from_secs(25)));
+    assert_eq!(latency.average(), Some(Duration::from_millis(12_505)));
+}
+
+#[test]
+fn prompt_countdown_ends_before_the_daemon_gives_up() {
+    let (tx, _rx) = oneshot::channel();
+    let prompt = PromptDialog::new(Connection::default(), "node".to_string(), tx);
+    assert_eq!(prompt.timeout_secs, 15);
+    let prompt = prompt.with_deadline(ask_deadline(10));
+    assert_eq!(prompt.timeout_secs, 8);
+    // A longer daemon timeout doesn't stretch the UI's own
+    let (tx, _rx) = oneshot::channel();
+    let prompt = PromptDialog::new(Connection::default(), "node".to_string(), tx).with_deadline(ask_deadline(120));
+    assert_eq!(prompt.timeout_secs, 15);
+}
@@ -0,0 +1,53 @@
+//! The panic button and the configuration it brings back
+
+use tokio::sync::broadcast;
+
+use opensnitch_tui::app::AppState;
+use opensnitch_tui::db::Database;
+use opensnitch_tui::models::node::ClientConfig;
+
+const CONFIG: &str = r#"{"DefaultAction":"allow","InterceptUnknown":false}"#;
+
+fn config() -> ClientConfig {
+    ClientConfig {
+        name: "laptop".to_string(),
+        version: "1.6.6".to_string(),
+        config: CONFIG.to_string(),
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn a_panic_survives_a_restart_until_lifted() {
+    let path = std::env::temp_dir().join(format!("opensnitch-tui-panic-{}.db", std::process::id()));
+    let path = path.to_string_lossy().to_string();
+
+    {
+        let (ui_update_tx, _) = broadcast::channel(100);
+        let state = AppState::new(Database::open(&path).unwrap(), ui_update_tx);
+        state.nodes.write().await.add_node("node-a", config());
+        state.set_panic("node-a", true).await;
+        assert!(state.in_panic("node-a"));
+        assert_ne!(state.nodes.read().await.get_node("node-a").unwrap().config, CONFIG);
+    }
+
+    let (ui_update_tx, _) = broadcast::channel(100);
+    let state = AppState::new(Database::open(&path).unwrap(), ui_update_tx);
+    state.restore_panics();
+    assert_eq!(state.panicked_nodes(), ["node-a"]);
+
+    // Offered to lift once the node is back, and only once
+    assert_eq!(state.take_restored_panic().await, None);
+    state.nodes.write().await.add_node("node-a", config());
+    assert_eq!(state.take_restored_panic().await, Some(("node-a".to_string(), "laptop".to_string())));
+    assert_eq!(state.take_restored_panic().await, None);
+
+    state.set_panic("node-a", false).await;
+    assert!(!state.in_panic("node-a"));
+    assert_eq!(state.nodes.read().await.get_node("node-a").unwrap().config, CONFIG);
+    assert!(state.db.select_panics().unwrap().is_empty());
+
+    for suffix in ["", "-wal", "-shm"] {
+        let _ = std::fs::remove_file(format!("{}{}", path, suffix));
+    }
+}
@@ -0,0 +1,66 @@
+//! Data and state directories
+
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+
+use opensnitch_tui::config::paths::{create_private_dir, runtime_dir, runtime_dir_in};
+use opensnitch_tui::config::{DataDirs, Settings};
+
+#[test]
+fn data_dir_holds_everything() {
+    let dirs = DataDirs::at(Path::new("/srv/osui"));
+    assert_eq!(dirs.database(), Path::new("/srv/osui/opensnitch.db"));
+    assert_eq!(dirs.session(), Path::new("/srv/osui/state/session.json"));
+    assert_eq!(dirs.daemon_socket(), Path::new("/srv/osui/state/daemon.sock"));
+    assert_eq!(dirs.exports(), Path::new("/srv/osui/exports"));
+
+    // An explicit database path still wins
+    let mut settings = Settings { data_dir: "/srv/osui".to_string(), ..Default::default() };
+    assert_eq!(settings.database_file(), "/srv/osui/opensnitch.db");
+    settings.database_path = ":memory:".to_string();
+    assert_eq!(settings.database_file(), ":memory:");
+}
+
+#[test]
+fn follows_xdg_directories() {
+    std::env::set_var("XDG_DATA_HOME", "/xdg/data");
+    std::env::set_var("XDG_STATE_HOME", "relative/state");
+    let dirs = DataDirs::xdg();
+    assert_eq!(dirs.exports(), Path::new("/xdg/data/opensnitch-tui/exports"));
+
+    // Relative paths are invalid and fall back to ~/.local/state
+    assert!(dirs.session().ends_with(".local/state/opensnitch-tui/session.json"));
+}
+
+#[test]
+fn sockets_go_to_a_private_runtime_directory() {
+    let base = std::env::temp_dir().join(format!("opensnitch-tui-runtime-{}", std::process::id()));
+    std::fs::create_dir_all(&base).unwrap();
+    let dir = runtime_dir_in(Some(base.clone()));
+    assert_eq!(dir, base.join("opensnitch-tui"));
+    // Relative paths are invalid, /tmp holds a directory per user instead
+    assert!(runtime_dir_in(Some("relative".into())).starts_with(std::env::temp_dir()));
+    assert_eq!(Path::new(&Settings::default().control_socket), runtime_dir().join("control.sock"));
+
+    create_private_dir(&dir).unwrap();
+    assert_eq!(std::fs::metadata(&dir).unwrap().permissions().mode() & 0o777, 0o700);
+    // Once there it's checked, not trusted
+    create_private_dir(&dir).unwrap();
+    std::fs::set_permissions(&dir, std::fs::Permissions::from_mode(0o755)).unwrap();
+    assert!(create_private_dir(&dir).is_err());
+    std::fs::remove_dir(&dir).unwrap();
+    std::os::unix::fs::symlink(&base, &dir).unwrap();
+    assert!(create_private_dir(&dir).is_err());
+
+    // Settings saved with the old default in /tmp move along
+    let config = base.join("config.json");
+    std::fs::write(
+        &config,
+        r#"{"control_socket": "/tmp/opensnitch-tui.sock", "server_fallback": ["127.0.0.1:50052", "unix:///tmp/osui-tui.sock"]}"#,
+    )
+    .unwrap();
+    let settings = Settings::load(config.to_str()).unwrap();
+    assert_eq!(Path::new(&settings.control_socket), runtime_dir().join("control.sock"));
+    assert_eq!(settings.server_fallback[1], format!("unix://{}", runtime_dir().join("grpc.sock").display()));
+    std::fs::remove_dir_all(&base).unwrap();
+}
@@ -0,0 +1,151 @@
+//! Events and alerts in the database: buffered through outages, restored at startup
+
+mod common;
+
+use tokio::sync::broadcast;
+
+use opensnitch_tui::app::AppState;
+use opensnitch_tui::db::Database;
+use opensnitch_tui::models::{
+    Alert, AlertData, AlertPriority, AlertType, AlertWhat, Connection, Event, Operator, Rule, RuleAction, RuleDuration,
+};
+
+use common::connection;
+
+fn event(pid: u32) -> Event {
+    Event::new(Connection { process_id: pid, ..connection("/usr/bin/curl", "example.org", 443) }, None)
+}
+
+#[tokio::test]
+async fn writes_are_buffered_until_the_database_works() {
+    // A file where the database directory should be
+    let dir = std::env::temp_dir().join(format!("opensnitch-tui-outage-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::write(&dir, "").unwrap();
+    let path = dir.join("opensnitch-tui.db").to_string_lossy().to_string();
+
+    let (db, error) = Database::open_or_memory(&path);
+    let (ui_update_tx, _) = broadcast::channel(100);
+    let state = AppState::new(db, ui_update_tx);
+    state.db_failed(error.expect("database directory is a file").to_string());
+
+    for pid in 1..=3 {
+        state.add_connection(event(pid)).await;
+    }
+    let alert = Alert::new(1, AlertType::Error, AlertPriority::High, AlertWhat::Generic, Some(AlertData::Text("x".into())));
+    state.add_alert(alert).await;
+
+    // Still shown, waiting for the database
+    assert_eq!(state.connections.read().await.len(), 3);
+    assert_eq!(state.db_outage().unwrap().buffered, 4);
+    assert!(!state.retry_db());
+    assert_eq!(state.db_outage().unwrap().buffered, 4);
+
+    std::fs::remove_file(&dir).unwrap();
+    assert!(state.retry_db());
+    assert_eq!(state.db_outage(), None);
+    assert_eq!(state.db.connection_count().unwrap(), 3);
+    assert_eq!(state.db.alert_count().unwrap(), 1);
+
+    // Straight through again
+    state.add_connection(event(4)).await;
+    assert_eq!(state.db.connection_count().unwrap(), 4);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn recent_history_is_restored_at_startup() {
+    let db = Database::open(":memory:").unwrap();
+    for pid in 1..=3 {
+        let mut stored = event(pid);
+        stored.time = format!("2024-03-01T10:00:0{}+00:00", pid);
+        db.insert_connection(&stored).unwrap();
+    }
+    let alert = Alert::new(1, AlertType::Warning, AlertPriority::Low, AlertWhat::Generic, Some(AlertData::Text("x".into())));
+    db.insert_alert(&alert).unwrap();
+
+    let (ui_update_tx, _) = broadcast::channel(100);
+    let state = AppState::new(db, ui_update_tx).with_limits(2, 10);
+    state.restore_history().await;
+
+    // Only as many as are kept, newest first
+    let connections = state.connections.read().await;
+    let pids: Vec<u32> = connections.iter().map(|e| e.connection.process_id).collect();
+    assert_eq!(pids, [3, 2]);
+    assert!(connections.iter().all(|e| e.restored));
+    drop(connections);
+    let alerts = state.alerts.read().await;
+    assert_eq!(alerts.len(), 1);
+    assert!(alerts[0].restored);
+    drop(alerts);
+
+    // Live events go on top and aren't marked
+    state.add_connection(event(4)).await;
+    let connections = state.connections.read().await;
+    assert_eq!(connections.len(), 2);
+    assert!(!connections[0].restored);
+    assert_eq!(connections[1].connection.process_id, 3);
+}
+
+#[tokio::test]
+async fn acknowledgements_are_stored() {
+    let (ui_update_tx, _) = broadcast::channel(100);
+    let state = AppState::new(Database::open(":memory:").unwrap(), ui_update_tx);
+    for (id, node) in [(1, "node-a"), (2, "node-b"), (3, "node-a")] {
+        let mut alert = Alert::new(id, AlertType::Warning, AlertPriority::Low, AlertWhat::Generic, Some(AlertData::Text("x".into())));
+        alert.node = node.to_string();
+        state.add_alert(alert).await;
+    }
+
+    let second = state.alerts.read().await[1].clone();
+    state.acknowledge_alert(second.id, second.timestamp).await;
+    let stored: Vec<_> = state.db.select_alerts(10).unwrap().into_iter().map(|a| (a.node, a.acknowledged)).collect();
+    assert_eq!(stored, [("node-a".to_string(), false), ("node-b".to_string(), true), ("node-a".to_string(), false)]);
+
+    state.acknowledge_all_alerts().await;
+    assert!(state.db.select_alerts(10).unwrap().iter().all(|a| a.acknowledged));
+}
+
+#[tokio::test]
+async fn rule_hits_are_counted_and_kept_across_runs() {
+    let path = std::env::temp_dir().join(format!("opensnitch-tui-hits-{}.db", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let path = path.to_string_lossy().to_string();
+    let rule = Rule::new("allow-curl", RuleAction::Allow, RuleDuration::Always, Operator::simple("process.path", "/usr/bin/curl"));
+
+    let (ui_update_tx, _) = broadcast::channel(100);
+    let state = AppState::new(Database::open(&path).unwrap(), ui_update_tx.clone());
+    for (pid, second) in [(1, 1), (2, 2)] {
+        let mut hit = event(pid);
+        hit.rule = Some(rule.clone());
+        hit.time = format!("2024-03-01T10:00:0{}+00:00", second);
+        state.record_rule_hit("node-a", &hit);
+    }
+    // A minute later the count is stored again
+    let mut hit = event(3);
+    hit.rule = Some(rule.clone());
+    hit.time = "2024-03-01T10:01:05+00:00".to_string();
+    state.record_rule_hit("node-a", &hit);
+    state.record_rule_hit("node-a", &event(4));
+
+    let hits = state.rule_hits.lock().unwrap().node_hits("node-a");
+    assert_eq!(hits["allow-curl"].count, 3);
+    assert!(state.rule_hits.lock().unwrap().node_hits("node-b").is_empty());
+
+    // Editing the rule keeps its hits
+    state.db.insert_rule("node-a", &rule).unwrap();
+    drop(state);
+
+    let state = AppState::new(Database::open(&path).unwrap(), ui_update_tx);
+    state.restore_rule_hits();
+    let hits = state.rule_hits.lock().unwrap().node_hits("node-a");
+    assert_eq!(hits["allow-curl"].count, 3);
+    assert_eq!(hits["allow-curl"].last_hit.unwrap().to_rfc3339(), "2024-03-01T10:01:05+00:00");
+    let stored: Vec<_> = state.db.select_rules("node-a").unwrap().into_iter().map(|r| r.name).collect();
+    assert_eq!(stored, ["allow-curl"]);
+    drop(state);
+
+    for suffix in ["", "-wal", "-shm"] {
+        let _ = std::fs::remove_file(format!("{}{}", path, suffix));
+    }
+}
@@ -0,0 +1,203 @@
+//! Simulating rule precedence to catch rules that would never apply
+
+use opensnitch_tui::models::merge::extension;
+use opensnitch_tui::models::precedence::{conflicts, shadowing, verdict, Replay, Shadow};
+use opensnitch_tui::models::{Connection, Event, Operator, OperatorType, Rule, RuleAction, RuleDuration};
+
+fn curl() -> Connection {
+    Connection {
+        protocol: "tcp".to_string(),
+        dst_ip: "198.51.100.7".to_string(),
+        dst_host: "example.org".to_string(),
+        dst_port: 443,
+        user_id: 1000,
+        process_path: "/usr/bin/curl".to_string(),
+        ..Default::default()
+    }
+}
+
+fn rule(name: &str, action: RuleAction, operator: Operator) -> Rule {
+    Rule::new(name, action, RuleDuration::Always, operator)
+}
+
+#[test]
+fn operators_match_like_the_daemon() {
+    let conn = curl();
+    assert!(Operator::simple("process.path", "/USR/bin/curl").matches(&conn));
+    assert!(!Operator::simple("process.path", "/USR/bin/curl").with_sensitive(true).matches(&conn));
+    assert!(Operator::regexp("dest.host", r"\.org$").matches(&conn));
+    assert!(Operator::network("dest.network", "198.51.100.0/24").matches(&conn));
+    assert!(!Operator::network("dest.network", "10.0.0.0/8").matches(&conn));
+    assert!(Operator::list(vec![
+        Operator::simple("dest.port", "443"),
+        Operator::simple("user.id", "1000"),
+    ])
+    .matches(&conn));
+    assert!(!Operator::list(vec![
+        Operator::simple("dest.port", "443"),
+        Operator::simple("user.id", "0"),
+    ])
+    .matches(&conn));
+    // Lists live on the daemon's disk
+    assert!(!Operator::simple("lists.domains", "/etc/blocklists").matches(&conn));
+}
+
+#[test]
+fn deny_and_precedence_rules_decide_first() {
+    let conn = curl();
+    let rules = [
+        rule("a-allow-curl", RuleAction::Allow, Operator::simple("process.path", "/usr/bin/curl")),
+        rule("b-deny-org", RuleAction::Deny, Operator::regexp("dest.host", r"\.org$")),
+        rule("c-allow-443", RuleAction::Allow, Operator::simple("dest.port", "443")),
+    ];
+    assert_eq!(verdict(&rules, &conn).map(|r| r.name.as_str()), Some("b-deny-org"));
+
+    let mut rules = rules.to_vec();
+    rules[1].enabled = false;
+    // The last matching allow wins
+    assert_eq!(verdict(&rules, &conn).map(|r| r.name.as_str()), Some("c-allow-443"));
+}
+
+#[test]
+fn warns_about_ineffective_and_redundant_rules() {
+    let conn = curl();
+    let existing = vec![
+        rule("deny-org", RuleAction::Deny, Operator::regexp("dest.host", r"\.org$")).with_precedence(true),
+        rule("allow-https", RuleAction::Allow, Operator::simple("dest.port", "443")),
+    ];
+    let allow = rule("curl-example", RuleAction::Allow, Operator::simple("process.path", "/usr/bin/curl"));
+    match shadowing(&existing, &allow, &conn) {
+        Some(Shadow::Overridden(winner)) => assert_eq!(winner.name, "deny-org"),
+        other => panic!("expected the deny rule to win, got {:?}", other),
+    }
+
+    // A broad allow already covers the new allow
+    let broad = &existing[1..];
+    match shadowing(broad, &allow, &conn) {
+        Some(Shadow::Redundant(winner)) => assert_eq!(winner.name, "allow-https"),
+        other => panic!("expected a redundant rule, got {:?}", other),
+    }
+
+    // A deny beats the broad allow, so it is effective
+    let deny = rule("curl-example", RuleAction::Deny, Operator::simple("process.path", "/usr/bin/curl"));
+    assert!(shadowing(broad, &deny, &conn).is_none());
+    assert!(shadowing(&[], &allow, &conn).is_none());
+}
+
+#[test]
+fn replays_past_connections_against_todays_rules() {
+    let allow = rule("allow-curl", RuleAction::Allow, Operator::simple("process.path", "/usr/bin/curl"));
+    let event = Event::new(curl(), Some(allow.clone()));
+
+    let replay = Replay::of(std::slice::from_ref(&allow), &event);
+    assert!(!replay.changed());
+    assert!(replay.message().contains("Unchanged: today's rules would still allow it."));
+
+    // A deny added since then decides first
+    let rules = [allow, rule("block-org", RuleAction::Deny, Operator::regexp("dest.host", r"\.org$"))];
+    let replay = Replay::of(&rules, &event);
+    assert_eq!(replay.now, Some(("block-org".to_string(), RuleAction::Deny)));
+    assert!(replay.changed());
+    assert!(replay.message().starts_with("Then: allow by rule 'allow-curl'\nNow:  deny by rule 'block-org'"));
+
+    // Without rules the daemon would ask
+    let replay = Replay::of(&[], &event);
+    assert_eq!(replay.now, None);
+    assert!(replay.message().contains("the daemon would ask"));
+}
+
+#[test]
+fn conflicting_rules_with_the_opposite_action() {
+    let curl_path = || Operator::simple("process.path", "/usr/bin/curl");
+    let existing = vec![
+        rule("000-deny-curl", RuleAction::Deny, curl_path()),
+        rule(
+            "500-allow-curl-https",
+            RuleAction::Allow,
+            Operator::list(vec![curl_path(), Operator::simple("dest.port", "443")]),
+        ),
+        rule("600-deny-wget", RuleAction::Deny, Operator::simple("process.path", "/usr/bin/wget")),
+        rule("700-deny-port-80", RuleAction::Deny, Operator::simple("dest.port", "80")),
+    ];
+
+    let new_rule = rule("100-allow-curl", RuleAction::Allow, curl_path());
+    let found = conflicts(&existing, &new_rule);
+    let names: Vec<_> = found.iter().map(|c| c.rule.name.as_str()).collect();
+    // Same action, another process and an unrelated port don't conflict
+    assert_eq!(names, ["000-deny-curl"]);
+    assert!(!found[0].new_rule_wins);
+    assert!(found[0].message().contains("it is applied first"));
+
+    // Denies are applied right away, whatever comes after
+    let new_rule = rule("900-deny-curl-https", RuleAction::Deny, Operator::list(vec![
+        Operator::simple("dest.port", "443"),
+        curl_path(),
+    ]));
+    let found = conflicts(&existing, &new_rule);
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].rule.name, "500-allow-curl-https");
+    assert!(found[0].new_rule_wins);
+    assert!(found[0].message().contains("this rule is applied first"));
+
+    // Different exact values on the same operand never match together
+    let new_rule = rule("900-allow-curl-ssh", RuleAction::Allow, Operator::list(vec![
+        curl_path(),
+        Operator::simple("dest.port", "22"),
+    ]));
+    assert!(conflicts(&existing[..1], &new_rule).len() == 1);
+    assert!(conflicts(&existing[3..], &new_rule).is_empty());
+
+    // Editing a rule doesn't conflict with its old version
+    let mut new_rule = rule("000-deny-curl", RuleAction::Allow, curl_path());
+    assert!(conflicts(&existing[..1], &new_rule).is_empty());
+    new_rule.name = "050-allow-curl".to_string();
+    new_rule.enabled = false;
+    assert!(conflicts(&existing, &new_rule).is_empty());
+}
+
+#[test]
+fn prompt_answers_extend_nearly_identical_rules() {
+    let curl_to = |name: &str, port: &str| {
+        rule(name, RuleAction::Allow, Operator::list(vec![
+            Operator::simple("process.path", "/usr/bin/curl"),
+            Operator::simple("dest.port", port),
+        ]))
+    };
+    let existing = vec![
+        rule("deny-curl", RuleAction::Deny, Operator::simple("process.path", "/usr/bin/curl")),
+        curl_to("curl-https", "443"),
+    ];
+
+    let ext = extension(&existing, &curl_to("curl-8443", "8443")).expect("same program, new port");
+    assert_eq!(ext.rule.name, "curl-https");
+    assert_eq!((ext.operand.as_str(), ext.value.as_str()), ("dest.port", "8443"));
+    assert_eq!(ext.rule.operator.list[1].op_type, OperatorType::Regexp);
+    assert_eq!(ext.rule.operator.list[1].data, "^(443|8443)$");
+    // Both ports now match, the program still has to
+    let mut conn = curl();
+    assert!(ext.rule.operator.matches(&conn));
+    conn.dst_port = 8443;
+    assert!(ext.rule.operator.matches(&conn));
+    conn.process_path = "/usr/bin/wget".to_string();
+    assert!(!ext.rule.operator.matches(&conn));
+
+    // Extending again adds another alternative
+    let ext = extension(&[ext.rule], &curl_to("curl-80", "80")).unwrap();
+    assert_eq!(ext.rule.operator.list[1].data, "^(443|8443|80)$");
+
+    // Another program, another action or two differences are separate rules
+    let wget = rule("wget-https", RuleAction::Allow, Operator::list(vec![
+        Operator::simple("process.path", "/usr/bin/wget"),
+        Operator::simple("dest.port", "443"),
+    ]));
+    assert!(extension(&existing, &wget).is_none());
+    let mut deny = curl_to("deny-8443", "8443");
+    deny.action = RuleAction::Deny;
+    assert!(extension(&existing, &deny).is_none());
+    let other = rule("other", RuleAction::Allow, Operator::list(vec![
+        Operator::simple("process.path", "/usr/bin/curl"),
+        Operator::simple("dest.port", "80"),
+        Operator::simple("user.id", "0"),
+    ]));
+    assert!(extension(&existing, &other).is_none());
+}
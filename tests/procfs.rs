@@ -0,0 +1,98 @@
+//! Process details read from /proc when local daemons leave them out
+
+mod common;
+
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use opensnitch_tui::models::Connection;
+use opensnitch_tui::utils::host::is_local_node;
+use opensnitch_tui::utils::process::{read_proc, start_time, ProcCache, ENRICHED_ARGS, ENRICHED_CWD};
+
+use common::connection;
+
+const TTL: Duration = Duration::from_secs(60);
+
+/// A proc tree with one process
+fn proc_root(name: &str) -> PathBuf {
+    let root = std::env::temp_dir().join(format!("opensnitch-tui-proc-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&root);
+    let dir = root.join("4242");
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("cmdline"), b"/usr/bin/curl\0-s\0https://example.org\0").unwrap();
+    write_stat(&root, 7000);
+    std::os::unix::fs::symlink("/usr/bin/curl", dir.join("exe")).unwrap();
+    std::os::unix::fs::symlink("/home/user", dir.join("cwd")).unwrap();
+    root
+}
+
+/// Stat line of the process, started `start` ticks after boot
+fn write_stat(root: &std::path::Path, start: u64) {
+    let stat = format!("4242 (cu rl) S 1 4242 4242 0 -1 4194560 0 0 0 0 0 0 0 0 20 0 1 0 {} 0 0", start);
+    std::fs::write(root.join("4242").join("stat"), stat).unwrap();
+}
+
+#[test]
+fn missing_fields_are_filled_and_marked() {
+    let root = proc_root("fill");
+    let info = read_proc(&root, 4242).unwrap();
+    assert_eq!(info.args, ["/usr/bin/curl", "-s", "https://example.org"]);
+    assert_eq!(read_proc(&root, 1), None);
+    assert_eq!(start_time(&root, 4242), Some(7000));
+
+    let mut cache = ProcCache::new(&root, TTL);
+    let now = Instant::now();
+
+    let mut conn = Connection { process_id: 4242, ..connection("/usr/bin/curl", "", 0) };
+    cache.enrich(&mut conn, now);
+    assert_eq!(conn.process_args.len(), 3);
+    assert_eq!(conn.process_cwd, "/home/user");
+    assert_eq!(conn.enriched, [ENRICHED_ARGS, ENRICHED_CWD]);
+
+    // Reported fields are kept
+    let mut conn = Connection { process_id: 4242, ..connection("/usr/bin/curl", "", 0) };
+    conn.process_cwd = "/tmp".to_string();
+    cache.enrich(&mut conn, now);
+    assert_eq!(conn.process_cwd, "/tmp");
+    assert_eq!(conn.enriched, [ENRICHED_ARGS]);
+
+    // The PID now belongs to another program
+    let mut conn = Connection { process_id: 4242, ..connection("/usr/bin/wget", "", 0) };
+    cache.enrich(&mut conn, now);
+    assert!(conn.process_args.is_empty());
+    assert!(conn.enriched.is_empty());
+    std::fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn processes_are_read_once_per_start() {
+    let root = proc_root("cache");
+    let mut cache = ProcCache::new(&root, TTL);
+    let now = Instant::now();
+    assert_eq!(cache.get(4242, now).unwrap().args.len(), 3);
+
+    // Served from the cache while the same process runs
+    std::fs::write(root.join("4242").join("cmdline"), b"/usr/bin/curl\0").unwrap();
+    assert_eq!(cache.get(4242, now + Duration::from_secs(5)).unwrap().args.len(), 3);
+    // Read again once stale
+    assert_eq!(cache.get(4242, now + TTL).unwrap().args.len(), 1);
+
+    // The PID was reused by a newer process
+    std::fs::write(root.join("4242").join("cmdline"), b"/usr/bin/curl\0-I\0").unwrap();
+    write_stat(&root, 9000);
+    assert_eq!(cache.get(4242, now + TTL).unwrap().args.len(), 2);
+
+    // Gone with the process
+    std::fs::remove_dir_all(root.join("4242")).unwrap();
+    assert_eq!(cache.get(4242, now + TTL), None);
+    std::fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn only_local_nodes_are_enriched() {
+    assert!(is_local_node("unix:///tmp/osui.sock"));
+    assert!(is_local_node("127.0.0.1:50051"));
+    assert!(is_local_node("[::1]:50051"));
+    assert!(!is_local_node("192.0.2.7:50051"));
+    assert!(!is_local_node("unknown"));
+}
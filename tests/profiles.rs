@@ -0,0 +1,42 @@
+//! Shipped application profiles
+
+mod common;
+
+use std::collections::HashSet;
+
+use opensnitch_tui::models::profile::catalog;
+use opensnitch_tui::models::{Connection, RuleAction};
+
+use common::connection;
+
+#[test]
+fn profiles_have_unique_rule_names() {
+    let mut names = HashSet::new();
+    for profile in catalog() {
+        for rule in profile.rules() {
+            assert_eq!((rule.action, rule.profile()), (RuleAction::Allow, Some(profile.id)));
+            assert!(names.insert(rule.name.clone()), "{} twice", rule.name);
+        }
+    }
+}
+
+#[test]
+fn profile_rules_only_allow_what_the_application_needs() {
+    let profiles = catalog();
+    let rules = |id: &str| profiles.iter().find(|p| p.id == id).unwrap().rules();
+    let allowed = |id: &str, conn: &Connection| rules(id).iter().any(|rule| rule.operator.matches(conn));
+
+    assert!(allowed("firefox", &connection("/usr/lib/firefox/firefox", "example.org", 443)));
+    assert!(allowed("firefox", &Connection { protocol: "udp".to_string(), ..connection("/usr/lib/firefox/firefox", "example.org", 443) }));
+    assert!(!allowed("firefox", &connection("/usr/lib/firefox/firefox", "example.org", 22)));
+    assert!(!allowed("firefox", &connection("/usr/bin/curl", "example.org", 443)));
+
+    assert!(allowed("apt", &connection("/usr/lib/apt/methods/http", "example.org", 80)));
+    assert!(allowed("thunderbird", &connection("/usr/lib/thunderbird/thunderbird", "example.org", 993)));
+    assert!(allowed("steam", &Connection { protocol: "udp".to_string(), ..connection("/home/me/.local/share/Steam/ubuntu12_32/steam", "example.org", 27036) }));
+    assert!(!allowed("steam", &Connection { protocol: "udp".to_string(), ..connection("/home/me/.local/share/Steam/ubuntu12_32/steam", "example.org", 27060) }));
+    assert!(allowed("steam", &connection("/usr/games/steam", "store.steampowered.com", 443)));
+    // Anything else named steam is not Steam
+    assert!(!allowed("steam", &connection("/tmp/steam", "example.org", 443)));
+    assert!(!allowed("steam", &connection("/home/me/Downloads/steamwebhelper", "example.org", 443)));
+}
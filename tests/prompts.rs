@@ -0,0 +1,344 @@
+//! Prompts written ahead and the ones missed
+
+mod common;
+
+use std::time::Duration;
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::layout::Rect;
+use tokio::sync::{broadcast, oneshot};
+
+use opensnitch_tui::app::state::{PendingPrompt, PromptBatch};
+use opensnitch_tui::app::AppState;
+use opensnitch_tui::config::{PromptPosition, Settings};
+use opensnitch_tui::db::Database;
+use opensnitch_tui::models::node::ask_deadline;
+use opensnitch_tui::models::{OperatorType, PromptOutcome, RuleAction, RuleDuration};
+use opensnitch_tui::ui::dialogs::missed::{MissedPromptsDialog, MissedPromptsResult};
+use opensnitch_tui::ui::dialogs::prompt::PromptDialog;
+use opensnitch_tui::ui::dialogs::prompt_settings::{PromptSettingsDialog, PromptSettingsResult};
+use opensnitch_tui::ui::theme::Theme;
+use opensnitch_tui::utils::domain::is_public_suffix;
+
+use common::connection;
+
+#[test]
+fn prompts_left_pending_are_lost_on_restart() {
+    let db = Database::open(":memory:").unwrap();
+    let answered = db.insert_prompt("node-a", &connection("/usr/bin/curl", "answered.org", 443)).unwrap();
+    let timed_out = db.insert_prompt("node-a", &connection("/usr/bin/curl", "slow.org", 443)).unwrap();
+    db.insert_prompt("node-a", &connection("/usr/bin/curl", "crash.org", 443)).unwrap();
+    db.set_prompt_outcome(answered, PromptOutcome::Answered).unwrap();
+    db.set_prompt_outcome(timed_out, PromptOutcome::TimedOut).unwrap();
+
+    // What the next start finds
+    assert_eq!(db.mark_pending_prompts_lost().unwrap(), 1);
+    let missed = db.select_missed_prompts(10).unwrap();
+    let mut hosts: Vec<_> = missed
+        .iter()
+        .map(|p| (p.connection.dst_host.as_str(), p.outcome))
+        .collect();
+    hosts.sort_by_key(|(host, _)| *host);
+    assert_eq!(
+        hosts,
+        vec![("crash.org", PromptOutcome::Lost), ("slow.org", PromptOutcome::TimedOut)]
+    );
+    assert!(missed.iter().all(|p| p.node_addr == "node-a"));
+}
+
+#[tokio::test]
+async fn missed_prompts_become_rules() {
+    let db = Database::open(":memory:").unwrap();
+    db.insert_prompt("node-a", &connection("/usr/bin/curl", "example.org", 443)).unwrap();
+    let (tx, _) = broadcast::channel(4);
+    let state = AppState::new(db, tx);
+    state.recover_missed_prompts().await;
+
+    let missed = state.missed_prompts.read().await.clone();
+    assert_eq!(missed.len(), 1);
+    let mut dialog = MissedPromptsDialog::new(missed);
+    let Some(MissedPromptsResult::Create { id, node_addr, rule }) =
+        dialog.handle_key(KeyEvent::new(KeyCode::Char('d'), KeyModifiers::NONE))
+    else {
+        panic!("expected a rule");
+    };
+    assert_eq!(node_addr, "node-a");
+    assert_eq!(rule.action, RuleAction::Deny);
+    assert!(rule.operator.list.iter().any(|op| op.operand == "dest.host" && op.data == "example.org"));
+    assert!(dialog.is_empty());
+
+    // Resolved prompts are not missed anymore, now or on the next start
+    state.set_prompt_outcome(Some(id), PromptOutcome::Resolved).await;
+    assert!(state.missed_prompts.read().await.is_empty());
+    state.recover_missed_prompts().await;
+    assert!(state.missed_prompts.read().await.is_empty());
+}
+
+fn screen(dialog: &PromptDialog, width: u16, height: u16) -> Vec<String> {
+    let mut terminal = ratatui::Terminal::new(ratatui::backend::TestBackend::new(width, height)).unwrap();
+    let frame = terminal.draw(|f| dialog.render(f, &Theme::default())).unwrap();
+    let symbols: Vec<&str> = frame.buffer.content().iter().map(|cell| cell.symbol()).collect();
+    symbols.chunks(width as usize).map(|row| row.concat()).collect()
+}
+
+#[test]
+fn small_terminals_get_the_compact_prompt() {
+    let (tx, _rx) = oneshot::channel();
+    let mut dialog = PromptDialog::new(connection("/usr/bin/curl", "example.org", 443), "node".to_string(), tx);
+
+    assert!(!dialog.is_compact(Rect::new(0, 0, 80, 24)));
+    assert!(dialog.is_compact(Rect::new(0, 0, 50, 24)));
+    assert!(dialog.is_compact(Rect::new(0, 0, 80, 12)));
+
+    let rows = screen(&dialog, 50, 12);
+    let text = rows.concat();
+    assert!(text.contains("curl → example.org:443"), "{}", text);
+    assert!(text.contains("[a] ALLOW"), "{}", text);
+
+    // Advanced options overflow, the focused one scrolls into view
+    dialog.handle_key(KeyEvent::new(KeyCode::Char(' '), KeyModifiers::NONE));
+    for _ in 0..4 {
+        dialog.handle_key(KeyEvent::new(KeyCode::Down, KeyModifiers::NONE));
+    }
+    let text = screen(&dialog, 50, 8).concat();
+    assert!(text.contains("▶ [ ] Executable checksum"), "{}", text);
+    assert!(!text.contains("curl → example.org"), "{}", text);
+}
+
+#[test]
+fn prompts_dock_at_the_bottom() {
+    let (tx, _rx) = oneshot::channel();
+    let dialog = PromptDialog::new(connection("/usr/bin/curl", "example.org", 443), "node".to_string(), tx)
+        .with_position(PromptPosition::Bottom);
+
+    let rows = screen(&dialog, 100, 40);
+    assert!(rows[17].trim().is_empty(), "{}", rows[17]);
+    assert!(rows[18].starts_with('┌') && rows[18].trim_end().ends_with('┐'), "{}", rows[18]);
+    assert!(rows[18].contains("New Connection"));
+    assert!(rows[39].starts_with('└'));
+}
+
+#[tokio::test]
+async fn bursts_of_identical_prompts_are_answered_in_a_batch() {
+    let (tx, _) = broadcast::channel(4);
+    let state = AppState::new(Database::open(":memory:").unwrap(), tx);
+    let mut answers = Vec::new();
+    for host in ["example.org", "example.org", "other.org", "example.org", "example.org"] {
+        let (response_tx, response_rx) = oneshot::channel();
+        answers.push(response_rx);
+        state.pending_prompts.write().await.push_back(PendingPrompt {
+            connection: connection("/usr/bin/curl", host, 443),
+            node_addr: "node-a".to_string(),
+            id: None,
+            response_tx,
+        });
+    }
+    // The daemon gave up on the first one
+    drop(answers.remove(0));
+
+    let shown = state.next_prompt().await.unwrap();
+    assert_eq!(shown.connection.dst_host, "example.org");
+    assert_eq!(state.pending_prompts.read().await.len(), 3);
+    let mut dialog = PromptDialog::new(shown.connection, shown.node_addr, shown.response_tx);
+    dialog.queued = 3;
+    let text = screen(&dialog, 100, 30).concat();
+    assert!(text.contains("3 queued  b=batch  n=skip"), "{}", text);
+
+    // Deny this one and the next two alike, cycling 5 then back past 100 to 0 and 5
+    for _ in 0..6 {
+        dialog.handle_key(KeyEvent::new(KeyCode::Char('b'), KeyModifiers::NONE));
+    }
+    assert_eq!(dialog.batch, 5);
+    assert!(dialog.handle_key(KeyEvent::new(KeyCode::Char('d'), KeyModifiers::NONE)));
+    let rule = dialog.answered().unwrap().clone();
+    let mut batch = PromptBatch {
+        node_addr: dialog.node_addr.clone(),
+        connection: dialog.connection.clone(),
+        rule,
+        remaining: 2,
+    };
+    assert_eq!(state.answer_batch(&mut batch).await, 2);
+    assert_eq!(batch.remaining, 0);
+
+    let mut answers = answers.into_iter();
+    assert_eq!(answers.next().unwrap().await.unwrap().action, RuleAction::Deny);
+    // The other destination waits for its own answer
+    let mut other = answers.next().unwrap();
+    assert!(other.try_recv().is_err());
+    for answer in answers {
+        assert_eq!(answer.await.unwrap().action, RuleAction::Deny);
+    }
+    let queued = state.pending_prompts.read().await;
+    assert_eq!(queued.len(), 1);
+    assert_eq!(queued[0].connection.dst_host, "other.org");
+}
+
+#[test]
+fn prompts_are_skipped_only_with_others_queued() {
+    let (tx, mut rx) = oneshot::channel();
+    let mut dialog = PromptDialog::new(connection("/usr/bin/curl", "example.org", 443), "node".to_string(), tx);
+    assert!(!dialog.handle_key(KeyEvent::new(KeyCode::Char('n'), KeyModifiers::NONE)));
+
+    dialog.queued = 2;
+    assert!(dialog.handle_key(KeyEvent::new(KeyCode::Char('n'), KeyModifiers::NONE)));
+    assert!(dialog.answered().is_none());
+    assert!(dialog.response_tx.is_some());
+    assert!(rx.try_recv().is_err());
+}
+
+#[test]
+fn advanced_options_generalize_the_rule() {
+    let (tx, _rx) = oneshot::channel();
+    let mut dialog = PromptDialog::new(connection("/usr/bin/curl", "www.example.org", 443), "node".to_string(), tx);
+    dialog.duration = RuleDuration::Always;
+    dialog.handle_key(KeyEvent::new(KeyCode::Char(' '), KeyModifiers::NONE));
+    let text = screen(&dialog, 100, 40).concat();
+    assert!(text.contains("[ ] Any program in /usr/bin/"), "{}", text);
+    assert!(text.contains("[ ] *.example.org"), "{}", text);
+    assert!(text.contains("[ ] Network 93.184.216.0/24"), "{}", text);
+
+    // The last three options, from the top going up
+    for _ in 0..3 {
+        dialog.handle_key(KeyEvent::new(KeyCode::Up, KeyModifiers::NONE));
+        dialog.handle_key(KeyEvent::new(KeyCode::Char(' '), KeyModifiers::NONE));
+    }
+    assert!(dialog.handle_key(KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE)));
+    let rule = dialog.answered().unwrap();
+    let ops: Vec<_> = rule.operator.list.iter().map(|op| (op.operand.as_str(), op.op_type.clone(), op.data.as_str())).collect();
+    assert_eq!(
+        ops,
+        vec![
+            ("process.path", OperatorType::Regexp, r"^/usr/bin/[^/]+$"),
+            ("dest.host", OperatorType::Regexp, r"^(.*\.)?example\.org$"),
+            ("dest.network", OperatorType::Network, "93.184.216.0/24"),
+        ]
+    );
+
+    // Siblings match, lookalikes don't
+    let mut other = connection("/usr/bin/curl", "cdn.example.org", 443);
+    other.process_path = "/usr/bin/wget".to_string();
+    other.dst_ip = "93.184.216.99".to_string();
+    assert!(rule.operator.matches(&other));
+    other.dst_host = "example.org.evil.net".to_string();
+    assert!(!rule.operator.matches(&other));
+}
+
+#[test]
+fn subdomains_stop_short_of_public_suffixes() {
+    assert!(is_public_suffix("co.uk") && is_public_suffix("com") && is_public_suffix("UK."));
+    assert!(!is_public_suffix("example.co.uk") && !is_public_suffix("example.com"));
+
+    let parent = |host: &str| {
+        let (tx, _rx) = oneshot::channel();
+        let mut dialog = PromptDialog::new(connection("/usr/bin/curl", host, 443), "node".to_string(), tx);
+        dialog.handle_key(KeyEvent::new(KeyCode::Char(' '), KeyModifiers::NONE));
+        screen(&dialog, 100, 40).concat()
+    };
+    assert!(parent("www.example.co.uk").contains("[ ] *.example.co.uk"));
+    // Right under the suffix, its own subdomains and no further
+    assert!(parent("example.co.uk").contains("[ ] *.example.co.uk"));
+    assert!(!parent("example.co.uk").contains("*.co.uk"));
+}
+
+#[test]
+fn prompts_open_with_the_configured_defaults() {
+    let (tx, _rx) = oneshot::channel();
+    let dialog = PromptDialog::new(connection("/usr/bin/curl", "example.org", 443), "node".to_string(), tx)
+        .with_defaults(RuleAction::Deny, RuleDuration::OneHour)
+        .with_timeout(Duration::from_secs(30))
+        .with_deadline(ask_deadline(60));
+    assert_eq!((dialog.action, dialog.duration.clone()), (RuleAction::Deny, RuleDuration::OneHour));
+    assert_eq!(dialog.timeout_secs, 30);
+    assert!(screen(&dialog, 100, 40).concat().contains("of 30s"));
+
+    let settings = Settings { prompt_timeout: 12, ..Default::default() };
+    let mut editor = PromptSettingsDialog::new(&settings);
+    let key = |code| KeyEvent::new(code, KeyModifiers::NONE);
+    editor.handle_key(key(KeyCode::Right));
+    editor.handle_key(key(KeyCode::Down));
+    editor.handle_key(key(KeyCode::Left));
+    editor.handle_key(key(KeyCode::Down));
+    // Back onto the steps of five seconds
+    editor.handle_key(key(KeyCode::Right));
+    match editor.handle_key(key(KeyCode::Enter)) {
+        Some(PromptSettingsResult::Save { default_action, default_duration, prompt_timeout }) => {
+            assert_eq!(default_action, RuleAction::Deny);
+            assert_eq!(default_duration, RuleDuration::OneHour);
+            assert_eq!(prompt_timeout, Duration::from_secs(15));
+        }
+        _ => panic!("not saved"),
+    }
+
+    // Longer timeouts would outlast the daemon's wait
+    let settings = Settings { prompt_timeout: 300, ..Default::default() };
+    let mut editor = PromptSettingsDialog::new(&settings);
+    assert_eq!(editor.prompt_timeout, 15);
+    editor.handle_key(key(KeyCode::Down));
+    editor.handle_key(key(KeyCode::Down));
+    editor.handle_key(key(KeyCode::Right));
+    assert_eq!(editor.prompt_timeout, 15);
+}
+
+#[test]
+fn the_rule_preview_follows_the_options_and_takes_a_name() {
+    let (tx, _rx) = oneshot::channel();
+    let mut dialog = PromptDialog::new(connection("/usr/bin/curl", "www.example.org", 443), "node".to_string(), tx);
+    let key = |code| KeyEvent::new(code, KeyModifiers::NONE);
+    assert!(!screen(&dialog, 120, 40).concat().contains("Name: curl-www"));
+
+    dialog.handle_key(key(KeyCode::Char('p')));
+    let text = screen(&dialog, 120, 40).concat();
+    assert!(text.contains("Name: curl-www"), "{}", text);
+    assert!(text.contains("├─ process.path simple /usr/bin/curl"), "{}", text);
+    assert!(text.contains("└─ dest.host simple www.example.org"), "{}", text);
+    assert!(text.contains(r#""operator": {"#), "{}", text);
+    assert!(!text.contains("dest.port"), "{}", text);
+
+    // Toggling an option shows up right away
+    dialog.handle_key(key(KeyCode::Char(' ')));
+    dialog.handle_key(key(KeyCode::Down));
+    dialog.handle_key(key(KeyCode::Down));
+    dialog.handle_key(key(KeyCode::Char(' ')));
+    let text = screen(&dialog, 120, 40).concat();
+    assert!(text.contains("└─ dest.port simple 443"), "{}", text);
+
+    // Renamed inline, keys typed there don't answer the prompt
+    dialog.handle_key(key(KeyCode::Char('N')));
+    for _ in 0..3 {
+        dialog.handle_key(key(KeyCode::Backspace));
+    }
+    for c in "web-allowed".chars() {
+        assert!(!dialog.handle_key(key(KeyCode::Char(c))));
+    }
+    let text = screen(&dialog, 120, 40).concat();
+    assert!(text.contains("Name: curl-web-allowed"), "{}", text);
+    assert!(text.contains(r#""name": "curl-web-allowed""#), "{}", text);
+    assert!(!dialog.handle_key(key(KeyCode::Enter)));
+    assert_eq!(dialog.name.as_deref(), Some("curl-web-allowed"));
+
+    // Esc drops an edit, and an emptied name goes back to the generated one
+    dialog.handle_key(key(KeyCode::Char('N')));
+    dialog.handle_key(key(KeyCode::Char('x')));
+    assert!(!dialog.handle_key(key(KeyCode::Esc)));
+    assert_eq!(dialog.name.as_deref(), Some("curl-web-allowed"));
+
+    assert!(dialog.handle_key(key(KeyCode::Char('d'))));
+    let rule = dialog.answered().unwrap();
+    assert_eq!(rule.name, "curl-web-allowed");
+    assert_eq!(rule.action, RuleAction::Deny);
+
+    let (tx, _rx) = oneshot::channel();
+    let mut dialog = PromptDialog::new(connection("/usr/bin/curl", "www.example.org", 443), "node".to_string(), tx);
+    dialog.name = Some("kept".to_string());
+    dialog.handle_key(key(KeyCode::Char('N')));
+    dialog.handle_key(key(KeyCode::End));
+    for _ in 0..4 {
+        dialog.handle_key(key(KeyCode::Backspace));
+    }
+    dialog.handle_key(key(KeyCode::Enter));
+    assert_eq!(dialog.name, None);
+    // Too narrow for the side pane, the compact layout lists the rule
+    let text = screen(&dialog, 80, 60).concat();
+    assert!(text.contains("Name: curl-www"), "{}", text);
+    assert!(dialog.is_compact(Rect::new(0, 0, 80, 60)));
+}
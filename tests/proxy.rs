@@ -0,0 +1,74 @@
+//! Attributing proxied connections to the clients behind them
+
+mod common;
+
+use opensnitch_tui::app::proxy::ProxyCorrelator;
+use opensnitch_tui::config::KnownProxy;
+use opensnitch_tui::models::{Connection, Event};
+use opensnitch_tui::ui::tabs::connections::ConnectionsTab;
+
+use common::connection;
+
+const MILLI: i64 = 1_000_000;
+
+fn event(process: &str, dst_ip: &str, dst_port: u32, src_port: u32, millis: i64) -> Event {
+    let connection = Connection {
+        dst_ip: dst_ip.to_string(),
+        src_port,
+        ..connection(&format!("/usr/bin/{}", process), "", dst_port)
+    };
+    let mut event = Event::new(connection, None);
+    event.unix_nano = millis * MILLI;
+    event
+}
+
+#[test]
+fn outgoing_proxy_connections_are_attributed_to_clients() {
+    let correlator = ProxyCorrelator::new(vec![KnownProxy::new("tor", 9050)]);
+    let curl = event("curl", "127.0.0.1", 9050, 40001, 0);
+    let wget = event("wget", "127.0.0.1", 9050, 40002, 100);
+    let first = event("tor", "198.51.100.7", 443, 50001, 150);
+    let second = event("tor", "198.51.100.8", 443, 50002, 300);
+    let unrelated = event("firefox", "203.0.113.1", 443, 50003, 200);
+
+    // Order of the input does not matter
+    let events = [&second, &unrelated, &first, &wget, &curl];
+    let chains = correlator.correlate(events);
+
+    assert_eq!(chains.proxy(&curl), Some("tor"));
+    assert_eq!(chains.origin(&first), Some("curl"));
+    assert_eq!(chains.origin(&second), Some("wget"));
+    assert_eq!(chains.origin(&unrelated), None);
+    assert_eq!(chains.proxy(&unrelated), None);
+}
+
+#[test]
+fn stale_or_unknown_proxy_connections_are_not_paired() {
+    let correlator = ProxyCorrelator::new(vec![KnownProxy::new("privoxy", 8118)]);
+    let client = event("curl", "127.0.0.1", 8118, 40001, 0);
+    let late = event("privoxy", "198.51.100.7", 443, 50001, 5_000);
+    // Not a known proxy port
+    let other = event("curl", "127.0.0.1", 8080, 40002, 5_100);
+    let after_other = event("privoxy", "198.51.100.8", 443, 50002, 5_200);
+
+    let chains = correlator.correlate([&client, &late, &other, &after_other]);
+    assert_eq!(chains.origin(&late), None);
+    assert_eq!(chains.origin(&after_other), None);
+    assert_eq!(chains.proxy(&other), None);
+}
+
+#[test]
+fn connections_tab_finds_proxied_rows_by_client() {
+    let events = [
+        event("curl", "127.0.0.1", 9050, 40001, 0),
+        event("tor", "198.51.100.7", 443, 50001, 50),
+        event("firefox", "203.0.113.1", 443, 50003, 60),
+    ];
+    let mut tab = ConnectionsTab::new().with_proxies(vec![KnownProxy::new("tor", 9050)]);
+    tab.set_events(events.iter(), None);
+    assert_eq!(tab.visible_len(), 3);
+
+    tab.search_bar_mut().query = "curl".to_string();
+    // The loopback hop and the connection tor made for curl
+    assert_eq!(tab.visible_len(), 2);
+}
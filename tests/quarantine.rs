@@ -0,0 +1,112 @@
+//! Quarantining a node and restoring its rules afterwards
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::sync::{broadcast, mpsc};
+
+use opensnitch_tui::app::snapshots;
+use opensnitch_tui::app::state::{run_state_manager, AppMessage};
+use opensnitch_tui::app::suggestions::DenialTracker;
+use opensnitch_tui::app::AppState;
+use opensnitch_tui::db::Database;
+use opensnitch_tui::models::node::ClientConfig;
+use opensnitch_tui::models::{quarantine, Connection, Operator, Rule, RuleAction, RuleDuration};
+
+fn rule(name: &str, enabled: bool) -> Rule {
+    let mut rule = Rule::new(name, RuleAction::Allow, RuleDuration::Always, Operator::simple("process.path", "/usr/bin/curl"));
+    rule.enabled = enabled;
+    rule
+}
+
+#[test]
+fn quarantine_allows_only_dns_dhcp_and_management_hosts() {
+    let rules = quarantine::rules(&["10.0.0.5".to_string(), "192.168.1.0/24".to_string(), "mgmt.example".to_string()]).unwrap();
+    let mut names: Vec<&str> = rules.iter().map(|r| r.name.as_str()).collect();
+    names.sort();
+    // The daemon goes through rules by name, the allowed ones come first
+    assert_eq!(names.last(), Some(&quarantine::DENY_RULE));
+    assert!(rules.iter().filter(|r| r.action == RuleAction::Allow).all(|r| r.precedence));
+    assert!(names.iter().all(|name| !name.contains('/')));
+
+    let connection = |ip: &str, host: &str, port: u32| Connection {
+        protocol: "udp".to_string(),
+        dst_ip: ip.to_string(),
+        dst_host: host.to_string(),
+        dst_port: port,
+        ..Default::default()
+    };
+    let allowed = |conn: &Connection| rules.iter().any(|r| r.action == RuleAction::Allow && r.operator.matches(conn));
+    assert!(allowed(&connection("1.1.1.1", "", 53)));
+    assert!(allowed(&connection("255.255.255.255", "", 67)));
+    assert!(allowed(&connection("10.0.0.5", "", 22)));
+    assert!(allowed(&connection("192.168.1.20", "", 443)));
+    assert!(allowed(&connection("203.0.113.9", "mgmt.example", 443)));
+    assert!(!allowed(&connection("203.0.113.9", "example.org", 443)));
+
+    assert!(quarantine::rules(&["10.0.0.0/40".to_string()]).is_err());
+    assert!(quarantine::rules(&["not a host".to_string()]).is_err());
+}
+
+#[tokio::test]
+async fn quarantine_is_lifted_with_the_rules_saved_before() {
+    let (ui_update_tx, _) = broadcast::channel(100);
+    let state = Arc::new(AppState::new(Database::open(":memory:").unwrap(), ui_update_tx.clone()));
+    state.nodes.write().await.add_node("node-a", ClientConfig::default());
+    let (tx, rx) = mpsc::channel(10);
+    let manager = tokio::spawn(run_state_manager(
+        state.clone(),
+        rx,
+        ui_update_tx,
+        DenialTracker::new(0, Duration::from_secs(60)),
+    ));
+    let (daemon_tx, mut daemon_rx) = mpsc::channel(20);
+    let node_addr = "node-a".to_string();
+    tx.send(AppMessage::NotificationChannelOpened { node_addr: node_addr.clone(), tx: daemon_tx }).await.unwrap();
+    for rule in [rule("curl", true), rule("wget", false)] {
+        tx.send(AppMessage::RuleAdded { node_addr: node_addr.clone(), rule }).await.unwrap();
+    }
+    while state.nodes.read().await.get_node("node-a").unwrap().rules.len() < 2 {
+        tokio::task::yield_now().await;
+    }
+
+    let message = snapshots::quarantine(&state, &tx, "node-a", &["10.0.0.5".to_string()]).await.unwrap();
+    assert!(message.starts_with("1 rule disabled"), "{}", message);
+    // curl disabled, then DNS, DHCP, the host and the deny rule added
+    for _ in 0..5 {
+        daemon_rx.recv().await.unwrap();
+    }
+    let nodes = state.nodes.read().await;
+    let rules = &nodes.get_node("node-a").unwrap().rules;
+    assert!(rules.iter().any(|r| r.name == quarantine::DENY_RULE && r.enabled));
+    assert!(rules.iter().filter(|r| !quarantine::is_quarantine_rule(&r.name)).all(|r| !r.enabled));
+    drop(nodes);
+    assert!(snapshots::quarantined(&state, "node-a"));
+    assert!(snapshots::quarantine(&state, &tx, "node-a", &[]).await.is_err());
+
+    // Still quarantined, and liftable, with the deny rule deleted by hand
+    tx.send(AppMessage::RuleDeleted { node_addr: node_addr.clone(), name: quarantine::DENY_RULE.to_string() })
+        .await
+        .unwrap();
+    while state.nodes.read().await.get_node("node-a").unwrap().rules.iter().any(|r| r.name == quarantine::DENY_RULE) {
+        tokio::task::yield_now().await;
+    }
+    assert!(snapshots::quarantined(&state, "node-a"));
+
+    let snapshot = snapshots::lift_quarantine(&state, &tx, "node-a").await.unwrap();
+    assert_eq!(snapshot.rules.len(), 2);
+    assert!(!snapshots::quarantined(&state, "node-a"));
+    // Three quarantine rules deleted, curl enabled again
+    for _ in 0..4 {
+        daemon_rx.recv().await.unwrap();
+    }
+    let nodes = state.nodes.read().await;
+    let rules = &nodes.get_node("node-a").unwrap().rules;
+    let enabled: Vec<(&str, bool)> = rules.iter().map(|r| (r.name.as_str(), r.enabled)).collect();
+    assert_eq!(enabled, [("curl", true), ("wget", false)]);
+    drop(nodes);
+    assert!(snapshots::lift_quarantine(&state, &tx, "node-a").await.is_err());
+
+    drop(tx);
+    manager.await.unwrap();
+}
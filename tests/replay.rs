@@ -0,0 +1,93 @@
+//! Replaying captured daemon calls through the state manager
+
+mod common;
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::sync::{broadcast, mpsc};
+
+use opensnitch_tui::app::replay::{self, ReplayCall, ReplayStep};
+use opensnitch_tui::app::state::run_state_manager;
+use opensnitch_tui::app::suggestions::DenialTracker;
+use opensnitch_tui::app::AppState;
+use opensnitch_tui::config::InterceptionMode;
+use opensnitch_tui::db::Database;
+use opensnitch_tui::models::node::ClientConfig;
+use opensnitch_tui::models::{Alert, AlertPriority, AlertType, AlertWhat, Event, Statistics};
+
+use common::connection;
+
+fn step(delay_ms: u64, call: ReplayCall) -> String {
+    serde_json::to_string(&ReplayStep { delay_ms, call }).unwrap()
+}
+
+#[test]
+fn captures_are_read_line_by_line() {
+    let path = std::env::temp_dir().join(format!("opensnitch-tui-replay-{}.jsonl", std::process::id()));
+    std::fs::write(
+        &path,
+        "{\"type\": \"disconnect\", \"node\": \"unix:/demo\"}\n\n{\"type\": \"ping\", \"delay_ms\": 20}\n",
+    )
+    .unwrap();
+    let error = replay::load(&path).unwrap_err();
+    assert!(format!("{:#}", error).contains("line 3"), "{:#}", error);
+
+    std::fs::write(&path, "{\"type\": \"disconnect\", \"node\": \"unix:/demo\"}\n\n").unwrap();
+    let steps = replay::load(&path).unwrap();
+    assert_eq!(steps.len(), 1);
+    assert_eq!(steps[0].delay_ms, 0);
+    assert!(matches!(&steps[0].call, ReplayCall::Disconnect { node } if node == "unix:/demo"));
+
+    let _ = std::fs::remove_file(&path);
+}
+
+#[tokio::test]
+async fn replayed_calls_show_up_as_they_would_live() {
+    let node = "unix:/demo".to_string();
+    let config = ClientConfig { name: "demo-box".to_string(), version: "1.6.5".to_string(), ..Default::default() };
+    let stats = Statistics { events: vec![Event::new(connection("/usr/bin/curl", "example.org", 443), None)], ..Default::default() };
+    let alert = Alert::new(7, AlertType::Warning, AlertPriority::High, AlertWhat::Generic, None);
+    let capture = [
+        step(0, ReplayCall::Subscribe { node: node.clone(), config }),
+        step(10, ReplayCall::Ping { node: node.clone(), stats }),
+        step(0, ReplayCall::Ask { node: node.clone(), connection: connection("/usr/bin/curl", "ask.example.org", 443) }),
+        step(0, ReplayCall::Alert { node: node.clone(), alert }),
+    ]
+    .join("\n");
+    let path = std::env::temp_dir().join(format!("opensnitch-tui-replay-live-{}.jsonl", std::process::id()));
+    std::fs::write(&path, capture).unwrap();
+    let steps = replay::load(&path).unwrap();
+    let _ = std::fs::remove_file(&path);
+
+    let (ui_update_tx, _) = broadcast::channel(100);
+    let state = Arc::new(AppState::new(Database::open(":memory:").unwrap(), ui_update_tx.clone()));
+    let (tx, rx) = mpsc::channel(10);
+    let manager = tokio::spawn(run_state_manager(
+        state.clone(),
+        rx,
+        ui_update_tx,
+        DenialTracker::new(0, Duration::from_secs(60)),
+    ));
+    replay::run(steps, state.clone(), tx).await;
+    manager.await.unwrap();
+
+    let nodes = state.nodes.read().await;
+    assert_eq!(nodes.nodes.get(&node).map(|n| n.name.as_str()), Some("demo-box"));
+    drop(nodes);
+    assert!(state.connections.read().await.iter().any(|e| e.connection.dst_host == "example.org"));
+    let prompts = state.pending_prompts.read().await;
+    assert_eq!(prompts.len(), 1);
+    assert_eq!(prompts[0].connection.dst_host, "ask.example.org");
+    drop(prompts);
+    let alerts = state.alerts.read().await;
+    assert_eq!(alerts.len(), 1);
+    assert_eq!(alerts[0].node, node);
+}
+
+#[test]
+fn asks_only_prompt_when_asking() {
+    let ask = || ReplayCall::Ask { node: "unix:/demo".to_string(), connection: connection("/usr/bin/curl", "example.org", 443) };
+    assert_eq!(ask().messages(InterceptionMode::Ask).len(), 2);
+    assert_eq!(ask().messages(InterceptionMode::AllowAll).len(), 1);
+}
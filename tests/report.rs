@@ -0,0 +1,68 @@
+//! Human-readable rule reports
+
+use chrono::Utc;
+
+use opensnitch_tui::models::report::{rule_summary, rules_report, ReportFormat};
+use opensnitch_tui::models::{Operator, Rule, RuleAction, RuleDuration};
+
+fn doubleclick() -> Rule {
+    Rule::new(
+        "deny-doubleclick",
+        RuleAction::Deny,
+        RuleDuration::Always,
+        Operator::list(vec![
+            Operator::simple("process.path", "/usr/lib/firefox/firefox"),
+            Operator::regexp("dest.host", r"^.*\.doubleclick\.net$"),
+        ]),
+    )
+}
+
+#[test]
+fn rules_read_as_sentences() {
+    assert_eq!(rule_summary(&doubleclick()), "deny firefox → *.doubleclick.net always");
+
+    let curl = Rule::new(
+        "allow-curl",
+        RuleAction::Allow,
+        RuleDuration::OneHour,
+        Operator::list(vec![
+            Operator::simple("process.path", "/usr/bin/curl"),
+            Operator::simple("dest.port", "443"),
+            Operator::simple("protocol", "tcp"),
+        ]),
+    );
+    assert_eq!(rule_summary(&curl), "allow curl → port 443 over tcp for 1h");
+
+    let lan = Rule::new("lan", RuleAction::Allow, RuleDuration::Always, Operator::network("dest.network", "192.168.0.0/16"));
+    assert_eq!(rule_summary(&lan), "allow any application → 192.168.0.0/16 always");
+
+    // Patterns that don't read as globs are kept as they are
+    let tracker = Rule::new("t", RuleAction::Reject, RuleDuration::Once, Operator::regexp("dest.host", "^(ads|track)\\."));
+    assert_eq!(rule_summary(&tracker), "reject any application → /^(ads|track)\\./ once");
+}
+
+#[test]
+fn report_groups_rules_by_application() {
+    let mut ssh = Rule::new("allow-ssh", RuleAction::Allow, RuleDuration::Always, Operator::simple("dest.port", "22"));
+    ssh.enabled = false;
+    let rules = vec![
+        ssh,
+        doubleclick(),
+        Rule::new("allow-apt", RuleAction::Allow, RuleDuration::Always, Operator::simple("process.path", "/usr/bin/apt"))
+            .with_description("Package updates"),
+    ];
+
+    let report = rules_report("node-a", &rules, ReportFormat::Markdown, Utc::now());
+    assert!(report.starts_with("# OpenSnitch rules: node-a\n"));
+    assert!(report.contains("3 rules (1 disabled) for 3 applications"));
+    let apt = report.find("## apt").unwrap();
+    let firefox = report.find("## firefox").unwrap();
+    let any = report.find("## any application").unwrap();
+    assert!(apt < firefox && firefox < any);
+    assert!(report.contains("- `allow-apt`: allow apt → * always\n  Package updates\n"));
+    assert!(report.contains("- `allow-ssh`: allow any application → port 22 always (disabled)\n"));
+
+    let text = rules_report("node-a", &rules, ReportFormat::Text, Utc::now());
+    assert!(text.starts_with("OpenSnitch rules: node-a\n========================\n"));
+    assert!(text.contains("firefox\n-------\n  * deny-doubleclick: deny firefox → *.doubleclick.net always\n"));
+}
@@ -0,0 +1,146 @@
+//! Earlier versions of rules changed from the TUI, and undoing changes
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use tokio::sync::{broadcast, mpsc};
+
+use opensnitch_tui::app::state::{run_state_manager, AppMessage};
+use opensnitch_tui::app::suggestions::DenialTracker;
+use opensnitch_tui::app::AppState;
+use opensnitch_tui::db::Database;
+use opensnitch_tui::grpc::notifications::NotificationAction;
+use opensnitch_tui::models::node::ClientConfig;
+use opensnitch_tui::models::{Operator, Rule, RuleAction, RuleChange, RuleDuration};
+use opensnitch_tui::ui::tabs::{rules::RulesTab, Tab, TabCommand};
+
+fn curl() -> Rule {
+    Rule::new("curl", RuleAction::Allow, RuleDuration::Always, Operator::simple("process.path", "/usr/bin/curl"))
+}
+
+#[tokio::test]
+async fn changes_are_undone_latest_first() {
+    let (ui_update_tx, _) = broadcast::channel(100);
+    let state = Arc::new(AppState::new(Database::open(":memory:").unwrap(), ui_update_tx.clone()));
+    state.nodes.write().await.add_node("node-a", ClientConfig::default());
+    let (tx, rx) = mpsc::channel(10);
+    let manager = tokio::spawn(run_state_manager(
+        state.clone(),
+        rx,
+        ui_update_tx,
+        DenialTracker::new(0, Duration::from_secs(60)),
+    ));
+    let (daemon_tx, mut daemon_rx) = mpsc::channel(10);
+    let node_addr = "node-a".to_string();
+    tx.send(AppMessage::NotificationChannelOpened { node_addr: node_addr.clone(), tx: daemon_tx }).await.unwrap();
+
+    let mut denied = curl();
+    denied.action = RuleAction::Deny;
+    for (local, action) in [
+        (AppMessage::RuleAdded { node_addr: node_addr.clone(), rule: curl() }, NotificationAction::ChangeRule(curl())),
+        (AppMessage::RuleModified { node_addr: node_addr.clone(), rule: denied.clone() }, NotificationAction::ChangeRule(denied)),
+        (AppMessage::RuleDeleted { node_addr: node_addr.clone(), name: "curl".to_string() }, NotificationAction::DeleteRule("curl".to_string())),
+    ] {
+        tx.send(local).await.unwrap();
+        tx.send(AppMessage::SendNotification { node_addr: node_addr.clone(), action }).await.unwrap();
+        daemon_rx.recv().await.unwrap();
+    }
+    assert!(state.nodes.read().await.get_node("node-a").unwrap().rules.is_empty());
+
+    // Back as it was before being deleted, then before being denied
+    assert_eq!(state.undo_rule_change("node-a").await.unwrap(), "curl is back as it was before being deleted");
+    let restored = daemon_rx.recv().await.unwrap();
+    assert_eq!(restored.rules[0].action, "deny");
+    state.undo_rule_change("node-a").await.unwrap();
+    assert_eq!(daemon_rx.recv().await.unwrap().rules[0].action, "allow");
+    let nodes = state.nodes.read().await;
+    assert_eq!(nodes.get_node("node-a").unwrap().rules[0].action, RuleAction::Allow);
+    drop(nodes);
+    // Then gone, as before it was added
+    state.undo_rule_change("node-a").await.unwrap();
+    assert_eq!(daemon_rx.recv().await.unwrap().rules.len(), 0);
+    assert!(state.db.select_rules("node-a").unwrap().is_empty());
+    assert_eq!(state.undo_rule_change("node-a").await.unwrap_err(), "no change left to undo");
+
+    // Most recent first, the reverts logged but never undone
+    let changes: Vec<_> = state
+        .db
+        .select_rule_versions("node-a", "curl", 10)
+        .unwrap()
+        .into_iter()
+        .map(|v| (v.change, v.undone, v.previous.map(|r| r.action)))
+        .collect();
+    assert_eq!(
+        changes,
+        [
+            (RuleChange::Reverted, false, Some(RuleAction::Allow)),
+            (RuleChange::Reverted, false, Some(RuleAction::Deny)),
+            (RuleChange::Reverted, false, None),
+            (RuleChange::Deleted, true, Some(RuleAction::Deny)),
+            (RuleChange::Modified, true, Some(RuleAction::Allow)),
+            (RuleChange::Added, true, None),
+        ]
+    );
+    manager.abort();
+}
+
+#[tokio::test]
+async fn a_rule_added_under_a_taken_name_replaces_it_until_undone() {
+    let (ui_update_tx, _) = broadcast::channel(100);
+    let state = Arc::new(AppState::new(Database::open(":memory:").unwrap(), ui_update_tx.clone()));
+    state.nodes.write().await.add_node("node-a", ClientConfig::default());
+    let (tx, rx) = mpsc::channel(10);
+    let manager = tokio::spawn(run_state_manager(
+        state.clone(),
+        rx,
+        ui_update_tx,
+        DenialTracker::new(0, Duration::from_secs(60)),
+    ));
+    let (daemon_tx, mut daemon_rx) = mpsc::channel(10);
+    let node_addr = "node-a".to_string();
+    tx.send(AppMessage::NotificationChannelOpened { node_addr: node_addr.clone(), tx: daemon_tx }).await.unwrap();
+
+    let mut denied = curl();
+    denied.action = RuleAction::Deny;
+    denied.operator = Operator::simple("process.path", "/usr/local/bin/curl");
+    for rule in [curl(), denied.clone()] {
+        tx.send(AppMessage::RuleAdded { node_addr: node_addr.clone(), rule: rule.clone() }).await.unwrap();
+        let action = NotificationAction::ChangeRule(rule);
+        tx.send(AppMessage::SendNotification { node_addr: node_addr.clone(), action }).await.unwrap();
+        daemon_rx.recv().await.unwrap();
+    }
+    let nodes = state.nodes.read().await;
+    let rules = &nodes.get_node("node-a").unwrap().rules;
+    assert_eq!(rules.len(), 1);
+    assert_eq!(rules[0].action, RuleAction::Deny);
+    drop(nodes);
+
+    state.undo_rule_change("node-a").await.unwrap();
+    assert_eq!(daemon_rx.recv().await.unwrap().rules[0].action, "allow");
+    let nodes = state.nodes.read().await;
+    let rules = &nodes.get_node("node-a").unwrap().rules;
+    assert_eq!(rules.len(), 1);
+    assert_eq!((rules[0].action, rules[0].operator.data.as_str()), (RuleAction::Allow, "/usr/bin/curl"));
+    drop(nodes);
+    let stored = state.db.select_rules("node-a").unwrap();
+    assert_eq!(stored.len(), 1);
+    assert_eq!(stored[0].operator.data, "/usr/bin/curl");
+    manager.abort();
+}
+
+#[test]
+fn undo_and_history_keys() {
+    let key = |code| KeyEvent::new(code, KeyModifiers::NONE);
+    let mut tab = RulesTab::new();
+    assert!(tab.handle_key(key(KeyCode::Char('u'))).is_empty());
+
+    tab.set_rules(vec![curl()], Some("node-a".to_string()));
+    let commands = tab.handle_key(key(KeyCode::Char('u')));
+    assert!(matches!(&commands[..], [TabCommand::UndoRuleChange { node_addr }] if node_addr == "node-a"));
+
+    tab.handle_key(key(KeyCode::Char('H')));
+    assert!(tab.showing_dialog());
+    tab.handle_key(key(KeyCode::Esc));
+    assert!(!tab.showing_dialog());
+}
@@ -0,0 +1,80 @@
+//! Rules directory shared with opensnitchd and its GUI
+
+use std::path::PathBuf;
+
+use opensnitch_tui::db::rules_io::{read_rules_dir, write_rules_dir};
+use opensnitch_tui::models::{Operator, OperatorType, Rule, RuleAction, RuleDuration};
+
+fn rules_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("opensnitch-tui-rules-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    dir
+}
+
+#[test]
+fn rules_written_by_the_daemon_are_read() {
+    let dir = rules_dir("daemon");
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(
+        dir.join("000-allow-curl.json"),
+        r#"{
+  "created": "2024-03-01T10:15:00.123456789+01:00",
+  "updated": "2024-03-01T10:15:00.123456789+01:00",
+  "name": "000-allow-curl",
+  "description": "",
+  "enabled": true,
+  "precedence": false,
+  "nolog": false,
+  "action": "allow",
+  "duration": "always",
+  "operator": {
+    "type": "simple",
+    "operand": "process.path",
+    "sensitive": false,
+    "data": "/usr/bin/curl",
+    "list": null
+  }
+}"#,
+    )
+    .unwrap();
+    std::fs::write(dir.join("broken.json"), "{").unwrap();
+    std::fs::write(dir.join("notes.txt"), "not a rule").unwrap();
+
+    let (rules, report) = read_rules_dir(&dir).unwrap();
+    assert_eq!(rules.len(), 1);
+    assert_eq!((rules[0].name.as_str(), rules[0].operator.data.as_str()), ("000-allow-curl", "/usr/bin/curl"));
+    assert!(rules[0].operator.list.is_empty());
+    assert_eq!(report.errors.len(), 1);
+    assert!(report.errors[0].starts_with("broken.json: "));
+
+    assert!(read_rules_dir(&dir.join("missing")).is_err());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn permanent_rules_are_written_one_file_each() {
+    let dir = rules_dir("export");
+    let list = Rule::new(
+        "Deny Tracking",
+        RuleAction::Deny,
+        RuleDuration::Always,
+        Operator::list(vec![
+            Operator::simple("process.path", "/usr/bin/firefox"),
+            Operator::regexp("dest.host", r"\.doubleclick\.net$"),
+        ]),
+    );
+    let temporary = Rule::new("curl", RuleAction::Allow, RuleDuration::OneHour, Operator::simple("dest.port", "443"));
+    let clash = Rule::new("deny tracking", RuleAction::Allow, RuleDuration::Always, Operator::simple("dest.port", "80"));
+
+    let report = write_rules_dir(&dir, &[list, temporary, clash]).unwrap();
+    assert_eq!((report.rules, report.skipped, report.errors.len()), (1, 1, 1));
+    assert!(dir.join("deny-tracking.json").is_file());
+
+    let (rules, _) = read_rules_dir(&dir).unwrap();
+    assert_eq!(rules.len(), 1);
+    assert_eq!(rules[0].name, "Deny Tracking");
+    assert_eq!(rules[0].operator.op_type, OperatorType::List);
+    let data: Vec<&str> = rules[0].operator.list.iter().map(|op| op.data.as_str()).collect();
+    assert_eq!(data, ["/usr/bin/firefox", r"\.doubleclick\.net$"]);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,37 @@
+//! Sampling events under extreme load
+
+use std::time::{Duration, Instant};
+
+use opensnitch_tui::app::sampling::EventSampler;
+
+#[test]
+fn samples_while_the_rate_is_over_the_threshold() {
+    let mut sampler = EventSampler::new(100, 10);
+    let start = Instant::now();
+
+    // A burst of 1000 events within a second
+    let kept = (0..1000)
+        .filter(|i| sampler.admit(start + Duration::from_micros(i * 500)))
+        .count();
+    // The first 100 get through before sampling kicks in
+    assert_eq!(kept, 100 + 90);
+    let status = sampler.status().unwrap();
+    assert_eq!(status.one_in, 10);
+    assert_eq!(status.seen, 900);
+
+    // The burst window still averages over the threshold
+    sampler.admit(start + Duration::from_secs(2));
+    assert!(sampler.status().is_some());
+
+    // A quiet window brings back full fidelity
+    assert!(sampler.admit(start + Duration::from_millis(3100)));
+    assert_eq!(sampler.status(), None);
+}
+
+#[test]
+fn disabled_sampler_keeps_everything() {
+    let mut sampler = EventSampler::new(0, 10);
+    let now = Instant::now();
+    assert!((0..10_000).all(|_| sampler.admit(now)));
+    assert_eq!(sampler.status(), None);
+}
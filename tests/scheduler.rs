@@ -0,0 +1,139 @@
+//! Maintenance and reports run on a schedule
+
+use chrono::{Duration, Local, TimeZone, Utc};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+use tokio::sync::broadcast;
+
+use opensnitch_tui::app::scheduler::{next_run, run_task, LastRun, Scheduler};
+use opensnitch_tui::app::AppState;
+use opensnitch_tui::config::{ScheduledTask, TaskKind};
+use opensnitch_tui::db::Database;
+use opensnitch_tui::models::{Connection, Event};
+
+fn task(name: &str, kind: TaskKind, every_hours: u64, at_hour: Option<u32>) -> ScheduledTask {
+    ScheduledTask {
+        name: name.to_string(),
+        task: kind,
+        every_hours,
+        at_hour,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn tasks_run_at_their_hour_and_catch_up() {
+    let nightly = task("purge", TaskKind::Purge, 24, Some(3));
+    let at = |d: u32, h: u32, m: u32| Local.with_ymd_and_hms(2026, 3, d, h, m, 0).unwrap().with_timezone(&Utc);
+
+    // Never ran: the next 03:00, or right away within that hour
+    assert_eq!(next_run(&nightly, None, at(10, 14, 0)), at(11, 3, 0));
+    assert!(next_run(&nightly, None, at(10, 3, 20)) <= at(10, 3, 20));
+
+    // Ran last night: tonight, even when last night's run started late
+    assert_eq!(next_run(&nightly, Some(at(10, 3, 40)), at(10, 12, 0)), at(11, 3, 0));
+    // Missed while nothing was running: caught up
+    assert!(next_run(&nightly, Some(at(7, 3, 0)), at(10, 12, 0)) <= at(10, 12, 0));
+
+    let weekly = task("report", TaskKind::Report, 168, Some(3));
+    assert_eq!(next_run(&weekly, Some(at(2, 3, 1)), at(3, 3, 0)), at(9, 3, 0));
+
+    let hourly = task("lists", TaskKind::Blocklist, 6, None);
+    assert_eq!(next_run(&hourly, Some(at(10, 1, 0)), at(10, 2, 0)), at(10, 7, 0));
+}
+
+#[test]
+fn last_runs_survive_a_restart() {
+    let path = std::env::temp_dir().join(format!("opensnitch-tui-schedule-{}.json", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let tasks = vec![task("purge", TaskKind::Purge, 24, None), task("", TaskKind::Report, 24, None)];
+    let now = Utc::now();
+
+    let mut scheduler = Scheduler::new(tasks.clone()).with_state_file(path.clone());
+    // Unnamed tasks are left out
+    assert_eq!(scheduler.due(now).len(), 1);
+    let run = LastRun { time: now, result: Ok("3 events and 0 alerts purged".to_string()) };
+    scheduler.record("purge", run.clone()).unwrap();
+    assert!(scheduler.due(now).is_empty());
+
+    let scheduler = Scheduler::new(tasks).with_state_file(path.clone());
+    assert!(scheduler.due(now + Duration::hours(23)).is_empty());
+    assert_eq!(scheduler.due(now + Duration::hours(24)).len(), 1);
+    let status = scheduler.status(now);
+    assert_eq!(status[0].schedule, "every 24h");
+    assert_eq!(status[0].last_run, Some(run));
+    let _ = std::fs::remove_file(&path);
+}
+
+#[tokio::test]
+async fn tasks_purge_and_refresh_lists() {
+    let (ui_update_tx, _) = broadcast::channel(10);
+    let state = AppState::new(Database::open(":memory:").unwrap(), ui_update_tx);
+    let event = |port: u32| Event::new(Connection { dst_port: port, ..Default::default() }, None);
+    let mut old = event(80);
+    old.time = (Utc::now() - Duration::days(40)).to_rfc3339();
+    state.db.insert_connection(&old).unwrap();
+    state.db.insert_connection(&event(443)).unwrap();
+
+    let exports = std::env::temp_dir();
+    let purge = task("purge", TaskKind::Purge, 24, None);
+    assert_eq!(run_task(&purge, &state, &exports).await, Ok("1 events and 0 alerts purged".to_string()));
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/hosts", listener.local_addr().unwrap());
+    tokio::spawn(async move {
+        let (mut stream, _) = listener.accept().await.unwrap();
+        let mut request = vec![0; 4096];
+        let _ = stream.read(&mut request).await.unwrap();
+        stream
+            .write_all(b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n12\r\n# ads\nads.example\n\r\ne\r\ntrack.example\n\r\n0\r\n\r\n")
+            .await
+            .unwrap();
+    });
+    let path = std::env::temp_dir().join(format!("opensnitch-tui-blocklist-{}.txt", std::process::id()));
+    let lists = ScheduledTask {
+        url,
+        path: path.to_string_lossy().to_string(),
+        ..task("lists", TaskKind::Blocklist, 24, None)
+    };
+    assert!(run_task(&lists, &state, &exports).await.unwrap().starts_with("2 entries"));
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "# ads\nads.example\ntrack.example\n");
+
+    // A failed refresh keeps the previous list
+    let failing = ScheduledTask { command: "exit 1".to_string(), ..lists };
+    assert!(run_task(&failing, &state, &exports).await.is_err());
+    assert!(path.exists());
+    let _ = std::fs::remove_file(&path);
+}
+
+#[tokio::test]
+async fn vacuum_gives_back_the_space_of_purged_rows() {
+    let path = std::env::temp_dir().join(format!("opensnitch-tui-vacuum-{}.db", std::process::id()));
+    let (ui_update_tx, _) = broadcast::channel(10);
+    let state = AppState::new(Database::open(&path.to_string_lossy()).unwrap(), ui_update_tx);
+    for pid in 0..500 {
+        let connection = Connection { process_id: pid, process_args: vec!["x".repeat(1000)], ..Default::default() };
+        let mut old = Event::new(connection, None);
+        old.time = (Utc::now() - Duration::days(40)).to_rfc3339();
+        state.db.insert_connection(&old).unwrap();
+    }
+    state.db.insert_connection(&Event::new(Connection::default(), None)).unwrap();
+    let full = state.db.size().unwrap();
+    assert_eq!(full.connections, 501);
+
+    let exports = std::env::temp_dir();
+    let purge = task("purge", TaskKind::Purge, 24, None);
+    assert_eq!(run_task(&purge, &state, &exports).await, Ok("500 events and 0 alerts purged".to_string()));
+    let purged = state.db.size().unwrap();
+    assert_eq!(purged.connections, 1);
+    assert!(purged.free_bytes > 0);
+
+    let vacuum = task("vacuum", TaskKind::Vacuum, 168, None);
+    assert!(run_task(&vacuum, &state, &exports).await.unwrap().contains("freed"));
+    let compacted = state.db.size().unwrap();
+    assert_eq!(compacted.free_bytes, 0);
+    assert!(compacted.bytes < full.bytes / 2, "{:?} {:?}", compacted, full);
+    for suffix in ["", "-wal", "-shm"] {
+        let _ = std::fs::remove_file(format!("{}{}", path.display(), suffix));
+    }
+}
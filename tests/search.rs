@@ -0,0 +1,82 @@
+//! Search of the stored connections
+
+mod common;
+
+use chrono::{Local, TimeZone, Utc};
+
+use opensnitch_tui::db::search::ConnectionQuery;
+use opensnitch_tui::db::Database;
+use opensnitch_tui::models::{Connection, Event, Operator, Rule, RuleAction, RuleDuration};
+
+use common::connection;
+
+fn event(minute: u32, process: &str, host: &str, port: u32, uid: u32, action: RuleAction) -> Event {
+    let connection = Connection { user_id: uid, process_id: minute, ..connection(process, host, port) };
+    let rule = Rule::new("rule", action, RuleDuration::Always, Operator::simple("dest.port", "443"));
+    let mut event = Event::new(connection, Some(rule));
+    event.time = Utc.with_ymd_and_hms(2024, 3, 1, 10, minute, 0).unwrap().to_rfc3339();
+    event
+}
+
+#[test]
+fn queries_are_words_and_terms() {
+    let now = Utc.with_ymd_and_hms(2024, 3, 1, 12, 0, 0).unwrap();
+    let query = ConnectionQuery::parse("firefox host:example port:443 action:DENY since:2h fe80::1", now).unwrap();
+    assert_eq!(query.words, ["firefox", "fe80::1"]);
+    assert_eq!(query.host, ["example"]);
+    assert_eq!((query.port, query.action.as_deref()), (Some(443), Some("deny")));
+    assert_eq!(query.since, Some(Utc.with_ymd_and_hms(2024, 3, 1, 10, 0, 0).unwrap()));
+
+    assert_eq!(ConnectionQuery::parse("port:https", now).unwrap_err(), "invalid port: https");
+    assert_eq!(ConnectionQuery::parse("since:yesterday", now).unwrap_err(), "invalid time: yesterday");
+    assert!(ConnectionQuery::parse("until:2024-03-01T14:30", now).unwrap().until.is_some());
+}
+
+#[test]
+fn stored_connections_are_searched_a_page_at_a_time() {
+    let db = Database::open(":memory:").unwrap();
+    for minute in 0..30 {
+        let process = if minute % 2 == 0 { "/usr/lib/firefox/firefox" } else { "/usr/bin/curl" };
+        let action = if minute % 3 == 0 { RuleAction::Deny } else { RuleAction::Allow };
+        db.insert_connection(&event(minute, process, "example.org", 443, 1000, action)).unwrap();
+    }
+    db.insert_connection(&event(45, "/usr/bin/ssh", "git.example.org", 22, 0, RuleAction::Allow)).unwrap();
+    let now = Utc.with_ymd_and_hms(2024, 3, 1, 12, 0, 0).unwrap();
+    let search = |query: &str, offset| db.search_connections(&ConnectionQuery::parse(query, now).unwrap(), offset, 10).unwrap();
+
+    let all = search("", 0);
+    assert_eq!((all.total, all.events.len()), (31, 10));
+    assert_eq!(all.events[0].connection.process_path, "/usr/bin/ssh");
+
+    let firefox = search("firefox port:443", 10);
+    assert_eq!((firefox.total, firefox.offset, firefox.events.len()), (15, 10, 5));
+    assert!(firefox.events.iter().all(|e| e.connection.process_path.ends_with("firefox")));
+
+    // Every third minute was denied, half of them by curl
+    assert_eq!(search("curl action:deny", 0).total, 5);
+    assert_eq!(search("user:0", 0).total, 1);
+    assert_eq!(search("host:git.", 0).total, 1);
+    assert_eq!(search("since:110m", 0).total, 21);
+    assert_eq!(search("until:100m process:ssh", 0).total, 0);
+    // LIKE wildcards are searched literally
+    assert_eq!(search("fire%fox", 0).total, 0);
+
+    // Scoped to a node, connections stored without one still show
+    let mut other = event(50, "/usr/bin/wget", "example.net", 443, 1000, RuleAction::Allow);
+    other.node = "10.0.0.2:50051".to_string();
+    db.insert_connection(&other).unwrap();
+    let mut scoped = ConnectionQuery::parse("port:443", now).unwrap();
+    scoped.node = Some("unix:///tmp/osui.sock".to_string());
+    assert_eq!(db.search_connections(&scoped, 0, 10).unwrap().total, 30);
+    scoped.node = Some(other.node.clone());
+    let page = db.search_connections(&scoped, 0, 10).unwrap();
+    assert_eq!((page.total, page.events[0].node.as_str()), (31, "10.0.0.2:50051"));
+
+    // Daemons stamp events with their local time, without a zone
+    let mut daemon = event(55, "/usr/bin/nc", "example.com", 80, 1000, RuleAction::Allow);
+    let local = Utc.with_ymd_and_hms(2024, 3, 1, 11, 55, 0).unwrap().with_timezone(&Local);
+    daemon.time = local.format("%Y-%m-%d %H:%M:%S").to_string();
+    db.insert_connection(&daemon).unwrap();
+    assert_eq!(search("since:10m", 0).total, 1);
+    assert_eq!(search("until:10m process:nc", 0).total, 0);
+}
@@ -0,0 +1,179 @@
+//! Changes sent to daemons, logged with their replies
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::sync::{broadcast, mpsc};
+
+use opensnitch_tui::app::state::{run_state_manager, AppMessage};
+use opensnitch_tui::app::suggestions::DenialTracker;
+use opensnitch_tui::app::AppState;
+use opensnitch_tui::db::Database;
+use opensnitch_tui::grpc::notifications::{Delivery, NotificationAction};
+use opensnitch_tui::grpc::proto;
+use opensnitch_tui::models::node::ClientConfig;
+use opensnitch_tui::models::{Operator, Rule, RuleAction, RuleDuration};
+
+#[test]
+fn actions_are_summarized() {
+    let rule = Rule::new("block-tracker", RuleAction::Deny, RuleDuration::Always, Operator::simple("dest.host", "t.example"));
+    let action = NotificationAction::ChangeRule(rule);
+    assert_eq!(action.name(), "change rule");
+    assert_eq!(action.summary(), "block-tracker: deny always if dest.host t.example");
+
+    let config = NotificationAction::ChangeConfig(r#"{"DefaultAction":"deny","LogLevel":2}"#.to_string());
+    assert_eq!(config.summary(), "DefaultAction, LogLevel");
+}
+
+#[tokio::test]
+async fn sent_changes_get_their_replies() {
+    let (ui_update_tx, _) = broadcast::channel(100);
+    let state = Arc::new(AppState::new(Database::open(":memory:").unwrap(), ui_update_tx.clone()));
+    let (tx, rx) = mpsc::channel(10);
+    let manager = tokio::spawn(run_state_manager(
+        state.clone(),
+        rx,
+        ui_update_tx,
+        DenialTracker::new(0, Duration::from_secs(60)),
+    ));
+
+    let (daemon_tx, mut daemon_rx) = mpsc::channel(10);
+    let node_addr = "node-a".to_string();
+    tx.send(AppMessage::NotificationChannelOpened { node_addr: node_addr.clone(), tx: daemon_tx }).await.unwrap();
+    let rule = Rule::new("curl", RuleAction::Allow, RuleDuration::Always, Operator::simple("process.path", "/usr/bin/curl"));
+    for action in [NotificationAction::ChangeRule(rule), NotificationAction::DeleteRule("old".to_string())] {
+        tx.send(AppMessage::SendNotification { node_addr: node_addr.clone(), action }).await.unwrap();
+    }
+    tx.send(AppMessage::SendNotification { node_addr: "node-b".to_string(), action: NotificationAction::Stop })
+        .await
+        .unwrap();
+
+    let changed = daemon_rx.recv().await.unwrap();
+    let deleted = daemon_rx.recv().await.unwrap();
+    for (id, code, data) in [
+        (changed.id, proto::NotificationReplyCode::Ok as i32, String::new()),
+        (deleted.id, proto::NotificationReplyCode::Error as i32, "no such rule".to_string()),
+    ] {
+        tx.send(AppMessage::NotificationReply { node_addr: node_addr.clone(), id, code, data }).await.unwrap();
+    }
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    let sent = state.sent_notifications.read().await;
+    let log: Vec<_> = sent.iter().map(|s| (s.node_addr.as_str(), s.action, s.delivery.clone())).collect();
+    assert_eq!(
+        log,
+        [
+            ("node-b", "stop daemon", Delivery::NotSent),
+            ("node-a", "delete rule", Delivery::Error("no such rule".to_string())),
+            ("node-a", "change rule", Delivery::Ok(String::new())),
+        ]
+    );
+    // The payload as sent, readable
+    assert!(sent[2].payload.contains("\n  \"name\": \"curl\""));
+    drop(sent);
+    manager.abort();
+}
+
+#[tokio::test]
+async fn panic_button_locks_a_node_down_until_resumed() {
+    let (ui_update_tx, _) = broadcast::channel(100);
+    let state = Arc::new(AppState::new(Database::open(":memory:").unwrap(), ui_update_tx.clone()));
+    let original = r#"{"DefaultAction":"allow","InterceptUnknown":false,"FwOptions":{"QueueBypass":true}}"#;
+    let config = ClientConfig { config: original.to_string(), ..Default::default() };
+    state.nodes.write().await.add_node("node-a", config);
+    let (tx, rx) = mpsc::channel(10);
+    let manager = tokio::spawn(run_state_manager(
+        state.clone(),
+        rx,
+        ui_update_tx,
+        DenialTracker::new(0, Duration::from_secs(60)),
+    ));
+    let (daemon_tx, mut daemon_rx) = mpsc::channel(10);
+    tx.send(AppMessage::NotificationChannelOpened { node_addr: "node-a".to_string(), tx: daemon_tx }).await.unwrap();
+
+    // Pressed twice, the second press changes nothing
+    for _ in 0..2 {
+        tx.send(AppMessage::Panic { node_addr: "node-a".to_string(), engage: true }).await.unwrap();
+    }
+    let locked: serde_json::Value = serde_json::from_str(&daemon_rx.recv().await.unwrap().data).unwrap();
+    assert_eq!(locked["DefaultAction"], "deny");
+    assert_eq!(locked["InterceptUnknown"], true);
+    assert_eq!(locked["FwOptions"]["QueueBypass"], false);
+    assert_eq!(state.panicked_nodes(), ["node-a"]);
+
+    tx.send(AppMessage::Panic { node_addr: "node-a".to_string(), engage: false }).await.unwrap();
+    let restored = daemon_rx.recv().await.unwrap().data;
+    assert_eq!(restored, original);
+    assert!(!state.in_panic("node-a"));
+    assert_eq!(state.nodes.read().await.get_node("node-a").unwrap().config, original);
+    manager.abort();
+}
+
+#[tokio::test]
+async fn rejected_rule_changes_are_rolled_back() {
+    let (ui_update_tx, _) = broadcast::channel(100);
+    let state = Arc::new(AppState::new(Database::open(":memory:").unwrap(), ui_update_tx.clone()));
+    state.nodes.write().await.add_node("node-a", ClientConfig::default());
+    let (tx, rx) = mpsc::channel(10);
+    let manager = tokio::spawn(run_state_manager(
+        state.clone(),
+        rx,
+        ui_update_tx,
+        DenialTracker::new(0, Duration::from_secs(60)),
+    ));
+    let (daemon_tx, mut daemon_rx) = mpsc::channel(10);
+    let node_addr = "node-a".to_string();
+    tx.send(AppMessage::NotificationChannelOpened { node_addr: node_addr.clone(), tx: daemon_tx }).await.unwrap();
+
+    let curl = Rule::new("curl", RuleAction::Allow, RuleDuration::Always, Operator::simple("process.path", "/usr/bin/curl"));
+    let mut denied = curl.clone();
+    denied.action = RuleAction::Deny;
+    let wget = Rule::new("wget", RuleAction::Allow, RuleDuration::Always, Operator::simple("process.path", "/usr/bin/wget"));
+    for (local, action) in [
+        (AppMessage::RuleAdded { node_addr: node_addr.clone(), rule: curl.clone() }, NotificationAction::ChangeRule(curl.clone())),
+        (AppMessage::RuleModified { node_addr: node_addr.clone(), rule: denied.clone() }, NotificationAction::ChangeRule(denied)),
+        (AppMessage::RuleAdded { node_addr: node_addr.clone(), rule: wget.clone() }, NotificationAction::ChangeRule(wget)),
+    ] {
+        tx.send(local).await.unwrap();
+        tx.send(AppMessage::SendNotification { node_addr: node_addr.clone(), action }).await.unwrap();
+    }
+    let ids: Vec<_> = [daemon_rx.recv().await, daemon_rx.recv().await, daemon_rx.recv().await]
+        .into_iter()
+        .map(|n| n.unwrap().id)
+        .collect();
+    // Shown as pending until the daemon replies
+    let mut pending: Vec<_> = state.unconfirmed_rules("node-a").await.into_iter().collect();
+    pending.sort();
+    assert_eq!(pending, ["curl", "wget"]);
+
+    for (id, code) in [
+        (ids[0], proto::NotificationReplyCode::Ok),
+        (ids[1], proto::NotificationReplyCode::Error),
+        (ids[2], proto::NotificationReplyCode::Error),
+    ] {
+        tx.send(AppMessage::NotificationReply { node_addr: node_addr.clone(), id, code: code as i32, data: "invalid operator".to_string() })
+            .await
+            .unwrap();
+    }
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    // The change to curl is undone and wget is gone again
+    let nodes = state.nodes.read().await;
+    let rules = &nodes.get_node("node-a").unwrap().rules;
+    assert_eq!(rules.len(), 1);
+    assert_eq!(rules[0].action, RuleAction::Allow);
+    drop(nodes);
+    assert!(state.unconfirmed_rules("node-a").await.is_empty());
+    let stored: Vec<_> = state.db.select_rules("node-a").unwrap().into_iter().map(|r| (r.name, r.action)).collect();
+    assert_eq!(stored, [("curl".to_string(), RuleAction::Allow)]);
+
+    let rejected: Vec<_> = state.rejected.read().await.iter().map(|r| r.message()).collect();
+    assert_eq!(
+        rejected,
+        [
+            "node-a rejected change rule curl: deny always if process.path /usr/bin/curl: invalid operator. Put back as before: curl.",
+            "node-a rejected change rule wget: allow always if process.path /usr/bin/wget: invalid operator. Put back as before: wget.",
+        ]
+    );
+    manager.abort();
+}
@@ -0,0 +1,83 @@
+//! Binding the daemon socket when its address is taken
+
+use std::os::unix::fs::PermissionsExt;
+
+use opensnitch_tui::config::paths::runtime_dir;
+use opensnitch_tui::config::Settings;
+use opensnitch_tui::grpc::server::{load_tls, BindOutcome, ServerListener};
+
+#[tokio::test]
+async fn falls_back_when_the_port_is_taken() {
+    let taken = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let taken = taken.local_addr().unwrap().to_string();
+
+    let outcome = BindOutcome::first_free(&[taken.clone(), "127.0.0.1:0".to_string()]).await;
+    let listener = outcome.listener.as_ref().unwrap();
+    assert_ne!(listener.address(), taken);
+    assert_eq!(outcome.failures.len(), 1);
+    let notice = outcome.notice().unwrap();
+    assert!(notice.contains(&format!("{} is already in use", taken)));
+    assert!(notice.contains(&format!("Listening on {} instead", listener.address())));
+
+    // Nothing free at all
+    let outcome = BindOutcome::first_free(&[taken]).await;
+    assert!(outcome.listener.is_none());
+    assert!(outcome.notice().unwrap().contains("daemons can't connect"));
+}
+
+#[tokio::test]
+async fn live_unix_sockets_are_not_taken_over() {
+    let path = std::env::temp_dir().join(format!("opensnitch-tui-server-{}.sock", std::process::id()));
+    let address = format!("unix://{}", path.display());
+
+    // A stale socket file is replaced
+    drop(std::os::unix::net::UnixListener::bind(&path).unwrap());
+    let first = ServerListener::bind(&address).await.unwrap();
+    assert_eq!(first.address(), address);
+
+    let err = ServerListener::bind(&address).await.err().unwrap();
+    assert_eq!(err.kind(), std::io::ErrorKind::AddrInUse);
+
+    drop(first);
+    let _ = std::fs::remove_file(&path);
+}
+
+#[test]
+fn tls_needs_a_certificate_and_its_key() {
+    assert!(load_tls("", "", "").unwrap().is_none());
+    assert!(load_tls("/etc/ssl/server.pem", "", "").is_err());
+    assert!(load_tls("", "", "/etc/ssl/ca.pem").is_err());
+
+    let missing = std::env::temp_dir().join(format!("opensnitch-tui-missing-{}.pem", std::process::id()));
+    let missing = missing.to_string_lossy();
+    let err = load_tls(&missing, &missing, "").err().unwrap();
+    assert!(err.to_string().contains("Cannot read"), "{}", err);
+}
+
+#[tokio::test]
+async fn only_tcp_listeners_carry_tls() {
+    let tcp = ServerListener::bind("127.0.0.1:0").await.unwrap();
+    assert!(tcp.is_tcp());
+
+    let path = std::env::temp_dir().join(format!("opensnitch-tui-plain-{}.sock", std::process::id()));
+    let unix = ServerListener::bind(&format!("unix://{}", path.display())).await.unwrap();
+    assert!(!unix.is_tcp());
+    drop(unix);
+    let _ = std::fs::remove_file(&path);
+}
+
+#[tokio::test]
+async fn the_fallback_socket_is_private() {
+    let dir = runtime_dir();
+    let fallback = Settings::default().server_fallback.last().cloned().unwrap();
+    assert_eq!(fallback, format!("unix://{}", dir.join("grpc.sock").display()));
+
+    // Bound the way the fallback is, under a name of its own
+    let path = dir.join(format!("grpc-test-{}.sock", std::process::id()));
+    let listener = ServerListener::bind(&format!("unix://{}", path.display())).await.unwrap();
+    assert_eq!(std::fs::metadata(&dir).unwrap().permissions().mode() & 0o777, 0o700);
+    assert_eq!(std::fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+    assert!(tokio::net::UnixStream::connect(&path).await.is_ok());
+    drop(listener);
+    std::fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,50 @@
+//! Exporting statistics for spreadsheets and dashboards
+
+use std::collections::HashMap;
+
+use chrono::{TimeZone, Utc};
+
+use opensnitch_tui::models::{Statistics, StatsFormat};
+
+fn stats() -> Statistics {
+    Statistics {
+        daemon_version: "1.6.5".to_string(),
+        connections: 12,
+        accepted: 9,
+        dropped: 3,
+        by_proto: HashMap::from([("tcp".to_string(), 10), ("udp".to_string(), 2)]),
+        by_host: HashMap::from([("example.com, inc".to_string(), 4)]),
+        by_executable: HashMap::from([("/usr/bin/curl".to_string(), 7)]),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn json_export_has_node_counters_and_sorted_breakdowns() {
+    let generated = Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap();
+    let json = stats().export("unix:///tmp/osui.sock", "laptop", generated, StatsFormat::Json);
+    let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+
+    assert_eq!(value["node"], "unix:///tmp/osui.sock");
+    assert_eq!(value["name"], "laptop");
+    assert_eq!(value["generated"], "2024-05-01T12:00:00+00:00");
+    assert_eq!(value["summary"]["connections"], 12);
+    assert_eq!(value["summary"]["dropped"], 3);
+    assert_eq!(value["by_protocol"][0]["value"], "tcp");
+    assert_eq!(value["by_protocol"][1]["count"], 2);
+    assert_eq!(value["by_port"], serde_json::json!([]));
+}
+
+#[test]
+fn csv_export_has_one_row_per_counter_and_entry() {
+    let generated = Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap();
+    let csv = stats().export("10.0.0.2:50051", "gateway", generated, StatsFormat::Csv);
+    let lines: Vec<&str> = csv.lines().collect();
+
+    assert_eq!(lines[0], "node,name,generated,section,key,count");
+    assert!(lines.contains(&"10.0.0.2:50051,gateway,2024-05-01T12:00:00+00:00,summary,accepted,9"));
+    assert!(lines.contains(&"10.0.0.2:50051,gateway,2024-05-01T12:00:00+00:00,by_protocol,udp,2"));
+    // Separators in values are quoted
+    assert!(lines.contains(&"10.0.0.2:50051,gateway,2024-05-01T12:00:00+00:00,by_host,\"example.com, inc\",4"));
+    assert_eq!(lines.len(), 1 + 9 + 4);
+}
@@ -0,0 +1,51 @@
+//! Status summary for status bars
+
+mod common;
+
+use chrono::{Duration, Utc};
+use tokio::sync::broadcast;
+
+use opensnitch_tui::app::status::StatusSummary;
+use opensnitch_tui::app::AppState;
+use opensnitch_tui::db::Database;
+use opensnitch_tui::models::node::ClientConfig;
+use opensnitch_tui::models::{Connection, Event, Operator, Rule, RuleAction, RuleDuration};
+
+use common::connection;
+
+fn event(action: RuleAction, age: Duration) -> Event {
+    let rule = Rule::new("r", action, RuleDuration::Always, Operator::simple("dest.host", "x"));
+    let mut event = Event::new(connection("/usr/bin/curl", "example.org", 443), Some(rule));
+    event.time = (Utc::now() - age).to_rfc3339();
+    event
+}
+
+#[tokio::test]
+async fn summary_counts_nodes_prompts_and_recent_denials() {
+    let (ui_update_tx, _) = broadcast::channel(10);
+    let state = AppState::new(Database::open(":memory:").unwrap(), ui_update_tx);
+    state.nodes.write().await.add_node("node-a", ClientConfig::default());
+    state.db.insert_prompt("node-a", &Connection::default()).unwrap();
+    {
+        let mut connections = state.connections.write().await;
+        let mut burst = event(RuleAction::Deny, Duration::minutes(5));
+        burst.count = 3;
+        connections.push_back(burst);
+        connections.push_back(event(RuleAction::Reject, Duration::minutes(30)));
+        connections.push_back(event(RuleAction::Allow, Duration::minutes(1)));
+        connections.push_back(event(RuleAction::Deny, Duration::hours(2)));
+    }
+
+    let summary = StatusSummary::capture(&state).await;
+    assert_eq!((summary.connected_nodes, summary.pending_prompts, summary.denied_last_hour), (1, 1, 4));
+    assert_eq!(summary.oneline(), "OpenSnitch 1 node | 1 prompt | 4 denied/h");
+
+    let path = std::env::temp_dir().join(format!("opensnitch-tui-status-{}.json", std::process::id()));
+    summary.save(&path).unwrap();
+    let loaded = StatusSummary::load(&path).unwrap();
+    assert_eq!(loaded, summary);
+    assert!(!loaded.is_stale(Utc::now()));
+    // Left behind by a collector that stopped
+    assert!(loaded.is_stale(Utc::now() + Duration::minutes(1)));
+    let _ = std::fs::remove_file(&path);
+}
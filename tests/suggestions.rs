@@ -0,0 +1,77 @@
+//! Denial counting behind rule suggestions
+
+mod common;
+
+use std::time::Duration;
+
+use opensnitch_tui::app::suggestions::DenialTracker;
+use opensnitch_tui::models::{Event, Operator, Rule, RuleAction, RuleDuration};
+
+use common::connection;
+
+const SECOND: i64 = 1_000_000_000;
+
+fn event(host: &str, action: RuleAction, secs: i64) -> Event {
+    let connection = connection("/usr/bin/curl", host, 443);
+    let rule = Rule::new("r", action, RuleDuration::Always, Operator::simple("true", ""));
+    let mut event = Event::new(connection, Some(rule));
+    event.unix_nano = secs * SECOND;
+    event
+}
+
+#[test]
+fn suggests_once_threshold_is_reached() {
+    let mut tracker = DenialTracker::new(3, Duration::from_secs(60));
+
+    assert!(tracker.record("node", &event("api.example.com", RuleAction::Deny, 1)).is_none());
+    assert!(tracker.record("node", &event("api.example.com", RuleAction::Allow, 2)).is_none());
+    assert!(tracker.record("node", &event("api.example.com", RuleAction::Reject, 3)).is_none());
+    let suggestion = tracker
+        .record("node", &event("api.example.com", RuleAction::Deny, 4))
+        .expect("third denial suggests a rule");
+    assert_eq!(suggestion.count, 3);
+    assert_eq!(suggestion.message(), "curl has been denied 3 times for api.example.com");
+
+    // The same connection is not suggested twice
+    for secs in 5..10 {
+        assert!(tracker.record("node", &event("api.example.com", RuleAction::Deny, secs)).is_none());
+    }
+}
+
+#[test]
+fn denials_outside_window_and_repeats_do_not_count() {
+    let mut tracker = DenialTracker::new(2, Duration::from_secs(10));
+
+    let first = event("api.example.com", RuleAction::Deny, 0);
+    assert!(tracker.record("node", &first).is_none());
+    // The same event resent by a later stats update
+    assert!(tracker.record("node", &first).is_none());
+    // Too long after the first one
+    assert!(tracker.record("node", &event("api.example.com", RuleAction::Deny, 30)).is_none());
+    // Different nodes are counted apart
+    assert!(tracker.record("other", &event("api.example.com", RuleAction::Deny, 31)).is_none());
+    assert!(tracker.record("node", &event("api.example.com", RuleAction::Deny, 35)).is_some());
+}
+
+#[test]
+fn suggested_rules_match_process_and_destination() {
+    let mut tracker = DenialTracker::new(1, Duration::from_secs(60));
+    let mut e = event("", RuleAction::Deny, 1);
+    e.connection.dst_host.clear();
+    let suggestion = tracker.record("node", &e).unwrap();
+    assert_eq!(suggestion.destination, "93.184.216.34");
+
+    let deny = suggestion.rule(RuleAction::Deny);
+    assert_eq!(deny.duration, RuleDuration::Always);
+    assert!(!deny.precedence);
+    let operands: Vec<&str> = deny.operator.list.iter().map(|o| o.operand.as_str()).collect();
+    assert_eq!(operands, ["process.path", "dest.ip"]);
+
+    assert!(suggestion.rule(RuleAction::Allow).precedence);
+}
+
+#[test]
+fn zero_threshold_disables_suggestions() {
+    let mut tracker = DenialTracker::new(0, Duration::from_secs(60));
+    assert!(tracker.record("node", &event("api.example.com", RuleAction::Deny, 1)).is_none());
+}
@@ -0,0 +1,1481 @@
+//! Key handling of the tab state machines, driven without a running app
+
+mod common;
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
+use tokio::sync::broadcast;
+
+use opensnitch_tui::app::state::{AppMessage, UiUpdateSignal};
+use opensnitch_tui::app::AppState;
+use opensnitch_tui::app::xref::ConnectionRef;
+use opensnitch_tui::config::Settings;
+use opensnitch_tui::db::search::SearchPage;
+use opensnitch_tui::db::Database;
+use opensnitch_tui::grpc::notifications::NotificationAction;
+use opensnitch_tui::models::node::{ClientConfig, MAX_REJECTED_PEERS};
+use opensnitch_tui::models::{
+    Alert, AlertData, AlertPriority, AlertType, AlertWhat, Connection, DnsEntry, Event, FwChain, FwChains,
+    FwRule, Node, NodeManager, Operator, OperatorType, Policies, Rule, RuleAction, RuleDuration, RuleHits, RuleIndex, Statistics, StatsFormat,
+    SysFirewall, TrafficHistory,
+};
+use opensnitch_tui::ui::app::{StaleTabs, TabId};
+use opensnitch_tui::ui::dialogs::confirm::ConfirmDialog;
+use opensnitch_tui::ui::dialogs::rule_editor::{EditorFocus, RuleEditorDialog, RuleEditorResult};
+use opensnitch_tui::ui::tabs::{
+    alerts::AlertsTab, connections::ConnectionsTab, dns::DnsTab, firewall::FirewallTab, nodes::NodesTab,
+    rules::{PolicyRow, RulesTab}, statistics::StatisticsTab, step_index, Jump, Tab, TabCommand,
+};
+use opensnitch_tui::ui::theme::Theme;
+use opensnitch_tui::utils::Formats;
+
+use common::{ctrl, key};
+
+/// Feed keys one by one, keeping the commands of the last key
+fn press(tab: &mut impl Tab, keys: &[KeyEvent]) -> Vec<TabCommand> {
+    keys.iter().fold(Vec::new(), |_, k| tab.handle_key(*k))
+}
+
+fn type_text(tab: &mut impl Tab, text: &str) {
+    for c in text.chars() {
+        tab.handle_key(key(KeyCode::Char(c)));
+    }
+}
+
+fn sent(commands: Vec<TabCommand>) -> Vec<AppMessage> {
+    commands
+        .into_iter()
+        .map(|c| match c {
+            TabCommand::Send(msg) => *msg,
+            other => panic!("expected a message, got {:?}", other),
+        })
+        .collect()
+}
+
+fn rule(name: &str) -> Rule {
+    Rule::new(name, RuleAction::Allow, RuleDuration::Always, Operator::simple("process.path", name))
+}
+
+#[test]
+fn step_index_clamps_and_jumps() {
+    assert_eq!(step_index(Some(0), 0, 1), None);
+    assert_eq!(step_index(Some(0), 3, -1), Some(0));
+    assert_eq!(step_index(Some(1), 3, 10), Some(2));
+    assert_eq!(step_index(None, 3, 1), Some(1));
+    assert_eq!(step_index(Some(1), 3, i32::MIN), Some(0));
+    assert_eq!(step_index(Some(0), 3, i32::MAX), Some(2));
+}
+
+// Alerts
+
+fn alert(id: u64, priority: AlertPriority, text: &str) -> Alert {
+    Alert::new(id, AlertType::Warning, priority, AlertWhat::Generic, Some(AlertData::Text(text.to_string())))
+}
+
+fn acked_id(commands: Vec<TabCommand>) -> u64 {
+    match commands.as_slice() {
+        [TabCommand::AcknowledgeAlert { id, .. }] => *id,
+        other => panic!("expected an acknowledge command, got {:?}", other),
+    }
+}
+
+#[test]
+fn alerts_navigation_selects_alert_to_acknowledge() {
+    let mut tab = AlertsTab::new();
+    tab.set_alerts(vec![
+        alert(1, AlertPriority::Low, "one"),
+        alert(2, AlertPriority::High, "two"),
+        alert(3, AlertPriority::Medium, "three"),
+    ]);
+
+    assert_eq!(acked_id(tab.handle_key(key(KeyCode::Char('a')))), 1);
+    assert_eq!(acked_id(press(&mut tab, &[key(KeyCode::Down), key(KeyCode::Char('a'))])), 2);
+    assert_eq!(acked_id(press(&mut tab, &[key(KeyCode::End), key(KeyCode::Char('a'))])), 3);
+    assert_eq!(acked_id(press(&mut tab, &[key(KeyCode::Down), key(KeyCode::Char('a'))])), 3);
+    assert!(matches!(
+        tab.handle_key(key(KeyCode::Char('A'))).as_slice(),
+        [TabCommand::AcknowledgeAllAlerts]
+    ));
+}
+
+#[test]
+fn acknowledged_alerts_are_hidden_and_details_open_with_enter() {
+    let mut tab = AlertsTab::new();
+    let mut acked = alert(1, AlertPriority::Low, "disk full");
+    acked.acknowledged = true;
+    let rule = rule("block-tracker");
+    tab.set_alerts(vec![
+        acked,
+        Alert::new(2, AlertType::Error, AlertPriority::High, AlertWhat::Rule, Some(AlertData::Rule(rule))),
+    ]);
+    let ids = |tab: &AlertsTab| tab.filtered_alerts().iter().map(|a| a.id).collect::<Vec<_>>();
+    assert_eq!(ids(&tab), [2]);
+    tab.handle_key(key(KeyCode::Char('v')));
+    assert_eq!(ids(&tab), [1, 2]);
+    tab.handle_key(key(KeyCode::Char('v')));
+
+    assert!(tab.handle_key(key(KeyCode::Enter)).is_empty());
+    assert!(tab.showing_dialog());
+    let theme = Theme::default();
+    let screen = rendered(|f| tab.render(f, f.area(), &theme));
+    assert!(screen.contains("Name:     block-tracker"), "{}", screen);
+    assert!(screen.contains("Status:   open"));
+
+    // Acknowledged from the dialog, which closes
+    assert_eq!(acked_id(tab.handle_key(key(KeyCode::Char('a')))), 2);
+    assert!(!tab.showing_dialog());
+}
+
+fn mouse(kind: MouseEventKind, column: u16, row: u16) -> MouseEvent {
+    MouseEvent { kind, column, row, modifiers: KeyModifiers::NONE }
+}
+
+#[test]
+fn mouse_selects_rows_scrolls_and_presses_buttons() {
+    let mut tab = AlertsTab::new();
+    tab.set_alerts((1..=8).map(|id| alert(id, AlertPriority::Low, "disk full")).collect());
+    let theme = Theme::default();
+    rendered(|f| tab.render(f, f.area(), &theme));
+
+    // Title and header lines come before the first row
+    let click = |row| mouse(MouseEventKind::Down(MouseButton::Left), 10, row);
+    assert!(tab.handle_mouse(click(1)).is_empty());
+    assert_eq!(acked_id(tab.handle_key(key(KeyCode::Char('a')))), 1);
+    tab.handle_mouse(click(4));
+    assert_eq!(acked_id(tab.handle_key(key(KeyCode::Char('a')))), 3);
+    // Past the last row
+    tab.handle_mouse(click(15));
+    assert_eq!(acked_id(tab.handle_key(key(KeyCode::Char('a')))), 3);
+
+    tab.handle_mouse(mouse(MouseEventKind::ScrollDown, 10, 5));
+    assert_eq!(acked_id(tab.handle_key(key(KeyCode::Char('a')))), 6);
+    tab.handle_mouse(mouse(MouseEventKind::ScrollUp, 10, 5));
+    assert_eq!(acked_id(tab.handle_key(key(KeyCode::Char('a')))), 3);
+
+    let dialog = ConfirmDialog::new("Daemon Configuration", "Set the default action to deny?");
+    let screen = rendered(|f| dialog.render(f, &theme));
+    let (column, row) = screen
+        .chars()
+        .collect::<Vec<_>>()
+        .chunks(100)
+        .enumerate()
+        .find_map(|(row, line)| {
+            let line: String = line.iter().collect();
+            line.find("[ No ]").map(|column| (line[..column].chars().count() as u16 + 2, row as u16))
+        })
+        .unwrap();
+    assert_eq!(dialog.click(column, row), Some(key(KeyCode::Char('n'))));
+    assert_eq!(dialog.click(0, 0), None);
+}
+
+#[test]
+fn alerts_severity_and_search_filter() {
+    let mut tab = AlertsTab::new();
+    tab.set_alerts(vec![
+        alert(1, AlertPriority::Low, "disk full"),
+        alert(2, AlertPriority::High, "rule error"),
+        alert(3, AlertPriority::High, "disk error"),
+    ]);
+
+    tab.handle_key(key(KeyCode::Char('p')));
+    let ids: Vec<u64> = tab.filtered_alerts().iter().map(|a| a.id).collect();
+    assert_eq!(ids, [2, 3]);
+
+    tab.handle_key(key(KeyCode::Char('/')));
+    type_text(&mut tab, "disk");
+    tab.handle_key(key(KeyCode::Enter));
+    let ids: Vec<u64> = tab.filtered_alerts().iter().map(|a| a.id).collect();
+    assert_eq!(ids, [3]);
+
+    // Normal mode Esc clears the query, Ctrl+R brings it back
+    tab.handle_key(key(KeyCode::Esc));
+    assert_eq!(tab.filtered_alerts().len(), 2);
+    tab.handle_key(ctrl('r'));
+    assert_eq!(tab.filtered_alerts().len(), 1);
+}
+
+/// Render a tab into an off-screen terminal and return its text
+fn rendered(render: impl FnOnce(&mut ratatui::Frame)) -> String {
+    let mut terminal = ratatui::Terminal::new(ratatui::backend::TestBackend::new(100, 20)).unwrap();
+    let frame = terminal.draw(render).unwrap();
+    frame.buffer.content().iter().map(|cell| cell.symbol()).collect()
+}
+
+#[test]
+fn filter_shows_live_match_count() {
+    let mut tab = AlertsTab::new();
+    tab.set_alerts(vec![
+        alert(1, AlertPriority::Low, "disk full"),
+        alert(2, AlertPriority::High, "rule error"),
+        alert(3, AlertPriority::High, "disk error"),
+    ]);
+    let theme = Theme::default();
+
+    tab.handle_key(key(KeyCode::Char('/')));
+    type_text(&mut tab, "disk");
+    let screen = rendered(|f| tab.render(f, f.area(), &theme));
+    assert!(screen.contains("matches: 2 of 3"));
+
+    type_text(&mut tab, " e");
+    let screen = rendered(|f| tab.render(f, f.area(), &theme));
+    assert!(screen.contains("matches: 1 of 3"));
+}
+
+// Connections
+
+fn event(process: &str, host: &str, port: u32, time: &str) -> Event {
+    let mut event = common::event(process, host, port);
+    event.time = time.to_string();
+    event
+}
+
+#[test]
+fn connections_replay_selected_event() {
+    let mut tab = ConnectionsTab::new();
+    let events = [
+        event("/usr/bin/curl", "example.com", 443, "2024-01-01T10:00:00"),
+        event("/usr/bin/ssh", "host.lan", 22, "2024-01-01T10:00:01"),
+    ];
+    tab.set_events(events.iter(), None);
+
+    match tab.handle_key(key(KeyCode::Char('R'))).as_slice() {
+        [TabCommand::ReplayEvent(event)] => assert_eq!(event.connection.process_path, "/usr/bin/ssh"),
+        other => panic!("unexpected commands {:?}", other),
+    }
+}
+
+#[test]
+fn connections_search_history_a_page_at_a_time() {
+    let mut tab = ConnectionsTab::new();
+    tab.set_events([event("/usr/bin/curl", "live.example.com", 443, "2024-01-01T10:00:00")].iter(), None);
+
+    press(&mut tab, &[key(KeyCode::Char('H'))]);
+    assert!(tab.showing_dialog());
+    type_text(&mut tab, "ssh port:22");
+    match tab.handle_key(key(KeyCode::Enter)).as_slice() {
+        [TabCommand::SearchConnections { query, offset: 0 }] => assert_eq!(query, "ssh port:22"),
+        other => panic!("unexpected commands {:?}", other),
+    }
+
+    // Identical stored events are listed one by one
+    let old = event("/usr/bin/ssh", "old.example.com", 22, "2023-06-01T08:00:00");
+    let events = vec![old.clone(), old];
+    tab.set_search_results(Ok(SearchPage { events, offset: 0, total: 150 }));
+    assert_eq!(tab.visible_len(), 2);
+    let screen = rendered(|f| tab.render(f, f.area(), &Theme::default()));
+    assert!(screen.contains("History 1-2 of 150"));
+    assert!(!screen.contains("live.example.com"));
+
+    assert!(matches!(
+        tab.handle_key(key(KeyCode::Char('n'))).as_slice(),
+        [TabCommand::SearchConnections { offset: 100, .. }]
+    ));
+    assert!(tab.handle_key(key(KeyCode::Char('p'))).is_empty());
+
+    tab.set_search_results(Err("invalid port: x".to_string()));
+    let screen = rendered(|f| tab.render(f, f.area(), &Theme::default()));
+    assert!(screen.contains("search failed: invalid port: x"));
+
+    // Back to live connections, they show again at the next refresh
+    tab.handle_key(key(KeyCode::Esc));
+    assert_eq!(tab.visible_len(), 0);
+    assert!(tab.handle_key(key(KeyCode::Char('n'))).is_empty());
+}
+
+#[test]
+fn connections_privacy_mode_masks_hosts() {
+    let mut tab = ConnectionsTab::new();
+    let events = [event("/usr/bin/curl", "secret.example.com", 443, "2024-01-01T10:00:00")];
+    tab.set_events(events.iter(), None);
+    let screen = rendered(|f| tab.render(f, f.area(), &Theme::default()));
+    assert!(screen.contains("secret.example.com"));
+
+    tab.set_formats(Formats::default().with_private(true));
+    tab.handle_key(key(KeyCode::Enter));
+    let screen = rendered(|f| tab.render(f, f.area(), &Theme::default()));
+    assert!(!screen.contains("secret"));
+    assert!(screen.contains(".com"));
+}
+
+#[test]
+fn connections_show_the_action_as_a_word() {
+    let mut tab = ConnectionsTab::new();
+    let mut denied = event("/usr/bin/curl", "ads.example.com", 443, "2024-01-01T10:00:00");
+    denied.rule = Some(Rule::new("ads", RuleAction::Deny, RuleDuration::Always, Operator::simple("dest.host", "x")));
+    tab.set_events([denied].iter(), None);
+    let screen = rendered(|f| tab.render(f, f.area(), &Theme::colorblind()));
+    assert!(screen.contains("✗ DENY"));
+}
+
+#[test]
+fn connections_filter_and_block_flow() {
+    let mut tab = ConnectionsTab::new();
+    let events = [
+        event("/usr/bin/curl", "example.com", 443, "2024-01-01T10:00:00"),
+        event("/usr/bin/curl", "example.com", 443, "2024-01-01T10:00:05"),
+        event("/usr/bin/ssh", "host.lan", 22, "2024-01-01T10:00:01"),
+    ];
+    tab.set_events(events.iter(), Some("unix:/tmp/osui.sock".to_string()));
+    assert_eq!(tab.visible_len(), 2);
+
+    tab.handle_key(key(KeyCode::Char('/')));
+    type_text(&mut tab, "ssh");
+    tab.handle_key(key(KeyCode::Enter));
+    assert_eq!(tab.visible_len(), 1);
+
+    // The details dialog opens on the filtered row, not the first aggregated one
+    tab.handle_key(key(KeyCode::Enter));
+    assert!(tab.showing_dialog());
+    let commands = press(&mut tab, &[key(KeyCode::Tab), key(KeyCode::Enter)]);
+    assert!(!tab.showing_dialog());
+
+    match sent(commands).as_slice() {
+        [AppMessage::RuleAdded { rule, .. }, AppMessage::SendNotification { action: NotificationAction::ChangeRule(_), .. }] => {
+            assert_eq!(rule.action, RuleAction::Deny);
+            assert_eq!(rule.operator.data, "/usr/bin/ssh");
+        }
+        other => panic!("unexpected messages {:?}", other),
+    }
+}
+
+#[test]
+fn connections_filter_by_interface() {
+    let mut tab = ConnectionsTab::new();
+    let mut events = [
+        event("/usr/bin/curl", "example.com", 443, "2024-01-01T10:00:00"),
+        event("/usr/bin/ssh", "host.lan", 22, "2024-01-01T10:00:01"),
+    ];
+    events[0].connection.interface = "tun0".to_string();
+    events[1].connection.interface = "wlan0".to_string();
+    tab.set_events(events.iter(), None);
+
+    tab.handle_key(key(KeyCode::Char('/')));
+    type_text(&mut tab, "iface:tun0");
+    assert_eq!(tab.visible_len(), 1);
+    // Combined with a text filter
+    type_text(&mut tab, " ssh");
+    assert_eq!(tab.visible_len(), 0);
+}
+
+#[test]
+fn connections_anomalies_only_filter() {
+    let mut tab = ConnectionsTab::new();
+    let events = [
+        event("/usr/bin/curl", "example.com", 8443, "2024-01-01T10:00:00"),
+        event("/usr/bin/ssh", "ssh.github.com", 443, "2024-01-01T10:00:01"),
+        event("/usr/bin/ssh", "host.lan", 22, "2024-01-01T10:00:02"),
+    ];
+    tab.set_events(events.iter(), None);
+    assert_eq!(tab.visible_len(), 3);
+
+    tab.handle_key(key(KeyCode::Char('a')));
+    assert_eq!(tab.visible_len(), 1);
+    let screen = rendered(|f| tab.render(f, f.area(), &Theme::default()));
+    assert!(screen.contains("SSH:443"));
+
+    tab.handle_key(key(KeyCode::Char('a')));
+    assert_eq!(tab.visible_len(), 3);
+}
+
+#[test]
+fn connections_dialog_without_node_sends_nothing() {
+    let mut tab = ConnectionsTab::new();
+    let events = [event("/usr/bin/curl", "example.com", 443, "2024-01-01T10:00:00")];
+    tab.set_events(events.iter(), None);
+
+    let commands = press(&mut tab, &[key(KeyCode::Enter), key(KeyCode::Tab), key(KeyCode::Enter)]);
+    assert!(commands.is_empty());
+    assert!(!tab.showing_dialog());
+}
+
+#[test]
+fn connections_timeline_filters_to_bucket() {
+    let at_minute = |process: &str, minute: i64| {
+        let mut e = event(process, "example.com", 443, "2024-01-01T10:00:00");
+        e.unix_nano = minute * 60_000_000_000;
+        e
+    };
+    let mut tab = ConnectionsTab::new();
+    let events = [at_minute("/usr/bin/curl", 100), at_minute("/usr/bin/ssh", 101), at_minute("/usr/bin/curl", 101)];
+    tab.set_events(events.iter(), None);
+    assert_eq!(tab.visible_len(), 2);
+
+    // Arrows only move the cursor with the timeline shown
+    tab.handle_key(key(KeyCode::Left));
+    assert_eq!(tab.visible_len(), 2);
+
+    // The cursor starts at the newest minute
+    press(&mut tab, &[key(KeyCode::Char('t')), key(KeyCode::Right)]);
+    assert_eq!(tab.visible_len(), 2);
+    tab.handle_key(key(KeyCode::Left));
+    assert_eq!(tab.visible_len(), 1);
+
+    // Esc drops the bucket before touching the search query
+    tab.handle_key(key(KeyCode::Esc));
+    assert_eq!(tab.visible_len(), 2);
+}
+
+// Rules
+
+#[test]
+fn rules_toggle_follows_filtered_selection() {
+    let mut tab = RulesTab::new();
+    tab.set_rules(vec![rule("alpha"), rule("beta"), rule("gamma")], Some("node".to_string()));
+
+    tab.handle_key(key(KeyCode::Char('/')));
+    type_text(&mut tab, "a");
+    tab.handle_key(key(KeyCode::Enter));
+    assert_eq!(tab.filtered_rules().len(), 3);
+    tab.handle_key(key(KeyCode::Char('/')));
+    type_text(&mut tab, "mm");
+    tab.handle_key(key(KeyCode::Enter));
+    assert_eq!(tab.selected_rule().map(|r| r.name.as_str()), Some("gamma"));
+
+    match sent(tab.handle_key(key(KeyCode::Char(' ')))).as_slice() {
+        [AppMessage::RuleToggled { name, enabled, .. }, AppMessage::SendNotification { action: NotificationAction::DisableRule(n), .. }] => {
+            assert_eq!(name, "gamma");
+            assert!(!enabled);
+            assert_eq!(n, "gamma");
+        }
+        other => panic!("unexpected messages {:?}", other),
+    }
+}
+
+#[test]
+fn rules_sort_by_hits_to_find_dead_ones() {
+    let mut tab = RulesTab::new();
+    tab.set_rules(vec![rule("alpha"), rule("beta"), rule("gamma")], Some("node".to_string()));
+    let last_hit = Some(chrono::Utc::now() - chrono::Duration::minutes(5));
+    tab.set_hits(HashMap::from([
+        ("alpha".to_string(), RuleHits { count: 2, last_hit }),
+        ("gamma".to_string(), RuleHits { count: 40, last_hit }),
+    ]));
+    let names = |tab: &RulesTab| tab.filtered_rules().iter().map(|r| r.name.clone()).collect::<Vec<_>>();
+    assert_eq!(names(&tab), ["alpha", "beta", "gamma"]);
+
+    let theme = Theme::default();
+    let screen = rendered(|f| tab.render(f, f.area(), &theme));
+    assert!(screen.contains("Last Hit"), "{}", screen);
+    assert!(screen.contains("5m ago"), "{}", screen);
+    assert!(screen.contains("never"), "{}", screen);
+
+    // Name, Enabled, Action, Duration, Updated, then Hits
+    for _ in 0..6 {
+        tab.handle_key(key(KeyCode::Char('s')));
+    }
+    assert_eq!(names(&tab), ["beta", "alpha", "gamma"]);
+    assert_eq!(tab.selected_rule().map(|r| r.name.as_str()), Some("beta"));
+    let screen = rendered(|f| tab.render(f, f.area(), &theme));
+    assert!(screen.contains("Hits ▲"), "{}", screen);
+    tab.handle_key(key(KeyCode::Char('S')));
+    assert_eq!(names(&tab), ["gamma", "alpha", "beta"]);
+    for _ in 0..4 {
+        tab.handle_key(key(KeyCode::Char('s')));
+    }
+    assert_eq!(names(&tab), ["alpha", "beta", "gamma"]);
+}
+
+#[test]
+fn rules_tab_cuts_a_process_off_for_a_while() {
+    let mut tab = RulesTab::new().with_sandbox_duration(RuleDuration::OneHour);
+    tab.set_rules(vec![rule("/usr/bin/curl")], Some("node".to_string()));
+
+    // Starts from the process of the selected rule
+    tab.handle_key(key(KeyCode::Char('b')));
+    assert!(tab.showing_dialog());
+    let theme = Theme::default();
+    let screen = rendered(|f| tab.render(f, f.area(), &theme));
+    assert!(screen.contains("No network for 1h"), "{}", screen);
+
+    match sent(tab.handle_key(key(KeyCode::Enter))).as_slice() {
+        [AppMessage::RuleAdded { node_addr, rule }, AppMessage::SendNotification { action: NotificationAction::ChangeRule(sent), .. }] => {
+            assert_eq!(node_addr, "node");
+            assert!(rule.name.starts_with("sandbox-curl-"), "{}", rule.name);
+            assert_ne!(rule.name, Rule::sandbox("/usr/local/bin/curl", RuleDuration::OneHour).name);
+            assert_eq!(rule.action, RuleAction::Deny);
+            assert_eq!(rule.duration, RuleDuration::OneHour);
+            assert!(rule.precedence);
+            assert_eq!(rule.operator.data, "/usr/bin/curl");
+            assert_eq!(sent.name, rule.name);
+        }
+        other => panic!("unexpected messages {:?}", other),
+    }
+    assert!(!tab.showing_dialog());
+
+    // Or any path typed in
+    tab.handle_key(key(KeyCode::Char('b')));
+    for _ in 0.."/usr/bin/curl".len() {
+        tab.handle_key(key(KeyCode::Backspace));
+    }
+    type_text(&mut tab, "/opt/app/bin/app");
+    match sent(tab.handle_key(key(KeyCode::Enter))).as_slice() {
+        [AppMessage::RuleAdded { rule, .. }, _] => assert_eq!(rule.operator.data, "/opt/app/bin/app"),
+        other => panic!("unexpected messages {:?}", other),
+    }
+}
+
+#[test]
+fn tables_sort_by_column() {
+    let mut tab = AlertsTab::new();
+    tab.set_alerts(vec![
+        alert(1, AlertPriority::Low, "disk full"),
+        alert(2, AlertPriority::High, "rule error"),
+        alert(3, AlertPriority::Medium, "disk error"),
+    ]);
+    let ids = |tab: &AlertsTab| tab.filtered_alerts().iter().map(|a| a.id).collect::<Vec<u64>>();
+
+    // Time, Type, then Priority
+    press(&mut tab, &[key(KeyCode::Char('s')), key(KeyCode::Char('s')), key(KeyCode::Char('s'))]);
+    assert_eq!(ids(&tab), [1, 3, 2]);
+    let theme = Theme::default();
+    let screen = rendered(|f| tab.render(f, f.area(), &theme));
+    assert!(screen.contains("Priority ▲"), "{}", screen);
+
+    tab.handle_key(key(KeyCode::Char('S')));
+    assert_eq!(ids(&tab), [2, 3, 1]);
+    let screen = rendered(|f| tab.render(f, f.area(), &theme));
+    assert!(screen.contains("Priority ▼"), "{}", screen);
+
+    // Past the last column back to the order they came in, which reverses too
+    press(&mut tab, &[key(KeyCode::Char('s')), key(KeyCode::Char('s')), key(KeyCode::Char('s'))]);
+    assert_eq!(ids(&tab), [1, 2, 3]);
+    tab.handle_key(key(KeyCode::Char('S')));
+    assert_eq!(ids(&tab), [3, 2, 1]);
+}
+
+#[test]
+fn connections_group_by_process_destination_or_not_at_all() {
+    let events = [
+        event("/usr/bin/curl", "a.example", 443, "12:00:01"),
+        event("/usr/bin/curl", "a.example", 443, "12:00:02"),
+        event("/usr/bin/curl", "b.example", 80, "12:00:03"),
+        event("/usr/bin/wget", "b.example", 80, "12:00:04"),
+    ];
+    let mut tab = ConnectionsTab::new();
+    let theme = Theme::default();
+    let regroup = |tab: &mut ConnectionsTab| {
+        tab.handle_key(key(KeyCode::Char('g')));
+        tab.set_events(events.iter(), None);
+        rendered(|f| tab.render(f, f.area(), &theme))
+    };
+    tab.set_events(events.iter(), None);
+    assert_eq!(tab.visible_len(), 3);
+
+    let screen = regroup(&mut tab);
+    assert_eq!(tab.visible_len(), 2);
+    assert!(screen.contains("Connections by Process (2)"), "{}", screen);
+    assert!(screen.contains("2 destinations"), "{}", screen);
+
+    let screen = regroup(&mut tab);
+    assert_eq!(tab.visible_len(), 2);
+    assert!(screen.contains("2 processes"), "{}", screen);
+
+    let screen = regroup(&mut tab);
+    assert_eq!(tab.visible_len(), 4);
+    assert!(screen.contains("All Connections (4)"), "{}", screen);
+
+    regroup(&mut tab);
+    assert_eq!(tab.visible_len(), 3);
+}
+
+#[test]
+fn marked_rules_are_copied_to_another_node() {
+    let mut tab = RulesTab::new();
+    tab.set_rules(vec![rule("alpha"), rule("beta"), rule("gamma")], Some("node-a".to_string()));
+    tab.set_copy_targets(vec![("node-b".to_string(), "web".to_string())]);
+
+    // Marking moves on to the next rule, beta is marked and unmarked again
+    press(&mut tab, &[key(KeyCode::Char('m')), key(KeyCode::Char('m')), key(KeyCode::Up), key(KeyCode::Char('m'))]);
+    let theme = Theme::default();
+    let screen = rendered(|f| tab.render(f, f.area(), &theme));
+    assert!(screen.contains("[1 marked, c = copy to node"), "{}", screen);
+    tab.handle_key(key(KeyCode::Char('m')));
+
+    tab.handle_key(key(KeyCode::Char('c')));
+    assert!(tab.showing_dialog());
+    let screen = rendered(|f| tab.render(f, f.area(), &theme));
+    assert!(screen.contains("Copy 2 rules to node"), "{}", screen);
+    assert!(screen.contains("alpha, gamma"));
+    match tab.handle_key(key(KeyCode::Enter)).as_slice() {
+        [TabCommand::CopyRules { node_addr, rules }] => {
+            assert_eq!(node_addr, "node-b");
+            let names: Vec<_> = rules.iter().map(|r| r.name.as_str()).collect();
+            assert_eq!(names, ["alpha", "gamma"]);
+        }
+        other => panic!("unexpected commands {:?}", other),
+    }
+
+    // Marks are gone, the selected rule is copied alone
+    tab.handle_key(key(KeyCode::Char('c')));
+    match tab.handle_key(key(KeyCode::Enter)).as_slice() {
+        [TabCommand::CopyRules { rules, .. }] => assert_eq!(rules.len(), 1),
+        other => panic!("unexpected commands {:?}", other),
+    }
+}
+
+#[test]
+fn rule_editor_shows_the_recent_connections_it_matches() {
+    let rule = Rule::new("web", RuleAction::Deny, RuleDuration::Always, Operator::simple("dest.host", "example.com"));
+    let mut editor = RuleEditorDialog::edit(&rule);
+    editor.set_recent_events(vec![
+        event("/usr/bin/curl", "example.com", 443, "12:00:03"),
+        event("/usr/bin/wget", "other.org", 80, "12:00:02"),
+        event("/usr/bin/wget", "example.com", 80, "12:00:01"),
+    ]);
+    assert_eq!(editor.matching_events().len(), 2);
+
+    // Follows the rule as it is edited
+    editor.data = "other.org".to_string();
+    editor.handle_key(key(KeyCode::Tab));
+    let matching = editor.matching_events();
+    assert_eq!(matching.len(), 1);
+    assert_eq!(matching[0].connection.dst_port, 80);
+
+    let theme = Theme::default();
+    let mut terminal = ratatui::Terminal::new(ratatui::backend::TestBackend::new(130, 30)).unwrap();
+    let frame = terminal.draw(|f| editor.render(f, &theme)).unwrap();
+    let screen: String = frame.buffer.content().iter().map(|cell| cell.symbol()).collect();
+    assert!(screen.contains("Matches: 1 of 3 recent connections"));
+    assert!(screen.contains("/usr/bin/wget → other.org:80"));
+}
+
+#[test]
+fn rule_editor_edits_the_entries_of_list_rules() {
+    let operator = Operator::list(vec![
+        Operator::simple("process.path", "/usr/bin/curl"),
+        Operator::simple("dest.host", "example.com"),
+    ]);
+    let rule = Rule::new("curl-web", RuleAction::Allow, RuleDuration::Always, operator);
+    let mut editor = RuleEditorDialog::edit(&rule);
+    let kept = editor.build_rule().operator;
+    assert_eq!(kept.op_type, OperatorType::List);
+    assert_eq!(kept.list.len(), 2);
+
+    let feed = |editor: &mut RuleEditorDialog, keys: &[KeyEvent]| {
+        for k in keys {
+            editor.handle_key(*k);
+        }
+    };
+    // Down to the entries, past the operand a list doesn't have
+    feed(&mut editor, &[key(KeyCode::Down); 5]);
+    assert_eq!(editor.focus, EditorFocus::Data);
+    editor.handle_key(key(KeyCode::Enter));
+    let screen = rendered(|f| editor.render(f, &Theme::default()));
+    assert!(screen.contains("all 2 entries must match"), "{}", screen);
+
+    // A second host, then the process dropped
+    feed(&mut editor, &[key(KeyCode::Down), key(KeyCode::Char('a'))]);
+    let typed: Vec<_> = "example.org".chars().map(|c| key(KeyCode::Char(c))).collect();
+    feed(&mut editor, &typed);
+    feed(&mut editor, &[key(KeyCode::Enter), key(KeyCode::Up), key(KeyCode::Up)]);
+    feed(&mut editor, &[key(KeyCode::Char('d')), key(KeyCode::Esc)]);
+
+    let operator = match editor.handle_key(ctrl('s')) {
+        Some(RuleEditorResult::Save(rule)) => rule.operator,
+        _ => panic!("expected the rule to be saved"),
+    };
+    let entries: Vec<_> = operator.list.iter().map(|e| (e.operand.as_str(), e.data.as_str())).collect();
+    assert_eq!(entries, [("dest.host", "example.com"), ("dest.host", "example.org")]);
+}
+
+#[test]
+fn rules_count_down_to_their_expiry() {
+    let mut tab = RulesTab::new();
+    let mut temporary = rule("temporary");
+    temporary.duration = RuleDuration::OneHour;
+    temporary.expires = Some(chrono::Utc::now() + chrono::Duration::minutes(42) + chrono::Duration::seconds(30));
+    tab.set_rules(vec![rule("permanent"), temporary], Some("node".to_string()));
+
+    let screen = rendered(|f| tab.render(f, f.area(), &Theme::default()));
+    assert!(screen.contains("42m left"), "{}", screen);
+    assert!(screen.contains("always"), "{}", screen);
+    assert!(screen.contains("0s ago"), "{}", screen);
+}
+
+#[test]
+fn rules_tab_only_keeps_a_window_of_huge_rulesets() {
+    let mut tab = RulesTab::new();
+    let rules: Vec<Rule> = (0..10_000).map(|i| rule(&format!("rule-{:05}", i))).collect();
+    tab.set_rules(rules.clone(), Some("node".to_string()));
+    assert_eq!(tab.filtered_rules().len(), 200);
+
+    tab.handle_key(key(KeyCode::End));
+    assert_eq!(tab.selected_rule().map(|r| r.name.as_str()), Some("rule-09999"));
+    assert_eq!(tab.filtered_rules().len(), 200);
+
+    // The selection is clamped to what the filter leaves
+    tab.handle_key(key(KeyCode::Char('/')));
+    type_text(&mut tab, "rule-0001");
+    assert_eq!(tab.selected_rule().map(|r| r.name.as_str()), Some("rule-00019"));
+
+    let mut nodes = NodeManager::new();
+    nodes.add_node("node", ClientConfig { rules, ..Default::default() });
+    let page = nodes.rule_page("node", "RULE-0002", 5, 200);
+    assert_eq!((page.offset, page.matched, page.total), (0, 10, 10_000));
+    assert_eq!(page.rules[0].name, "rule-00020");
+    let page = nodes.rule_page("node", "", 9_990, 200);
+    assert_eq!((page.offset, page.rules.len()), (9_800, 200));
+}
+
+#[tokio::test]
+async fn rules_tab_fetches_the_window_the_selection_moves_into() {
+    let (ui_update_tx, _) = broadcast::channel(16);
+    let state = Arc::new(AppState::new(Database::open(":memory:").unwrap(), ui_update_tx));
+    let rules: Vec<Rule> = (0..10_000).map(|i| rule(&format!("rule-{:05}", i))).collect();
+    state.nodes.write().await.add_node("node", ClientConfig { rules, ..Default::default() });
+    state.nodes.write().await.set_active("node");
+
+    let mut tab = RulesTab::new();
+    tab.update_cache(&state).await;
+    assert_eq!(tab.filtered_rules().len(), 200);
+
+    // Within the window nothing is fetched
+    assert!(tab.handle_key(key(KeyCode::Down)).is_empty());
+    assert_eq!(tab.selected_rule().map(|r| r.name.as_str()), Some("rule-00001"));
+
+    let commands = tab.handle_key(key(KeyCode::End));
+    assert!(matches!(commands.as_slice(), [TabCommand::FetchRules]));
+    assert!(tab.selected_rule().is_none());
+    tab.update_cache(&state).await;
+    assert_eq!(tab.selected_rule().map(|r| r.name.as_str()), Some("rule-09999"));
+    assert_eq!(tab.filtered_rules()[0].name, "rule-09800");
+}
+
+#[test]
+fn rule_filter_narrows_as_it_is_typed_and_follows_rule_changes() {
+    let mut rules: Vec<Rule> = (0..2_000).map(|i| rule(&format!("rule-{:04}", i))).collect();
+    let mut index = RuleIndex::default();
+    index.update(&rules);
+    assert_eq!(index.filter("").len(), 2_000);
+    assert_eq!(index.filter("RULE-01").len(), 100);
+    assert_eq!(index.filter("rule-019"), (190..200).collect::<Vec<_>>());
+    // Shorter again, the matches come back
+    assert_eq!(index.filter("rule-01").len(), 100);
+
+    rules[5].description = "Rule-019 lookalike".to_string();
+    index.update(&rules);
+    assert_eq!(index.filter("rule-019").first(), Some(&5));
+}
+
+#[test]
+fn rules_tab_only_draws_the_rows_on_screen() {
+    let mut tab = RulesTab::new();
+    tab.set_rules((0..5_000).map(|i| rule(&format!("rule-{:04}", i))).collect(), Some("node".to_string()));
+    let screen = rendered(|f| tab.render(f, f.area(), &Theme::default()));
+    assert!(screen.contains("rule-0000") && !screen.contains("rule-0030"), "{}", screen);
+
+    tab.handle_key(key(KeyCode::End));
+    let screen = rendered(|f| tab.render(f, f.area(), &Theme::default()));
+    assert!(screen.contains("rule-4999") && !screen.contains("rule-4900"), "{}", screen);
+
+    // Moving up past the top row scrolls by one
+    for _ in 0..20 {
+        tab.handle_key(key(KeyCode::Up));
+    }
+    let screen = rendered(|f| tab.render(f, f.area(), &Theme::default()));
+    assert!(screen.contains("rule-4979") && !screen.contains("rule-4998"), "{}", screen);
+
+    // Sorted by name, reversed
+    tab.handle_key(key(KeyCode::Home));
+    tab.handle_key(key(KeyCode::Char('s')));
+    tab.handle_key(key(KeyCode::Char('S')));
+    let screen = rendered(|f| tab.render(f, f.area(), &Theme::default()));
+    assert_eq!(tab.selected_rule().map(|r| r.name.as_str()), Some("rule-4999"));
+    assert!(screen.contains("rule-4999") && !screen.contains("rule-0000"), "{}", screen);
+}
+
+#[test]
+fn noisy_rules_toggle_logging() {
+    let mut tab = RulesTab::new();
+    tab.set_rules(vec![rule("quiet"), rule("chatty"), rule("busy")], Some("node".to_string()));
+    tab.set_rule_hits(HashMap::from([("chatty".to_string(), 40), ("busy".to_string(), 7)]));
+
+    tab.handle_key(key(KeyCode::Char('N')));
+    let ranked: Vec<(&str, u64)> = tab.noisy_rules().iter().map(|(r, n)| (r.name.as_str(), *n)).collect();
+    assert_eq!(ranked, [("chatty", 40), ("busy", 7)]);
+    let screen = rendered(|f| tab.render(f, f.area(), &Theme::default()));
+    assert!(screen.contains("Noisy rules"));
+
+    match sent(press(&mut tab, &[key(KeyCode::Down), key(KeyCode::Char('l'))])).as_slice() {
+        [AppMessage::RuleModified { rule, .. }, AppMessage::SendNotification { action: NotificationAction::ChangeRule(changed), .. }] => {
+            assert_eq!(rule.name, "busy");
+            assert!(rule.nolog);
+            assert!(changed.nolog);
+        }
+        other => panic!("unexpected messages {:?}", other),
+    }
+
+    // Back to the rule list
+    tab.handle_key(key(KeyCode::Esc));
+    assert!(tab.handle_key(key(KeyCode::Char('l'))).is_empty());
+}
+
+#[test]
+fn policies_group_rules_and_toggle_together() {
+    let mut policies = Policies::default();
+    policies.assign("node", "vpn-a", "Work VPN");
+    policies.assign("node", "vpn-b", "Work VPN");
+    let mut tab = RulesTab::new().with_policies(policies);
+    tab.set_rules(vec![rule("vpn-a"), rule("other"), rule("vpn-b")], Some("node".to_string()));
+
+    tab.handle_key(key(KeyCode::Char('P')));
+    let rows: Vec<String> = tab
+        .policy_rows()
+        .into_iter()
+        .map(|row| match row {
+            PolicyRow::Policy { name, rules, .. } => format!("{}:{}", name.unwrap_or_default(), rules),
+            PolicyRow::Rule(rule) => rule.name,
+        })
+        .collect();
+    assert_eq!(rows, ["Work VPN:2", "vpn-a", "vpn-b", ":1", "other"]);
+    let screen = rendered(|f| tab.render(f, f.area(), &Theme::default()));
+    assert!(screen.contains("Work VPN"));
+    assert!(screen.contains("No policy"));
+
+    // The whole policy goes off, with one notification for both rules
+    match sent(tab.handle_key(key(KeyCode::Char(' ')))).as_slice() {
+        [AppMessage::RuleToggled { name: a, enabled: false, .. }, AppMessage::RuleToggled { name: b, enabled: false, .. }, AppMessage::SendNotification { action: NotificationAction::DisableRules(rules), .. }] =>
+        {
+            assert_eq!((a.as_str(), b.as_str()), ("vpn-a", "vpn-b"));
+            assert!(rules.iter().all(|r| !r.enabled));
+            assert_eq!(rules.len(), 2);
+        }
+        other => panic!("unexpected messages {:?}", other),
+    }
+
+    // Put the last rule into the policy too
+    press(&mut tab, &[key(KeyCode::End), key(KeyCode::Char('a'))]);
+    assert!(tab.showing_dialog());
+    type_text(&mut tab, "Work VPN");
+    match tab.handle_key(key(KeyCode::Enter)).as_slice() {
+        [TabCommand::SavePolicies(saved)] => assert_eq!(saved.policy_of("node", "other"), Some("Work VPN")),
+        other => panic!("unexpected commands {:?}", other),
+    }
+    assert!(!tab.showing_dialog());
+
+    // Folded, only the heading is left
+    press(&mut tab, &[key(KeyCode::Home), key(KeyCode::Enter)]);
+    assert!(matches!(tab.policy_rows().as_slice(), [PolicyRow::Policy { rules: 3, collapsed: true, .. }]));
+
+    tab.handle_key(key(KeyCode::Esc));
+    assert_eq!(tab.filtered_rules().len(), 3);
+}
+
+#[test]
+fn rules_delete_confirmation_flow() {
+    let mut tab = RulesTab::new();
+    tab.set_rules(vec![rule("alpha"), rule("beta")], Some("node".to_string()));
+
+    // Cancelling sends nothing
+    assert!(press(&mut tab, &[key(KeyCode::Down), key(KeyCode::Char('d'))]).is_empty());
+    assert!(tab.showing_dialog());
+    assert!(tab.handle_key(key(KeyCode::Esc)).is_empty());
+    assert!(!tab.showing_dialog());
+
+    let commands = press(&mut tab, &[key(KeyCode::Char('d')), key(KeyCode::Char('y'))]);
+    assert!(!tab.showing_dialog());
+    match sent(commands).as_slice() {
+        [AppMessage::RuleDeleted { name, .. }, AppMessage::SendNotification { action: NotificationAction::DeleteRule(n), .. }] => {
+            assert_eq!(name, "beta");
+            assert_eq!(n, "beta");
+        }
+        other => panic!("unexpected messages {:?}", other),
+    }
+}
+
+#[test]
+fn rules_apply_and_remove_application_profiles() {
+    let mut tab = RulesTab::new();
+    let firefox = opensnitch_tui::models::profile::catalog().remove(0);
+    let mut rules = vec![rule("alpha")];
+    rules.extend(firefox.rules().into_iter().take(1));
+    tab.set_rules(rules, Some("node".to_string()));
+
+    // Only the rule the node doesn't have yet is added
+    assert!(press(&mut tab, &[key(KeyCode::Char('p'))]).is_empty());
+    assert!(tab.showing_dialog());
+    let added: Vec<_> = sent(tab.handle_key(key(KeyCode::Enter)))
+        .into_iter()
+        .filter_map(|msg| match msg {
+            AppMessage::RuleAdded { rule, .. } => Some(rule),
+            _ => None,
+        })
+        .collect();
+    assert!(!tab.showing_dialog());
+    assert_eq!(added.len(), 1);
+    assert_eq!((added[0].name.as_str(), added[0].profile()), ("profile-firefox-http3", Some("firefox")));
+
+    // Removing takes the rules that came from the profile, nothing else
+    let commands = press(&mut tab, &[key(KeyCode::Char('p')), key(KeyCode::Char('d'))]);
+    match sent(commands).as_slice() {
+        [AppMessage::RuleDeleted { name, .. }, AppMessage::SendNotification { action: NotificationAction::DeleteRule(_), .. }] => {
+            assert_eq!(name, "profile-firefox-web");
+        }
+        other => panic!("unexpected messages {:?}", other),
+    }
+}
+
+#[test]
+fn rules_import_and_export_through_a_rules_directory() {
+    let mut tab = RulesTab::new();
+    tab.set_rules(vec![rule("alpha")], Some("node".to_string()));
+
+    let commands = press(&mut tab, &[key(KeyCode::Char('o')), key(KeyCode::Enter)]);
+    match commands.as_slice() {
+        [TabCommand::ExportRulesDir { node_addr, path }] => {
+            assert_eq!((node_addr.as_str(), path.as_str()), ("node", "/etc/opensnitchd/rules"));
+        }
+        _ => panic!("expected an export"),
+    }
+    // The dialog stays for the report
+    tab.set_rules_dir_result(Err("permission denied".to_string()));
+    assert!(tab.showing_dialog());
+    assert!(press(&mut tab, &[key(KeyCode::Enter)]).is_empty());
+    assert!(!tab.showing_dialog());
+
+    press(&mut tab, &[key(KeyCode::Char('i'))]);
+    for _ in "rules".chars() {
+        tab.handle_key(key(KeyCode::Backspace));
+    }
+    type_text(&mut tab, "imported");
+    let commands = tab.handle_key(key(KeyCode::Enter));
+    assert!(matches!(
+        commands.as_slice(),
+        [TabCommand::ImportRulesDir { path, .. }] if path == "/etc/opensnitchd/imported"
+    ));
+}
+
+#[test]
+fn rules_editor_saves_as_modify() {
+    let mut tab = RulesTab::new();
+    tab.set_rules(vec![rule("alpha")], Some("node".to_string()));
+
+    tab.handle_key(key(KeyCode::Char('e')));
+    assert!(tab.showing_dialog());
+    let commands = tab.handle_key(ctrl('s'));
+    assert!(!tab.showing_dialog());
+    assert!(matches!(
+        sent(commands).as_slice(),
+        [AppMessage::RuleModified { .. }, AppMessage::SendNotification { .. }]
+    ));
+
+    // A new rule cancelled from the editor sends nothing
+    tab.handle_key(key(KeyCode::Char('n')));
+    assert!(tab.handle_key(key(KeyCode::Esc)).is_empty());
+    assert!(!tab.showing_dialog());
+}
+
+#[test]
+fn rules_editor_warns_about_conflicts_before_saving() {
+    let mut tab = RulesTab::new();
+    let deny = Rule::new("alpha-deny", RuleAction::Deny, RuleDuration::Always, Operator::simple("process.path", "alpha"));
+    tab.set_rules(vec![rule("alpha"), deny], Some("node".to_string()));
+
+    tab.handle_key(key(KeyCode::Char('e')));
+    assert!(tab.handle_key(ctrl('s')).is_empty());
+    assert!(tab.showing_dialog());
+    let screen = rendered(|f| tab.render(f, f.area(), &Theme::default()));
+    assert!(screen.contains("Conflicts with existing rules"));
+    assert!(screen.contains("'alpha-deny' would deny"));
+
+    // Any other key goes back to editing, saving twice goes ahead
+    tab.handle_key(key(KeyCode::Down));
+    assert!(tab.handle_key(ctrl('s')).is_empty());
+    let commands = tab.handle_key(ctrl('s'));
+    assert!(!tab.showing_dialog());
+    assert!(matches!(sent(commands).as_slice(), [AppMessage::RuleModified { .. }, ..]));
+}
+
+// Firewall
+
+fn firewall() -> SysFirewall {
+    let mut fw = SysFirewall::new();
+    fw.system_rules.push(FwChains {
+        rule: None,
+        chains: vec![
+            FwChain::new("input", "filter", "input")
+                .with_rules(vec![FwRule::new("ssh", "accept"), FwRule::new("http", "accept")]),
+            FwChain::new("output", "filter", "output").with_rules(vec![FwRule::new("dns", "accept")]),
+        ],
+    });
+    fw
+}
+
+fn saved_firewall(commands: Vec<TabCommand>) -> SysFirewall {
+    match commands.into_iter().next() {
+        Some(TabCommand::SaveFirewall { firewall, .. }) => firewall,
+        other => panic!("expected a save command, got {:?}", other),
+    }
+}
+
+#[test]
+fn firewall_navigation_between_chains_and_rules() {
+    let mut tab = FirewallTab::new();
+    tab.set_firewall(Some(firewall()), Some("node".to_string()));
+    assert_eq!(tab.selected_rule().map(|r| r.description.as_str()), Some("ssh"));
+
+    // Chains focus moves the chain and resets the rule selection
+    tab.handle_key(key(KeyCode::Down));
+    assert_eq!(tab.selected_rule().map(|r| r.description.as_str()), Some("dns"));
+
+    press(&mut tab, &[key(KeyCode::Up), key(KeyCode::Tab), key(KeyCode::End)]);
+    assert_eq!(tab.selected_rule().map(|r| r.description.as_str()), Some("http"));
+}
+
+#[test]
+fn firewall_delete_and_toggle_save_config() {
+    let mut tab = FirewallTab::new();
+    tab.set_firewall(Some(firewall()), Some("node".to_string()));
+
+    // Rule actions only apply with the rules list focused
+    assert!(press(&mut tab, &[key(KeyCode::Char('d'))]).is_empty());
+    assert!(!tab.showing_dialog());
+
+    let fw = saved_firewall(press(&mut tab, &[key(KeyCode::Tab), key(KeyCode::Char('d')), key(KeyCode::Char('y'))]));
+    let input = fw.find_chain("filter", "inet", "input").unwrap();
+    assert_eq!(input.rules.len(), 1);
+    assert_eq!(input.rules[0].description, "http");
+
+    let fw = saved_firewall(tab.handle_key(key(KeyCode::Char(' '))));
+    assert!(!fw.find_chain("filter", "inet", "input").unwrap().rules[0].enabled);
+}
+
+#[test]
+fn firewall_toggle_confirmation_flow() {
+    let mut tab = FirewallTab::new();
+    tab.set_firewall(Some(firewall()), Some("node".to_string()));
+
+    tab.handle_key(key(KeyCode::F(2)));
+    assert!(tab.showing_dialog());
+    assert!(tab.handle_key(key(KeyCode::Char('n'))).is_empty());
+
+    let commands = press(&mut tab, &[key(KeyCode::F(2)), key(KeyCode::Char('y'))]);
+    assert!(!tab.showing_dialog());
+    assert!(matches!(
+        sent(commands).as_slice(),
+        [AppMessage::SendNotification { action: NotificationAction::EnableFirewall, .. }]
+    ));
+}
+
+#[test]
+fn firewall_topology_view_navigates_chains() {
+    let mut tab = FirewallTab::new();
+    tab.set_firewall(Some(firewall()), Some("node".to_string()));
+    let theme = Theme::default();
+
+    tab.handle_key(key(KeyCode::Char('t')));
+    let screen = rendered(|f| tab.render(f, f.area(), &theme));
+    assert!(screen.contains("table inet filter  2 chains, 3 rules"));
+    assert!(screen.contains("hook output  priority 0"));
+
+    // Enter jumps to the rules of the chain picked in the tree
+    press(&mut tab, &[key(KeyCode::Down), key(KeyCode::Enter)]);
+    assert_eq!(tab.selected_rule().map(|r| r.description.as_str()), Some("dns"));
+    let screen = rendered(|f| tab.render(f, f.area(), &theme));
+    assert!(screen.contains("Rules: output"));
+}
+
+#[test]
+fn firewall_killswitch_assistant_installs_and_toggles() {
+    let mut tab = FirewallTab::new();
+    tab.set_firewall(Some(firewall()), Some("node".to_string()));
+
+    // The endpoint is required before the preview
+    tab.handle_key(key(KeyCode::Char('k')));
+    assert!(tab.showing_dialog());
+    assert!(tab.handle_key(key(KeyCode::Enter)).is_empty());
+    assert!(rendered(|f| tab.render(f, f.area(), &Theme::default())).contains("not an IP address"));
+
+    press(&mut tab, &[key(KeyCode::Tab)]);
+    type_text(&mut tab, "198.51.100.7");
+    tab.handle_key(key(KeyCode::Enter));
+    let screen = rendered(|f| tab.render(f, f.area(), &Theme::default()));
+    assert!(screen.contains("Chain vpn-killswitch (output), policy drop"));
+
+    let fw = saved_firewall(tab.handle_key(key(KeyCode::Enter)));
+    assert!(!tab.showing_dialog());
+    let chain = fw.find_chain("filter", "inet", "vpn-killswitch").unwrap();
+    assert_eq!(chain.policy, "drop");
+    let names: Vec<_> = chain.rules.iter().map(|r| r.description.as_str()).collect();
+    assert_eq!(
+        names,
+        ["Kill-switch: loopback", "Kill-switch: tunnel tun0", "Kill-switch: tunnel wg0", "Kill-switch: VPN endpoint"]
+    );
+    // Existing chains are kept
+    assert!(fw.find_chain("filter", "inet", "input").is_some());
+
+    let fw = saved_firewall(tab.handle_key(key(KeyCode::Char('K'))));
+    let chain = fw.find_chain("filter", "inet", "vpn-killswitch").unwrap();
+    assert_eq!(chain.policy, "accept");
+    assert!(chain.rules.iter().all(|r| !r.enabled));
+}
+
+// Statistics
+
+fn stats() -> Statistics {
+    let mut stats = Statistics::new();
+    stats.by_proto = HashMap::from([("tcp".to_string(), 10), ("udp".to_string(), 5), ("icmp".to_string(), 1)]);
+    stats
+}
+
+#[test]
+fn statistics_expand_and_search() {
+    let mut tab = StatisticsTab::new(&Settings::default());
+    tab.set_stats(Some(stats()));
+
+    // Summary has no breakdown to expand
+    tab.handle_key(key(KeyCode::Enter));
+    assert!(!tab.showing_dialog());
+
+    press(&mut tab, &[key(KeyCode::Tab), key(KeyCode::Enter)]);
+    assert!(tab.showing_dialog());
+    let keys: Vec<String> = tab.expanded_entries().into_iter().map(|(k, _)| k).collect();
+    assert_eq!(keys, ["tcp", "udp", "icmp"]);
+
+    tab.handle_key(key(KeyCode::Char('/')));
+    type_text(&mut tab, "c");
+    tab.handle_key(key(KeyCode::Enter));
+    assert_eq!(tab.expanded_entries().len(), 2);
+
+    // First Esc clears the query, the second closes the view
+    tab.handle_key(key(KeyCode::Esc));
+    assert_eq!(tab.expanded_entries().len(), 3);
+    assert!(tab.showing_dialog());
+    tab.handle_key(key(KeyCode::Esc));
+    assert!(!tab.showing_dialog());
+}
+
+#[test]
+fn statistics_limit_caps_panel() {
+    let mut settings = Settings::default();
+    settings.stats_limits.by_protocol = 2;
+    let mut tab = StatisticsTab::new(&settings);
+    tab.set_stats(Some(stats()));
+
+    press(&mut tab, &[key(KeyCode::Tab), key(KeyCode::Enter)]);
+    let keys: Vec<String> = tab.expanded_entries().into_iter().map(|(k, _)| k).collect();
+    assert_eq!(keys, ["tcp", "udp"]);
+
+    // Limits step by 5 and 0 means unlimited
+    press(&mut tab, &[key(KeyCode::Esc), key(KeyCode::Char('-')), key(KeyCode::Enter)]);
+    assert_eq!(tab.expanded_entries().len(), 3);
+}
+
+#[test]
+fn statistics_export_keys() {
+    let mut tab = StatisticsTab::new(&Settings::default());
+    tab.set_stats(Some(stats()));
+
+    assert!(matches!(press(&mut tab, &[key(KeyCode::Char('x'))]).as_slice(), [TabCommand::ExportStats(StatsFormat::Json)]));
+    assert!(matches!(press(&mut tab, &[key(KeyCode::Char('X'))]).as_slice(), [TabCommand::ExportStats(StatsFormat::Csv)]));
+
+    tab.set_export_result(Ok("/tmp/stats.json".to_string()));
+    let screen = rendered(|f| tab.render(f, f.area(), &Theme::default()));
+    assert!(screen.contains("Exported to /tmp/stats.json"));
+}
+
+#[test]
+fn statistics_traffic_rates() {
+    let start = chrono::Utc::now();
+    let counters = |connections, dropped| Statistics { connections, dropped, ..Default::default() };
+    let mut history = TrafficHistory::default();
+    history.record("node", &counters(100, 10), start);
+    history.record("node", &counters(120, 14), start + chrono::Duration::seconds(2));
+    // The daemon restarted
+    history.record("node", &counters(5, 0), start + chrono::Duration::seconds(4));
+    history.record("node", &counters(15, 1), start + chrono::Duration::seconds(5));
+
+    let rates: Vec<(f64, f64)> = history.samples("node").iter().map(|s| (s.connections, s.dropped)).collect();
+    assert_eq!(rates, [(10.0, 2.0), (10.0, 1.0)]);
+    assert!(history.samples("other").is_empty());
+
+    let mut tab = StatisticsTab::new(&Settings::default());
+    tab.set_rates(history.samples("node"));
+    let screen = rendered(|f| tab.render(f, f.area(), &Theme::default()));
+    assert!(screen.contains("Connections/s 10.0"));
+    assert!(screen.contains("Dropped/s 1.0"));
+
+    tab.handle_key(key(KeyCode::Char('t')));
+    let screen = rendered(|f| tab.render(f, f.area(), &Theme::default()));
+    assert!(!screen.contains("Connections/s"));
+
+    // Only the last minutes are kept
+    history.record("node", &counters(20, 1), start + chrono::Duration::minutes(12));
+    assert_eq!(history.samples("node").len(), 1);
+}
+
+// Nodes
+
+#[test]
+fn unauthenticated_requests_are_counted_by_ip_not_listed_as_nodes() {
+    let mut nodes = NodeManager::new();
+    assert_eq!(nodes.record_rejection("127.0.0.1:40001"), 1);
+    assert_eq!(nodes.record_rejection("127.0.0.1:40002"), 2);
+    assert_eq!(nodes.record_rejection("[::1]:40003"), 1);
+    assert!(nodes.nodes.is_empty());
+    assert_eq!(nodes.rejected.len(), 2);
+
+    // A flood from ever new addresses stays bounded
+    for i in 0..100 {
+        nodes.record_rejection(&format!("10.0.0.{}:5000", i));
+    }
+    assert_eq!(nodes.rejected.len(), MAX_REJECTED_PEERS);
+    assert!(nodes.rejected.iter().any(|peer| peer.ip == "10.0.0.99"));
+
+    let mut tab = NodesTab::new();
+    tab.set_rejected(nodes.rejected.clone());
+    let screen = rendered(|f| tab.render(f, f.area(), &Theme::default()));
+    assert!(screen.contains("Nodes (0)"), "{}", screen);
+    assert!(screen.contains("16 unauthenticated requests rejected from 10.0.0.99 and 15 more"), "{}", screen);
+}
+
+#[test]
+fn alerts_and_nodes_privacy_mode_masks_hosts() {
+    let mut alerts = AlertsTab::new();
+    let conn = Connection { dst_host: "secret.example.com".to_string(), dst_port: 443, ..Default::default() };
+    alerts.set_alerts(vec![Alert::new(1, AlertType::Warning, AlertPriority::High, AlertWhat::Connection, Some(AlertData::Connection(conn)))]);
+    let mut nodes = NodesTab::new();
+    nodes.set_nodes(vec![Node::new("192.0.2.10:50051")], None);
+    let theme = Theme::default();
+    assert!(rendered(|f| alerts.render(f, f.area(), &theme)).contains("secret.example.com"));
+    assert!(rendered(|f| nodes.render(f, f.area(), &theme)).contains("192.0.2.10"));
+
+    alerts.set_formats(Formats::default().with_private(true));
+    nodes.set_formats(Formats::default().with_private(true));
+    assert!(!rendered(|f| alerts.render(f, f.area(), &theme)).contains("secret"));
+    assert!(!rendered(|f| nodes.render(f, f.area(), &theme)).contains("192.0.2"));
+}
+
+#[test]
+fn nodes_select_active_node() {
+    let mut tab = NodesTab::new();
+    tab.set_nodes(vec![Node::new("unix:/a"), Node::new("unix:/b")], None);
+
+    match press(&mut tab, &[key(KeyCode::Down), key(KeyCode::Enter)]).as_slice() {
+        [TabCommand::SetActiveNode(addr)] => assert_eq!(addr, "unix:/b"),
+        other => panic!("unexpected commands {:?}", other),
+    }
+}
+
+#[test]
+fn nodes_show_where_daemons_can_connect() {
+    let mut tab = NodesTab::new();
+    let screen = rendered(|f| tab.render(f, f.area(), &Theme::default()));
+    assert!(screen.contains("not listening"));
+
+    tab.set_listeners(vec!["unix:///tmp/osui.sock".to_string(), "0.0.0.0:50051 (TLS)".to_string()]);
+    let screen = rendered(|f| tab.render(f, f.area(), &Theme::default()));
+    assert!(screen.contains("listening on unix:///tmp/osui.sock, 0.0.0.0:50051 (TLS)"), "{}", screen);
+}
+
+#[test]
+fn nodes_import_dialog_flow() {
+    let mut tab = NodesTab::new();
+    tab.handle_key(key(KeyCode::Char('i')));
+    assert!(tab.showing_dialog());
+
+    // Replace the default path before importing
+    for _ in 0..200 {
+        tab.handle_key(key(KeyCode::Backspace));
+    }
+    type_text(&mut tab, "/tmp/gui.db");
+    match tab.handle_key(key(KeyCode::Enter)).as_slice() {
+        [TabCommand::ImportGuiDatabase(path)] => assert_eq!(path, "/tmp/gui.db"),
+        other => panic!("unexpected commands {:?}", other),
+    }
+    assert!(tab.showing_dialog());
+
+    // The report stays up until dismissed
+    tab.set_import_result(Err("no such file".to_string()));
+    assert!(tab.handle_key(key(KeyCode::Char('x'))).is_empty());
+    assert!(tab.showing_dialog());
+    tab.handle_key(key(KeyCode::Enter));
+    assert!(!tab.showing_dialog());
+}
+
+#[test]
+fn jumps_between_related_items_of_other_tabs() {
+    // From a rule to the connections it matched
+    let mut rules = RulesTab::new();
+    rules.set_rules(vec![rule("a"), rule("b"), rule("c")], Some("node".to_string()));
+    rules.select_rule(1);
+    match press(&mut rules, &[key(KeyCode::Char('g')), key(KeyCode::Char('e'))]).as_slice() {
+        [TabCommand::Jump(Jump::RuleEvents(name))] => assert_eq!(name, "b"),
+        other => panic!("unexpected commands {:?}", other),
+    }
+    // Positions are the node's order, a sort by name descending is dropped
+    press(&mut rules, &[key(KeyCode::Char('s')), key(KeyCode::Char('S'))]);
+    assert_eq!(rules.filtered_rules()[0].name, "c");
+    rules.select_rule(0);
+    assert_eq!(rules.selected_rule().map(|r| r.name.as_str()), Some("a"));
+    rules.select_rule(1);
+    // `gg` still goes to the top
+    assert!(press(&mut rules, &[key(KeyCode::Char('g')), key(KeyCode::Char('g'))]).is_empty());
+    assert_eq!(rules.selected_rule().map(|r| r.name.as_str()), Some("a"));
+
+    let mut connections = ConnectionsTab::new();
+    let events = [
+        event("/usr/bin/curl", "example.com", 443, "2024-01-01T10:00:00"),
+        event("/usr/bin/ssh", "host.lan", 22, "2024-01-01T10:00:01"),
+    ];
+    connections.set_events(events.iter(), None);
+    connections.show_related("rule b".to_string(), vec![ConnectionRef::of(&events[1].connection)]);
+    assert_eq!(connections.visible_len(), 1);
+    let screen = rendered(|f| connections.render(f, f.area(), &Theme::default()));
+    assert!(screen.contains("[rule b, Esc = all]"), "{}", screen);
+    connections.handle_key(key(KeyCode::Esc));
+    assert_eq!(connections.visible_len(), 2);
+
+    // From an alert to its connection and the rule that matched it
+    let mut alerts = AlertsTab::new();
+    let connection = events[0].connection.clone();
+    alerts.set_alerts(vec![Alert::new(
+        1,
+        AlertType::Warning,
+        AlertPriority::High,
+        AlertWhat::Connection,
+        Some(AlertData::Connection(connection)),
+    )]);
+    match press(&mut alerts, &[key(KeyCode::Char('g')), key(KeyCode::Char('c'))]).as_slice() {
+        [TabCommand::Jump(Jump::ConnectionEvents(conn))] => assert_eq!(conn.dst_host, "example.com"),
+        other => panic!("unexpected commands {:?}", other),
+    }
+    match press(&mut alerts, &[key(KeyCode::Char('g')), key(KeyCode::Char('r'))]).as_slice() {
+        [TabCommand::Jump(Jump::ConnectionRule(conn))] => assert_eq!(conn.process_path, "/usr/bin/curl"),
+        other => panic!("unexpected commands {:?}", other),
+    }
+
+    // From a system firewall rule to the alerts about it
+    let mut system = FirewallTab::new();
+    system.set_firewall(Some(firewall()), Some("node".to_string()));
+    system.handle_key(key(KeyCode::Tab));
+    let uuid = system.selected_rule().unwrap().uuid.clone();
+    match press(&mut system, &[key(KeyCode::Char('g')), key(KeyCode::Char('e'))]).as_slice() {
+        [TabCommand::Jump(Jump::FirewallEvents { uuid: jumped, description })] => {
+            assert_eq!((jumped, description.as_str()), (&uuid, "ssh"));
+        }
+        other => panic!("unexpected commands {:?}", other),
+    }
+    alerts.show_related("firewall rule ssh".to_string(), Vec::new());
+    assert!(alerts.filtered_alerts().is_empty());
+    alerts.handle_key(key(KeyCode::Esc));
+    assert_eq!(alerts.filtered_alerts().len(), 1);
+}
+
+// DNS
+
+#[test]
+fn dns_entries_are_filtered_and_turned_into_rules() {
+    let seen = chrono::Utc::now();
+    let entry = |node: &str, host: &str, ip: &str| DnsEntry {
+        node: node.to_string(),
+        host: host.to_string(),
+        ip: ip.to_string(),
+        first_seen: seen,
+        last_seen: seen,
+        count: 1,
+    };
+    let mut tab = DnsTab::new();
+    tab.set_entries(
+        vec![
+            entry("unix:/local", "tracker.example", "192.0.2.7"),
+            entry("unix:/local", "example.org", "192.0.2.1"),
+            entry("10.0.0.2:50051", "tracker.example", "192.0.2.8"),
+        ],
+        Some("unix:/local".to_string()),
+    );
+    assert_eq!(tab.filtered_entries().len(), 2);
+    press(&mut tab, &[key(KeyCode::Char('n'))]);
+    assert_eq!(tab.filtered_entries().len(), 3);
+
+    press(&mut tab, &[key(KeyCode::Char('/'))]);
+    type_text(&mut tab, "track");
+    press(&mut tab, &[key(KeyCode::Enter), key(KeyCode::Down)]);
+    assert_eq!(tab.filtered_entries().len(), 2);
+
+    match press(&mut tab, &[key(KeyCode::Char('r'))]).as_slice() {
+        [TabCommand::NewRule { node_addr, rule }] => {
+            assert_eq!(node_addr, "10.0.0.2:50051");
+            assert_eq!((rule.operator.operand.as_str(), rule.operator.data.as_str()), ("dest.host", "tracker.example"));
+        }
+        other => panic!("expected a new rule, got {:?}", other),
+    }
+}
+
+#[test]
+fn firewall_chains_are_created_edited_and_deleted() {
+    let mut tab = FirewallTab::new();
+    tab.set_firewall(Some(firewall()), Some("node".to_string()));
+
+    // A taken name or a bad priority keeps the editor open
+    tab.handle_key(key(KeyCode::Char('N')));
+    assert!(tab.showing_dialog());
+    press(&mut tab, &[key(KeyCode::Enter)]);
+    type_text(&mut tab, "input");
+    press(&mut tab, &[key(KeyCode::Enter)]);
+    assert!(tab.handle_key(key(KeyCode::F(2))).is_empty());
+    assert!(tab.showing_dialog());
+
+    press(&mut tab, &[key(KeyCode::Enter), key(KeyCode::End)]);
+    type_text(&mut tab, "-vpn");
+    // Name, table, family, type, hook: forward, priority -10, policy: drop
+    press(&mut tab, &[key(KeyCode::Enter), key(KeyCode::Tab), key(KeyCode::Tab), key(KeyCode::Tab), key(KeyCode::Tab)]);
+    press(&mut tab, &[key(KeyCode::Right), key(KeyCode::Right), key(KeyCode::Tab), key(KeyCode::Enter), key(KeyCode::Backspace)]);
+    type_text(&mut tab, "-10");
+    press(&mut tab, &[key(KeyCode::Enter), key(KeyCode::Tab), key(KeyCode::Char(' '))]);
+    let fw = saved_firewall(tab.handle_key(ctrl('s')));
+    assert!(!tab.showing_dialog());
+    let chain = fw.find_chain("filter", "inet", "input-vpn").unwrap();
+    assert_eq!(
+        (chain.table.as_str(), chain.hook.as_str(), chain.priority.as_str(), chain.policy.as_str()),
+        ("filter", "forward", "-10", "drop")
+    );
+    assert_eq!(fw.chain_count(), 3);
+
+    // The new chain is selected, its policy goes back to accept
+    let fw = saved_firewall(press(&mut tab, &[key(KeyCode::Char('P')), key(KeyCode::Left), key(KeyCode::F(2))]));
+    assert_eq!(fw.find_chain("filter", "inet", "input-vpn").unwrap().policy, "accept");
+
+    let fw = saved_firewall(press(&mut tab, &[key(KeyCode::Char('D')), key(KeyCode::Char('y'))]));
+    assert!(fw.find_chain("filter", "inet", "input-vpn").is_none());
+    assert_eq!(fw.chain_count(), 2);
+}
+
+#[test]
+fn chains_of_the_same_name_in_other_tables_are_told_apart() {
+    let mut fw = firewall();
+    fw.add_chain(FwChain::new("input", "mangle", "prerouting").with_policy("drop"));
+    assert_eq!(fw.find_chain("mangle", "inet", "input").unwrap().policy, "drop");
+    assert_eq!(fw.find_chain("filter", "inet", "input").unwrap().rules.len(), 2);
+    assert!(fw.find_chain("filter", "ip", "input").is_none());
+
+    assert!(fw.remove_chain("mangle", "inet", "input"));
+    assert!(!fw.remove_chain("mangle", "inet", "input"));
+    assert!(fw.find_chain("filter", "inet", "input").is_some());
+}
+
+#[test]
+fn tabs_out_of_sight_catch_up_when_their_data_changes() {
+    let start = std::time::Instant::now();
+    let mut stale = StaleTabs::default();
+    stale.mark(&UiUpdateSignal::Redraw);
+    assert!(stale.due(TabId::Connections, start).is_empty());
+
+    stale.mark(&UiUpdateSignal::ConnectionsUpdated);
+    // The visible tab is refreshed anyway
+    assert_eq!(stale.due(TabId::Connections, start), [TabId::Statistics, TabId::Dns]);
+    stale.refreshed(TabId::Statistics, start);
+    stale.refreshed(TabId::Dns, start);
+    assert!(stale.due(TabId::Connections, start).is_empty());
+
+    // Changes coming in quick succession wait for the next refresh
+    stale.mark(&UiUpdateSignal::ConnectionsUpdated);
+    assert!(stale.due(TabId::Connections, start).is_empty());
+    let later = start + std::time::Duration::from_secs(2);
+    assert_eq!(stale.due(TabId::Connections, later), [TabId::Statistics, TabId::Dns]);
+
+    stale.mark(&UiUpdateSignal::NodeChanged);
+    assert_eq!(stale.due(TabId::Rules, later).len(), TabId::all().len() - 1);
+}
@@ -0,0 +1,77 @@
+//! Placeholders in operator data, expanded when rules are saved
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+use opensnitch_tui::models::template::TemplateVars;
+use opensnitch_tui::models::{Operator, Rule, RuleAction, RuleDuration};
+use opensnitch_tui::ui::dialogs::rule_editor::{RuleEditorDialog, RuleEditorResult};
+
+const PASSWD: &str = "root:x:0:0:root:/root:/bin/bash\n\
+                      alice:x:1000:1000:Alice:/home/alice:/bin/zsh\n\
+                      bob:x:1001:1001::/home/b.ob:/bin/sh\n";
+
+fn rule(operator: Operator) -> Rule {
+    Rule::new("template", RuleAction::Allow, RuleDuration::Always, operator)
+}
+
+#[test]
+fn placeholders_expand_for_the_user_and_executable_of_the_rule() {
+    // The user running the TUI by default
+    let mut plain = rule(Operator::simple("process.path", "{home}/.local/bin/tool"));
+    plain.expand_placeholders(&TemplateVars::for_rule(&plain, PASSWD, 1000)).unwrap();
+    assert_eq!(plain.operator.data, "/home/alice/.local/bin/tool");
+
+    // The user and executable the rule is for, escaped inside regexps
+    let mut list = rule(Operator::list(vec![
+        Operator::simple("user.name", "bob"),
+        Operator::simple("process.path", "/opt/app/bin/app"),
+        Operator::regexp("process.parent.path", "^({home}|{exe_dir})/[a-z]{2,}$"),
+        Operator::simple("user.id", "{uid}"),
+    ]));
+    let vars = TemplateVars::for_rule(&list, PASSWD, 1000);
+    assert_eq!((vars.user.as_deref(), vars.exe_dir.as_deref()), (Some("bob"), Some("/opt/app/bin")));
+    list.expand_placeholders(&vars).unwrap();
+    assert_eq!(list.operator.list[2].data, r"^(/home/b\.ob|/opt/app/bin)/[a-z]{2,}$");
+    assert_eq!(list.operator.list[3].data, "1001");
+
+    // Nothing to take the directory from
+    let mut unknown = rule(Operator::simple("process.parent.path", "{exe_dir}/helper"));
+    let error = unknown.expand_placeholders(&TemplateVars::for_rule(&unknown, PASSWD, 0)).unwrap_err();
+    assert_eq!(error, "{exe_dir} in process.parent.path has no value");
+}
+
+#[test]
+fn users_of_remote_nodes_are_not_looked_up_here() {
+    let home = rule(Operator::simple("process.path", "{home}/.local/bin/tool"));
+    let vars = TemplateVars::for_node(&home, "192.0.2.10:50051", Some(1000));
+    assert_eq!(vars, TemplateVars { uid: Some(1000), exe_dir: None, ..Default::default() });
+    let error = home.clone().expand_placeholders(&vars).unwrap_err();
+    assert_eq!(error, "{home} in process.path has no value");
+
+    // The rule's own user wins over the connection's
+    let list = rule(Operator::list(vec![
+        Operator::simple("user.id", "1001"),
+        Operator::simple("process.path", "/opt/app/bin/app"),
+    ]));
+    let vars = TemplateVars::for_node(&list, "192.0.2.10:50051", Some(1000));
+    assert_eq!((vars.uid, vars.exe_dir.as_deref()), (Some(1001), Some("/opt/app/bin")));
+}
+
+#[test]
+fn rule_editor_expands_placeholders_on_save() {
+    let ctrl_s = KeyEvent::new(KeyCode::Char('s'), KeyModifiers::CONTROL);
+    let mut editor = RuleEditorDialog::new().with_node_addr("unix:/run/opensnitch/osui.sock");
+    editor.name = "helper".to_string();
+    editor.data = "{exe_dir}/helper".to_string();
+    assert!(editor.handle_key(ctrl_s).is_none());
+
+    editor.data = "/usr/bin/{uid}-{nope}".to_string();
+    match editor.handle_key(ctrl_s) {
+        Some(RuleEditorResult::Save(rule)) => {
+            assert!(rule.operator.data.starts_with("/usr/bin/"));
+            assert!(rule.operator.data.ends_with("-{nope}"));
+            assert!(!rule.operator.data.contains("{uid}"));
+        }
+        _ => panic!("expected the rule to be saved"),
+    }
+}
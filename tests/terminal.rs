@@ -0,0 +1,53 @@
+//! Rendering on terminals without UTF-8 or a rich palette
+
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::widgets::{Block, Borders, Widget};
+
+use opensnitch_tui::config::TerminalMode;
+use opensnitch_tui::ui::terminal::{ascii_symbol, Capabilities};
+
+#[test]
+fn detects_limited_terminals() {
+    assert_eq!(Capabilities::from_env("xterm-256color", "", "en_US.UTF-8"), Capabilities::full());
+    assert_eq!(Capabilities::from_env("linux", "", "C"), Capabilities::basic());
+    assert!(Capabilities::from_env("vt100", "", "en_US.utf8").unicode);
+    assert!(Capabilities::from_env("linux", "truecolor", "POSIX").rich_colors);
+    assert!(!Capabilities::from_env("", "", "").rich_colors);
+
+    // The setting wins over detection
+    assert_eq!(Capabilities::detect(TerminalMode::Basic), Capabilities::basic());
+    assert_eq!(Capabilities::detect(TerminalMode::Full), Capabilities::full());
+}
+
+#[test]
+fn basic_mode_renders_ascii_and_basic_colors() {
+    let area = Rect::new(0, 0, 12, 3);
+    let mut buffer = Buffer::empty(area);
+    Block::default()
+        .borders(Borders::ALL)
+        .title("▶ Rules ✓")
+        .border_style(Style::default().fg(Color::LightBlue).bg(Color::Rgb(250, 250, 250)))
+        .render(area, &mut buffer);
+    buffer.set_string(1, 1, "a→b", Style::default().fg(Color::DarkGray));
+
+    Capabilities::basic().downgrade(&mut buffer);
+    let lines: Vec<String> = (0..3)
+        .map(|y| (0..12).map(|x| buffer[(x, y)].symbol().to_string()).collect())
+        .collect();
+    assert_eq!(lines, ["+> Rules +-+", "|a>b       |", "+----------+"]);
+
+    let corner = &buffer[(0, 0)];
+    assert_eq!((corner.fg, corner.bg), (Color::Blue, Color::Gray));
+    let dim = &buffer[(1, 1)];
+    assert_eq!(dim.fg, Color::Gray);
+    assert!(dim.modifier.contains(Modifier::DIM));
+
+    // Full capabilities leave the frame alone
+    let mut untouched = Buffer::empty(area);
+    untouched.set_string(0, 0, "★", Style::default().fg(Color::DarkGray));
+    Capabilities::full().downgrade(&mut untouched);
+    assert_eq!(untouched[(0, 0)].symbol(), "★");
+    assert_eq!(ascii_symbol("漢"), "?");
+}
@@ -0,0 +1,75 @@
+//! Truncation and cursor editing on multi-byte text
+
+mod common;
+
+use crossterm::event::KeyCode;
+
+use opensnitch_tui::ui::widgets::searchbar::SearchBar;
+use opensnitch_tui::ui::widgets::textarea::TextArea;
+use opensnitch_tui::utils::process::truncate_path;
+use opensnitch_tui::utils::text;
+
+use common::key;
+
+#[test]
+fn truncation_never_splits_a_character() {
+    assert_eq!(text::truncate("bücher.example", 2), "bü");
+    assert_eq!(text::elide("bücher.example.de", 10), "bücher....");
+    assert_eq!(text::elide("short", 10), "short");
+
+    // Wide characters take two columns each, half of one is left out
+    assert_eq!(text::width("例え.jp"), 7);
+    assert_eq!(text::truncate("例え.jp", 3), "例");
+    assert_eq!(text::elide("例え.例え.jp", 8), "例え....");
+    assert_eq!(text::elide("例え.例え.jp", 7), "例え...");
+    assert_eq!(text::pad("例", 4), "例  ");
+
+    // A letter with a combining accent stays whole
+    assert_eq!(text::truncate("e\u{301}x", 1), "e\u{301}");
+    assert_eq!(text::elide_start("/usr/bin/ćurl-wrapper", 10), "...wrapper");
+    assert_eq!(truncate_path("/opt/アプリ/bin/アプリ", 14), "/opt.../アプリ");
+}
+
+#[test]
+fn cursors_move_over_whole_graphemes() {
+    let mut value = String::from("ae\u{301}");
+    let mut cursor = value.len();
+    assert_eq!(text::column(&value, cursor), 2);
+    assert!(text::backspace(&mut value, &mut cursor));
+    assert_eq!((value.as_str(), cursor), ("a", 1));
+
+    text::insert(&mut value, &mut cursor, '日');
+    assert_eq!((value.as_str(), cursor, text::column(&value, cursor)), ("a日", 4, 3));
+    assert_eq!(text::prev_boundary(&value, cursor), 1);
+    assert!(text::delete(&mut value, 1));
+    assert!(!text::delete(&mut value, 1));
+    assert_eq!(value, "a");
+}
+
+#[test]
+fn search_edits_multi_byte_queries() {
+    let mut search = SearchBar::new();
+    search.activate();
+    for c in "čaj".chars() {
+        search.insert(c);
+    }
+    search.move_home();
+    search.move_right();
+    search.insert('ř');
+    search.backspace();
+    search.backspace();
+    search.delete();
+    search.insert('ü');
+    assert_eq!(search.query, "üj");
+}
+
+#[test]
+fn text_area_keeps_the_screen_column_between_lines() {
+    let mut area = TextArea::new("日本\nabcd");
+    area.handle_key(key(KeyCode::End));
+    area.handle_key(key(KeyCode::Down));
+    area.handle_key(key(KeyCode::Char('!')));
+    area.handle_key(key(KeyCode::Up));
+    area.handle_key(key(KeyCode::Backspace));
+    assert_eq!(area.text(), "日\nabcd!");
+}
@@ -0,0 +1,75 @@
+//! Theme picker
+
+mod common;
+
+use crossterm::event::KeyCode;
+
+use opensnitch_tui::config::SessionState;
+use opensnitch_tui::ui::dialogs::theme::{ThemeDialog, ThemeDialogResult};
+use opensnitch_tui::ui::theme::Theme;
+
+use common::key;
+
+#[test]
+fn every_named_theme_exists() {
+    for name in Theme::NAMES {
+        assert!(Theme::named(name).is_some(), "{}", name);
+    }
+    assert!(Theme::named("default").is_some());
+    assert!(Theme::named("neon").is_none());
+}
+
+#[test]
+fn cycles_live_and_reverts_on_escape() {
+    let mut dialog = ThemeDialog::new("default");
+    assert_eq!(dialog.selected_name(), "dark");
+
+    assert!(dialog.handle_key(key(KeyCode::Right)).is_none());
+    assert_eq!(dialog.selected_name(), "light");
+    // Wraps around both ways
+    dialog.handle_key(key(KeyCode::Left));
+    dialog.handle_key(key(KeyCode::Left));
+    assert_eq!(dialog.selected_name(), "colorblind");
+    dialog.handle_key(key(KeyCode::Right));
+    assert_eq!(dialog.selected_name(), "dark");
+    dialog.handle_key(key(KeyCode::Right));
+    assert_eq!(dialog.selected_name(), "light");
+
+    assert!(matches!(dialog.handle_key(key(KeyCode::Esc)), Some(ThemeDialogResult::Revert("dark"))));
+    assert!(matches!(dialog.handle_key(key(KeyCode::Enter)), Some(ThemeDialogResult::Keep("light"))));
+}
+
+#[test]
+fn actions_carry_a_marker_and_a_word() {
+    assert_eq!(Theme::action_label("allow"), "✓ ALLOW");
+    assert_eq!(Theme::action_label("drop"), "✗ DROP");
+    assert_eq!(Theme::action_label("reject"), "✗ REJECT");
+    assert_eq!(Theme::action_label("N/A"), "N/A");
+
+    // Allow and deny differ in more than red and green
+    let theme = Theme::colorblind();
+    assert_ne!(theme.action_style("allow"), theme.action_style("deny"));
+    assert_ne!(theme.allow, Theme::dark().allow);
+}
+
+#[test]
+fn previews_every_theme() {
+    let dialog = ThemeDialog::new("light");
+    let mut terminal = ratatui::Terminal::new(ratatui::backend::TestBackend::new(100, 30)).unwrap();
+    let frame = terminal.draw(|f| dialog.render(f, &Theme::default())).unwrap();
+    let screen: String = frame.buffer.content().iter().map(|cell| cell.symbol()).collect();
+    assert!(screen.contains(" dark "));
+    assert!(screen.contains("▶ light"));
+}
+
+#[test]
+fn only_a_theme_picked_in_the_ui_outlives_the_configured_one() {
+    let mut session = SessionState::default();
+    assert_eq!(session.theme, None);
+
+    session.pick_theme("light", "dark");
+    assert_eq!(session.theme.as_deref(), Some("light"));
+    // Back to the configured theme, the configuration decides again
+    session.pick_theme("dark", "dark");
+    assert_eq!(session.theme, None);
+}
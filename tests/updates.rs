@@ -0,0 +1,47 @@
+//! Checking for new releases
+
+use chrono::{TimeZone, Utc};
+
+use opensnitch_tui::app::updates::{self, newer_releases, parse_version};
+
+const RELEASES: &str = r#"[
+    {"tag_name": "v0.3.0-rc1", "prerelease": true, "body": "Candidate"},
+    {"tag_name": "v0.2.1", "name": "Fixes", "body": "- Prompt timeout\r\n", "published_at": "2024-05-02T10:00:00Z"},
+    {"tag_name": "v0.4.0", "draft": true},
+    {"tag_name": "v0.10.0", "html_url": "https://github.com/icryo/opensnitch-tui/releases/tag/v0.10.0"},
+    {"tag_name": "nightly"},
+    {"tag_name": "v0.1.0", "body": "First release"}
+]"#;
+
+#[test]
+fn only_newer_stable_releases_are_offered() {
+    assert_eq!(parse_version("v1.2.3"), Some((1, 2, 3)));
+    assert_eq!(parse_version("0.10"), Some((0, 10, 0)));
+    assert_eq!(parse_version("2.0.0-beta.1+build"), Some((2, 0, 0)));
+    assert_eq!(parse_version("nightly"), None);
+
+    let now = Utc.with_ymd_and_hms(2024, 6, 1, 0, 0, 0).unwrap();
+    let update = newer_releases(RELEASES, "0.1.0", now).unwrap().unwrap();
+    let tags: Vec<_> = update.releases.iter().map(|r| r.tag_name.as_str()).collect();
+    // Newest first by version, not alphabetically
+    assert_eq!(tags, ["v0.10.0", "v0.2.1"]);
+    assert_eq!(update.latest().tag_name, "v0.10.0");
+    assert_eq!(update.releases[1].name.as_deref(), Some("Fixes"));
+
+    assert_eq!(newer_releases(RELEASES, "0.10.0", now).unwrap(), None);
+    assert!(newer_releases("{\"message\": \"API rate limit exceeded\"}", "0.1.0", now).is_err());
+}
+
+#[tokio::test]
+async fn releases_are_fetched_with_the_command() {
+    let dir = std::env::temp_dir().join(format!("opensnitch-tui-updates-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("releases.json");
+    std::fs::write(&path, r#"[{"tag_name": "v999.0.0"}]"#).unwrap();
+
+    let update = updates::check(&format!("cat {}", path.display())).await.unwrap().unwrap();
+    assert_eq!(update.latest().tag_name, "v999.0.0");
+    let error = updates::check("echo 'could not resolve host' >&2; exit 6").await.unwrap_err();
+    assert!(error.ends_with("could not resolve host"), "{}", error);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,41 @@
+//! Cross-references between rules, events and alerts
+
+mod common;
+
+use opensnitch_tui::app::xref::XrefIndex;
+use opensnitch_tui::models::{
+    Alert, AlertData, AlertPriority, AlertType, AlertWhat, Event, FwRule, Operator, Rule, RuleAction,
+    RuleDuration,
+};
+
+use common::connection;
+
+fn event(process: &str, host: &str, rule: Option<&str>) -> Event {
+    let connection = connection(process, host, 443);
+    let rule = rule.map(|name| Rule::new(name, RuleAction::Allow, RuleDuration::Always, Operator::simple("process.path", process)));
+    Event::new(connection, rule)
+}
+
+#[test]
+fn rules_events_and_alerts_are_cross_referenced() {
+    let mut xref = XrefIndex::default();
+    xref.add_event(&event("/usr/bin/curl", "example.org", Some("allow-curl")));
+    xref.add_event(&event("/usr/bin/curl", "example.net", Some("allow-curl")));
+    xref.add_event(&event("/usr/bin/ssh", "host.lan", None));
+    // Seen again, it becomes the most recent
+    let again = event("/usr/bin/curl", "example.org", Some("allow-curl"));
+    xref.add_event(&again);
+
+    let hosts: Vec<_> = xref.rule_events("allow-curl").into_iter().map(|r| r.dst_host).collect();
+    assert_eq!(hosts, ["example.org", "example.net"]);
+    assert!(xref.rule_events("other").is_empty());
+    assert_eq!(xref.connection_rule(&again.connection), Some("allow-curl"));
+    assert_eq!(xref.connection_rule(&event("/usr/bin/ssh", "host.lan", None).connection), None);
+
+    let mut fw_rule = FwRule::new("ssh", "accept");
+    fw_rule.uuid = "fw-1".to_string();
+    let alert = Alert::new(7, AlertType::Warning, AlertPriority::Medium, AlertWhat::Firewall, Some(AlertData::FirewallRule(fw_rule)));
+    xref.add_alert(&alert);
+    xref.add_alert(&Alert::new(8, AlertType::Info, AlertPriority::Low, AlertWhat::Generic, Some(AlertData::Text("x".into()))));
+    assert_eq!(xref.firewall_alerts("fw-1"), [(7, alert.timestamp)]);
+}